@@ -270,7 +270,7 @@ fn rotation_update(scene: &Scene, delta: f32) {
     }
 }
 
-fn visualize_collision(scene: &Scene, entity: Entity, _other: &[Entity]) {
+fn visualize_collision(scene: &Scene, entity: Entity, _collisions: &[Collision]) {
     let collider_manager = scene.get_manager::<ColliderManager>();
     collider_manager.bvh_manager().get(entity).unwrap().collider.debug_draw_color(color::RED);
 }
@@ -0,0 +1,152 @@
+//! Queries for the extensions and implementation limits of the current context.
+//!
+//! All functions in this module require that a context is current on the calling thread.
+
+use std::collections::HashSet;
+use std::collections::hash_set;
+use std::ffi::CStr;
+use super::*;
+
+/// The set of extensions supported by a context.
+#[derive(Debug, Clone, Default)]
+pub struct Extensions {
+    names: HashSet<String>,
+}
+
+impl Extensions {
+    /// Returns `true` if the extension is supported.
+    ///
+    /// `name` should be the full name of the extension, e.g. `"GL_ARB_buffer_storage"`.
+    pub fn supports(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// Returns an iterator over the names of all supported extensions.
+    pub fn iter(&self) -> hash_set::Iter<String> {
+        self.names.iter()
+    }
+
+    /// Returns the number of supported extensions.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+/// Queries the extensions supported by the current context.
+///
+/// Uses `NumExtensions` and `get_string_i()`, so requires GL 3.0 or later.
+pub fn extensions() -> Extensions {
+    let num_extensions = get_integer(IntegerName::NumExtensions);
+
+    let mut names = HashSet::with_capacity(num_extensions as usize);
+    for index in 0..num_extensions as u32 {
+        let name = unsafe { get_string_i(StringName::Extensions, index) };
+        if name.is_null() {
+            continue;
+        }
+
+        let name = unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned();
+        names.insert(name);
+    }
+
+    Extensions { names: names }
+}
+
+/// The implementation-dependent limits of a context.
+///
+/// Used to gracefully degrade on older hardware, e.g. by using smaller textures or falling back
+/// to a simpler rendering path when an extension isn't available.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    /// The GL version of the context as `(major, minor)`.
+    pub version: (u32, u32),
+
+    /// The largest width or height of a 2D texture.
+    pub max_texture_size: u32,
+
+    /// The largest width or height of a face of a cube map texture.
+    pub max_cube_map_texture_size: u32,
+
+    /// The number of vertex attribute locations available to a vertex shader.
+    pub max_vertex_attribs: u32,
+
+    /// The number of texture units available to a fragment shader.
+    pub max_texture_image_units: u32,
+
+    /// The number of texture units available across all shader stages.
+    pub max_combined_texture_image_units: u32,
+
+    /// The number of draw buffers a fragment shader can write to at once.
+    pub max_draw_buffers: u32,
+
+    /// The number of color attachments a framebuffer can have.
+    pub max_color_attachments: u32,
+
+    /// The largest number of samples supported for multisampling.
+    pub max_samples: u32,
+
+    /// The largest supported degree of anisotropic filtering.
+    ///
+    /// `None` if anisotropic filtering isn't supported.
+    pub max_anisotropy: Option<f32>,
+
+    /// Whether the Direct State Access functions in the `dsa` module are available.
+    pub direct_state_access: bool,
+
+    /// Whether `clip_control` is available.
+    pub clip_control: bool,
+
+    /// The extensions supported by the context.
+    pub extensions: Extensions,
+}
+
+impl Capabilities {
+    /// Queries the capabilities of the current context.
+    pub fn query() -> Capabilities {
+        let get = |name| get_integer(name) as u32;
+
+        let version = (get(IntegerName::MajorVersion), get(IntegerName::MinorVersion));
+        let extensions = extensions();
+
+        let max_anisotropy = if version >= (4, 6)
+            || extensions.supports("GL_EXT_texture_filter_anisotropic")
+            || extensions.supports("GL_ARB_texture_filter_anisotropic")
+        {
+            Some(get_float(FloatName::MaxTextureMaxAnisotropy))
+        } else {
+            None
+        };
+
+        let direct_state_access = version >= (4, 5)
+            || extensions.supports("GL_ARB_direct_state_access");
+
+        let clip_control = version >= (4, 5)
+            || extensions.supports("GL_ARB_clip_control");
+
+        Capabilities {
+            version: version,
+            max_texture_size: get(IntegerName::MaxTextureSize),
+            max_cube_map_texture_size: get(IntegerName::MaxCubeMapTextureSize),
+            max_vertex_attribs: get(IntegerName::MaxVertexAttribs),
+            max_texture_image_units: get(IntegerName::MaxTextureImageUnits),
+            max_combined_texture_image_units: get(IntegerName::MaxCombinedTextureImageUnits),
+            max_draw_buffers: get(IntegerName::MaxDrawBuffers),
+            max_color_attachments: get(IntegerName::MaxColorAttachments),
+            max_samples: get(IntegerName::MaxSamples),
+            max_anisotropy: max_anisotropy,
+            direct_state_access: direct_state_access,
+            clip_control: clip_control,
+            extensions: extensions,
+        }
+    }
+
+    /// Returns `true` if the context's version is at least `major.minor`.
+    pub fn is_version_at_least(&self, major: u32, minor: u32) -> bool {
+        self.version >= (major, minor)
+    }
+
+    /// Returns `true` if the extension is supported.
+    pub fn supports(&self, extension: &str) -> bool {
+        self.extensions.supports(extension)
+    }
+}
@@ -0,0 +1,303 @@
+//! Direct State Access (DSA) entry points.
+//!
+//! DSA functions operate on objects by name rather than on whatever object is bound to a target,
+//! which avoids having to bind an object just to modify it. They are core since version 4.5 and
+//! are otherwise provided by `ARB_direct_state_access`. Check
+//! `Capabilities::direct_state_access` before using any of the functions in this module, since
+//! loading the procs will fail on drivers that don't support DSA.
+
+use types::*;
+
+gl_proc!(glCheckNamedFramebufferStatus:
+    /// Checks the completeness status of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCheckFramebufferStatus)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `check_framebuffer_status`.
+    fn check_named_framebuffer_status(
+        framebuffer: FramebufferName,
+        target: FramebufferTarget
+    ) -> FramebufferStatus);
+
+gl_proc!(glCreateBuffers:
+    /// Creates buffer objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCreateBuffers)
+    ///
+    /// Core since version 4.5
+    ///
+    /// Unlike `gen_buffers`, the buffer objects are created immediately rather than when they
+    /// are first bound.
+    fn create_buffers(count: i32, buffers: *mut BufferName));
+
+gl_proc!(glCreateFramebuffers:
+    /// Creates framebuffer objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCreateFramebuffers)
+    ///
+    /// Core since version 4.5
+    fn create_framebuffers(count: i32, framebuffers: *mut FramebufferName));
+
+gl_proc!(glCreateTextures:
+    /// Creates texture objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCreateTextures)
+    ///
+    /// Core since version 4.5
+    ///
+    /// Unlike `gen_textures`, the texture objects are created immediately with the type given
+    /// by `target`.
+    fn create_textures(target: TextureBindTarget, count: i32, textures: *mut TextureObject));
+
+gl_proc!(glCreateVertexArrays:
+    /// Creates vertex array objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCreateVertexArrays)
+    ///
+    /// Core since version 4.5
+    fn create_vertex_arrays(count: i32, arrays: *mut VertexArrayName));
+
+gl_proc!(glEnableVertexArrayAttrib:
+    /// Enables a generic vertex attribute of a vertex array.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glEnableVertexAttribArray)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `enable_vertex_attrib_array`.
+    fn enable_vertex_array_attrib(vertex_array: VertexArrayName, attrib: AttributeLocation));
+
+gl_proc!(glMapNamedBufferRange:
+    /// Maps all or part of a buffer object's data store into the client's address space.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glMapBufferRange)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `map_buffer_range`.
+    fn map_named_buffer_range(
+        buffer: BufferName,
+        offset: isize,
+        length: isize,
+        access: MapAccessFlags
+    ) -> *mut ());
+
+gl_proc!(glNamedBufferData:
+    /// Creates and initializes a buffer object's data store.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBufferData)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `buffer_data_raw`.
+    fn named_buffer_data_raw(buffer: BufferName, size: isize, data: *const (), usage: BufferUsage));
+
+gl_proc!(glNamedBufferStorage:
+    /// Creates and initializes a buffer object's immutable data store.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBufferStorage)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `buffer_storage_raw`.
+    fn named_buffer_storage_raw(
+        buffer: BufferName,
+        size: isize,
+        data: *const (),
+        flags: BufferStorageFlags));
+
+gl_proc!(glNamedBufferSubData:
+    /// Updates a subset of a buffer object's data store.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBufferSubData)
+    ///
+    /// Core since version 4.5
+    ///
+    /// Replaces `size` bytes of the data store of `buffer` starting at `offset` with the data
+    /// pointed to by `data`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `offset` or `size` is negative, or if
+    ///   `offset + size` is greater than the size of the buffer's data store.
+    /// - `GL_INVALID_OPERATION` is generated if the buffer is mapped without `MAP_PERSISTENT`.
+    fn named_buffer_sub_data_raw(buffer: BufferName, offset: isize, size: isize, data: *const ()));
+
+gl_proc!(glNamedFramebufferDrawBuffers:
+    /// Specifies a list of color buffers to be drawn into.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawBuffers)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `draw_buffers`.
+    fn named_framebuffer_draw_buffers(
+        framebuffer: FramebufferName,
+        count: i32,
+        buffers: *const DrawBuffer));
+
+gl_proc!(glNamedFramebufferRenderbuffer:
+    /// Attaches a renderbuffer as an image of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFramebufferRenderbuffer)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `framebuffer_renderbuffer`.
+    fn named_framebuffer_renderbuffer(
+        framebuffer: FramebufferName,
+        attachment: FramebufferAttachment,
+        renderbuffer_target: RenderbufferTarget,
+        renderbuffer: RenderbufferName));
+
+gl_proc!(glNamedFramebufferTexture:
+    /// Attaches a level of a texture object as an image of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFramebufferTexture)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `framebuffer_texture_2d`. The texture target is inferred from the type of
+    /// `texture`.
+    fn named_framebuffer_texture(
+        framebuffer: FramebufferName,
+        attachment: FramebufferAttachment,
+        texture: TextureObject,
+        level: i32));
+
+gl_proc!(glTextureParameterf:
+    /// Sets floating-point texture parameters.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glTexParameter)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `texture_parameter_f32`.
+    fn named_texture_parameter_f32(texture: TextureObject, name: TextureParameterName, param: f32));
+
+gl_proc!(glTextureParameteri:
+    /// Sets integer texture parameters.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glTexParameter)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `texture_parameter_i32`.
+    fn named_texture_parameter_i32(texture: TextureObject, name: TextureParameterName, param: i32));
+
+gl_proc!(glTextureStorage2D:
+    /// Allocates immutable storage for all levels of a two-dimensional texture.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glTexStorage2D)
+    ///
+    /// Core since version 4.5
+    ///
+    /// `internal_format` must be a sized format. After the storage is allocated its format and
+    /// dimensions can't be changed, though the contents can be updated with
+    /// `named_texture_sub_image_2d`.
+    fn texture_storage_2d(
+        texture: TextureObject,
+        levels: i32,
+        internal_format: TextureInternalFormat,
+        width: i32,
+        height: i32));
+
+gl_proc!(glTextureSubImage2D:
+    /// Specifies a two-dimensional texture subimage.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glTexSubImage2D)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `texture_sub_image_2d`. Replaces the `width` by `height` region of `level`
+    /// of `texture` starting at `(x_offset, y_offset)` with the pixel data in `data`.
+    fn named_texture_sub_image_2d(
+        texture: TextureObject,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data_type: TextureDataType,
+        data: *const ()));
+
+gl_proc!(glUnmapNamedBuffer:
+    /// Releases the mapping of a buffer object's data store into the client's address space.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glMapBuffer)
+    ///
+    /// Core since version 4.5
+    ///
+    /// DSA version of `unmap_buffer`.
+    fn unmap_named_buffer(buffer: BufferName) -> Boolean);
+
+gl_proc!(glVertexArrayAttribBinding:
+    /// Associates a vertex attribute with a vertex buffer binding of a vertex array.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexAttribBinding)
+    ///
+    /// Core since version 4.5
+    fn vertex_array_attrib_binding(
+        vertex_array: VertexArrayName,
+        attrib: AttributeLocation,
+        binding_index: u32));
+
+gl_proc!(glVertexArrayAttribFormat:
+    /// Specifies the layout of a floating-point vertex attribute of a vertex array.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexAttribFormat)
+    ///
+    /// Core since version 4.5
+    ///
+    /// `relative_offset` is the offset in bytes of the attribute from the start of each vertex
+    /// in the buffer bound to the attribute's binding (see `vertex_array_attrib_binding`).
+    fn vertex_array_attrib_format(
+        vertex_array: VertexArrayName,
+        attrib: AttributeLocation,
+        size: i32,
+        gl_type: GlType,
+        normalize: Boolean,
+        relative_offset: u32));
+
+gl_proc!(glVertexArrayAttribIFormat:
+    /// Specifies the layout of an integer vertex attribute of a vertex array.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexAttribFormat)
+    ///
+    /// Core since version 4.5
+    ///
+    /// Like `vertex_array_attrib_format`, except that the data is passed to the shader as
+    /// integer data.
+    fn vertex_array_attrib_i_format(
+        vertex_array: VertexArrayName,
+        attrib: AttributeLocation,
+        size: i32,
+        gl_type: GlType,
+        relative_offset: u32));
+
+gl_proc!(glVertexArrayElementBuffer:
+    /// Binds a buffer as the element (index) buffer of a vertex array.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexArrayElementBuffer)
+    ///
+    /// Core since version 4.5
+    fn vertex_array_element_buffer(vertex_array: VertexArrayName, buffer: BufferName));
+
+gl_proc!(glVertexArrayVertexBuffer:
+    /// Binds a buffer to a vertex buffer binding of a vertex array.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBindVertexBuffer)
+    ///
+    /// Core since version 4.5
+    ///
+    /// `offset` is the offset in bytes of the first vertex in `buffer`, and `stride` is the
+    /// distance in bytes between consecutive vertices.
+    fn vertex_array_vertex_buffer(
+        vertex_array: VertexArrayName,
+        binding_index: u32,
+        buffer: BufferName,
+        offset: isize,
+        stride: i32));
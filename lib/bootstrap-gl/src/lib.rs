@@ -12,6 +12,7 @@
 //! parameters with special enum types that only contain variants that are valid options for that
 //! function.
 
+#![feature(associated_consts)]
 #![feature(const_fn)]
 #![allow(bad_style)]
 
@@ -26,10 +27,14 @@ pub mod platform;
 #[path="linux.rs"]
 pub mod platform;
 
+pub mod capabilities;
+pub mod dsa;
 pub mod types;
 
 use std::mem;
 
+pub use capabilities::*;
+pub use dsa::*;
 pub use types::*;
 pub use platform::*;
 
@@ -57,6 +62,32 @@ pub fn gen_buffer() -> Option<BufferName> {
     }
 }
 
+pub fn gen_framebuffer() -> Option<FramebufferName> {
+    let mut framebuffer_name = FramebufferName::null();
+    unsafe {
+        gen_framebuffers(1, &mut framebuffer_name);
+    }
+
+    if framebuffer_name.is_null() {
+        None
+    } else {
+        Some(framebuffer_name)
+    }
+}
+
+pub fn gen_renderbuffer() -> Option<RenderbufferName> {
+    let mut renderbuffer_name = RenderbufferName::null();
+    unsafe {
+        gen_renderbuffers(1, &mut renderbuffer_name);
+    }
+
+    if renderbuffer_name.is_null() {
+        None
+    } else {
+        Some(renderbuffer_name)
+    }
+}
+
 pub fn gen_vertex_array() -> Option<VertexArrayName> {
     let mut vertex_array_name = VertexArrayName::null();
     unsafe {
@@ -70,6 +101,20 @@ pub fn gen_vertex_array() -> Option<VertexArrayName> {
     }
 }
 
+/// Returns the value of a single-valued floating-point parameter.
+pub fn get_float(name: FloatName) -> f32 {
+    let mut value = 0.0;
+    unsafe { get_floats(name, &mut value); }
+    value
+}
+
+/// Returns the value of a single-valued integer parameter.
+pub fn get_integer(name: IntegerName) -> i32 {
+    let mut value = 0;
+    unsafe { get_integers(name, &mut value); }
+    value
+}
+
 gl_proc!(glActiveTexture:
     /// Selects active texture unit.
     ///
@@ -115,6 +160,27 @@ gl_proc!(glAttachShader:
     /// - `glInvalidOperation` is generated if shader​ is already attached to program​.
     fn attach_shader(program: ProgramObject, shader: ShaderObject));
 
+gl_proc!(glBeginConditionalRender:
+    /// Starts conditional rendering.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBeginConditionalRender)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Rendering commands issued between `begin_conditional_render` and
+    /// `end_conditional_render` are discarded if the result of the occlusion query `query` is
+    /// zero. `mode` specifies whether the GL waits for the query result to become available
+    /// before deciding whether to render. If a no-wait mode is used and the result isn't
+    /// available the rendering commands are executed as normal.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `query` is not the name of an existing query object.
+    /// - `GL_INVALID_OPERATION` is generated if conditional rendering is already active, if
+    ///   `query` is the name of a query that is currently in progress, or if `query` is not
+    ///   a `SamplesPassed`, `AnySamplesPassed`, or `AnySamplesPassedConservative` query.
+    fn begin_conditional_render(query: QueryObject, mode: ConditionalRenderMode));
+
 gl_proc!(glBeginQuery:
     /// Delimits the start of a query object.
     ///
@@ -226,6 +292,60 @@ gl_proc!(glBindBuffer:
     ///   `gen_buffers`.
     fn bind_buffer(target: BufferTarget, buffer: BufferName));
 
+gl_proc!(glBindBufferBase:
+    /// Binds a buffer object to an indexed buffer target.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBindBufferBase)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Binds the buffer object `buffer` to the binding point at index `index` of the array of
+    /// targets specified by `target`. Each `target` represents an indexed array of buffer binding
+    /// points, as well as a single general binding point that can be used by other buffer
+    /// manipulation functions such as `bind_buffer`. In addition to binding `buffer` to the
+    /// indexed binding point, `bind_buffer_base` also binds `buffer` to the generic buffer binding
+    /// point specified by `target`.
+    ///
+    /// # Version Availability
+    ///
+    /// - The `Uniform` target is available only if the GL version is 3.1 or greater.
+    /// - The `AtomicCounter` target is available only if the GL version is 4.2 or greater.
+    /// - The `ShaderStorage` target is available only if the GL version is 4.3 or greater.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if `target` is not `TransformFeedback`, `Uniform`,
+    ///   `AtomicCounter`, or `ShaderStorage`.
+    /// - `GL_INVALID_VALUE` is generated if `index` is greater than or equal to the number of
+    ///   target-specific indexed binding points.
+    /// - `GL_INVALID_VALUE` is generated if `buffer` does not have an associated data store, or if
+    ///   the size of that store is zero.
+    fn bind_buffer_base(target: BufferTarget, index: u32, buffer: BufferName));
+
+gl_proc!(glBindFramebuffer:
+    /// Binds a framebuffer to a framebuffer target.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBindFramebuffer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Binding to `Framebuffer` binds the framebuffer for both reading and drawing. Binding
+    /// `FramebufferName::null()` binds the default framebuffer provided by the windowing system.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if `framebuffer` is not zero or the name of a
+    ///   framebuffer previously returned from a call to `gen_framebuffers`.
+    fn bind_framebuffer(target: FramebufferTarget, framebuffer: FramebufferName));
+
+gl_proc!(glBindRenderbuffer:
+    /// Binds a renderbuffer to a renderbuffer target.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBindRenderbuffer)
+    ///
+    /// Core since version 3.0
+    fn bind_renderbuffer(target: RenderbufferTarget, renderbuffer: RenderbufferName));
+
 gl_proc!(glBindTexture:
     /// Binds a named texture to a texturing target.
     ///
@@ -380,6 +500,42 @@ gl_proc!(glBlendFunc:
     ///   `GL_MAX_DUAL_SOURCE_DRAW_BUFFERS`, which may be lower than `GL_MAX_DRAW_BUFFERS`.
     fn blend_func(src_factor: SourceFactor, dest_factor: DestFactor));
 
+gl_proc!(glBlitFramebuffer:
+    /// Copies a block of pixels from the read framebuffer to the draw framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBlitFramebuffer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Transfers the rectangle `src_x0`, `src_y0`, `src_x1`, `src_y1` of the framebuffer bound
+    /// to `ReadFramebuffer` to the rectangle `dst_x0`, `dst_y0`, `dst_x1`, `dst_y1` of the
+    /// framebuffer bound to `DrawFramebuffer`. `mask` specifies which buffers are copied. If the
+    /// rectangles are different sizes the image is scaled using `filter`.
+    ///
+    /// If the read framebuffer is multisampled and the draw framebuffer isn't, the samples are
+    /// resolved to a single value. In that case the rectangles must be the same size.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if `mask` contains `Depth` or `Stencil` and
+    ///   `filter` is not `Nearest`.
+    /// - `GL_INVALID_OPERATION` is generated if `mask` contains `Depth` or `Stencil` and the
+    ///   source and destination depth and stencil formats do not match.
+    /// - `GL_INVALID_OPERATION` is generated if the read framebuffer is multisampled and the
+    ///   source and destination rectangles are not the same size.
+    /// - `GL_INVALID_FRAMEBUFFER_OPERATION` is generated if either framebuffer is not complete.
+    fn blit_framebuffer(
+        src_x0: i32,
+        src_y0: i32,
+        src_x1: i32,
+        src_y1: i32,
+        dst_x0: i32,
+        dst_y0: i32,
+        dst_x1: i32,
+        dst_y1: i32,
+        mask: ClearBufferMask,
+        filter: BlitFilter));
+
 gl_proc!(glBufferData:
     /// Creates and initializes a buffer object's data store.
     ///
@@ -435,6 +591,63 @@ gl_proc!(glBufferData:
     ///   specified size​.
     fn buffer_data_raw(target: BufferTarget, size: isize, data: *const (), usage: BufferUsage));
 
+gl_proc!(glBufferStorage:
+    /// Creates and initializes a buffer object's immutable data store.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBufferStorage)
+    ///
+    /// Core since version 4.4
+    ///
+    /// Creates a new immutable data store for the buffer object currently bound to `target​`. The
+    /// size of the data store is specified by `size`. If an initial data is available, its
+    /// address may be supplied in `data​`. Otherwise, to create an uninitialized data store,
+    /// `data` should be null.
+    ///
+    /// `flags` specifies the intended usage of the buffer's data store. Unlike `buffer_data_raw`
+    /// these flags are not hints, they constrain how the buffer may be used:
+    ///
+    /// - `DYNAMIC_STORAGE` - The contents of the data store may be updated after creation through
+    ///   calls to `buffer_sub_data`.
+    /// - `MAP_READ` and `MAP_WRITE` - The data store may be mapped by the client for read or write
+    ///   access.
+    /// - `MAP_PERSISTENT` - The client may request that the server read from or write to the
+    ///   buffer while it is mapped.
+    /// - `MAP_COHERENT` - Shared access to buffers that are simultaneously mapped for client
+    ///   access and are used by the server will be coherent, so long as that mapping is performed
+    ///   using `map_buffer_range`.
+    /// - `CLIENT_STORAGE` - A hint that the data store should be backed by client memory.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `size` is less than or equal to zero.
+    /// - `GL_INVALID_OPERATION` is generated if the reserved buffer object name 0 is bound to
+    ///   `target`.
+    /// - `GL_OUT_OF_MEMORY` is generated if the GL is unable to create a data store of the
+    ///   requested size.
+    /// - `GL_INVALID_VALUE` is generated if `flags` has any bits set other than those listed
+    ///   above.
+    /// - `GL_INVALID_VALUE` is generated if `flags` contains `MAP_PERSISTENT` but does not
+    ///   contain at least one of `MAP_READ` or `MAP_WRITE`.
+    /// - `GL_INVALID_VALUE` is generated if `flags` contains `MAP_COHERENT`, but does not also
+    ///   contain `MAP_PERSISTENT`.
+    /// - `GL_INVALID_OPERATION` is generated if the buffer already has an immutable data store.
+    fn buffer_storage_raw(
+        target: BufferTarget,
+        size: isize,
+        data: *const (),
+        flags: BufferStorageFlags));
+
+gl_proc!(glCheckFramebufferStatus:
+    /// Checks the completeness status of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCheckFramebufferStatus)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Returns the completeness status of the framebuffer bound to `target`. If an error occurs
+    /// `Error` is returned.
+    fn check_framebuffer_status(target: FramebufferTarget) -> FramebufferStatus);
+
 gl_proc!(glClear:
     /// Clears buffers to preset values.
     ///
@@ -471,6 +684,64 @@ gl_proc!(glClear:
 gl_proc!(glClearColor:
     fn clear_color(red: f32, green: f32, blue: f32, alpha: f32));
 
+gl_proc!(glClearDepth:
+    /// Specifies the clear value for the depth buffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glClearDepth)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Specifies the depth value used by `clear` to clear the depth buffer. The value is clamped
+    /// to the range [0, 1]. The initial value is 1.
+    fn clear_depth(depth: f64));
+
+gl_proc!(glClientWaitSync:
+    /// Blocks and waits for a sync object to become signaled.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glClientWaitSync)
+    ///
+    /// Core since version 3.2
+    ///
+    /// Causes the client to block and wait for the sync object specified by `sync` to become
+    /// signaled. If `sync` is signaled when `client_wait_sync` is called it returns immediately,
+    /// otherwise it will block and wait for up to `timeout` nanoseconds for `sync` to become
+    /// signaled.
+    ///
+    /// The return value is one of four status values:
+    ///
+    /// - `AlreadySignaled` indicates that `sync` was signaled at the time that
+    ///   `client_wait_sync` was called.
+    /// - `TimeoutExpired` indicates that at least `timeout` nanoseconds passed and `sync` did not
+    ///   become signaled.
+    /// - `ConditionSatisfied` indicates that `sync` was signaled before the timeout expired.
+    /// - `WaitFailed` indicates that an error occurred.
+    ///
+    /// If `flags` contains `FlushCommands` the GL will flush the command stream before blocking,
+    /// ensuring that the fence is actually submitted and that the wait can complete.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `sync` is not the name of an existing sync object.
+    fn client_wait_sync(sync: SyncObject, flags: SyncWaitFlags, timeout: u64) -> SyncStatus);
+
+gl_proc!(glClipControl:
+    /// Controls clipping and the mapping of clip space to window coordinates.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glClipControl)
+    ///
+    /// Core since version 4.5, otherwise requires `ARB_clip_control`.
+    ///
+    /// `origin` specifies whether the origin of window coordinates is in the lower left or upper
+    /// left corner. `depth` specifies whether clip-space depth from -1 to 1 or from 0 to 1 is
+    /// mapped to the depth range (see `depth_range`), and likewise which depth values are clipped.
+    /// The initial values are `LowerLeft` and `NegativeOneToOne`.
+    ///
+    /// Mapping depth from 0 to 1 is typically used with reverse-Z projections, which map the
+    /// near plane to 1 and the far plane to 0. Since floating-point depth buffers have the most
+    /// precision close to 0 this evens out the precision over the view distance, but only if the
+    /// depth isn't first scaled and biased from -1 to 1.
+    fn clip_control(origin: ClipOrigin, depth: ClipDepth));
+
 gl_proc!(glCompileShader:
     /// Compiles a shader object.
     ///
@@ -496,6 +767,90 @@ gl_proc!(glCompileShader:
     /// - `GL_INVALID_VALUE` is generated if shader​ is not a value generated by OpenGL.
     fn compile_shader(shader: ShaderObject));
 
+gl_proc!(glCompressedTexImage2D:
+    /// Specifies a two-dimensional texture image in a compressed format.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCompressedTexImage2D)
+    ///
+    /// Core since version 1.3
+    ///
+    /// Works like `texture_image_2d` except that `data` is already compressed in
+    /// `internal_format`, so no pixel transfer or conversion is performed. `image_size` is the
+    /// size of `data` in bytes, which must match the size implied by `internal_format`, `width`,
+    /// and `height`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if `internal_format` is not a supported compressed
+    ///   format.
+    /// - `GL_INVALID_VALUE` is generated if `image_size` is not consistent with the format,
+    ///   dimensions, and contents of the compressed image.
+    /// - `GL_INVALID_VALUE` is generated if `border` is not 0.
+    fn compressed_texture_image_2d(
+        target: Texture2dTarget,
+        level: i32,
+        internal_format: CompressedTextureFormat,
+        width: i32,
+        height: i32,
+        border: i32,
+        image_size: i32,
+        data: *const ()));
+
+gl_proc!(glCompressedTexSubImage2D:
+    /// Replaces a rectangular region of a compressed texture image.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCompressedTexSubImage2D)
+    ///
+    /// Core since version 1.3
+    ///
+    /// Works like `texture_sub_image_2d` except that `data` is already compressed in `format`,
+    /// which must be the internal format of the texture image. For block-compressed formats the
+    /// region must be aligned to the 4x4 blocks, except where it touches the edge of the image.
+    /// If a buffer is bound to `PixelUnpack`, `data` is an offset into that buffer.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if `format` doesn't match the internal format of the
+    ///   texture image.
+    /// - `GL_INVALID_VALUE` is generated if `image_size` is not consistent with the format,
+    ///   dimensions, and contents of the compressed region.
+    fn compressed_texture_sub_image_2d(
+        target: Texture2dTarget,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: CompressedTextureFormat,
+        image_size: i32,
+        data: *const ()));
+
+gl_proc!(glCopyBufferSubData:
+    /// Copies part of the data store of one buffer object to another.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCopyBufferSubData)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Copies `size` bytes starting at `read_offset` in the buffer bound to `read_target` to
+    /// `write_offset` in the buffer bound to `write_target`. The copy happens on the server, so
+    /// the data never passes through client memory.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if any of `read_offset`, `write_offset` or `size` is
+    ///   negative, or if either range extends past the end of its buffer.
+    /// - `GL_INVALID_VALUE` is generated if the same buffer is bound to both targets and the
+    ///   ranges overlap.
+    /// - `GL_INVALID_OPERATION` is generated if no buffer is bound to either target, or if either
+    ///   buffer is mapped without `MAP_PERSISTENT`.
+    fn copy_buffer_sub_data(
+        read_target: BufferTarget,
+        write_target: BufferTarget,
+        read_offset: isize,
+        write_offset: isize,
+        size: isize));
+
 gl_proc!(glCreateProgram:
     /// Creates a program object.
     ///
@@ -617,6 +972,17 @@ gl_proc!(glDeleteBuffers:
     /// `GL_INVALID_VALUE` is generated if `num_buffers` is negative.
     fn delete_buffers(num_buffers: i32, buffers: *const BufferName));
 
+gl_proc!(glDeleteFramebuffers:
+    /// Deletes framebuffer objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDeleteFramebuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// If a framebuffer that is currently bound is deleted the binding reverts to the default
+    /// framebuffer. Unused names and zero are silently ignored.
+    fn delete_framebuffers(count: i32, framebuffers: *const FramebufferName));
+
 gl_proc!(glDeleteProgram:
     /// Deletes a program object.
     ///
@@ -640,6 +1006,17 @@ gl_proc!(glDeleteProgram:
     /// `get_program_param` with arguments `program_object` and `DeleteStatus`.
     fn delete_program(program_object: ProgramObject));
 
+gl_proc!(glDeleteRenderbuffers:
+    /// Deletes renderbuffer objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDeleteRenderbuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// If a renderbuffer that is attached to the currently bound framebuffer is deleted it is
+    /// detached from the framebuffer. Unused names and zero are silently ignored.
+    fn delete_renderbuffers(count: i32, renderbuffers: *const RenderbufferName));
+
 gl_proc!(glDeleteShader:
     /// Deletes a shader object.
     ///
@@ -679,6 +1056,27 @@ gl_proc!(glDeleteQueries:
     /// - `GL_INVALID_VALUE` is generated if `count` is negative.
     fn delete_queries(count: i32, queries: *const QueryObject));
 
+gl_proc!(glDeleteSync:
+    /// Deletes a sync object.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDeleteSync)
+    ///
+    /// Core since version 3.2
+    ///
+    /// Deletes the sync object specified by `sync`. If the fence command corresponding to the
+    /// specified sync object has completed, or if no `client_wait_sync` commands are blocking on
+    /// `sync`, the object is deleted immediately. Otherwise, `sync` is flagged for deletion and
+    /// will be deleted when it is no longer associated with any fence command and is no longer
+    /// blocking any wait command. In either case, after `delete_sync` returns the name `sync` is
+    /// invalid and can no longer be used to refer to the sync object.
+    ///
+    /// `delete_sync` will silently ignore a `sync` value of zero.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `sync` is neither zero nor the name of a sync object.
+    fn delete_sync(sync: SyncObject));
+
 gl_proc!(glDeleteTextures:
     /// Deletes named textures.
     ///
@@ -738,6 +1136,46 @@ gl_proc!(glDepthFunc:
     /// testing is disabled or if no depth buffer exists it is as if the depth test always passes.
     fn depth_func(func: Comparison));
 
+gl_proc!(glDepthMask:
+    /// Enables or disables writing into the depth buffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDepthMask)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Specifies whether the depth buffer is enabled for writing. If `flag​` is `False` depth
+    /// buffer writing is disabled. Otherwise, it is enabled. Initially, depth buffer writing is
+    /// enabled.
+    ///
+    /// # Notes
+    ///
+    /// - Even if the depth buffer exists and the depth mask is non-zero, the depth buffer is not
+    ///   updated if the depth test is disabled.
+    fn depth_mask(flag: Boolean));
+
+gl_proc!(glDepthRange:
+    /// Specifies mapping of depth values from normalized device coordinates to window coordinates.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDepthRange)
+    ///
+    /// Core since version 1.0
+    ///
+    /// After clipping and division by w, depth coordinates range from -1 to 1, corresponding to
+    /// the near and far clipping planes. `depth_range` specifies a linear mapping of the
+    /// normalized depth coordinates in this range to window depth coordinates. Regardless of the
+    /// actual depth buffer implementation, window coordinate depth values are treated as though
+    /// they range from 0 through 1 (like color components). Thus, the values accepted by
+    /// `depth_range` are both clamped to this range before they are accepted.
+    ///
+    /// The setting of (0, 1) maps the near plane to 0 and the far plane to 1. With this mapping,
+    /// the depth buffer range is fully utilized.
+    ///
+    /// # Notes
+    ///
+    /// - It is not necessary that `near​` be less than `far​`. Reverse mappings such as `near = 1`
+    ///   and `far = 0` are acceptable.
+    fn depth_range(near: f64, far: f64));
+
 gl_proc!(glDetachShader:
     /// Detaches a shader object from a program object to which it is attached.
     ///
@@ -826,6 +1264,53 @@ gl_proc!(glDrawArrays:
     ///    object.
     fn draw_arrays(mode: DrawMode, first: i32, count: i32));
 
+gl_proc!(glDrawArraysInstanced:
+    /// Draws multiple instances of a range of elements.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawArraysInstanced)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Behaves identically to `draw_arrays` except that `instance_count` instances of the range of
+    /// elements are executed and the value of the internal counter `gl_InstanceID` advances for
+    /// each iteration. Vertex attributes with a non-zero divisor (see `vertex_attrib_divisor`)
+    /// advance once per `divisor` instances rather than once per vertex.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if count​ or `instance_count` is negative.
+    /// - `GL_INVALID_OPERATION` is generated if a geometry shader is active and mode​ is
+    ///   incompatible with the input primitive type of the geometry shader in the currently
+    ///   installed program object.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an
+    ///   enabled array and the buffer object's data store is currently mapped.
+    fn draw_arrays_instanced(mode: DrawMode, first: i32, count: i32, instance_count: i32));
+
+gl_proc!(glDrawBuffers:
+    /// Specifies a list of color buffers to be drawn into.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawBuffers)
+    ///
+    /// Core since version 2.0
+    ///
+    /// Defines an array of buffers into which outputs from the fragment shader data will be
+    /// written. If a fragment shader writes a value to one or more user defined output variables,
+    /// then the value of each variable will be written into the buffer specified at a location
+    /// within `buffers` corresponding to the location assigned to that user defined output. The
+    /// draw buffer used for user defined outputs assigned to locations greater than or equal to
+    /// `count` is implicitly set to `None` and any data written to such an output is discarded.
+    ///
+    /// The draw buffers are state of the framebuffer currently bound to `DrawFramebuffer`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if one of the values in `buffers` is not an accepted
+    ///   value.
+    /// - `GL_INVALID_OPERATION` is generated if a symbolic constant other than `None` appears
+    ///   more than once in `buffers`.
+    /// - `GL_INVALID_VALUE` is generated if `count` is greater than `MaxDrawBuffers`.
+    fn draw_buffers(count: i32, buffers: *const DrawBuffer));
+
 gl_proc!(glDrawElements:
     /// Renders primitives from array data.
     ///
@@ -867,6 +1352,133 @@ gl_proc!(glDrawElements:
     ///   mapped.
     fn draw_elements(mode: DrawMode, count: i32, index_type: IndexType, offset: usize));
 
+gl_proc!(glDrawElementsBaseVertex:
+    /// Renders primitives from array data with a per-element offset.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawElementsBaseVertex)
+    ///
+    /// Core since version 3.2
+    ///
+    /// Behaves identically to `draw_elements` except that the `i`th element transferred by the
+    /// corresponding draw call will be taken from element `indices[i] + base_vertex` of each
+    /// enabled array. If the resulting value is larger than the maximum value representable by
+    /// `index_type` it should behave as if the calculation were upconverted to 32-bit unsigned
+    /// integers. If the resulting value is negative the behavior is undefined.
+    ///
+    /// This allows multiple meshes to be stored in a single vertex buffer without needing to
+    /// rewrite the indices of each mesh.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if count​ is negative.
+    /// - `GL_INVALID_OPERATION` is generated if a geometry shader is active and mode​ is
+    ///   incompatible with the input primitive type of the geometry shader in the currently
+    ///   installed program object.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an
+    ///   enabled array or the element array and the buffer object's data store is currently
+    ///   mapped.
+    fn draw_elements_base_vertex(
+        mode: DrawMode,
+        count: i32,
+        index_type: IndexType,
+        offset: usize,
+        base_vertex: i32));
+
+gl_proc!(glDrawElementsInstanced:
+    /// Draws multiple instances of a set of elements.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawElementsInstanced)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Behaves identically to `draw_elements` except that `instance_count` instances of the set
+    /// of elements are executed and the value of the internal counter `gl_InstanceID` advances
+    /// for each iteration. Vertex attributes with a non-zero divisor (see
+    /// `vertex_attrib_divisor`) advance once per `divisor` instances rather than once per vertex.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if count​ or `instance_count` is negative.
+    /// - `GL_INVALID_OPERATION` is generated if a geometry shader is active and mode​ is
+    ///   incompatible with the input primitive type of the geometry shader in the currently
+    ///   installed program object.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an
+    ///   enabled array or the element array and the buffer object's data store is currently
+    ///   mapped.
+    fn draw_elements_instanced(
+        mode: DrawMode,
+        count: i32,
+        index_type: IndexType,
+        offset: usize,
+        instance_count: i32));
+
+gl_proc!(glDrawRangeElements:
+    /// Renders primitives from array data, specifying the range of vertices accessed.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawRangeElements)
+    ///
+    /// Core since version 1.2
+    ///
+    /// A restricted form of `draw_elements`. `mode​`, `count`, `index_type`, and `offset` match
+    /// the corresponding arguments to `draw_elements`, with the additional constraint that all
+    /// values in the index range must lie between `start​` and `end​`, inclusive.
+    ///
+    /// Implementations denote recommended maximum amounts of vertex and index data, which may
+    /// be queried by calling `get` with argument `GL_MAX_ELEMENTS_VERTICES` and
+    /// `GL_MAX_ELEMENTS_INDICES`. If `end - start + 1` is greater than the value of
+    /// `GL_MAX_ELEMENTS_VERTICES`, or if `count​` is greater than the value of
+    /// `GL_MAX_ELEMENTS_INDICES`, then the call may operate at reduced performance. There is no
+    /// requirement that all vertices in the range `[start, end]` be referenced.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if count​ is negative.
+    /// - `GL_INVALID_VALUE` is generated if `end < start`.
+    /// - `GL_INVALID_OPERATION` is generated if a geometry shader is active and mode​ is
+    ///   incompatible with the input primitive type of the geometry shader in the currently
+    ///   installed program object.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an
+    ///   enabled array or the element array and the buffer object's data store is currently
+    ///   mapped.
+    fn draw_range_elements(
+        mode: DrawMode,
+        start: u32,
+        end: u32,
+        count: i32,
+        index_type: IndexType,
+        offset: usize));
+
+gl_proc!(glDrawRangeElementsBaseVertex:
+    /// Renders primitives from array data with a per-element offset, specifying the range of
+    /// vertices accessed.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawRangeElementsBaseVertex)
+    ///
+    /// Core since version 3.2
+    ///
+    /// A restricted form of `draw_elements_base_vertex`. `start` and `end` specify the range of
+    /// index values, before `base_vertex` is added, that may be referenced by the draw. See
+    /// `draw_range_elements` and `draw_elements_base_vertex` for more information.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if count​ is negative.
+    /// - `GL_INVALID_VALUE` is generated if `end < start`.
+    /// - `GL_INVALID_OPERATION` is generated if a geometry shader is active and mode​ is
+    ///   incompatible with the input primitive type of the geometry shader in the currently
+    ///   installed program object.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an
+    ///   enabled array or the element array and the buffer object's data store is currently
+    ///   mapped.
+    fn draw_range_elements_base_vertex(
+        mode: DrawMode,
+        start: u32,
+        end: u32,
+        count: i32,
+        index_type: IndexType,
+        offset: usize,
+        base_vertex: i32));
+
 gl_proc!(glEnable:
     /// Enables server-side GL capabilities.
     ///
@@ -880,12 +1492,50 @@ gl_proc!(glEnable:
     /// `true`.
     fn enable(capability: ServerCapability));
 
+gl_proc!(glEndConditionalRender:
+    /// Ends conditional rendering.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBeginConditionalRender)
+    ///
+    /// Core since version 3.0
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if conditional rendering is not active.
+    fn end_conditional_render());
+
 gl_proc!(glEndQuery:
     /// Delimits the end of a query object.
     ///
     /// TODO: Add documentation.
     fn end_query(query_type: QueryType));
 
+gl_proc!(glFenceSync:
+    /// Creates a new sync object and inserts it into the GL command stream.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFenceSync)
+    ///
+    /// Core since version 3.2
+    ///
+    /// Creates a new fence sync object, inserts a fence command into the GL command stream and
+    /// associates it with that sync object, and returns a non-zero name corresponding to the sync
+    /// object.
+    ///
+    /// When the specified `condition` of the sync object is satisfied by the fence command, the
+    /// sync object is signaled by the GL, causing any `client_wait_sync` or `wait_sync` commands
+    /// blocking on the sync object to unblock. No other state is affected by `fence_sync` or by
+    /// the execution of the associated fence command.
+    ///
+    /// `condition` must be `GpuCommandsComplete`. This condition is satisfied by completion of
+    /// the fence command corresponding to the sync object and all preceding commands in the same
+    /// command stream. `flags` is currently unused and must be zero.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `flags` is not zero.
+    /// - If `fence_sync` fails it will return the null sync object.
+    fn fence_sync(condition: SyncCondition, flags: u32) -> SyncObject);
+
 gl_proc!(glEnableVertexAttribArray:
     /// Enables a generic vertex attribute array.
     ///
@@ -914,6 +1564,47 @@ gl_proc!(glFlush:
     /// TODO: Add documentation.
     fn flush());
 
+gl_proc!(glFramebufferRenderbuffer:
+    /// Attaches a renderbuffer as an image of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFramebufferRenderbuffer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Attaches `renderbuffer` to `attachment` of the framebuffer bound to `target`. If
+    /// `renderbuffer` is `RenderbufferName::null()` the current attachment is detached.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if the default framebuffer is bound to `target`.
+    fn framebuffer_renderbuffer(
+        target: FramebufferTarget,
+        attachment: FramebufferAttachment,
+        renderbuffer_target: RenderbufferTarget,
+        renderbuffer: RenderbufferName));
+
+gl_proc!(glFramebufferTexture2D:
+    /// Attaches a level of a texture object as an image of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFramebufferTexture)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Attaches `level` of `texture` to `attachment` of the framebuffer bound to `target`. If
+    /// `texture` is `TextureObject::null()` the current attachment is detached.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if the default framebuffer is bound to `target`.
+    /// - `GL_INVALID_OPERATION` is generated if `texture` is not zero and `tex_target` doesn't
+    ///   match the type of `texture`.
+    fn framebuffer_texture_2d(
+        target: FramebufferTarget,
+        attachment: FramebufferAttachment,
+        tex_target: Texture2dTarget,
+        texture: TextureObject,
+        level: i32));
+
 gl_proc!(glFrontFace:
     /// Defines front- and back-facing polygons.
     ///
@@ -960,6 +1651,40 @@ gl_proc!(glGenBuffers:
     /// `GL_INVALID_VALUE` is generated if `num_buffers`​ is negative.
     fn gen_buffers(num_buffers: i32, buffers: *mut BufferName));
 
+gl_proc!(glGenFramebuffers:
+    /// Generates framebuffer object names.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGenFramebuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Returns `count` framebuffer object names in `framebuffers`. The framebuffer objects are
+    /// not created until they are first bound with `bind_framebuffer`.
+    fn gen_framebuffers(count: i32, framebuffers: *mut FramebufferName));
+
+gl_proc!(glGenerateMipmap:
+    /// Generates mipmaps for the texture bound to `target`.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGenerateMipmap)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Computes every mipmap level below the base level by repeatedly filtering the previous
+    /// level down to half its size, replacing any existing contents of those levels.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if `target` is not an accepted value.
+    fn generate_mipmap(target: TextureParameterTarget));
+
+gl_proc!(glGenRenderbuffers:
+    /// Generates renderbuffer object names.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGenRenderbuffers)
+    ///
+    /// Core since version 3.0
+    fn gen_renderbuffers(count: i32, renderbuffers: *mut RenderbufferName));
+
 gl_proc!(glGenTextures:
     /// Generates texture names.
     ///
@@ -1014,6 +1739,39 @@ gl_proc!(glGenVertexArrays:
     /// `GL_INVALID_VALUE` is generated if `num_arrays`​ is negative.
     fn gen_vertex_arrays(num_arrays: i32, arrays: *mut VertexArrayName));
 
+gl_proc!(glGetActiveUniform:
+    /// Returns information about an active uniform variable for the specified program object.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGetActiveUniform)
+    ///
+    /// Core since version 2.0
+    ///
+    /// `index` must be less than the value of `ActiveUniforms` for the program (see
+    /// `get_program_param`). The name of the uniform is written to `name`, which must have room
+    /// for `buf_size` bytes including the null terminator. The number of bytes written (excluding
+    /// the null terminator) is written to `length`. The number of elements in the uniform (1 for
+    /// non-array uniforms) is written to `size`, and the type of the uniform is written to
+    /// `uniform_type`.
+    ///
+    /// `uniform_type` is left as a raw `GLenum` since the driver may return types that don't
+    /// have a corresponding `UniformType` variant, use `UniformType::from_raw` to convert it.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `program` is not a value generated by OpenGL.
+    /// - `GL_INVALID_OPERATION` is generated if `program` is not a program object.
+    /// - `GL_INVALID_VALUE` is generated if `index` is greater than or equal to the number of
+    ///   active uniform variables in `program`.
+    /// - `GL_INVALID_VALUE` is generated if `buf_size` is less than 0.
+    fn get_active_uniform(
+        program: ProgramObject,
+        index: u32,
+        buf_size: i32,
+        length: *mut i32,
+        size: *mut i32,
+        uniform_type: *mut u32,
+        name: *mut u8));
+
 gl_proc!(glGetAttribLocation:
     /// Returns the location of an attribute variable.
     ///
@@ -1043,6 +1801,16 @@ gl_proc!(glGetAttribLocation:
     /// - `GL_INVALID_OPERATION` is generated if `program` has not been successfully linked.
     fn get_attrib_location(program: ProgramObject, name: *const u8) -> i32);
 
+gl_proc!(glGetFloatv:
+    /// Returns the value or values of a selected floating-point parameter.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGet)
+    ///
+    /// Core since version 1.0
+    ///
+    /// See `get_integers` for details on type conversion.
+    fn get_floats(name: FloatName, params: *mut f32));
+
 gl_proc!(glGetInteger64v:
     /// Returns the value for simple state variables.
     ///
@@ -1335,6 +2103,22 @@ gl_proc!(glGetString:
     /// information.
     fn get_string(name: StringName) -> *const i8);
 
+gl_proc!(glGetStringi:
+    /// Returns a string describing an indexed part of the current OpenGL connection.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGetString)
+    ///
+    /// Core since version 3.0
+    ///
+    /// The only accepted value for `name` is `Extensions`, in which case the name of the
+    /// extension at `index` is returned. `index` must be less than the value of `NumExtensions`
+    /// (see `get_integers()`).
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `index` is outside the valid range for `name`.
+    fn get_string_i(name: StringName, index: u32) -> *const i8);
+
 gl_proc!(glUniform1f:
     /// Specify the value of a uniform variable for the current program object.
     ///
@@ -1883,6 +2667,25 @@ gl_proc!(glUniformMatrix3fv:
         transpose: Boolean,
         values: *const f32));
 
+gl_proc!(glGetUniformBlockIndex:
+    /// Retrieves the index of a named uniform block.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGetUniformBlockIndex)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Retrieves the index of the uniform block named `uniform_block_name` within `program`.
+    /// `uniform_block_name` must be a null terminated string. If `uniform_block_name` does not
+    /// identify an active uniform block of `program`, `GL_INVALID_INDEX` (`0xFFFFFFFF`) is
+    /// returned. The indices of the active uniform blocks of a program are assigned in
+    /// consecutive order, beginning with zero.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if `program` is not the name of a program object for
+    ///   which `link_program` has been called in the past.
+    fn get_uniform_block_index(program: ProgramObject, uniform_block_name: *const u8) -> u32);
+
 gl_proc!(glGetUniformLocation:
     /// Returns the location of a uniform variable.
     ///
@@ -2035,6 +2838,51 @@ gl_proc!(glLinkProgram:
     ///   and transform feedback mode is active.
     fn link_program(program: ProgramObject));
 
+gl_proc!(glMapBufferRange:
+    /// Maps all or part of a buffer object's data store into the client's address space.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glMapBufferRange)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Maps all or part of the data store of the buffer object bound to `target` into the
+    /// client's address space. `offset` and `length` indicate the range of data in the buffer
+    /// object that is to be mapped, in terms of basic machine units (bytes). `access` is a
+    /// bitfield containing flags which describe the requested mapping:
+    ///
+    /// - `MAP_READ` - The returned pointer may be used to read buffer object data.
+    /// - `MAP_WRITE` - The returned pointer may be used to modify buffer object data.
+    /// - `MAP_PERSISTENT` - The mapping is to be made in a persistent fashion and that the client
+    ///   intends to hold and use the returned pointer during subsequent GL operation. It is not
+    ///   an error to call drawing commands while the buffer is mapped using this flag.
+    /// - `MAP_COHERENT` - A persistent mapping is also to be coherent. Writes to the mapped range
+    ///   by the client will become visible to the server without further action by the client.
+    /// - `MAP_INVALIDATE_RANGE` - The previous contents of the specified range may be discarded.
+    /// - `MAP_INVALIDATE_BUFFER` - The previous contents of the entire buffer may be discarded.
+    /// - `MAP_FLUSH_EXPLICIT` - One or more discrete subranges of the mapping may be modified and
+    ///   must be flushed with `flush_mapped_buffer_range`.
+    /// - `MAP_UNSYNCHRONIZED` - The GL should not attempt to synchronize pending operations on
+    ///   the buffer prior to returning from `map_buffer_range`.
+    ///
+    /// If no error occurs a pointer to the beginning of the mapped range is returned. If an error
+    /// occurs a null pointer is returned.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if either of `offset` or `length` is negative, or if
+    ///   `offset + length` is greater than the value of `GL_BUFFER_SIZE`.
+    /// - `GL_INVALID_OPERATION` is generated if the buffer is already in a mapped state.
+    /// - `GL_INVALID_OPERATION` is generated if neither `MAP_READ` or `MAP_WRITE` is set.
+    /// - `GL_INVALID_OPERATION` is generated if any of `MAP_PERSISTENT` or `MAP_COHERENT` are set
+    ///   but the corresponding flag was not specified when the buffer's storage was created with
+    ///   `buffer_storage_raw`.
+    fn map_buffer_range(
+        target: BufferTarget,
+        offset: isize,
+        length: isize,
+        access: MapAccessFlags
+    ) -> *mut ());
+
 gl_proc!(glObjectLabel:
     /// Labels a named object for use in debug messages.
     ///
@@ -2081,6 +2929,63 @@ gl_proc!(glPolygonMode:
     /// `edge_flag`.
     fn polygon_mode(face: Face, mode: PolygonMode));
 
+gl_proc!(glPolygonOffset:
+    /// Sets the scale and units used to calculate depth values.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glPolygonOffset)
+    ///
+    /// Core since version 1.1
+    ///
+    /// When `PolygonOffsetFill`, `PolygonOffsetLine`, or `PolygonOffsetPoint` is enabled, each
+    /// fragment's depth value will be offset after it is interpolated from the depth values of
+    /// the appropriate vertices. The value of the offset is `factor * DZ + r * units`, where `DZ`
+    /// is a measurement of the change in depth relative to the screen area of the polygon, and
+    /// `r` is the smallest value that is guaranteed to produce a resolvable offset for a given
+    /// implementation. The offset is added before the depth test is performed and before the
+    /// value is written into the depth buffer.
+    ///
+    /// Polygon offset is useful for rendering hidden-line images, for applying decals to
+    /// surfaces, and for rendering solids with highlighted edges.
+    fn polygon_offset(factor: f32, units: f32));
+
+gl_proc!(glPopDebugGroup:
+    /// Pops the active debug group.
+    ///
+    /// [Official docs](https://www.khronos.org/opengl/wiki/GLAPI/glPopDebugGroup)
+    ///
+    /// Core since version 4.3
+    ///
+    /// Ends the debug group most recently started with `push_debug_group()`, generating a debug
+    /// message with the same source, id, and message as the one that started it.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_STACK_UNDERFLOW` is generated if the debug group stack only contains the default
+    ///   group.
+    fn pop_debug_group());
+
+gl_proc!(glPushDebugGroup:
+    /// Pushes a named debug group onto the debug group stack.
+    ///
+    /// [Official docs](https://www.khronos.org/opengl/wiki/GLAPI/glPushDebugGroup)
+    ///
+    /// Core since version 4.3
+    ///
+    /// Starts a debug group annotated with `message`, which points to `length` characters, or to
+    /// a null-terminated string if `length` is negative. Commands issued until the matching
+    /// `pop_debug_group()` are grouped under the message in debugging tools such as RenderDoc and
+    /// Nsight. `source` must be `DebugSource::Application` or `DebugSource::ThirdParty`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if `source` is not `DebugSource::Application` or
+    ///   `DebugSource::ThirdParty`.
+    /// - `GL_INVALID_VALUE` is generated if `length` is negative and `message` is not
+    ///   null-terminated, or if the length of the message is `GL_MAX_DEBUG_MESSAGE_LENGTH` or
+    ///   greater.
+    /// - `GL_STACK_OVERFLOW` is generated if the debug group stack is full.
+    fn push_debug_group(source: DebugSource, id: u32, length: i32, message: *const u8));
+
 gl_proc!(glQueryCounter:
     /// Records the GL time into a query object after all previous commands have reached the GL
     /// server.
@@ -2104,6 +3009,120 @@ gl_proc!(glQueryCounter:
     ///   from a previous call to `gen_queries()`.
     fn query_counter(query: QueryObject, target: QueryCounterTarget));
 
+gl_proc!(glReadBuffer:
+    /// Selects a color buffer source for pixels.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glReadBuffer)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Sets the buffer of the framebuffer bound to `ReadFramebuffer` that is used by pixel read
+    /// operations such as `blit_framebuffer`. Framebuffers without a color attachment (e.g. a
+    /// depth-only shadow map) should set this to `DrawBuffer::None`, since on versions before
+    /// 4.1 the framebuffer is otherwise incomplete.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if `mode` is not an accepted value.
+    /// - `GL_INVALID_OPERATION` is generated if `mode` refers to a buffer that doesn't exist for
+    ///   the current framebuffer.
+    fn read_buffer(mode: DrawBuffer));
+
+gl_proc!(glReadPixels:
+    /// Reads a block of pixels from the framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glReadPixels)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Returns pixel data from the framebuffer bound to `ReadFramebuffer`, starting with the
+    /// pixel whose lower left corner is at `(x, y)`, into client memory starting at location
+    /// `data`. Color values are read from the buffer selected with `read_buffer`. Pixels are
+    /// returned row by row from the lowest to the highest row, and each row is padded to the
+    /// alignment set with `GL_PACK_ALIGNMENT`, initially 4.
+    ///
+    /// If a non-zero buffer object is bound to `BufferTarget::PixelPack` `data` is treated as a
+    /// byte offset into the buffer object's data store instead.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_ENUM` is generated if `format` or `data_type` is not an accepted value.
+    /// - `GL_INVALID_VALUE` is generated if either width or height is negative.
+    /// - `GL_INVALID_OPERATION` is generated if the read framebuffer is not framebuffer complete.
+    fn read_pixels(
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data_type: TextureDataType,
+        data: *mut ()));
+
+gl_proc!(glRenderbufferStorage:
+    /// Establishes the data storage, format, and dimensions of a renderbuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glRenderbufferStorage)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Equivalent to calling `renderbuffer_storage_multisample` with `samples` set to 0.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if either `width` or `height` is negative or greater
+    ///   than `GL_MAX_RENDERBUFFER_SIZE`.
+    /// - `GL_OUT_OF_MEMORY` is generated if the GL is unable to create a data store of the
+    ///   requested size.
+    fn renderbuffer_storage(
+        target: RenderbufferTarget,
+        internal_format: TextureInternalFormat,
+        width: i32,
+        height: i32));
+
+gl_proc!(glRenderbufferStorageMultisample:
+    /// Establishes the data storage, format, dimensions, and sample count of a renderbuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glRenderbufferStorageMultisample)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Any existing data store for the renderbuffer is deleted and the contents of the new data
+    /// store are undefined. The number of samples allocated may be larger than `samples`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `samples` is greater than `MaxSamples`.
+    /// - `GL_INVALID_VALUE` is generated if either `width` or `height` is negative or greater
+    ///   than `GL_MAX_RENDERBUFFER_SIZE`.
+    /// - `GL_OUT_OF_MEMORY` is generated if the GL is unable to create a data store of the
+    ///   requested size.
+    fn renderbuffer_storage_multisample(
+        target: RenderbufferTarget,
+        samples: i32,
+        internal_format: TextureInternalFormat,
+        width: i32,
+        height: i32));
+
+gl_proc!(glScissor:
+    /// Defines the scissor box.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glScissor)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Defines a rectangle, called the scissor box, in window coordinates. The first two
+    /// arguments, `x` and `y`, specify the lower left corner of the box. `width` and `height`
+    /// specify the width and height of the box.
+    ///
+    /// While `ServerCapability::ScissorTest` is enabled only pixels that lie within the scissor
+    /// box can be modified by drawing or clearing commands. Initially the scissor test is
+    /// disabled and the scissor box is the full size of the window.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if either width or height is negative.
+    fn scissor(x: i32, y: i32, width: i32, height: i32));
+
 gl_proc!(glShaderSource:
     /// Replaces the source code in a shader object.
     ///
@@ -2246,6 +3265,19 @@ gl_proc!(glTexImage2D:
         data_type: TextureDataType,
         data: *const ()));
 
+gl_proc!(glTexParameterf:
+    /// Sets floating-point texture parameters.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glTexParameter)
+    ///
+    /// Core since version 1.0
+    ///
+    /// See `texture_parameter_i32`.
+    fn texture_parameter_f32(
+        target: TextureParameterTarget,
+        name: TextureParameterName,
+        param: f32));
+
 gl_proc!(glTexParameteri:
     /// Sets texture parameters.
     ///
@@ -2259,6 +3291,77 @@ gl_proc!(glTexParameteri:
         name: TextureParameterName,
         param: i32));
 
+gl_proc!(glTexSubImage2D:
+    /// Replaces a rectangular region of a two-dimensional texture image.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glTexSubImage2D)
+    ///
+    /// Core since version 1.1
+    ///
+    /// The region starts at `x_offset` and `y_offset` and must lie within the texture image
+    /// specified for `level`. If a buffer is bound to `PixelUnpack`, `data` is an offset into
+    /// that buffer instead of a pointer to client memory.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if the region extends past the edges of the texture
+    ///   image.
+    /// - `GL_INVALID_OPERATION` is generated if the texture image hasn't been specified.
+    fn texture_sub_image_2d(
+        target: Texture2dTarget,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data_type: TextureDataType,
+        data: *const ()));
+
+gl_proc!(glUniformBlockBinding:
+    /// Assigns a binding point to an active uniform block.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glUniformBlockBinding)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Binding points for active uniform blocks are assigned using `uniform_block_binding`. Each
+    /// of a program's active uniform blocks has a corresponding uniform buffer binding point.
+    /// `program` is the name of a program object for which the command `link_program` has been
+    /// issued in the past. If successful, `uniform_block_binding` specifies that `program` will
+    /// use the data store of the buffer object bound to the binding point
+    /// `uniform_block_binding` to extract the values of the uniforms in the uniform block
+    /// identified by `uniform_block_index`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `uniform_block_index` is not an active uniform block
+    ///   index of `program`.
+    /// - `GL_INVALID_VALUE` is generated if `uniform_block_binding` is greater than or equal to
+    ///   the value of `GL_MAX_UNIFORM_BUFFER_BINDINGS`.
+    /// - `GL_INVALID_VALUE` is generated if `program` is not the name of a program object
+    ///   generated by the GL.
+    fn uniform_block_binding(program: ProgramObject, uniform_block_index: u32, uniform_block_binding: u32));
+
+gl_proc!(glUnmapBuffer:
+    /// Releases the mapping of a buffer object's data store into the client's address space.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glMapBuffer)
+    ///
+    /// Core since version 1.5
+    ///
+    /// Unmaps the buffer object bound to `target`. Returns `True` unless the data store contents
+    /// have become corrupt during the time the data store was mapped. This can occur for
+    /// system-specific reasons that affect the availability of graphics memory, such as screen
+    /// mode changes. In such situations `False` is returned and the data store contents are
+    /// undefined.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if the buffer bound to `target` is not currently
+    ///   mapped.
+    fn unmap_buffer(target: BufferTarget) -> Boolean);
+
 gl_proc!(glUseProgram:
     /// Installs a program as part of the current rendering state.
     ///
@@ -2400,6 +3503,53 @@ gl_proc!(glVertexAttribPointer:
         stride: i32,
         offset: usize));
 
+gl_proc!(glVertexAttribIPointer:
+    /// Defines an array of generic integer vertex attribute data.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexAttribPointer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Behaves the same as `vertex_attrib_pointer` except that the values in the array are left
+    /// as integers when they are accessed by the shader. The attribute must be declared with an
+    /// integer type (e.g. `int`, `uvec4`) in the shader. Only the integer types `Byte`,
+    /// `UnsignedByte`, `Short`, `UnsignedShort`, `Int`, and `UnsignedInt` are accepted for
+    /// `gl_type`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if index​ is greater than or equal to `GL_MAX_VERTEX_ATTRIBS`.
+    /// - `GL_INVALID_VALUE` is generated if size​ is not 1, 2, 3, or 4.
+    /// - `GL_INVALID_ENUM` is generated if type​ is not an accepted value.
+    /// - `GL_INVALID_VALUE` is generated if stride​ is negative.
+    /// - `GL_INVALID_OPERATION` is generated if zero is bound to the `BufferTarget::Array` buffer
+    ///   object binding point and the offset argument is not 0.
+    /// - `GL_INVALID_OPERATION` is generated if no vertex array object is bound.
+    fn vertex_attrib_i_pointer(
+        attrib: AttributeLocation,
+        size: i32,
+        gl_type: GlType,
+        stride: i32,
+        offset: usize));
+
+gl_proc!(glVertexAttribDivisor:
+    /// Modifies the rate at which generic vertex attributes advance during instanced rendering.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexAttribDivisor)
+    ///
+    /// Core since version 3.3
+    ///
+    /// If `divisor` is zero the attribute at `attrib` advances once per vertex. If `divisor` is
+    /// non-zero the attribute advances once per `divisor` instances of the set(s) of vertices
+    /// being rendered, see `draw_arrays_instanced` and `draw_elements_instanced`. The divisor is
+    /// saved as part of the state of the currently bound vertex array object.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if index​ is greater than or equal to `GL_MAX_VERTEX_ATTRIBS`.
+    /// - `GL_INVALID_OPERATION` is generated if no vertex array object is bound.
+    fn vertex_attrib_divisor(attrib: AttributeLocation, divisor: u32));
+
 gl_proc!(glViewport:
     /// Sets the viewport.
     ///
@@ -2429,6 +3579,7 @@ pub mod wgl {
     pub use platform::{
         create_context_attribs,
         get_extension_string,
+        get_pixel_format_attribiv,
         get_swap_interval,
         set_swap_interval,
     };
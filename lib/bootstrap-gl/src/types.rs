@@ -61,6 +61,14 @@ impl AttributeLocation {
     }
 }
 
+/// The interpolation used when a blit stretches an image, see `blit_framebuffer`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlitFilter {
+    Nearest = 0x2600,
+    Linear = 0x2601,
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BufferName(pub u32);
@@ -75,6 +83,38 @@ impl BufferName {
     }
 }
 
+/// Bitmask specifying the intended usage of an immutable buffer data store.
+///
+/// See `buffer_storage_raw` for a description of each flag.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferStorageFlags(pub u32);
+
+impl BufferStorageFlags {
+    pub const MAP_READ: BufferStorageFlags = BufferStorageFlags(0x0001);
+    pub const MAP_WRITE: BufferStorageFlags = BufferStorageFlags(0x0002);
+    pub const MAP_PERSISTENT: BufferStorageFlags = BufferStorageFlags(0x0040);
+    pub const MAP_COHERENT: BufferStorageFlags = BufferStorageFlags(0x0080);
+    pub const DYNAMIC_STORAGE: BufferStorageFlags = BufferStorageFlags(0x0100);
+    pub const CLIENT_STORAGE: BufferStorageFlags = BufferStorageFlags(0x0200);
+
+    pub const fn empty() -> BufferStorageFlags {
+        BufferStorageFlags(0)
+    }
+
+    pub fn contains(self, other: BufferStorageFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for BufferStorageFlags {
+    type Output = BufferStorageFlags;
+
+    fn bitor(self, rhs: BufferStorageFlags) -> BufferStorageFlags {
+        BufferStorageFlags(self.0 | rhs.0)
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BufferTarget {
@@ -125,6 +165,98 @@ impl BitOr for ClearBufferMask {
     }
 }
 
+/// The depth range that clip space is mapped to, see `clip_control`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipDepth {
+    /// The default, mapping clip-space depth from -1 to 1 onto the depth range.
+    NegativeOneToOne = 0x935E,
+
+    /// Maps clip-space depth from 0 to 1 onto the depth range, which avoids losing precision
+    /// when converting depth for reverse-Z projections.
+    ZeroToOne = 0x935F,
+}
+
+impl Default for ClipDepth {
+    fn default() -> ClipDepth { ClipDepth::NegativeOneToOne }
+}
+
+/// The origin of window coordinates, see `clip_control`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipOrigin {
+    LowerLeft = 0x8CA1,
+    UpperLeft = 0x8CA2,
+}
+
+impl Default for ClipOrigin {
+    fn default() -> ClipOrigin { ClipOrigin::LowerLeft }
+}
+
+/// Internal formats for pre-compressed texture data.
+///
+/// The S3TC formats require `EXT_texture_compression_s3tc` (and `EXT_texture_sRGB` for the sRGB
+/// variants). The ETC2/EAC formats are core since version 4.3.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressedTextureFormat {
+    // S3TC
+    RgbS3tcDxt1 = 0x83F0,
+    RgbaS3tcDxt1 = 0x83F1,
+    RgbaS3tcDxt3 = 0x83F2,
+    RgbaS3tcDxt5 = 0x83F3,
+    SrgbS3tcDxt1 = 0x8C4C,
+    SrgbAlphaS3tcDxt1 = 0x8C4D,
+    SrgbAlphaS3tcDxt3 = 0x8C4E,
+    SrgbAlphaS3tcDxt5 = 0x8C4F,
+
+    // ETC2/EAC
+    R11Eac = 0x9270,
+    SignedR11Eac = 0x9271,
+    Rg11Eac = 0x9272,
+    SignedRg11Eac = 0x9273,
+    Rgb8Etc2 = 0x9274,
+    Srgb8Etc2 = 0x9275,
+    Rgb8PunchthroughAlpha1Etc2 = 0x9276,
+    Srgb8PunchthroughAlpha1Etc2 = 0x9277,
+    Rgba8Etc2Eac = 0x9278,
+    Srgb8Alpha8Etc2Eac = 0x9279,
+}
+
+impl CompressedTextureFormat {
+    /// Returns the size in bytes of a single 4x4 block of texels.
+    pub fn block_size(&self) -> usize {
+        match *self {
+            CompressedTextureFormat::RgbS3tcDxt1
+            | CompressedTextureFormat::RgbaS3tcDxt1
+            | CompressedTextureFormat::SrgbS3tcDxt1
+            | CompressedTextureFormat::SrgbAlphaS3tcDxt1
+            | CompressedTextureFormat::R11Eac
+            | CompressedTextureFormat::SignedR11Eac
+            | CompressedTextureFormat::Rgb8Etc2
+            | CompressedTextureFormat::Srgb8Etc2
+            | CompressedTextureFormat::Rgb8PunchthroughAlpha1Etc2
+            | CompressedTextureFormat::Srgb8PunchthroughAlpha1Etc2 => 8,
+
+            CompressedTextureFormat::RgbaS3tcDxt3
+            | CompressedTextureFormat::RgbaS3tcDxt5
+            | CompressedTextureFormat::SrgbAlphaS3tcDxt3
+            | CompressedTextureFormat::SrgbAlphaS3tcDxt5
+            | CompressedTextureFormat::Rg11Eac
+            | CompressedTextureFormat::SignedRg11Eac
+            | CompressedTextureFormat::Rgba8Etc2Eac
+            | CompressedTextureFormat::Srgb8Alpha8Etc2Eac => 16,
+        }
+    }
+
+    /// Returns the size in bytes of a compressed image with the specified dimensions.
+    pub fn image_size(&self, width: usize, height: usize) -> usize {
+        let blocks_wide = (width + 3) / 4;
+        let blocks_high = (height + 3) / 4;
+        blocks_wide * blocks_high * self.block_size()
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Comparison {
@@ -138,6 +270,76 @@ pub enum Comparison {
     Always = 0x0207,
 }
 
+/// Specifies how conditional rendering waits on the query result.
+///
+/// See `begin_conditional_render`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConditionalRenderMode {
+    /// Wait for the query result before rendering.
+    QueryWait = 0x8E13,
+
+    /// Don't wait for the query result, rendering as normal if it isn't available.
+    QueryNoWait = 0x8E14,
+
+    /// Like `QueryWait`, but the GL may discard rendering per screen region.
+    QueryByRegionWait = 0x8E15,
+
+    /// Like `QueryNoWait`, but the GL may discard rendering per screen region.
+    QueryByRegionNoWait = 0x8E16,
+}
+
+/// Options used when creating an OpenGL context.
+///
+/// The default configuration lets the driver pick the context version and profile, which
+/// generally results in the highest supported compatibility profile context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContextConfig {
+    /// The minimum GL version to request, as `(major, minor)`.
+    ///
+    /// If `None` the driver's default version is used.
+    pub version: Option<(u32, u32)>,
+
+    /// The profile to request.
+    ///
+    /// Profiles are only supported for GL 3.2 and later, so this is ignored if `version` is
+    /// earlier than 3.2.
+    pub profile: ContextProfile,
+
+    /// Whether or not to request a debug context.
+    ///
+    /// Debug contexts may be slower, but generate more detailed debug output.
+    pub debug: bool,
+
+    /// Whether or not rendering to the default framebuffer should perform linear to sRGB
+    /// conversion.
+    ///
+    /// This requires that the window's pixel format is sRGB-capable, which is fixed when the window
+    /// is created. If it isn't, a warning is printed and only framebuffer attachments with an sRGB
+    /// format are converted.
+    pub srgb: bool,
+}
+
+impl Default for ContextConfig {
+    fn default() -> ContextConfig {
+        ContextConfig {
+            version: None,
+            profile: ContextProfile::Compatibility,
+            debug: false,
+            srgb: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContextProfile {
+    /// Only the core GL functionality is available, deprecated functionality is removed.
+    Core,
+
+    /// Deprecated functionality is available in addition to the core functionality.
+    Compatibility,
+}
+
 pub type DebugMessageCallback = extern "system" fn(DebugSource, DebugType, UInt, DebugSeverity, SizeI, *const u8, *mut ());
 
 #[repr(u32)]
@@ -208,6 +410,51 @@ impl Default for DestFactor {
     fn default() -> DestFactor { DestFactor::Zero }
 }
 
+/// A buffer that a fragment shader output can be written to, see `draw_buffers`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrawBuffer {
+    /// The fragment shader output is discarded.
+    None = 0,
+
+    // Default framebuffer.
+    FrontLeft = 0x0400,
+    FrontRight = 0x0401,
+    BackLeft = 0x0402,
+    BackRight = 0x0403,
+
+    // Framebuffer objects.
+    Color0 = 0x8CE0,
+    Color1 = 0x8CE1,
+    Color2 = 0x8CE2,
+    Color3 = 0x8CE3,
+    Color4 = 0x8CE4,
+    Color5 = 0x8CE5,
+    Color6 = 0x8CE6,
+    Color7 = 0x8CE7,
+}
+
+impl DrawBuffer {
+    /// Returns the draw buffer for the color attachment with the specified index.
+    ///
+    /// # Panics
+    ///
+    /// - If `index` is greater than 7.
+    pub fn color(index: u32) -> DrawBuffer {
+        match index {
+            0 => DrawBuffer::Color0,
+            1 => DrawBuffer::Color1,
+            2 => DrawBuffer::Color2,
+            3 => DrawBuffer::Color3,
+            4 => DrawBuffer::Color4,
+            5 => DrawBuffer::Color5,
+            6 => DrawBuffer::Color6,
+            7 => DrawBuffer::Color7,
+            _ => panic!("Color attachment index {} is out of range", index),
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DrawMode {
@@ -247,6 +494,88 @@ impl Default for Face {
     fn default() -> Face { Face::Back }
 }
 
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatName {
+    // Core in version 4.6, otherwise requires EXT_texture_filter_anisotropic.
+    MaxTextureMaxAnisotropy = 0x84FF,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramebufferAttachment {
+    Color0 = 0x8CE0,
+    Color1 = 0x8CE1,
+    Color2 = 0x8CE2,
+    Color3 = 0x8CE3,
+    Color4 = 0x8CE4,
+    Color5 = 0x8CE5,
+    Color6 = 0x8CE6,
+    Color7 = 0x8CE7,
+    DepthStencil = 0x821A,
+    Depth = 0x8D00,
+    Stencil = 0x8D20,
+}
+
+impl FramebufferAttachment {
+    /// Returns the color attachment point for the specified index.
+    ///
+    /// # Panics
+    ///
+    /// - If `index` is greater than 7.
+    pub fn color(index: u32) -> FramebufferAttachment {
+        match index {
+            0 => FramebufferAttachment::Color0,
+            1 => FramebufferAttachment::Color1,
+            2 => FramebufferAttachment::Color2,
+            3 => FramebufferAttachment::Color3,
+            4 => FramebufferAttachment::Color4,
+            5 => FramebufferAttachment::Color5,
+            6 => FramebufferAttachment::Color6,
+            7 => FramebufferAttachment::Color7,
+            _ => panic!("Color attachment index {} is out of range", index),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FramebufferName(pub u32);
+
+impl FramebufferName {
+    pub const fn null() -> FramebufferName {
+        FramebufferName(0)
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == FramebufferName(0)
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramebufferStatus {
+    /// Returned if an error occurs while checking the framebuffer status.
+    Error = 0,
+    Complete = 0x8CD5,
+    Undefined = 0x8219,
+    IncompleteAttachment = 0x8CD6,
+    IncompleteMissingAttachment = 0x8CD7,
+    IncompleteDrawBuffer = 0x8CDB,
+    IncompleteReadBuffer = 0x8CDC,
+    Unsupported = 0x8CDD,
+    IncompleteMultisample = 0x8D56,
+    IncompleteLayerTargets = 0x8DA8,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramebufferTarget {
+    Framebuffer = 0x8D40,
+    ReadFramebuffer = 0x8CA8,
+    DrawFramebuffer = 0x8CA9,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GlType {
@@ -286,10 +615,58 @@ pub enum Integer64Name {
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum IntegerName {
+    MaxTextureSize = 0x0D33,
+    MaxCubeMapTextureSize = 0x851C,
+    MaxDrawBuffers = 0x8824,
+    MaxVertexAttribs = 0x8869,
+    MaxTextureImageUnits = 0x8872,
+    MaxCombinedTextureImageUnits = 0x8B4D,
+
+    /// The current viewport as `[x, y, width, height]`.
+    ///
+    /// Returns 4 values, so it can't be used with `get_integer()`.
+    Viewport = 0x0BA2,
+
     // Version 3.0
     MajorVersion = 0x821B,
     MinorVersion = 0x821C,
     NumExtensions = 0x821D,
+    MaxColorAttachments = 0x8CDF,
+    MaxSamples = 0x8D57,
+}
+
+/// Bitmask specifying the access requested when mapping a buffer.
+///
+/// See `map_buffer_range` for a description of each flag.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MapAccessFlags(pub u32);
+
+impl MapAccessFlags {
+    pub const MAP_READ: MapAccessFlags = MapAccessFlags(0x0001);
+    pub const MAP_WRITE: MapAccessFlags = MapAccessFlags(0x0002);
+    pub const MAP_INVALIDATE_RANGE: MapAccessFlags = MapAccessFlags(0x0004);
+    pub const MAP_INVALIDATE_BUFFER: MapAccessFlags = MapAccessFlags(0x0008);
+    pub const MAP_FLUSH_EXPLICIT: MapAccessFlags = MapAccessFlags(0x0010);
+    pub const MAP_UNSYNCHRONIZED: MapAccessFlags = MapAccessFlags(0x0020);
+    pub const MAP_PERSISTENT: MapAccessFlags = MapAccessFlags(0x0040);
+    pub const MAP_COHERENT: MapAccessFlags = MapAccessFlags(0x0080);
+
+    pub const fn empty() -> MapAccessFlags {
+        MapAccessFlags(0)
+    }
+
+    pub fn contains(self, other: MapAccessFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for MapAccessFlags {
+    type Output = MapAccessFlags;
+
+    fn bitor(self, rhs: MapAccessFlags) -> MapAccessFlags {
+        MapAccessFlags(self.0 | rhs.0)
+    }
 }
 
 #[repr(u32)]
@@ -369,6 +746,26 @@ pub enum QueryType {
     TimeElapsed = 0x88BF,
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderbufferName(pub u32);
+
+impl RenderbufferName {
+    pub const fn null() -> RenderbufferName {
+        RenderbufferName(0)
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == RenderbufferName(0)
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderbufferTarget {
+    Renderbuffer = 0x8D41,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ServerCapability {
@@ -401,6 +798,10 @@ pub enum ServerCapability {
     // Introduced: OpenGL 3.0
     FramebufferSrgb = 0x8DB9,
 
+    // Introduced: OpenGL 3.2
+    ProgramPointSize = 0x8642,
+    TextureCubeMapSeamless = 0x884F,
+
     // Introduced: OpenGL 4.3
     DebugOutput = 0x92E0,
 }
@@ -470,6 +871,46 @@ pub enum StringName {
     Extensions = 0x1F03,
 }
 
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncCondition {
+    GpuCommandsComplete = 0x9117,
+}
+
+/// An opaque handle to a GL sync object.
+///
+/// Unlike most GL object names sync objects are represented by a pointer, so `SyncObject` is
+/// neither `Send` nor `Sync`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyncObject(pub *const ());
+
+impl SyncObject {
+    pub const fn null() -> SyncObject {
+        SyncObject(0 as *const ())
+    }
+
+    pub fn is_null(self) -> bool {
+        self.0.is_null()
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncStatus {
+    AlreadySignaled = 0x911A,
+    TimeoutExpired = 0x911B,
+    ConditionSatisfied = 0x911C,
+    WaitFailed = 0x911D,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncWaitFlags {
+    None = 0,
+    FlushCommands = 0x00000001,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Texture2dTarget {
@@ -493,6 +934,18 @@ pub enum TextureBindTarget {
     CubeMap = 0x8513,
 }
 
+/// Specifies how depth textures are sampled.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureCompareMode {
+    /// The depth value is returned directly.
+    None = 0,
+
+    /// The depth value is compared against the reference value using the texture's compare
+    /// function, and the result of the comparison is returned. Used with `sampler2DShadow`.
+    CompareRefToTexture = 0x884E,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TextureDataType {
@@ -542,6 +995,7 @@ pub enum TextureFormat {
     Rgba = 0x1908,
     Bgr = 0x80E0,
     Bgra = 0x80E1,
+    DepthComponent = 0x1902,
     // GL_COLOR_INDEX,
     // GL_RED,
     // GL_GREEN,
@@ -558,6 +1012,7 @@ impl TextureFormat {
             TextureFormat::Rgba => 4,
             TextureFormat::Bgr => 3,
             TextureFormat::Bgra => 4,
+            TextureFormat::DepthComponent => 1,
         }
     }
 }
@@ -571,6 +1026,15 @@ pub enum TextureInternalFormat {
     Four = 4,
     Rgb = 0x1907,
     Rgba = 0x1908,
+    DepthComponent = 0x1902,
+    DepthComponent16 = 0x81A5,
+    DepthComponent24 = 0x81A6,
+    DepthComponent32F = 0x8CAC,
+    Rgba8 = 0x8058,
+    Rgba16F = 0x881A,
+    R16F = 0x822D,
+    Srgb8 = 0x8C41,
+    Srgb8Alpha8 = 0x8C43,
     // GL_ALPHA,
     // GL_ALPHA4,
     // GL_ALPHA8,
@@ -582,9 +1046,6 @@ pub enum TextureInternalFormat {
     // GL_COMPRESSED_INTENSITY,
     // GL_COMPRESSED_RGB,
     // GL_COMPRESSED_RGBA,
-    // GL_DEPTH_COMPONENT,
-    // GL_DEPTH_COMPONENT16,
-    // GL_DEPTH_COMPONENT24,
     // GL_DEPTH_COMPONENT32,
     // GL_LUMINANCE,
     // GL_LUMINANCE4,
@@ -613,7 +1074,6 @@ pub enum TextureInternalFormat {
     // GL_RGBA2,
     // GL_RGBA4,
     // GL_RGB5_A1,
-    // GL_RGBA8,
     // GL_RGB10_A2,
     // GL_RGBA12,
     // GL_RGBA16,
@@ -622,9 +1082,20 @@ pub enum TextureInternalFormat {
     // GL_SLUMINANCE_ALPHA,
     // GL_SLUMINANCE8_ALPHA8,
     // GL_SRGB,
-    // GL_SRGB8,
     // GL_SRGB_ALPHA,
-    // GL_SRGB8_ALPHA8,
+}
+
+impl TextureInternalFormat {
+    /// Returns `true` if the format stores depth values.
+    pub fn is_depth(&self) -> bool {
+        match *self {
+            TextureInternalFormat::DepthComponent
+            | TextureInternalFormat::DepthComponent16
+            | TextureInternalFormat::DepthComponent24
+            | TextureInternalFormat::DepthComponent32F => true,
+            _ => false,
+        }
+    }
 }
 
 #[repr(C)]
@@ -646,15 +1117,23 @@ impl TextureObject {
 pub enum TextureParameterName {
     MagFilter = 0x2800,
     MinFilter = 0x2801,
+
+    // Core in version 4.6, otherwise requires EXT_texture_filter_anisotropic.
+    MaxAnisotropy = 0x84FE,
+
+    // Version 3.0
+    CompareMode = 0x884C,
+    CompareFunc = 0x884D,
+
+    // Version 1.2
+    BaseLevel = 0x813C,
+    MaxLevel = 0x813D,
+
     // GL_DEPTH_STENCIL_TEXTURE_MODE
-    // GL_TEXTURE_BASE_LEVEL,
-    // GL_TEXTURE_COMPARE_FUNC,
-    // GL_TEXTURE_COMPARE_MODE,
     // GL_TEXTURE_LOD_BIAS,
     // GL_TEXTURE_MAG_FILTER,
     // GL_TEXTURE_MIN_LOD,
     // GL_TEXTURE_MAX_LOD,
-    // GL_TEXTURE_MAX_LEVEL,
     // GL_TEXTURE_SWIZZLE_R,
     // GL_TEXTURE_SWIZZLE_G,
     // GL_TEXTURE_SWIZZLE_B,
@@ -673,7 +1152,7 @@ pub enum TextureParameterTarget {
     // GL_TEXTURE_1D_ARRAY,
     // GL_TEXTURE_2D_ARRAY,
     // GL_TEXTURE_RECTANGLE,
-    // GL_TEXTURE_CUBE_MAP,
+    CubeMap = 0x8513,
 }
 
 #[repr(C)]
@@ -686,6 +1165,89 @@ impl UniformLocation {
     }
 }
 
+/// The GLSL type of a uniform variable, see `get_active_uniform`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UniformType {
+    Float = 0x1406,
+    FloatVec2 = 0x8B50,
+    FloatVec3 = 0x8B51,
+    FloatVec4 = 0x8B52,
+    Int = 0x1404,
+    IntVec2 = 0x8B53,
+    IntVec3 = 0x8B54,
+    IntVec4 = 0x8B55,
+    UnsignedInt = 0x1405,
+    UnsignedIntVec2 = 0x8DC6,
+    UnsignedIntVec3 = 0x8DC7,
+    UnsignedIntVec4 = 0x8DC8,
+    Bool = 0x8B56,
+    BoolVec2 = 0x8B57,
+    BoolVec3 = 0x8B58,
+    BoolVec4 = 0x8B59,
+    FloatMat2 = 0x8B5A,
+    FloatMat3 = 0x8B5B,
+    FloatMat4 = 0x8B5C,
+    Sampler1d = 0x8B5D,
+    Sampler2d = 0x8B5E,
+    Sampler3d = 0x8B5F,
+    SamplerCube = 0x8B60,
+    Sampler2dShadow = 0x8B62,
+    Sampler2dArray = 0x8DC1,
+    Sampler2dMultisample = 0x9108,
+}
+
+impl UniformType {
+    /// Converts a raw `GLenum` to a `UniformType`, returning `None` for unsupported types.
+    pub fn from_raw(raw: u32) -> Option<UniformType> {
+        let uniform_type = match raw {
+            0x1406 => UniformType::Float,
+            0x8B50 => UniformType::FloatVec2,
+            0x8B51 => UniformType::FloatVec3,
+            0x8B52 => UniformType::FloatVec4,
+            0x1404 => UniformType::Int,
+            0x8B53 => UniformType::IntVec2,
+            0x8B54 => UniformType::IntVec3,
+            0x8B55 => UniformType::IntVec4,
+            0x1405 => UniformType::UnsignedInt,
+            0x8DC6 => UniformType::UnsignedIntVec2,
+            0x8DC7 => UniformType::UnsignedIntVec3,
+            0x8DC8 => UniformType::UnsignedIntVec4,
+            0x8B56 => UniformType::Bool,
+            0x8B57 => UniformType::BoolVec2,
+            0x8B58 => UniformType::BoolVec3,
+            0x8B59 => UniformType::BoolVec4,
+            0x8B5A => UniformType::FloatMat2,
+            0x8B5B => UniformType::FloatMat3,
+            0x8B5C => UniformType::FloatMat4,
+            0x8B5D => UniformType::Sampler1d,
+            0x8B5E => UniformType::Sampler2d,
+            0x8B5F => UniformType::Sampler3d,
+            0x8B60 => UniformType::SamplerCube,
+            0x8B62 => UniformType::Sampler2dShadow,
+            0x8DC1 => UniformType::Sampler2dArray,
+            0x9108 => UniformType::Sampler2dMultisample,
+            _ => return None,
+        };
+
+        Some(uniform_type)
+    }
+
+    /// Returns `true` if the type is one of the sampler types.
+    pub fn is_sampler(&self) -> bool {
+        match *self {
+            UniformType::Sampler1d
+            | UniformType::Sampler2d
+            | UniformType::Sampler3d
+            | UniformType::SamplerCube
+            | UniformType::Sampler2dShadow
+            | UniformType::Sampler2dArray
+            | UniformType::Sampler2dMultisample => true,
+            _ => false,
+        }
+    }
+}
+
 /// TODO: Use NonZero here so that Option<VertexArrayName>::None can be used instead of 0.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
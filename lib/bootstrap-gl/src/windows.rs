@@ -11,7 +11,50 @@ use self::winapi::*;
 pub type DeviceContext = HDC;
 pub type Context = (HDC, HGLRC);
 
+const WGL_CONTEXT_MAJOR_VERSION_ARB: i32 = 0x2091;
+const WGL_CONTEXT_MINOR_VERSION_ARB: i32 = 0x2092;
+const WGL_CONTEXT_FLAGS_ARB: i32 = 0x2094;
+const WGL_CONTEXT_PROFILE_MASK_ARB: i32 = 0x9126;
+
+const WGL_CONTEXT_DEBUG_BIT_ARB: i32 = 0x0001;
+
+const WGL_CONTEXT_CORE_PROFILE_BIT_ARB: i32 = 0x0001;
+const WGL_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB: i32 = 0x0002;
+
+const WGL_FRAMEBUFFER_SRGB_CAPABLE_ARB: i32 = 0x20A9;
+
 pub unsafe fn create_context(device_context: DeviceContext) -> Option<Context> {
+    create_context_with_config(device_context, &::types::ContextConfig::default())
+}
+
+pub unsafe fn create_context_with_config(
+    device_context: DeviceContext,
+    config: &::types::ContextConfig,
+) -> Option<Context> {
+    create_context_impl(device_context, ptr::null_mut(), config)
+}
+
+/// Creates a new context that shares objects (textures, buffers, programs, etc.) with `share_with`.
+///
+/// The new context is not made current. Only one thread may have a given context current at a
+/// time, so the usual pattern is to create the shared context on the main thread and then send it
+/// to a worker thread to be made current there.
+pub unsafe fn create_shared_context(
+    device_context: DeviceContext,
+    share_with: Context,
+    config: &::types::ContextConfig,
+) -> Option<Context> {
+    let (_, share_render_context) = share_with;
+    create_context_impl(device_context, share_render_context, config)
+}
+
+unsafe fn create_context_impl(
+    device_context: DeviceContext,
+    share_render_context: HGLRC,
+    config: &::types::ContextConfig,
+) -> Option<Context> {
+    let attribs = context_attribs(config);
+
     let tmp_context = opengl32::wglCreateContext(device_context);
     if tmp_context.is_null() {
         return None;
@@ -19,7 +62,7 @@ pub unsafe fn create_context(device_context: DeviceContext) -> Option<Context> {
 
     make_current((device_context, tmp_context));
 
-    let render_context = create_context_attribs(device_context, ptr::null_mut(), ptr::null());
+    let render_context = create_context_attribs(device_context, share_render_context, attribs.as_ptr());
 
     clear_current();
     opengl32::wglDeleteContext(tmp_context);
@@ -42,9 +85,67 @@ pub unsafe fn create_context(device_context: DeviceContext) -> Option<Context> {
     }
 }
 
+/// Builds the null-terminated attribute list passed to `wglCreateContextAttribsARB`.
+fn context_attribs(config: &::types::ContextConfig) -> Vec<i32> {
+    let mut attribs = Vec::new();
+
+    if let Some((major, minor)) = config.version {
+        attribs.push(WGL_CONTEXT_MAJOR_VERSION_ARB);
+        attribs.push(major as i32);
+        attribs.push(WGL_CONTEXT_MINOR_VERSION_ARB);
+        attribs.push(minor as i32);
+
+        // Profiles were introduced in 3.2, earlier versions don't accept the profile mask.
+        if (major, minor) >= (3, 2) {
+            let profile_bit = match config.profile {
+                ::types::ContextProfile::Core => WGL_CONTEXT_CORE_PROFILE_BIT_ARB,
+                ::types::ContextProfile::Compatibility => WGL_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB,
+            };
+            attribs.push(WGL_CONTEXT_PROFILE_MASK_ARB);
+            attribs.push(profile_bit);
+        }
+    } else if config.profile == ::types::ContextProfile::Core {
+        attribs.push(WGL_CONTEXT_PROFILE_MASK_ARB);
+        attribs.push(WGL_CONTEXT_CORE_PROFILE_BIT_ARB);
+    }
+
+    if config.debug {
+        attribs.push(WGL_CONTEXT_FLAGS_ARB);
+        attribs.push(WGL_CONTEXT_DEBUG_BIT_ARB);
+    }
+
+    attribs.push(0);
+    attribs
+}
+
+/// Returns whether the pixel format of `device_context` can convert writes to the default
+/// framebuffer from linear to sRGB.
+///
+/// The pixel format is chosen when the window is created and can't be changed afterwards. Must be
+/// called with a context current so that `wglGetPixelFormatAttribivARB` can be loaded. Returns
+/// `false` if the pixel format can't be queried.
+pub unsafe fn is_framebuffer_srgb_capable(device_context: DeviceContext) -> bool {
+    let pixel_format = gdi32::GetPixelFormat(device_context);
+    if pixel_format == 0 || get_pixel_format_attribiv::load().is_none() {
+        return false;
+    }
+
+    let attrib = WGL_FRAMEBUFFER_SRGB_CAPABLE_ARB;
+    let mut value = 0;
+    let result = get_pixel_format_attribiv(device_context, pixel_format, 0, 1, &attrib, &mut value);
+    result == TRUE && value != 0
+}
+
+/// Deletes `context`.
+///
+/// The context is only made non-current if it's current on the calling thread, so destroying a
+/// context that was never made current (e.g. an unused shared context) leaves the thread's
+/// current context alone.
 pub unsafe fn destroy_context(context: Context) {
     let (_, render_context) = context;
-    clear_current();
+    if opengl32::wglGetCurrentContext() == render_context {
+        clear_current();
+    }
 
     let result = opengl32::wglDeleteContext(render_context);
 
@@ -138,6 +239,16 @@ gl_proc!(wglCreateContextAttribsARB:
         attrib_list: *const i32
     ) -> ::platform::winapi::HGLRC);
 
+gl_proc!(wglGetPixelFormatAttribivARB:
+    fn get_pixel_format_attribiv(
+        hdc: ::platform::winapi::HDC,
+        pixel_format: i32,
+        layer_plane: i32,
+        attribute_count: u32,
+        attributes: *const i32,
+        values: *mut i32
+    ) -> ::platform::winapi::BOOL);
+
 gl_proc!(wglGetSwapIntervalEXT:
     fn get_swap_interval() -> i32);
 
@@ -0,0 +1,119 @@
+//! Checks that clearing a framebuffer resets its depth buffer on every frame, even when the last
+//! draw of the previous frame disabled depth writes.
+//!
+//! Each frame clears an offscreen framebuffer and draws a far quad, which should always pass the
+//! depth test against the cleared depth buffer. The frame then draws a near quad, followed by a
+//! draw with depth writes disabled, the way a skybox or transparent pass would.
+
+extern crate bootstrap_rs as bootstrap;
+extern crate gl_util as gl;
+
+use bootstrap::window::*;
+use gl::*;
+use gl::context::Context;
+use gl::framebuffer::*;
+use gl::renderbuffer::{Renderbuffer, TextureInternalFormat};
+use gl::shader::*;
+use gl::texture::Texture2d;
+
+static VERT_SOURCE: &'static str = r#"
+#version 330 core
+
+uniform float depth;
+
+layout(location = 0) in vec4 position;
+
+void main() {
+    gl_Position = vec4(position.xy, depth, 1.0);
+}
+"#;
+
+static FRAG_SOURCE: &'static str = r#"
+#version 330 core
+
+uniform vec4 surface_color;
+
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = surface_color;
+}
+"#;
+
+static QUAD: [f32; 24] = [
+    -1.0, -1.0, 0.0, 1.0,
+     1.0, -1.0, 0.0, 1.0,
+     1.0,  1.0, 0.0, 1.0,
+    -1.0, -1.0, 0.0, 1.0,
+     1.0,  1.0, 0.0, 1.0,
+    -1.0,  1.0, 0.0, 1.0,
+];
+
+const SIZE: usize = 16;
+
+fn main() {
+    let window = Window::new("gl-util - clear depth example").unwrap();
+    let context = Context::from_window(&window).unwrap();
+
+    let vert_shader = Shader::new(&context, VERT_SOURCE, ShaderType::Vertex).unwrap();
+    let frag_shader = Shader::new(&context, FRAG_SOURCE, ShaderType::Fragment).unwrap();
+    let program = Program::new(&context, &[vert_shader, frag_shader]).unwrap();
+
+    let mut vertex_array = VertexArray::new(&context, &QUAD);
+    vertex_array.set_attrib(
+        AttributeLocation::from_index(0),
+        AttribLayout { elements: 4, offset: 0, stride: 0 },
+    );
+
+    let color = Texture2d::allocate(&context, TextureInternalFormat::Rgba8, SIZE, SIZE).unwrap();
+    let depth = Renderbuffer::new(&context, TextureInternalFormat::DepthComponent24, SIZE, SIZE)
+        .unwrap();
+    let mut framebuffer = Framebuffer::new(&context).unwrap();
+    framebuffer.attach_texture(FramebufferAttachment::Color0, &color);
+    framebuffer.attach_renderbuffer(FramebufferAttachment::Depth, &depth);
+    assert!(framebuffer.is_complete(), "Framebuffer is incomplete: {:?}", framebuffer.status());
+
+    let frame_colors = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]];
+    for (frame, &frame_color) in frame_colors.iter().enumerate() {
+        context.clear_framebuffer(&framebuffer);
+
+        // The far quad must pass the depth test against the freshly cleared depth buffer.
+        DrawBuilder::new(&context, &vertex_array, DrawMode::Triangles)
+            .framebuffer(&framebuffer)
+            .program(&program)
+            .depth_test(Comparison::Less)
+            .uniform("depth", 0.5)
+            .uniform("surface_color", frame_color)
+            .draw();
+
+        let pixels = read_pixels(&context, Some(&framebuffer), Rect::new(0, 0, 1, 1));
+        let expected = [
+            (frame_color[0] * 255.0) as u8,
+            (frame_color[1] * 255.0) as u8,
+            (frame_color[2] * 255.0) as u8,
+            (frame_color[3] * 255.0) as u8,
+        ];
+        assert_eq!(&expected[..], &pixels[..], "Depth buffer wasn't cleared on frame {}", frame);
+
+        // Write depth in front of the far quad, then leave depth writes disabled.
+        DrawBuilder::new(&context, &vertex_array, DrawMode::Triangles)
+            .framebuffer(&framebuffer)
+            .program(&program)
+            .depth_test(Comparison::Less)
+            .uniform("depth", -0.5)
+            .uniform("surface_color", [0.0, 0.0, 1.0, 1.0])
+            .draw();
+        DrawBuilder::new(&context, &vertex_array, DrawMode::Triangles)
+            .framebuffer(&framebuffer)
+            .program(&program)
+            .depth_test(Comparison::Always)
+            .depth_write(false)
+            .uniform("depth", 0.0)
+            .uniform("surface_color", [0.0, 0.0, 1.0, 1.0])
+            .draw();
+
+        context.swap_buffers();
+    }
+
+    println!("Depth buffer was cleared on every frame");
+}
@@ -0,0 +1,106 @@
+//! Checks that a vertex array keeps drawing the right data after streaming buffers are disabled.
+//!
+//! The first frame streams a quad into the vertex array's streaming buffers and then disables
+//! streaming, which has to carry the streamed vertices over to the regular vertex buffer. The
+//! second frame streams the quad again, this time through the fallback path that reallocates the
+//! vertex and index buffers.
+
+extern crate bootstrap_rs as bootstrap;
+extern crate gl_util as gl;
+
+use bootstrap::window::*;
+use gl::*;
+use gl::context::Context;
+use gl::framebuffer::*;
+use gl::renderbuffer::TextureInternalFormat;
+use gl::shader::*;
+use gl::texture::Texture2d;
+
+static VERT_SOURCE: &'static str = r#"
+#version 330 core
+
+layout(location = 0) in vec4 position;
+
+void main() {
+    gl_Position = position;
+}
+"#;
+
+static FRAG_SOURCE: &'static str = r#"
+#version 330 core
+
+uniform vec4 surface_color;
+
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = surface_color;
+}
+"#;
+
+static QUAD_VERTICES: [f32; 16] = [
+    -1.0, -1.0, 0.0, 1.0,
+     1.0, -1.0, 0.0, 1.0,
+     1.0,  1.0, 0.0, 1.0,
+    -1.0,  1.0, 0.0, 1.0,
+];
+
+static QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+const SIZE: usize = 16;
+
+fn main() {
+    let window = Window::new("gl-util - stream fallback example").unwrap();
+    let context = Context::from_window(&window).unwrap();
+
+    let vert_shader = Shader::new(&context, VERT_SOURCE, ShaderType::Vertex).unwrap();
+    let frag_shader = Shader::new(&context, FRAG_SOURCE, ShaderType::Fragment).unwrap();
+    let program = Program::new(&context, &[vert_shader, frag_shader]).unwrap();
+
+    let color = Texture2d::allocate(&context, TextureInternalFormat::Rgba8, SIZE, SIZE).unwrap();
+    let mut framebuffer = Framebuffer::new(&context).unwrap();
+    framebuffer.attach_texture(FramebufferAttachment::Color0, &color);
+    assert!(framebuffer.is_complete(), "Framebuffer is incomplete: {:?}", framebuffer.status());
+
+    let position = AttributeLocation::from_index(0);
+    let layout = AttribLayout { elements: 4, offset: 0, stride: 0 };
+
+    let mut vertex_array = VertexArray::with_index_buffer(&context, &[], &[]);
+    vertex_array.stream_raw_data(&QUAD_VERTICES);
+    vertex_array.set_attrib(position, layout);
+
+    // Switch to the fallback after the vertices were streamed, the way a failure to create the
+    // index stream would. The attributes have to be declared again afterwards.
+    vertex_array.disable_streaming();
+    vertex_array.set_attrib(position, layout);
+    vertex_array.stream_indices(&QUAD_INDICES);
+
+    let frame_colors = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]];
+    for (frame, &frame_color) in frame_colors.iter().enumerate() {
+        if frame > 0 {
+            vertex_array.stream_raw_data(&QUAD_VERTICES);
+            vertex_array.set_attrib(position, layout);
+            vertex_array.stream_indices(&QUAD_INDICES);
+        }
+
+        context.clear_framebuffer(&framebuffer);
+        DrawBuilder::new(&context, &vertex_array, DrawMode::Triangles)
+            .framebuffer(&framebuffer)
+            .program(&program)
+            .uniform("surface_color", frame_color)
+            .draw();
+
+        let pixels = read_pixels(&context, Some(&framebuffer), Rect::new(0, 0, 1, 1));
+        let expected = [
+            (frame_color[0] * 255.0) as u8,
+            (frame_color[1] * 255.0) as u8,
+            (frame_color[2] * 255.0) as u8,
+            (frame_color[3] * 255.0) as u8,
+        ];
+        assert_eq!(&expected[..], &pixels[..], "Streamed quad wasn't drawn on frame {}", frame);
+
+        context.swap_buffers();
+    }
+
+    println!("Streamed data was drawn with streaming disabled");
+}
@@ -0,0 +1,314 @@
+use context::{Context, ContextInner};
+use gl;
+use gl::*;
+use std::{mem, ptr, slice};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+pub use gl::BufferTarget;
+
+/// The number of regions a `StreamingBuffer` is split into.
+///
+/// With three regions the CPU can write one region while the GPU is still reading from the other
+/// two, which is enough to avoid stalling when the buffer is updated once per frame.
+pub const STREAMING_REGIONS: usize = 3;
+
+/// How long to block on a single call to `client_wait_sync()`, in nanoseconds.
+const WAIT_TIMEOUT_NS: u64 = 1_000_000;
+
+/// A persistently mapped buffer used for uploading new data to the GPU every frame.
+///
+/// `StreamingBuffer` allocates an immutable data store large enough to hold
+/// `STREAMING_REGIONS` copies of the data and keeps it mapped for its entire lifetime. Each frame
+/// the next region is handed out for writing, and a fence is placed in the command stream after
+/// the draws that read from that region. Before a region is handed out again its fence is waited
+/// on, so the CPU never writes to memory that the GPU is still reading from.
+///
+/// The intended usage is:
+///
+/// 1. Call `next_region()` and write the frame's data into the returned slice.
+/// 2. Issue draw calls that read from the buffer starting at `region_offset()`.
+/// 3. Call `fence_region()` to mark the region as in use by the GPU.
+#[derive(Debug)]
+pub struct StreamingBuffer<T: Copy> {
+    buffer_name: BufferName,
+    target: BufferTarget,
+
+    /// Pointer to the start of the mapped data store.
+    mapped: *mut T,
+
+    /// The number of elements in each region.
+    region_len: usize,
+
+    /// The region that was most recently handed out by `next_region()`.
+    current_region: usize,
+
+    /// The fence guarding each region, if the GPU may still be reading from it.
+    fences: [SyncObject; STREAMING_REGIONS],
+
+    context: Rc<RefCell<ContextInner>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Copy> StreamingBuffer<T> {
+    /// Creates a new streaming buffer where each region holds `region_len` elements.
+    ///
+    /// Requires GL 4.4 or the `ARB_buffer_storage` extension.
+    ///
+    /// # Panics
+    ///
+    /// - If `region_len` is 0.
+    pub fn new(
+        context: &Context,
+        target: BufferTarget,
+        region_len: usize,
+    ) -> Result<StreamingBuffer<T>, Error> {
+        StreamingBuffer::from_inner(context.inner(), target, region_len)
+    }
+
+    /// Creates a new streaming buffer for the context behind `context`, see `new()`.
+    pub(crate) fn from_inner(
+        context: Rc<RefCell<ContextInner>>,
+        target: BufferTarget,
+        region_len: usize,
+    ) -> Result<StreamingBuffer<T>, Error> {
+        assert!(region_len > 0, "Cannot create a streaming buffer with empty regions");
+
+        let (buffer_name, mapped) = {
+            let mut inner = context.borrow_mut();
+            let _guard = ::context::ContextGuard::new(inner.raw());
+
+            // `buffer_storage_raw()` panics if the proc can't be loaded, which is the case on
+            // drivers older than GL 4.4 without `ARB_buffer_storage`.
+            let supported = unsafe {
+                gl::buffer_storage_raw::load().is_some() && gl::map_buffer_range::load().is_some()
+            };
+            if !supported {
+                return Err(Error::FailedToMapBuffer);
+            }
+
+            let buffer_name = gl::gen_buffer().ok_or(Error::FailedToGenerateBuffer)?;
+            let size = (region_len * STREAMING_REGIONS * mem::size_of::<T>()) as isize;
+
+            inner.bind_buffer(target, buffer_name);
+            let mapped = unsafe {
+                gl::buffer_storage_raw(
+                    target,
+                    size,
+                    ptr::null(),
+                    BufferStorageFlags::MAP_WRITE
+                        | BufferStorageFlags::MAP_PERSISTENT
+                        | BufferStorageFlags::MAP_COHERENT,
+                );
+
+                gl::map_buffer_range(
+                    target,
+                    0,
+                    size,
+                    MapAccessFlags::MAP_WRITE
+                        | MapAccessFlags::MAP_PERSISTENT
+                        | MapAccessFlags::MAP_COHERENT,
+                )
+            };
+            inner.bind_buffer(target, BufferName::null());
+
+            if mapped.is_null() {
+                unsafe { gl::delete_buffers(1, &buffer_name); }
+                return Err(Error::FailedToMapBuffer);
+            }
+
+            (buffer_name, mapped)
+        };
+
+        Ok(StreamingBuffer {
+            buffer_name: buffer_name,
+            target: target,
+
+            mapped: mapped as *mut T,
+
+            region_len: region_len,
+            current_region: STREAMING_REGIONS - 1,
+            fences: [SyncObject::null(); STREAMING_REGIONS],
+
+            context: context,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Advances to the next region and returns it for writing.
+    ///
+    /// If the GPU is still reading from the region this will block until it is finished.
+    pub fn next_region(&mut self) -> &mut [T] {
+        self.current_region = (self.current_region + 1) % STREAMING_REGIONS;
+
+        let fence = self.fences[self.current_region];
+        if !fence.is_null() {
+            let _guard = ::context::ContextGuard::new(self.context.borrow().raw());
+            wait_for_fence(fence);
+            unsafe { gl::delete_sync(fence); }
+            self.fences[self.current_region] = SyncObject::null();
+        }
+
+        unsafe {
+            slice::from_raw_parts_mut(
+                self.mapped.offset((self.current_region * self.region_len) as isize),
+                self.region_len,
+            )
+        }
+    }
+
+    /// Places a fence in the command stream guarding the current region.
+    ///
+    /// This must be called after all draw calls that read from the current region have been
+    /// issued, otherwise the region may be overwritten while the GPU is still reading from it.
+    pub fn fence_region(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context.borrow().raw());
+
+        let old_fence = self.fences[self.current_region];
+        if !old_fence.is_null() {
+            unsafe { gl::delete_sync(old_fence); }
+        }
+
+        self.fences[self.current_region] = unsafe {
+            gl::fence_sync(SyncCondition::GpuCommandsComplete, 0)
+        };
+    }
+
+    /// Returns the offset, in bytes, of the current region from the start of the buffer.
+    pub fn region_offset(&self) -> usize {
+        self.current_region * self.region_len * mem::size_of::<T>()
+    }
+
+    /// Returns the offset, in elements, of the current region from the start of the buffer.
+    ///
+    /// This is useful as the first vertex or index when drawing from the current region.
+    pub fn region_start(&self) -> usize {
+        self.current_region * self.region_len
+    }
+
+    /// Returns the number of elements in each region.
+    pub fn region_len(&self) -> usize {
+        self.region_len
+    }
+
+    /// Binds the buffer to the target it was created for.
+    ///
+    /// Binding to `BufferTarget::ElementArray` unbinds the current vertex array, since the binding
+    /// would otherwise replace that vertex array's indices.
+    pub fn bind(&self) {
+        let mut context = self.context.borrow_mut();
+        let _guard = ::context::ContextGuard::new(context.raw());
+        context.bind_buffer(self.target, self.buffer_name);
+    }
+
+    /// Returns the target the buffer was created for.
+    pub fn target(&self) -> BufferTarget {
+        self.target
+    }
+
+    pub(crate) fn buffer_name(&self) -> BufferName {
+        self.buffer_name
+    }
+}
+
+impl<T: Copy> Drop for StreamingBuffer<T> {
+    fn drop(&mut self) {
+        let mut context = self.context.borrow_mut();
+        let _guard = ::context::ContextGuard::new(context.raw());
+
+        unsafe {
+            for fence in &self.fences {
+                if !fence.is_null() {
+                    gl::delete_sync(*fence);
+                }
+            }
+        }
+
+        context.bind_buffer(self.target, self.buffer_name);
+        unsafe { gl::unmap_buffer(self.target); }
+        context.bind_buffer(self.target, BufferName::null());
+        unsafe { gl::delete_buffers(1, &self.buffer_name); }
+    }
+}
+
+/// A buffer holding the values of a uniform block.
+///
+/// The buffer is attached to an indexed uniform buffer binding point with `bind()`, and every
+/// program whose block was assigned that binding point (see `Program::bind_uniform_block()`)
+/// reads its values from the buffer until another buffer is bound there. The data must follow
+/// the block's memory layout, usually `std140`.
+#[derive(Debug)]
+pub struct UniformBuffer {
+    buffer_name: BufferName,
+    context: ::gl::Context,
+}
+
+impl UniformBuffer {
+    /// Creates a new, empty uniform buffer.
+    pub fn new(context: &Context) -> Result<UniformBuffer, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let buffer_name = gl::gen_buffer().ok_or(Error::FailedToGenerateBuffer)?;
+
+        Ok(UniformBuffer {
+            buffer_name: buffer_name,
+            context: context,
+        })
+    }
+
+    /// Replaces the contents of the buffer.
+    ///
+    /// A new data store is allocated each time, so updating the buffer never waits for draws
+    /// that are still reading the previous contents.
+    pub fn set_data<T: Copy>(&self, data: &[T]) {
+        let _guard = ::context::ContextGuard::new(self.context);
+
+        unsafe { gl::bind_buffer(BufferTarget::Uniform, self.buffer_name); }
+        gl::buffer_data(BufferTarget::Uniform, data, BufferUsage::StreamDraw);
+        unsafe { gl::bind_buffer(BufferTarget::Uniform, BufferName::null()); }
+    }
+
+    /// Attaches the buffer to the uniform buffer binding point `binding`.
+    ///
+    /// The buffer must have been given data with `set_data()` first.
+    pub fn bind(&self, binding: u32) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::bind_buffer_base(BufferTarget::Uniform, binding, self.buffer_name); }
+    }
+}
+
+impl Drop for UniformBuffer {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::delete_buffers(1, &self.buffer_name); }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Indicates that `gl::gen_buffer()` failed to create a buffer object.
+    FailedToGenerateBuffer,
+
+    /// Indicates that the buffer's data store could not be mapped.
+    ///
+    /// This will happen if the GL implementation doesn't support GL 4.4 or
+    /// `ARB_buffer_storage`, in which case no buffer is created.
+    FailedToMapBuffer,
+}
+
+/// Blocks until `fence` has been signaled.
+fn wait_for_fence(fence: SyncObject) {
+    loop {
+        let status = unsafe {
+            gl::client_wait_sync(fence, SyncWaitFlags::FlushCommands, WAIT_TIMEOUT_NS)
+        };
+
+        match status {
+            SyncStatus::AlreadySignaled | SyncStatus::ConditionSatisfied => return,
+            SyncStatus::TimeoutExpired => {},
+            SyncStatus::WaitFailed => panic!("Failed to wait on fence {:?}", fence),
+        }
+    }
+}
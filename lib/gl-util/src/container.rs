@@ -0,0 +1,681 @@
+//! Loading of GPU-ready textures from KTX and DDS containers.
+//!
+//! Both containers store images in the layout they're uploaded in, so a `TextureContainer` holds
+//! the raw data of every mip level and cube map face without decoding it. Compressed images are
+//! uploaded as is with `Texture2d::from_container()` or `TextureCube::from_container()`, which
+//! lets art pipelines compress and build mip chains offline.
+//!
+//! Supported are 2D textures and cube maps that are either compressed in one of the
+//! `CompressedTextureFormat`s, or uncompressed with 8 bits per channel in RGBA or BGRA order.
+//! Array textures, volume textures, and other pixel formats are rejected with `Error::Unsupported`.
+
+use gl::{CompressedTextureFormat, TextureFormat, TextureInternalFormat};
+use std::cmp;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read};
+use std::mem;
+use std::path::Path;
+
+/// The identifier at the start of every KTX file.
+const KTX_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// The magic number at the start of every DDS file, "DDS " in ASCII.
+const DDS_MAGIC: [u8; 4] = [0x44, 0x44, 0x53, 0x20];
+
+/// A texture loaded from a container file, ready to be uploaded to the GPU.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureContainer {
+    format: ContainerFormat,
+    is_cube_map: bool,
+    levels: Vec<MipLevel>,
+}
+
+impl TextureContainer {
+    /// Loads a KTX or DDS file from disk.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<TextureContainer, Error> {
+        let mut file = File::open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        TextureContainer::from_bytes(&bytes)
+    }
+
+    /// Parses a KTX or DDS file, detecting the container from its header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TextureContainer, Error> {
+        if bytes.starts_with(&KTX_IDENTIFIER) {
+            TextureContainer::from_ktx(bytes)
+        } else if bytes.starts_with(&DDS_MAGIC) {
+            TextureContainer::from_dds(bytes)
+        } else {
+            Err(Error::UnrecognizedContainer)
+        }
+    }
+
+    /// Parses a KTX (version 1) file.
+    pub fn from_ktx(bytes: &[u8]) -> Result<TextureContainer, Error> {
+        if !bytes.starts_with(&KTX_IDENTIFIER) {
+            return Err(Error::UnrecognizedContainer);
+        }
+
+        let mut reader = Reader::new(&bytes[KTX_IDENTIFIER.len()..]);
+
+        // Files written on big-endian machines have every header field byte-swapped. The data of
+        // the supported formats is made up of single bytes, so it doesn't need to be swapped.
+        match reader.u32()? {
+            0x04030201 => {},
+            0x01020304 => reader.swap_bytes = true,
+            _ => return Err(Error::Malformed("Invalid endianness")),
+        }
+
+        let gl_type = reader.u32()?;
+        let _gl_type_size = reader.u32()?;
+        let gl_format = reader.u32()?;
+        let gl_internal_format = reader.u32()?;
+        let _gl_base_internal_format = reader.u32()?;
+        let width = reader.u32()? as usize;
+        let height = reader.u32()? as usize;
+        let depth = reader.u32()?;
+        let array_elements = reader.u32()?;
+        let faces = reader.u32()?;
+        let level_count = reader.u32()?;
+        let key_value_bytes = reader.u32()? as usize;
+
+        if depth > 0 {
+            return Err(Error::Unsupported("Volume textures"));
+        }
+        if array_elements > 0 {
+            return Err(Error::Unsupported("Array textures"));
+        }
+        if width == 0 || height == 0 {
+            return Err(Error::Malformed("Texture has no size"));
+        }
+
+        let format = if gl_type == 0 && gl_format == 0 {
+            ContainerFormat::Compressed(compressed_format(gl_internal_format)?)
+        } else if gl_type == GL_UNSIGNED_BYTE {
+            let format = match gl_format {
+                GL_RGBA => TextureFormat::Rgba,
+                GL_BGRA => TextureFormat::Bgra,
+                _ => return Err(Error::Unsupported("Pixel format")),
+            };
+            let internal_format = match gl_internal_format {
+                GL_RGBA8 => TextureInternalFormat::Rgba8,
+                GL_SRGB8_ALPHA8 => TextureInternalFormat::Srgb8Alpha8,
+                _ => return Err(Error::Unsupported("Pixel format")),
+            };
+            ContainerFormat::Uncompressed { format: format, internal_format: internal_format }
+        } else {
+            return Err(Error::Unsupported("Pixel format"));
+        };
+
+        let is_cube_map = match faces {
+            1 => false,
+            6 => true,
+            _ => return Err(Error::Malformed("Invalid number of faces")),
+        };
+        let face_count = faces as usize;
+
+        // A level count of 0 asks for the mip chain to be generated after loading, so only the
+        // base level is stored.
+        let level_count = cmp::max(level_count as usize, 1);
+        if level_count > max_level_count(width, height) {
+            return Err(Error::Malformed("More mip levels than the texture's size allows"));
+        }
+
+        reader.skip(key_value_bytes)?;
+
+        // Each level starts with its size, which is the size of a single face for cube maps.
+        // Faces and levels are padded to 4 bytes, which never happens for the supported formats.
+        let mut levels = Vec::with_capacity(level_count);
+        for level in 0..level_count {
+            let (level_width, level_height) = level_size(width, height, level);
+            let face_size = format.image_size(level_width, level_height);
+
+            let image_size = reader.u32()? as usize;
+            if image_size != face_size {
+                return Err(Error::Malformed("Image size doesn't match the texture's format"));
+            }
+
+            let mut level_faces = Vec::with_capacity(face_count);
+            for _ in 0..face_count {
+                level_faces.push(reader.bytes(face_size)?.to_vec());
+                reader.align(4);
+            }
+            reader.align(4);
+
+            levels.push(MipLevel {
+                width: level_width,
+                height: level_height,
+                faces: level_faces,
+            });
+        }
+
+        Ok(TextureContainer {
+            format: format,
+            is_cube_map: is_cube_map,
+            levels: levels,
+        })
+    }
+
+    /// Parses a DDS file, including files with the DX10 header extension.
+    ///
+    /// DXT1, DXT3, and DXT5 images without the DX10 extension are assumed to be linear, since the
+    /// legacy header can't describe sRGB data.
+    pub fn from_dds(bytes: &[u8]) -> Result<TextureContainer, Error> {
+        if !bytes.starts_with(&DDS_MAGIC) {
+            return Err(Error::UnrecognizedContainer);
+        }
+
+        let mut reader = Reader::new(&bytes[DDS_MAGIC.len()..]);
+
+        if reader.u32()? != 124 {
+            return Err(Error::Malformed("Invalid header size"));
+        }
+        let flags = reader.u32()?;
+        let height = reader.u32()? as usize;
+        let width = reader.u32()? as usize;
+        let _pitch_or_linear_size = reader.u32()?;
+        let _depth = reader.u32()?;
+        let mip_map_count = reader.u32()?;
+        reader.skip(11 * 4)?;
+
+        // The pixel format.
+        let _pixel_format_size = reader.u32()?;
+        let pixel_format_flags = reader.u32()?;
+        let four_cc = reader.u32()?;
+        let rgb_bit_count = reader.u32()?;
+        let masks = [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?];
+
+        let _caps = reader.u32()?;
+        let caps_2 = reader.u32()?;
+        reader.skip(3 * 4)?;
+
+        if caps_2 & DDSCAPS2_VOLUME != 0 {
+            return Err(Error::Unsupported("Volume textures"));
+        }
+        if width == 0 || height == 0 {
+            return Err(Error::Malformed("Texture has no size"));
+        }
+
+        let mut is_cube_map = caps_2 & DDSCAPS2_CUBEMAP != 0;
+        let format = if pixel_format_flags & DDPF_FOURCC != 0 {
+            match four_cc {
+                FOURCC_DXT1 => ContainerFormat::Compressed(CompressedTextureFormat::RgbaS3tcDxt1),
+                FOURCC_DXT3 => ContainerFormat::Compressed(CompressedTextureFormat::RgbaS3tcDxt3),
+                FOURCC_DXT5 => ContainerFormat::Compressed(CompressedTextureFormat::RgbaS3tcDxt5),
+                FOURCC_DX10 => {
+                    let dxgi_format = reader.u32()?;
+                    let _resource_dimension = reader.u32()?;
+                    let misc_flag = reader.u32()?;
+                    let array_size = reader.u32()?;
+                    let _misc_flags_2 = reader.u32()?;
+
+                    if array_size > 1 {
+                        return Err(Error::Unsupported("Array textures"));
+                    }
+                    if misc_flag & DDS_RESOURCE_MISC_TEXTURECUBE != 0 {
+                        is_cube_map = true;
+                    }
+
+                    dxgi_format_to_container(dxgi_format)?
+                },
+                _ => return Err(Error::Unsupported("Pixel format")),
+            }
+        } else if pixel_format_flags & DDPF_RGB != 0 && rgb_bit_count == 32 {
+            match masks {
+                [0x000000FF, 0x0000FF00, 0x00FF0000, 0xFF000000] => ContainerFormat::Uncompressed {
+                    format: TextureFormat::Rgba,
+                    internal_format: TextureInternalFormat::Rgba8,
+                },
+                [0x00FF0000, 0x0000FF00, 0x000000FF, 0xFF000000] => ContainerFormat::Uncompressed {
+                    format: TextureFormat::Bgra,
+                    internal_format: TextureInternalFormat::Rgba8,
+                },
+                _ => return Err(Error::Unsupported("Pixel format")),
+            }
+        } else {
+            return Err(Error::Unsupported("Pixel format"));
+        };
+
+        if is_cube_map && caps_2 & DDSCAPS2_CUBEMAP_ALLFACES != DDSCAPS2_CUBEMAP_ALLFACES {
+            return Err(Error::Unsupported("Cube maps without all six faces"));
+        }
+        let face_count = if is_cube_map { 6 } else { 1 };
+
+        let level_count = if flags & DDSD_MIPMAPCOUNT != 0 { cmp::max(mip_map_count as usize, 1) } else { 1 };
+        if level_count > max_level_count(width, height) {
+            return Err(Error::Malformed("More mip levels than the texture's size allows"));
+        }
+
+        let mut levels: Vec<MipLevel> = (0..level_count)
+            .map(|level| {
+                let (level_width, level_height) = level_size(width, height, level);
+                MipLevel {
+                    width: level_width,
+                    height: level_height,
+                    faces: Vec::with_capacity(face_count),
+                }
+            })
+            .collect();
+
+        // Unlike KTX, DDS stores the whole mip chain of each face before the next face.
+        for _ in 0..face_count {
+            for level in &mut levels {
+                let face_size = format.image_size(level.width, level.height);
+                level.faces.push(reader.bytes(face_size)?.to_vec());
+            }
+        }
+
+        Ok(TextureContainer {
+            format: format,
+            is_cube_map: is_cube_map,
+            levels: levels,
+        })
+    }
+
+    /// Returns the width of the base level.
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    /// Returns the height of the base level.
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    /// Returns the format the images are stored in.
+    pub fn format(&self) -> ContainerFormat {
+        self.format
+    }
+
+    /// Returns `true` if the container holds the six faces of a cube map.
+    pub fn is_cube_map(&self) -> bool {
+        self.is_cube_map
+    }
+
+    /// Returns the mip levels stored in the container, starting with the base level.
+    ///
+    /// The chain may stop before reaching a 1x1 level.
+    pub fn levels(&self) -> &[MipLevel] {
+        &self.levels
+    }
+
+    /// Consumes the container, returning its mip levels starting with the base level.
+    pub fn into_levels(self) -> Vec<MipLevel> {
+        self.levels
+    }
+}
+
+/// A single level of a texture's mip chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MipLevel {
+    pub width: usize,
+    pub height: usize,
+
+    /// The image data of each face, in the order of `CubeFace::ALL` for cube maps.
+    pub faces: Vec<Vec<u8>>,
+}
+
+/// The format of the images in a `TextureContainer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContainerFormat {
+    /// Images compressed in a GPU block compression format.
+    Compressed(CompressedTextureFormat),
+
+    /// Images with 8 bits per channel and 4 channels in the order given by `format`.
+    Uncompressed {
+        format: TextureFormat,
+        internal_format: TextureInternalFormat,
+    },
+}
+
+impl ContainerFormat {
+    /// Returns the size in bytes of a single image with the specified dimensions.
+    pub fn image_size(&self, width: usize, height: usize) -> usize {
+        match *self {
+            ContainerFormat::Compressed(format) => format.image_size(width, height),
+            ContainerFormat::Uncompressed { .. } => width * height * 4,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    IoError(io::Error),
+
+    /// The data doesn't start with the header of a KTX or DDS file.
+    UnrecognizedContainer,
+
+    /// The data ended before all of the images declared by the header.
+    UnexpectedEnd,
+
+    /// The header is invalid.
+    Malformed(&'static str),
+
+    /// The container holds a kind of texture that can't be loaded.
+    Unsupported(&'static str),
+}
+
+impl From<io::Error> for Error {
+    fn from(from: io::Error) -> Error {
+        Error::IoError(from)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::IoError(ref error) => write!(formatter, "Failed to read texture container: {}", error),
+            Error::UnrecognizedContainer => write!(formatter, "Data is not a KTX or DDS file"),
+            Error::UnexpectedEnd => write!(formatter, "Texture container ended unexpectedly"),
+            Error::Malformed(reason) => write!(formatter, "Malformed texture container: {}", reason),
+            Error::Unsupported(feature) => write!(formatter, "Unsupported texture container feature: {}", feature),
+        }
+    }
+}
+
+// GL enums used by KTX headers.
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_RGBA: u32 = 0x1908;
+const GL_BGRA: u32 = 0x80E1;
+const GL_RGBA8: u32 = 0x8058;
+const GL_SRGB8_ALPHA8: u32 = 0x8C43;
+
+// DDS header flags.
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+// DDS four-character codes, read as little-endian integers.
+const FOURCC_DXT1: u32 = 0x31545844;
+const FOURCC_DXT3: u32 = 0x33545844;
+const FOURCC_DXT5: u32 = 0x35545844;
+const FOURCC_DX10: u32 = 0x30315844;
+
+/// Returns the compressed format with the GL enum `value`.
+fn compressed_format(value: u32) -> Result<CompressedTextureFormat, Error> {
+    let format = match value {
+        0x83F0 => CompressedTextureFormat::RgbS3tcDxt1,
+        0x83F1 => CompressedTextureFormat::RgbaS3tcDxt1,
+        0x83F2 => CompressedTextureFormat::RgbaS3tcDxt3,
+        0x83F3 => CompressedTextureFormat::RgbaS3tcDxt5,
+        0x8C4C => CompressedTextureFormat::SrgbS3tcDxt1,
+        0x8C4D => CompressedTextureFormat::SrgbAlphaS3tcDxt1,
+        0x8C4E => CompressedTextureFormat::SrgbAlphaS3tcDxt3,
+        0x8C4F => CompressedTextureFormat::SrgbAlphaS3tcDxt5,
+        0x9270 => CompressedTextureFormat::R11Eac,
+        0x9271 => CompressedTextureFormat::SignedR11Eac,
+        0x9272 => CompressedTextureFormat::Rg11Eac,
+        0x9273 => CompressedTextureFormat::SignedRg11Eac,
+        0x9274 => CompressedTextureFormat::Rgb8Etc2,
+        0x9275 => CompressedTextureFormat::Srgb8Etc2,
+        0x9276 => CompressedTextureFormat::Rgb8PunchthroughAlpha1Etc2,
+        0x9277 => CompressedTextureFormat::Srgb8PunchthroughAlpha1Etc2,
+        0x9278 => CompressedTextureFormat::Rgba8Etc2Eac,
+        0x9279 => CompressedTextureFormat::Srgb8Alpha8Etc2Eac,
+        _ => return Err(Error::Unsupported("Compressed format")),
+    };
+
+    Ok(format)
+}
+
+/// Returns the format of the `DXGI_FORMAT` value `value`.
+fn dxgi_format_to_container(value: u32) -> Result<ContainerFormat, Error> {
+    let format = match value {
+        28 => ContainerFormat::Uncompressed {
+            format: TextureFormat::Rgba,
+            internal_format: TextureInternalFormat::Rgba8,
+        },
+        29 => ContainerFormat::Uncompressed {
+            format: TextureFormat::Rgba,
+            internal_format: TextureInternalFormat::Srgb8Alpha8,
+        },
+        71 => ContainerFormat::Compressed(CompressedTextureFormat::RgbaS3tcDxt1),
+        72 => ContainerFormat::Compressed(CompressedTextureFormat::SrgbAlphaS3tcDxt1),
+        74 => ContainerFormat::Compressed(CompressedTextureFormat::RgbaS3tcDxt3),
+        75 => ContainerFormat::Compressed(CompressedTextureFormat::SrgbAlphaS3tcDxt3),
+        77 => ContainerFormat::Compressed(CompressedTextureFormat::RgbaS3tcDxt5),
+        78 => ContainerFormat::Compressed(CompressedTextureFormat::SrgbAlphaS3tcDxt5),
+        87 => ContainerFormat::Uncompressed {
+            format: TextureFormat::Bgra,
+            internal_format: TextureInternalFormat::Rgba8,
+        },
+        91 => ContainerFormat::Uncompressed {
+            format: TextureFormat::Bgra,
+            internal_format: TextureInternalFormat::Srgb8Alpha8,
+        },
+        _ => return Err(Error::Unsupported("DXGI format")),
+    };
+
+    Ok(format)
+}
+
+/// Returns the number of levels in the full mip chain of a `width` by `height` texture, i.e.
+/// `floor(log2(max(width, height))) + 1`.
+fn max_level_count(width: usize, height: usize) -> usize {
+    let size = cmp::max(width, height);
+    mem::size_of::<usize>() * 8 - size.leading_zeros() as usize
+}
+
+/// Returns the size of mip level `level` of a `width` by `height` texture.
+fn level_size(width: usize, height: usize, level: usize) -> (usize, usize) {
+    let shrink = |size: usize| cmp::max(size.checked_shr(level as u32).unwrap_or(0), 1);
+    (shrink(width), shrink(height))
+}
+
+/// Reads little-endian values from a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+
+    /// Whether the values are stored big-endian instead.
+    swap_bytes: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader {
+            bytes: bytes,
+            offset: 0,
+            swap_bytes: false,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() - self.offset < len {
+            return Err(Error::UnexpectedEnd);
+        }
+
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        let value = bytes[0] as u32
+            | (bytes[1] as u32) << 8
+            | (bytes[2] as u32) << 16
+            | (bytes[3] as u32) << 24;
+
+        Ok(if self.swap_bytes { value.swap_bytes() } else { value })
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.bytes(len).map(|_| ())
+    }
+
+    /// Skips padding up to the next multiple of `alignment` from the start of the data.
+    fn align(&mut self, alignment: usize) {
+        let padding = (alignment - self.offset % alignment) % alignment;
+        self.offset = cmp::min(self.offset + padding, self.bytes.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the header of an uncompressed RGBA KTX file, without any image data.
+    fn ktx_header(width: u32, height: u32, level_count: u32) -> Vec<u8> {
+        let mut bytes = KTX_IDENTIFIER.to_vec();
+        let fields = [
+            0x04030201, GL_UNSIGNED_BYTE, 1, GL_RGBA, GL_RGBA8, GL_RGBA,
+            width, height, 0, 0, 1, level_count, 0,
+        ];
+        for &field in &fields {
+            push_u32(&mut bytes, field);
+        }
+        bytes
+    }
+
+    /// Builds the header of an uncompressed RGBA DDS file, without any image data.
+    fn dds_header(width: u32, height: u32, level_count: u32) -> Vec<u8> {
+        let mut bytes = DDS_MAGIC.to_vec();
+        for &field in &[124, DDSD_MIPMAPCOUNT, height, width, 0, 0, level_count] {
+            push_u32(&mut bytes, field);
+        }
+        for _ in 0..11 {
+            push_u32(&mut bytes, 0);
+        }
+        let pixel_format = [32, DDPF_RGB, 0, 32, 0x000000FF, 0x0000FF00, 0x00FF0000, 0xFF000000];
+        for &field in &pixel_format {
+            push_u32(&mut bytes, field);
+        }
+        for _ in 0..5 {
+            push_u32(&mut bytes, 0);
+        }
+        bytes
+    }
+
+    fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+        for &shift in &[0, 8, 16, 24] {
+            bytes.push((value >> shift) as u8);
+        }
+    }
+
+    #[test]
+    fn ktx_mip_chain() {
+        let mut bytes = ktx_header(4, 2, 3);
+        for &(width, height) in &[(4, 2), (2, 1), (1, 1)] {
+            push_u32(&mut bytes, width * height * 4);
+            bytes.extend((0..width * height * 4).map(|byte| byte as u8));
+        }
+
+        let container = TextureContainer::from_bytes(&bytes).unwrap();
+        let sizes: Vec<_> = container.levels()
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect();
+        assert_eq!(vec![(4, 2), (2, 1), (1, 1)], sizes);
+        assert!(!container.is_cube_map());
+    }
+
+    #[test]
+    fn ktx_truncated() {
+        let mut bytes = ktx_header(4, 4, 1);
+        push_u32(&mut bytes, 64);
+        bytes.extend(&[0; 32]);
+
+        match TextureContainer::from_ktx(&bytes) {
+            Err(Error::UnexpectedEnd) => {},
+            result => panic!("Expected UnexpectedEnd, got {:?}", result),
+        }
+
+        match TextureContainer::from_ktx(&bytes[..20]) {
+            Err(Error::UnexpectedEnd) => {},
+            result => panic!("Expected UnexpectedEnd, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn ktx_oversized_mip_chain() {
+        let bytes = ktx_header(4, 4, 4);
+
+        match TextureContainer::from_ktx(&bytes) {
+            Err(Error::Malformed(_)) => {},
+            result => panic!("Expected Malformed, got {:?}", result),
+        }
+
+        match TextureContainer::from_ktx(&ktx_header(1, 1, u32::max_value())) {
+            Err(Error::Malformed(_)) => {},
+            result => panic!("Expected Malformed, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn ktx_zero_size() {
+        for &(width, height) in &[(0, 4), (4, 0), (0, 0)] {
+            match TextureContainer::from_ktx(&ktx_header(width, height, 1)) {
+                Err(Error::Malformed(_)) => {},
+                result => panic!("Expected Malformed for {}x{}, got {:?}", width, height, result),
+            }
+        }
+    }
+
+    #[test]
+    fn dds_mip_chain() {
+        let mut bytes = dds_header(2, 4, 3);
+        for &(width, height) in &[(2, 4), (1, 2), (1, 1)] {
+            bytes.extend((0..width * height * 4).map(|byte| byte as u8));
+        }
+
+        let container = TextureContainer::from_bytes(&bytes).unwrap();
+        let sizes: Vec<_> = container.levels()
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect();
+        assert_eq!(vec![(2, 4), (1, 2), (1, 1)], sizes);
+    }
+
+    #[test]
+    fn dds_truncated() {
+        let mut bytes = dds_header(4, 4, 1);
+        bytes.extend(&[0; 32]);
+
+        match TextureContainer::from_dds(&bytes) {
+            Err(Error::UnexpectedEnd) => {},
+            result => panic!("Expected UnexpectedEnd, got {:?}", result),
+        }
+
+        match TextureContainer::from_dds(&bytes[..64]) {
+            Err(Error::UnexpectedEnd) => {},
+            result => panic!("Expected UnexpectedEnd, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn dds_oversized_mip_chain() {
+        match TextureContainer::from_dds(&dds_header(4, 4, 4)) {
+            Err(Error::Malformed(_)) => {},
+            result => panic!("Expected Malformed, got {:?}", result),
+        }
+
+        match TextureContainer::from_dds(&dds_header(1, 1, u32::max_value())) {
+            Err(Error::Malformed(_)) => {},
+            result => panic!("Expected Malformed, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn dds_zero_size() {
+        for &(width, height) in &[(0, 4), (4, 0), (0, 0)] {
+            match TextureContainer::from_dds(&dds_header(width, height, 1)) {
+                Err(Error::Malformed(_)) => {},
+                result => panic!("Expected Malformed for {}x{}, got {:?}", width, height, result),
+            }
+        }
+    }
+
+    #[test]
+    fn level_sizes() {
+        assert_eq!(3, max_level_count(4, 2));
+        assert_eq!(1, max_level_count(1, 1));
+        assert_eq!((1, 1), level_size(4, 2, 200));
+    }
+}
@@ -1,113 +1,364 @@
 use bootstrap::window::Window;
+use framebuffer::{Framebuffer, Rect};
 use gl;
 use gl::*;
+use pipeline::{PipelineKey, PipelineState};
 use std::cell::RefCell;
 use std::ffi::CStr;
-use std::ptr;
+use std::{mem, ptr};
 use std::rc::Rc;
 
+pub use gl::{Capabilities, ContextConfig, ContextProfile, Extensions};
+
 #[derive(Debug)]
 pub struct Context {
     raw: gl::Context,
     inner: Rc<RefCell<ContextInner>>,
+
+    device_context: gl::DeviceContext,
+    config: ContextConfig,
+    capabilities: Capabilities,
+    srgb_capable: bool,
 }
 
 impl Context {
     /// Creates a new rendering context for the specified window.
+    ///
+    /// The context is created with the default `ContextConfig`.
     pub fn from_window(window: &Window) -> Result<Context, Error> {
+        Context::from_window_with(window, ContextConfig::default())
+    }
+
+    /// Creates a new rendering context for the specified window using the provided options.
+    ///
+    /// Use this to request a specific GL version, a core profile context, or a debug context.
+    pub fn from_window_with(window: &Window, config: ContextConfig) -> Result<Context, Error> {
         let device_context = window.platform().device_context();
-        Context::from_device_context(device_context)
+        Context::from_device_context(device_context, config)
     }
 
-    /// Initializes global OpenGL state and creates the OpenGL context needed to perform rendering.
-    fn from_device_context(device_context: gl::DeviceContext) -> Result<Context, Error> {
-        pub extern "system" fn debug_callback(
-            source: DebugSource,
-            message_type: DebugType,
-            object_id: u32,
-            severity: DebugSeverity,
-            _length: i32,
-            message: *const u8,
-            _user_param: *mut ()
-        ) {
-            use std::ffi::CStr;
-
-            let message = unsafe { CStr::from_ptr(message as *const _) }.to_string_lossy();
-
-            println!(
-                r#"Recieved some kind of debug message.
-                source: {:?},
-                type: {:?},
-                object_id: 0x{:x},
-                severity: {:?},
-                message: {}"#,
-                source,
-                message_type,
-                object_id,
-                severity,
-                message);
-        }
+    /// Creates a new context that shares objects with this one.
+    ///
+    /// Textures, buffers, shaders, and programs created in either context can be used in both,
+    /// which allows resources to be loaded on a background thread without blocking rendering.
+    /// The returned `SharedContext` can be sent to another thread and turned into a full
+    /// `Context` there with `SharedContext::into_context()`.
+    ///
+    /// Note that commands issued from one context are not guaranteed to be visible to the
+    /// other until they have completed. Call `Context::finish()` (or use a fence) on the loading
+    /// context before handing its resources to the render thread. Vertex arrays are *not* shared
+    /// between contexts.
+    pub fn create_shared(&self) -> Result<SharedContext, Error> {
+        let raw = unsafe {
+            gl::create_shared_context(self.device_context, self.raw, &self.config)
+            .ok_or(Error::UnableToCreateRenderContext)?
+        };
+
+        Ok(SharedContext {
+            raw: raw,
+            device_context: self.device_context,
+            config: self.config,
+        })
+    }
 
+    /// Initializes global OpenGL state and creates the OpenGL context needed to perform rendering.
+    fn from_device_context(
+        device_context: gl::DeviceContext,
+        config: ContextConfig,
+    ) -> Result<Context, Error> {
         unsafe {
             let context =
-                gl::create_context(device_context)
+                gl::create_context_with_config(device_context, &config)
                 .ok_or(Error::UnableToCreateRenderContext)?;
 
-            {
-                let _guard = ::context::ContextGuard::new(context);
-
-                gl::enable(ServerCapability::DebugOutput);
-                gl::debug_message_callback(Some(debug_callback), ptr::null_mut());
-
-                let vendor = CStr::from_ptr(gl::get_string(StringName::Vendor)).to_str().unwrap();
-                let renderer = CStr::from_ptr(gl::get_string(StringName::Renderer)).to_str().unwrap();
-                let version = CStr::from_ptr(gl::get_string(StringName::Version)).to_str().unwrap();
-                let glsl_version = CStr::from_ptr(gl::get_string(StringName::ShadingLanguageVersion)).to_str().unwrap();
-
-                println!("OpenGL Information:");
-                println!("\tvendor: {}", vendor);
-                println!("\trenderer: {}", renderer);
-                println!("\tversion: {}", version);
-                println!("\tglsl version: {}", glsl_version);
+            Ok(Context::from_raw(context, device_context, config))
+        }
+    }
 
-                // Load a bunch of proc pointers for funsies.
-                gl::get_attrib_location::load();
-                gl::gen_vertex_arrays::load();
+    /// Initializes the state for an already-created OpenGL context.
+    unsafe fn from_raw(
+        context: gl::Context,
+        device_context: gl::DeviceContext,
+        config: ContextConfig,
+    ) -> Context {
+        let (capabilities, srgb_capable) = {
+            let _guard = ::context::ContextGuard::new(context);
+
+            gl::enable(ServerCapability::DebugOutput);
+            gl::debug_message_callback(Some(debug_callback), ptr::null_mut());
+
+            let vendor = CStr::from_ptr(gl::get_string(StringName::Vendor)).to_str().unwrap();
+            let renderer = CStr::from_ptr(gl::get_string(StringName::Renderer)).to_str().unwrap();
+            let version = CStr::from_ptr(gl::get_string(StringName::Version)).to_str().unwrap();
+            let glsl_version = CStr::from_ptr(gl::get_string(StringName::ShadingLanguageVersion)).to_str().unwrap();
+
+            println!("OpenGL Information:");
+            println!("\tvendor: {}", vendor);
+            println!("\trenderer: {}", renderer);
+            println!("\tversion: {}", version);
+            println!("\tglsl version: {}", glsl_version);
+
+            // Load a bunch of proc pointers for funsies.
+            gl::get_attrib_location::load();
+            gl::gen_vertex_arrays::load();
+            let srgb_capable = gl::platform::is_framebuffer_srgb_capable(device_context);
+            if config.srgb {
+                if !srgb_capable {
+                    println!(
+                        "WARNING: The window's pixel format isn't sRGB-capable, only sRGB \
+                        framebuffer attachments will be converted to sRGB",
+                    );
+                }
                 gl::enable(ServerCapability::FramebufferSrgb);
-                gl::enable(ServerCapability::Blend);
             }
-
-            let inner = Rc::new(RefCell::new(ContextInner {
-                raw: context,
-
-                server_srgb_enabled: true,
-                server_cull_enabled: false,
-                server_depth_test_enabled: false,
-                server_blend_enabled: true,
-
-                bound_vertex_array: None,
-                front_polygon_mode: PolygonMode::default(),
-                back_polygon_mode: PolygonMode::default(),
-                program: None,
-                cull_mode: Face::default(),
-                winding_order: WindingOrder::default(),
-                depth_test: Comparison::Less,
-                blend: Default::default(),
-            }));
-
-            Ok(Context {
-                raw: context,
-                inner: inner,
-            })
+            gl::enable(ServerCapability::Blend);
+
+            // Let vertex programs set the size of points with `gl_PointSize`.
+            gl::enable(ServerCapability::ProgramPointSize);
+
+            // Filter across the edges of cube map faces, so that blurry environment maps don't
+            // show seams.
+            gl::enable(ServerCapability::TextureCubeMapSeamless);
+
+            (Capabilities::query(), srgb_capable)
+        };
+
+        let inner = Rc::new(RefCell::new(ContextInner {
+            raw: context,
+
+            server_srgb_enabled: config.srgb,
+            server_cull_enabled: false,
+            server_depth_test_enabled: false,
+            server_blend_enabled: true,
+            server_polygon_offset_fill_enabled: false,
+
+            bound_vertex_array: None,
+            bound_framebuffer: FramebufferName::null(),
+            front_polygon_mode: PolygonMode::default(),
+            back_polygon_mode: PolygonMode::default(),
+            polygon_offset: (0.0, 0.0),
+            program: None,
+            cull_mode: Face::default(),
+            winding_order: WindingOrder::default(),
+            depth_test: Comparison::Less,
+            depth_write: true,
+            depth_range: (0.0, 1.0),
+            clip_depth: ClipDepth::default(),
+            blend: Default::default(),
+
+            pipeline: None,
+        }));
+
+        Context {
+            raw: context,
+            inner: inner,
+
+            device_context: device_context,
+            config: config,
+            capabilities: capabilities,
+            srgb_capable: srgb_capable,
         }
     }
 
+    /// Returns the extensions and implementation limits of the context.
+    ///
+    /// The capabilities are queried once when the context is created.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Returns whether the window's pixel format supports converting writes to the default
+    /// framebuffer from linear to sRGB, see `ContextConfig::srgb`.
+    pub fn is_default_framebuffer_srgb(&self) -> bool {
+        self.srgb_capable
+    }
+
+    /// Blocks until all previously issued commands have completed.
+    ///
+    /// Useful on a loading context to ensure that resources are ready before they are used from
+    /// another context.
+    pub fn finish(&self) {
+        let _guard = ::context::ContextGuard::new(self.raw);
+        unsafe { gl::finish(); }
+    }
+
+    /// Clears the default framebuffer.
+    ///
     /// TODO: Take clear mask (and values) as parameters.
     pub fn clear(&self) {
         let _guard = ::context::ContextGuard::new(self.raw);
+        let mut inner = self.inner.borrow_mut();
+        inner.bind_framebuffer(FramebufferName::null());
+        inner.enable_depth_clear();
         unsafe { gl::clear(ClearBufferMask::Color | ClearBufferMask::Depth); }
     }
 
+    /// Clears the color and depth attachments of `framebuffer`.
+    ///
+    /// # Panics
+    ///
+    /// - If `framebuffer` was created for a different context.
+    pub fn clear_framebuffer(&self, framebuffer: &Framebuffer) {
+        assert!(
+            self.raw == framebuffer.context(),
+            "Specified framebuffer's context does not match the context"
+        );
+
+        let _guard = ::context::ContextGuard::new(self.raw);
+        let mut inner = self.inner.borrow_mut();
+        inner.bind_framebuffer(framebuffer.inner());
+        inner.enable_depth_clear();
+        unsafe { gl::clear(ClearBufferMask::Color | ClearBufferMask::Depth); }
+    }
+
+    /// Clears the region `rect` of `framebuffer`, or of the default framebuffer if `framebuffer`
+    /// is `None`.
+    ///
+    /// The color attachments are cleared to `color` if it's `Some`, and the depth attachment is
+    /// cleared to `depth` if it's `Some`. Pixels outside of `rect` are left untouched, so several
+    /// viewports can share a single framebuffer.
+    ///
+    /// # Panics
+    ///
+    /// - If `framebuffer` was created for a different context.
+    pub fn clear_rect(
+        &self,
+        framebuffer: Option<&Framebuffer>,
+        rect: Rect,
+        color: Option<[f32; 4]>,
+        depth: Option<f32>,
+    ) {
+        let framebuffer_name = match framebuffer {
+            Some(framebuffer) => {
+                assert!(
+                    self.raw == framebuffer.context(),
+                    "Specified framebuffer's context does not match the context"
+                );
+                framebuffer.inner()
+            },
+            None => FramebufferName::null(),
+        };
+
+        let mask = match (color.is_some(), depth.is_some()) {
+            (true, true) => ClearBufferMask::Color | ClearBufferMask::Depth,
+            (true, false) => ClearBufferMask::Color,
+            (false, true) => ClearBufferMask::Depth,
+            (false, false) => return,
+        };
+
+        let _guard = ::context::ContextGuard::new(self.raw);
+        let mut inner = self.inner.borrow_mut();
+        inner.bind_framebuffer(framebuffer_name);
+
+        // Depth is only cleared while depth writes are enabled.
+        inner.enable_depth_clear();
+
+        unsafe {
+            if let Some(color) = color {
+                gl::clear_color(color[0], color[1], color[2], color[3]);
+            }
+            if let Some(depth) = depth {
+                gl::clear_depth(depth as f64);
+            }
+
+            gl::enable(ServerCapability::ScissorTest);
+            gl::scissor(rect.x, rect.y, rect.width, rect.height);
+            gl::clear(mask);
+            gl::disable(ServerCapability::ScissorTest);
+
+            // Restore the initial clear values, which are used by `clear()`.
+            gl::clear_color(0.0, 0.0, 0.0, 0.0);
+            gl::clear_depth(1.0);
+        }
+    }
+
+    /// Binds all of the state in `pipeline` to the context.
+    ///
+    /// Binding a pipeline before a batch of draws that share that pipeline means the state only
+    /// needs to be set once, since `DrawBuilder::draw()` skips setting the state when its pipeline
+    /// matches the one that is already bound.
+    ///
+    /// # Panics
+    ///
+    /// - If `pipeline` was created for a different context.
+    pub fn bind_pipeline(&self, pipeline: &PipelineState) {
+        assert!(
+            self.raw == pipeline.context(),
+            "Specified pipeline's context does not match the context it's being bound to"
+        );
+
+        let _guard = ::context::ContextGuard::new(self.raw);
+        self.inner.borrow_mut().bind_pipeline(pipeline.key());
+    }
+
+    /// Sets whether writes to sRGB framebuffer attachments are converted from linear to sRGB.
+    ///
+    /// Initially set by `ContextConfig::srgb`. Writes to attachments that don't have an sRGB
+    /// format are never converted.
+    pub fn set_srgb_enabled(&self, enabled: bool) {
+        let _guard = ::context::ContextGuard::new(self.raw);
+        self.inner.borrow_mut().enable_server_srgb(enabled);
+    }
+
+    /// Sets whether clip-space depth from -1 to 1 (the default) or from 0 to 1 is mapped to the
+    /// depth buffer.
+    ///
+    /// `ClipDepth::ZeroToOne` is meant for reverse-Z projections. Depth outside of the selected
+    /// range is clipped.
+    ///
+    /// # Panics
+    ///
+    /// - If `depth` isn't `ClipDepth::NegativeOneToOne` and the context doesn't support clip
+    ///   control, see `Capabilities::clip_control`.
+    pub fn set_clip_depth(&self, depth: ClipDepth) {
+        assert!(
+            depth == ClipDepth::NegativeOneToOne || self.capabilities.clip_control,
+            "Clip control is not supported by the context"
+        );
+
+        let _guard = ::context::ContextGuard::new(self.raw);
+        self.inner.borrow_mut().clip_depth(depth);
+    }
+
+    /// Starts a debug group named `name`, which lasts until the matching `pop_debug_group()`.
+    ///
+    /// Debug groups can be nested, and are shown as labels around the commands issued while
+    /// they're active in debugging tools such as RenderDoc and Nsight.
+    pub fn push_debug_group(&self, name: &str) {
+        let _guard = ::context::ContextGuard::new(self.raw);
+        unsafe {
+            gl::push_debug_group(DebugSource::Application, 0, name.len() as i32, name.as_ptr());
+        }
+    }
+
+    /// Ends the debug group most recently started with `push_debug_group()`.
+    pub fn pop_debug_group(&self) {
+        let _guard = ::context::ContextGuard::new(self.raw);
+        unsafe { gl::pop_debug_group(); }
+    }
+
+    /// Returns the current viewport.
+    ///
+    /// The viewport is initially the full size of the window the context was created for.
+    pub fn viewport(&self) -> Rect {
+        let _guard = ::context::ContextGuard::new(self.raw);
+
+        let mut viewport = [0i32; 4];
+        unsafe { gl::get_integers(IntegerName::Viewport, viewport.as_mut_ptr()); }
+
+        Rect::new(viewport[0], viewport[1], viewport[2], viewport[3])
+    }
+
+    /// Sets the region of the framebuffer that draws are mapped to.
+    ///
+    /// The viewport isn't tied to a framebuffer, so when drawing to a framebuffer that's a
+    /// different size than the window (e.g. a shadow map) the viewport has to be set before
+    /// drawing and restored afterwards.
+    pub fn set_viewport(&self, viewport: Rect) {
+        let _guard = ::context::ContextGuard::new(self.raw);
+        unsafe { gl::viewport(viewport.x, viewport.y, viewport.width, viewport.height); }
+    }
+
     pub fn swap_buffers(&self) {
         let _guard = ::context::ContextGuard::new(self.raw);
         unsafe { gl::platform::swap_buffers(self.raw); }
@@ -122,6 +373,37 @@ impl Context {
     }
 }
 
+/// A context that shares objects with another context, but which hasn't been initialized yet.
+///
+/// Created with `Context::create_shared()`. Unlike `Context`, `SharedContext` can be sent to
+/// another thread, where it is turned into a usable `Context` with `into_context()`. Objects
+/// created with the resulting context must also be dropped on that thread.
+#[derive(Debug)]
+pub struct SharedContext {
+    raw: gl::Context,
+    device_context: gl::DeviceContext,
+    config: ContextConfig,
+}
+
+// NOTE: The raw context handles are only pointers, the GL context itself isn't bound to any thread
+// until it is made current. `SharedContext` is never made current, so it's safe to send.
+unsafe impl Send for SharedContext {}
+
+impl SharedContext {
+    /// Initializes the shared context for use on the current thread.
+    pub fn into_context(self) -> Context {
+        let context = unsafe { Context::from_raw(self.raw, self.device_context, self.config) };
+        mem::forget(self);
+        context
+    }
+}
+
+impl Drop for SharedContext {
+    fn drop(&mut self) {
+        unsafe { gl::destroy_context(self.raw); }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ContextInner {
     raw: gl::Context,
@@ -130,15 +412,24 @@ pub(crate) struct ContextInner {
     server_cull_enabled: bool,
     server_depth_test_enabled: bool,
     server_blend_enabled: bool,
+    server_polygon_offset_fill_enabled: bool,
 
     bound_vertex_array: Option<VertexArrayName>,
+    bound_framebuffer: FramebufferName,
     front_polygon_mode: PolygonMode,
     back_polygon_mode: PolygonMode,
+    polygon_offset: (f32, f32),
     program: Option<ProgramObject>,
     cull_mode: Face,
     winding_order: WindingOrder,
     depth_test: Comparison,
+    depth_write: bool,
+    depth_range: (f64, f64),
+    clip_depth: ClipDepth,
     blend: (SourceFactor, DestFactor),
+
+    /// The most recently bound pipeline state.
+    pipeline: Option<PipelineKey>,
 }
 
 impl ContextInner {
@@ -146,6 +437,14 @@ impl ContextInner {
         self.raw
     }
 
+    /// Applies the pipeline state if it differs from the currently bound pipeline.
+    pub(crate) fn bind_pipeline(&mut self, pipeline: PipelineKey) {
+        if Some(pipeline) != self.pipeline {
+            pipeline.apply(self);
+            self.pipeline = Some(pipeline);
+        }
+    }
+
     pub(crate) fn bind_vertex_array(&mut self, vertex_array_name: VertexArrayName) {
         if Some(vertex_array_name) != self.bound_vertex_array {
             unsafe { gl::bind_vertex_array(vertex_array_name); }
@@ -160,6 +459,43 @@ impl ContextInner {
         }
     }
 
+    /// Binds `buffer_name` to `target`.
+    ///
+    /// The element array binding is part of the bound vertex array's state, so for
+    /// `BufferTarget::ElementArray` the vertex array is unbound first. Otherwise the buffer would
+    /// replace the indices of whichever vertex array was drawn last.
+    pub(crate) fn bind_buffer(&mut self, target: BufferTarget, buffer_name: BufferName) {
+        if target == BufferTarget::ElementArray
+            && self.bound_vertex_array != Some(VertexArrayName::null())
+        {
+            unsafe { gl::bind_vertex_array(VertexArrayName::null()); }
+            self.bound_vertex_array = Some(VertexArrayName::null());
+        }
+
+        unsafe { gl::bind_buffer(target, buffer_name); }
+    }
+
+    /// Binds the framebuffer for drawing, or the default framebuffer for `FramebufferName::null()`.
+    ///
+    /// Only the draw framebuffer binding is tracked. Code that needs to bind a framebuffer
+    /// temporarily (e.g. to modify its attachments) should use the read framebuffer binding so
+    /// that it doesn't disturb the tracked state.
+    pub(crate) fn bind_framebuffer(&mut self, framebuffer_name: FramebufferName) {
+        if framebuffer_name != self.bound_framebuffer {
+            unsafe { gl::bind_framebuffer(FramebufferTarget::DrawFramebuffer, framebuffer_name); }
+            self.bound_framebuffer = framebuffer_name;
+        }
+    }
+
+    pub(crate) fn unbind_framebuffer(&mut self, framebuffer_name: FramebufferName) {
+        if framebuffer_name == self.bound_framebuffer {
+            unsafe {
+                gl::bind_framebuffer(FramebufferTarget::DrawFramebuffer, FramebufferName::null());
+            }
+            self.bound_framebuffer = FramebufferName::null();
+        }
+    }
+
     pub(crate) fn polygon_mode(&mut self, mode: PolygonMode) {
         if mode != self.front_polygon_mode || mode != self.back_polygon_mode {
             unsafe { gl::polygon_mode(Face::FrontAndBack, mode); }
@@ -189,6 +525,23 @@ impl ContextInner {
         }
     }
 
+    pub(crate) fn clip_depth(&mut self, depth: ClipDepth) {
+        if depth != self.clip_depth {
+            unsafe { gl::clip_control(ClipOrigin::LowerLeft, depth); }
+            self.clip_depth = depth;
+        }
+    }
+
+    pub(crate) fn enable_server_srgb(&mut self, enabled: bool) {
+        if enabled != self.server_srgb_enabled {
+            match enabled {
+                true => unsafe { gl::enable(ServerCapability::FramebufferSrgb); },
+                false => unsafe { gl::disable(ServerCapability::FramebufferSrgb); },
+            }
+            self.server_srgb_enabled = enabled;
+        }
+    }
+
     pub(crate) fn enable_server_depth_test(&mut self, enabled: bool) {
         if enabled != self.server_depth_test_enabled {
             match enabled {
@@ -199,6 +552,23 @@ impl ContextInner {
         }
     }
 
+    pub(crate) fn enable_server_polygon_offset_fill(&mut self, enabled: bool) {
+        if enabled != self.server_polygon_offset_fill_enabled {
+            match enabled {
+                true => unsafe { gl::enable(ServerCapability::PolygonOffsetFill); },
+                false => unsafe { gl::disable(ServerCapability::PolygonOffsetFill); },
+            }
+            self.server_polygon_offset_fill_enabled = enabled;
+        }
+    }
+
+    pub(crate) fn polygon_offset(&mut self, factor: f32, units: f32) {
+        if (factor, units) != self.polygon_offset {
+            unsafe { gl::polygon_offset(factor, units); }
+            self.polygon_offset = (factor, units);
+        }
+    }
+
     pub(crate) fn cull_mode(&mut self, face: Face) {
         if self.cull_mode != face {
             unsafe { gl::cull_face(face); }
@@ -220,6 +590,31 @@ impl ContextInner {
         }
     }
 
+    /// Enables depth writes, which clearing the depth buffer depends on.
+    ///
+    /// The last draw may have left depth writes disabled, in which case the bound pipeline no
+    /// longer matches the context's state and is forgotten so that the next draw reapplies it.
+    pub(crate) fn enable_depth_clear(&mut self) {
+        if !self.depth_write {
+            self.depth_write(true);
+            self.pipeline = None;
+        }
+    }
+
+    pub(crate) fn depth_write(&mut self, enabled: bool) {
+        if enabled != self.depth_write {
+            unsafe { gl::depth_mask(enabled.into()); }
+            self.depth_write = enabled;
+        }
+    }
+
+    pub(crate) fn depth_range(&mut self, near: f64, far: f64) {
+        if (near, far) != self.depth_range {
+            unsafe { gl::depth_range(near, far); }
+            self.depth_range = (near, far);
+        }
+    }
+
     pub(crate) fn blend(&mut self, source_factor: SourceFactor, dest_factor: DestFactor) {
         if (source_factor, dest_factor) != self.blend {
             unsafe { gl::blend_func(source_factor, dest_factor); }
@@ -228,6 +623,31 @@ impl ContextInner {
     }
 }
 
+extern "system" fn debug_callback(
+    source: DebugSource,
+    message_type: DebugType,
+    object_id: u32,
+    severity: DebugSeverity,
+    _length: i32,
+    message: *const u8,
+    _user_param: *mut ()
+) {
+    let message = unsafe { CStr::from_ptr(message as *const _) }.to_string_lossy();
+
+    println!(
+        r#"Recieved some kind of debug message.
+        source: {:?},
+        type: {:?},
+        object_id: 0x{:x},
+        severity: {:?},
+        message: {}"#,
+        source,
+        message_type,
+        object_id,
+        severity,
+        message);
+}
+
 impl Drop for Context {
     fn drop(&mut self) {
         unsafe {
@@ -248,7 +668,8 @@ pub enum Error {
 
     /// Indicates that the program failed the create the rendering context.
     ///
-    /// This might happen because reasons.
+    /// This might happen because reasons, most commonly because the requested `ContextConfig`
+    /// isn't supported by the driver (e.g. requesting a GL version that is too new).
     UnableToCreateRenderContext,
 }
 
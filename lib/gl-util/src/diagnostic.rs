@@ -0,0 +1,289 @@
+//! Parsing of shader compile and program link logs.
+//!
+//! Drivers report errors as free-form text and each vendor uses a different format. The
+//! functions in this module parse the formats used by the common drivers into
+//! `ShaderDiagnostic`s so that tools can point at the offending line:
+//!
+//! - NVIDIA: `0(12) : error C0000: syntax error, unexpected ';'`
+//! - AMD and Intel: `ERROR: 0:12: 'foo' : undeclared identifier`
+//! - Mesa: `0:12(5): error: 'foo' undeclared`
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+/// Like `try!()`, but for `Option`.
+macro_rules! try_opt {
+    ($expr:expr) => {
+        match $expr {
+            Some(value) => value,
+            None => return None,
+        }
+    }
+}
+
+/// A single error or warning reported by the driver.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShaderDiagnostic {
+    /// The file the diagnostic refers to.
+    ///
+    /// This is only known for shaders created with `Shader::with_includes()`.
+    pub file: Option<String>,
+
+    /// The line the diagnostic refers to, starting from 1.
+    pub line: Option<usize>,
+
+    pub severity: Severity,
+
+    /// The message reported by the driver, without the location and severity.
+    pub message: String,
+}
+
+impl Display for ShaderDiagnostic {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (&Some(ref file), Some(line)) => write!(formatter, "{}:{}: ", file, line)?,
+            (&None, Some(line)) => write!(formatter, "{}: ", line)?,
+            (&Some(ref file), None) => write!(formatter, "{}: ", file)?,
+            (&None, None) => {},
+        }
+
+        write!(formatter, "{}: {}", self.severity, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+
+    /// Any line of the log that couldn't be recognized as an error or warning.
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let name = match *self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+
+        formatter.write_str(name)
+    }
+}
+
+/// Parses a shader or program info log into a list of diagnostics.
+///
+/// Empty lines are skipped, and lines that aren't in a recognized format are returned as
+/// `Severity::Note` diagnostics with no location so that no information is lost.
+pub fn parse_info_log(log: &str) -> Vec<ShaderDiagnostic> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            parse_log_line(line)
+                .map(|(diagnostic, _)| diagnostic)
+                .unwrap_or_else(|| ShaderDiagnostic {
+                    file: None,
+                    line: None,
+                    severity: Severity::Note,
+                    message: String::from(line),
+                })
+        })
+        .collect()
+}
+
+/// Parses a single line of an info log that starts without whitespace.
+///
+/// Returns the diagnostic along with the byte range of its location in `line`, i.e. the source
+/// string index and line number such as `0(12)` or `0:12`, so that the location can be replaced
+/// when rewriting the log.
+pub(crate) fn parse_log_line(line: &str) -> Option<(ShaderDiagnostic, Range<usize>)> {
+    parse_nvidia(line)
+        .or_else(|| parse_amd(line))
+        .or_else(|| parse_mesa(line))
+}
+
+/// Parses lines of the form `0(12) : error C0000: message`.
+fn parse_nvidia(line: &str) -> Option<(ShaderDiagnostic, Range<usize>)> {
+    let (_, rest) = try_opt!(parse_number(line));
+    let rest = try_opt!(strip_prefix(rest, "("));
+    let (line_number, rest) = try_opt!(parse_number(rest));
+    let rest = try_opt!(strip_prefix(rest, ")"));
+    let location = 0..line.len() - rest.len();
+    let rest = try_opt!(strip_prefix(rest.trim_left(), ":")).trim_left();
+    let (severity, rest) = try_opt!(parse_severity(rest));
+
+    let diagnostic = ShaderDiagnostic {
+        file: None,
+        line: Some(line_number),
+        severity: severity,
+        message: String::from(rest.trim_left()),
+    };
+    Some((diagnostic, location))
+}
+
+/// Parses lines of the form `ERROR: 0:12: message`.
+fn parse_amd(line: &str) -> Option<(ShaderDiagnostic, Range<usize>)> {
+    let (severity, rest) = try_opt!(parse_severity(line));
+    let rest = try_opt!(strip_prefix(rest, ":")).trim_left();
+    let location_start = line.len() - rest.len();
+    let (_, rest) = try_opt!(parse_number(rest));
+    let rest = try_opt!(strip_prefix(rest, ":"));
+    let (line_number, rest) = try_opt!(parse_number(rest));
+    let location = location_start..line.len() - rest.len();
+    let rest = try_opt!(strip_prefix(rest, ":"));
+
+    let diagnostic = ShaderDiagnostic {
+        file: None,
+        line: Some(line_number),
+        severity: severity,
+        message: String::from(rest.trim_left()),
+    };
+    Some((diagnostic, location))
+}
+
+/// Parses lines of the form `0:12(5): error: message`, where 5 is the column.
+fn parse_mesa(line: &str) -> Option<(ShaderDiagnostic, Range<usize>)> {
+    let (_, rest) = try_opt!(parse_number(line));
+    let rest = try_opt!(strip_prefix(rest, ":"));
+    let (line_number, rest) = try_opt!(parse_number(rest));
+    let location = 0..line.len() - rest.len();
+    let rest = try_opt!(strip_prefix(rest, "("));
+    let (_, rest) = try_opt!(parse_number(rest));
+    let rest = try_opt!(strip_prefix(rest, "):")).trim_left();
+    let (severity, rest) = try_opt!(parse_severity(rest));
+    let rest = try_opt!(strip_prefix(rest, ":"));
+
+    let diagnostic = ShaderDiagnostic {
+        file: None,
+        line: Some(line_number),
+        severity: severity,
+        message: String::from(rest.trim_left()),
+    };
+    Some((diagnostic, location))
+}
+
+/// Parses a leading decimal number, returning the number and the remainder of the string.
+fn parse_number(text: &str) -> Option<(usize, &str)> {
+    let end = text.find(|c: char| !c.is_digit(10)).unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+
+    let number = try_opt!(text[..end].parse().ok());
+    Some((number, &text[end..]))
+}
+
+/// Parses a leading `error` or `warning`, ignoring case.
+fn parse_severity(text: &str) -> Option<(Severity, &str)> {
+    let lower = text.to_lowercase();
+    if lower.starts_with("error") {
+        Some((Severity::Error, &text["error".len()..]))
+    } else if lower.starts_with("warning") {
+        Some((Severity::Warning, &text["warning".len()..]))
+    } else {
+        None
+    }
+}
+
+fn strip_prefix<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    if text.starts_with(prefix) {
+        Some(&text[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vendor_formats() {
+        let cases = [
+            // NVIDIA
+            (
+                r#"0(12) : error C1008: undefined variable "foo""#,
+                Some(12),
+                Severity::Error,
+                r#"C1008: undefined variable "foo""#,
+            ),
+            (
+                r#"0(7) : warning C7022: unrecognized profile specifier "highp""#,
+                Some(7),
+                Severity::Warning,
+                r#"C7022: unrecognized profile specifier "highp""#,
+            ),
+
+            // AMD
+            (
+                "ERROR: 0:12: 'foo' : undeclared identifier",
+                Some(12),
+                Severity::Error,
+                "'foo' : undeclared identifier",
+            ),
+            (
+                "WARNING: 0:3: 'uniform' : unused variable",
+                Some(3),
+                Severity::Warning,
+                "'uniform' : unused variable",
+            ),
+
+            // Mesa
+            (
+                "0:12(5): error: `foo' undeclared",
+                Some(12),
+                Severity::Error,
+                "`foo' undeclared",
+            ),
+            (
+                "0:1(10): warning: extension `GL_ARB_gpu_shader5' unsupported in fragment shader",
+                Some(1),
+                Severity::Warning,
+                "extension `GL_ARB_gpu_shader5' unsupported in fragment shader",
+            ),
+
+            // Unrecognized
+            (
+                "error: linking with uncompiled shader",
+                None,
+                Severity::Note,
+                "error: linking with uncompiled shader",
+            ),
+        ];
+
+        for &(log, line, severity, message) in &cases {
+            let diagnostics = parse_info_log(log);
+            let expected = ShaderDiagnostic {
+                file: None,
+                line: line,
+                severity: severity,
+                message: String::from(message),
+            };
+            assert_eq!(vec![expected], diagnostics, "Failed to parse {:?}", log);
+        }
+    }
+
+    #[test]
+    fn multi_line_log() {
+        let log = "\n  0:2(1): error: syntax error\n\nERROR: 0:3: 'x' : redefinition\n";
+        let lines: Vec<_> = parse_info_log(log)
+            .into_iter()
+            .map(|diagnostic| diagnostic.line)
+            .collect();
+        assert_eq!(vec![Some(2), Some(3)], lines);
+    }
+
+    #[test]
+    fn location_ranges() {
+        fn location(line: &str) -> Option<&str> {
+            parse_log_line(line).map(|(_, range)| &line[range])
+        }
+
+        assert_eq!(Some("0(12)"), location(r#"0(12) : error C1008: undefined variable "foo""#));
+        assert_eq!(Some("0:12"), location("ERROR: 0:12: 'foo' : undeclared identifier"));
+        assert_eq!(Some("0:12"), location("0:12(5): error: `foo' undeclared"));
+        assert_eq!(None, location("error: linking with uncompiled shader"));
+    }
+}
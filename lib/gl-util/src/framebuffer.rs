@@ -0,0 +1,370 @@
+use context::{Context, ContextInner};
+use gl;
+use gl::*;
+use renderbuffer::Renderbuffer;
+use std::cell::RefCell;
+use std::rc::Rc;
+use texture::{CubeFace, Texture2d, TextureCube};
+
+pub use gl::{BlitFilter, ClearBufferMask, DrawBuffer, FramebufferAttachment, FramebufferStatus};
+
+/// An off-screen render target.
+///
+/// Textures and renderbuffers are attached to a framebuffer with `attach_texture()` and
+/// `attach_renderbuffer()`, and the framebuffer can then be drawn to with
+/// `DrawBuilder::framebuffer()`. Attaching an image doesn't borrow it, so the caller must keep
+/// the image alive for as long as it's attached.
+#[derive(Debug)]
+pub struct Framebuffer {
+    framebuffer_name: FramebufferName,
+
+    context: Rc<RefCell<ContextInner>>,
+}
+
+impl Framebuffer {
+    /// Creates a new framebuffer with no attachments.
+    pub fn new(context: &Context) -> Result<Framebuffer, Error> {
+        let _guard = ::context::ContextGuard::new(context.raw());
+
+        let framebuffer_name = gl::gen_framebuffer().ok_or(Error::FailedToGenerateFramebuffer)?;
+
+        Ok(Framebuffer {
+            framebuffer_name: framebuffer_name,
+
+            context: context.inner(),
+        })
+    }
+
+    /// Attaches `texture` to the specified attachment point, replacing the previous attachment.
+    ///
+    /// Depth textures (see `Texture2d::allocate()`) should be attached to
+    /// `FramebufferAttachment::Depth`.
+    ///
+    /// # Panics
+    ///
+    /// - If `texture` was created for a different context.
+    pub fn attach_texture(&mut self, attachment: FramebufferAttachment, texture: &Texture2d) {
+        assert!(
+            self.context() == texture.context(),
+            "Specified texture's context does not match framebuffer's context"
+        );
+
+        // NOTE: The read binding is used so that the context's draw framebuffer binding isn't
+        // disturbed, see `ContextInner::bind_framebuffer()`.
+        let _guard = ::context::ContextGuard::new(self.context());
+        unsafe {
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, self.framebuffer_name);
+            gl::framebuffer_texture_2d(
+                FramebufferTarget::ReadFramebuffer,
+                attachment,
+                Texture2dTarget::Texture2d,
+                texture.inner(),
+                0,
+            );
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+        }
+    }
+
+    /// Attaches one face of `texture` to the specified attachment point, replacing the previous
+    /// attachment.
+    ///
+    /// To render all six faces of a cube map, e.g. for an omnidirectional shadow map, attach each
+    /// face in turn and draw the scene once per face.
+    ///
+    /// # Panics
+    ///
+    /// - If `texture` was created for a different context.
+    pub fn attach_cube_face(
+        &mut self,
+        attachment: FramebufferAttachment,
+        texture: &TextureCube,
+        face: CubeFace,
+    ) {
+        self.attach_cube_face_level(attachment, texture, face, 0);
+    }
+
+    /// Attaches one mipmap level of one face of `texture` to the specified attachment point,
+    /// replacing the previous attachment.
+    ///
+    /// The texture must already have storage for `level`, see `TextureCube::generate_mipmaps()`.
+    /// Rendering into each level in turn allows filtered versions of a cube map to be stored in
+    /// its mip chain, e.g. for prefiltered environment maps.
+    ///
+    /// # Panics
+    ///
+    /// - If `texture` was created for a different context.
+    pub fn attach_cube_face_level(
+        &mut self,
+        attachment: FramebufferAttachment,
+        texture: &TextureCube,
+        face: CubeFace,
+        level: usize,
+    ) {
+        assert!(
+            self.context() == texture.context(),
+            "Specified texture's context does not match framebuffer's context"
+        );
+
+        let _guard = ::context::ContextGuard::new(self.context());
+        unsafe {
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, self.framebuffer_name);
+            gl::framebuffer_texture_2d(
+                FramebufferTarget::ReadFramebuffer,
+                attachment,
+                face.target(),
+                texture.inner(),
+                level as i32,
+            );
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+        }
+    }
+
+    /// Attaches `renderbuffer` to the specified attachment point, replacing the previous
+    /// attachment.
+    ///
+    /// All attachments of a framebuffer must have the same number of samples, so a multisampled
+    /// renderbuffer can't be combined with texture attachments.
+    ///
+    /// # Panics
+    ///
+    /// - If `renderbuffer` was created for a different context.
+    pub fn attach_renderbuffer(
+        &mut self,
+        attachment: FramebufferAttachment,
+        renderbuffer: &Renderbuffer,
+    ) {
+        assert!(
+            self.context() == renderbuffer.context(),
+            "Specified renderbuffer's context does not match framebuffer's context"
+        );
+
+        let _guard = ::context::ContextGuard::new(self.context());
+        unsafe {
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, self.framebuffer_name);
+            gl::framebuffer_renderbuffer(
+                FramebufferTarget::ReadFramebuffer,
+                attachment,
+                RenderbufferTarget::Renderbuffer,
+                renderbuffer.inner(),
+            );
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+        }
+    }
+
+    /// Attaches each texture in `textures` as a color attachment for multiple render targets.
+    ///
+    /// `textures[i]` is attached to `FramebufferAttachment::color(i)`, and the draw buffers are
+    /// set so that the fragment shader output at location `i` is written to it. This is the
+    /// common setup for a deferred shading G-buffer. Use `set_draw_buffers()` directly for more
+    /// control over which outputs are written to which attachments.
+    ///
+    /// # Panics
+    ///
+    /// - If more than 8 textures are provided.
+    /// - If any texture was created for a different context.
+    pub fn attach_color_textures(&mut self, textures: &[&Texture2d]) {
+        assert!(textures.len() <= 8, "Cannot attach more than 8 color textures");
+
+        let mut draw_buffers = Vec::with_capacity(textures.len());
+        for (index, texture) in textures.iter().enumerate() {
+            self.attach_texture(FramebufferAttachment::color(index as u32), texture);
+            draw_buffers.push(DrawBuffer::color(index as u32));
+        }
+
+        self.set_draw_buffers(&*draw_buffers);
+    }
+
+    /// Sets which attachment each fragment shader output is written to.
+    ///
+    /// The fragment shader output at location `i` is written to `buffers[i]`. Outputs at
+    /// locations greater than or equal to `buffers.len()` are discarded. By default only
+    /// output 0 is written, to `DrawBuffer::Color0`.
+    pub fn set_draw_buffers(&mut self, buffers: &[DrawBuffer]) {
+        // NOTE: Draw buffers are only set for the framebuffer bound to the draw target, so we go
+        // through the context to bind it.
+        let mut context = self.context.borrow_mut();
+        let _guard = ::context::ContextGuard::new(context.raw());
+        context.bind_framebuffer(self.framebuffer_name);
+        unsafe { gl::draw_buffers(buffers.len() as i32, buffers.as_ptr()); }
+    }
+
+    /// Sets which attachment is used as the source for reads from the framebuffer, e.g. blits.
+    ///
+    /// Framebuffers without color attachments should call `set_draw_buffers(&[DrawBuffer::None])`
+    /// and `set_read_buffer(DrawBuffer::None)` to be complete on older drivers.
+    pub fn set_read_buffer(&mut self, buffer: DrawBuffer) {
+        let _guard = ::context::ContextGuard::new(self.context());
+        unsafe {
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, self.framebuffer_name);
+            gl::read_buffer(buffer);
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+        }
+    }
+
+    /// Returns the completeness status of the framebuffer.
+    ///
+    /// A framebuffer can only be drawn to if its status is `FramebufferStatus::Complete`.
+    pub fn status(&self) -> FramebufferStatus {
+        let _guard = ::context::ContextGuard::new(self.context());
+        unsafe {
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, self.framebuffer_name);
+            let status = gl::check_framebuffer_status(FramebufferTarget::ReadFramebuffer);
+            gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+
+            status
+        }
+    }
+
+    /// Returns `true` if the framebuffer is complete and can be drawn to.
+    pub fn is_complete(&self) -> bool {
+        self.status() == FramebufferStatus::Complete
+    }
+
+    /// Returns the OpenGL primitive managed by this object.
+    pub(crate) fn inner(&self) -> FramebufferName {
+        self.framebuffer_name
+    }
+
+    pub(crate) fn context(&self) -> ::gl::Context {
+        self.context.borrow().raw()
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        let mut context = self.context.borrow_mut();
+        let _guard = ::context::ContextGuard::new(context.raw());
+        unsafe { gl::delete_framebuffers(1, &self.framebuffer_name); }
+        context.unbind_framebuffer(self.framebuffer_name);
+    }
+}
+
+/// A rectangular region of a framebuffer, in pixels.
+///
+/// `(x, y)` is the lower left corner of the rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        }
+    }
+}
+
+/// Copies a region of one framebuffer to another.
+///
+/// A `None` framebuffer refers to the default framebuffer (i.e. the backbuffer). `mask` selects
+/// which buffers are copied. If `src_rect` and `dst_rect` are different sizes the image is
+/// stretched using `filter`, though depth and stencil data can only be copied with
+/// `BlitFilter::Nearest`.
+///
+/// Blitting from a multisampled framebuffer to one that isn't multisampled resolves the samples,
+/// which is how MSAA render targets are resolved. In that case the rectangles must be the same
+/// size.
+///
+/// # Panics
+///
+/// - If either framebuffer was created for a different context.
+pub fn blit(
+    context: &Context,
+    src_framebuffer: Option<&Framebuffer>,
+    dst_framebuffer: Option<&Framebuffer>,
+    src_rect: Rect,
+    dst_rect: Rect,
+    mask: ClearBufferMask,
+    filter: BlitFilter,
+) {
+    for framebuffer in src_framebuffer.iter().chain(dst_framebuffer.iter()) {
+        assert!(
+            context.raw() == framebuffer.context(),
+            "Specified framebuffer's context does not match the context"
+        );
+    }
+
+    let src_name = src_framebuffer.map_or(FramebufferName::null(), Framebuffer::inner);
+    let dst_name = dst_framebuffer.map_or(FramebufferName::null(), Framebuffer::inner);
+
+    let inner = context.inner();
+    let mut inner = inner.borrow_mut();
+    let _guard = ::context::ContextGuard::new(inner.raw());
+
+    inner.bind_framebuffer(dst_name);
+    unsafe {
+        gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, src_name);
+        gl::blit_framebuffer(
+            src_rect.x,
+            src_rect.y,
+            src_rect.x + src_rect.width,
+            src_rect.y + src_rect.height,
+            dst_rect.x,
+            dst_rect.y,
+            dst_rect.x + dst_rect.width,
+            dst_rect.y + dst_rect.height,
+            mask,
+            filter,
+        );
+        gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+    }
+}
+
+/// Reads the color of the pixels within `rect` back from a framebuffer.
+///
+/// A `None` framebuffer refers to the default framebuffer (i.e. the backbuffer). The pixels are
+/// read from the first color attachment as RGBA with 8 bits per channel, row by row starting from
+/// the bottom row. Reading blocks until all previous draws to the framebuffer have completed.
+/// An empty `rect`, e.g. the viewport of a minimized window, returns no pixels without blocking.
+///
+/// # Panics
+///
+/// - If `framebuffer` was created for a different context.
+/// - If `rect` has a negative width or height.
+pub fn read_pixels(context: &Context, framebuffer: Option<&Framebuffer>, rect: Rect) -> Vec<u8> {
+    if let Some(framebuffer) = framebuffer {
+        assert!(
+            context.raw() == framebuffer.context(),
+            "Specified framebuffer's context does not match the context"
+        );
+    }
+    assert!(rect.width >= 0 && rect.height >= 0, "Rect must not have a negative size: {:?}", rect);
+
+    let name = framebuffer.map_or(FramebufferName::null(), Framebuffer::inner);
+
+    // Rows of RGBA pixels are always 4-byte aligned, so the default pack alignment is fine.
+    let mut pixels = vec![0u8; rect.width as usize * rect.height as usize * 4];
+    if pixels.is_empty() {
+        return pixels;
+    }
+
+    let _guard = ::context::ContextGuard::new(context.raw());
+    unsafe {
+        gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, name);
+        gl::read_pixels(
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            TextureFormat::Rgba,
+            TextureDataType::u8,
+            pixels.as_mut_ptr() as *mut (),
+        );
+        gl::bind_framebuffer(FramebufferTarget::ReadFramebuffer, FramebufferName::null());
+    }
+
+    pixels
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Indicates that `gl::gen_framebuffer()` failed to create a framebuffer object.
+    FailedToGenerateFramebuffer,
+}
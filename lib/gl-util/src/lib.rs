@@ -12,17 +12,24 @@
 extern crate bootstrap_rs as bootstrap;
 extern crate bootstrap_gl as gl;
 
+use buffer::StreamingBuffer;
 use context::{Context, ContextInner};
+use framebuffer::Framebuffer;
 use gl::*;
-use shader::Program;
-use std::mem;
+use pipeline::PipelineState;
+use shader::{Program, UniformType};
+use std::{cmp, mem, ptr};
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
 use std::rc::Rc;
-use texture::Texture2d;
+use texture::{Texture2d, TextureCube};
+use vertex::{AttribKind, Vertex, VertexAttrib};
 
+pub use framebuffer::blit;
 pub use gl::{
     AttributeLocation,
+    ClipDepth,
     Comparison,
     DestFactor,
     DrawMode,
@@ -33,9 +40,19 @@ pub use gl::{
     WindingOrder,
 };
 
+pub mod buffer;
+pub mod container;
 pub mod context;
+pub mod diagnostic;
+pub mod framebuffer;
+pub mod pipeline;
+pub mod preprocess;
+pub mod query;
+pub mod renderbuffer;
 pub mod shader;
 pub mod texture;
+#[macro_use]
+pub mod vertex;
 
 #[cfg(target_os="windows")]
 #[path="windows\\mod.rs"]
@@ -71,15 +88,34 @@ pub struct VertexArray {
     vertex_buffer_name: BufferName,
     index_buffer: Option<IndexBuffer>,
 
-    /// The number of primitive elments in the buffer.
+    /// The size of the vertex buffer's data, in bytes.
+    vertex_buffer_size: usize,
+
+    /// The size in bytes of each attribute declared with `set_attrib()` and friends.
+    ///
+    /// Declaring an attribute again replaces its size, so the sum of the sizes is the size of a
+    /// vertex. Used to determine how many vertices are in the buffer.
+    attrib_sizes: HashMap<AttributeLocation, usize>,
+
+    /// The number of vertices in the buffer, if the buffer was filled with typed vertex data.
     ///
-    /// Does not reflect the number of vertices in the buffer.
-    vertex_primitive_len: usize,
+    /// When this is `None` the vertex count is derived from `vertex_buffer_size` and
+    /// `attrib_sizes`.
+    vertex_len: Option<usize>,
 
-    /// The total number of primitive elements per vertex.
+    /// The ring buffer written by `stream_raw_data()`, once it has been used.
     ///
-    /// Used to can determine how many vertices are in the buffer.
-    elements_per_vertex: usize,
+    /// While this is `Some` the attributes read from the stream's current region instead of
+    /// from `vertex_buffer_name`.
+    vertex_stream: Option<StreamingBuffer<f32>>,
+
+    /// The ring buffer written by `stream_indices()`, once it has been used.
+    index_stream: Option<StreamingBuffer<u32>>,
+
+    /// Set once a `StreamingBuffer` couldn't be created, e.g. because `ARB_buffer_storage` isn't
+    /// supported. Streamed data is then uploaded by reallocating the regular buffers instead, and
+    /// both streams are empty.
+    stream_fallback: bool,
 
     context: Rc<RefCell<ContextInner>>,
 }
@@ -117,13 +153,88 @@ impl VertexArray {
             vertex_buffer_name: vertex_buffer_name,
             index_buffer: None,
 
-            vertex_primitive_len: vertex_data.len(),
-            elements_per_vertex: 0,
+            vertex_buffer_size: vertex_data.len() * mem::size_of::<f32>(),
+            attrib_sizes: HashMap::new(),
+            vertex_len: None,
+
+            vertex_stream: None,
+            index_stream: None,
+            stream_fallback: false,
 
             context: context_inner,
         }
     }
 
+    /// Creates a new VAO and vertex buffer from typed vertex data.
+    ///
+    /// All vertex attributes are configured automatically from the layout described by `V`, so
+    /// there's no need to call `set_attrib()`.
+    pub fn from_vertices<V: Vertex>(context: &Context, vertices: &[V]) -> VertexArray {
+        let mut vertex_array = VertexArray::new(context, &[]);
+        vertex_array.set_data(vertices);
+        vertex_array
+    }
+
+    /// Replaces the contents of the vertex buffer with typed vertex data.
+    ///
+    /// The vertex attributes are reconfigured to match the layout described by `V`. Any
+    /// attributes previously declared with `set_attrib()` that aren't part of `V` are left
+    /// enabled, so avoid mixing the two methods of declaring attributes.
+    pub fn set_data<V: Vertex>(&mut self, vertices: &[V]) {
+        self.buffer_vertices(vertices, BufferUsage::StaticDraw);
+    }
+
+    /// Replaces the contents of the vertex buffer with typed vertex data that will be replaced
+    /// again soon, e.g. every frame.
+    ///
+    /// Behaves like `set_data()`, but hints to the driver that the buffer is only drawn a few
+    /// times before its contents change.
+    pub fn stream_data<V: Vertex>(&mut self, vertices: &[V]) {
+        self.buffer_vertices(vertices, BufferUsage::StreamDraw);
+    }
+
+    fn buffer_vertices<V: Vertex>(&mut self, vertices: &[V], usage: BufferUsage) {
+        let stride = mem::size_of::<V>();
+        self.vertex_stream = None;
+
+        unsafe {
+            let mut context = self.context.borrow_mut();
+            let _guard = ::context::ContextGuard::new(context.raw());
+            context.bind_vertex_array(self.vertex_array_name);
+
+            gl::bind_buffer(BufferTarget::Array, self.vertex_buffer_name);
+            gl::buffer_data(BufferTarget::Array, vertices, usage);
+
+            for attrib in V::attribs() {
+                assert!(
+                    attrib.offset + attrib.size() <= stride,
+                    "Attrib at location {:?} extends past the end of the vertex",
+                    attrib.location,
+                );
+
+                attrib_pointer(attrib, stride);
+            }
+        }
+
+        self.vertex_buffer_size = vertices.len() * stride;
+        self.vertex_len = Some(vertices.len());
+    }
+
+    /// Returns the number of vertices in the vertex buffer.
+    pub fn vertex_len(&self) -> usize {
+        match self.vertex_len {
+            Some(vertex_len) => vertex_len,
+            None => {
+                let bytes_per_vertex = self.attrib_sizes.values().sum::<usize>();
+                if bytes_per_vertex == 0 {
+                    0
+                } else {
+                    self.vertex_buffer_size / bytes_per_vertex
+                }
+            },
+        }
+    }
+
     /// Creates a new VAO with the provided vertex and index data.
     pub fn with_index_buffer(context: &Context, vertex_data: &[f32], index_data: &[u32]) -> VertexArray {
         let mut vertex_array = VertexArray::new(context, vertex_data);
@@ -146,51 +257,321 @@ impl VertexArray {
         vertex_array.index_buffer = Some(IndexBuffer {
             name: index_buffer_name,
             primitive_len: index_data.len(),
+            offset: 0,
         });
 
         vertex_array
     }
 
+    /// Replaces the contents of the vertex buffer with raw `f32` data that will be replaced again
+    /// soon, e.g. every frame.
+    ///
+    /// The data is written to the next region of a `StreamingBuffer`, so updating it doesn't wait
+    /// for draws that are still reading the previous contents. If streaming buffers aren't
+    /// supported the vertex buffer is reallocated with a streaming usage hint instead.
+    ///
+    /// Attributes keep pointing into the previous data and no longer count towards
+    /// `vertex_len()`, so they must be declared again with `set_attrib()` and friends.
+    pub fn stream_raw_data(&mut self, vertex_data: &[f32]) {
+        let streamed = !self.stream_fallback
+            && write_stream(&mut self.vertex_stream, &self.context, BufferTarget::Array, vertex_data);
+
+        if !streamed {
+            self.disable_streaming();
+
+            unsafe {
+                let mut context = self.context.borrow_mut();
+                let _guard = ::context::ContextGuard::new(context.raw());
+                context.bind_vertex_array(self.vertex_array_name);
+
+                gl::bind_buffer(BufferTarget::Array, self.vertex_buffer_name);
+                gl::buffer_data(BufferTarget::Array, vertex_data, BufferUsage::StreamDraw);
+            }
+        }
+
+        self.vertex_buffer_size = vertex_data.len() * mem::size_of::<f32>();
+        self.attrib_sizes.clear();
+        self.vertex_len = None;
+    }
+
+    /// Replaces the contents of the index buffer with data that will be replaced again soon,
+    /// e.g. every frame.
+    ///
+    /// Like `stream_raw_data()`, the indices are written to the next region of a
+    /// `StreamingBuffer` when streaming buffers are supported. If the index stream can't be
+    /// created streaming is disabled, see `disable_streaming()`.
+    ///
+    /// # Panics
+    ///
+    /// - If the vertex array was created without an index buffer.
+    pub fn stream_indices(&mut self, index_data: &[u32]) {
+        assert!(self.index_buffer.is_some(), "Cannot stream indices without an index buffer");
+
+        let streamed = !self.stream_fallback && write_stream(
+            &mut self.index_stream,
+            &self.context,
+            BufferTarget::ElementArray,
+            index_data,
+        );
+        if !streamed {
+            self.disable_streaming();
+        }
+
+        let index_buffer = self.index_buffer.as_mut().unwrap();
+        let mut context = self.context.borrow_mut();
+        let _guard = ::context::ContextGuard::new(context.raw());
+
+        // The element array binding is part of the vertex array's state.
+        context.bind_vertex_array(self.vertex_array_name);
+
+        unsafe {
+            match self.index_stream {
+                Some(ref stream) => {
+                    gl::bind_buffer(BufferTarget::ElementArray, stream.buffer_name());
+                    index_buffer.offset = stream.region_offset();
+                },
+                None => {
+                    gl::bind_buffer(BufferTarget::ElementArray, index_buffer.name);
+                    gl::buffer_data(BufferTarget::ElementArray, index_data, BufferUsage::StreamDraw);
+                    index_buffer.offset = 0;
+                },
+            }
+        }
+
+        index_buffer.primitive_len = index_data.len();
+    }
+
+    /// Stops using `StreamingBuffer`s for streamed data, uploading it by reallocating the vertex
+    /// and index buffers instead.
+    ///
+    /// This happens automatically when a stream can't be created. Vertex data already written to
+    /// the vertex stream is copied into the vertex buffer, but attributes aren't moved over so
+    /// they must be declared again with `set_attrib()` and friends. Indices must be uploaded
+    /// again with `stream_indices()`.
+    pub fn disable_streaming(&mut self) {
+        self.stream_fallback = true;
+
+        if let Some(ref stream) = self.vertex_stream {
+            let mut context = self.context.borrow_mut();
+            let _guard = ::context::ContextGuard::new(context.raw());
+            context.bind_vertex_array(self.vertex_array_name);
+
+            unsafe {
+                gl::bind_buffer(BufferTarget::Array, self.vertex_buffer_name);
+                gl::buffer_data_raw(
+                    BufferTarget::Array,
+                    self.vertex_buffer_size as isize,
+                    ptr::null(),
+                    BufferUsage::StreamDraw,
+                );
+
+                gl::bind_buffer(BufferTarget::CopyRead, stream.buffer_name());
+                gl::copy_buffer_sub_data(
+                    BufferTarget::CopyRead,
+                    BufferTarget::Array,
+                    stream.region_offset() as isize,
+                    0,
+                    self.vertex_buffer_size as isize,
+                );
+                gl::bind_buffer(BufferTarget::CopyRead, BufferName::null());
+            }
+        }
+
+        // Streams borrow the context to unbind their buffers, so they're destroyed outside of the
+        // borrow above.
+        self.vertex_stream = None;
+        self.index_stream = None;
+    }
+
     /// Declares a vetex attribute within the vertex buffer.
+    ///
+    /// The attribute data is treated as `f32` elements. `layout` is specified in terms of `f32`
+    /// elements. Declaring an attribute at the same location again replaces the previous one.
     pub fn set_attrib(
         &mut self,
         attrib_location: AttributeLocation,
         layout: AttribLayout,
     ) {
-        assert!(
-            layout.elements <= 4,
-            "Layout elements must not be more than 4 (was actually {})",
-            layout.elements,
-        );
-        // TODO: Verify validity of layout?
+        self.set_attrib_typed(attrib_location, layout, GlType::Float, AttribKind::Float);
+    }
+
+    /// Declares an `i32` vertex attribute that is passed to the shader as integer data.
+    ///
+    /// `layout` is specified in terms of `i32` elements.
+    pub fn set_attrib_i32(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::Int, AttribKind::Integer);
+    }
+
+    /// Declares a `u32` vertex attribute that is passed to the shader as integer data.
+    ///
+    /// `layout` is specified in terms of `u32` elements.
+    pub fn set_attrib_u32(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::UnsignedInt, AttribKind::Integer);
+    }
+
+    /// Declares an `i16` vertex attribute that is passed to the shader as integer data.
+    ///
+    /// `layout` is specified in terms of `i16` elements.
+    pub fn set_attrib_i16(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::Short, AttribKind::Integer);
+    }
+
+    /// Declares a `u16` vertex attribute that is passed to the shader as integer data.
+    ///
+    /// `layout` is specified in terms of `u16` elements. Useful for data such as bone indices.
+    pub fn set_attrib_u16(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::UnsignedShort, AttribKind::Integer);
+    }
+
+    /// Declares an `i8` vertex attribute that is passed to the shader as integer data.
+    ///
+    /// `layout` is specified in terms of `i8` elements.
+    pub fn set_attrib_i8(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::Byte, AttribKind::Integer);
+    }
+
+    /// Declares a `u8` vertex attribute that is passed to the shader as integer data.
+    ///
+    /// `layout` is specified in terms of `u8` elements.
+    pub fn set_attrib_u8(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::UnsignedByte, AttribKind::Integer);
+    }
+
+    /// Declares an `i16` vertex attribute that is normalized to the range [-1, 1].
+    ///
+    /// `layout` is specified in terms of `i16` elements.
+    pub fn set_attrib_i16_normalized(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::Short, AttribKind::Normalized);
+    }
+
+    /// Declares a `u16` vertex attribute that is normalized to the range [0, 1].
+    ///
+    /// `layout` is specified in terms of `u16` elements.
+    pub fn set_attrib_u16_normalized(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::UnsignedShort, AttribKind::Normalized);
+    }
+
+    /// Declares an `i8` vertex attribute that is normalized to the range [-1, 1].
+    ///
+    /// `layout` is specified in terms of `i8` elements.
+    pub fn set_attrib_i8_normalized(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::Byte, AttribKind::Normalized);
+    }
+
+    /// Declares a `u8` vertex attribute that is normalized to the range [0, 1].
+    ///
+    /// `layout` is specified in terms of `u8` elements. Useful for colors packed as 4 bytes.
+    pub fn set_attrib_u8_normalized(&mut self, attrib_location: AttributeLocation, layout: AttribLayout) {
+        self.set_attrib_typed(attrib_location, layout, GlType::UnsignedByte, AttribKind::Normalized);
+    }
+
+    fn set_attrib_typed(
+        &mut self,
+        attrib_location: AttributeLocation,
+        layout: AttribLayout,
+        gl_type: GlType,
+        kind: AttribKind,
+    ) {
         // TODO: Verify that `attrib_location` is valid? How would we even do that?
 
-        // Update the total number of elements per vertex.
-        self.elements_per_vertex += layout.elements;
+        // Streamed data is read from the current region of the stream.
+        let (buffer_name, buffer_offset) = match self.vertex_stream {
+            Some(ref stream) => (stream.buffer_name(), stream.region_offset()),
+            None => (self.vertex_buffer_name, 0),
+        };
+
+        let element_size = vertex::gl_type_size(gl_type);
+        let attrib = VertexAttrib {
+            location: attrib_location,
+            elements: layout.elements,
+            offset: buffer_offset + layout.offset * element_size,
+            gl_type: gl_type,
+            kind: kind,
+        };
+
+        // Update the total size of each vertex.
+        self.attrib_sizes.insert(attrib_location, attrib.size());
 
         unsafe {
             let mut context = self.context.borrow_mut();
             let _guard = ::context::ContextGuard::new(context.raw());
             context.bind_vertex_array(self.vertex_array_name);
+            gl::bind_buffer(BufferTarget::Array, buffer_name);
 
-            gl::enable_vertex_attrib_array(attrib_location);
-            gl::vertex_attrib_pointer(
-                attrib_location,
-                layout.elements as i32,
-                GlType::Float,
-                False,
-                (layout.stride * mem::size_of::<f32>()) as i32, // TODO: Correctly handle non-f32
-                layout.offset * mem::size_of::<f32>(), // attrib data types.
-            );
+            attrib_pointer(attrib, layout.stride * element_size);
         }
     }
 }
 
+/// Writes `data` to the next region of `stream`, creating the stream if there isn't one yet or
+/// replacing it with a larger one if `data` doesn't fit in a region.
+///
+/// Returns `false` if a stream couldn't be created, in which case `stream` is left empty.
+fn write_stream<T: Copy>(
+    stream: &mut Option<StreamingBuffer<T>>,
+    context: &Rc<RefCell<ContextInner>>,
+    target: BufferTarget,
+    data: &[T],
+) -> bool {
+    let fits = stream.as_ref().map_or(false, |stream| stream.region_len() >= data.len());
+    if fits {
+        // Every draw reading the current region was issued before this update, so the region can
+        // be fenced before moving on to the next one.
+        stream.as_mut().unwrap().fence_region();
+    } else {
+        let region_len = cmp::max(data.len(), 1).next_power_of_two();
+        *stream = StreamingBuffer::from_inner(context.clone(), target, region_len).ok();
+    }
+
+    match *stream {
+        Some(ref mut stream) => {
+            stream.next_region()[..data.len()].copy_from_slice(data);
+            true
+        },
+        None => false,
+    }
+}
+
+/// Enables `attrib` and points it into the buffer currently bound to `BufferTarget::Array`.
+///
+/// `stride` is in bytes. Must be called with the vertex array bound.
+unsafe fn attrib_pointer(attrib: VertexAttrib, stride: usize) {
+    assert!(
+        attrib.elements <= 4,
+        "Attrib elements must not be more than 4 (was actually {})",
+        attrib.elements,
+    );
+
+    gl::enable_vertex_attrib_array(attrib.location);
+    match attrib.kind {
+        AttribKind::Float | AttribKind::Normalized => gl::vertex_attrib_pointer(
+            attrib.location,
+            attrib.elements as i32,
+            attrib.gl_type,
+            (attrib.kind == AttribKind::Normalized).into(),
+            stride as i32,
+            attrib.offset,
+        ),
+        AttribKind::Integer => gl::vertex_attrib_i_pointer(
+            attrib.location,
+            attrib.elements as i32,
+            attrib.gl_type,
+            stride as i32,
+            attrib.offset,
+        ),
+    }
+}
+
 impl Drop for VertexArray {
     fn drop(&mut self) {
+        // Streams borrow the context to unbind their buffers, so they're destroyed first.
+        self.index_stream.take();
+        self.vertex_stream.take();
+
         let mut context = self.context.borrow_mut();
         let _guard = ::context::ContextGuard::new(context.raw());
         let buffers = &mut [self.vertex_buffer_name, self.index_buffer.clone().map_or(BufferName::null(), |buf| buf.name)];
+
         unsafe {
             gl::delete_vertex_arrays(1, &mut self.vertex_array_name);
             gl::delete_buffers(2, buffers.as_ptr());
@@ -209,6 +590,111 @@ struct IndexBuffer {
     /// This does not reflect number of primitive shapes described by the index buffer, e.g. an
     /// index length of 3 may only describe a single triangle.
     primitive_len: usize,
+
+    /// The offset in bytes of the first index, which is the start of the current region when the
+    /// indices are streamed.
+    offset: usize,
+}
+
+/// A buffer of per-instance attribute data used when drawing multiple instances.
+///
+/// Unlike vertex data, instance data isn't tied to a single `VertexArray`. It's attached to the
+/// vertex array for the duration of a draw with `DrawBuilder::instances()`, so the same mesh can
+/// be drawn with several different sets of instances. The attributes described by the instance
+/// type advance once per instance rather than once per vertex, so their locations must not
+/// overlap those of the vertex array's attributes.
+#[derive(Debug)]
+pub struct InstanceBuffer {
+    buffer_name: BufferName,
+    attribs: Vec<VertexAttrib>,
+    stride: usize,
+
+    /// The number of instances in the buffer.
+    len: usize,
+
+    context: Rc<RefCell<ContextInner>>,
+}
+
+impl InstanceBuffer {
+    /// Creates a new, empty instance buffer.
+    pub fn new(context: &Context) -> InstanceBuffer {
+        let context_inner = context.inner();
+
+        let buffer_name = {
+            let context = context_inner.borrow();
+            let _guard = ::context::ContextGuard::new(context.raw());
+            gl::gen_buffer().expect("Failed to create buffer object")
+        };
+
+        InstanceBuffer {
+            buffer_name: buffer_name,
+            attribs: Vec::new(),
+            stride: 0,
+            len: 0,
+
+            context: context_inner,
+        }
+    }
+
+    /// Replaces the contents of the buffer with typed instance data.
+    ///
+    /// The buffer is expected to be replaced frequently, e.g. every frame.
+    pub fn set_data<V: Vertex>(&mut self, instances: &[V]) {
+        let stride = mem::size_of::<V>();
+        let attribs = V::attribs();
+        for attrib in &attribs {
+            assert!(
+                attrib.offset + attrib.size() <= stride,
+                "Attrib at location {:?} extends past the end of the instance",
+                attrib.location,
+            );
+        }
+
+        unsafe {
+            let context = self.context.borrow();
+            let _guard = ::context::ContextGuard::new(context.raw());
+
+            gl::bind_buffer(BufferTarget::Array, self.buffer_name);
+            gl::buffer_data(BufferTarget::Array, instances, BufferUsage::StreamDraw);
+        }
+
+        self.attribs = attribs;
+        self.stride = stride;
+        self.len = instances.len();
+    }
+
+    /// Returns the number of instances in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Enables the instance attributes on the currently bound vertex array.
+    unsafe fn attach(&self) {
+        gl::bind_buffer(BufferTarget::Array, self.buffer_name);
+        for &attrib in &self.attribs {
+            attrib_pointer(attrib, self.stride);
+            gl::vertex_attrib_divisor(attrib.location, 1);
+        }
+    }
+
+    /// Disables the instance attributes on the currently bound vertex array, so that the vertex
+    /// array doesn't keep referencing the buffer after the draw.
+    unsafe fn detach(&self) {
+        for attrib in &self.attribs {
+            gl::vertex_attrib_divisor(attrib.location, 0);
+            gl::disable_vertex_attrib_array(attrib.location);
+        }
+    }
+}
+
+impl Drop for InstanceBuffer {
+    fn drop(&mut self) {
+        let context = self.context.borrow();
+        let _guard = ::context::ContextGuard::new(context.raw());
+        unsafe {
+            gl::delete_buffers(1, &self.buffer_name);
+        }
+    }
 }
 
 /// A configuration object for specifying all of the various configurable options for a draw call.
@@ -216,14 +702,15 @@ struct IndexBuffer {
 pub struct DrawBuilder<'a> {
     vertex_array: &'a VertexArray,
     draw_mode: DrawMode,
-    polygon_mode: Option<PolygonMode>,
-    program: Option<&'a Program>,
-    cull: Option<Face>,
-    depth_test: Option<Comparison>,
-    winding_order: WindingOrder,
-    blend: (SourceFactor, DestFactor),
+    framebuffer: Option<&'a Framebuffer>,
+    pipeline: PipelineState<'a>,
     uniforms: HashMap<UniformLocation, UniformValue<'a>>,
 
+    /// The range of indices (or vertices, if there's no index buffer) to draw, as `(start, count)`.
+    index_range: Option<(usize, usize)>,
+    base_vertex: Option<i32>,
+    instances: Option<&'a InstanceBuffer>,
+
     context: Rc<RefCell<ContextInner>>,
 }
 
@@ -234,44 +721,87 @@ impl<'a> DrawBuilder<'a> {
         DrawBuilder {
             vertex_array: vertex_array,
             draw_mode: draw_mode,
-            polygon_mode: None,
-            program: None,
-            cull: None,
-            depth_test: None,
-            winding_order: WindingOrder::default(),
-            blend: Default::default(),
+            framebuffer: None,
+            pipeline: PipelineState::new(context),
             uniforms: HashMap::new(),
 
+            index_range: None,
+            base_vertex: None,
+            instances: None,
+
             context: context.inner(),
         }
     }
 
-    pub fn polygon_mode(&mut self, polygon_mode: PolygonMode) -> &mut DrawBuilder<'a> {
-        self.polygon_mode = Some(polygon_mode);
+    /// Draws to `framebuffer` instead of the default framebuffer.
+    pub fn framebuffer(&mut self, framebuffer: &'a Framebuffer) -> &mut DrawBuilder<'a> {
+        assert!(
+            self.context.borrow().raw() == framebuffer.context(),
+            "Specified framebuffer's context does not match draw builder's context"
+        );
+        self.framebuffer = Some(framebuffer);
         self
     }
 
-    pub fn program(&mut self, program: &'a Program) -> &mut DrawBuilder<'a> {
+    /// Replaces all pipeline state for the draw with `pipeline`.
+    ///
+    /// Any state set on the builder before calling `pipeline()` is discarded, and any state set
+    /// afterwards modifies the builder's copy of the pipeline state.
+    pub fn pipeline(&mut self, pipeline: &PipelineState<'a>) -> &mut DrawBuilder<'a> {
         assert!(
-            self.context.borrow().raw() == program.context,
-            "Specified program's context does not match draw builder's context"
+            self.context.borrow().raw() == pipeline.context(),
+            "Specified pipeline's context does not match draw builder's context"
         );
-        self.program = Some(program);
+        self.pipeline = pipeline.clone();
+        self
+    }
+
+    pub fn polygon_mode(&mut self, polygon_mode: PolygonMode) -> &mut DrawBuilder<'a> {
+        self.pipeline.polygon_mode(polygon_mode);
+        self
+    }
+
+    /// Offsets the depth of filled polygons, see `PipelineState::polygon_offset()`.
+    pub fn polygon_offset(&mut self, factor: f32, units: f32) -> &mut DrawBuilder<'a> {
+        self.pipeline.polygon_offset(factor, units);
+        self
+    }
+
+    pub fn program(&mut self, program: &'a Program) -> &mut DrawBuilder<'a> {
+        self.pipeline.program(program);
         self
     }
 
     pub fn cull(&mut self, face: Face) -> &mut DrawBuilder<'a> {
-        self.cull = Some(face);
+        self.pipeline.cull(face);
         self
     }
 
     pub fn depth_test(&mut self, comparison: Comparison) -> &mut DrawBuilder<'a> {
-        self.depth_test = Some(comparison);
+        self.pipeline.depth_test(comparison);
+        self
+    }
+
+    /// Enables or disables writing to the depth buffer.
+    ///
+    /// Depth writes are enabled by default. Disabling depth writes while leaving the depth test
+    /// enabled is useful for drawing transparent geometry, which should be occluded by opaque
+    /// geometry without occluding anything itself.
+    pub fn depth_write(&mut self, enabled: bool) -> &mut DrawBuilder<'a> {
+        self.pipeline.depth_write(enabled);
+        self
+    }
+
+    /// Sets the mapping of depth values from normalized device coordinates to window coordinates.
+    ///
+    /// Both `near` and `far` are clamped to [0, 1]. The default is `(0.0, 1.0)`.
+    pub fn depth_range(&mut self, near: f64, far: f64) -> &mut DrawBuilder<'a> {
+        self.pipeline.depth_range(near, far);
         self
     }
 
     pub fn winding(&mut self, winding_order: WindingOrder) -> &mut DrawBuilder<'a> {
-        self.winding_order = winding_order;
+        self.pipeline.winding(winding_order);
         self
     }
 
@@ -280,7 +810,52 @@ impl<'a> DrawBuilder<'a> {
         source_factor: SourceFactor,
         dest_factor: DestFactor
     ) -> &mut DrawBuilder<'a> {
-        self.blend = (source_factor, dest_factor);
+        self.pipeline.blend(source_factor, dest_factor);
+        self
+    }
+
+    /// Restricts the draw to a sub-range of the index buffer.
+    ///
+    /// `start` is the first index to draw, and `count` is the number of indices to draw. If the
+    /// vertex array doesn't have an index buffer then `start` and `count` refer to vertices
+    /// instead. This allows multiple submeshes stored in a single vertex array to be drawn
+    /// separately.
+    ///
+    /// # Panics
+    ///
+    /// - When drawing, if `start + count` is larger than the number of indices (or vertices) in
+    ///   the vertex array.
+    pub fn index_range(&mut self, start: usize, count: usize) -> &mut DrawBuilder<'a> {
+        self.index_range = Some((start, count));
+        self
+    }
+
+    /// Sets a value that is added to each index before it is used to read vertex data.
+    ///
+    /// Combined with `index_range()` this allows submeshes with their own 0-based indices to be
+    /// packed into a single vertex buffer. If the vertex array doesn't have an index buffer then
+    /// `base_vertex` offsets the first vertex drawn.
+    pub fn base_vertex(&mut self, base_vertex: i32) -> &mut DrawBuilder<'a> {
+        self.base_vertex = Some(base_vertex);
+        self
+    }
+
+    /// Draws one instance of the vertices for each instance in `instances`, all in a single draw
+    /// call.
+    ///
+    /// Shaders read the current instance's attributes from the locations described by the
+    /// instance type, and can read the index of the instance from `gl_InstanceID`.
+    ///
+    /// # Panics
+    ///
+    /// - If `instances` doesn't come from the same context as the draw builder.
+    /// - When drawing, if a base vertex was also set with `base_vertex()`.
+    pub fn instances(&mut self, instances: &'a InstanceBuffer) -> &mut DrawBuilder<'a> {
+        assert!(
+            self.context.borrow().raw() == instances.context.borrow().raw(),
+            "Specified instance buffer's context does not match draw builder's context"
+        );
+        self.instances = Some(instances);
         self
     }
 
@@ -290,6 +865,10 @@ impl<'a> DrawBuilder<'a> {
     /// so it is always safe to speculatively set uniform values even if the shader program may
     /// not use that uniform.
     ///
+    /// In debug builds a `value` whose type doesn't match the type of the uniform declared in
+    /// the shader is reported on stdout and isn't set. Use `try_uniform()` to handle the mismatch
+    /// instead.
+    ///
     /// # Panics
     ///
     /// - If the program has not been set using `program()`.
@@ -299,48 +878,68 @@ impl<'a> DrawBuilder<'a> {
         value: T
     ) -> &mut DrawBuilder<'a>
         where T: Into<UniformValue<'a>>
+    {
+        match self.try_uniform(name, value) {
+            Ok(_) | Err(UniformError::NotFound { .. }) => {},
+            Err(error) => println!("WARNING: {}", error),
+        }
+
+        self
+    }
+
+    /// Sets the value of a uniform variable, returning an error if it can't be set.
+    ///
+    /// In debug builds the type of `value` is checked against the type of the uniform reported
+    /// by the driver when the program was linked, so that e.g. passing a `vec3` to a `mat4`
+    /// uniform is reported rather than silently producing garbage. The check is skipped in
+    /// release builds.
+    ///
+    /// # Panics
+    ///
+    /// - If the program has not been set using `program()`.
+    pub fn try_uniform<T>(
+        &mut self,
+        name: &str,
+        value: T
+    ) -> Result<&mut DrawBuilder<'a>, UniformError>
+        where T: Into<UniformValue<'a>>
     {
         let value = value.into();
 
         let program =
-            self.program.expect("Cannot set a uniform without a shader program");
+            self.pipeline.get_program().expect("Cannot set a uniform without a shader program");
 
-        // TODO: This checking is bad? Or maybe not? I don't remember.
         let uniform_location = match program.get_uniform_location(name) {
             Some(location) => location,
-            None => return self,
+            None => return Err(UniformError::NotFound { name: String::from(name) }),
         };
 
+        if cfg!(debug_assertions) {
+            let uniform_type = program.uniform_info(name).and_then(|info| info.uniform_type);
+            if let Some(uniform_type) = uniform_type {
+                if !value.is_compatible_with(uniform_type) {
+                    return Err(UniformError::TypeMismatch {
+                        name: String::from(name),
+                        expected: uniform_type,
+                        actual: value.type_name(),
+                    });
+                }
+            }
+        }
+
         // Add uniform to the uniform map.
         self.uniforms.insert(uniform_location, value);
 
-        self
+        Ok(self)
     }
 
     pub fn draw(&mut self) {
         let mut context = self.context.borrow_mut();
         let _guard = ::context::ContextGuard::new(context.raw());
 
-        context.polygon_mode(self.polygon_mode.unwrap_or_default());
-        context.use_program(self.program.map(Program::inner));
-
-        if let Some(face) = self.cull {
-            context.enable_server_cull(true);
-            context.cull_mode(face);
-            context.winding_order(self.winding_order);
-        } else {
-            context.enable_server_cull(false);
-        }
-
-        if let Some(depth_test) = self.depth_test {
-            context.enable_server_depth_test(true);
-            context.depth_test(depth_test);
-        } else {
-            context.enable_server_depth_test(false);
-        }
-
-        let (source_factor, dest_factor) = self.blend;
-        context.blend(source_factor, dest_factor);
+        let framebuffer_name = self.framebuffer.map_or(FramebufferName::null(), Framebuffer::inner);
+        context.bind_framebuffer(framebuffer_name);
+        context.bind_pipeline(self.pipeline.key());
 
         let mut active_texture = 0;
         // Apply uniforms.
@@ -354,20 +953,90 @@ impl<'a> DrawBuilder<'a> {
             // first.
             context.bind_vertex_array(self.vertex_array.vertex_array_name);
 
+            if let Some(instances) = self.instances {
+                assert!(
+                    self.base_vertex.is_none(),
+                    "Base vertex is not supported when drawing multiple instances",
+                );
+
+                instances.attach();
+            }
+            let instance_count = self.instances.map(InstanceBuffer::len);
+
             if let Some(indices) = self.vertex_array.index_buffer.as_ref() {
-                gl::draw_elements(
-                    self.draw_mode,
-                    indices.primitive_len as i32,
-                    IndexType::UnsignedInt,
-                    0,
+                let (start, count) = self.index_range.unwrap_or((0, indices.primitive_len));
+                assert!(
+                    start + count <= indices.primitive_len,
+                    "Index range {}..{} is out of bounds for index buffer with {} indices",
+                    start,
+                    start + count,
+                    indices.primitive_len,
                 );
+
+                let offset = indices.offset + start * mem::size_of::<u32>();
+
+                // Use `draw_range_elements()` when drawing a sub-range, since knowing the bounds of
+                // the vertex buffer gives the driver a chance to optimize the draw. We don't know
+                // the range of index values when a base vertex is used, so in that case we can't.
+                let vertex_len = self.vertex_array.vertex_len();
+                match self.base_vertex {
+                    None if instance_count.is_some() => gl::draw_elements_instanced(
+                        self.draw_mode,
+                        count as i32,
+                        IndexType::UnsignedInt,
+                        offset,
+                        instance_count.unwrap() as i32,
+                    ),
+                    Some(base_vertex) => gl::draw_elements_base_vertex(
+                        self.draw_mode,
+                        count as i32,
+                        IndexType::UnsignedInt,
+                        offset,
+                        base_vertex,
+                    ),
+                    None if self.index_range.is_some() && vertex_len > 0 => gl::draw_range_elements(
+                        self.draw_mode,
+                        0,
+                        vertex_len as u32 - 1,
+                        count as i32,
+                        IndexType::UnsignedInt,
+                        offset,
+                    ),
+                    None => gl::draw_elements(
+                        self.draw_mode,
+                        count as i32,
+                        IndexType::UnsignedInt,
+                        offset,
+                    ),
+                }
             } else {
-                let vertex_len = self.vertex_array.vertex_primitive_len / self.vertex_array.elements_per_vertex;
-                gl::draw_arrays(
-                    self.draw_mode,
-                    0,
-                    vertex_len as i32,
+                let vertex_len = self.vertex_array.vertex_len();
+                let (start, count) = self.index_range.unwrap_or((0, vertex_len));
+                assert!(
+                    start + count <= vertex_len,
+                    "Vertex range {}..{} is out of bounds for vertex array with {} vertices",
+                    start,
+                    start + count,
+                    vertex_len,
                 );
+
+                match instance_count {
+                    Some(instance_count) => gl::draw_arrays_instanced(
+                        self.draw_mode,
+                        start as i32,
+                        count as i32,
+                        instance_count as i32,
+                    ),
+                    None => gl::draw_arrays(
+                        self.draw_mode,
+                        start as i32 + self.base_vertex.unwrap_or(0),
+                        count as i32,
+                    ),
+                }
+            }
+
+            if let Some(instances) = self.instances {
+                instances.detach();
             }
         }
     }
@@ -421,6 +1090,17 @@ impl<'a> DrawBuilder<'a> {
                 },
                 _ => panic!("Unsupported matrix data length: {}", matrix.data.len()),
             },
+            UniformValue::Matrix4v(ref matrix) => unsafe {
+                assert!(
+                    matrix.data.len() % 16 == 0,
+                    "Matrix array data length must be a multiple of 16, got {}",
+                    matrix.data.len());
+                gl::uniform_matrix_f32x4v(
+                    location,
+                    (matrix.data.len() / 16) as i32,
+                    matrix.transpose.into(),
+                    matrix.data.as_ptr())
+            },
             UniformValue::Texture(texture) => {
                 unsafe {
                     texture::set_active_texture(*active_texture as u32);
@@ -428,6 +1108,15 @@ impl<'a> DrawBuilder<'a> {
                     gl::uniform_i32x1(location, *active_texture);
                 }
 
+                *active_texture += 1;
+            }
+            UniformValue::TextureCube(texture) => {
+                unsafe {
+                    texture::set_active_texture(*active_texture as u32);
+                    gl::bind_texture(TextureBindTarget::CubeMap, texture.inner());
+                    gl::uniform_i32x1(location, *active_texture);
+                }
+
                 *active_texture += 1;
             }
         }
@@ -448,7 +1137,73 @@ pub enum UniformValue<'a> {
     I32x1v(&'a [i32]),
     U32(u32),
     Matrix(GlMatrix<'a>),
+
+    /// An array of 4x4 matrices, with 16 elements of `data` for each matrix.
+    Matrix4v(GlMatrix<'a>),
+
     Texture(&'a Texture2d),
+    TextureCube(&'a TextureCube),
+}
+
+impl<'a> UniformValue<'a> {
+    /// Returns `true` if the value can be used to set a uniform of type `uniform_type`.
+    fn is_compatible_with(&self, uniform_type: UniformType) -> bool {
+        match *self {
+            UniformValue::F32(_) | UniformValue::F32x1v(_) => uniform_type == UniformType::Float,
+            UniformValue::F32x2(_) => uniform_type == UniformType::FloatVec2,
+            UniformValue::F32x3(_) | UniformValue::F32x3v(_) => {
+                uniform_type == UniformType::FloatVec3
+            },
+            UniformValue::F32x4(_) | UniformValue::F32x4v(_) => {
+                uniform_type == UniformType::FloatVec4
+            },
+
+            // Booleans and samplers are set with the integer functions.
+            UniformValue::I32(_) | UniformValue::I32x1v(_) => {
+                uniform_type == UniformType::Int
+                    || uniform_type == UniformType::Bool
+                    || uniform_type.is_sampler()
+            },
+            UniformValue::U32(_) => {
+                uniform_type == UniformType::UnsignedInt || uniform_type == UniformType::Bool
+            },
+            UniformValue::Matrix(ref matrix) => match matrix.data.len() {
+                16 => uniform_type == UniformType::FloatMat4,
+                9 => uniform_type == UniformType::FloatMat3,
+                _ => false,
+            },
+            UniformValue::Matrix4v(_) => uniform_type == UniformType::FloatMat4,
+            UniformValue::Texture(_) => {
+                uniform_type == UniformType::Sampler2d
+                    || uniform_type == UniformType::Sampler2dShadow
+            },
+            UniformValue::TextureCube(_) => uniform_type == UniformType::SamplerCube,
+        }
+    }
+
+    /// Returns the GLSL name of the type the value would be used for, for error messages.
+    fn type_name(&self) -> &'static str {
+        match *self {
+            UniformValue::F32(_) => "float",
+            UniformValue::F32x2(_) => "vec2",
+            UniformValue::F32x3(_) => "vec3",
+            UniformValue::F32x4(_) => "vec4",
+            UniformValue::F32x1v(_) => "float[]",
+            UniformValue::F32x3v(_) => "vec3[]",
+            UniformValue::F32x4v(_) => "vec4[]",
+            UniformValue::I32(_) => "int",
+            UniformValue::I32x1v(_) => "int[]",
+            UniformValue::U32(_) => "uint",
+            UniformValue::Matrix(ref matrix) => match matrix.data.len() {
+                16 => "mat4",
+                9 => "mat3",
+                _ => "matrix",
+            },
+            UniformValue::Matrix4v(_) => "mat4[]",
+            UniformValue::Texture(_) => "sampler2D",
+            UniformValue::TextureCube(_) => "samplerCube",
+        }
+    }
 }
 
 impl<'a> From<f32> for UniformValue<'a> {
@@ -547,6 +1302,49 @@ impl<'a> From<&'a Texture2d> for UniformValue<'a> {
     }
 }
 
+impl<'a> From<&'a TextureCube> for UniformValue<'a> {
+    fn from(from: &'a TextureCube) -> UniformValue<'a> {
+        UniformValue::TextureCube(from)
+    }
+}
+
+/// An error from setting a uniform value with `DrawBuilder::try_uniform()`.
+#[derive(Debug, Clone)]
+pub enum UniformError {
+    /// Indicates that the program has no active uniform with the given name.
+    ///
+    /// Uniforms that aren't used by the shader may be optimized out by the driver, so this isn't
+    /// necessarily a mistake.
+    NotFound {
+        name: String,
+    },
+
+    /// Indicates that the type of the value doesn't match the type of the uniform declared in
+    /// the shader.
+    TypeMismatch {
+        name: String,
+        expected: UniformType,
+        actual: &'static str,
+    },
+}
+
+impl Display for UniformError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            UniformError::NotFound { ref name } => {
+                write!(formatter, "No active uniform named \"{}\"", name)
+            },
+            UniformError::TypeMismatch { ref name, expected, actual } => write!(
+                formatter,
+                "Uniform \"{}\" is declared as {:?} but was given a {} value",
+                name,
+                expected,
+                actual,
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GlMatrix<'a> {
     pub data: &'a [f32],
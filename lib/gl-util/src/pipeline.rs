@@ -0,0 +1,186 @@
+use context::{Context, ContextInner};
+use gl::*;
+use shader::Program;
+
+/// The complete set of fixed-function and program state used when drawing.
+///
+/// A `PipelineState` is meant to be created once and reused for every draw that shares the same
+/// state. The context remembers the last pipeline state that was bound, so drawing many objects
+/// with the same pipeline only sets the GL state once. Use `Context::bind_pipeline()` to bind the
+/// state up front before a batch of draws, and `DrawBuilder::pipeline()` to draw with it.
+#[derive(Debug, Clone)]
+pub struct PipelineState<'a> {
+    program: Option<&'a Program>,
+    polygon_mode: Option<PolygonMode>,
+    polygon_offset: Option<(f32, f32)>,
+    cull: Option<Face>,
+    winding_order: WindingOrder,
+    depth_test: Option<Comparison>,
+    depth_write: bool,
+    depth_range: (f64, f64),
+    blend: (SourceFactor, DestFactor),
+
+    context: ::gl::Context,
+}
+
+impl<'a> PipelineState<'a> {
+    /// Creates a new pipeline state with the default GL state and no program.
+    pub fn new(context: &Context) -> PipelineState<'a> {
+        PipelineState {
+            program: None,
+            polygon_mode: None,
+            polygon_offset: None,
+            cull: None,
+            winding_order: WindingOrder::default(),
+            depth_test: None,
+            depth_write: true,
+            depth_range: (0.0, 1.0),
+            blend: Default::default(),
+
+            context: context.raw(),
+        }
+    }
+
+    pub fn program(&mut self, program: &'a Program) -> &mut PipelineState<'a> {
+        assert!(
+            self.context == program.context,
+            "Specified program's context does not match pipeline state's context"
+        );
+        self.program = Some(program);
+        self
+    }
+
+    pub fn polygon_mode(&mut self, polygon_mode: PolygonMode) -> &mut PipelineState<'a> {
+        self.polygon_mode = Some(polygon_mode);
+        self
+    }
+
+    /// Offsets the depth of filled polygons by `factor * DZ + r * units`.
+    ///
+    /// `DZ` is the change in depth across the polygon and `r` is the smallest resolvable depth
+    /// difference. Useful for drawing decals and wireframe overlays on top of filled geometry
+    /// without z-fighting. Polygon offset is disabled by default.
+    pub fn polygon_offset(&mut self, factor: f32, units: f32) -> &mut PipelineState<'a> {
+        self.polygon_offset = Some((factor, units));
+        self
+    }
+
+    pub fn cull(&mut self, face: Face) -> &mut PipelineState<'a> {
+        self.cull = Some(face);
+        self
+    }
+
+    pub fn winding(&mut self, winding_order: WindingOrder) -> &mut PipelineState<'a> {
+        self.winding_order = winding_order;
+        self
+    }
+
+    pub fn depth_test(&mut self, comparison: Comparison) -> &mut PipelineState<'a> {
+        self.depth_test = Some(comparison);
+        self
+    }
+
+    /// Enables or disables writing to the depth buffer.
+    ///
+    /// Depth writes are enabled by default.
+    pub fn depth_write(&mut self, enabled: bool) -> &mut PipelineState<'a> {
+        self.depth_write = enabled;
+        self
+    }
+
+    /// Sets the mapping of depth values from normalized device coordinates to window coordinates.
+    ///
+    /// Both `near` and `far` are clamped to [0, 1]. The default is `(0.0, 1.0)`.
+    pub fn depth_range(&mut self, near: f64, far: f64) -> &mut PipelineState<'a> {
+        self.depth_range = (near, far);
+        self
+    }
+
+    pub fn blend(
+        &mut self,
+        source_factor: SourceFactor,
+        dest_factor: DestFactor
+    ) -> &mut PipelineState<'a> {
+        self.blend = (source_factor, dest_factor);
+        self
+    }
+
+    /// Returns the program used by the pipeline, if one has been set.
+    pub fn get_program(&self) -> Option<&'a Program> {
+        self.program
+    }
+
+    /// Returns a snapshot of the state that doesn't borrow the program.
+    ///
+    /// Used by the context to determine if the pipeline is already bound.
+    pub(crate) fn key(&self) -> PipelineKey {
+        PipelineKey {
+            program: self.program.map(Program::inner),
+            polygon_mode: self.polygon_mode.unwrap_or_default(),
+            polygon_offset: self.polygon_offset,
+            cull: self.cull,
+            winding_order: self.winding_order,
+            depth_test: self.depth_test,
+            depth_write: self.depth_write,
+            depth_range: self.depth_range,
+            blend: self.blend,
+        }
+    }
+
+    pub(crate) fn context(&self) -> ::gl::Context {
+        self.context
+    }
+}
+
+/// A snapshot of a `PipelineState` used to track the currently bound state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PipelineKey {
+    program: Option<ProgramObject>,
+    polygon_mode: PolygonMode,
+    polygon_offset: Option<(f32, f32)>,
+    cull: Option<Face>,
+    winding_order: WindingOrder,
+    depth_test: Option<Comparison>,
+    depth_write: bool,
+    depth_range: (f64, f64),
+    blend: (SourceFactor, DestFactor),
+}
+
+impl PipelineKey {
+    /// Applies the pipeline state to the context.
+    ///
+    /// The context must be current.
+    pub(crate) fn apply(&self, context: &mut ContextInner) {
+        context.polygon_mode(self.polygon_mode);
+        context.use_program(self.program);
+
+        if let Some((factor, units)) = self.polygon_offset {
+            context.enable_server_polygon_offset_fill(true);
+            context.polygon_offset(factor, units);
+        } else {
+            context.enable_server_polygon_offset_fill(false);
+        }
+
+        if let Some(face) = self.cull {
+            context.enable_server_cull(true);
+            context.cull_mode(face);
+            context.winding_order(self.winding_order);
+        } else {
+            context.enable_server_cull(false);
+        }
+
+        if let Some(depth_test) = self.depth_test {
+            context.enable_server_depth_test(true);
+            context.depth_test(depth_test);
+        } else {
+            context.enable_server_depth_test(false);
+        }
+
+        context.depth_write(self.depth_write);
+        let (near, far) = self.depth_range;
+        context.depth_range(near, far);
+
+        let (source_factor, dest_factor) = self.blend;
+        context.blend(source_factor, dest_factor);
+    }
+}
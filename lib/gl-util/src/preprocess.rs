@@ -0,0 +1,567 @@
+//! Shader source preprocessing.
+//!
+//! GLSL has no way to share code between shaders, so large shaders end up duplicating lighting
+//! functions, vertex declarations, and the like. `preprocess()` resolves `#include "file"`
+//! directives before the source is handed to the driver, and records where each line of the
+//! output came from so that line numbers in compile errors can be mapped back to the original
+//! files.
+//!
+//! ```ignore
+//! let mut files = VirtualFileSystem::new();
+//! files.add_file("lighting.glsl", LIGHTING_SOURCE);
+//!
+//! let shader = Shader::with_includes(
+//!     &context,
+//!     "forward.frag",
+//!     source,
+//!     ShaderType::Fragment,
+//!     &files,
+//! )?;
+//! ```
+//!
+//! `ShaderSource` handles the `#version` line, so that one shader can target whichever GLSL
+//! version the current context supports. Optional features are exposed to the shader as
+//! `HAS_*` macros:
+//!
+//! ```ignore
+//! let source = ShaderSource::for_context(FORWARD_SOURCE, &context).build();
+//! let shader = Shader::new(&context, source, ShaderType::Fragment)?;
+//! ```
+//!
+//! ```glsl
+//! #ifdef HAS_EXPLICIT_UNIFORM_LOCATION
+//! layout(location = 0) uniform mat4 model_transform;
+//! #else
+//! uniform mat4 model_transform;
+//! #endif
+//! ```
+
+use context::{Capabilities, Context};
+use diagnostic;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+/// Provides the source of included files.
+///
+/// Implemented for `VirtualFileSystem` and for any `Fn(&str) -> Option<String>`, so includes can
+/// also be loaded from disk or from a resource system with a callback.
+pub trait IncludeResolver {
+    /// Returns the source of the file at `path`, or `None` if there's no such file.
+    fn resolve(&self, path: &str) -> Option<String>;
+}
+
+impl<F> IncludeResolver for F where F: Fn(&str) -> Option<String> {
+    fn resolve(&self, path: &str) -> Option<String> {
+        self(path)
+    }
+}
+
+/// An in-memory set of files that can be included by shaders.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualFileSystem {
+    files: HashMap<String, String>,
+}
+
+impl VirtualFileSystem {
+    pub fn new() -> VirtualFileSystem {
+        VirtualFileSystem::default()
+    }
+
+    /// Registers a file, replacing any existing file with the same path.
+    pub fn add_file<P, S>(&mut self, path: P, source: S)
+        where P: Into<String>, S: Into<String>
+    {
+        self.files.insert(path.into(), source.into());
+    }
+}
+
+impl IncludeResolver for VirtualFileSystem {
+    fn resolve(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+}
+
+/// The original location of a line of preprocessed source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    /// The name of the file that the line came from.
+    pub file: String,
+
+    /// The line number in the original file, starting from 1.
+    pub line: usize,
+}
+
+/// Shader source with all includes resolved.
+#[derive(Debug, Clone)]
+pub struct PreprocessedSource {
+    source: String,
+
+    /// The original location of each line of `source`.
+    line_map: Vec<SourceLocation>,
+}
+
+impl PreprocessedSource {
+    /// Returns the preprocessed source.
+    pub fn source(&self) -> &str {
+        &*self.source
+    }
+
+    /// Returns the original location of a line of the preprocessed source.
+    ///
+    /// `line` starts from 1, matching the line numbers reported by GLSL compilers.
+    pub fn original_location(&self, line: usize) -> Option<&SourceLocation> {
+        if line == 0 {
+            return None;
+        }
+
+        self.line_map.get(line - 1)
+    }
+
+    /// Rewrites the line numbers in a compiler info log to refer to the original files.
+    ///
+    /// Locations are found with the same parsers as `diagnostic::parse_info_log()`, so the
+    /// NVIDIA (`0(12)`), AMD and Intel (`0:12`), and Mesa (`0:12(5)`) formats are all handled.
+    /// Lines of the log that don't contain a recognized location are left unchanged.
+    pub fn remap_log(&self, log: &str) -> String {
+        let mut result = String::with_capacity(log.len());
+        for log_line in log.lines() {
+            let trimmed = log_line.trim_left();
+            let indent = log_line.len() - trimmed.len();
+
+            let remapped = diagnostic::parse_log_line(trimmed).and_then(|(diagnostic, range)| {
+                diagnostic.line
+                    .and_then(|line| self.original_location(line))
+                    .map(|location| (range, location))
+            });
+
+            match remapped {
+                Some((range, location)) => {
+                    result.push_str(&log_line[..indent + range.start]);
+                    result.push_str(&*format!("{}:{}", location.file, location.line));
+                    result.push_str(&log_line[indent + range.end..]);
+                },
+                None => result.push_str(log_line),
+            }
+            result.push('\n');
+        }
+
+        result
+    }
+}
+
+/// Resolves all `#include "file"` directives in `source`.
+///
+/// `name` is used to identify `source` in the line map. Each file is included at most once, so
+/// shared files don't need include guards. Including a file from itself (directly or
+/// indirectly) is an error.
+pub fn preprocess(
+    name: &str,
+    source: &str,
+    resolver: &IncludeResolver,
+) -> Result<PreprocessedSource, PreprocessError> {
+    let mut preprocessed = PreprocessedSource {
+        source: String::with_capacity(source.len()),
+        line_map: Vec::new(),
+    };
+
+    let mut stack = vec![String::from(name)];
+    let mut included = Vec::new();
+    preprocess_file(name, source, resolver, &mut stack, &mut included, &mut preprocessed)?;
+
+    Ok(preprocessed)
+}
+
+fn preprocess_file(
+    name: &str,
+    source: &str,
+    resolver: &IncludeResolver,
+    stack: &mut Vec<String>,
+    included: &mut Vec<String>,
+    output: &mut PreprocessedSource,
+) -> Result<(), PreprocessError> {
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+
+        let trimmed = line.trim_left();
+        if !trimmed.starts_with("#include") {
+            output.source.push_str(line);
+            output.source.push('\n');
+            output.line_map.push(SourceLocation {
+                file: String::from(name),
+                line: line_number,
+            });
+            continue;
+        }
+
+        let path = parse_include(trimmed).ok_or_else(|| PreprocessError::MalformedInclude {
+            file: String::from(name),
+            line: line_number,
+        })?;
+
+        if stack.iter().any(|file| file == path) {
+            return Err(PreprocessError::RecursiveInclude {
+                file: String::from(name),
+                line: line_number,
+                path: String::from(path),
+            });
+        }
+
+        if included.iter().any(|file| file == path) {
+            continue;
+        }
+
+        let include_source = resolver.resolve(path).ok_or_else(|| PreprocessError::IncludeNotFound {
+            file: String::from(name),
+            line: line_number,
+            path: String::from(path),
+        })?;
+
+        included.push(String::from(path));
+        stack.push(String::from(path));
+        preprocess_file(path, &*include_source, resolver, stack, included, output)?;
+        stack.pop();
+    }
+
+    Ok(())
+}
+
+/// Extracts the path from an `#include "path"` directive.
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line["#include".len()..].trim();
+    if rest.len() < 2 || !rest.starts_with('"') || !rest.ends_with('"') {
+        return None;
+    }
+
+    Some(&rest[1..rest.len() - 1])
+}
+
+/// A version of GLSL that a shader can target.
+///
+/// Only versions supported by core profile contexts of GL 3.3 or later are included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GlslVersion {
+    Glsl330,
+    Glsl400,
+    Glsl410,
+    Glsl420,
+    Glsl430,
+    Glsl440,
+    Glsl450,
+}
+
+impl GlslVersion {
+    /// Returns the version number as it appears in the `#version` directive, e.g. 330.
+    pub fn number(self) -> u32 {
+        match self {
+            GlslVersion::Glsl330 => 330,
+            GlslVersion::Glsl400 => 400,
+            GlslVersion::Glsl410 => 410,
+            GlslVersion::Glsl420 => 420,
+            GlslVersion::Glsl430 => 430,
+            GlslVersion::Glsl440 => 440,
+            GlslVersion::Glsl450 => 450,
+        }
+    }
+
+    /// Returns the newest GLSL version supported by a context with the given capabilities.
+    ///
+    /// Since GL 3.3 the GLSL version matches the GL version. Contexts older than 3.3 aren't
+    /// supported by gl-util, so `Glsl330` is returned for them.
+    pub fn for_capabilities(capabilities: &Capabilities) -> GlslVersion {
+        match capabilities.version {
+            (major, _) if major > 4 => GlslVersion::Glsl450,
+            (4, minor) if minor >= 5 => GlslVersion::Glsl450,
+            (4, 4) => GlslVersion::Glsl440,
+            (4, 3) => GlslVersion::Glsl430,
+            (4, 2) => GlslVersion::Glsl420,
+            (4, 1) => GlslVersion::Glsl410,
+            (4, 0) => GlslVersion::Glsl400,
+            _ => GlslVersion::Glsl330,
+        }
+    }
+}
+
+/// Optional GLSL features that are exposed to shaders as macros.
+///
+/// Each entry is the macro, the GLSL version that made the feature core, and the extension that
+/// provides it on older versions.
+const FEATURES: &'static [(&'static str, u32, &'static str)] = &[
+    ("HAS_GPU_SHADER5", 400, "GL_ARB_gpu_shader5"),
+    ("HAS_TEXTURE_GATHER", 400, "GL_ARB_texture_gather"),
+    ("HAS_SHADING_LANGUAGE_420PACK", 420, "GL_ARB_shading_language_420pack"),
+    ("HAS_SHADER_IMAGE_LOAD_STORE", 420, "GL_ARB_shader_image_load_store"),
+    ("HAS_EXPLICIT_UNIFORM_LOCATION", 430, "GL_ARB_explicit_uniform_location"),
+    ("HAS_SHADER_STORAGE_BUFFER_OBJECT", 430, "GL_ARB_shader_storage_buffer_object"),
+    ("HAS_ENHANCED_LAYOUTS", 440, "GL_ARB_enhanced_layouts"),
+];
+
+/// Builds the final source of a shader, handling the `#version` directive and feature macros.
+///
+/// Any `#version` directive in the source is replaced with one for the target version, followed
+/// by `#define`s for the available features and any additional macros. A `#line` directive is
+/// emitted after the generated lines, so line numbers in compile errors still refer to the
+/// original source.
+#[derive(Debug, Clone)]
+pub struct ShaderSource<'a> {
+    source: &'a str,
+    target: Option<GlslVersion>,
+    capabilities: Option<&'a Capabilities>,
+    defines: Vec<(String, String)>,
+}
+
+impl<'a> ShaderSource<'a> {
+    /// Wraps `source` without changing its version.
+    pub fn new(source: &'a str) -> ShaderSource<'a> {
+        ShaderSource {
+            source: source,
+            target: None,
+            capabilities: None,
+            defines: Vec::new(),
+        }
+    }
+
+    /// Targets the newest GLSL version supported by `context`, and enables the features that the
+    /// context supports through extensions.
+    pub fn for_context(source: &'a str, context: &'a Context) -> ShaderSource<'a> {
+        let capabilities = context.capabilities();
+        ShaderSource::new(source)
+            .with_target(GlslVersion::for_capabilities(capabilities))
+            .with_capabilities(capabilities)
+    }
+
+    /// Sets the GLSL version to compile the shader as, replacing the source's `#version`.
+    ///
+    /// The profile given by the source's `#version` is kept, and the core profile is used if the
+    /// source doesn't give one.
+    pub fn with_target(mut self, version: GlslVersion) -> ShaderSource<'a> {
+        self.target = Some(version);
+        self
+    }
+
+    /// Enables features that aren't core in the target version but are supported as extensions.
+    ///
+    /// Without capabilities only the features that are core in the target version are defined.
+    pub fn with_capabilities(mut self, capabilities: &'a Capabilities) -> ShaderSource<'a> {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Adds a `#define name value` to the start of the shader.
+    pub fn define<N, V>(mut self, name: N, value: V) -> ShaderSource<'a>
+        where N: Into<String>, V: Into<String>
+    {
+        self.defines.push((name.into(), value.into()));
+        self
+    }
+
+    /// Returns the source with the generated directives inserted.
+    pub fn build(&self) -> String {
+        // Blank out the existing `#version` line rather than removing it, so that the `#line`
+        // directive below keeps the original line numbers.
+        let mut original_version = None;
+        let mut body = String::with_capacity(self.source.len());
+        for line in self.source.lines() {
+            let trimmed = line.trim_left();
+            if original_version.is_none() && trimmed.starts_with("#version") {
+                original_version = Some(trimmed);
+            } else {
+                body.push_str(line);
+            }
+            body.push('\n');
+        }
+
+        let mut result = String::with_capacity(body.len() + 256);
+        let version_number = match (self.target, original_version) {
+            (Some(target), _) => {
+                // Keep the profile requested by the source, defaulting to the core profile.
+                let profile = original_version
+                    .and_then(|line| line.split_whitespace().nth(2))
+                    .unwrap_or("core");
+                result.push_str(&*format!("#version {} {}\n", target.number(), profile));
+                Some(target.number())
+            },
+            (None, Some(line)) => {
+                result.push_str(line);
+                result.push('\n');
+                line.split_whitespace().nth(1).and_then(|number| number.parse().ok())
+            },
+            (None, None) => None,
+        };
+
+        if let Some(version_number) = version_number {
+            for &(name, core_version, extension) in FEATURES {
+                if version_number >= core_version {
+                    result.push_str(&*format!("#define {} 1\n", name));
+                } else if self.capabilities.map_or(false, |caps| caps.supports(extension)) {
+                    result.push_str(&*format!("#extension {} : enable\n", extension));
+                    result.push_str(&*format!("#define {} 1\n", name));
+                }
+            }
+        }
+
+        for &(ref name, ref value) in &self.defines {
+            result.push_str(&*format!("#define {} {}\n", name, value));
+        }
+
+        // Before GLSL 4.20 `#line n` set the number of the *following* line to n + 1.
+        if version_number.map_or(false, |number| number >= 420) {
+            result.push_str("#line 1\n");
+        } else {
+            result.push_str("#line 0\n");
+        }
+        result.push_str(&*body);
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PreprocessError {
+    /// Indicates that an `#include` directive couldn't be parsed.
+    MalformedInclude {
+        file: String,
+        line: usize,
+    },
+
+    /// Indicates that the include resolver didn't have a file for an `#include`.
+    IncludeNotFound {
+        file: String,
+        line: usize,
+        path: String,
+    },
+
+    /// Indicates that a file includes itself, either directly or through other includes.
+    RecursiveInclude {
+        file: String,
+        line: usize,
+        path: String,
+    },
+}
+
+impl Display for PreprocessError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            PreprocessError::MalformedInclude { ref file, line } => {
+                write!(formatter, "{}:{}: malformed #include directive", file, line)
+            },
+            PreprocessError::IncludeNotFound { ref file, line, ref path } => {
+                write!(formatter, "{}:{}: included file \"{}\" not found", file, line, path)
+            },
+            PreprocessError::RecursiveInclude { ref file, line, ref path } => {
+                write!(formatter, "{}:{}: \"{}\" is included recursively", file, line, path)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> VirtualFileSystem {
+        let mut files = VirtualFileSystem::new();
+        files.add_file("common.glsl", "float square(float x) {\n    return x * x;\n}");
+        files.add_file("lighting.glsl", "#include \"common.glsl\"\nfloat light() { return 1.0; }");
+        files
+    }
+
+    #[test]
+    fn include_expansion() {
+        let source = "#version 330 core\n\
+                      #include \"lighting.glsl\"\n\
+                      #include \"common.glsl\"\n\
+                      void main() {}";
+        let preprocessed = preprocess("main.frag", source, &files()).unwrap();
+
+        assert_eq!(
+            "#version 330 core\n\
+             float square(float x) {\n    return x * x;\n}\n\
+             float light() { return 1.0; }\n\
+             void main() {}\n",
+            preprocessed.source(),
+        );
+
+        let location = |file: &str, line| {
+            Some(SourceLocation { file: String::from(file), line: line })
+        };
+        assert_eq!(location("main.frag", 1).as_ref(), preprocessed.original_location(1));
+        assert_eq!(location("common.glsl", 1).as_ref(), preprocessed.original_location(2));
+        assert_eq!(location("common.glsl", 3).as_ref(), preprocessed.original_location(4));
+        assert_eq!(location("lighting.glsl", 2).as_ref(), preprocessed.original_location(5));
+        assert_eq!(location("main.frag", 4).as_ref(), preprocessed.original_location(6));
+        assert_eq!(None, preprocessed.original_location(0));
+        assert_eq!(None, preprocessed.original_location(7));
+    }
+
+    #[test]
+    fn include_cycle() {
+        let mut files = VirtualFileSystem::new();
+        files.add_file("a.glsl", "#include \"b.glsl\"");
+        files.add_file("b.glsl", "\n#include \"a.glsl\"");
+
+        match preprocess("a.glsl", "#include \"b.glsl\"", &files) {
+            Err(PreprocessError::RecursiveInclude { ref file, line: 2, ref path }) => {
+                assert_eq!("b.glsl", file.as_str());
+                assert_eq!("a.glsl", path.as_str());
+            },
+            result => panic!("Expected RecursiveInclude, got {:?}", result),
+        }
+
+        match preprocess("self.glsl", "#include \"self.glsl\"", &files) {
+            Err(PreprocessError::RecursiveInclude { .. }) => {},
+            result => panic!("Expected RecursiveInclude, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn include_errors() {
+        match preprocess("main.frag", "#include \"missing.glsl\"", &files()) {
+            Err(PreprocessError::IncludeNotFound { line: 1, .. }) => {},
+            result => panic!("Expected IncludeNotFound, got {:?}", result),
+        }
+
+        match preprocess("main.frag", "\n#include common.glsl", &files()) {
+            Err(PreprocessError::MalformedInclude { line: 2, .. }) => {},
+            result => panic!("Expected MalformedInclude, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn version_profile() {
+        let first_line = |source| {
+            let built = ShaderSource::new(source).with_target(GlslVersion::Glsl410).build();
+            String::from(built.lines().next().unwrap())
+        };
+
+        assert_eq!("#version 410 core", first_line("#version 330\nvoid main() {}"));
+        assert_eq!("#version 410 core", first_line("#version 330 core\nvoid main() {}"));
+        assert_eq!(
+            "#version 410 compatibility",
+            first_line("#version 330 compatibility\nvoid main() {}"),
+        );
+        assert_eq!("#version 410 core", first_line("void main() {}"));
+
+        // Without a target the source's own directive is kept as is.
+        let built = ShaderSource::new("#version 330 compatibility\nvoid main() {}").build();
+        assert!(built.starts_with("#version 330 compatibility\n"));
+    }
+
+    #[test]
+    fn log_remapping() {
+        let source = "#include \"common.glsl\"\nvoid main() {}";
+        let preprocessed = preprocess("main.frag", source, &files()).unwrap();
+
+        let log = "0(2) : error C1008: undefined variable \"y\"\n\
+                   ERROR: 0:4: 'main' : syntax error\n\
+                   0:3(5): error: `x' undeclared\n  \
+                   0:4(1): warning: unused variable\n\
+                   0:9(1): error: past the end\n\
+                   error: no location";
+        let expected = "common.glsl:2 : error C1008: undefined variable \"y\"\n\
+                        ERROR: main.frag:2: 'main' : syntax error\n\
+                        common.glsl:3(5): error: `x' undeclared\n  \
+                        main.frag:2(1): warning: unused variable\n\
+                        0:9(1): error: past the end\n\
+                        error: no location\n";
+        assert_eq!(expected, preprocessed.remap_log(log));
+    }
+}
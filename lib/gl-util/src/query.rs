@@ -0,0 +1,149 @@
+use context::Context;
+use gl;
+use gl::*;
+
+pub use gl::{ConditionalRenderMode, QueryType};
+
+/// A GL query object, e.g. an occlusion query or a timer query.
+///
+/// Commands issued while the guard returned by `begin()` is alive are counted by the query. The
+/// result can then be read back with `result()`, or used to skip draws entirely with
+/// `conditional_render()`.
+#[derive(Debug)]
+pub struct Query {
+    query_object: QueryObject,
+    query_type: QueryType,
+
+    context: ::gl::Context,
+}
+
+impl Query {
+    pub fn new(context: &Context, query_type: QueryType) -> Result<Query, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut query_object = QueryObject::null();
+        unsafe { gl::gen_queries(1, &mut query_object); }
+
+        // Check if the query object was successfully created.
+        if query_object == QueryObject::null() {
+            return Err(Error::FailedToGenerateQuery);
+        }
+
+        Ok(Query {
+            query_object: query_object,
+            query_type: query_type,
+
+            context: context,
+        })
+    }
+
+    /// Starts the query, ending it when the returned guard is dropped.
+    ///
+    /// Only one query of each type can be active at a time.
+    pub fn begin(&mut self) -> ActiveQuery {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::begin_query(self.query_type, self.query_object); }
+
+        ActiveQuery { query: self }
+    }
+
+    /// Returns `true` if the result of the query is available without blocking.
+    pub fn is_result_available(&self) -> bool {
+        let _guard = ::context::ContextGuard::new(self.context);
+
+        let mut available = 0;
+        unsafe {
+            gl::get_query_object_u64v(
+                self.query_object,
+                QueryResultType::ResultAvailable,
+                &mut available,
+            );
+        }
+
+        available != 0
+    }
+
+    /// Returns the result of the query, blocking until it is available.
+    ///
+    /// For occlusion queries this is the number of samples that passed the depth test (or for
+    /// `AnySamplesPassed` queries, 1 if any did). For `TimeElapsed` queries it's the GPU time
+    /// spent on the commands in nanoseconds.
+    pub fn result(&self) -> u64 {
+        let _guard = ::context::ContextGuard::new(self.context);
+
+        let mut result = 0;
+        unsafe {
+            gl::get_query_object_u64v(self.query_object, QueryResultType::Result, &mut result);
+        }
+
+        result
+    }
+
+    /// Starts conditional rendering based on the result of the query.
+    ///
+    /// Draws issued while the returned guard is alive are skipped by the GL if the query
+    /// reported zero samples. This avoids drawing objects that were hidden by the occlusion
+    /// query, without having to read the result back to the CPU.
+    ///
+    /// # Panics
+    ///
+    /// - If the query isn't an occlusion query (`SamplesPassed`, `AnySamplesPassed`, or
+    ///   `AnySamplesPassedConservative`).
+    pub fn conditional_render(&self, mode: ConditionalRenderMode) -> ConditionalRender {
+        match self.query_type {
+            QueryType::SamplesPassed
+            | QueryType::AnySamplesPassed
+            | QueryType::AnySamplesPassedConservative => {},
+            _ => panic!("Cannot use a {:?} query for conditional rendering", self.query_type),
+        }
+
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::begin_conditional_render(self.query_object, mode); }
+
+        ConditionalRender { query: self }
+    }
+}
+
+impl Drop for Query {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::delete_queries(1, &self.query_object); }
+    }
+}
+
+/// A query that is in progress, see `Query::begin()`.
+///
+/// Ends the query when dropped.
+#[derive(Debug)]
+pub struct ActiveQuery<'a> {
+    query: &'a mut Query,
+}
+
+impl<'a> Drop for ActiveQuery<'a> {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.query.context);
+        unsafe { gl::end_query(self.query.query_type); }
+    }
+}
+
+/// Guard that keeps conditional rendering active, see `Query::conditional_render()`.
+///
+/// Ends conditional rendering when dropped.
+#[derive(Debug)]
+pub struct ConditionalRender<'a> {
+    query: &'a Query,
+}
+
+impl<'a> Drop for ConditionalRender<'a> {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.query.context);
+        unsafe { gl::end_conditional_render(); }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Indicates that `gl::gen_queries()` failed to create a query object.
+    FailedToGenerateQuery,
+}
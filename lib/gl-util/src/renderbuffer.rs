@@ -0,0 +1,121 @@
+use context::Context;
+use gl;
+use gl::*;
+
+pub use gl::TextureInternalFormat;
+
+/// An image that can be attached to a `Framebuffer` but never sampled.
+///
+/// Renderbuffers are useful for framebuffer attachments that are only needed while rendering,
+/// e.g. the depth buffer for an off-screen color target. Unlike textures they support
+/// multisampling, see `Renderbuffer::multisampled()`.
+#[derive(Debug)]
+pub struct Renderbuffer {
+    renderbuffer_name: RenderbufferName,
+    width: usize,
+    height: usize,
+    samples: usize,
+
+    context: ::gl::Context,
+}
+
+impl Renderbuffer {
+    /// Creates a new renderbuffer with storage for a `width` by `height` image.
+    ///
+    /// `internal_format` should be a sized format, e.g. `TextureInternalFormat::Rgba8` or
+    /// `TextureInternalFormat::DepthComponent24`.
+    pub fn new(
+        context: &Context,
+        internal_format: TextureInternalFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<Renderbuffer, Error> {
+        Renderbuffer::multisampled(context, internal_format, width, height, 0)
+    }
+
+    /// Creates a new multisampled renderbuffer with storage for a `width` by `height` image.
+    ///
+    /// The GL may allocate more samples than requested. A `samples` of 0 creates a renderbuffer
+    /// that isn't multisampled.
+    ///
+    /// # Panics
+    ///
+    /// - If `samples` is greater than the context's `max_samples`.
+    pub fn multisampled(
+        context: &Context,
+        internal_format: TextureInternalFormat,
+        width: usize,
+        height: usize,
+        samples: usize,
+    ) -> Result<Renderbuffer, Error> {
+        let max_samples = context.capabilities().max_samples as usize;
+        assert!(
+            samples <= max_samples,
+            "Requested {} samples but the context supports at most {}",
+            samples,
+            max_samples,
+        );
+
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let renderbuffer_name =
+            gl::gen_renderbuffer().ok_or(Error::FailedToGenerateRenderbuffer)?;
+
+        unsafe {
+            gl::bind_renderbuffer(RenderbufferTarget::Renderbuffer, renderbuffer_name);
+            gl::renderbuffer_storage_multisample(
+                RenderbufferTarget::Renderbuffer,
+                samples as i32,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+            gl::bind_renderbuffer(RenderbufferTarget::Renderbuffer, RenderbufferName::null());
+        }
+
+        Ok(Renderbuffer {
+            renderbuffer_name: renderbuffer_name,
+            width: width,
+            height: height,
+            samples: samples,
+
+            context: context,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of samples requested when the renderbuffer was created.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns the OpenGL primitive managed by this object.
+    pub(crate) fn inner(&self) -> RenderbufferName {
+        self.renderbuffer_name
+    }
+
+    pub(crate) fn context(&self) -> ::gl::Context {
+        self.context
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::delete_renderbuffers(1, &self.renderbuffer_name); }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Indicates that `gl::gen_renderbuffer()` failed to create a renderbuffer object.
+    FailedToGenerateRenderbuffer,
+}
@@ -1,9 +1,14 @@
 use context::Context;
+use diagnostic::{self, ShaderDiagnostic};
 use gl;
 use gl::*;
+use preprocess::{self, IncludeResolver, PreprocessError};
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::mem;
 
+pub use gl::UniformType;
+
 /// Represents a single shader which can be used to create a `Program`.
 #[derive(Debug, Clone)]
 pub struct Shader {
@@ -46,10 +51,47 @@ impl Shader {
             }),
             ShaderCompileStatus::Failure => {
                 let log = shader_log(shader_object);
-                Err(ShaderError::CompileError(log))
+                let diagnostics = diagnostic::parse_info_log(&*log);
+                Err(ShaderError::CompileError { log: log, diagnostics: diagnostics })
             }
         }
     }
+
+    /// Creates a shader from source that may contain `#include "file"` directives.
+    ///
+    /// Includes are resolved with `resolver` (see the `preprocess` module). `name` identifies
+    /// `source` in error messages. If compilation fails, the line numbers in the error log and
+    /// in the diagnostics are rewritten to refer to the original files rather than the combined
+    /// source.
+    pub fn with_includes<T: AsRef<str>>(
+        context: &Context,
+        name: &str,
+        source: T,
+        shader_type: ShaderType,
+        resolver: &IncludeResolver,
+    ) -> Result<Shader, ShaderError> {
+        let preprocessed = preprocess::preprocess(name, source.as_ref(), resolver)
+            .map_err(ShaderError::PreprocessError)?;
+
+        match Shader::new(context, preprocessed.source(), shader_type) {
+            Err(ShaderError::CompileError { log, mut diagnostics }) => {
+                for diagnostic in &mut diagnostics {
+                    let location = diagnostic.line
+                        .and_then(|line| preprocessed.original_location(line));
+                    if let Some(location) = location {
+                        diagnostic.file = Some(location.file.clone());
+                        diagnostic.line = Some(location.line);
+                    }
+                }
+
+                Err(ShaderError::CompileError {
+                    log: preprocessed.remap_log(&*log),
+                    diagnostics: diagnostics,
+                })
+            },
+            result @ _ => result,
+        }
+    }
 }
 
 impl Drop for Shader {
@@ -68,8 +110,15 @@ pub enum ShaderError {
 
     /// Indicates that an error occurred while compiling the the shader.
     ///
-    /// The inner string is the error log retrieved from OpenGL.
-    CompileError(String)
+    /// `log` is the error log retrieved from OpenGL, and `diagnostics` contains the errors and
+    /// warnings parsed from the log.
+    CompileError {
+        log: String,
+        diagnostics: Vec<ShaderDiagnostic>,
+    },
+
+    /// Indicates that the shader's `#include` directives couldn't be resolved.
+    PreprocessError(PreprocessError),
 }
 
 fn compile_status(shader_object: ShaderObject) -> ShaderCompileStatus {
@@ -130,6 +179,9 @@ enum ShaderCompileStatus {
 pub struct Program {
     program_object: ProgramObject,
 
+    /// The active uniforms of the program, queried once after linking.
+    uniforms: HashMap<String, UniformInfo>,
+
     pub(crate) context: ::gl::Context,
 }
 
@@ -141,8 +193,9 @@ impl Program {
         let _guard = ::context::ContextGuard::new(context);
 
         // Create shader program.
-        let program = Program {
+        let mut program = Program {
             program_object: unsafe { gl::create_program() },
+            uniforms: HashMap::new(),
 
             context: context,
         };
@@ -166,10 +219,14 @@ impl Program {
         // Check for errors.
         let link_status = link_status(program.inner());
         match link_status {
-            ProgramLinkStatus::Success => Ok(program),
+            ProgramLinkStatus::Success => {
+                program.uniforms = active_uniforms(program.inner());
+                Ok(program)
+            },
             ProgramLinkStatus::Failure => {
                 let log = program_log(program.inner());
-                Err(ProgramError::LinkError(log))
+                let diagnostics = diagnostic::parse_info_log(&*log);
+                Err(ProgramError::LinkError { log: log, diagnostics: diagnostics })
             }
         }
     }
@@ -191,7 +248,50 @@ impl Program {
         }
     }
 
+    /// Returns the location, type, and size of an active uniform.
+    ///
+    /// Returns `None` if there's no active uniform named `name`. Uniforms that are declared in
+    /// the shader source but not used may be optimized out by the driver and so won't be found.
+    /// Array uniforms can be looked up either by their base name or with `[0]` appended.
+    pub fn uniform_info(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.get(name)
+    }
+
+    /// Returns an iterator over the names and info of all active uniforms.
+    pub fn uniforms(&self) -> ::std::collections::hash_map::Iter<String, UniformInfo> {
+        self.uniforms.iter()
+    }
+
+    /// Assigns the uniform buffer binding point `binding` to the uniform block named `name`.
+    ///
+    /// Returns `false` if the program has no active uniform block named `name`, e.g. because the
+    /// block isn't declared or none of its members are used.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) -> bool {
+        let _guard = ::context::ContextGuard::new(self.context);
+
+        let mut null_terminated = String::from(name);
+        null_terminated.push('\0');
+
+        let block_index = unsafe {
+            gl::get_uniform_block_index(self.inner(), null_terminated.as_ptr())
+        };
+
+        // `GL_INVALID_INDEX` is returned for names that aren't active uniform blocks.
+        if block_index == !0 {
+            return false;
+        }
+
+        unsafe { gl::uniform_block_binding(self.inner(), block_index, binding); }
+        true
+    }
+
     pub(crate) fn get_uniform_location(&self, name: &str) -> Option<UniformLocation> {
+        if let Some(info) = self.uniforms.get(name) {
+            return Some(info.location);
+        }
+
+        // Fall back to querying the driver for names that aren't in the table, e.g. individual
+        // array elements other than the first.
         let _guard = ::context::ContextGuard::new(self.context);
 
         let mut null_terminated = String::from(name);
@@ -221,6 +321,18 @@ impl Drop for Program {
     }
 }
 
+/// Information about an active uniform of a `Program`, see `Program::uniform_info()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub location: UniformLocation,
+
+    /// The GLSL type of the uniform, or `None` if it's a type that gl-util doesn't support.
+    pub uniform_type: Option<UniformType>,
+
+    /// The number of array elements, or 1 if the uniform isn't an array.
+    pub size: usize,
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
@@ -239,9 +351,13 @@ pub enum ProgramError {
     /// Indicates that an error occurred while linking the program.
     ///
     /// Link errors can occur for various reasons, usually relating to undeclared variables or
-    /// variables that are declared differently between different shaders in the program. The
-    /// wrapped error message will contain information about the source of the error.
-    LinkError(String),
+    /// variables that are declared differently between different shaders in the program. `log`
+    /// will contain information about the source of the error, and `diagnostics` contains the
+    /// errors and warnings parsed from the log.
+    LinkError {
+        log: String,
+        diagnostics: Vec<ShaderDiagnostic>,
+    },
 }
 
 fn link_status(program_object: ProgramObject) -> ProgramLinkStatus {
@@ -288,3 +404,59 @@ fn program_log(program_object: ProgramObject) -> String {
         String::new()
     }
 }
+
+/// Queries the location and type of every active uniform in a linked program.
+///
+/// Array uniforms are reported by the driver as `name[0]`, so they're also added under their
+/// base name.
+fn active_uniforms(program_object: ProgramObject) -> HashMap<String, UniformInfo> {
+    let mut count = 0;
+    let mut max_length = 0;
+    unsafe {
+        gl::get_program_param(program_object, ProgramParam::ActiveUniforms, &mut count);
+        gl::get_program_param(program_object, ProgramParam::ActiveUniformMaxLength, &mut max_length);
+    }
+
+    let mut uniforms = HashMap::with_capacity(count as usize);
+    let mut name_buffer = vec![0u8; max_length as usize + 1];
+    for index in 0..count as u32 {
+        let mut length = 0;
+        let mut size = 0;
+        let mut raw_type = 0;
+        unsafe {
+            gl::get_active_uniform(
+                program_object,
+                index,
+                name_buffer.len() as i32,
+                &mut length,
+                &mut size,
+                &mut raw_type,
+                name_buffer.as_mut_ptr());
+        }
+
+        let name = String::from_utf8_lossy(&name_buffer[..length as usize]).into_owned();
+
+        // Uniforms in uniform blocks don't have a location, and can't be set with `uniform()`.
+        let mut null_terminated = name.clone();
+        null_terminated.push('\0');
+        let raw_location = unsafe {
+            gl::get_uniform_location(program_object, null_terminated.as_ptr())
+        };
+        if raw_location == -1 {
+            continue;
+        }
+
+        let info = UniformInfo {
+            location: UniformLocation::from_index(raw_location as u32),
+            uniform_type: UniformType::from_raw(raw_type),
+            size: size as usize,
+        };
+
+        if name.ends_with("[0]") {
+            uniforms.insert(String::from(&name[..name.len() - 3]), info);
+        }
+        uniforms.insert(name, info);
+    }
+
+    uniforms
+}
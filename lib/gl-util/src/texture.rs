@@ -1,9 +1,13 @@
+use buffer::StreamingBuffer;
+use container::{ContainerFormat, TextureContainer};
 use context::Context;
 use gl;
+use gl::{BufferName, BufferTarget};
 
 pub use gl::{
-    TextureObject, TextureFilterFunction, TextureFormat, TextureBindTarget, Texture2dTarget,
-    TextureInternalFormat, TextureDataType, TextureParameterName, TextureParameterTarget};
+    Comparison, CompressedTextureFormat, TextureCompareMode, TextureObject, TextureFilterFunction, TextureFormat,
+    TextureBindTarget, Texture2dTarget, TextureInternalFormat, TextureDataType,
+    TextureParameterName, TextureParameterTarget};
 
 #[derive(Debug)]
 pub struct Texture2d {
@@ -77,6 +81,198 @@ impl Texture2d {
         })
     }
 
+    /// Constructs a new `Texture2d` with uninitialized storage.
+    ///
+    /// Useful for creating render targets for a `Framebuffer`. If `internal_format` is a depth
+    /// format (e.g. `TextureInternalFormat::DepthComponent24`) the texture can be used as the
+    /// depth attachment of a framebuffer, e.g. when rendering shadow maps.
+    pub fn allocate(
+        context: &Context,
+        internal_format: TextureInternalFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<Texture2d, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        // No data is uploaded, but the format and type must still be compatible with the internal
+        // format.
+        let (data_format, data_type) = if internal_format.is_depth() {
+            (TextureFormat::DepthComponent, TextureDataType::f32)
+        } else {
+            (TextureFormat::Rgba, TextureDataType::u8)
+        };
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, texture_object);
+            gl::texture_image_2d(
+                Texture2dTarget::Texture2d,
+                0,
+                internal_format,
+                width as i32,
+                height as i32,
+                0,
+                data_format,
+                data_type,
+                ::std::ptr::null());
+
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::Nearest.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Nearest.into());
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+
+        Ok(Texture2d {
+            texture_object: texture_object,
+
+            context: context,
+        })
+    }
+
+    /// Constructs a new `Texture2d` from a texture loaded from a KTX or DDS file.
+    ///
+    /// Every mip level stored in the container is uploaded without being decoded, compressed or
+    /// not. The texture is sampled with linear filtering, and with trilinear filtering if the
+    /// container holds more than one level. Compressed formats must be supported by the context,
+    /// e.g. the S3TC formats require `EXT_texture_compression_s3tc`.
+    ///
+    /// # Panics
+    ///
+    /// - If `container` holds a cube map, see `TextureCube::from_container()`.
+    pub fn from_container(context: &Context, container: &TextureContainer) -> Result<Texture2d, Error> {
+        assert!(!container.is_cube_map(), "Cannot create a 2D texture from a cube map container");
+
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, texture_object);
+            for (index, level) in container.levels().iter().enumerate() {
+                upload_container_image(
+                    Texture2dTarget::Texture2d,
+                    index,
+                    container.format(),
+                    level.width,
+                    level.height,
+                    &level.faces[0]);
+            }
+            set_container_parameters(TextureParameterTarget::Texture2d, container);
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+
+        Ok(Texture2d {
+            texture_object: texture_object,
+
+            context: context,
+        })
+    }
+
+    /// Constructs a new `Texture2d` with uninitialized storage for `level_count` mip levels,
+    /// which are filled in afterwards with `set_level_rows()`.
+    ///
+    /// This is meant for streaming, where the levels arrive over several frames. The texture
+    /// starts out incomplete so that it samples as black until `set_base_level()` is called for
+    /// a level whose data is available. It's sampled with trilinear filtering.
+    pub fn allocate_levels(
+        context: &Context,
+        format: ContainerFormat,
+        width: usize,
+        height: usize,
+        level_count: usize,
+    ) -> Result<Texture2d, Error> {
+        assert!(level_count > 0, "Cannot allocate a texture without any levels");
+
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, texture_object);
+            for level in 0..level_count {
+                let level_width = ::std::cmp::max(width >> level, 1);
+                let level_height = ::std::cmp::max(height >> level, 1);
+                match format {
+                    ContainerFormat::Compressed(format) => {
+                        gl::compressed_texture_image_2d(
+                            Texture2dTarget::Texture2d,
+                            level as i32,
+                            format,
+                            level_width as i32,
+                            level_height as i32,
+                            0,
+                            format.image_size(level_width, level_height) as i32,
+                            ::std::ptr::null());
+                    },
+                    ContainerFormat::Uncompressed { format, internal_format } => {
+                        gl::texture_image_2d(
+                            Texture2dTarget::Texture2d,
+                            level as i32,
+                            internal_format,
+                            level_width as i32,
+                            level_height as i32,
+                            0,
+                            format,
+                            TextureDataType::u8,
+                            ::std::ptr::null());
+                    },
+                }
+            }
+
+            // A base level past the max level makes the texture incomplete.
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::BaseLevel,
+                level_count as i32);
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MaxLevel,
+                level_count as i32 - 1);
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::LinearMipmapLinear.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Linear.into());
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+
+        Ok(Texture2d {
+            texture_object: texture_object,
+
+            context: context,
+        })
+    }
+
     pub fn empty(context: &Context) -> Texture2d {
         Texture2d {
             texture_object: TextureObject::null(),
@@ -85,10 +281,289 @@ impl Texture2d {
         }
     }
 
+    /// Replaces the texture's data with pre-compressed image data.
+    ///
+    /// `data` must already be compressed in `format`, which allows compressed game textures to
+    /// be uploaded directly without being decompressed first. If the texture was created with
+    /// `empty()` a new texture object is created.
+    ///
+    /// # Panics
+    ///
+    /// - If `data.len()` doesn't match the compressed size of a `width` by `height` image.
+    pub fn set_compressed_data(
+        &mut self,
+        format: CompressedTextureFormat,
+        width: usize,
+        height: usize,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let expected_size = format.image_size(width, height);
+        assert!(
+            expected_size == data.len(),
+            "Wrong amount of compressed data, width: {}, height: {}, expected bytes: {}, actual bytes: {}",
+            width,
+            height,
+            expected_size,
+            data.len());
+
+        let _guard = ::context::ContextGuard::new(self.context);
+
+        let is_new = self.texture_object.is_null();
+        if is_new {
+            unsafe { gl::gen_textures(1, &mut self.texture_object); }
+
+            // Check if the texture object was successfully created.
+            if self.texture_object.is_null() {
+                return Err(Error::FailedToGenerateTexture);
+            }
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, self.texture_object);
+            gl::compressed_texture_image_2d(
+                Texture2dTarget::Texture2d,
+                0,
+                format,
+                width as i32,
+                height as i32,
+                0,
+                data.len() as i32,
+                data.as_ptr() as *const ());
+
+            if is_new {
+                gl::texture_parameter_i32(
+                    TextureParameterTarget::Texture2d,
+                    TextureParameterName::MinFilter,
+                    TextureFilterFunction::Nearest.into());
+                gl::texture_parameter_i32(
+                    TextureParameterTarget::Texture2d,
+                    TextureParameterName::MagFilter,
+                    TextureFilterFunction::Nearest.into());
+            }
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the rows `y..y + height` of mip level `level` with `data`.
+    ///
+    /// `format` must be the format the level was allocated with, and `width` the level's width.
+    /// For compressed formats `y` must be a multiple of 4, and `height` too unless the rows reach
+    /// the top of the level.
+    ///
+    /// # Panics
+    ///
+    /// - If `data.len()` doesn't match the size of the rows.
+    pub fn set_level_rows(
+        &mut self,
+        level: usize,
+        format: ContainerFormat,
+        width: usize,
+        y: usize,
+        height: usize,
+        data: &[u8],
+    ) {
+        let expected_size = format.image_size(width, height);
+        assert!(
+            expected_size == data.len(),
+            "Wrong amount of level data, width: {}, height: {}, expected bytes: {}, actual bytes: {}",
+            width,
+            height,
+            expected_size,
+            data.len());
+
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            self.upload_level_rows(level, format, width, y, height, data.len(), data.as_ptr() as *const ());
+        }
+    }
+
+    /// Replaces the rows `y..y + height` of mip level `level` with `len` bytes read from `buffer`
+    /// starting at `offset`, see `set_level_rows()`.
+    ///
+    /// The data is copied on the GPU, so uploading from a persistently mapped pixel unpack buffer
+    /// doesn't stall the CPU. A fence must be placed with `fence_region()` after the upload.
+    ///
+    /// # Panics
+    ///
+    /// - If `buffer` wasn't created for `BufferTarget::PixelUnpack`.
+    /// - If `len` doesn't match the size of the rows.
+    pub fn set_level_rows_from_buffer(
+        &mut self,
+        level: usize,
+        format: ContainerFormat,
+        width: usize,
+        y: usize,
+        height: usize,
+        buffer: &StreamingBuffer<u8>,
+        offset: usize,
+        len: usize,
+    ) {
+        assert!(buffer.target() == BufferTarget::PixelUnpack, "Texture data must be read from a pixel unpack buffer");
+        assert!(
+            format.image_size(width, height) == len,
+            "Wrong amount of level data, width: {}, height: {}, expected bytes: {}, actual bytes: {}",
+            width,
+            height,
+            format.image_size(width, height),
+            len);
+
+        let _guard = ::context::ContextGuard::new(self.context);
+        buffer.bind();
+        unsafe {
+            // While a pixel unpack buffer is bound the data pointer is an offset into it.
+            self.upload_level_rows(level, format, width, y, height, len, offset as *const ());
+            gl::bind_buffer(BufferTarget::PixelUnpack, BufferName::null());
+        }
+    }
+
+    unsafe fn upload_level_rows(
+        &mut self,
+        level: usize,
+        format: ContainerFormat,
+        width: usize,
+        y: usize,
+        height: usize,
+        len: usize,
+        data: *const (),
+    ) {
+        gl::bind_texture(TextureBindTarget::Texture2d, self.texture_object);
+        match format {
+            ContainerFormat::Compressed(format) => {
+                gl::compressed_texture_sub_image_2d(
+                    Texture2dTarget::Texture2d,
+                    level as i32,
+                    0,
+                    y as i32,
+                    width as i32,
+                    height as i32,
+                    format,
+                    len as i32,
+                    data);
+            },
+            ContainerFormat::Uncompressed { format, .. } => {
+                gl::texture_sub_image_2d(
+                    Texture2dTarget::Texture2d,
+                    level as i32,
+                    0,
+                    y as i32,
+                    width as i32,
+                    height as i32,
+                    format,
+                    TextureDataType::u8,
+                    data);
+            },
+        }
+        gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+    }
+
+    /// Sets the most detailed mip level that's sampled.
+    ///
+    /// Levels above the base level can still be written to, so a streamed texture lowers its
+    /// base level each time another level has been uploaded completely.
+    pub fn set_base_level(&mut self, level: usize) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, self.texture_object);
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::BaseLevel,
+                level as i32);
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+    }
+
+    /// Sets the degree of anisotropic filtering used when sampling the texture.
+    ///
+    /// Anisotropic filtering keeps textures sharp when viewed at grazing angles, e.g. floors and
+    /// terrain. `anisotropy` is clamped to the range supported by the context, and a value of
+    /// 1.0 disables anisotropic filtering. If the context doesn't support anisotropic filtering
+    /// this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// - If `anisotropy` is less than 1.0.
+    pub fn set_anisotropy(&mut self, context: &Context, anisotropy: f32) {
+        assert!(anisotropy >= 1.0, "Anisotropy must be at least 1.0, got {}", anisotropy);
+        assert!(
+            self.context == context.raw(),
+            "Specified context does not match the texture's context"
+        );
+
+        let max_anisotropy = match context.capabilities().max_anisotropy {
+            Some(max_anisotropy) => max_anisotropy,
+            None => return,
+        };
+
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, self.texture_object);
+            gl::texture_parameter_f32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MaxAnisotropy,
+                anisotropy.min(max_anisotropy));
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+    }
+
+    /// Sets the depth comparison used when sampling a depth texture.
+    ///
+    /// With `Some(comparison)` the texture can be sampled with a `sampler2DShadow` in GLSL, which
+    /// returns the result of comparing the reference value against the stored depth value. With
+    /// `None` the stored depth value is returned directly.
+    pub fn set_depth_compare(&mut self, comparison: Option<Comparison>) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, self.texture_object);
+            match comparison {
+                Some(comparison) => {
+                    gl::texture_parameter_i32(
+                        TextureParameterTarget::Texture2d,
+                        TextureParameterName::CompareMode,
+                        TextureCompareMode::CompareRefToTexture as i32);
+                    gl::texture_parameter_i32(
+                        TextureParameterTarget::Texture2d,
+                        TextureParameterName::CompareFunc,
+                        comparison as i32);
+                },
+                None => {
+                    gl::texture_parameter_i32(
+                        TextureParameterTarget::Texture2d,
+                        TextureParameterName::CompareMode,
+                        TextureCompareMode::None as i32);
+                },
+            }
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+    }
+
+    /// Regenerates the texture's mipmaps from its base level.
+    ///
+    /// After the first call the texture is sampled with trilinear filtering, so shaders can read
+    /// lower levels with `textureLod()`. This is useful for reducing a render target, e.g. the
+    /// smallest level holds the average of the whole image.
+    pub fn generate_mipmaps(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, self.texture_object);
+            gl::generate_mipmap(TextureParameterTarget::Texture2d);
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::LinearMipmapLinear.into());
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+    }
+
     /// Returns the OpenGL primitive managed by this object.
     pub(crate) fn inner(&self) -> TextureObject {
         self.texture_object
     }
+
+    pub(crate) fn context(&self) -> ::gl::Context {
+        self.context
+    }
 }
 
 impl Drop for Texture2d {
@@ -98,6 +573,360 @@ impl Drop for Texture2d {
     }
 }
 
+/// A cube map texture, made up of six square faces.
+///
+/// Cube maps are sampled with a direction rather than a coordinate, which makes them useful for
+/// environment maps and omnidirectional shadow maps. Each face can be rendered to by attaching
+/// it to a `Framebuffer` with `Framebuffer::attach_cube_face()`.
+#[derive(Debug)]
+pub struct TextureCube {
+    texture_object: TextureObject,
+    size: usize,
+
+    context: ::gl::Context,
+}
+
+impl TextureCube {
+    /// Constructs a new `TextureCube` from the data for each of its six faces.
+    ///
+    /// The faces are given in the order of `CubeFace::ALL`. The cube map is sampled with linear
+    /// filtering.
+    ///
+    /// # Panics
+    ///
+    /// - If `size * size` doesn't match the number of pixels in every face.
+    pub fn new<T: TextureData>(
+        context: &Context,
+        data_format: TextureFormat,
+        internal_format: TextureInternalFormat,
+        size: usize,
+        faces: [&[T]; 6],
+    ) -> Result<TextureCube, Error> {
+        let expected_pixels = size * size * data_format.elements() / T::ELEMENTS;
+        for face in &faces {
+            assert!(
+                expected_pixels == face.len(),
+                "Wrong number of pixels in cube map face, size: {}, expected pixels: {}, actual pixels: {}",
+                size,
+                expected_pixels,
+                face.len());
+        }
+
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::CubeMap, texture_object);
+            for (face, data) in CubeFace::ALL.iter().zip(faces.iter()) {
+                gl::texture_image_2d(
+                    face.target(),
+                    0,
+                    internal_format,
+                    size as i32,
+                    size as i32,
+                    0,
+                    data_format,
+                    T::DATA_TYPE,
+                    data.as_ptr() as *const ());
+            }
+
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::Linear.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Linear.into());
+            gl::bind_texture(TextureBindTarget::CubeMap, TextureObject::null());
+        }
+
+        Ok(TextureCube {
+            texture_object: texture_object,
+            size: size,
+
+            context: context,
+        })
+    }
+
+    /// Constructs a new `TextureCube` from a cube map loaded from a KTX or DDS file.
+    ///
+    /// Every mip level stored in the container is uploaded for each face, and the cube map is
+    /// filtered the same way as textures created with `Texture2d::from_container()`.
+    ///
+    /// # Panics
+    ///
+    /// - If `container` doesn't hold a cube map.
+    /// - If the faces of `container` aren't square.
+    pub fn from_container(context: &Context, container: &TextureContainer) -> Result<TextureCube, Error> {
+        assert!(container.is_cube_map(), "Cannot create a cube map from a 2D texture container");
+        assert!(
+            container.width() == container.height(),
+            "Cube map faces must be square, width: {}, height: {}",
+            container.width(),
+            container.height());
+
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::CubeMap, texture_object);
+            for (index, level) in container.levels().iter().enumerate() {
+                for (face, data) in CubeFace::ALL.iter().zip(level.faces.iter()) {
+                    upload_container_image(
+                        face.target(),
+                        index,
+                        container.format(),
+                        level.width,
+                        level.height,
+                        data);
+                }
+            }
+            set_container_parameters(TextureParameterTarget::CubeMap, container);
+            gl::bind_texture(TextureBindTarget::CubeMap, TextureObject::null());
+        }
+
+        Ok(TextureCube {
+            texture_object: texture_object,
+            size: container.width(),
+
+            context: context,
+        })
+    }
+
+    /// Constructs a new `TextureCube` with uninitialized storage for six `size` by `size` faces.
+    ///
+    /// If `internal_format` is a depth format the faces can be used as the depth attachment of a
+    /// framebuffer.
+    pub fn allocate(
+        context: &Context,
+        internal_format: TextureInternalFormat,
+        size: usize,
+    ) -> Result<TextureCube, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        let (data_format, data_type) = if internal_format.is_depth() {
+            (TextureFormat::DepthComponent, TextureDataType::f32)
+        } else {
+            (TextureFormat::Rgba, TextureDataType::u8)
+        };
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::CubeMap, texture_object);
+            for face in &CubeFace::ALL {
+                gl::texture_image_2d(
+                    face.target(),
+                    0,
+                    internal_format,
+                    size as i32,
+                    size as i32,
+                    0,
+                    data_format,
+                    data_type,
+                    ::std::ptr::null());
+            }
+
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::Nearest.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Nearest.into());
+            gl::bind_texture(TextureBindTarget::CubeMap, TextureObject::null());
+        }
+
+        Ok(TextureCube {
+            texture_object: texture_object,
+            size: size,
+
+            context: context,
+        })
+    }
+
+    /// Returns the width and height of each face.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the number of mipmap levels in a full mip chain for the texture.
+    pub fn levels(&self) -> usize {
+        let mut levels = 1;
+        while self.size >> levels > 0 {
+            levels += 1;
+        }
+        levels
+    }
+
+    /// Sets the filtering used when sampling the texture.
+    pub fn set_filter(&mut self, min_filter: TextureFilterFunction, mag_filter: TextureFilterFunction) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            gl::bind_texture(TextureBindTarget::CubeMap, self.texture_object);
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MinFilter,
+                min_filter.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MagFilter,
+                mag_filter.into());
+            gl::bind_texture(TextureBindTarget::CubeMap, TextureObject::null());
+        }
+    }
+
+    /// Regenerates the texture's mipmaps from its base level of each face.
+    ///
+    /// This also allocates storage for every level of the mip chain, which can then be rendered
+    /// into with `Framebuffer::attach_cube_face_level()`. After the first call the texture is
+    /// sampled with trilinear filtering, so shaders can read lower levels with `textureLod()`.
+    pub fn generate_mipmaps(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe {
+            gl::bind_texture(TextureBindTarget::CubeMap, self.texture_object);
+            gl::generate_mipmap(TextureParameterTarget::CubeMap);
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::LinearMipmapLinear.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::CubeMap,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Linear.into());
+            gl::bind_texture(TextureBindTarget::CubeMap, TextureObject::null());
+        }
+    }
+
+    /// Returns the OpenGL primitive managed by this object.
+    pub(crate) fn inner(&self) -> TextureObject {
+        self.texture_object
+    }
+
+    pub(crate) fn context(&self) -> ::gl::Context {
+        self.context
+    }
+}
+
+impl Drop for TextureCube {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::delete_textures(1, &mut self.inner()); }
+    }
+}
+
+/// Identifies one of the six faces of a `TextureCube`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    /// All faces, in the order GL numbers them.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    pub(crate) fn target(self) -> Texture2dTarget {
+        match self {
+            CubeFace::PositiveX => Texture2dTarget::CubeMapPositiveX,
+            CubeFace::NegativeX => Texture2dTarget::CubeMapNegativeX,
+            CubeFace::PositiveY => Texture2dTarget::CubeMapPositiveY,
+            CubeFace::NegativeY => Texture2dTarget::CubeMapNegativeY,
+            CubeFace::PositiveZ => Texture2dTarget::CubeMapPositiveZ,
+            CubeFace::NegativeZ => Texture2dTarget::CubeMapNegativeZ,
+        }
+    }
+}
+
+/// Uploads a single image of a `TextureContainer` to `target`, which must be bound.
+unsafe fn upload_container_image(
+    target: Texture2dTarget,
+    level: usize,
+    format: ContainerFormat,
+    width: usize,
+    height: usize,
+    data: &[u8],
+) {
+    match format {
+        ContainerFormat::Compressed(format) => {
+            gl::compressed_texture_image_2d(
+                target,
+                level as i32,
+                format,
+                width as i32,
+                height as i32,
+                0,
+                data.len() as i32,
+                data.as_ptr() as *const ());
+        },
+        ContainerFormat::Uncompressed { format, internal_format } => {
+            gl::texture_image_2d(
+                target,
+                level as i32,
+                internal_format,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                TextureDataType::u8,
+                data.as_ptr() as *const ());
+        },
+    }
+}
+
+/// Sets the filtering of a texture uploaded from `container`, which must be bound to `target`.
+///
+/// The mip chain is limited to the levels in the container so that the texture is complete even
+/// if the chain stops before a 1x1 level.
+unsafe fn set_container_parameters(target: TextureParameterTarget, container: &TextureContainer) {
+    let level_count = container.levels().len();
+    let min_filter = if level_count > 1 {
+        TextureFilterFunction::LinearMipmapLinear
+    } else {
+        TextureFilterFunction::Linear
+    };
+
+    gl::texture_parameter_i32(target, TextureParameterName::MaxLevel, level_count as i32 - 1);
+    gl::texture_parameter_i32(target, TextureParameterName::MinFilter, min_filter.into());
+    gl::texture_parameter_i32(target, TextureParameterName::MagFilter, TextureFilterFunction::Linear.into());
+}
+
 pub trait TextureData {
     const DATA_TYPE: TextureDataType;
     const ELEMENTS: usize;
@@ -0,0 +1,193 @@
+//! Typed vertex layouts.
+//!
+//! Rather than manually describing each attribute with `VertexArray::set_attrib()`, a
+//! `#[repr(C)]` vertex struct can implement `Vertex` to describe its own layout. The easiest way
+//! to do that is with the `impl_vertex!` macro:
+//!
+//! ```ignore
+//! #[repr(C)]
+//! #[derive(Debug, Clone, Copy)]
+//! struct MyVertex {
+//!     position: [f32; 4],
+//!     normal: [f32; 3],
+//!     uv: [f32; 2],
+//! }
+//!
+//! impl_vertex!(MyVertex {
+//!     position => 0,
+//!     normal => 1,
+//!     uv => 2,
+//! });
+//!
+//! let vertex_array = VertexArray::from_vertices(&context, &vertices[..]);
+//! ```
+
+use gl::{AttributeLocation, GlType};
+
+/// Describes the memory layout of a vertex type.
+///
+/// # Safety
+///
+/// The attributes returned by `attribs()` must lie entirely within the bounds of the vertex
+/// type, otherwise the GPU will read past the end of the vertex buffer. Implementors should also
+/// be `#[repr(C)]` so that field offsets are stable. Prefer using `impl_vertex!` over
+/// implementing this trait manually.
+pub unsafe trait Vertex: Copy {
+    /// Returns a description of each attribute in the vertex.
+    fn attribs() -> Vec<VertexAttrib>;
+}
+
+/// Describes a single attribute within a `Vertex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VertexAttrib {
+    /// The attribute location in the shader program that the attribute is bound to.
+    pub location: AttributeLocation,
+
+    /// The number of primitive elements in the attribute.
+    ///
+    /// This may not be larger than 4.
+    pub elements: usize,
+
+    /// The offset, in bytes, from the start of the vertex to the attribute.
+    pub offset: usize,
+
+    /// The data type of each element in the attribute.
+    pub gl_type: GlType,
+
+    /// How the attribute is presented to the shader.
+    pub kind: AttribKind,
+}
+
+impl VertexAttrib {
+    /// Creates the description of an attribute from a field of a vertex.
+    ///
+    /// Used by `impl_vertex!` to infer the number of elements from the type of the field.
+    pub fn from_field<T: AttribData>(_field: &T, location: u32, offset: usize) -> VertexAttrib {
+        VertexAttrib {
+            location: AttributeLocation::from_index(location),
+            elements: T::ELEMENTS,
+            offset: offset,
+            gl_type: T::GL_TYPE,
+            kind: T::KIND,
+        }
+    }
+
+    /// Returns the size of the attribute in bytes.
+    pub fn size(&self) -> usize {
+        self.elements * gl_type_size(self.gl_type)
+    }
+}
+
+/// Describes how vertex attribute data is presented to the shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttribKind {
+    /// The data is floating point and is passed to the shader as-is.
+    Float,
+
+    /// The data is integer and is converted to floating point in the range [0, 1] (for unsigned
+    /// types) or [-1, 1] (for signed types) before being passed to the shader.
+    ///
+    /// Useful for packed data like colors stored as 4 `u8`s.
+    Normalized,
+
+    /// The data is integer and is passed to the shader as integer data.
+    ///
+    /// The attribute must be declared with an integer type (e.g. `ivec4`) in the shader. Useful
+    /// for data like bone indices.
+    Integer,
+}
+
+/// Wrapper used to mark an integer attribute in a `Vertex` as normalized.
+///
+/// See `AttribKind::Normalized` for more information.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Normalized<T>(pub T);
+
+/// A type that can be used as a single vertex attribute.
+pub trait AttribData {
+    /// The number of primitive elements in the attribute.
+    const ELEMENTS: usize;
+
+    /// The data type of each element.
+    const GL_TYPE: GlType;
+
+    /// How the attribute is presented to the shader.
+    const KIND: AttribKind;
+}
+
+macro_rules! attrib_data {
+    ( $ty:ty, $gl_type:expr, $kind:expr ) => {
+        impl AttribData for $ty {
+            const ELEMENTS: usize = 1;
+            const GL_TYPE: GlType = $gl_type;
+            const KIND: AttribKind = $kind;
+        }
+
+        attrib_data!(@array [$ty; 1], 1, $gl_type, $kind);
+        attrib_data!(@array [$ty; 2], 2, $gl_type, $kind);
+        attrib_data!(@array [$ty; 3], 3, $gl_type, $kind);
+        attrib_data!(@array [$ty; 4], 4, $gl_type, $kind);
+    };
+
+    ( @array $ty:ty, $elements:expr, $gl_type:expr, $kind:expr ) => {
+        impl AttribData for $ty {
+            const ELEMENTS: usize = $elements;
+            const GL_TYPE: GlType = $gl_type;
+            const KIND: AttribKind = $kind;
+        }
+    };
+}
+
+attrib_data!(f32, GlType::Float, AttribKind::Float);
+attrib_data!(i32, GlType::Int, AttribKind::Integer);
+attrib_data!(u32, GlType::UnsignedInt, AttribKind::Integer);
+attrib_data!(i16, GlType::Short, AttribKind::Integer);
+attrib_data!(u16, GlType::UnsignedShort, AttribKind::Integer);
+attrib_data!(i8, GlType::Byte, AttribKind::Integer);
+attrib_data!(u8, GlType::UnsignedByte, AttribKind::Integer);
+
+impl<T: AttribData> AttribData for Normalized<T> {
+    const ELEMENTS: usize = T::ELEMENTS;
+    const GL_TYPE: GlType = T::GL_TYPE;
+    const KIND: AttribKind = AttribKind::Normalized;
+}
+
+/// Returns the size in bytes of a single element of the specified type.
+pub fn gl_type_size(gl_type: GlType) -> usize {
+    match gl_type {
+        GlType::Byte | GlType::UnsignedByte => 1,
+        GlType::Short | GlType::UnsignedShort | GlType::HalfFloat => 2,
+        GlType::Int | GlType::UnsignedInt | GlType::Float | GlType::Fixed => 4,
+        GlType::Double => 8,
+    }
+}
+
+/// Implements `Vertex` for a struct, mapping each field to an attribute location.
+///
+/// Each field listed is given in the form `field => location`. Fields that aren't listed are
+/// ignored, though they still contribute to the stride of the vertex.
+///
+/// Field offsets are measured on a zeroed vertex, so the struct must be valid when all of its
+/// bytes are zero, which is the case for vertices made up of numbers.
+#[macro_export]
+macro_rules! impl_vertex {
+    ( $vertex:ty { $( $field:ident => $location:expr ),* $(,)* } ) => {
+        unsafe impl $crate::vertex::Vertex for $vertex {
+            fn attribs() -> Vec<$crate::vertex::VertexAttrib> {
+                // Offsets are measured from a real value, since taking a reference to a field
+                // through a null pointer is undefined behavior. The value is never dropped.
+                let vertex: $vertex = unsafe { ::std::mem::zeroed() };
+                let base = &vertex as *const $vertex as usize;
+                let mut attribs = Vec::new();
+                $({
+                    let field = &vertex.$field;
+                    let offset = field as *const _ as usize - base;
+                    attribs.push($crate::vertex::VertexAttrib::from_field(field, $location, offset));
+                })*
+                ::std::mem::forget(vertex);
+                attribs
+            }
+        }
+    }
+}
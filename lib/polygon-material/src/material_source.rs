@@ -73,6 +73,8 @@ pub enum PropertyType {
     Color,
     Texture2d,
     f32,
+    i32,
+    Vector2,
     Vector3,
 }
 
@@ -62,6 +62,8 @@ impl<'a> Parser<'a> {
                 "Color" => PropertyType::Color,
                 "Texture2d" => PropertyType::Texture2d,
                 "f32" => PropertyType::f32,
+                "i32" => PropertyType::i32,
+                "Vector2" => PropertyType::Vector2,
                 "Vector3" => PropertyType::Vector3,
                 _ => return Err(Error::BadPropertyType(span)),
             },
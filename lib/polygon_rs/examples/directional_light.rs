@@ -43,7 +43,8 @@ fn main() {
     let camera_anchor_id = renderer.register_anchor(camera_anchor);
 
     // Create the light and an anchor for it.
-    let light = Light::directional(Vector3::new(1.0, -1.0, -1.0), 0.25, Color::rgb(1.0, 1.0, 1.0));
+    let mut light = Light::directional(Vector3::new(1.0, -1.0, -1.0), 0.25, Color::rgb(1.0, 1.0, 1.0));
+    light.set_cast_shadows(true);
     renderer.register_light(light);
 
     let mut camera = Camera::default();
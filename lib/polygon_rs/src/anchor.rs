@@ -1,10 +1,33 @@
+//! Anchors position objects in the scene.
+//!
+//! An anchor can be attached to a parent anchor with `Renderer::set_anchor_parent()`, in which
+//! case its position, orientation, and scale are relative to its parent, so that it moves along
+//! with the parent (e.g. a weapon held in a hand, or a turret on a tank). The world transform of
+//! each anchor is derived from its parents by the renderer before drawing each frame, and is what
+//! the renderer uses to draw anything attached to the anchor.
+//!
+//! World scale is the component-wise product of the scales in the hierarchy, so a parent with
+//! non-uniform scale won't shear a rotated child.
+//!
+//! An anchor can also be made a billboard with `Anchor::set_billboard()`, in which case anything
+//! attached to it is turned to face each camera that draws it, e.g. for sprites, particles, or
+//! health bars.
+
 use math::*;
+use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Anchor {
     position: Point,
     orientation: Orientation,
     scale: Vector3,
+
+    parent: Option<AnchorId>,
+    billboard: Billboard,
+
+    world_position: Point,
+    world_orientation: Orientation,
+    world_scale: Vector3,
 }
 
 impl Anchor {
@@ -14,52 +37,95 @@ impl Anchor {
             position: Point::origin(),
             orientation: Orientation::new(),
             scale: Vector3::one(),
+
+            parent: None,
+            billboard: Billboard::None,
+
+            world_position: Point::origin(),
+            world_orientation: Orientation::new(),
+            world_scale: Vector3::one(),
         }
     }
 
-    /// Gets the current position of the anchor.
+    /// Gets the current position of the anchor, relative to its parent.
     pub fn position(&self) -> Point {
         self.position
     }
 
-    /// Sets the position of the anchor.
+    /// Sets the position of the anchor, relative to its parent.
     pub fn set_position(&mut self, position: Point) {
         self.position = position;
+        self.update_root_transform();
     }
 
-    /// Gets the current orientation of the anchor.
+    /// Gets the current orientation of the anchor, relative to its parent.
     pub fn orientation(&self) -> Orientation {
         self.orientation
     }
 
-    /// Sets the orientation of the anchor.
+    /// Sets the orientation of the anchor, relative to its parent.
     pub fn set_orientation(&mut self, orientation: Orientation) {
         self.orientation = orientation;
+        self.update_root_transform();
     }
 
-    /// Gets the current scale of the anchor.
+    /// Gets the current scale of the anchor, relative to its parent.
     pub fn scale(&self) -> Vector3 {
         self.scale
     }
 
-    /// Sets the scale of the anchor.
+    /// Sets the scale of the anchor, relative to its parent.
     pub fn set_scale(&mut self, scale: Vector3) {
         self.scale = scale;
+        self.update_root_transform();
+    }
+
+    /// Gets the anchor's parent, if it has one.
+    pub fn parent(&self) -> Option<AnchorId> {
+        self.parent
+    }
+
+    /// Gets how the anchor is turned to face the camera.
+    pub fn billboard(&self) -> Billboard {
+        self.billboard
+    }
+
+    /// Sets how the anchor is turned to face the camera.
+    pub fn set_billboard(&mut self, billboard: Billboard) {
+        self.billboard = billboard;
+    }
+
+    /// Gets the position of the anchor in world space.
+    ///
+    /// For an anchor with a parent this is updated when the renderer draws, so it doesn't reflect
+    /// changes made to the anchor or its parents since the last frame.
+    pub fn world_position(&self) -> Point {
+        self.world_position
+    }
+
+    /// Gets the orientation of the anchor in world space, see `world_position()`.
+    pub fn world_orientation(&self) -> Orientation {
+        self.world_orientation
+    }
+
+    /// Gets the scale of the anchor in world space, see `world_position()`.
+    pub fn world_scale(&self) -> Vector3 {
+        self.world_scale
     }
 
     /// Calculates the matrix to convert from object space to world space.
     pub fn matrix(&self) -> Matrix4 {
-        let position = Matrix4::from_point(self.position);
-        let orientation = Matrix4::from(self.orientation);
-        let scale = Matrix4::from_scale_vector(self.scale);
+        let position = Matrix4::from_point(self.world_position);
+        let orientation = Matrix4::from(self.world_orientation);
+        let scale = Matrix4::from_scale_vector(self.world_scale);
 
         position * (orientation * scale)
     }
 
     /// Calculates the matrix used to convert normals from object space to world space.
     pub fn normal_matrix(&self) -> Matrix3 {
-        let inv_scale = Matrix3::from_scale_vector(1.0 / self.scale);
-        let orientation: Matrix3 = self.orientation.into();
+        let inv_scale = Matrix3::from_scale_vector(1.0 / self.world_scale);
+        let orientation: Matrix3 = self.world_orientation.into();
         let inv_rotation = orientation.transpose();
         // let inv_translation = Matrix3::from_point(-self.position);
 
@@ -67,21 +133,197 @@ impl Anchor {
         inverse.transpose()
     }
 
+    /// Calculates the matrix to convert from object space to world space when drawn by a camera
+    /// at `camera_position` with orientation `camera_orientation`.
+    ///
+    /// The orientation is replaced by the billboard orientation, see `Billboard`. This is the
+    /// same as `matrix()` if the anchor isn't a billboard.
+    pub fn billboard_matrix(&self, camera_position: Point, camera_orientation: Orientation) -> Matrix4 {
+        match self.billboard_rotation(camera_position, camera_orientation) {
+            Some(rotation) => {
+                let position = Matrix4::from_point(self.world_position);
+                let scale = Matrix4::from_scale_vector(self.world_scale);
+                position * (rotation * scale)
+            },
+            None => self.matrix(),
+        }
+    }
+
+    /// Calculates the matrix used to convert normals from object space to world space when drawn
+    /// by a camera, see `billboard_matrix()`.
+    pub fn billboard_normal_matrix(&self, camera_position: Point, camera_orientation: Orientation) -> Matrix3 {
+        match self.billboard_rotation(camera_position, camera_orientation) {
+            // The rotation is orthonormal, so it's its own inverse transpose.
+            Some(rotation) => Matrix3::from(rotation) * Matrix3::from_scale_vector(1.0 / self.world_scale),
+            None => self.normal_matrix(),
+        }
+    }
+
+    /// Calculates the rotation replacing the anchor's orientation, or `None` if the anchor isn't
+    /// a billboard.
+    fn billboard_rotation(&self, camera_position: Point, camera_orientation: Orientation) -> Option<Matrix4> {
+        match self.billboard {
+            Billboard::None => None,
+            Billboard::Spherical => Some(Matrix4::from(camera_orientation)),
+            Billboard::Cylindrical => {
+                let to_camera = camera_position - self.world_position;
+                let back = Vector3::new(to_camera.x, 0.0, to_camera.z);
+                if back.is_zero() {
+                    return Some(Matrix4::identity());
+                }
+
+                let back = back.normalized();
+                let up = Vector3::up();
+                let right = Vector3::cross(up, back);
+
+                // The basis vectors are the columns of the rotation.
+                let mut rotation = Matrix4::identity();
+                for axis in 0..3 {
+                    rotation[axis][0] = right[axis];
+                    rotation[axis][1] = up[axis];
+                    rotation[axis][2] = back[axis];
+                }
+                Some(rotation)
+            },
+        }
+    }
+
     /// Calculates the view transform for the camera.
     ///
     /// The view transform the matrix that converts from world coordinates to camera coordinates.
     pub fn view_matrix(&self) -> Matrix4 {
-        let inv_orientation = Matrix4::from(self.orientation).transpose();
+        let inv_orientation = Matrix4::from(self.world_orientation).transpose();
         let inv_translation = Matrix4::translation(
-            -self.position.x,
-            -self.position.y,
-            -self.position.z);
+            -self.world_position.x,
+            -self.world_position.y,
+            -self.world_position.z);
         inv_orientation * inv_translation
     }
 
     /// Calculates the inverse view matrix.
     pub fn inverse_view_matrix(&self) -> Matrix4 {
-        Matrix4::from_point(self.position) * self.orientation.into()
+        Matrix4::from_point(self.world_position) * self.world_orientation.into()
+    }
+
+    /// Creates an anchor without a parent at this anchor's world transform moved by `offset`,
+    /// which is given in world space.
+    pub(crate) fn displaced(&self, offset: Vector3) -> Anchor {
+        let position = self.world_position + offset;
+        Anchor {
+            position: position,
+            orientation: self.world_orientation,
+            scale: self.world_scale,
+
+            parent: None,
+            billboard: self.billboard,
+
+            world_position: position,
+            world_orientation: self.world_orientation,
+            world_scale: self.world_scale,
+        }
+    }
+
+    /// Keeps the world transform of an anchor without a parent in sync with its local transform,
+    /// so that it's correct without waiting for the next frame.
+    fn update_root_transform(&mut self) {
+        if self.parent.is_none() {
+            self.update_world_transform(None);
+        }
+    }
+
+    /// Derives the world transform from the local transform and the parent's world transform.
+    fn update_world_transform(&mut self, parent: Option<(Point, Orientation, Vector3)>) {
+        match parent {
+            Some((parent_position, parent_orientation, parent_scale)) => {
+                let offset = parent_orientation * (parent_scale * Vector3::from(self.position));
+                self.world_position = parent_position + offset;
+                self.world_orientation = parent_orientation + self.orientation;
+                self.world_scale = parent_scale * self.scale;
+            },
+            None => {
+                self.world_position = self.position;
+                self.world_orientation = self.orientation;
+                self.world_scale = self.scale;
+            },
+        }
+    }
+}
+
+/// How an anchor is turned to face the camera.
+///
+/// A billboard anchor's world orientation is replaced by one that turns its +Z axis towards the
+/// camera when it's drawn, so a quad in the anchor's XY plane is always seen from the front. Its
+/// world position and scale are used as usual, and children of the anchor aren't affected. Since
+/// the orientation depends on the camera, mesh instances on billboard anchors are never drawn
+/// with instancing. Shadows are cast with the orientation facing the camera being drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Billboard {
+    /// The anchor isn't a billboard and keeps its own orientation.
+    None,
+
+    /// The anchor has the same orientation as the camera, so it's parallel to the screen.
+    Spherical,
+
+    /// The anchor only rotates around the world Y axis to face the camera's position, staying
+    /// upright, e.g. for trees.
+    Cylindrical,
+}
+
+impl Default for Billboard {
+    fn default() -> Billboard {
+        Billboard::None
+    }
+}
+
+/// Sets the parent of `anchor_id`.
+///
+/// # Panics
+///
+/// - If `anchor_id` or `parent` don't exist.
+/// - If the parent is `anchor_id` itself or one of its descendants.
+pub(crate) fn set_parent(anchors: &mut HashMap<AnchorId, Anchor>, anchor_id: AnchorId, parent: Option<AnchorId>) {
+    if let Some(parent_id) = parent {
+        let mut ancestor = Some(parent_id);
+        while let Some(ancestor_id) = ancestor {
+            assert!(
+                ancestor_id != anchor_id,
+                "Cannot make anchor {:?} the parent of {:?}, it would create a cycle",
+                parent_id,
+                anchor_id,
+            );
+            ancestor = anchors.get(&ancestor_id).expect("No such parent anchor exists").parent;
+        }
+    }
+
+    let anchor = anchors.get_mut(&anchor_id).expect("No such anchor exists");
+    anchor.parent = parent;
+    anchor.update_root_transform();
+}
+
+/// Derives the world transforms of all anchors, parents before their children.
+pub(crate) fn update_world_transforms(anchors: &mut HashMap<AnchorId, Anchor>) {
+    // Sort the anchors by their depth in the hierarchy, so that every parent is updated before
+    // its children.
+    let mut order: Vec<(usize, AnchorId)> = anchors
+        .keys()
+        .map(|&anchor_id| {
+            let mut depth = 0;
+            let mut parent = anchors[&anchor_id].parent;
+            while let Some(parent_id) = parent {
+                depth += 1;
+                parent = anchors[&parent_id].parent;
+            }
+            (depth, anchor_id)
+        })
+        .collect();
+    order.sort_by_key(|&(depth, _)| depth);
+
+    for (_, anchor_id) in order {
+        let parent = anchors[&anchor_id].parent.map(|parent_id| {
+            let parent = &anchors[&parent_id];
+            (parent.world_position, parent.world_orientation, parent.world_scale)
+        });
+        anchors.get_mut(&anchor_id).unwrap().update_world_transform(parent);
     }
 }
 
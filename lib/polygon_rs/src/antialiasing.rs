@@ -0,0 +1,42 @@
+//! Anti-aliasing configuration.
+//!
+//! With multisample anti-aliasing (MSAA) enabled the scene is rendered into multisampled
+//! intermediate targets, which are resolved at the start of the built-in `"post"` pass. The
+//! edges of geometry are smoothed without shading every sample, so MSAA doesn't help with
+//! aliasing inside of a surface, e.g. from high frequency textures or specular highlights. See
+//! `Renderer::set_anti_aliasing()`.
+
+/// The anti-aliasing modes a renderer can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AntiAliasing {
+    /// The scene is rendered with a single sample per pixel.
+    Off,
+
+    /// Multisample anti-aliasing with 2 samples per pixel.
+    Msaa2x,
+
+    /// Multisample anti-aliasing with 4 samples per pixel.
+    Msaa4x,
+
+    /// Multisample anti-aliasing with 8 samples per pixel.
+    Msaa8x,
+}
+
+impl AntiAliasing {
+    /// Returns the number of samples per pixel requested by the mode, or 0 if multisampling is
+    /// disabled.
+    pub fn samples(&self) -> usize {
+        match *self {
+            AntiAliasing::Off => 0,
+            AntiAliasing::Msaa2x => 2,
+            AntiAliasing::Msaa4x => 4,
+            AntiAliasing::Msaa8x => 8,
+        }
+    }
+}
+
+impl Default for AntiAliasing {
+    fn default() -> AntiAliasing {
+        AntiAliasing::Off
+    }
+}
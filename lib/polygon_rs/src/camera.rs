@@ -1,7 +1,25 @@
-use anchor::AnchorId;
+use anchor::{Anchor, AnchorId};
 use math::*;
+use std::f32;
+use texture::GpuTexture;
 
 /// A camera in the scene.
+///
+/// Any number of cameras can be registered with the renderer. Each camera draws the scene into
+/// its own viewport, so cameras with different viewports can be used for split-screen, and a
+/// camera with a small viewport drawn on top of another can be used for picture-in-picture
+/// views like a minimap. Cameras are drawn in order of increasing priority.
+///
+/// A camera can also draw into a render target instead of the window, see `set_target()`.
+///
+/// The aspect ratio isn't updated to match the viewport, so it should be set to the aspect ratio
+/// of the viewport in pixels.
+///
+/// For large scenes, depth precision can be improved with `set_reverse_z()`, which maps the near
+/// plane to a depth of 1 and the far plane to 0, and `set_infinite_far()`, which pushes the far
+/// plane out to infinity so that distant geometry is never clipped.
+///
+/// A camera can draw the scene once for each eye for stereo output, see `set_stereo()`.
 #[derive(Debug, Clone)]
 pub struct Camera
 {
@@ -9,6 +27,18 @@ pub struct Camera
     aspect: f32,
     near: f32,
     far: f32,
+    reverse_z: bool,
+    infinite_far: bool,
+
+    /// The horizontal offset of the frustum's center on the near plane, used by the cameras of
+    /// each eye of a stereo camera to converge, see `eye_camera()`.
+    frustum_shift: f32,
+    stereo: Option<Stereo>,
+
+    viewport: Viewport,
+    clear: CameraClear,
+    priority: i32,
+    target: Option<GpuTexture>,
 
     anchor: Option<AnchorId>,
 }
@@ -21,6 +51,16 @@ impl Camera
             aspect: aspect,
             near: near,
             far: far,
+            reverse_z: false,
+            infinite_far: false,
+
+            frustum_shift: 0.0,
+            stereo: None,
+
+            viewport: Viewport::full(),
+            clear: CameraClear::default(),
+            priority: 0,
+            target: None,
 
             anchor: None,
         }
@@ -30,19 +70,91 @@ impl Camera
     ///
     /// The projection matrix is the matrix that converts from camera space to
     /// clip space. This effectively converts the viewing frustrum into a unit cube.
+    ///
+    /// If the camera uses reverse-Z the near plane is mapped to a depth of 1 and the far plane to
+    /// 0, otherwise they're mapped to -1 and 1. If the camera has an infinite far plane, the far
+    /// plane's depth is only reached at infinity.
     pub fn projection_matrix(&self) -> Matrix4 {
+        self.projection(self.reverse_z)
+    }
+
+    /// Calculates the projection matrix for the camera without reverse-Z, mapping the near plane
+    /// to a depth of -1 and the far plane to 1.
+    ///
+    /// This is the projection used for culling on the CPU, since points in its clip space are
+    /// inside the view frustum if each of `x`, `y`, and `z` is between `-w` and `w` regardless of
+    /// the camera's depth settings.
+    pub fn standard_projection_matrix(&self) -> Matrix4 {
+        self.projection(false)
+    }
+
+    fn projection(&self, reverse_z: bool) -> Matrix4 {
         let height = 2.0 * self.near * (self.fov * 0.5).tan();
         let width = self.aspect * height;
 
+        let (depth_scale, depth_offset) = match (reverse_z, self.infinite_far) {
+            (false, false) => (
+                -(self.far + self.near) / (self.far - self.near),
+                -2.0 * self.far * self.near / (self.far - self.near),
+            ),
+            (false, true) => (-1.0, -2.0 * self.near),
+            (true, false) => (
+                self.near / (self.far - self.near),
+                self.far * self.near / (self.far - self.near),
+            ),
+            (true, true) => (0.0, self.near),
+        };
+
         let mut projection = Matrix4::new();
         projection[0][0] = 2.0 * self.near / width;
+        projection[0][2] = 2.0 * self.frustum_shift / width;
         projection[1][1] = 2.0 * self.near / height;
-        projection[2][2] = -(self.far + self.near) / (self.far - self.near);
-        projection[2][3] = -2.0 * self.far * self.near / (self.far - self.near);
+        projection[2][2] = depth_scale;
+        projection[2][3] = depth_offset;
         projection[3][2] = -1.0;
         projection
     }
 
+    /// Calculates the inverse of `projection_matrix()`, converting points in projection space
+    /// back to view space.
+    pub fn inverse_projection_matrix(&self) -> Matrix4 {
+        let projection = self.projection_matrix();
+
+        let mut inverse = Matrix4::new();
+        inverse[0][0] = 1.0 / projection[0][0];
+        inverse[0][3] = projection[0][2] / projection[0][0];
+        inverse[1][1] = 1.0 / projection[1][1];
+        inverse[2][3] = -1.0;
+        inverse[3][2] = 1.0 / projection[2][3];
+        inverse[3][3] = projection[2][2] / projection[2][3];
+        inverse
+    }
+
+    /// Calculates the world-space ray passing through a point on the camera's viewport.
+    ///
+    /// `x` and `y` are normalized viewport coordinates, where `(0.0, 0.0)` is the top-left corner
+    /// of the viewport and `(1.0, 1.0)` is the bottom-right, matching the convention used for
+    /// mouse coordinates. The coordinates are relative to the camera's viewport rather than the
+    /// whole window, see `Viewport::window_to_viewport()` for converting window coordinates.
+    /// `anchor` must be the anchor the camera is attached to. The ray starts on the near plane
+    /// and its direction is normalized.
+    pub fn ray_from_viewport(&self, anchor: &Anchor, x: f32, y: f32) -> Ray {
+        // Convert to normalized device coordinates, flipping y since NDC has +y going up.
+        let ndc_x = x * 2.0 - 1.0;
+        let ndc_y = 1.0 - y * 2.0;
+
+        // Find the point on the near plane in camera space. The camera looks down the -z axis.
+        let half_height = self.near * (self.fov * 0.5).tan();
+        let half_width = self.aspect * half_height;
+        let near_point = Vector3::new(ndc_x * half_width + self.frustum_shift, ndc_y * half_height, -self.near);
+
+        let offset = anchor.world_orientation() * near_point;
+        Ray {
+            origin: anchor.world_position() + offset,
+            direction: offset.normalized(),
+        }
+    }
+
     pub fn anchor(&self) -> Option<AnchorId> {
         self.anchor
     }
@@ -51,6 +163,214 @@ impl Camera
         self.anchor = Some(anchor_id);
     }
 
+    /// Gets the distance to the near plane.
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    /// Gets the distance to the far plane.
+    ///
+    /// The far plane is still used for sorting and debug views if the camera has an infinite far
+    /// plane, see `set_infinite_far()`.
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    /// Gets whether the camera uses reverse-Z depth.
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    /// Sets whether the camera uses reverse-Z depth.
+    ///
+    /// With reverse-Z the near plane is mapped to a depth of 1 and the far plane to 0, depth is
+    /// cleared to 0, and the depth test keeps the fragments with the greater depth. With a
+    /// floating-point depth buffer this cancels out the precision that the projection
+    /// concentrates right in front of the near plane against the precision that floating-point
+    /// values have close to 0, which avoids z-fighting in the distance of large scenes. The
+    /// depth buffers of the window and of the renderer's intermediate targets are 24-bit
+    /// fixed-point, since depth is copied between them, and gain less from reverse-Z.
+    ///
+    /// Reverse-Z needs OpenGL 4.5 or `GL_ARB_clip_control` to map depth without losing precision.
+    /// Without it reverse-Z still works, but only half of the depth buffer's range is used.
+    ///
+    /// Cameras that draw on top of another camera without clearing depth (see
+    /// `CameraClear::Depth` and `CameraClear::Nothing`) must use the same setting as the camera
+    /// beneath them, since the depth buffer is interpreted differently.
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.reverse_z = reverse_z;
+    }
+
+    /// Gets whether the camera's far plane is at infinity.
+    pub fn infinite_far(&self) -> bool {
+        self.infinite_far
+    }
+
+    /// Sets whether the camera's far plane is at infinity, so that geometry is never clipped for
+    /// being too far away.
+    ///
+    /// Without reverse-Z, depth precision gets worse the farther the far plane is, so infinite
+    /// far planes are best combined with `set_reverse_z()`.
+    pub fn set_infinite_far(&mut self, infinite_far: bool) {
+        self.infinite_far = infinite_far;
+    }
+
+    /// Gets the camera's stereo settings, or `None` if the camera draws a single view.
+    pub fn stereo(&self) -> Option<Stereo> {
+        self.stereo
+    }
+
+    /// Sets the camera's stereo settings, or `None` to draw a single view.
+    ///
+    /// A stereo camera draws the scene once for each eye, with the eyes placed on either side of
+    /// the camera's anchor along its x axis, see `eye_camera()`. Mesh instances are culled once
+    /// for both eyes against a frustum enclosing both of their frustums, so both eyes always draw
+    /// the same mesh instances at the same levels of detail. Shadow maps are also only drawn once.
+    ///
+    /// For side-by-side output the aspect ratio should be set to that of a single eye's half of
+    /// the viewport.
+    pub fn set_stereo(&mut self, stereo: Option<Stereo>) {
+        if let Some(stereo) = stereo {
+            debug_assert!(stereo.eye_separation >= 0.0, "Eye separation must be non-negative: {}", stereo.eye_separation);
+            debug_assert!(stereo.convergence > 0.0, "Convergence distance must be positive: {}", stereo.convergence);
+        }
+        self.stereo = stereo;
+    }
+
+    /// Gets the offset of `eye` from the camera's anchor, in camera space.
+    ///
+    /// Returns zero if the camera isn't a stereo camera.
+    pub fn eye_offset(&self, eye: Eye) -> Vector3 {
+        let half_separation = self.stereo.map(|stereo| stereo.eye_separation * 0.5).unwrap_or(0.0);
+        match eye {
+            Eye::Left => Vector3::new(-half_separation, 0.0, 0.0),
+            Eye::Right => Vector3::new(half_separation, 0.0, 0.0),
+        }
+    }
+
+    /// Creates the camera drawing the view from `eye`.
+    ///
+    /// The eye's camera isn't a stereo camera, and draws into the eye's half of the viewport or
+    /// into the eye's render target, depending on the stereo layout. Its frustum is shifted toward
+    /// the other eye so that both frustums meet at the convergence distance. The eye's camera
+    /// expects an anchor moved by `eye_offset()`, e.g. for `ray_from_viewport()`.
+    ///
+    /// Returns a copy of the camera if it isn't a stereo camera.
+    pub fn eye_camera(&self, eye: Eye) -> Camera {
+        let mut camera = self.clone();
+        let stereo = match self.stereo {
+            Some(stereo) => stereo,
+            None => return camera,
+        };
+
+        camera.stereo = None;
+        camera.frustum_shift = -self.eye_offset(eye).x * self.near / stereo.convergence;
+
+        match stereo.layout {
+            StereoLayout::SideBySide => {
+                let half_width = self.viewport.width * 0.5;
+                let x = match eye {
+                    Eye::Left => self.viewport.x,
+                    Eye::Right => self.viewport.x + half_width,
+                };
+                camera.viewport = Viewport::new(x, self.viewport.y, half_width, self.viewport.height);
+            },
+            StereoLayout::Targets { left, right } => {
+                camera.target = match eye {
+                    Eye::Left => Some(left),
+                    Eye::Right => Some(right),
+                };
+            },
+        }
+
+        camera
+    }
+
+    /// Creates the camera used to cull the scene for both eyes of a stereo camera, along with
+    /// its offset from the camera's anchor in camera space.
+    ///
+    /// The culling camera sits behind the eyes and is just wide enough for its frustum to enclose
+    /// both of theirs, see `set_stereo()`. Returns `None` if the camera isn't a stereo camera.
+    pub(crate) fn culling_camera(&self) -> Option<(Camera, Vector3)> {
+        let stereo = match self.stereo {
+            Some(stereo) => stereo,
+            None => return None,
+        };
+
+        // Each eye's frustum is shifted inward by its distance from the center divided by the
+        // convergence distance, which widens the combined frustum by the same slope on each side.
+        let half_separation = stereo.eye_separation * 0.5;
+        let tan_half_height = (self.fov * 0.5).tan();
+        let tan_half_width = self.aspect * tan_half_height + half_separation / stereo.convergence;
+
+        // Moving back until the frustum's edges pass through the eyes encloses both frustums.
+        let back = half_separation / tan_half_width;
+
+        let mut camera = self.clone();
+        camera.stereo = None;
+        camera.aspect = tan_half_width / tan_half_height;
+        camera.near = self.near + back;
+        camera.far = self.far + back;
+        Some((camera, Vector3::new(0.0, 0.0, back)))
+    }
+
+    /// Gets the region of the window the camera draws to.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Sets the region of the window the camera draws to.
+    ///
+    /// # Panics
+    ///
+    /// - If `viewport` doesn't fit within the window, see `Viewport::new()`.
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        viewport.assert_valid();
+        self.viewport = viewport;
+    }
+
+    /// Gets how the camera's viewport is cleared before the camera draws.
+    pub fn clear(&self) -> CameraClear {
+        self.clear
+    }
+
+    /// Sets how the camera's viewport is cleared before the camera draws.
+    pub fn set_clear(&mut self, clear: CameraClear) {
+        self.clear = clear;
+    }
+
+    /// Gets the camera's priority.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Gets the render target the camera draws into, or `None` if it draws to the window.
+    pub fn target(&self) -> Option<GpuTexture> {
+        self.target
+    }
+
+    /// Sets the render target the camera draws into, or `None` to draw to the window.
+    ///
+    /// `target` must have been created with `Renderer::register_render_target()`. The camera's
+    /// viewport is then relative to the render target instead of the window. Cameras with a render
+    /// target are drawn before all cameras drawing to the window, and their priorities only order
+    /// them relative to each other.
+    ///
+    /// A camera must not see any surfaces that display its own render target, since a texture
+    /// can't be sampled while it's being drawn into.
+    pub fn set_target(&mut self, target: Option<GpuTexture>) {
+        self.target = target;
+    }
+
+    /// Sets the camera's priority.
+    ///
+    /// Cameras with a lower priority are drawn first, so cameras with a higher priority are
+    /// drawn on top of them. Cameras with the same priority are drawn in the order they were
+    /// registered. The default priority is 0.
+    pub fn set_priority(&mut self, priority: i32) {
+        self.priority = priority;
+    }
+
     pub fn set_fov(&mut self, fov: f32) {
         debug_assert!(fov > 0.0, "Field of view must be non-negative: {}", fov);
         debug_assert!(fov < PI * 2.0, "Field of view must be less than 180 degrees: {}", fov);
@@ -83,12 +403,140 @@ impl Default for Camera {
             aspect: 1.0,
             near: 0.001,
             far: 1_000.0,
+            reverse_z: false,
+            infinite_far: false,
+
+            frustum_shift: 0.0,
+            stereo: None,
+
+            viewport: Viewport::full(),
+            clear: CameraClear::default(),
+            priority: 0,
+            target: None,
 
             anchor: None,
         }
     }
 }
 
+/// The region of the window a camera draws to.
+///
+/// The rectangle is given in normalized window coordinates, where `(0.0, 0.0)` is the top-left
+/// corner of the window and `(1.0, 1.0)` is the bottom-right, the same convention used by
+/// `Camera::ray_from_viewport()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    /// Creates a new viewport with its top-left corner at `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// - If `width` or `height` isn't positive.
+    /// - If any part of the viewport is outside of the window.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Viewport {
+        let viewport = Viewport {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        };
+        viewport.assert_valid();
+        viewport
+    }
+
+    /// Creates a viewport covering the whole window.
+    pub fn full() -> Viewport {
+        Viewport::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    /// Converts the point `(x, y)` from normalized window coordinates to coordinates relative to
+    /// the viewport, as expected by `Camera::ray_from_viewport()`.
+    ///
+    /// Returns `None` if the point is outside of the viewport.
+    pub fn window_to_viewport(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        let local_x = (x - self.x) / self.width;
+        let local_y = (y - self.y) / self.height;
+        if local_x >= 0.0 && local_x <= 1.0 && local_y >= 0.0 && local_y <= 1.0 {
+            Some((local_x, local_y))
+        } else {
+            None
+        }
+    }
+
+    fn assert_valid(&self) {
+        assert!(
+            self.width > 0.0 && self.height > 0.0,
+            "Viewport must have a positive size, width: {}, height: {}",
+            self.width,
+            self.height,
+        );
+        assert!(
+            self.x >= 0.0 && self.y >= 0.0 && self.x + self.width <= 1.0 && self.y + self.height <= 1.0,
+            "Viewport must be within the window: {:?}",
+            self,
+        );
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Viewport {
+        Viewport::full()
+    }
+}
+
+/// How a camera's viewport is cleared before the camera draws.
+///
+/// The renderer doesn't clear the window by itself, so every part of the window should be
+/// cleared by the first camera drawing to it each frame. Overlay cameras drawn on top of another
+/// camera, e.g. for a HUD or a first-person weapon, can use `Depth` or `Nothing` to keep what's
+/// already been drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraClear {
+    /// Clears color to the specified color and clears depth.
+    Color(Color),
+
+    /// Fills color with the environment map seen from the camera (see
+    /// `Renderer::set_environment_map()`) and clears depth. Clears color to black if there's no
+    /// environment map or the camera doesn't have an anchor.
+    Skybox,
+
+    /// Only clears depth, so the scene is drawn over whatever earlier cameras drew without being
+    /// hidden by it.
+    Depth,
+
+    /// Doesn't clear anything, so the scene is depth tested against what earlier cameras drew.
+    Nothing,
+}
+
+impl Default for CameraClear {
+    /// Clears to black.
+    fn default() -> CameraClear {
+        CameraClear::Color(Color::rgb(0.0, 0.0, 0.0))
+    }
+}
+
+/// A half-line in world space, e.g. for picking objects under the mouse.
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Point,
+
+    /// The direction of the ray, normalized.
+    pub direction: Vector3,
+}
+
+impl Ray {
+    /// Returns the point `distance` units along the ray.
+    pub fn point_at(&self, distance: f32) -> Point {
+        self.origin + self.direction * distance
+    }
+}
+
 /// Identifies an achor that has been registered with the renderer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct CameraId(usize);
@@ -0,0 +1,54 @@
+//! Debug visualization of the scene.
+//!
+//! While a debug mode other than `DebugMode::Off` is set (see `Renderer::set_debug_mode()`) the
+//! built-in `"opaque"` pass draws every mesh instance, opaque or transparent, with a single
+//! debug program instead of its material, and the built-in `"transparent"` pass draws nothing.
+//! Lighting, materials, and instancing are skipped entirely, so the result shows the geometry
+//! that's submitted to the GPU and nothing else. Shadows, post-processing, custom passes, and
+//! debug primitives are still rendered as usual, so HDR exposure applies to the debug colors.
+
+/// The ways the renderer can visualize the scene for debugging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugMode {
+    /// The scene is rendered normally.
+    Off,
+
+    /// The edges of every triangle are drawn in white, including back faces.
+    Wireframe,
+
+    /// The view-space normal of each surface is drawn as a color, mapping each component from
+    /// `[-1, 1]` to `[0, 1]`. Normal maps aren't applied.
+    Normals,
+
+    /// The first set of texture coordinates is drawn with u in the red channel and v in the green
+    /// channel. Coordinates outside of `[0, 1]` wrap.
+    Uvs,
+
+    /// The linear view depth of each surface is drawn in grayscale, from white at the camera to
+    /// black at the camera's far plane.
+    Depth,
+
+    /// Every fragment drawn adds a fixed amount to its pixel with depth testing disabled, so
+    /// brighter areas are drawn to more times per frame.
+    Overdraw,
+}
+
+impl DebugMode {
+    /// Returns the value of the mode as passed to the debug program.
+    pub(crate) fn index(&self) -> i32 {
+        match *self {
+            DebugMode::Off => 0,
+            DebugMode::Wireframe => 1,
+            DebugMode::Normals => 2,
+            DebugMode::Uvs => 3,
+            DebugMode::Depth => 4,
+            DebugMode::Overdraw => 5,
+        }
+    }
+}
+
+impl Default for DebugMode {
+    fn default() -> DebugMode {
+        DebugMode::Off
+    }
+}
@@ -1,4 +1,5 @@
 use math::*;
+use skeleton::MAX_JOINTS;
 
 pub type MeshIndex = u32;
 
@@ -6,14 +7,25 @@ pub type MeshIndex = u32;
 ///
 /// Meshes are represented as list of vertex positions and a list of faces.
 /// Each face is represented as 3 indices into the vertex array.
+///
+/// The faces are divided into one or more submeshes, each of which is drawn with the material
+/// in its material slot. A mesh built without explicit submeshes has a single submesh covering
+/// all of its indices.
 #[derive(Debug, Clone)]
 pub struct Mesh {
     vertex_data: Vec<f32>,
     indices:     Vec<MeshIndex>,
+    submeshes:   Vec<Submesh>,
+    vertex_count: usize,
 
     position: VertexAttribute,
     normal:   Option<VertexAttribute>,
+    tangent:  Option<VertexAttribute>,
     texcoord: Vec<VertexAttribute>,
+    color:    Option<VertexAttribute>,
+
+    joint_indices: Option<VertexAttribute>,
+    joint_weights: Option<VertexAttribute>,
 }
 
 impl Mesh {
@@ -29,13 +41,90 @@ impl Mesh {
         self.position
     }
 
+    /// Gets the number of vertices in the mesh.
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    /// Gets the position of each vertex, read from the vertex data.
+    pub fn positions(&self) -> Vec<Point> {
+        let attribute = self.position;
+        let stride = if attribute.stride == 0 { attribute.elements } else { attribute.stride };
+
+        (0..self.vertex_count)
+            .map(|vertex| {
+                let start = attribute.offset + vertex * stride;
+                Point::from(&self.vertex_data[start..start + attribute.elements])
+            })
+            .collect()
+    }
+
+    /// Gets the smallest axis-aligned box containing every vertex, as its minimum and maximum
+    /// corners.
+    ///
+    /// A mesh without vertices has an empty box at the origin.
+    pub fn bounds(&self) -> (Point, Point) {
+        let positions = self.positions();
+        if positions.is_empty() {
+            return (Point::origin(), Point::origin());
+        }
+
+        positions.iter().fold((Point::max(), Point::min()), |(min, max), position| {
+            (
+                Point::new(min.x.min(position.x), min.y.min(position.y), min.z.min(position.z)),
+                Point::new(max.x.max(position.x), max.y.max(position.y), max.z.max(position.z)),
+            )
+        })
+    }
+
     pub fn normal(&self) -> Option<VertexAttribute> {
         self.normal
     }
 
+    /// Gets the attribute holding the tangent of each vertex, used for normal mapping.
+    ///
+    /// Tangents have 4 elements, the 4th of which is the handedness of the bitangent, see
+    /// `Vertex::tangent`.
+    pub fn tangent(&self) -> Option<VertexAttribute> {
+        self.tangent
+    }
+
     pub fn texcoord(&self) -> &[VertexAttribute] {
         &*self.texcoord
     }
+
+    pub fn color(&self) -> Option<VertexAttribute> {
+        self.color
+    }
+
+    /// Gets the attribute holding the indices of the joints that influence each vertex.
+    ///
+    /// Joint indices are stored as floats alongside the rest of the vertex data.
+    pub fn joint_indices(&self) -> Option<VertexAttribute> {
+        self.joint_indices
+    }
+
+    /// Gets the attribute holding the weight of each joint that influences each vertex.
+    pub fn joint_weights(&self) -> Option<VertexAttribute> {
+        self.joint_weights
+    }
+
+    pub fn submeshes(&self) -> &[Submesh] {
+        &*self.submeshes
+    }
+}
+
+/// A range of a mesh's indices that is drawn with a single material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Submesh {
+    /// The first index of the submesh.
+    pub start: usize,
+
+    /// The number of indices in the submesh.
+    pub count: usize,
+
+    /// The material slot used to draw the submesh, see `MeshInstance::set_slot_material()`.
+    pub material_slot: usize,
 }
 
 /// Represents a single vertex in a mesh with all of its supported attributes.
@@ -44,9 +133,25 @@ pub struct Vertex {
     pub position: Point,
     pub normal: Option<Vector3>,
 
+    /// The direction of increasing u texture coordinate, used to orient normal maps.
+    ///
+    /// The 4th element is either 1.0 or -1.0, and gives the direction of the bitangent as
+    /// `cross(normal, tangent) * w`. Tangents can be generated from the normals and texcoords with
+    /// `MeshBuilder::generate_tangents()`.
+    pub tangent: Option<[f32; 4]>,
+
     /// Support an arbitrary number of texture units. The actual maximum is dependent on hardware
     /// and so is not limited by polygon directly. If the number of
     pub texcoord: Vec<Vector2>,
+
+    pub color: Option<Color>,
+
+    /// The indices of up to 4 joints in the mesh's skeleton that move the vertex.
+    pub joint_indices: Option<[u32; 4]>,
+
+    /// The weight of each joint in `joint_indices`, which should sum to 1.0. Unused joints
+    /// should have a weight of 0.0.
+    pub joint_weights: Option<[f32; 4]>,
 }
 
 impl Vertex {
@@ -54,7 +159,11 @@ impl Vertex {
         Vertex {
             position: position,
             normal: None,
+            tangent: None,
             texcoord: Vec::new(),
+            color: None,
+            joint_indices: None,
+            joint_weights: None,
         }
     }
 }
@@ -86,13 +195,29 @@ pub enum BuildMeshError {
         expected: usize,
         actual: usize,
     },
+
+    /// Indicates that a submesh's index range extends past the end of the index data.
+    SubmeshOutOfBounds {
+        submesh: Submesh,
+        index_count: usize,
+    },
+
+    /// Indicates that a vertex references a joint index of `skeleton::MAX_JOINTS` or more.
+    JointIndexOutOfBounds {
+        vertex: usize,
+        joint_index: u32,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum VertexAttributeType {
     Position,
     Normal,
+    Tangent,
     Texcoord,
+    Color,
+    JointIndices,
+    JointWeights,
 }
 
 /// Provides a safe interface for building a mesh from raw vertex data.
@@ -107,13 +232,21 @@ pub enum VertexAttributeType {
 /// - Check for different data count for different attributes (e.g. if the position attribute data
 ///   for a different number of elements than the normal attribute).
 /// - Any of the indicies would be out of bounds for the given vertex data.
+/// - Any of the submeshes would be out of bounds for the given index data.
+/// - Any of the joint indices would be out of bounds for the largest supported skeleton. Joint
+///   indices and joint weights must be specified together.
 #[derive(Debug, Clone)]
 pub struct MeshBuilder {
     position_data: Vec<Point>,
     normal_data: Vec<Vector3>,
+    tangent_data: Vec<[f32; 4]>,
     texcoord_data: Vec<Vector2>,
+    color_data: Vec<Color>,
+    joint_index_data: Vec<[u32; 4]>,
+    joint_weight_data: Vec<[f32; 4]>,
 
     indices:  Vec<u32>,
+    submeshes: Vec<Submesh>,
 }
 
 impl MeshBuilder {
@@ -121,8 +254,13 @@ impl MeshBuilder {
         MeshBuilder {
             position_data: Vec::new(),
             normal_data:   Vec::new(),
+            tangent_data:  Vec::new(),
             texcoord_data: Vec::new(),
+            color_data:    Vec::new(),
+            joint_index_data: Vec::new(),
+            joint_weight_data: Vec::new(),
             indices:       Vec::new(),
+            submeshes:     Vec::new(),
         }
     }
 
@@ -133,12 +271,28 @@ impl MeshBuilder {
             self.normal_data.push(normal);
         }
 
+        if let Some(tangent) = vertex.tangent {
+            self.tangent_data.push(tangent);
+        }
+
         assert!(vertex.texcoord.len() <= 1, "More than one texcoord per vertex is currently not supported");
 
         // Add each texcoord to its corresponding list.
         if vertex.texcoord.len() > 0 {
             self.texcoord_data.push(vertex.texcoord[0])
         }
+
+        if let Some(color) = vertex.color {
+            self.color_data.push(color);
+        }
+
+        if let Some(joint_indices) = vertex.joint_indices {
+            self.joint_index_data.push(joint_indices);
+        }
+
+        if let Some(joint_weights) = vertex.joint_weights {
+            self.joint_weight_data.push(joint_weights);
+        }
     }
 
     pub fn add_index(mut self, index: MeshIndex) -> MeshBuilder {
@@ -158,18 +312,110 @@ impl MeshBuilder {
         self
     }
 
+    pub fn set_tangent_data(mut self, tangent_data: &[[f32; 4]]) -> MeshBuilder {
+        self.tangent_data.clear();
+        self.tangent_data.extend(tangent_data);
+        self
+    }
+
     pub fn set_texcoord_data(mut self, texcoord_data: &[Vector2]) -> MeshBuilder {
         self.texcoord_data.clear();
         self.texcoord_data.extend(texcoord_data);
         self
     }
 
+    pub fn set_color_data(mut self, color_data: &[Color]) -> MeshBuilder {
+        self.color_data.clear();
+        self.color_data.extend(color_data);
+        self
+    }
+
+    pub fn set_joint_index_data(mut self, joint_index_data: &[[u32; 4]]) -> MeshBuilder {
+        self.joint_index_data.clear();
+        self.joint_index_data.extend(joint_index_data);
+        self
+    }
+
+    pub fn set_joint_weight_data(mut self, joint_weight_data: &[[f32; 4]]) -> MeshBuilder {
+        self.joint_weight_data.clear();
+        self.joint_weight_data.extend(joint_weight_data);
+        self
+    }
+
     pub fn set_indices(mut self, indices: &[u32]) -> MeshBuilder {
         self.indices.clear();
         self.indices.extend(indices);
         self
     }
 
+    /// Generates tangents from the normals, texcoords, and indices, replacing any existing
+    /// tangent data.
+    ///
+    /// Each vertex's tangent is the average of the tangents of the triangles using it,
+    /// orthogonalized against the vertex's normal. Nothing is generated unless every vertex has
+    /// a normal and a texcoord, so this should be called after all other data has been set.
+    pub fn generate_tangents(mut self) -> MeshBuilder {
+        let vertex_count = self.position_data.len();
+        if self.normal_data.len() != vertex_count || self.texcoord_data.len() != vertex_count {
+            return self;
+        }
+
+        // Accumulate the directions of increasing u and v for every triangle using each vertex.
+        let mut u_directions = vec![Vector3::zero(); vertex_count];
+        let mut v_directions = vec![Vector3::zero(); vertex_count];
+        for triangle in self.indices.chunks(3) {
+            if triangle.len() < 3 || triangle.iter().any(|&index| index as usize >= vertex_count) {
+                continue;
+            }
+
+            let (i0, i1, i2) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+
+            let edge1 = self.position_data[i1] - self.position_data[i0];
+            let edge2 = self.position_data[i2] - self.position_data[i0];
+            let uv1 = self.texcoord_data[i1] - self.texcoord_data[i0];
+            let uv2 = self.texcoord_data[i2] - self.texcoord_data[i0];
+
+            let determinant = uv1.x * uv2.y - uv2.x * uv1.y;
+            if determinant == 0.0 {
+                continue;
+            }
+            let r = 1.0 / determinant;
+
+            let u_direction = (edge1 * uv2.y - edge2 * uv1.y) * r;
+            let v_direction = (edge2 * uv1.x - edge1 * uv2.x) * r;
+            for &index in &[i0, i1, i2] {
+                u_directions[index] += u_direction;
+                v_directions[index] += v_direction;
+            }
+        }
+
+        self.tangent_data = self.normal_data
+            .iter()
+            .zip(u_directions.iter().zip(v_directions.iter()))
+            .map(|(&normal, (&u_direction, &v_direction))| {
+                // Gram-Schmidt orthogonalize the tangent against the normal.
+                let tangent = (u_direction - normal * normal.dot(u_direction)).normalized();
+                let handedness = if Vector3::cross(normal, tangent).dot(v_direction) < 0.0 { -1.0 } else { 1.0 };
+                [tangent.x, tangent.y, tangent.z, handedness]
+            })
+            .collect();
+
+        self
+    }
+
+    /// Adds a submesh covering `count` indices starting at `start`.
+    ///
+    /// If no submeshes are added the mesh will have a single submesh covering all of its indices
+    /// using material slot 0.
+    pub fn add_submesh(mut self, start: usize, count: usize, material_slot: usize) -> MeshBuilder {
+        self.submeshes.push(Submesh {
+            start: start,
+            count: count,
+            material_slot: material_slot,
+        });
+        self
+    }
+
     pub fn build(self) -> Result<Mesh, BuildMeshError> {
         // The vertex count is defined by the position data, since position is the only required
         // vertex attribute.
@@ -184,6 +430,14 @@ impl MeshBuilder {
             });
         }
 
+        if self.tangent_data.len() != 0 && self.tangent_data.len() != vertex_count {
+            return Err(BuildMeshError::IncorrectAttributeCount {
+                attribute: VertexAttributeType::Tangent,
+                expected: vertex_count,
+                actual: self.tangent_data.len(),
+            });
+        }
+
         if self.texcoord_data.len() != 0 && self.texcoord_data.len() != vertex_count {
             return Err(BuildMeshError::IncorrectAttributeCount {
                 attribute: VertexAttributeType::Texcoord,
@@ -192,6 +446,45 @@ impl MeshBuilder {
             });
         }
 
+        if self.color_data.len() != 0 && self.color_data.len() != vertex_count {
+            return Err(BuildMeshError::IncorrectAttributeCount {
+                attribute: VertexAttributeType::Color,
+                expected: vertex_count,
+                actual: self.color_data.len(),
+            });
+        }
+
+        // Joint indices and weights are only meaningful together, so if either is present both
+        // must have data for every vertex.
+        let is_skinned = self.joint_index_data.len() != 0 || self.joint_weight_data.len() != 0;
+
+        if is_skinned && self.joint_index_data.len() != vertex_count {
+            return Err(BuildMeshError::IncorrectAttributeCount {
+                attribute: VertexAttributeType::JointIndices,
+                expected: vertex_count,
+                actual: self.joint_index_data.len(),
+            });
+        }
+
+        if is_skinned && self.joint_weight_data.len() != vertex_count {
+            return Err(BuildMeshError::IncorrectAttributeCount {
+                attribute: VertexAttributeType::JointWeights,
+                expected: vertex_count,
+                actual: self.joint_weight_data.len(),
+            });
+        }
+
+        for (vertex, joint_indices) in self.joint_index_data.iter().enumerate() {
+            for &joint_index in joint_indices {
+                if joint_index as usize >= MAX_JOINTS {
+                    return Err(BuildMeshError::JointIndexOutOfBounds {
+                        vertex: vertex,
+                        joint_index: joint_index,
+                    });
+                }
+            }
+        }
+
         // Make sure all indices at least point to a valid vertex.
         for index in self.indices.iter().cloned() {
             if index >= vertex_count as MeshIndex {
@@ -202,12 +495,26 @@ impl MeshBuilder {
             }
         }
 
+        for submesh in &self.submeshes {
+            let end = submesh.start.checked_add(submesh.count);
+            if end.map_or(true, |end| end > self.indices.len()) {
+                return Err(BuildMeshError::SubmeshOutOfBounds {
+                    submesh: *submesh,
+                    index_count: self.indices.len(),
+                });
+            }
+        }
+
         // TODO: Check for degenerate triangles? Actually, should that be a failure or a warning?
 
         let float_count =
             self.position_data.len() * 4
           + self.normal_data.len() * 3
-          + self.texcoord_data.len() * 2;
+          + self.tangent_data.len() * 4
+          + self.texcoord_data.len() * 2
+          + self.color_data.len() * 4
+          + self.joint_index_data.len() * 4
+          + self.joint_weight_data.len() * 4;
 
         // Create the mesh.
         let mut vertex_data = Vec::<f32>::with_capacity(float_count);
@@ -234,6 +541,22 @@ impl MeshBuilder {
             None
         };
 
+        // Setup tangent data.
+        let tangent_attrib = if self.tangent_data.len() > 0 {
+            let attrib = VertexAttribute {
+                elements: 4,
+                offset: vertex_data.len(),
+                stride: 0,
+            };
+            for tangent in &self.tangent_data {
+                vertex_data.extend(tangent);
+            }
+
+            Some(attrib)
+        } else {
+            None
+        };
+
         // Setup texcoord data.
         let mut texcoord_attribs = Vec::new();
         if self.texcoord_data.len() > 0 {
@@ -245,14 +568,112 @@ impl MeshBuilder {
             vertex_data.extend(Vector2::as_ref(&*self.texcoord_data));
         }
 
+        // Setup color data.
+        let color_attrib = if self.color_data.len() > 0 {
+            let attrib = VertexAttribute {
+                elements: 4,
+                offset: vertex_data.len(),
+                stride: 0,
+            };
+            for color in &self.color_data {
+                vertex_data.extend(color.as_ref());
+            }
+
+            Some(attrib)
+        } else {
+            None
+        };
+
+        // Setup joint data.
+        let (joint_index_attrib, joint_weight_attrib) = if is_skinned {
+            let index_attrib = VertexAttribute {
+                elements: 4,
+                offset: vertex_data.len(),
+                stride: 0,
+            };
+            for joint_indices in &self.joint_index_data {
+                vertex_data.extend(joint_indices.iter().map(|&joint_index| joint_index as f32));
+            }
+
+            let weight_attrib = VertexAttribute {
+                elements: 4,
+                offset: vertex_data.len(),
+                stride: 0,
+            };
+            for joint_weights in &self.joint_weight_data {
+                vertex_data.extend(joint_weights);
+            }
+
+            (Some(index_attrib), Some(weight_attrib))
+        } else {
+            (None, None)
+        };
+
+        // Default to a single submesh covering the whole mesh.
+        let submeshes = if self.submeshes.len() > 0 {
+            self.submeshes
+        } else {
+            vec![Submesh {
+                start: 0,
+                count: self.indices.len(),
+                material_slot: 0,
+            }]
+        };
+
         // By our powers combined! We are! A mesh.
         Ok(Mesh {
             vertex_data: vertex_data,
             indices: self.indices,
+            submeshes: submeshes,
+            vertex_count: vertex_count,
 
             position: position_attrib,
             normal: normal_attrib,
+            tangent: tangent_attrib,
             texcoord: texcoord_attribs,
+            color: color_attrib,
+
+            joint_indices: joint_index_attrib,
+            joint_weights: joint_weight_attrib,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::usize;
+
+    fn triangle() -> MeshBuilder {
+        MeshBuilder::new()
+            .set_position_data(&[
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+            ])
+            .set_indices(&[0, 1, 2])
+    }
+
+    #[test]
+    fn submesh_in_bounds() {
+        let mesh = triangle().add_submesh(0, 3, 0).build().unwrap();
+        assert_eq!(&[Submesh { start: 0, count: 3, material_slot: 0 }], mesh.submeshes());
+    }
+
+    #[test]
+    fn submesh_out_of_bounds() {
+        for &(start, count) in &[(0, 4), (3, 1), (usize::MAX, 2)] {
+            match triangle().add_submesh(start, count, 0).build() {
+                Err(BuildMeshError::SubmeshOutOfBounds { submesh, index_count: 3 }) => {
+                    assert_eq!((start, count), (submesh.start, submesh.count));
+                },
+                result => panic!(
+                    "Expected SubmeshOutOfBounds for ({}, {}), got {:?}",
+                    start,
+                    count,
+                    result,
+                ),
+            }
+        }
+    }
+}
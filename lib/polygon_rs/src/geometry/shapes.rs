@@ -0,0 +1,483 @@
+//! Procedurally generated meshes.
+//!
+//! `MeshBuilder` can generate a few common shapes, so that tests, prototypes, and debug geometry
+//! don't need to load mesh assets. Every shape is centered on the origin with +Y up, and has
+//! normals, a single set of texcoords, and tangents. Front faces are wound counter-clockwise.
+//!
+//! The generators return a `MeshBuilder` rather than a `Mesh` so that more vertex data, e.g.
+//! colors, can be added before calling `build()`.
+
+use geometry::mesh::{MeshBuilder, MeshIndex};
+use math::*;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+impl MeshBuilder {
+    /// Generates an axis-aligned cube with edges of length `size`.
+    ///
+    /// Each face has its own vertices so that its normals are flat, and is textured with the
+    /// full `[0, 1]` texcoord range.
+    ///
+    /// # Panics
+    ///
+    /// - If `size` isn't positive.
+    pub fn cube(size: f32) -> MeshBuilder {
+        assert!(size > 0.0, "Cube size must be positive, but was {}", size);
+
+        let half = size * 0.5;
+        let faces = [
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Vector3::new(0.0, -1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Vector3::new(0.0, 0.0, -1.0), Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+        ];
+
+        let mut shape = Shape::new();
+        for &(normal, u_axis, v_axis) in &faces {
+            let corner = Point::from((normal - u_axis - v_axis) * half);
+            shape.add_grid(corner, u_axis * size, v_axis * size, normal, 1, 1);
+        }
+
+        shape.into_builder()
+    }
+
+    /// Generates a flat plane in the XZ plane facing +Y, `width` long along the X axis and
+    /// `depth` long along the Z axis.
+    ///
+    /// The plane is divided into `subdivisions` quads along each axis. Texcoords span `[0, 1]`
+    /// across the whole plane, with v increasing towards -Z.
+    ///
+    /// # Panics
+    ///
+    /// - If `width` or `depth` isn't positive.
+    /// - If `subdivisions` is 0.
+    pub fn plane(width: f32, depth: f32, subdivisions: usize) -> MeshBuilder {
+        assert!(width > 0.0 && depth > 0.0, "Plane size must be positive, but was {}x{}", width, depth);
+        assert!(subdivisions > 0, "Plane must have at least 1 subdivision");
+
+        let mut shape = Shape::new();
+        shape.add_grid(
+            Point::new(-width * 0.5, 0.0, depth * 0.5),
+            Vector3::new(width, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, -depth),
+            Vector3::new(0.0, 1.0, 0.0),
+            subdivisions,
+            subdivisions,
+        );
+
+        shape.into_builder()
+    }
+
+    /// Generates a sphere made of `segments` slices around the Y axis and `rings` stacks from
+    /// pole to pole.
+    ///
+    /// Texcoords are an equirectangular mapping, with u going around the Y axis starting at +Z
+    /// and v going from 0 at the bottom pole to 1 at the top pole.
+    ///
+    /// # Panics
+    ///
+    /// - If `radius` isn't positive.
+    /// - If `segments` is less than 3 or `rings` is less than 2.
+    pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> MeshBuilder {
+        assert!(radius > 0.0, "Sphere radius must be positive, but was {}", radius);
+        assert!(segments >= 3, "Sphere must have at least 3 segments, but had {}", segments);
+        assert!(rings >= 2, "Sphere must have at least 2 rings, but had {}", rings);
+
+        let profile: Vec<ProfilePoint> = (0..rings + 1)
+            .map(|ring| {
+                let polar = ring as f32 / rings as f32 * PI;
+                let sin = polar_sin(polar);
+                ProfilePoint {
+                    radius: radius * sin,
+                    y: radius * polar.cos(),
+                    normal: (sin, polar.cos()),
+                    v: 1.0 - ring as f32 / rings as f32,
+                }
+            })
+            .collect();
+
+        let mut shape = Shape::new();
+        shape.add_revolution(&profile, segments);
+        shape.into_builder()
+    }
+
+    /// Generates a sphere by subdividing the faces of an icosahedron `subdivisions` times.
+    ///
+    /// Unlike a UV sphere the triangles of an icosphere are all close to the same size. Texcoords
+    /// use the same equirectangular mapping as `uv_sphere()`, with vertices duplicated along the
+    /// seam at u = 0.
+    ///
+    /// # Panics
+    ///
+    /// - If `radius` isn't positive.
+    pub fn icosphere(radius: f32, subdivisions: usize) -> MeshBuilder {
+        assert!(radius > 0.0, "Sphere radius must be positive, but was {}", radius);
+
+        let t = (1.0 + 5.0f32.sqrt()) * 0.5;
+        let mut directions: Vec<Vector3> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ].iter().map(|&direction| Vector3::from(direction).normalized()).collect();
+
+        let mut triangles: Vec<[usize; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        // Split each triangle into 4, sharing the new vertex on each edge between the triangles
+        // on either side of it.
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |first: usize, second: usize, directions: &mut Vec<Vector3>| -> usize {
+                let key = if first < second { (first, second) } else { (second, first) };
+                *midpoints.entry(key).or_insert_with(|| {
+                    let direction = ((directions[first] + directions[second]) * 0.5).normalized();
+                    directions.push(direction);
+                    directions.len() - 1
+                })
+            };
+
+            let mut subdivided = Vec::with_capacity(triangles.len() * 4);
+            for triangle in &triangles {
+                let (a, b, c) = (triangle[0], triangle[1], triangle[2]);
+                let ab = midpoint(a, b, &mut directions);
+                let bc = midpoint(b, c, &mut directions);
+                let ca = midpoint(c, a, &mut directions);
+
+                subdivided.push([a, ab, ca]);
+                subdivided.push([b, bc, ab]);
+                subdivided.push([c, ca, bc]);
+                subdivided.push([ab, bc, ca]);
+            }
+            triangles = subdivided;
+        }
+
+        let texcoord = |direction: Vector3| {
+            let u = direction.x.atan2(direction.z) / (2.0 * PI);
+            Vector2::new(if u < 0.0 { u + 1.0 } else { u }, 0.5 + direction.y.asin() / PI)
+        };
+
+        let mut shape = Shape::new();
+        for &direction in &directions {
+            shape.add_vertex(Point::from(direction * radius), direction, texcoord(direction));
+        }
+
+        // Triangles crossing the seam would interpolate u across the whole texture, so the
+        // vertices on the low side of the seam are duplicated with u shifted past 1.
+        let mut wrapped = HashMap::new();
+        for triangle in &triangles {
+            let us: Vec<f32> = triangle.iter().map(|&index| shape.texcoords[index].x).collect();
+            let crosses_seam = us.iter().cloned().fold(0.0f32, f32::max) - us.iter().cloned().fold(1.0f32, f32::min) > 0.5;
+
+            let mut indices = [0; 3];
+            for (corner, &index) in triangle.iter().enumerate() {
+                indices[corner] = if crosses_seam && shape.texcoords[index].x < 0.5 {
+                    *wrapped.entry(index).or_insert_with(|| {
+                        let position = shape.positions[index];
+                        let normal = shape.normals[index];
+                        let texcoord = shape.texcoords[index];
+                        shape.add_vertex(position, normal, Vector2::new(texcoord.x + 1.0, texcoord.y))
+                    })
+                } else {
+                    index as MeshIndex
+                };
+            }
+
+            shape.add_triangle(indices[0], indices[1], indices[2]);
+        }
+
+        shape.into_builder()
+    }
+
+    /// Generates a cylinder along the Y axis with flat caps on both ends.
+    ///
+    /// The side is textured with u going around the Y axis starting at +Z and v going from 0 at
+    /// the bottom to 1 at the top. Each cap is textured with a planar projection of the `[0, 1]`
+    /// texcoord range onto its circle.
+    ///
+    /// # Panics
+    ///
+    /// - If `radius` or `height` isn't positive.
+    /// - If `segments` is less than 3.
+    pub fn cylinder(radius: f32, height: f32, segments: usize) -> MeshBuilder {
+        assert!(radius > 0.0, "Cylinder radius must be positive, but was {}", radius);
+        assert!(height > 0.0, "Cylinder height must be positive, but was {}", height);
+        assert!(segments >= 3, "Cylinder must have at least 3 segments, but had {}", segments);
+
+        let half_height = height * 0.5;
+
+        let mut shape = Shape::new();
+        shape.add_revolution(
+            &[
+                ProfilePoint { radius: radius, y: half_height, normal: (1.0, 0.0), v: 1.0 },
+                ProfilePoint { radius: radius, y: -half_height, normal: (1.0, 0.0), v: 0.0 },
+            ],
+            segments,
+        );
+
+        for &(y, facing) in &[(half_height, 1.0), (-half_height, -1.0)] {
+            let normal = Vector3::new(0.0, facing, 0.0);
+            let center = shape.add_vertex(Point::new(0.0, y, 0.0), normal, Vector2::new(0.5, 0.5));
+
+            let first = center + 1;
+            for segment in 0..segments + 1 {
+                let angle = segment as f32 / segments as f32 * 2.0 * PI;
+                let (x, z) = (angle.sin(), angle.cos());
+
+                // Seen from outside, the top cap's texture has -Z up and the bottom cap's has +Z
+                // up, so neither is mirrored.
+                shape.add_vertex(
+                    Point::new(x * radius, y, z * radius),
+                    normal,
+                    Vector2::new(0.5 + x * 0.5, 0.5 - z * 0.5 * facing),
+                );
+            }
+
+            for segment in 0..segments as MeshIndex {
+                if facing > 0.0 {
+                    shape.add_triangle(center, first + segment, first + segment + 1);
+                } else {
+                    shape.add_triangle(center, first + segment + 1, first + segment);
+                }
+            }
+        }
+
+        shape.into_builder()
+    }
+
+    /// Generates a capsule along the Y axis, made of a cylinder with a hemisphere on each end.
+    ///
+    /// `height` is the total height of the capsule including the hemispheres, and each
+    /// hemisphere is made of `rings` stacks. Texcoords are mapped like `uv_sphere()`, with v
+    /// spaced by distance along the surface so that the texture isn't stretched.
+    ///
+    /// # Panics
+    ///
+    /// - If `radius` isn't positive or `height` is less than `2.0 * radius`.
+    /// - If `segments` is less than 3 or `rings` is 0.
+    pub fn capsule(radius: f32, height: f32, segments: usize, rings: usize) -> MeshBuilder {
+        assert!(radius > 0.0, "Capsule radius must be positive, but was {}", radius);
+        assert!(
+            height >= 2.0 * radius,
+            "Capsule height must be at least twice its radius, but was {} with radius {}",
+            height,
+            radius);
+        assert!(segments >= 3, "Capsule must have at least 3 segments, but had {}", segments);
+        assert!(rings > 0, "Capsule must have at least 1 ring, but had {}", rings);
+
+        let half_cylinder = height * 0.5 - radius;
+        let arc_length = radius * PI * 0.5;
+        let length = 2.0 * arc_length + 2.0 * half_cylinder;
+
+        // The profile goes from the top pole down to the bottom pole, with the cylinder between
+        // the top hemisphere's last ring and the bottom hemisphere's first ring.
+        let mut profile = Vec::with_capacity(2 * (rings + 1));
+        for &(offset, start_angle, start_distance) in &[
+            (half_cylinder, 0.0, 0.0),
+            (-half_cylinder, PI * 0.5, arc_length + 2.0 * half_cylinder),
+        ] {
+            for ring in 0..rings + 1 {
+                let fraction = ring as f32 / rings as f32;
+                let polar = start_angle + fraction * PI * 0.5;
+                let distance = start_distance + fraction * arc_length;
+                let sin = polar_sin(polar);
+
+                profile.push(ProfilePoint {
+                    radius: radius * sin,
+                    y: offset + radius * polar.cos(),
+                    normal: (sin, polar.cos()),
+                    v: 1.0 - distance / length,
+                });
+            }
+        }
+
+        let mut shape = Shape::new();
+        shape.add_revolution(&profile, segments);
+        shape.into_builder()
+    }
+
+    /// Generates a torus lying in the XZ plane.
+    ///
+    /// `major_radius` is the distance from the center of the torus to the center of its tube,
+    /// and `minor_radius` is the radius of the tube. The tube is made of `minor_segments`
+    /// segments around its circumference and the ring of `major_segments` segments. Texcoords
+    /// have u going around the Y axis starting at +Z and v going around the tube starting at
+    /// its outer edge.
+    ///
+    /// # Panics
+    ///
+    /// - If `major_radius` or `minor_radius` isn't positive.
+    /// - If `major_segments` or `minor_segments` is less than 3.
+    pub fn torus(major_radius: f32, minor_radius: f32, major_segments: usize, minor_segments: usize) -> MeshBuilder {
+        assert!(
+            major_radius > 0.0 && minor_radius > 0.0,
+            "Torus radii must be positive, but were {} and {}",
+            major_radius,
+            minor_radius);
+        assert!(major_segments >= 3, "Torus must have at least 3 major segments, but had {}", major_segments);
+        assert!(minor_segments >= 3, "Torus must have at least 3 minor segments, but had {}", minor_segments);
+
+        // The profile circle is walked clockwise, with the distance from the Y axis to the right
+        // and +Y up, which goes down the outside of the tube like the other revolved shapes.
+        let profile: Vec<ProfilePoint> = (0..minor_segments + 1)
+            .map(|segment| {
+                let fraction = 1.0 - segment as f32 / minor_segments as f32;
+                let angle = fraction * 2.0 * PI;
+                ProfilePoint {
+                    radius: major_radius + minor_radius * angle.cos(),
+                    y: minor_radius * angle.sin(),
+                    normal: (angle.cos(), angle.sin()),
+                    v: fraction,
+                }
+            })
+            .collect();
+
+        let mut shape = Shape::new();
+        shape.add_revolution(&profile, major_segments);
+        shape.into_builder()
+    }
+}
+
+/// Gets the sine of a polar angle in `[0, PI]`, which is exactly 0 at the poles.
+///
+/// `PI.sin()` isn't exactly 0, but profile points at the poles must lie on the Y axis.
+fn polar_sin(polar: f32) -> f32 {
+    if polar < PI { polar.sin() } else { 0.0 }
+}
+
+/// A point on the profile of a surface of revolution, see `Shape::add_revolution()`.
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    /// The distance from the Y axis.
+    radius: f32,
+
+    y: f32,
+
+    /// The outward normal as its components away from the Y axis and along the Y axis.
+    normal: (f32, f32),
+
+    /// The v texcoord of every vertex generated from the point.
+    v: f32,
+}
+
+/// The vertex data of a shape being generated.
+#[derive(Debug, Clone)]
+struct Shape {
+    positions: Vec<Point>,
+    normals: Vec<Vector3>,
+    texcoords: Vec<Vector2>,
+    indices: Vec<MeshIndex>,
+}
+
+impl Shape {
+    fn new() -> Shape {
+        Shape {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Adds a vertex, returning its index.
+    fn add_vertex(&mut self, position: Point, normal: Vector3, texcoord: Vector2) -> MeshIndex {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.texcoords.push(texcoord);
+        (self.positions.len() - 1) as MeshIndex
+    }
+
+    fn add_triangle(&mut self, a: MeshIndex, b: MeshIndex, c: MeshIndex) {
+        self.indices.extend(&[a, b, c]);
+    }
+
+    /// Adds a flat parallelogram starting at `corner` and spanning `u_axis` and `v_axis`, divided
+    /// into a grid of quads.
+    ///
+    /// `normal` must point along `cross(u_axis, v_axis)` for the grid to face it.
+    fn add_grid(
+        &mut self,
+        corner: Point,
+        u_axis: Vector3,
+        v_axis: Vector3,
+        normal: Vector3,
+        u_segments: usize,
+        v_segments: usize,
+    ) {
+        let first = self.positions.len() as MeshIndex;
+        for v_segment in 0..v_segments + 1 {
+            let v = v_segment as f32 / v_segments as f32;
+            for u_segment in 0..u_segments + 1 {
+                let u = u_segment as f32 / u_segments as f32;
+                self.add_vertex(corner + u_axis * u + v_axis * v, normal, Vector2::new(u, v));
+            }
+        }
+
+        let row = (u_segments + 1) as MeshIndex;
+        for v_segment in 0..v_segments as MeshIndex {
+            for u_segment in 0..u_segments as MeshIndex {
+                let a = first + v_segment * row + u_segment;
+                let b = a + 1;
+                let c = b + row;
+                let d = a + row;
+                self.add_triangle(a, b, c);
+                self.add_triangle(a, c, d);
+            }
+        }
+    }
+
+    /// Adds the surface made by revolving `profile` around the Y axis in `segments` steps.
+    ///
+    /// The profile must go down the outside of the surface, e.g. from the top pole of a sphere to
+    /// the bottom pole. Vertices are duplicated along the seam at +Z so that u can go from 0 to
+    /// 1, and triangles touching a profile point on the axis are left out since they'd have no
+    /// area.
+    fn add_revolution(&mut self, profile: &[ProfilePoint], segments: usize) {
+        let first = self.positions.len() as MeshIndex;
+        for point in profile {
+            for segment in 0..segments + 1 {
+                let u = segment as f32 / segments as f32;
+                let angle = u * 2.0 * PI;
+                let (sin, cos) = angle.sin_cos();
+
+                self.add_vertex(
+                    Point::new(point.radius * sin, point.y, point.radius * cos),
+                    Vector3::new(point.normal.0 * sin, point.normal.1, point.normal.0 * cos),
+                    Vector2::new(u, point.v),
+                );
+            }
+        }
+
+        let row = (segments + 1) as MeshIndex;
+        for (ring, pair) in profile.windows(2).enumerate() {
+            for segment in 0..segments as MeshIndex {
+                let a = first + ring as MeshIndex * row + segment;
+                let b = a + row;
+                let c = b + 1;
+                let d = a + 1;
+
+                if pair[1].radius > 0.0 {
+                    self.add_triangle(a, b, c);
+                }
+
+                if pair[0].radius > 0.0 {
+                    self.add_triangle(a, c, d);
+                }
+            }
+        }
+    }
+
+    fn into_builder(self) -> MeshBuilder {
+        MeshBuilder::new()
+            .set_position_data(&*self.positions)
+            .set_normal_data(&*self.normals)
+            .set_texcoord_data(&*self.texcoords)
+            .set_indices(&*self.indices)
+            .generate_tangents()
+    }
+}
@@ -0,0 +1,192 @@
+//! Debug visualization of the scene, see the crate's `debug` module.
+//!
+//! Every debug mode uses the same program, which picks the output color based on `debug_mode`.
+//! The modes only differ in the pipeline state used for the draws: wireframe draws triangle edges
+//! without culling, and overdraw blends additively without depth testing.
+
+use anchor::Anchor;
+use camera::Camera;
+use debug::DebugMode;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::depth::depth_less;
+use super::gl_util::*;
+use super::lod::LOD_FADE_SOURCE;
+use super::queue::{QueueItem, QueuePass};
+use super::skinning::{SKINNING_SOURCE, apply_skinning_uniforms};
+
+pub fn debug_vert_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform mat4 model_view_transform;
+            uniform mat4 model_view_projection;
+            uniform mat3 view_normal_transform;
+
+            layout(location = 0) in vec4 vertex_position;
+            layout(location = 1) in vec3 vertex_normal;
+            layout(location = 2) in vec2 vertex_uv0;
+
+            out vec3 view_normal;
+            out vec2 uv0;
+            out float view_depth;
+
+            void main(void) {{
+                mat4 skin = skin_transform();
+                vec4 position = skin * vertex_position;
+                gl_Position = model_view_projection * position;
+                view_normal = view_normal_transform * (mat3(skin) * vertex_normal);
+                uv0 = vertex_uv0;
+
+                // The camera looks down the -z axis.
+                view_depth = -(model_view_transform * position).z;
+            }}
+        "#,
+        SKINNING_SOURCE)
+}
+
+pub fn debug_frag_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform int debug_mode;
+            uniform float camera_far;
+
+            in vec3 view_normal;
+            in vec2 uv0;
+            in float view_depth;
+
+            out vec4 fragment_color;
+
+            void main(void) {{
+                apply_lod_fade();
+
+                vec3 color;
+                if (debug_mode == 2) {{
+                    color = normalize(view_normal) * 0.5 + 0.5;
+                }} else if (debug_mode == 3) {{
+                    color = vec3(fract(uv0), 0.0);
+                }} else if (debug_mode == 4) {{
+                    color = vec3(1.0 - clamp(view_depth / camera_far, 0.0, 1.0));
+                }} else if (debug_mode == 5) {{
+                    color = vec3(0.1, 0.04, 0.01);
+                }} else {{
+                    color = vec3(1.0);
+                }}
+
+                fragment_color = vec4(color, 1.0);
+            }}
+        "#,
+        LOD_FADE_SOURCE)
+}
+
+impl GlRender {
+    /// Renders all mesh instances as seen by `camera` using the current debug mode.
+    pub(super) fn render_debug(&self, camera: &Camera, camera_anchor: &Anchor) {
+        let _stopwatch = Stopwatch::new("Debug pass");
+
+        // Overdraw is independent of the draw order, and the other modes only draw opaque
+        // colors, so both passes can be drawn in queue order.
+        for item in self.build_render_queue(camera, camera_anchor).items() {
+            self.render_debug_mesh_instance(item, camera, camera_anchor);
+        }
+    }
+
+    fn render_debug_mesh_instance(&self, item: &QueueItem, camera: &Camera, camera_anchor: &Anchor) {
+        let mesh_instance = item.mesh_instance;
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return,
+        };
+
+        let mesh_data = self.meshes.get(&item.mesh).expect("Mesh data does not exist for mesh id");
+
+        let camera_position = camera_anchor.world_position();
+        let camera_orientation = camera_anchor.world_orientation();
+        let model_view_transform = camera_anchor.view_matrix() * anchor.billboard_matrix(camera_position, camera_orientation);
+        let model_view_projection = camera.projection_matrix() * model_view_transform;
+        let view_normal_transform = {
+            let inverse_model = anchor.billboard_normal_matrix(camera_position, camera_orientation).transpose();
+            let inverse_view = camera_anchor.inverse_view_matrix().into();
+            let inverse_model_view = inverse_model * inverse_view;
+            inverse_model_view.transpose()
+        };
+
+        let joint_palette = self.joint_palette(mesh_instance);
+        let transparent = item.key.pass() == QueuePass::Transparent;
+
+        for submesh in &mesh_data.submeshes {
+            // Mesh instances with both opaque and transparent submeshes are queued once for each.
+            let material = mesh_instance.slot_material(submesh.material_slot).unwrap_or(item.material);
+            if material.is_transparent() != transparent {
+                continue;
+            }
+
+            let mut draw_builder = DrawBuilder::new(
+                &self.context,
+                &mesh_data.vertex_array,
+                DrawMode::Triangles,
+            );
+
+            draw_builder
+            .program(&self.debug_program)
+            .index_range(submesh.start, submesh.count)
+            .uniform("debug_mode", self.debug_mode.index())
+            .uniform("camera_far", camera.far())
+            .uniform("lod_fade", item.lod_fade)
+            .uniform(
+                "model_view_transform",
+                GlMatrix {
+                    data: model_view_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "model_view_projection",
+                GlMatrix {
+                    data: model_view_projection.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "view_normal_transform",
+                GlMatrix {
+                    data: view_normal_transform.raw_data(),
+                    transpose: true,
+                },
+            );
+
+            match self.debug_mode {
+                DebugMode::Wireframe => {
+                    draw_builder
+                    .polygon_mode(PolygonMode::Line)
+                    .depth_test(depth_less(camera));
+                },
+
+                DebugMode::Overdraw => {
+                    draw_builder
+                    .depth_write(false)
+                    .blend(SourceFactor::One, DestFactor::One);
+                },
+
+                _ => {
+                    draw_builder
+                    .cull(Face::Back)
+                    .depth_test(depth_less(camera));
+                },
+            }
+
+            if let Some(framebuffer) = self.scene_framebuffer() {
+                draw_builder.framebuffer(framebuffer);
+            }
+
+            apply_skinning_uniforms(&mut draw_builder, joint_palette.as_ref().map(|data| &**data));
+
+            draw_builder.draw();
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! The deferred rendering pipeline.
+//!
+//! Opaque geometry is first rendered into a G-buffer holding the albedo, view-space normal, and
+//! depth of the closest surface at each pixel. Lighting is then accumulated into the scene's
+//! framebuffer with one full-screen pass per light batch, so the cost of each light depends on
+//! the number of pixels rather than the amount of geometry. Finally the G-buffer's depth is
+//! copied to the scene's framebuffer so that anything drawn afterwards is depth tested against
+//! the scene.
+//!
+//! Materials' fragment programs aren't run by the deferred path. Instead the G-buffer is filled
+//! from the standard surface properties used by the built-in lit materials: `surface_color`,
+//! `surface_diffuse` (optional), `surface_normal` (optional), `surface_specular` (stored as a
+//! single intensity), and `surface_shininess`, along with the mesh's vertex colors. Lighting
+//! matches `diffuse_lit.material`.
+
+use anchor::Anchor;
+use camera::Camera;
+use material::MaterialProperty;
+use math::*;
+use stopwatch::Stopwatch;
+use super::{BUILT_IN_UNIFORMS, GlRender};
+use super::depth::{clear_depth, depth_less};
+use super::gl_util::*;
+use super::gl_util::context::Context;
+use super::gl_util::framebuffer::{
+    self,
+    BlitFilter,
+    ClearBufferMask,
+    FramebufferAttachment,
+    Framebuffer,
+};
+use super::gl_util::texture::{Texture2d as GlTexture2d, TextureInternalFormat};
+use super::lod::LOD_FADE_SOURCE;
+use super::normal_map::NORMAL_MAP_SOURCE;
+use super::queue::{QueueItem, QueuePass};
+use super::shadow::SHADOW_FUNCTIONS;
+use super::skinning::{SKINNING_SOURCE, apply_skinning_uniforms};
+
+pub fn gbuffer_vert_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform mat4 model_view_transform;
+            uniform mat4 model_view_projection;
+            uniform mat3 view_normal_transform;
+            uniform int has_vertex_color;
+
+            layout(location = 0) in vec4 vertex_position;
+            layout(location = 1) in vec3 vertex_normal;
+            layout(location = 2) in vec2 vertex_uv0;
+            layout(location = 3) in vec4 vertex_color;
+            layout(location = 13) in vec4 vertex_tangent;
+
+            out vec3 view_normal;
+            out vec4 view_tangent;
+            out vec2 uv0;
+            out vec4 color;
+
+            void main(void) {{
+                mat4 skin = skin_transform();
+                gl_Position = model_view_projection * (skin * vertex_position);
+                view_normal = view_normal_transform * (mat3(skin) * vertex_normal);
+                view_tangent = vec4(
+                    mat3(model_view_transform) * (mat3(skin) * vertex_tangent.xyz),
+                    vertex_tangent.w);
+                uv0 = vertex_uv0;
+                color = has_vertex_color != 0 ? vertex_color : vec4(1.0);
+            }}
+        "#,
+        SKINNING_SOURCE)
+}
+
+pub fn gbuffer_frag_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            {}
+
+            uniform vec4 surface_color;
+            uniform vec4 surface_specular;
+            uniform float surface_shininess;
+            uniform sampler2D surface_diffuse;
+            uniform int has_surface_diffuse;
+
+            in vec3 view_normal;
+            in vec4 view_tangent;
+            in vec2 uv0;
+            in vec4 color;
+
+            layout(location = 0) out vec4 gbuffer_albedo;
+            layout(location = 1) out vec4 gbuffer_normal;
+
+            void main(void) {{
+                apply_lod_fade();
+
+                vec4 albedo = surface_color * color;
+                if (has_surface_diffuse != 0) {{
+                    albedo *= texture(surface_diffuse, uv0);
+                }}
+
+                float specular = dot(surface_specular.rgb, vec3(1.0 / 3.0));
+                gbuffer_albedo = vec4(albedo.rgb, specular);
+                vec3 normal = apply_normal_map(view_normal, view_tangent, uv0);
+                gbuffer_normal = vec4(normal, surface_shininess);
+            }}
+        "#,
+        LOD_FADE_SOURCE,
+        NORMAL_MAP_SOURCE)
+}
+
+pub static FULLSCREEN_VERT_SOURCE: &'static str = r#"
+    #version 330 core
+
+    layout(location = 0) in vec2 vertex_position;
+
+    out vec2 _uv_;
+
+    void main(void) {
+        gl_Position = vec4(vertex_position, 0.0, 1.0);
+        _uv_ = vertex_position * 0.5 + 0.5;
+    }
+"#;
+
+/// Generates the source for the full-screen lighting shader.
+///
+/// The built-in uniforms and shadow functions are shared with the materials' shaders, so the
+/// shader declares `_vertex_world_position_` itself and fills it in from the G-buffer depth
+/// before calling `shadow_factor()`.
+pub fn lighting_frag_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform sampler2D gbuffer_albedo;
+            uniform sampler2D gbuffer_normal;
+            uniform sampler2D gbuffer_depth;
+            uniform mat4 inverse_projection;
+            uniform mat4 inverse_view;
+
+            // The depth the G-buffer was cleared to, and the scale and offset converting depth
+            // to normalized device depth, which depend on the camera, see the `depth` module.
+            uniform float far_depth;
+            uniform vec2 depth_to_ndc;
+
+            in vec2 _uv_;
+
+            out vec4 _fragment_color_;
+
+            vec4 _vertex_world_position_;
+
+            {}
+
+            void main(void) {{
+                // The G-buffer shares its origin with the window and render targets, so the
+                // fragment's texel is at its window coordinates, while `_uv_` is relative to the
+                // camera's viewport.
+                ivec2 texel = ivec2(gl_FragCoord.xy);
+
+                float depth = texelFetch(gbuffer_depth, texel, 0).r;
+                if (depth == far_depth) {{
+                    // Nothing was drawn to this pixel.
+                    discard;
+                }}
+
+                float ndc_depth = depth * depth_to_ndc.x + depth_to_ndc.y;
+                vec4 view_position = inverse_projection * vec4(_uv_ * 2.0 - 1.0, ndc_depth, 1.0);
+                view_position /= view_position.w;
+                _vertex_world_position_ = inverse_view * view_position;
+
+                vec4 albedo = texelFetch(gbuffer_albedo, texel, 0);
+                vec4 normal = texelFetch(gbuffer_normal, texel, 0);
+                vec4 surface_color = vec4(albedo.rgb, 1.0);
+                vec4 surface_specular = vec4(vec3(albedo.a), 1.0);
+                float surface_shininess = normal.w;
+
+                vec4 ambient = global_ambient * surface_color;
+                vec4 diffuse = vec4(0, 0, 0, 1);
+                vec4 specular = vec4(0, 0, 0, 1);
+
+                vec3 n = normalize(normal.xyz);
+                vec3 v = normalize(-view_position.xyz);
+
+                for (int index = 0; index < 8; index += 1) {{
+                    vec3 l;
+                    float attenuation;
+
+                    if (light_type[index] == 1) {{
+                        vec3 light_offset = (light_position_view[index] - view_position).xyz;
+                        float dist = length(light_offset);
+                        l = normalize(light_offset);
+                        attenuation = pow(clamp(1.0 - (dist / light_radius[index]), 0, 1), 2.0) * shadow_factor(index);
+                    }} else if (light_type[index] == 2) {{
+                        l = -light_direction_view[index];
+                        attenuation = shadow_factor(index);
+                    }} else if (light_type[index] == 3) {{
+                        vec3 light_offset = (light_position_view[index] - view_position).xyz;
+                        float dist = length(light_offset);
+                        l = normalize(light_offset);
+                        float cone = smoothstep(
+                            light_cos_outer[index],
+                            light_cos_inner[index],
+                            dot(-l, light_direction_view[index]));
+                        attenuation = pow(clamp(1.0 - (dist / light_radius[index]), 0, 1), 2.0) * cone;
+                    }}
+
+                    if (light_type[index] != 0) {{
+                        float l_dot_n = dot(l, n);
+                        diffuse += max(l_dot_n, 0) * surface_color * light_color[index] * attenuation * light_strength[index];
+
+                        if (l_dot_n > 0) {{
+                            vec3 r = normalize(reflect(-l, n));
+                            float r_dot_v = clamp(dot(r, v), 0.0, 1.0);
+                            float shine = pow(r_dot_v, surface_shininess);
+                            specular += surface_specular * shine * attenuation * light_strength[index] * light_color[index];
+                        }}
+                    }}
+                }}
+
+                _fragment_color_ = ambient + diffuse + specular;
+            }}
+        "#,
+        BUILT_IN_UNIFORMS,
+        SHADOW_FUNCTIONS)
+}
+
+/// A single triangle covering the whole screen, used for full-screen passes.
+pub static FULLSCREEN_TRIANGLE: [f32; 6] = [
+    -1.0, -1.0,
+     3.0, -1.0,
+    -1.0,  3.0,
+];
+
+/// The render targets for the deferred path.
+#[derive(Debug)]
+pub struct GBuffer {
+    pub framebuffer: Framebuffer,
+
+    /// The surface color in RGB and the specular intensity in alpha.
+    pub albedo: GlTexture2d,
+
+    /// The view-space normal in RGB and the shininess in alpha.
+    pub normal: GlTexture2d,
+
+    pub depth: GlTexture2d,
+
+    pub width: usize,
+    pub height: usize,
+}
+
+impl GBuffer {
+    pub fn new(context: &Context, width: usize, height: usize) -> GBuffer {
+        let albedo = GlTexture2d::allocate(context, TextureInternalFormat::Rgba8, width, height)
+            .expect("Failed to allocate G-buffer albedo texture");
+        let normal = GlTexture2d::allocate(context, TextureInternalFormat::Rgba16F, width, height)
+            .expect("Failed to allocate G-buffer normal texture");
+        let depth = GlTexture2d::allocate(context, TextureInternalFormat::DepthComponent32F, width, height)
+            .expect("Failed to allocate G-buffer depth texture");
+
+        let mut framebuffer = Framebuffer::new(context).expect("Failed to create G-buffer framebuffer");
+        framebuffer.attach_color_textures(&[&albedo, &normal]);
+        framebuffer.attach_texture(FramebufferAttachment::Depth, &depth);
+
+        GBuffer {
+            framebuffer: framebuffer,
+            albedo: albedo,
+            normal: normal,
+            depth: depth,
+
+            width: width,
+            height: height,
+        }
+    }
+}
+
+impl GlRender {
+    /// Makes sure the G-buffer exists and matches the size needed by the cameras.
+    pub(super) fn prepare_gbuffer(&mut self) {
+        let (width, height) = self.intermediate_target_size();
+
+        let is_current = match self.gbuffer {
+            Some(ref gbuffer) => gbuffer.width == width && gbuffer.height == height,
+            None => false,
+        };
+
+        if !is_current {
+            self.gbuffer = Some(GBuffer::new(&self.context, width, height));
+        }
+    }
+
+    /// Renders all mesh instances as seen by `camera` using the deferred path.
+    ///
+    /// # Panics
+    ///
+    /// - If `prepare_gbuffer()` hasn't been called.
+    pub(super) fn render_deferred(&self, camera: &Camera, camera_anchor: &Anchor) {
+        let gbuffer = self.gbuffer.as_ref().expect("G-buffer must be prepared before rendering");
+
+        // GEOMETRY PASS
+        // =============
+
+        {
+            let _stopwatch = Stopwatch::new("Geometry pass");
+
+            let rect = self.context.viewport();
+            self.context.clear_rect(Some(&gbuffer.framebuffer), rect, Some([0.0, 0.0, 0.0, 0.0]), Some(clear_depth(camera)));
+
+            for item in self.build_render_queue(camera, camera_anchor).pass(QueuePass::Opaque) {
+                self.render_gbuffer_mesh_instance(gbuffer, &item, camera, camera_anchor);
+            }
+        }
+
+        // LIGHTING PASSES
+        // ===============
+
+        let inverse_projection = camera.inverse_projection_matrix();
+        let inverse_view = camera_anchor.inverse_view_matrix();
+        let depth_to_ndc = self.depth_to_ndc(camera);
+
+        let light_batches = self.light_batches(camera_anchor.view_matrix());
+        for (pass, light_batch) in light_batches.iter().enumerate() {
+            let _stopwatch = Stopwatch::new("Lighting pass");
+
+            // The ambient term is only added once, by the first pass.
+            self.upload_frame_uniforms(camera, camera_anchor, light_batch, pass > 0);
+
+            let mut draw_builder = DrawBuilder::new(
+                &self.context,
+                &self.fullscreen_triangle,
+                DrawMode::Triangles,
+            );
+
+            draw_builder
+            .program(&self.deferred_lighting_program)
+            .blend(SourceFactor::One, DestFactor::One)
+            .uniform("gbuffer_albedo", &gbuffer.albedo)
+            .uniform("gbuffer_normal", &gbuffer.normal)
+            .uniform("gbuffer_depth", &gbuffer.depth)
+            .uniform(
+                "inverse_projection",
+                GlMatrix {
+                    data: inverse_projection.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "inverse_view",
+                GlMatrix {
+                    data: inverse_view.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform("far_depth", clear_depth(camera))
+            .uniform("depth_to_ndc", depth_to_ndc);
+
+            if let Some(framebuffer) = self.scene_framebuffer() {
+                draw_builder.framebuffer(framebuffer);
+            }
+
+            self.apply_shadow_maps(&mut draw_builder);
+
+            draw_builder.draw();
+        }
+
+        // Copy the scene's depth so that later draws are depth tested against it.
+        let rect = self.context.viewport();
+        framebuffer::blit(
+            &self.context,
+            Some(&gbuffer.framebuffer),
+            self.scene_framebuffer(),
+            rect,
+            rect,
+            ClearBufferMask::Depth,
+            BlitFilter::Nearest,
+        );
+    }
+
+    fn render_gbuffer_mesh_instance(
+        &self,
+        gbuffer: &GBuffer,
+        item: &QueueItem,
+        camera: &Camera,
+        camera_anchor: &Anchor,
+    ) {
+        let mesh_instance = item.mesh_instance;
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return,
+        };
+
+        let mesh_data = self.meshes.get(&item.mesh).expect("Mesh data does not exist for mesh id");
+
+        let camera_position = camera_anchor.world_position();
+        let camera_orientation = camera_anchor.world_orientation();
+        let model_view_transform = camera_anchor.view_matrix() * anchor.billboard_matrix(camera_position, camera_orientation);
+        let model_view_projection = camera.projection_matrix() * model_view_transform;
+        let view_normal_transform = {
+            let inverse_model = anchor.billboard_normal_matrix(camera_position, camera_orientation).transpose();
+            let inverse_view = camera_anchor.inverse_view_matrix().into();
+            let inverse_model_view = inverse_model * inverse_view;
+            inverse_model_view.transpose()
+        };
+
+        let default_texture = GlTexture2d::empty(&self.context);
+        let joint_palette = self.joint_palette(mesh_instance);
+
+        for submesh in &mesh_data.submeshes {
+            let material = mesh_instance.slot_material(submesh.material_slot).unwrap_or(item.material);
+
+            // Transparent submeshes are drawn later by the forward pipeline.
+            if material.is_transparent() {
+                continue;
+            }
+
+            let mut draw_builder = DrawBuilder::new(
+                &self.context,
+                &mesh_data.vertex_array,
+                DrawMode::Triangles,
+            );
+
+            draw_builder
+            .framebuffer(&gbuffer.framebuffer)
+            .program(&self.gbuffer_program)
+            .cull(Face::Back)
+            .depth_test(depth_less(camera))
+            .index_range(submesh.start, submesh.count)
+            .uniform("lod_fade", item.lod_fade)
+            .uniform("has_vertex_color", mesh_data.attributes.color as i32)
+            .uniform(
+                "model_view_transform",
+                GlMatrix {
+                    data: model_view_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "model_view_projection",
+                GlMatrix {
+                    data: model_view_projection.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "view_normal_transform",
+                GlMatrix {
+                    data: view_normal_transform.raw_data(),
+                    transpose: true,
+                },
+            );
+
+            if let Some(&MaterialProperty::Color(ref color)) = material.get_property("surface_color") {
+                draw_builder.uniform::<[f32; 4]>("surface_color", color.into());
+            }
+
+            if let Some(&MaterialProperty::Color(ref color)) = material.get_property("surface_specular") {
+                draw_builder.uniform::<[f32; 4]>("surface_specular", color.into());
+            }
+
+            if let Some(&MaterialProperty::f32(shininess)) = material.get_property("surface_shininess") {
+                draw_builder.uniform("surface_shininess", shininess);
+            }
+
+            match material.get_property("surface_diffuse") {
+                Some(&MaterialProperty::Texture(ref texture)) => {
+                    let gl_texture = self.textures.get(texture).unwrap_or(&default_texture);
+                    draw_builder
+                    .uniform("surface_diffuse", gl_texture)
+                    .uniform("has_surface_diffuse", 1i32);
+                },
+                _ => {
+                    draw_builder.uniform("has_surface_diffuse", 0i32);
+                },
+            }
+
+            self.apply_normal_map_uniforms(&mut draw_builder, material, &default_texture);
+            apply_skinning_uniforms(&mut draw_builder, joint_palette.as_ref().map(|data| &**data));
+
+            draw_builder.draw();
+        }
+    }
+}
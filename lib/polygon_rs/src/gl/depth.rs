@@ -0,0 +1,54 @@
+//! The depth conventions of each camera, see `Camera::set_reverse_z()`.
+//!
+//! Cameras using reverse-Z map the near plane to a depth of 1 and the far plane to 0, so every
+//! depth test and depth clear made while drawing for them is flipped. If clip control is
+//! supported their clip-space depth ranges from 0 to 1 instead of -1 to 1, which keeps the
+//! precision of a floating-point depth buffer from being lost in the conversion to window depth.
+//! Shadow maps always use the standard conventions.
+
+use camera::Camera;
+use super::GlRender;
+use super::gl_util::{ClipDepth, Comparison};
+
+/// The depth test passing fragments in front of what's already been drawn for `camera`.
+pub fn depth_less(camera: &Camera) -> Comparison {
+    if camera.reverse_z() { Comparison::Greater } else { Comparison::Less }
+}
+
+/// The depth test passing fragments in front of or at the same depth as what's already been
+/// drawn for `camera`.
+pub fn depth_less_equal(camera: &Camera) -> Comparison {
+    if camera.reverse_z() { Comparison::GreaterThanOrEqual } else { Comparison::LessThanOrEqual }
+}
+
+/// The depth of `camera`'s far plane, which its viewport's depth is cleared to.
+pub fn clear_depth(camera: &Camera) -> f32 {
+    if camera.reverse_z() { 0.0 } else { 1.0 }
+}
+
+/// The normalized device depth of `camera`'s near plane.
+pub fn near_ndc_depth(camera: &Camera) -> f32 {
+    if camera.reverse_z() { 1.0 } else { -1.0 }
+}
+
+impl GlRender {
+    /// The clip-space depth range used while drawing for `camera`.
+    ///
+    /// Without clip control reverse-Z cameras keep the default range, which still works but only
+    /// uses the upper half of the depth buffer.
+    pub(super) fn clip_depth(&self, camera: &Camera) -> ClipDepth {
+        if camera.reverse_z() && self.context.capabilities().clip_control {
+            ClipDepth::ZeroToOne
+        } else {
+            ClipDepth::NegativeOneToOne
+        }
+    }
+
+    /// The scale and offset converting window depth into normalized device depth for `camera`.
+    pub(super) fn depth_to_ndc(&self, camera: &Camera) -> (f32, f32) {
+        match self.clip_depth(camera) {
+            ClipDepth::ZeroToOne => (1.0, 0.0),
+            ClipDepth::NegativeOneToOne => (2.0, -1.0),
+        }
+    }
+}
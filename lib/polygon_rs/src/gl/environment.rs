@@ -0,0 +1,381 @@
+//! Image-based lighting from an environment map.
+//!
+//! When an environment map is set (see `Renderer::set_environment_map()`) two cube maps are
+//! generated from it on the GPU:
+//!
+//! - The irradiance map, which holds the cosine-weighted average of the environment over the
+//!   hemisphere around each direction, i.e. the diffuse light reaching a surface facing that way.
+//! - The prefiltered specular map, which holds the environment convolved with the GGX
+//!   distribution, with increasing roughness in each mip level.
+//!
+//! Fragment programs can call `environment_lighting()` to get the light reflected from the
+//! environment using the split-sum approximation, where the environment BRDF is approximated
+//! analytically instead of being read from a lookup texture.
+
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::*;
+use super::gl_util::framebuffer::{FramebufferAttachment, Framebuffer, Rect};
+use super::gl_util::texture::{CubeFace, TextureCube, TextureFilterFunction, TextureFormat, TextureInternalFormat};
+use texture::{ColorSpace, DataFormat, Texture2d, TextureData};
+
+/// The size of each face of the irradiance map.
+pub const IRRADIANCE_SIZE: usize = 32;
+
+/// The size of each face of the most detailed level of the prefiltered specular map.
+pub const SPECULAR_SIZE: usize = 128;
+
+/// The GLSL declarations for image-based lighting, shared by every material fragment program.
+///
+/// Must be included after `BUILT_IN_UNIFORMS`.
+pub static ENVIRONMENT_SOURCE: &'static str = r#"
+    uniform samplerCube environment_irradiance;
+    uniform samplerCube environment_specular;
+    uniform int has_environment;
+    uniform float environment_max_lod;
+
+    // Karis' analytic approximation of the split-sum environment BRDF, giving the scale and bias
+    // applied to the Fresnel reflectance at normal incidence.
+    vec2 environment_brdf(float roughness, float n_dot_v) {
+        const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+        const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+        vec4 r = roughness * c0 + c1;
+        float a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+        return vec2(-1.04, 1.04) * a004 + r.zw;
+    }
+
+    // Returns the light reflected from the environment. `n` and `v` are the normalized surface
+    // normal and direction to the camera in view space. Returns black if there's no environment.
+    vec3 environment_lighting(vec3 n, vec3 v, vec3 diffuse_color, vec3 f0, float roughness) {
+        if (has_environment == 0) {
+            return vec3(0.0);
+        }
+
+        // The environment maps are in world space.
+        mat3 view_to_world = transpose(mat3(view_transform));
+        vec3 world_normal = view_to_world * n;
+        vec3 world_reflection = view_to_world * reflect(-v, n);
+
+        vec3 irradiance = texture(environment_irradiance, world_normal).rgb;
+        vec3 prefiltered = textureLod(
+            environment_specular,
+            world_reflection,
+            roughness * environment_max_lod).rgb;
+        vec2 brdf = environment_brdf(roughness, max(dot(n, v), 0.0));
+
+        return diffuse_color * irradiance + prefiltered * (f0 * brdf.x + brdf.y);
+    }
+"#;
+
+/// Functions shared by the environment map generation programs.
+static CUBE_FACE_SOURCE: &'static str = r#"
+    const float PI = 3.14159265;
+
+    uniform samplerCube environment;
+    uniform int face;
+
+    in vec2 _uv_;
+
+    out vec4 fragment_color;
+
+    // Returns the direction through a point on a cube map face, following the GL conventions
+    // for cube map texture coordinates.
+    vec3 face_direction(vec2 uv) {
+        vec2 st = uv * 2.0 - 1.0;
+        if (face == 0) {
+            return normalize(vec3(1.0, -st.y, -st.x));
+        } else if (face == 1) {
+            return normalize(vec3(-1.0, -st.y, st.x));
+        } else if (face == 2) {
+            return normalize(vec3(st.x, 1.0, st.y));
+        } else if (face == 3) {
+            return normalize(vec3(st.x, -1.0, -st.y));
+        } else if (face == 4) {
+            return normalize(vec3(st.x, -st.y, 1.0));
+        } else {
+            return normalize(vec3(-st.x, -st.y, -1.0));
+        }
+    }
+"#;
+
+/// Generates the source for the program that convolves the environment into the irradiance map.
+pub fn irradiance_frag_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            void main(void) {{
+                vec3 normal = face_direction(_uv_);
+                vec3 up = abs(normal.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+                vec3 right = normalize(cross(up, normal));
+                up = cross(normal, right);
+
+                // Integrate over the hemisphere in evenly spaced steps. Each sample is weighted
+                // by cos(theta) for Lambert's law, and by sin(theta) for the smaller solid angle
+                // of the steps near the pole.
+                vec3 irradiance = vec3(0.0);
+                float count = 0.0;
+                for (float phi = 0.0; phi < 2.0 * PI; phi += 0.05) {{
+                    for (float theta = 0.0; theta < 0.5 * PI; theta += 0.05) {{
+                        vec3 tangent_direction = vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+                        vec3 direction =
+                            tangent_direction.x * right
+                            + tangent_direction.y * up
+                            + tangent_direction.z * normal;
+
+                        irradiance += texture(environment, direction).rgb * cos(theta) * sin(theta);
+                        count += 1.0;
+                    }}
+                }}
+
+                fragment_color = vec4(PI * irradiance / count, 1.0);
+            }}
+        "#,
+        CUBE_FACE_SOURCE)
+}
+
+/// Generates the source for the program that prefilters the environment for one level of the
+/// specular map.
+pub fn prefilter_frag_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform float roughness;
+            uniform float source_size;
+
+            const uint SAMPLE_COUNT = 256u;
+
+            // The Van der Corput sequence, reversing the bits of `bits` around the decimal point.
+            float radical_inverse(uint bits) {{
+                bits = (bits << 16u) | (bits >> 16u);
+                bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+                bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+                bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+                bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+                return float(bits) * 2.3283064365386963e-10;
+            }}
+
+            void main(void) {{
+                // Assume the view direction matches the normal, so the reflection does too.
+                vec3 normal = face_direction(_uv_);
+                if (roughness == 0.0) {{
+                    fragment_color = vec4(textureLod(environment, normal, 0.0).rgb, 1.0);
+                    return;
+                }}
+
+                vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+                vec3 right = normalize(cross(up, normal));
+                up = cross(normal, right);
+
+                float alpha = roughness * roughness;
+                float texel_solid_angle = 4.0 * PI / (6.0 * source_size * source_size);
+
+                vec3 prefiltered = vec3(0.0);
+                float total_weight = 0.0;
+                for (uint index = 0u; index < SAMPLE_COUNT; index++) {{
+                    // Importance sample a half vector from the GGX distribution.
+                    vec2 xi = vec2(float(index) / float(SAMPLE_COUNT), radical_inverse(index));
+                    float phi = 2.0 * PI * xi.x;
+                    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+                    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+                    vec3 half_vector = normalize(
+                        right * cos(phi) * sin_theta
+                        + up * sin(phi) * sin_theta
+                        + normal * cos_theta);
+
+                    vec3 light = normalize(2.0 * dot(normal, half_vector) * half_vector - normal);
+                    float n_dot_l = dot(normal, light);
+                    if (n_dot_l > 0.0) {{
+                        // Read from a blurrier level of the environment for less likely samples,
+                        // which each stand in for a larger solid angle. This avoids bright spots
+                        // from undersampling.
+                        float n_dot_h = max(cos_theta, 0.0);
+                        float d_denominator = n_dot_h * n_dot_h * (alpha * alpha - 1.0) + 1.0;
+                        float d = alpha * alpha / (PI * d_denominator * d_denominator);
+                        float pdf = d / 4.0 + 0.0001;
+                        float sample_solid_angle = 1.0 / (float(SAMPLE_COUNT) * pdf + 0.0001);
+                        float lod = 0.5 * log2(sample_solid_angle / texel_solid_angle);
+
+                        prefiltered += textureLod(environment, light, max(lod, 0.0)).rgb * n_dot_l;
+                        total_weight += n_dot_l;
+                    }}
+                }}
+
+                fragment_color = vec4(prefiltered / total_weight, 1.0);
+            }}
+        "#,
+        CUBE_FACE_SOURCE)
+}
+
+/// The cube maps generated from an environment map.
+#[derive(Debug)]
+pub struct Environment {
+    pub irradiance: TextureCube,
+    pub specular: TextureCube,
+}
+
+impl GlRender {
+    /// Uploads an environment map and generates its irradiance and prefiltered specular maps.
+    ///
+    /// # Panics
+    ///
+    /// - If the faces aren't square, or don't all have the same size.
+    pub(super) fn build_environment(&self, faces: [&Texture2d; 6]) -> Environment {
+        let _stopwatch = Stopwatch::new("Build environment");
+
+        let size = faces[0].width();
+        for face in &faces {
+            assert!(
+                face.width() == size && face.height() == size,
+                "Environment map faces must be square and have the same size, expected {}x{} but got {}x{}",
+                size,
+                size,
+                face.width(),
+                face.height(),
+            );
+        }
+
+        let face_data = [
+            face_rgba(faces[0], self.gamma_correct),
+            face_rgba(faces[1], self.gamma_correct),
+            face_rgba(faces[2], self.gamma_correct),
+            face_rgba(faces[3], self.gamma_correct),
+            face_rgba(faces[4], self.gamma_correct),
+            face_rgba(faces[5], self.gamma_correct),
+        ];
+        let mut source = TextureCube::new(
+            &self.context,
+            TextureFormat::Rgba,
+            TextureInternalFormat::Rgba16F,
+            size,
+            [&*face_data[0], &*face_data[1], &*face_data[2], &*face_data[3], &*face_data[4], &*face_data[5]],
+        ).expect("Failed to create environment map");
+
+        // The source is read from blurrier levels when it's undersampled.
+        source.generate_mipmaps();
+
+        let mut irradiance = TextureCube::allocate(&self.context, TextureInternalFormat::Rgba16F, IRRADIANCE_SIZE)
+            .expect("Failed to allocate irradiance map");
+        irradiance.set_filter(TextureFilterFunction::Linear, TextureFilterFunction::Linear);
+
+        let mut specular = TextureCube::allocate(&self.context, TextureInternalFormat::Rgba16F, SPECULAR_SIZE)
+            .expect("Failed to allocate specular map");
+        specular.generate_mipmaps();
+
+        let mut framebuffer = Framebuffer::new(&self.context).expect("Failed to create environment framebuffer");
+        let viewport = self.context.viewport();
+
+        self.context.set_viewport(Rect::new(0, 0, IRRADIANCE_SIZE as i32, IRRADIANCE_SIZE as i32));
+        for (index, &face) in CubeFace::ALL.iter().enumerate() {
+            framebuffer.attach_cube_face(FramebufferAttachment::Color0, &irradiance, face);
+
+            DrawBuilder::new(&self.context, &self.fullscreen_triangle, DrawMode::Triangles)
+            .framebuffer(&framebuffer)
+            .program(&self.irradiance_program)
+            .uniform("environment", &source)
+            .uniform("face", index as i32)
+            .draw();
+        }
+
+        let levels = specular.levels();
+        for level in 0..levels {
+            let level_size = (SPECULAR_SIZE >> level) as i32;
+            self.context.set_viewport(Rect::new(0, 0, level_size, level_size));
+
+            let roughness = level as f32 / (levels - 1) as f32;
+            for (index, &face) in CubeFace::ALL.iter().enumerate() {
+                framebuffer.attach_cube_face_level(FramebufferAttachment::Color0, &specular, face, level);
+
+                DrawBuilder::new(&self.context, &self.fullscreen_triangle, DrawMode::Triangles)
+                .framebuffer(&framebuffer)
+                .program(&self.prefilter_program)
+                .uniform("environment", &source)
+                .uniform("face", index as i32)
+                .uniform("roughness", roughness)
+                .uniform("source_size", size as f32)
+                .draw();
+            }
+        }
+
+        self.context.set_viewport(viewport);
+
+        Environment {
+            irradiance: irradiance,
+            specular: specular,
+        }
+    }
+
+    /// Sets the uniforms used by `environment_lighting()`.
+    ///
+    /// Like the ambient term, the environment is only applied by the first pass of a draw, so
+    /// it's disabled for additive passes.
+    pub(super) fn apply_environment_uniforms<'a>(&'a self, draw_builder: &mut DrawBuilder<'a>, additive: bool) {
+        match self.environment {
+            Some(ref environment) if !additive => {
+                draw_builder
+                .uniform("environment_irradiance", &environment.irradiance)
+                .uniform("environment_specular", &environment.specular)
+                .uniform("environment_max_lod", (environment.specular.levels() - 1) as f32)
+                .uniform("has_environment", 1i32);
+            },
+            _ => {
+                draw_builder
+                .uniform("environment_irradiance", &self.default_point_shadow_map)
+                .uniform("environment_specular", &self.default_point_shadow_map)
+                .uniform("has_environment", 0i32);
+            },
+        }
+    }
+}
+
+/// Converts the data of an environment map face into RGBA floats.
+///
+/// The color channels of sRGB faces are converted to linear if `gamma_correct` is set.
+fn face_rgba(texture: &Texture2d, gamma_correct: bool) -> Vec<f32> {
+    let decode_srgb = gamma_correct && texture.color_space() == ColorSpace::Srgb;
+    let channels: Vec<f32> = match *texture.data() {
+        TextureData::f32(ref data) => data.clone(),
+        TextureData::u8(ref data) => data.iter().map(|&channel| channel as f32 / 255.0).collect(),
+        TextureData::u8x3(ref data) => data
+            .iter()
+            .flat_map(|&(r, g, b)| vec![r, g, b])
+            .map(|channel| channel as f32 / 255.0)
+            .collect(),
+        TextureData::u8x4(ref data) => data
+            .iter()
+            .flat_map(|&(r, g, b, a)| vec![r, g, b, a])
+            .map(|channel| channel as f32 / 255.0)
+            .collect(),
+    };
+
+    let (elements, swap_red_blue) = match texture.format() {
+        DataFormat::Rgb => (3, false),
+        DataFormat::Rgba => (4, false),
+        DataFormat::Bgr => (3, true),
+        DataFormat::Bgra => (4, true),
+    };
+
+    let mut rgba = Vec::with_capacity(channels.len() / elements * 4);
+    for pixel in channels.chunks(elements) {
+        let (red, blue) = if swap_red_blue { (pixel[2], pixel[0]) } else { (pixel[0], pixel[2]) };
+        let alpha = if elements == 4 { pixel[3] } else { 1.0 };
+        if decode_srgb {
+            rgba.extend_from_slice(&[srgb_to_linear(red), srgb_to_linear(pixel[1]), srgb_to_linear(blue), alpha]);
+        } else {
+            rgba.extend_from_slice(&[red, pixel[1], blue, alpha]);
+        }
+    }
+    rgba
+}
+
+/// Converts a single sRGB color channel to linear.
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
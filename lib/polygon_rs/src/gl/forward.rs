@@ -0,0 +1,233 @@
+//! The forward rendering pipeline.
+//!
+//! Lights are sent to the shaders in batches of `LIGHTS_PER_PASS`. The first batch is rendered
+//! along with the ambient term, and each additional batch is blended on top of it as an additive
+//! pass, so any number of lights can affect a mesh. Draws are submitted in render queue order,
+//! see the `queue` module, followed by the instance batches, see the `instancing` module.
+//!
+//! Transparent geometry is drawn afterwards, sorted back-to-front so that each surface is
+//! blended over the surfaces behind it. Each transparent mesh instance is drawn
+//! with all of its light passes before moving on to the next one, keeping the blending order
+//! intact.
+
+use anchor::Anchor;
+use camera::Camera;
+use light::LightData;
+use math::*;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::instancing::InstanceBatch;
+use super::queue::QueuePass;
+
+/// The number of lights sent to the shaders in a single pass.
+///
+/// This must match the size of the light arrays in the `FrameUniforms` block of
+/// `BUILT_IN_UNIFORMS`.
+pub const LIGHTS_PER_PASS: usize = 8;
+
+/// The light uniforms for a single forward pass.
+#[derive(Debug)]
+pub struct LightBatch {
+    pub light_type: [i32; LIGHTS_PER_PASS],
+    pub light_strength: [f32; LIGHTS_PER_PASS],
+    pub light_color: [Color; LIGHTS_PER_PASS],
+    pub light_position: [Point; LIGHTS_PER_PASS],
+    pub light_position_view: [Point; LIGHTS_PER_PASS],
+    pub light_radius: [f32; LIGHTS_PER_PASS],
+    pub light_direction: [Vector3; LIGHTS_PER_PASS],
+    pub light_direction_view: [Vector3; LIGHTS_PER_PASS],
+    pub light_cos_inner: [f32; LIGHTS_PER_PASS],
+    pub light_cos_outer: [f32; LIGHTS_PER_PASS],
+
+    /// The index within the batch of the directional light casting shadows, or -1 if it isn't
+    /// in this batch.
+    pub shadow_light_index: i32,
+
+    /// The index of each light's point shadow map, or -1 if the light doesn't have one.
+    pub light_shadow_map: [i32; LIGHTS_PER_PASS],
+}
+
+impl LightBatch {
+    fn new() -> LightBatch {
+        LightBatch {
+            light_type: [0; LIGHTS_PER_PASS],
+            light_strength: [0.0; LIGHTS_PER_PASS],
+            light_color: [Color::rgb(0.0, 0.0, 0.0); LIGHTS_PER_PASS],
+            light_position: [Point::origin(); LIGHTS_PER_PASS],
+            light_position_view: [Point::origin(); LIGHTS_PER_PASS],
+            light_radius: [0.0; LIGHTS_PER_PASS],
+            light_direction: [Vector3::zero(); LIGHTS_PER_PASS],
+            light_direction_view: [Vector3::zero(); LIGHTS_PER_PASS],
+            light_cos_inner: [0.0; LIGHTS_PER_PASS],
+            light_cos_outer: [0.0; LIGHTS_PER_PASS],
+
+            shadow_light_index: -1,
+            light_shadow_map: [-1; LIGHTS_PER_PASS],
+        }
+    }
+}
+
+impl GlRender {
+    /// Renders all opaque mesh instances as seen by `camera`.
+    pub(super) fn render_forward(&self, camera: &Camera, camera_anchor: &Anchor) {
+        let light_batches = self.light_batches(camera_anchor.view_matrix());
+        let draws = self.build_render_queue(camera, camera_anchor).pass(QueuePass::Opaque);
+
+        for (pass, light_batch) in light_batches.iter().enumerate() {
+            let _stopwatch = Stopwatch::new("Forward pass");
+
+            let additive = pass > 0;
+            self.upload_frame_uniforms(camera, camera_anchor, light_batch, additive);
+
+            for item in &draws {
+                self.render_mesh_instance(
+                    item,
+                    camera,
+                    camera_anchor,
+                    light_batch,
+                    additive,
+                    false,
+                );
+            }
+
+            for batch in &self.instance_batches {
+                self.render_instance_batch(batch, camera, camera_anchor, light_batch, additive);
+            }
+        }
+    }
+
+    /// Draws every submesh of an instance batch with a single instanced draw call each.
+    fn render_instance_batch(
+        &self,
+        batch: &InstanceBatch,
+        camera: &Camera,
+        camera_anchor: &Anchor,
+        light_batch: &LightBatch,
+        additive: bool,
+    ) {
+        let _stopwatch = Stopwatch::new("Rendering instance batch");
+
+        let material = self.shared_materials.get(&batch.material).expect("No such material exists");
+        let mesh_data = self.meshes.get(&batch.mesh).expect("Mesh data does not exist for mesh id");
+
+        // The anchor is only used for the model transform uniforms, which the instances override.
+        let mesh_instance = self.mesh_instances.get(&batch.mesh_instances[0]).expect("No such mesh instance");
+        let anchor_id = mesh_instance.anchor().expect("Instanced mesh instance must have an anchor");
+        let anchor = self.anchors.get(&anchor_id).expect("No such anchor exists");
+
+        for submesh in &mesh_data.submeshes {
+            self.render_submesh(
+                anchor,
+                mesh_data,
+                submesh,
+                material,
+                None,
+                Some(&batch.instances),
+                0.0,
+                camera,
+                camera_anchor,
+                light_batch,
+                additive,
+            );
+        }
+    }
+
+    /// Renders all transparent mesh instances as seen by `camera`, blending them over the
+    /// current contents of the scene.
+    pub(super) fn render_transparent(&self, camera: &Camera, camera_anchor: &Anchor) {
+        let draws = self.build_render_queue(camera, camera_anchor).pass(QueuePass::Transparent);
+        if draws.is_empty() {
+            return;
+        }
+
+        let light_batches = self.light_batches(camera_anchor.view_matrix());
+        for item in &draws {
+            for (pass, light_batch) in light_batches.iter().enumerate() {
+                // Each transparent draw goes through every light pass before the next one, so
+                // the frame uniforms change between draws.
+                self.upload_frame_uniforms(camera, camera_anchor, light_batch, pass > 0);
+                self.render_mesh_instance(
+                    item,
+                    camera,
+                    camera_anchor,
+                    light_batch,
+                    pass > 0,
+                    true,
+                );
+            }
+        }
+    }
+
+    /// Splits the lights into batches of `LIGHTS_PER_PASS`, one for each forward pass.
+    ///
+    /// At least one batch is always returned, so that the ambient pass is rendered even if there
+    /// are no lights.
+    pub(super) fn light_batches(&self, view_transform: Matrix4) -> Vec<LightBatch> {
+        let _stopwatch = Stopwatch::new("Setup lights");
+
+        let mut batches = vec![LightBatch::new()];
+        for (light_index, light) in self.lights.values().enumerate() {
+            if light_index > 0 && light_index % LIGHTS_PER_PASS == 0 {
+                batches.push(LightBatch::new());
+            }
+
+            let batch = batches.last_mut().unwrap();
+            let index = light_index % LIGHTS_PER_PASS;
+
+            // Setup common light data.
+            batch.light_color[index] = light.color;
+            batch.light_strength[index] = light.strength;
+
+            // Setup data specific to the current type of light.
+            match light.data {
+                LightData::Point { radius } => {
+                    // Get the light's anchor.
+                    let light_anchor = match light.anchor() {
+                        Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                        None => panic!("Cannot render point light if it's not attached to an anchor"),
+                    };
+
+                    batch.light_type[index] = 1;
+                    batch.light_position[index] = light_anchor.world_position();
+                    batch.light_position_view[index] = light_anchor.world_position() * view_transform;
+                    batch.light_radius[index] = radius;
+                },
+
+                LightData::Directional { direction } => {
+                    batch.light_type[index] = 2;
+                    batch.light_direction[index] = direction;
+                    batch.light_direction_view[index] = direction * view_transform;
+                },
+
+                LightData::Spot { radius, direction, inner_angle, outer_angle } => {
+                    let light_anchor = match light.anchor() {
+                        Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                        None => panic!("Cannot render spot light if it's not attached to an anchor"),
+                    };
+
+                    // The direction is relative to the anchor, so it turns with the anchor.
+                    let direction = light_anchor.world_orientation() * direction;
+
+                    batch.light_type[index] = 3;
+                    batch.light_position[index] = light_anchor.world_position();
+                    batch.light_position_view[index] = light_anchor.world_position() * view_transform;
+                    batch.light_radius[index] = radius;
+                    batch.light_direction[index] = direction;
+                    batch.light_direction_view[index] = direction * view_transform;
+                    batch.light_cos_inner[index] = inner_angle.cos();
+                    batch.light_cos_outer[index] = outer_angle.cos();
+                },
+            }
+
+            if self.shadow_light == Some(light_index) {
+                batch.shadow_light_index = index as i32;
+            }
+
+            if let Some(shadow_index) = self.point_shadow_lights.iter().position(|&shadow_light| shadow_light == light_index) {
+                batch.light_shadow_map[index] = shadow_index as i32;
+            }
+        }
+
+        batches
+    }
+}
@@ -0,0 +1,267 @@
+//! HDR rendering and tone mapping.
+//!
+//! While HDR is enabled the scene passes draw into an `HdrTarget` instead of the default
+//! framebuffer. The post pass then resolves it to the default framebuffer, applying exposure and
+//! tone mapping. For auto-exposure the log-luminance of the scene is first drawn into a small
+//! texture and reduced by generating its mipmaps, so the smallest level holds the average
+//! log-luminance of the whole image.
+//!
+//! The HDR target is large enough for the window and every render target. Each camera draws into
+//! and resolves only the region covered by its viewport, so auto-exposure is measured separately
+//! for each camera.
+
+use hdr::ToneMapping;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::*;
+use super::gl_util::context::Context;
+use super::gl_util::framebuffer::{
+    self,
+    BlitFilter,
+    ClearBufferMask,
+    FramebufferAttachment,
+    Framebuffer,
+    Rect,
+};
+use super::gl_util::texture::{Texture2d as GlTexture2d, TextureInternalFormat};
+
+/// The width and height of the log-luminance texture used for auto-exposure.
+pub const LUMINANCE_SIZE: usize = 256;
+
+/// The smallest mipmap level of the log-luminance texture, which is 1x1.
+const LUMINANCE_LEVEL: f32 = 8.0;
+
+pub static LUMINANCE_FRAG_SOURCE: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D hdr_color;
+
+    // The region of `hdr_color` to measure, as `(x, y, width, height)` in texture coordinates.
+    uniform vec4 source_rect;
+
+    in vec2 _uv_;
+
+    out vec4 log_luminance;
+
+    void main(void) {
+        vec3 color = texture(hdr_color, source_rect.xy + _uv_ * source_rect.zw).rgb;
+        float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+        log_luminance = vec4(log(max(luminance, 0.0001)), 0.0, 0.0, 1.0);
+    }
+"#;
+
+pub static TONEMAP_FRAG_SOURCE: &'static str = r#"
+    #version 330 core
+
+    uniform sampler2D hdr_color;
+    uniform sampler2D log_luminance;
+    uniform float luminance_level;
+    uniform int tone_mapping;
+    uniform float exposure;
+    uniform int auto_exposure;
+    uniform float auto_exposure_key;
+    uniform float min_exposure;
+    uniform float max_exposure;
+
+    in vec2 _uv_;
+
+    out vec4 fragment_color;
+
+    // Krzysztof Narkowicz's fit of the ACES filmic tone mapping curve.
+    vec3 aces(vec3 x) {
+        const float a = 2.51;
+        const float b = 0.03;
+        const float c = 2.43;
+        const float d = 0.59;
+        const float e = 0.14;
+        return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+    }
+
+    void main(void) {
+        // The HDR target shares its origin with the window and render targets, so the fragment's
+        // texel is at its window coordinates regardless of the viewport.
+        vec3 color = texelFetch(hdr_color, ivec2(gl_FragCoord.xy), 0).rgb;
+
+        float scale = exposure;
+        if (auto_exposure != 0) {
+            float average_luminance = exp(textureLod(log_luminance, vec2(0.5), luminance_level).r);
+            scale *= clamp(auto_exposure_key / average_luminance, min_exposure, max_exposure);
+        }
+        color *= scale;
+
+        if (tone_mapping == 0) {
+            color = color / (1.0 + color);
+        } else {
+            color = aces(color);
+        }
+
+        fragment_color = vec4(color, 1.0);
+    }
+"#;
+
+/// The render targets used while HDR is enabled.
+#[derive(Debug)]
+pub struct HdrTarget {
+    pub framebuffer: Framebuffer,
+    pub color: GlTexture2d,
+    pub depth: GlTexture2d,
+
+    pub luminance_framebuffer: Framebuffer,
+
+    /// The log-luminance of the scene, `LUMINANCE_SIZE` texels square.
+    pub luminance: GlTexture2d,
+
+    pub width: usize,
+    pub height: usize,
+}
+
+impl HdrTarget {
+    pub fn new(context: &Context, width: usize, height: usize) -> HdrTarget {
+        let color = GlTexture2d::allocate(context, TextureInternalFormat::Rgba16F, width, height)
+            .expect("Failed to allocate HDR color texture");
+        let depth = GlTexture2d::allocate(context, TextureInternalFormat::DepthComponent24, width, height)
+            .expect("Failed to allocate HDR depth texture");
+
+        let mut framebuffer = Framebuffer::new(context).expect("Failed to create HDR framebuffer");
+        framebuffer.attach_texture(FramebufferAttachment::Color0, &color);
+        framebuffer.attach_texture(FramebufferAttachment::Depth, &depth);
+
+        let luminance = GlTexture2d::allocate(
+            context,
+            TextureInternalFormat::R16F,
+            LUMINANCE_SIZE,
+            LUMINANCE_SIZE,
+        ).expect("Failed to allocate luminance texture");
+
+        let mut luminance_framebuffer = Framebuffer::new(context).expect("Failed to create luminance framebuffer");
+        luminance_framebuffer.attach_texture(FramebufferAttachment::Color0, &luminance);
+
+        HdrTarget {
+            framebuffer: framebuffer,
+            color: color,
+            depth: depth,
+
+            luminance_framebuffer: luminance_framebuffer,
+            luminance: luminance,
+
+            width: width,
+            height: height,
+        }
+    }
+}
+
+impl GlRender {
+    /// Makes sure the HDR target exists and matches the size needed by the cameras, then clears
+    /// it.
+    pub(super) fn prepare_hdr_target(&mut self) {
+        let (width, height) = self.intermediate_target_size();
+
+        let is_current = match self.hdr_target {
+            Some(ref hdr_target) => hdr_target.width == width && hdr_target.height == height,
+            None => false,
+        };
+
+        if !is_current {
+            self.hdr_target = Some(HdrTarget::new(&self.context, width, height));
+        }
+
+        let hdr_target = self.hdr_target.as_ref().unwrap();
+        self.context.clear_framebuffer(&hdr_target.framebuffer);
+    }
+
+    /// Returns the framebuffer the scene passes draw into, or `None` for the default framebuffer.
+    ///
+    /// This is the MSAA target while MSAA is enabled, which is resolved into the HDR target (or
+    /// the output framebuffer) by the post pass.
+    pub(super) fn scene_framebuffer(&self) -> Option<&Framebuffer> {
+        if let Some(ref msaa_target) = self.msaa_target {
+            Some(&msaa_target.framebuffer)
+        } else if self.hdr_settings.enabled {
+            self.hdr_target.as_ref().map(|hdr_target| &hdr_target.framebuffer)
+        } else {
+            self.output_framebuffer()
+        }
+    }
+
+    /// Tone maps the region of the HDR target covered by the current viewport into the current
+    /// camera's output framebuffer, see `output_framebuffer()`.
+    ///
+    /// Does nothing if HDR is disabled.
+    pub(super) fn resolve_hdr(&mut self) {
+        if !self.hdr_settings.enabled {
+            return;
+        }
+
+        let _stopwatch = Stopwatch::new("Resolve HDR");
+
+        let settings = self.hdr_settings;
+        let hdr_target = self.hdr_target.as_mut().expect("HDR target must be prepared before resolving");
+        let viewport = self.context.viewport();
+
+        // Matches `output_framebuffer()`, which can't be called while the HDR target is borrowed.
+        let output_framebuffer = match self.camera_target {
+            Some(texture) => Some(&self.render_targets.get(&texture).expect("Camera's render target does not exist").framebuffer),
+            None => None,
+        };
+
+        if settings.auto_exposure {
+            let _stopwatch = Stopwatch::new("Luminance reduction");
+
+            let source_rect = [
+                viewport.x as f32 / hdr_target.width as f32,
+                viewport.y as f32 / hdr_target.height as f32,
+                viewport.width as f32 / hdr_target.width as f32,
+                viewport.height as f32 / hdr_target.height as f32,
+            ];
+
+            self.context.set_viewport(Rect::new(0, 0, LUMINANCE_SIZE as i32, LUMINANCE_SIZE as i32));
+
+            DrawBuilder::new(&self.context, &self.fullscreen_triangle, DrawMode::Triangles)
+            .framebuffer(&hdr_target.luminance_framebuffer)
+            .program(&self.luminance_program)
+            .uniform("hdr_color", &hdr_target.color)
+            .uniform("source_rect", source_rect)
+            .draw();
+
+            self.context.set_viewport(viewport);
+
+            hdr_target.luminance.generate_mipmaps();
+        }
+
+        let tone_mapping = match settings.tone_mapping {
+            ToneMapping::Reinhard => 0i32,
+            ToneMapping::Aces => 1i32,
+        };
+
+        let mut draw_builder = DrawBuilder::new(&self.context, &self.fullscreen_triangle, DrawMode::Triangles);
+        draw_builder
+        .program(&self.tonemap_program)
+        .uniform("hdr_color", &hdr_target.color)
+        .uniform("log_luminance", &hdr_target.luminance)
+        .uniform("luminance_level", LUMINANCE_LEVEL)
+        .uniform("tone_mapping", tone_mapping)
+        .uniform("exposure", settings.exposure)
+        .uniform("auto_exposure", settings.auto_exposure as i32)
+        .uniform("auto_exposure_key", settings.auto_exposure_key)
+        .uniform("min_exposure", settings.min_exposure)
+        .uniform("max_exposure", settings.max_exposure);
+
+        if let Some(framebuffer) = output_framebuffer {
+            draw_builder.framebuffer(framebuffer);
+        }
+
+        draw_builder.draw();
+
+        // Copy the scene's depth so that later draws are depth tested against it.
+        framebuffer::blit(
+            &self.context,
+            Some(&hdr_target.framebuffer),
+            output_framebuffer,
+            viewport,
+            viewport,
+            ClearBufferMask::Depth,
+            BlitFilter::Nearest,
+        );
+    }
+}
+
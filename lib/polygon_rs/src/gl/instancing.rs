@@ -0,0 +1,211 @@
+//! Hardware instancing of repeated meshes.
+//!
+//! Before each frame the renderer looks for mesh instances that share a mesh, a shared material,
+//! and a layer. Each such group of at least `MIN_INSTANCES` mesh instances becomes an
+//! `InstanceBatch`, whose per-instance transforms are uploaded to an instance buffer so that the
+//! whole group is drawn with a single draw call per submesh and light pass. Mesh instances with a
+//! skeleton, with slot materials, with a LOD group, on a billboard anchor, or with a transparent
+//! material or custom program are always drawn individually.
+//!
+//! Every material vertex program built from a material source includes `INSTANCING_SOURCE`. When `instanced` is set the model
+//! transforms are read from per-instance attributes instead of uniforms, which is hidden from the
+//! material's vertex program by redefining the names of the model transform uniforms. Instancing
+//! is only used by the forward pipeline.
+
+use {Counter, GpuMesh};
+use anchor::Billboard;
+use material::MaterialId;
+use mesh_instance::MeshInstanceId;
+use std::collections::HashMap;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::*;
+use super::gl_util::vertex::{Vertex, VertexAttrib};
+
+/// The smallest number of mesh instances that are drawn as an instance batch.
+pub const MIN_INSTANCES: usize = 4;
+
+/// The GLSL declarations for instancing, shared by every material vertex program.
+///
+/// Must be included after `BUILT_IN_UNIFORMS`, and `apply_instancing()` must be called at the
+/// start of `main()`.
+pub static INSTANCING_SOURCE: &'static str = r#"
+    uniform int instanced;
+
+    layout(location = 6) in mat4 instance_model_transform;
+    layout(location = 10) in mat3 instance_normal_transform;
+
+    mat4 _instanced_model_transform_;
+    mat3 _instanced_normal_transform_;
+    mat4 _instanced_model_view_transform_;
+    mat3 _instanced_view_normal_transform_;
+    mat4 _instanced_model_view_projection_;
+
+    void apply_instancing() {
+        if (instanced == 0) {
+            _instanced_model_transform_ = model_transform;
+            _instanced_normal_transform_ = normal_transform;
+            _instanced_model_view_transform_ = model_view_transform;
+            _instanced_view_normal_transform_ = view_normal_transform;
+            _instanced_model_view_projection_ = model_view_projection;
+            return;
+        }
+
+        _instanced_model_transform_ = instance_model_transform;
+        _instanced_normal_transform_ = instance_normal_transform;
+        _instanced_model_view_transform_ = view_transform * instance_model_transform;
+        _instanced_view_normal_transform_ = mat3(view_transform) * instance_normal_transform;
+        _instanced_model_view_projection_ = projection_transform * _instanced_model_view_transform_;
+    }
+
+    #define model_transform _instanced_model_transform_
+    #define normal_transform _instanced_normal_transform_
+    #define model_view_transform _instanced_model_view_transform_
+    #define view_normal_transform _instanced_view_normal_transform_
+    #define model_view_projection _instanced_model_view_projection_
+"#;
+
+/// The per-instance attributes of an instance batch.
+///
+/// The transforms are stored column by column, since that's how matrix attributes are read by
+/// the shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceData {
+    pub model_transform: [[f32; 4]; 4],
+    pub normal_transform: [[f32; 3]; 3],
+}
+
+unsafe impl Vertex for InstanceData {
+    fn attribs() -> Vec<VertexAttrib> {
+        let mut attribs = Vec::with_capacity(7);
+        for column in 0..4 {
+            attribs.push(VertexAttrib::from_field(&[0.0f32; 4], 6 + column as u32, column * 16));
+        }
+        for column in 0..3 {
+            attribs.push(VertexAttrib::from_field(&[0.0f32; 3], 10 + column as u32, 64 + column * 12));
+        }
+        attribs
+    }
+}
+
+/// A group of mesh instances drawn together with a single draw call.
+#[derive(Debug)]
+pub struct InstanceBatch {
+    pub mesh: GpuMesh,
+    pub material: MaterialId,
+    pub mesh_instances: Vec<MeshInstanceId>,
+    pub instances: InstanceBuffer,
+}
+
+impl GlRender {
+    /// Regroups the mesh instances into instance batches and uploads their transforms.
+    ///
+    /// Instance buffers from the previous frame are reused where possible.
+    pub(super) fn prepare_instancing(&mut self) {
+        let _stopwatch = Stopwatch::new("Prepare instancing");
+
+        let mut groups = HashMap::<(GpuMesh, MaterialId, u8), Vec<MeshInstanceId>>::new();
+        for (&material_id, mesh_instance_ids) in &self.mesh_instances_with_shared_materials {
+            let material = self.shared_materials.get(&material_id).expect("No such material exists");
+            if material.is_transparent() || self.custom_programs.contains_key(material.shader()) {
+                continue;
+            }
+
+            for &mesh_instance_id in mesh_instance_ids {
+                let mesh_instance = self.mesh_instances.get(&mesh_instance_id).expect("No such mesh instance");
+                let is_billboard = mesh_instance.anchor()
+                    .and_then(|anchor_id| self.anchors.get(&anchor_id))
+                    .map_or(false, |anchor| anchor.billboard() != Billboard::None);
+                if mesh_instance.anchor().is_none()
+                    || is_billboard
+                    || mesh_instance.skeleton().is_some()
+                    || mesh_instance.has_slot_materials()
+                    || mesh_instance.lod_group().is_some()
+                {
+                    continue;
+                }
+
+                groups
+                    .entry((*mesh_instance.mesh(), material_id, mesh_instance.layer()))
+                    .or_insert_with(Vec::new)
+                    .push(mesh_instance_id);
+            }
+        }
+
+        // Sort the groups so that the batches are drawn in the same order every frame.
+        let mut groups: Vec<_> = groups
+            .into_iter()
+            .filter(|&(_, ref mesh_instance_ids)| mesh_instance_ids.len() >= MIN_INSTANCES)
+            .collect();
+        groups.sort_by_key(|&((mesh, material, layer), _)| (layer, material.index(), mesh.index()));
+
+        self.instanced_mesh_instances.clear();
+        let mut instance_buffers: Vec<_> = self.instance_batches
+            .drain(..)
+            .map(|batch| batch.instances)
+            .collect();
+
+        for ((mesh, material, _), mut mesh_instance_ids) in groups {
+            mesh_instance_ids.sort_by_key(|mesh_instance_id| mesh_instance_id.index());
+
+            let instance_data: Vec<_> = mesh_instance_ids
+                .iter()
+                .map(|mesh_instance_id| self.instance_data(*mesh_instance_id))
+                .collect();
+
+            let mut instances = instance_buffers
+                .pop()
+                .unwrap_or_else(|| InstanceBuffer::new(&self.context));
+            instances.set_data(&*instance_data);
+
+            self.instanced_mesh_instances.extend(mesh_instance_ids.iter().cloned());
+
+            self.instance_batches.push(InstanceBatch {
+                mesh: mesh,
+                material: material,
+                mesh_instances: mesh_instance_ids,
+                instances: instances,
+            });
+        }
+    }
+
+    fn instance_data(&self, mesh_instance_id: MeshInstanceId) -> InstanceData {
+        let mesh_instance = self.mesh_instances.get(&mesh_instance_id).expect("No such mesh instance");
+        let anchor_id = mesh_instance.anchor().expect("Instanced mesh instance must have an anchor");
+        let anchor = self.anchors.get(&anchor_id).expect("No such anchor exists");
+
+        let model_columns = anchor.matrix().transpose();
+        let normal_columns = anchor.normal_matrix().transpose();
+        let model = model_columns.raw_data();
+        let normal = normal_columns.raw_data();
+
+        InstanceData {
+            model_transform: [
+                [model[0], model[1], model[2], model[3]],
+                [model[4], model[5], model[6], model[7]],
+                [model[8], model[9], model[10], model[11]],
+                [model[12], model[13], model[14], model[15]],
+            ],
+            normal_transform: [
+                [normal[0], normal[1], normal[2]],
+                [normal[3], normal[4], normal[5]],
+                [normal[6], normal[7], normal[8]],
+            ],
+        }
+    }
+}
+
+/// Sets the instancing uniforms for a draw and attaches the instances, see `INSTANCING_SOURCE`.
+pub fn apply_instancing<'a>(draw_builder: &mut DrawBuilder<'a>, instances: Option<&'a InstanceBuffer>) {
+    match instances {
+        Some(instances) => {
+            draw_builder
+            .uniform("instanced", 1i32)
+            .instances(instances);
+        },
+        None => {
+            draw_builder.uniform("instanced", 0i32);
+        },
+    }
+}
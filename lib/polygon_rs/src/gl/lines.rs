@@ -0,0 +1,124 @@
+//! Drawing the primitives collected by the `LineRenderer`.
+//!
+//! All vertices of a kind of primitive are streamed into a single vertex array and drawn with one
+//! draw call, so each frame takes at most six draws: lines, triangles, and points for both the
+//! depth tested and overlay batches.
+
+use line_renderer::{LineRenderer, PrimitiveBatch, PrimitiveVertex};
+use math::Matrix4;
+use std::mem;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::*;
+use super::gl_util::vertex::{Vertex, VertexAttrib};
+
+pub static PRIMITIVE_VERT_SOURCE: &'static str = r#"
+    #version 330 core
+
+    uniform mat4 view_projection;
+    uniform float point_size;
+
+    layout(location = 0) in vec4 vertex_position;
+    layout(location = 1) in vec4 vertex_color;
+
+    out vec4 _color_;
+
+    void main(void) {
+        _color_ = vertex_color;
+        gl_Position = view_projection * vertex_position;
+        gl_PointSize = point_size;
+    }
+"#;
+
+pub static PRIMITIVE_FRAG_SOURCE: &'static str = r#"
+    #version 330 core
+
+    in vec4 _color_;
+
+    out vec4 fragment_color;
+
+    void main(void) {
+        fragment_color = _color_;
+    }
+"#;
+
+unsafe impl Vertex for PrimitiveVertex {
+    fn attribs() -> Vec<VertexAttrib> {
+        let color_offset = mem::size_of::<[f32; 4]>();
+        vec![
+            VertexAttrib::from_field(&[0.0f32; 4], 0, 0),
+            VertexAttrib::from_field(&[0.0f32; 4], 1, color_offset),
+        ]
+    }
+}
+
+impl GlRender {
+    /// Draws the primitives in the line renderer with the camera transform `view_projection`.
+    ///
+    /// Primitives are always drawn to the camera's output framebuffer, after tone mapping, so that
+    /// their colors are displayed exactly as specified. Depth tested primitives use the comparison
+    /// `depth_test`, which depends on the camera's depth conventions.
+    pub(super) fn render_primitives(&mut self, view_projection: Matrix4, depth_test: Comparison) {
+        if self.line_renderer.is_empty() {
+            return;
+        }
+
+        let _stopwatch = Stopwatch::new("Render primitives");
+
+        // Take the line renderer so that its batches can be read while the vertex array is updated.
+        let line_renderer = mem::replace(&mut self.line_renderer, LineRenderer::new());
+        let point_size = line_renderer.point_size();
+        self.draw_primitive_batch(line_renderer.depth_tested(), Some(depth_test), point_size, view_projection.raw_data());
+        self.draw_primitive_batch(line_renderer.overlay(), None, point_size, view_projection.raw_data());
+
+        self.line_renderer = line_renderer;
+    }
+
+    fn draw_primitive_batch(
+        &mut self,
+        batch: &PrimitiveBatch,
+        depth_test: Option<Comparison>,
+        point_size: f32,
+        view_projection: &[f32; 16],
+    ) {
+        let draws = [
+            (&batch.lines, DrawMode::Lines),
+            (&batch.triangles, DrawMode::Triangles),
+            (&batch.points, DrawMode::Points),
+        ];
+
+        for &(vertices, draw_mode) in &draws {
+            if vertices.is_empty() {
+                continue;
+            }
+
+            self.primitive_vertex_array.stream_data(&vertices[..]);
+
+            let mut draw_builder = DrawBuilder::new(&self.context, &self.primitive_vertex_array, draw_mode);
+            draw_builder
+            .program(&self.primitive_program)
+            .blend(SourceFactor::SourceAlpha, DestFactor::OneMinusSourceAlpha)
+            .uniform(
+                "view_projection",
+                GlMatrix {
+                    data: view_projection,
+                    transpose: true,
+                },
+            )
+            .uniform("point_size", point_size);
+
+            if let Some(framebuffer) = self.output_framebuffer() {
+                draw_builder.framebuffer(framebuffer);
+            }
+
+            // Depth tested primitives are hidden by the scene, but don't hide each other.
+            if let Some(depth_test) = depth_test {
+                draw_builder
+                .depth_test(depth_test)
+                .depth_write(false);
+            }
+
+            draw_builder.draw();
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Cross-fading between LOD levels.
+//!
+//! While a mesh instance cross-fades between two LOD levels both levels are drawn, discarding
+//! fragments in an ordered dither pattern. The level fading in keeps the fragments whose dither
+//! threshold is below the fade, and the level fading out keeps the rest, so every pixel is
+//! covered by exactly one of the levels.
+
+/// The GLSL declarations for LOD cross-fading, shared by every fragment program that draws
+/// mesh instances.
+///
+/// `apply_lod_fade()` must be called at the start of `main()`.
+pub static LOD_FADE_SOURCE: &'static str = r#"
+    uniform float lod_fade;
+
+    void apply_lod_fade() {
+        if (lod_fade == 0.0) {
+            return;
+        }
+
+        // A 4x4 Bayer matrix, giving each pixel in a block a different threshold.
+        const float bayer[16] = float[16](
+            0.0, 8.0, 2.0, 10.0,
+            12.0, 4.0, 14.0, 6.0,
+            3.0, 11.0, 1.0, 9.0,
+            15.0, 7.0, 13.0, 5.0);
+
+        ivec2 cell = ivec2(gl_FragCoord.xy) % 4;
+        float threshold = (bayer[cell.y * 4 + cell.x] + 0.5) / 16.0;
+
+        bool visible = lod_fade > 0.0 ? threshold < lod_fade : threshold >= -lod_fade;
+        if (!visible) {
+            discard;
+        }
+    }
+"#;
@@ -1,30 +1,136 @@
 pub extern crate gl_util;
 
-use {BuildMaterialError, Counter, GpuMesh, Renderer};
-use anchor::*;
+use {BuildMaterialError, Counter, GpuMesh, RenderPath, Renderer};
+use anchor::{self, Anchor, AnchorId};
+use antialiasing::AntiAliasing;
 use bootstrap::window::Window;
 use camera::*;
-use geometry::mesh::{Mesh, VertexAttribute};
+use debug::DebugMode;
+use geometry::mesh::{Mesh, Submesh, VertexAttribute};
+use hdr::HdrSettings;
 use light::*;
+use line_renderer::LineRenderer;
+use lod::*;
 use material::*;
 use mesh_instance::*;
+use occlusion::{DepthBuffer, Occluder, OccluderId, OcclusionSettings};
 use math::*;
+use pass::*;
 use self::gl_util::*;
+use self::gl_util::buffer::UniformBuffer;
 use self::gl_util::context::{Context, Error as ContextError};
+use self::gl_util::framebuffer::{self, Rect};
 use self::gl_util::shader::*;
 use self::gl_util::shader::Shader as GlShader;
 use self::gl_util::texture::{
     Texture2d as GlTexture2d,
+    TextureCube,
     TextureFormat,
     TextureInternalFormat,
 };
+use self::debug::*;
+use self::deferred::*;
+use self::depth::*;
+use self::environment::*;
+use self::hdr::*;
+use self::instancing::*;
+use self::lod::LOD_FADE_SOURCE;
+use self::msaa::*;
+use self::normal_map::NORMAL_MAP_SOURCE;
+use self::queue::QueueItem;
+use self::render_target::*;
+use self::forward::LightBatch;
+use self::lines::*;
+use self::shadow::*;
+use self::skinning::*;
+use self::skybox::*;
+use self::stats::GpuTimers;
+use self::streaming::TextureStreamer;
+use self::uniforms::*;
 use shader::Shader;
-use std::collections::HashMap;
+use shadow::{MAX_CASCADES, MAX_POINT_SHADOWS, ShadowSettings};
+use skeleton::*;
+use stats::FrameStats;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::path::Path;
 use std::str;
+use std::time::Instant;
 use stopwatch::Stopwatch;
+use streaming::{StreamingError, StreamingSettings};
+use terrain::{Terrain, TerrainId};
 use texture::*;
 
+mod debug;
+mod deferred;
+mod depth;
+mod environment;
+mod hdr;
+mod forward;
+mod instancing;
+mod lines;
+mod lod;
+mod msaa;
+mod normal_map;
+mod occlusion;
+mod queue;
+mod render_target;
+mod shadow;
+mod skinning;
+mod skybox;
+mod stats;
+mod streaming;
+mod stereo;
+mod uniforms;
+
 static DEFAULT_SHADER_BYTES: &'static [u8] = include_bytes!("../../resources/materials/diffuse_lit.material");
+static PBR_SHADER_BYTES: &'static [u8] = include_bytes!("../../resources/materials/pbr_lit.material");
+static TERRAIN_SHADER_BYTES: &'static [u8] = include_bytes!("../../resources/materials/terrain.material");
+
+/// Uniforms available to every material's vertex and fragment programs, see the `material`
+/// module docs.
+///
+/// Everything but the samplers is grouped into the `FrameUniforms` and `ObjectUniforms` blocks,
+/// see the `uniforms` module. The order of the block members must match
+/// `upload_frame_uniforms()` and `ObjectUniforms`.
+static BUILT_IN_UNIFORMS: &'static str = r#"
+    layout(std140, row_major) uniform FrameUniforms {
+        mat4 view_transform;
+        mat4 projection_transform;
+        vec4 camera_position;
+        vec4 global_ambient;
+        float time;
+
+        int light_type[8];
+        vec4 light_position[8];
+        vec4 light_position_view[8];
+        float light_strength[8];
+        vec4 light_color[8];
+        float light_radius[8];
+        vec3 light_direction[8];
+        vec3 light_direction_view[8];
+        float light_cos_inner[8];
+        float light_cos_outer[8];
+
+        int shadow_light_index;
+        int shadow_cascade_count;
+        float shadow_bias;
+        mat4 shadow_transform[4];
+        int light_shadow_map[8];
+    };
+
+    layout(std140, row_major) uniform ObjectUniforms {
+        mat4 model_transform;
+        mat3 normal_transform;
+        mat3 view_normal_transform;
+        mat4 model_view_transform;
+        mat4 model_view_projection;
+        int has_vertex_color;
+    };
+
+    uniform sampler2DShadow shadow_map[4];
+    uniform samplerCube point_shadow_map[4];
+"#;
 
 #[derive(Debug)]
 pub struct GlRender {
@@ -37,8 +143,19 @@ pub struct GlRender {
     anchors: HashMap<AnchorId, Anchor>,
     cameras: HashMap<CameraId, Camera>,
     lights: HashMap<LightId, Light>,
+    skeletons: HashMap<SkeletonId, Skeleton>,
+    lod_groups: HashMap<LodGroupId, LodGroup>,
+    occluders: HashMap<OccluderId, Occluder>,
+    terrains: HashMap<TerrainId, Terrain>,
     programs: HashMap<Shader, Program>,
 
+    /// The names of the texture properties declared by each material program.
+    texture_properties: HashMap<Shader, Vec<String>>,
+
+    /// The built-in uniforms consumed by each custom program. Programs built from material
+    /// sources aren't listed, since they consume all of them.
+    custom_programs: HashMap<Shader, EngineUniforms>,
+
     mesh_instances_with_shared_materials: HashMap<MaterialId, Vec<MeshInstanceId>>,
     mesh_instances_with_owned_material: Vec<MeshInstanceId>,
 
@@ -49,11 +166,98 @@ pub struct GlRender {
     anchor_counter: AnchorId,
     camera_counter: CameraId,
     light_counter: LightId,
+    skeleton_counter: SkeletonId,
+    lod_group_counter: LodGroupId,
+    occluder_counter: OccluderId,
+    terrain_counter: TerrainId,
     shader_counter: Shader,
 
     ambient_color: Color,
 
+    /// When the renderer was created, and the seconds since then at the start of the current
+    /// frame, see the `time` built-in uniform.
+    start_time: Instant,
+    time: f32,
+
+    /// The buffers backing the `FrameUniforms` and `ObjectUniforms` blocks.
+    frame_uniforms: UniformBuffer,
+    object_uniforms: UniformBuffer,
+
     default_material: Material,
+    pbr_material: Material,
+    terrain_material: Material,
+
+    shadow_settings: ShadowSettings,
+    shadow_program: Program,
+    shadow_cascades: Vec<ShadowCascade>,
+    shadow_transforms: Vec<Matrix4>,
+    shadow_light: Option<usize>,
+    point_shadow_program: Program,
+    point_shadows: Vec<PointShadow>,
+    point_shadow_lights: Vec<usize>,
+
+    /// Bound to unused shadow map and environment samplers, since samplers of different types
+    /// must not refer to the same texture unit.
+    default_shadow_map: GlTexture2d,
+    default_point_shadow_map: TextureCube,
+
+    environment: Option<Environment>,
+    irradiance_program: Program,
+    prefilter_program: Program,
+
+    render_path: RenderPath,
+    gbuffer: Option<GBuffer>,
+    gbuffer_program: Program,
+    deferred_lighting_program: Program,
+    fullscreen_triangle: VertexArray,
+
+    hdr_settings: HdrSettings,
+    hdr_target: Option<HdrTarget>,
+    luminance_program: Program,
+    tonemap_program: Program,
+
+    anti_aliasing: AntiAliasing,
+    msaa_target: Option<MsaaTarget>,
+
+    gamma_correct: bool,
+
+    debug_mode: DebugMode,
+    debug_program: Program,
+
+    skybox_program: Program,
+
+    passes: PassList,
+
+    line_renderer: LineRenderer,
+    primitive_program: Program,
+    primitive_vertex_array: VertexArray,
+
+    instance_batches: Vec<InstanceBatch>,
+    instanced_mesh_instances: HashSet<MeshInstanceId>,
+
+    render_targets: HashMap<GpuTexture, RenderTarget>,
+
+    /// The render target of the camera currently being drawn, or `None` if it draws to the window.
+    camera_target: Option<GpuTexture>,
+
+    /// The camera and anchor mesh instances are culled against while drawing the eyes of a
+    /// stereo camera, see the `stereo` module.
+    culling_view: Option<(Camera, Anchor)>,
+
+    frame_capture: bool,
+    captured_frame: Option<Image>,
+
+    gpu_timers: GpuTimers,
+    frame_stats: FrameStats,
+
+    occlusion_settings: OcclusionSettings,
+
+    /// The occluders rasterized for the camera currently being drawn, or `None` if nothing is
+    /// culled.
+    occlusion_buffer: Option<DepthBuffer>,
+
+    texture_streamer: TextureStreamer,
+    streaming_settings: StreamingSettings,
 }
 
 impl GlRender {
@@ -70,6 +274,99 @@ impl GlRender {
             context.clear();
         }
 
+        let shadow_program = {
+            let vert_shader = GlShader::new(&context, shadow_vert_source(), ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, SHADOW_FRAG_SOURCE, ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let point_shadow_program = {
+            let vert_shader = GlShader::new(&context, point_shadow_vert_source(), ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, POINT_SHADOW_FRAG_SOURCE, ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let default_point_shadow_map = TextureCube::allocate(
+            &context,
+            TextureInternalFormat::DepthComponent24,
+            1,
+        ).expect("Failed to allocate default point shadow map");
+
+        let mut default_shadow_map = GlTexture2d::allocate(
+            &context,
+            TextureInternalFormat::DepthComponent24,
+            1,
+            1,
+        ).expect("Failed to allocate default shadow map");
+        default_shadow_map.set_depth_compare(Some(Comparison::LessEqual));
+
+        let gbuffer_program = {
+            let vert_shader = GlShader::new(&context, gbuffer_vert_source(), ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, gbuffer_frag_source(), ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let debug_program = {
+            let vert_shader = GlShader::new(&context, debug_vert_source(), ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, debug_frag_source(), ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let deferred_lighting_program = {
+            let vert_shader = GlShader::new(&context, FULLSCREEN_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, lighting_frag_source(), ShaderType::Fragment).unwrap();
+            let program = Program::new(&context, &[vert_shader, frag_shader]).unwrap();
+            bind_uniform_blocks(&program);
+            program
+        };
+
+        let luminance_program = {
+            let vert_shader = GlShader::new(&context, FULLSCREEN_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, LUMINANCE_FRAG_SOURCE, ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let tonemap_program = {
+            let vert_shader = GlShader::new(&context, FULLSCREEN_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, TONEMAP_FRAG_SOURCE, ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let skybox_program = {
+            let vert_shader = GlShader::new(&context, FULLSCREEN_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, SKYBOX_FRAG_SOURCE, ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let irradiance_program = {
+            let vert_shader = GlShader::new(&context, FULLSCREEN_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, irradiance_frag_source(), ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let prefilter_program = {
+            let vert_shader = GlShader::new(&context, FULLSCREEN_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, prefilter_frag_source(), ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let primitive_program = {
+            let vert_shader = GlShader::new(&context, PRIMITIVE_VERT_SOURCE, ShaderType::Vertex).unwrap();
+            let frag_shader = GlShader::new(&context, PRIMITIVE_FRAG_SOURCE, ShaderType::Fragment).unwrap();
+            Program::new(&context, &[vert_shader, frag_shader]).unwrap()
+        };
+
+        let frame_uniforms = UniformBuffer::new(&context).expect("Failed to create frame uniform buffer");
+        let object_uniforms = UniformBuffer::new(&context).expect("Failed to create object uniform buffer");
+
+        let primitive_vertex_array = VertexArray::from_vertices::<PrimitiveVertex>(&context, &[]);
+
+        let mut fullscreen_triangle = VertexArray::new(&context, &FULLSCREEN_TRIANGLE);
+        fullscreen_triangle.set_attrib(
+            AttributeLocation::from_index(0),
+            AttribLayout { elements: 2, stride: 0, offset: 0 },
+        );
+
         let mut renderer = GlRender {
             context: context,
 
@@ -80,7 +377,13 @@ impl GlRender {
             anchors: HashMap::new(),
             cameras: HashMap::new(),
             lights: HashMap::new(),
+            skeletons: HashMap::new(),
+            lod_groups: HashMap::new(),
+            occluders: HashMap::new(),
+            terrains: HashMap::new(),
             programs: HashMap::new(),
+            texture_properties: HashMap::new(),
+            custom_programs: HashMap::new(),
 
             mesh_instances_with_shared_materials: HashMap::new(),
             mesh_instances_with_owned_material: Vec::new(),
@@ -92,14 +395,89 @@ impl GlRender {
             anchor_counter: AnchorId::initial(),
             camera_counter: CameraId::initial(),
             light_counter: LightId::initial(),
+            skeleton_counter: SkeletonId::initial(),
+            lod_group_counter: LodGroupId::initial(),
+            occluder_counter: OccluderId::initial(),
+            terrain_counter: TerrainId::initial(),
             shader_counter: Shader::initial(),
 
             ambient_color: Color::rgb(0.01, 0.01, 0.01),
+            start_time: Instant::now(),
+            time: 0.0,
+
+            frame_uniforms: frame_uniforms,
+            object_uniforms: object_uniforms,
 
             // Use temporary value and replace it later.
             default_material: Material::new(Shader::initial()),
+            pbr_material: Material::new(Shader::initial()),
+            terrain_material: Material::new(Shader::initial()),
+
+            shadow_settings: ShadowSettings::default(),
+            shadow_program: shadow_program,
+            shadow_cascades: Vec::new(),
+            shadow_transforms: Vec::new(),
+            shadow_light: None,
+            point_shadow_program: point_shadow_program,
+            point_shadows: Vec::new(),
+            point_shadow_lights: Vec::new(),
+            default_shadow_map: default_shadow_map,
+            default_point_shadow_map: default_point_shadow_map,
+
+            environment: None,
+            irradiance_program: irradiance_program,
+            prefilter_program: prefilter_program,
+
+            render_path: RenderPath::Forward,
+            gbuffer: None,
+            gbuffer_program: gbuffer_program,
+            deferred_lighting_program: deferred_lighting_program,
+            fullscreen_triangle: fullscreen_triangle,
+
+            hdr_settings: HdrSettings::default(),
+            hdr_target: None,
+            luminance_program: luminance_program,
+            tonemap_program: tonemap_program,
+
+            anti_aliasing: AntiAliasing::Off,
+            msaa_target: None,
+
+            gamma_correct: true,
+
+            debug_mode: DebugMode::Off,
+            debug_program: debug_program,
+
+            skybox_program: skybox_program,
+
+            passes: PassList::new(),
+
+            line_renderer: LineRenderer::new(),
+            primitive_program: primitive_program,
+            primitive_vertex_array: primitive_vertex_array,
+
+            instance_batches: Vec::new(),
+            instanced_mesh_instances: HashSet::new(),
+
+            render_targets: HashMap::new(),
+            camera_target: None,
+            culling_view: None,
+
+            frame_capture: false,
+            captured_frame: None,
+
+            gpu_timers: GpuTimers::new(),
+            frame_stats: FrameStats::default(),
+
+            occlusion_settings: OcclusionSettings::default(),
+            occlusion_buffer: None,
+
+            texture_streamer: TextureStreamer::new(),
+            streaming_settings: StreamingSettings::default(),
         };
 
+        renderer.rebuild_shadow_cascades();
+        renderer.context.set_srgb_enabled(renderer.gamma_correct);
+
         // Load source code for the default material.
         let default_material_source = str::from_utf8(DEFAULT_SHADER_BYTES).unwrap();
         let material_source = MaterialSource::from_str(default_material_source).unwrap();
@@ -112,29 +490,107 @@ impl GlRender {
         let default_material = renderer.build_material(material_source).unwrap();
         renderer.default_material = default_material;
 
+        let pbr_material_source = str::from_utf8(PBR_SHADER_BYTES).unwrap();
+        let material_source = MaterialSource::from_str(pbr_material_source).unwrap();
+        let mut pbr_material = renderer.build_material(material_source).unwrap();
+        pbr_material.set_color("base_color", Color::rgb(1.0, 1.0, 1.0));
+        pbr_material.set_f32("roughness", 0.5);
+        pbr_material.set_f32("metallic", 0.0);
+        pbr_material.set_color("emissive", Color::rgb(0.0, 0.0, 0.0));
+        renderer.pbr_material = pbr_material;
+
+        let terrain_material_source = str::from_utf8(TERRAIN_SHADER_BYTES).unwrap();
+        let material_source = MaterialSource::from_str(terrain_material_source).unwrap();
+        let mut terrain_material = renderer.build_material(material_source).unwrap();
+        terrain_material.set_f32("layer_scale", 1.0);
+        renderer.terrain_material = terrain_material;
+
         Ok(renderer)
     }
 
+    /// Draws the submeshes of a queued mesh instance, either only those with opaque materials or
+    /// only those with transparent materials depending on `transparent`.
     fn render_mesh_instance(
         &self,
-        mesh_instance: &MeshInstance,
-        material: &Material,
+        item: &QueueItem,
         camera: &Camera,
         camera_anchor: &Anchor,
-        has_setup_lights: &mut bool,
-        has_setup_material: &mut bool,
+        light_batch: &LightBatch,
+        additive: bool,
+        transparent: bool,
     ) {
         let _s = Stopwatch::new("Rendering mesh instance");
 
+        let mesh_instance = item.mesh_instance;
         let anchor = match mesh_instance.anchor() {
             Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
             None => return,
         };
 
-        let model_transform = anchor.matrix();
-        let normal_transform = anchor.normal_matrix();
+        let mesh_data = self.meshes.get(&item.mesh).expect("Mesh data does not exist for mesh id");
+        let joint_palette = self.joint_palette(mesh_instance);
 
-        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+        for submesh in &mesh_data.submeshes {
+            let material = mesh_instance.slot_material(submesh.material_slot).unwrap_or(item.material);
+            if material.is_transparent() != transparent {
+                continue;
+            }
+
+            self.render_submesh(
+                anchor,
+                mesh_data,
+                submesh,
+                material,
+                joint_palette.as_ref().map(|data| &**data),
+                None,
+                item.lod_fade,
+                camera,
+                camera_anchor,
+                light_batch,
+                additive,
+            );
+        }
+    }
+
+    /// Draws a single submesh.
+    ///
+    /// The `FrameUniforms` block must already hold the values for `camera` and `light_batch`,
+    /// see `upload_frame_uniforms()`. The object uniforms and material properties are set for
+    /// every draw, since draws with different materials may be interleaved after sorting. If
+    /// `additive` is `true` the draw is blended on top of the previous passes without the ambient
+    /// term. Transparent materials are alpha blended and don't write depth. If `instances` is
+    /// given the submesh is drawn once for each instance, and `anchor` is only used for the model
+    /// transform uniforms that the instances override. `lod_fade` is the dither fade used while
+    /// cross-fading between LOD levels. Custom programs don't declare the uniform blocks, so they
+    /// only get the built-in uniforms they consume, and skip additive passes unless they consume
+    /// lights.
+    fn render_submesh(
+        &self,
+        anchor: &Anchor,
+        mesh_data: &MeshData,
+        submesh: &Submesh,
+        material: &Material,
+        joint_palette: Option<&[f32]>,
+        instances: Option<&InstanceBuffer>,
+        lod_fade: f32,
+        camera: &Camera,
+        camera_anchor: &Anchor,
+        light_batch: &LightBatch,
+        additive: bool,
+    ) {
+        let engine_uniforms = self.custom_programs.get(material.shader()).cloned();
+        let consumes_lights = engine_uniforms.map(|uniforms| uniforms.lights).unwrap_or(true);
+
+        // Programs that don't consume lights would draw the same color again in every
+        // additional light pass.
+        if additive && !consumes_lights {
+            return;
+        }
+
+        let camera_position = camera_anchor.world_position();
+        let camera_orientation = camera_anchor.world_orientation();
+        let model_transform = anchor.billboard_matrix(camera_position, camera_orientation);
+        let normal_transform = anchor.billboard_normal_matrix(camera_position, camera_orientation);
 
         let default_texture = GlTexture2d::empty(&self.context);
 
@@ -151,16 +607,14 @@ impl GlRender {
             inverse_model_view.transpose()
         };
 
-        // The data for the light uniforms must be declared before `draw_builder` so they
-        // can outlive it, since they are borrowed when the uniforms are set.
-        let mut light_type = [0i32; 8];
-        let mut light_strength = [0.0f32; 8];
-        let mut light_color = [Color::rgb(0.0, 0.0, 0.0); 8];
-        let mut light_position = [Point::origin(); 8];
-        let mut light_position_view = [Point::origin(); 8];
-        let mut light_radius = [0.0f32; 8];
-        let mut light_direction = [Vector3::zero(); 8];
-        let mut light_direction_view = [Vector3::zero(); 8];
+        let object_uniforms = ObjectUniforms {
+            model_transform: model_transform,
+            normal_transform: normal_transform,
+            view_normal_transform: view_normal_transform,
+            model_view_transform: model_view_transform,
+            model_view_projection: model_view_projection,
+            has_vertex_color: mesh_data.attributes.color,
+        };
 
         let program = self
             .programs
@@ -177,75 +631,50 @@ impl GlRender {
         draw_builder
         .program(program)
         .cull(Face::Back)
-        .depth_test(Comparison::Less);
+        .depth_test(depth_less(camera))
+        .index_range(submesh.start, submesh.count);
 
-        // Set uniform transforms.
-        {
-            let _stopwatch = Stopwatch::new("Transform uniforms");
+        if let Some(framebuffer) = self.scene_framebuffer() {
+            draw_builder.framebuffer(framebuffer);
+        }
 
+        // Additive passes only touch pixels that were already drawn by the first pass.
+        if additive {
             draw_builder
-            .uniform(
-                "model_transform",
-                GlMatrix {
-                    data: model_transform.raw_data(),
-                    transpose: true,
-                },
-            )
-            .uniform(
-                "normal_transform",
-                GlMatrix {
-                    data: normal_transform.raw_data(),
-                    transpose: true,
-                },
-            )
-            .uniform(
-                "view_normal_transform",
-                GlMatrix {
-                    data: view_normal_transform.raw_data(),
-                    transpose: true,
-                },
-            )
-            .uniform(
-                "view_transform",
-                GlMatrix {
-                    data: view_transform.raw_data(),
-                    transpose: true,
-                },
-            )
-            .uniform(
-                "model_view_transform",
-                GlMatrix {
-                    data: model_view_transform.raw_data(),
-                    transpose: true,
-                },
-            )
-            .uniform(
-                "projection_transform",
-                GlMatrix {
-                    data: projection_transform.raw_data(),
-                    transpose: true,
-                },
-            )
-            .uniform(
-                "model_view_projection",
-                GlMatrix {
-                    data: model_view_projection.raw_data(),
-                    transpose: true,
-                },
-            );
+            .depth_test(depth_less_equal(camera))
+            .depth_write(false)
+            .blend(SourceFactor::One, DestFactor::One);
         }
 
-        // Apply material attributes.
-        if !*has_setup_material {
-            let _stopwatch = Stopwatch::new("Material uniforms");
+        if material.is_transparent() {
+            draw_builder.depth_write(false);
 
-            *has_setup_material = true;
+            // Additional light passes must be scaled by the alpha as well, otherwise later
+            // lights would show at full strength through the surface.
+            if additive {
+                draw_builder.blend(SourceFactor::SourceAlpha, DestFactor::One);
+            } else {
+                draw_builder.blend(SourceFactor::SourceAlpha, DestFactor::OneMinusSourceAlpha);
+            }
+        }
 
-            // Set uniform colors.
-            draw_builder.uniform::<[f32; 4]>("global_ambient", self.ambient_color.into());
+        match engine_uniforms {
+            Some(engine_uniforms) => self.apply_custom_program_uniforms(
+                &mut draw_builder,
+                engine_uniforms,
+                &object_uniforms,
+                &view_transform,
+                &projection_transform,
+                camera_anchor,
+                light_batch,
+                additive,
+            ),
+            None => self.upload_object_uniforms(&object_uniforms),
+        }
 
-            // Other uniforms.
-            draw_builder.uniform("camera_position", *camera_anchor.position().as_array());
+        // Apply material attributes.
+        {
+            let _stopwatch = Stopwatch::new("Material uniforms");
 
             for (name, property) in material.properties() {
                 match *property {
@@ -255,74 +684,268 @@ impl GlRender {
                     MaterialProperty::f32(value) => {
                         draw_builder.uniform(name, value);
                     },
+                    MaterialProperty::i32(value) => {
+                        draw_builder.uniform(name, value);
+                    },
+                    MaterialProperty::Vector2(value) => {
+                        draw_builder.uniform(name, (value.x, value.y));
+                    },
                     MaterialProperty::Vector3(value) => {
                         draw_builder.uniform::<[f32; 3]>(name, value.into());
                     },
-                    MaterialProperty::Texture(ref texture) => {
-                        let gl_texture =
-                        self.textures
-                        .get(texture)
-                        .unwrap_or(&default_texture);
-                        draw_builder.uniform(name, gl_texture);
-                    },
+                    // Textures are set below, since they need to be set even if the property
+                    // isn't.
+                    MaterialProperty::Texture(_) => {},
                 }
             }
-        }
 
-        // Render all lights in a single pass by sending 8 lights at once in arrays.
-        // All light-related uniforms will stay the same for all draws for a given camera,
-        // so we only specify them for the first draw and leave them the same after that.
-        if !*has_setup_lights {
-            *has_setup_lights = true;
-
-            let _stopwatch = Stopwatch::new("Setup lights");
-
-            // TODO: Support having more than 8 lights active at a time. Maybe pick the 8
-            // most relevant lights? Or simply support more lights at once in the shader.
-            for (index, light) in self.lights.values().take(8).enumerate() {
-                // Setup common light data.
-                light_color[index] = light.color;
-                light_strength[index] = light.strength;
-
-                // Setup data specific to the current type of light.
-                match light.data {
-                    LightData::Point { radius } => {
-                        // Get the light's anchor.
-                        let light_anchor = match light.anchor() {
-                            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
-                            None => panic!("Cannot render point light if it's not attached to an anchor"),
-                        };
-
-                        light_type[index] = 1;
-                        light_position[index] = light_anchor.position();
-                        light_position_view[index] = light_anchor.position() * view_transform;
-                        light_radius[index] = radius;
-                    },
+            // Unset texture properties are bound to an empty texture and flagged as missing, so
+            // that nothing is left over from a previous draw with the same program.
+            let texture_properties = self.texture_properties.get(material.shader()).map(|names| &**names).unwrap_or(&[]);
+            for name in texture_properties {
+                let gl_texture = match material.get_property(name) {
+                    Some(&MaterialProperty::Texture(ref texture)) => self.textures.get(texture),
+                    _ => None,
+                };
 
-                    LightData::Directional { direction } => {
-                        light_type[index] = 2;
-                        light_direction[index] = direction;
-                        light_direction_view[index] = direction * view_transform;
-                    },
-                }
+                draw_builder
+                .uniform(&**name, gl_texture.unwrap_or(&default_texture))
+                .uniform(&*format!("has_{}", name), gl_texture.is_some() as i32);
             }
+        }
 
-            draw_builder.uniform("light_type", &light_type[..]);
-            draw_builder.uniform("light_strength", &light_strength[..]);
-            draw_builder.uniform("light_color", Color::as_slice_of_arrays(&light_color));
-            draw_builder.uniform("light_position", Point::as_slice_of_arrays(&light_position));
-            draw_builder.uniform("light_position_view", Point::as_slice_of_arrays(&light_position_view));
-            draw_builder.uniform("light_radius", &light_radius[..]);
-            draw_builder.uniform("light_direction", Vector3::as_slice_of_arrays(&light_direction));
-            draw_builder.uniform("light_direction_view", Vector3::as_slice_of_arrays(&light_direction_view));
+        if consumes_lights {
+            self.apply_shadow_maps(&mut draw_builder);
+            self.apply_environment_uniforms(&mut draw_builder, additive);
         }
 
+        self.apply_normal_map_uniforms(&mut draw_builder, material, &default_texture);
+        apply_skinning_uniforms(&mut draw_builder, joint_palette);
+        apply_instancing(&mut draw_builder, instances);
+        draw_builder.uniform("lod_fade", lod_fade);
+
         {
             let _s = Stopwatch::new("Draw mesh");
 
             draw_builder.draw();
         }
     }
+
+    /// Binds the shadow maps sampled by the built-in `shadow_factor()` function.
+    ///
+    /// The rest of the shadow uniforms are part of the `FrameUniforms` block.
+    fn apply_shadow_maps<'a>(&'a self, draw_builder: &mut DrawBuilder<'a>) {
+        let _stopwatch = Stopwatch::new("Shadow maps");
+
+        for index in 0..MAX_CASCADES {
+            let shadow_map = match self.shadow_cascades.get(index) {
+                Some(cascade) => &cascade.depth_texture,
+                None => &self.default_shadow_map,
+            };
+            draw_builder.uniform(SHADOW_MAP_NAMES[index], shadow_map);
+        }
+
+        for index in 0..MAX_POINT_SHADOWS {
+            let point_shadow_map = match self.point_shadows.get(index) {
+                Some(point_shadow) => &point_shadow.depth_texture,
+                None => &self.default_point_shadow_map,
+            };
+            draw_builder.uniform(POINT_SHADOW_MAP_NAMES[index], point_shadow_map);
+        }
+    }
+
+    /// Draws the scene as seen by the camera `camera_id` into the camera's viewport.
+    ///
+    /// `window_viewport` is the viewport covering the whole window. Stereo cameras draw the view
+    /// of each eye, see `render_stereo()`.
+    fn render_camera(&mut self, camera_id: CameraId, window_viewport: Rect) {
+        let _stopwatch = Stopwatch::new("Rendering camera");
+
+        // The camera and its anchor are copied so that the passes can mutate the renderer.
+        let camera = self.cameras.get(&camera_id).expect("No such camera exists").clone();
+        let camera_anchor = camera.anchor()
+            .map(|anchor_id| self.anchors.get(&anchor_id).expect("No such anchor exists").clone());
+
+        match camera_anchor {
+            Some(ref camera_anchor) if camera.stereo().is_some() => {
+                self.render_stereo(&camera, camera_anchor, window_viewport);
+            },
+            _ => self.render_view(&camera, camera_anchor.as_ref(), window_viewport, true),
+        }
+    }
+
+    /// Draws the scene as seen by `camera` from `camera_anchor` into the camera's viewport.
+    ///
+    /// The camera's normalized viewport is relative to the window, or to its render target if it
+    /// has one. The intermediate render targets are shared by all cameras, so each camera only
+    /// touches the region of them covered by its viewport.
+    ///
+    /// `prepare` is `false` for the second eye of a stereo camera, which reuses the occluders and
+    /// shadow maps prepared for the first eye.
+    fn render_view(&mut self, camera: &Camera, camera_anchor: Option<&Anchor>, window_viewport: Rect, prepare: bool) {
+        let viewport = camera.viewport();
+        let clear = camera.clear();
+        let target = camera.target();
+        let far_depth = clear_depth(camera);
+        let clip_depth = self.clip_depth(camera);
+
+        let target_rect = match target {
+            Some(texture) => {
+                let render_target = self.render_targets.get(&texture).expect("Camera's render target does not exist");
+                Rect::new(0, 0, render_target.width as i32, render_target.height as i32)
+            },
+            None => window_viewport,
+        };
+        self.camera_target = target;
+
+        let rect = viewport_rect(viewport, target_rect);
+        self.context.set_viewport(rect);
+        self.context.set_clip_depth(clip_depth);
+
+        match clear {
+            CameraClear::Color(color) => self.context.clear_rect(self.scene_framebuffer(), rect, Some(color.into()), Some(far_depth)),
+            CameraClear::Depth => self.context.clear_rect(self.scene_framebuffer(), rect, None, Some(far_depth)),
+            CameraClear::Skybox => {
+                self.context.clear_rect(self.scene_framebuffer(), rect, Some([0.0, 0.0, 0.0, 1.0]), Some(far_depth));
+
+                if let Some(camera_anchor) = camera_anchor {
+                    self.render_skybox(camera, camera_anchor);
+                }
+            },
+            CameraClear::Nothing => {},
+        }
+
+        if prepare {
+            self.prepare_occlusion(camera, camera_anchor);
+        }
+
+        // Take the pass list while executing it so that the passes can mutate the renderer.
+        let mut passes = mem::replace(&mut self.passes, PassList::new());
+        let mut after_post = false;
+        for pass in passes.iter_mut() {
+            if let Pass::BuiltIn(BuiltInPass::Shadow) = *pass {
+                if !prepare {
+                    continue;
+                }
+            }
+
+            let name = pass.name().to_string();
+            self.context.push_debug_group(&name);
+
+            let mut query = self.gpu_timers.query(&self.context);
+            {
+                let _active_query = query.begin();
+                self.execute_pass(pass, camera, camera_anchor, after_post);
+            }
+            self.gpu_timers.record(name, query);
+
+            self.context.pop_debug_group();
+
+            if let Pass::BuiltIn(BuiltInPass::Post) = *pass {
+                after_post = true;
+            }
+        }
+        self.passes = passes;
+
+        if let Some(camera_anchor) = camera_anchor {
+            let view_projection = camera.projection_matrix() * camera_anchor.view_matrix();
+            self.render_primitives(view_projection, depth_less_equal(camera));
+        }
+
+        self.context.set_clip_depth(ClipDepth::NegativeOneToOne);
+        self.camera_target = None;
+    }
+
+    /// Executes a single pass from the pass list for `camera`.
+    ///
+    /// `after_post` indicates that the post pass has already run, so custom passes draw to the
+    /// default framebuffer even when HDR is enabled.
+    fn execute_pass(&mut self, pass: &mut Pass, camera: &Camera, camera_anchor: Option<&Anchor>, after_post: bool) {
+        let _stopwatch = Stopwatch::new("Render pass");
+
+        match *pass {
+            // Shadows are fit to the camera, so they need the camera's position before rendering.
+            // Both eyes of a stereo camera share shadows fit to their culling view.
+            Pass::BuiltIn(BuiltInPass::Shadow) => {
+                let camera_transform = camera_anchor.map(|camera_anchor| {
+                    let (_, shadow_anchor) = self.culling_view(camera, camera_anchor);
+                    (shadow_anchor.world_position(), shadow_anchor.world_orientation())
+                });
+                if let Some((camera_position, camera_orientation)) = camera_transform {
+                    // Shadow maps use the standard depth range regardless of the camera's.
+                    let clip_depth = self.clip_depth(camera);
+                    self.context.set_clip_depth(ClipDepth::NegativeOneToOne);
+                    self.update_shadows(camera_position, camera_orientation);
+                    self.context.set_clip_depth(clip_depth);
+                }
+
+                return;
+            },
+
+            Pass::BuiltIn(BuiltInPass::Post) => {
+                self.resolve_msaa();
+                self.resolve_hdr();
+                return;
+            },
+
+            _ => {},
+        }
+
+        let camera_anchor = match camera_anchor {
+            Some(camera_anchor) => camera_anchor,
+            None => unimplemented!(),
+        };
+
+        match *pass {
+            Pass::BuiltIn(BuiltInPass::Shadow) | Pass::BuiltIn(BuiltInPass::Post) => unreachable!(),
+
+            // Debug modes draw both opaque and transparent geometry in the opaque pass.
+            Pass::BuiltIn(BuiltInPass::Opaque) if self.debug_mode != DebugMode::Off => {
+                self.render_debug(camera, camera_anchor)
+            },
+            Pass::BuiltIn(BuiltInPass::Transparent) if self.debug_mode != DebugMode::Off => {},
+
+            Pass::BuiltIn(BuiltInPass::Opaque) => match self.render_path {
+                RenderPath::Forward => self.render_forward(camera, camera_anchor),
+                RenderPath::Deferred => self.render_deferred(camera, camera_anchor),
+            },
+
+            // Transparent geometry is always rendered with the forward pipeline, since the
+            // G-buffer can only hold a single surface per pixel.
+            Pass::BuiltIn(BuiltInPass::Transparent) => self.render_transparent(camera, camera_anchor),
+
+            Pass::Custom(ref mut pass) => {
+                let context = PassContext {
+                    context: &self.context,
+                    framebuffer: if after_post { self.output_framebuffer() } else { self.scene_framebuffer() },
+                    camera: camera,
+                    camera_anchor: camera_anchor,
+                };
+                pass.execute(&context);
+            },
+        }
+    }
+}
+
+/// Converts a camera's normalized viewport, which has its origin in the top-left corner, into a
+/// rectangle in pixels with its origin in the bottom-left corner of `window_viewport`.
+///
+/// The edges are rounded independently so that adjacent viewports don't overlap or leave gaps.
+fn viewport_rect(viewport: Viewport, window_viewport: Rect) -> Rect {
+    let width = window_viewport.width as f32;
+    let height = window_viewport.height as f32;
+
+    let left = (viewport.x * width).round() as i32;
+    let right = ((viewport.x + viewport.width) * width).round() as i32;
+    let bottom = ((1.0 - viewport.y - viewport.height) * height).round() as i32;
+    let top = ((1.0 - viewport.y) * height).round() as i32;
+
+    Rect::new(
+        window_viewport.x + left,
+        window_viewport.y + bottom,
+        right - left,
+        top - bottom,
+    )
 }
 
 impl Drop for GlRender {
@@ -337,12 +960,22 @@ impl Drop for GlRender {
         // dropped if the context has already been dropped, then we'd get faster shutdown.
         self.shared_materials.clear();
         self.meshes.clear();
+        self.render_targets.clear();
         self.textures.clear();
         self.mesh_instances.clear();
         self.anchors.clear();
         self.cameras.clear();
         self.lights.clear();
+        self.skeletons.clear();
         self.programs.clear();
+        self.shadow_cascades.clear();
+        self.point_shadows.clear();
+        self.environment = None;
+        self.gbuffer = None;
+        self.hdr_target = None;
+        self.msaa_target = None;
+        self.instance_batches.clear();
+        self.gpu_timers.clear();
     }
 }
 
@@ -350,56 +983,73 @@ impl Renderer for GlRender {
     fn draw(&mut self) {
         let _stopwatch = Stopwatch::new("GLRender::draw()");
 
+        let elapsed = self.start_time.elapsed();
+        self.time = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1_000_000_000.0;
+
         {
-            let _stopwatch = Stopwatch::new("Clearing buffer");
-            self.context.clear();
+            let _stopwatch = Stopwatch::new("Updating anchor transforms");
+            anchor::update_world_transforms(&mut self.anchors);
         }
 
-        // TODO: Support rendering multiple cameras.
-        // TODO: Should we warn if there are no cameras?
-        if let Some(camera) = self.cameras.values().next() {
-            let _stopwatch = Stopwatch::new("Rendering camera");
+        self.update_streaming();
 
-            let camera_anchor = match camera.anchor() {
-                Some(ref anchor_id) => self.anchors.get(anchor_id).expect("No such anchor exists"),
-                None => unimplemented!(),
-            };
+        if self.render_path == RenderPath::Deferred {
+            self.prepare_gbuffer();
+        }
 
-            let mut has_setup_lights = false;
-
-            // Render shared materials first.
-            for (material_id, mesh_instances) in &self.mesh_instances_with_shared_materials {
-                let _s = Stopwatch::new("Rendering shared material");
-
-                let material = self.shared_materials.get(material_id).expect("No such material exists");
-                let mut has_setup_material = false;
-
-                for mesh_instance_id in mesh_instances {
-                    let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
-                    self.render_mesh_instance(
-                        mesh_instance,
-                        material,
-                        camera,
-                        camera_anchor,
-                        &mut has_setup_lights,
-                        &mut has_setup_material,
-                    );
-                }
-            }
+        // Instancing is only supported by the forward pipeline, and debug modes draw every mesh
+        // instance individually.
+        if self.render_path == RenderPath::Forward && self.debug_mode == DebugMode::Off {
+            self.prepare_instancing();
+        } else {
+            self.instance_batches.clear();
+            self.instanced_mesh_instances.clear();
+        }
 
-            // Render meshes with unique materials.
-            for mesh_instance_id in &self.mesh_instances_with_owned_material {
-                let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
-                let material = mesh_instance.material().expect("Mesh instance was in wrong bucket (was in the owned material bucket, had shared material)");
-                self.render_mesh_instance(
-                    mesh_instance,
-                    material,
-                    camera,
-                    camera_anchor,
-                    &mut false,
-                    &mut false,
-                );
-            }
+        if self.hdr_settings.enabled {
+            self.prepare_hdr_target();
+        }
+
+        self.prepare_msaa_target();
+
+        // Cameras are drawn in priority order, each into its own viewport. Cameras drawing into
+        // render targets go first, so that their textures are up to date for the other cameras.
+        // TODO: Should we warn if there are no cameras?
+        let window_viewport = self.context.viewport();
+        let mut cameras: Vec<_> = self.cameras
+            .iter()
+            .map(|(&camera_id, camera)| {
+                let to_window = match camera.stereo() {
+                    Some(Stereo { layout: StereoLayout::Targets { .. }, .. }) => false,
+                    _ => camera.target().is_none(),
+                };
+                (to_window, camera.priority(), camera_id)
+            })
+            .collect();
+        cameras.sort_by_key(|&(to_window, priority, camera_id)| (to_window, priority, camera_id.index()));
+        for (_, _, camera_id) in cameras {
+            self.render_camera(camera_id, window_viewport);
+        }
+        self.context.set_viewport(window_viewport);
+
+        // Primitives are only kept for a single frame, even if there was no camera to draw them.
+        self.line_renderer.clear();
+
+        // The backbuffer's contents are undefined once it's presented, so the frame has to be
+        // read back before swapping.
+        if self.frame_capture {
+            let _stopwatch = Stopwatch::new("Capture frame");
+
+            let pixels = framebuffer::read_pixels(&self.context, None, window_viewport);
+            self.captured_frame = Some(Image::from_bottom_up(
+                window_viewport.width as usize,
+                window_viewport.height as usize,
+                pixels,
+            ));
+        }
+
+        if let Some(frame_stats) = self.gpu_timers.end_frame() {
+            self.frame_stats = frame_stats;
         }
 
         {
@@ -412,9 +1062,18 @@ impl Renderer for GlRender {
         self.default_material.clone()
     }
 
-    fn build_material(&mut self, source: MaterialSource) -> Result<Material, BuildMaterialError> {
-        use polygon_material::material_source::PropertyType;
+    fn shading_model_material(&self, shading_model: ShadingModel) -> Material {
+        match shading_model {
+            ShadingModel::Simple => self.default_material.clone(),
+            ShadingModel::MetallicRoughness => self.pbr_material.clone(),
+        }
+    }
+
+    fn terrain_material(&self) -> Material {
+        self.terrain_material.clone()
+    }
 
+    fn build_material(&mut self, source: MaterialSource) -> Result<Material, BuildMaterialError> {
         // COMPILE SHADER SOURCE
         // =====================
 
@@ -429,6 +1088,8 @@ impl Renderer for GlRender {
                     PropertyType::Color => "vec4",
                     PropertyType::Texture2d => "sampler2D",
                     PropertyType::f32 => "float",
+                    PropertyType::i32 => "int",
+                    PropertyType::Vector2 => "vec2",
                     PropertyType::Vector3 => "vec3",
                 };
 
@@ -436,33 +1097,17 @@ impl Renderer for GlRender {
                 uniform_declarations.push(' ');
                 uniform_declarations.push_str(&*property.name);
                 uniform_declarations.push_str(";\n");
+
+                if property.property_type == PropertyType::Texture2d {
+                    uniform_declarations.push_str("uniform int has_");
+                    uniform_declarations.push_str(&*property.name);
+                    uniform_declarations.push_str(";\n");
+                }
             }
 
             uniform_declarations
         };
 
-        static BUILT_IN_UNIFORMS: &'static str = r#"
-            uniform mat4 model_transform;
-            uniform mat3 normal_transform;
-            uniform mat4 view_transform;
-            uniform mat3 view_normal_transform;
-            uniform mat4 model_view_transform;
-            uniform mat4 projection_transform;
-            uniform mat4 model_view_projection;
-
-            uniform vec4 global_ambient;
-            uniform vec4 camera_position;
-
-            uniform int light_type[8];
-            uniform vec4 light_position[8];
-            uniform vec4 light_position_view[8];
-            uniform float light_strength[8];
-            uniform vec4 light_color[8];
-            uniform float light_radius[8];
-            uniform vec3 light_direction[8];
-            uniform vec3 light_direction_view[8];
-        "#;
-
         // Generate the GLSL source for the vertex shader.
         let vert_shader = {
             static DEFAULT_VERT_MAIN: &'static str = r#"
@@ -470,13 +1115,17 @@ impl Renderer for GlRender {
 
                 @vertex.position = vertex_position;
                 @vertex.normal = vertex_normal;
+                @vertex.tangent = vertex_tangent;
                 @vertex.uv0 = vertex_uv0;
+                @vertex.color = vertex_color;
 
                 @vertex.world_position = model_transform * vertex_position;
                 @vertex.world_normal = normalize(normal_transform * vertex_normal);
+                @vertex.world_tangent = vec4(mat3(model_transform) * vertex_tangent.xyz, vertex_tangent.w);
 
                 @vertex.view_position = model_view_transform * vertex_position;
                 @vertex.view_normal = normalize(view_normal_transform * vertex_normal);
+                @vertex.view_tangent = vec4(mat3(model_view_transform) * vertex_tangent.xyz, vertex_tangent.w);
             "#;
 
             // Retrieve source string for the vertex shader.
@@ -493,11 +1142,15 @@ impl Renderer for GlRender {
                 .replace("@position", "gl_Position")
                 .replace("@vertex.position", "_vertex_position_")
                 .replace("@vertex.normal", "_vertex_normal_")
+                .replace("@vertex.tangent", "_vertex_tangent_")
                 .replace("@vertex.uv0", "_vertex_uv0_")
+                .replace("@vertex.color", "_vertex_color_")
                 .replace("@vertex.world_position", "_vertex_world_position_")
                 .replace("@vertex.world_normal", "_vertex_world_normal_")
+                .replace("@vertex.world_tangent", "_vertex_world_tangent_")
                 .replace("@vertex.view_position", "_vertex_view_position_")
-                .replace("@vertex.view_normal", "_vertex_view_normal_");
+                .replace("@vertex.view_normal", "_vertex_view_normal_")
+                .replace("@vertex.view_tangent", "_vertex_view_tangent_");
             let replaced_source = format!(r#"
                     #version 330 core
 
@@ -505,23 +1158,53 @@ impl Renderer for GlRender {
 
                     {}
 
-                    layout(location = 0) in vec4 vertex_position;
-                    layout(location = 1) in vec3 vertex_normal;
+                    {}
+
+                    {}
+
+                    layout(location = 0) in vec4 _vertex_position_attribute_;
+                    layout(location = 1) in vec3 _vertex_normal_attribute_;
                     layout(location = 2) in vec2 vertex_uv0;
+                    layout(location = 3) in vec4 _vertex_color_attribute_;
+                    layout(location = 13) in vec4 _vertex_tangent_attribute_;
+
+                    // The position, normal, and tangent after skinning, in model space.
+                    vec4 vertex_position;
+                    vec3 vertex_normal;
+                    vec4 vertex_tangent;
+
+                    // The vertex color, or white if the mesh doesn't have vertex colors.
+                    vec4 vertex_color;
 
                     out vec4 _vertex_position_;
                     out vec3 _vertex_normal_;
+                    out vec4 _vertex_tangent_;
                     out vec2 _vertex_uv0_;
+                    out vec4 _vertex_color_;
                     out vec4 _vertex_world_position_;
                     out vec3 _vertex_world_normal_;
+                    out vec4 _vertex_world_tangent_;
                     out vec4 _vertex_view_position_;
                     out vec3 _vertex_view_normal_;
+                    out vec4 _vertex_view_tangent_;
 
                     void main(void) {{
+                        apply_instancing();
+
+                        mat4 _skin_transform_ = skin_transform();
+                        vertex_position = _skin_transform_ * _vertex_position_attribute_;
+                        vertex_normal = mat3(_skin_transform_) * _vertex_normal_attribute_;
+                        vertex_tangent = vec4(
+                            mat3(_skin_transform_) * _vertex_tangent_attribute_.xyz,
+                            _vertex_tangent_attribute_.w);
+                        vertex_color = has_vertex_color != 0 ? _vertex_color_attribute_ : vec4(1.0);
+
                         {}
                     }}
                 "#,
                 BUILT_IN_UNIFORMS,
+                SKINNING_SOURCE,
+                INSTANCING_SOURCE,
                 uniform_declarations,
                 replaced_source);
 
@@ -544,11 +1227,15 @@ impl Renderer for GlRender {
                 .replace("@color", "_fragment_color_")
                 .replace("@vertex.position", "_vertex_position_")
                 .replace("@vertex.normal", "_vertex_normal_")
+                .replace("@vertex.tangent", "_vertex_tangent_")
                 .replace("@vertex.uv0", "_vertex_uv0_")
+                .replace("@vertex.color", "_vertex_color_")
                 .replace("@vertex.world_position", "_vertex_world_position_")
                 .replace("@vertex.world_normal", "_vertex_world_normal_")
+                .replace("@vertex.world_tangent", "_vertex_world_tangent_")
                 .replace("@vertex.view_position", "_vertex_view_position_")
-                .replace("@vertex.view_normal", "_vertex_view_normal_");
+                .replace("@vertex.view_normal", "_vertex_view_normal_")
+                .replace("@vertex.view_tangent", "_vertex_view_tangent_");
             let replaced_source = format!(r#"
                     #version 330 core
 
@@ -558,30 +1245,56 @@ impl Renderer for GlRender {
 
                     in vec4 _vertex_position_;
                     in vec3 _vertex_normal_;
+                    in vec4 _vertex_tangent_;
                     in vec2 _vertex_uv0_;
+                    in vec4 _vertex_color_;
                     in vec4 _vertex_world_position_;
                     in vec3 _vertex_world_normal_;
+                    in vec4 _vertex_world_tangent_;
                     in vec4 _vertex_view_position_;
                     in vec3 _vertex_view_normal_;
+                    in vec4 _vertex_view_tangent_;
 
                     out vec4 _fragment_color_;
 
+                    {}
+
+                    {}
+
+                    {}
+
+                    {}
+
                     void main(void) {{
+                        apply_lod_fade();
+
                         {}
                     }}
                 "#,
                 BUILT_IN_UNIFORMS,
                 uniform_declarations,
+                SHADOW_FUNCTIONS,
+                NORMAL_MAP_SOURCE,
+                ENVIRONMENT_SOURCE,
+                LOD_FADE_SOURCE,
                 replaced_source);
 
             GlShader::new(&self.context, replaced_source, ShaderType::Fragment).map_err(|err| BuildMaterialError)?
         };
 
         let program = Program::new(&self.context, &[vert_shader, frag_shader]).map_err(|err| BuildMaterialError)?;
+        bind_uniform_blocks(&program);
 
         let program_id = self.shader_counter.next();
         self.programs.insert(program_id, program);
 
+        let texture_properties = source.properties
+            .iter()
+            .filter(|property| property.property_type == PropertyType::Texture2d)
+            .map(|property| property.name.clone())
+            .collect();
+        self.texture_properties.insert(program_id, texture_properties);
+
         // BUILD MATERIAL OBJECT
         // =====================
 
@@ -589,12 +1302,33 @@ impl Renderer for GlRender {
 
         // Add the properties from the material declaration.
         for property in source.properties {
-            match property.property_type {
-                PropertyType::Color => material.set_color(property.name, Color::default()),
-                PropertyType::Texture2d => material.set_texture(property.name, GpuTexture::default()),
-                PropertyType::f32 => material.set_f32(property.name, f32::default()),
-                PropertyType::Vector3 => material.set_vector3(property.name, Vector3::default()),
-            };
+            set_default_property(&mut material, property.name, property.property_type);
+        }
+
+        Ok(material)
+    }
+
+    fn build_custom_material(&mut self, program: CustomProgram) -> Result<Material, BuildMaterialError> {
+        let vert_shader = GlShader::new(&self.context, program.vertex_source(), ShaderType::Vertex)
+            .map_err(|_| BuildMaterialError)?;
+        let frag_shader = GlShader::new(&self.context, program.fragment_source(), ShaderType::Fragment)
+            .map_err(|_| BuildMaterialError)?;
+        let gl_program = Program::new(&self.context, &[vert_shader, frag_shader]).map_err(|_| BuildMaterialError)?;
+
+        let program_id = self.shader_counter.next();
+        self.programs.insert(program_id, gl_program);
+        self.custom_programs.insert(program_id, program.engine_uniforms());
+
+        let texture_properties = program.properties()
+            .iter()
+            .filter(|&&(_, property_type)| property_type == PropertyType::Texture2d)
+            .map(|&(ref name, _)| name.clone())
+            .collect();
+        self.texture_properties.insert(program_id, texture_properties);
+
+        let mut material = Material::new(program_id);
+        for &(ref name, property_type) in program.properties() {
+            set_default_property(&mut material, name.clone(), property_type);
         }
 
         Ok(material)
@@ -617,9 +1351,6 @@ impl Renderer for GlRender {
     }
 
     fn register_mesh(&mut self, mesh: &Mesh) -> GpuMesh {
-        // Configure vertex attributes.
-        let position = mesh.position();
-
         let mesh_id = self.mesh_counter.next();
 
         let mut vertex_array = VertexArray::with_index_buffer(
@@ -627,30 +1358,49 @@ impl Renderer for GlRender {
             mesh.vertex_data(),
             mesh.indices(),
         );
-        vertex_array.set_attrib(AttributeLocation::from_index(0), position.into());
+        set_mesh_attribs(&mut vertex_array, mesh);
 
-        if let Some(normal) = mesh.normal() {
-            vertex_array.set_attrib(AttributeLocation::from_index(1), normal.into());
-        }
+        self.meshes.insert(mesh_id, MeshData::new(vertex_array, mesh, false));
 
-        // TODO: Support multiple texcoords.
-        if let Some(texcoord) = mesh.texcoord().first().cloned() {
-            vertex_array.set_attrib(AttributeLocation::from_index(2), texcoord.into());
-        }
+        mesh_id
+    }
 
-        self.meshes.insert(
-            mesh_id,
-            MeshData {
-                vertex_array: vertex_array,
-                position_attribute: mesh.position(),
-                normal_attribute: mesh.normal(),
-                uv_attribute: None,
-                element_count: mesh.indices().len(),
-            });
+    fn register_dynamic_mesh(&mut self, mesh: &Mesh) -> GpuMesh {
+        let mesh_id = self.mesh_counter.next();
+
+        let mut vertex_array = VertexArray::with_index_buffer(&self.context, &[], &[]);
+        vertex_array.stream_raw_data(mesh.vertex_data());
+        vertex_array.stream_indices(mesh.indices());
+        set_mesh_attribs(&mut vertex_array, mesh);
+
+        self.meshes.insert(mesh_id, MeshData::new(vertex_array, mesh, true));
 
         mesh_id
     }
 
+    fn update_dynamic_mesh(&mut self, mesh_id: GpuMesh, mesh: &Mesh) {
+        let mesh_data = self.meshes.get_mut(&mesh_id).expect("No such mesh exists");
+        assert!(mesh_data.dynamic, "Cannot update mesh {:?}, it wasn't registered as a dynamic mesh", mesh_id);
+        assert!(
+            mesh_data.attributes == MeshAttributes::of(mesh),
+            "Cannot update dynamic mesh {:?} with different vertex attributes, expected {:?} but got {:?}",
+            mesh_id,
+            mesh_data.attributes,
+            MeshAttributes::of(mesh));
+
+        // The offsets of the attributes depend on the vertex count, so they're set up again
+        // after replacing the data.
+        mesh_data.vertex_array.stream_raw_data(mesh.vertex_data());
+        mesh_data.vertex_array.stream_indices(mesh.indices());
+        set_mesh_attribs(&mut mesh_data.vertex_array, mesh);
+
+        mesh_data.position_attribute = mesh.position();
+        mesh_data.normal_attribute = mesh.normal();
+        mesh_data.element_count = mesh.indices().len();
+        mesh_data.submeshes = mesh.submeshes().to_vec();
+        mesh_data.bounds = mesh.bounds();
+    }
+
     fn register_texture(&mut self, texture: &Texture2d) -> GpuTexture {
         let (format, internal_format) = match texture.format() {
             DataFormat::Rgb => (TextureFormat::Rgb, TextureInternalFormat::Rgb),
@@ -659,6 +1409,13 @@ impl Renderer for GlRender {
             DataFormat::Bgra => (TextureFormat::Bgra, TextureInternalFormat::Rgba),
         };
 
+        // sRGB textures are converted to linear by the GL when they're sampled.
+        let internal_format = match (self.gamma_correct && texture.color_space() == ColorSpace::Srgb, internal_format) {
+            (true, TextureInternalFormat::Rgb) => TextureInternalFormat::Srgb8,
+            (true, TextureInternalFormat::Rgba) => TextureInternalFormat::Srgb8Alpha8,
+            (_, internal_format) => internal_format,
+        };
+
         // Create the Texture2d from the texture data.
         let texture_result = match texture.data() {
             &TextureData::f32(ref data) => {
@@ -709,6 +1466,29 @@ impl Renderer for GlRender {
         texture_id
     }
 
+    fn register_render_target(&mut self, width: usize, height: usize) -> GpuTexture {
+        assert!(width > 0 && height > 0, "Render target must have a positive size, width: {}, height: {}", width, height);
+
+        let color = GlTexture2d::allocate(&self.context, self.color_target_format(), width, height)
+            .expect("Failed to allocate render target color texture");
+        let render_target = RenderTarget::new(&self.context, &color, width, height);
+
+        let texture_id = self.texture_counter.next();
+        self.textures.insert(texture_id, color);
+        self.render_targets.insert(texture_id, render_target);
+
+        texture_id
+    }
+
+    fn register_streaming_texture(&mut self, path: &Path) -> GpuTexture {
+        // Materials can use the texture right away, it's replaced once the file has been loaded.
+        let texture_id = self.texture_counter.next();
+        self.textures.insert(texture_id, GlTexture2d::empty(&self.context));
+        self.texture_streamer.request(texture_id, path.to_path_buf());
+
+        texture_id
+    }
+
     fn register_mesh_instance(&mut self, mesh_instance: MeshInstance) -> MeshInstanceId {
         let mesh_instance_id = self.mesh_instance_counter.next();
 
@@ -749,6 +1529,10 @@ impl Renderer for GlRender {
         self.anchors.get_mut(&anchor_id)
     }
 
+    fn set_anchor_parent(&mut self, anchor_id: AnchorId, parent: Option<AnchorId>) {
+        anchor::set_parent(&mut self.anchors, anchor_id, parent);
+    }
+
     fn register_camera(&mut self, camera: Camera) -> CameraId {
         let camera_id = self.camera_counter.next();
 
@@ -783,9 +1567,284 @@ impl Renderer for GlRender {
         self.lights.get_mut(&light_id)
     }
 
+    fn register_skeleton(&mut self, skeleton: Skeleton) -> SkeletonId {
+        let skeleton_id = self.skeleton_counter.next();
+
+        let old = self.skeletons.insert(skeleton_id, skeleton);
+        assert!(old.is_none());
+
+        skeleton_id
+    }
+
+    fn get_skeleton(&self, skeleton_id: SkeletonId) -> Option<&Skeleton> {
+        self.skeletons.get(&skeleton_id)
+    }
+
+    fn get_skeleton_mut(&mut self, skeleton_id: SkeletonId) -> Option<&mut Skeleton> {
+        self.skeletons.get_mut(&skeleton_id)
+    }
+
+    fn register_lod_group(&mut self, lod_group: LodGroup) -> LodGroupId {
+        let lod_group_id = self.lod_group_counter.next();
+
+        let old = self.lod_groups.insert(lod_group_id, lod_group);
+        assert!(old.is_none());
+
+        lod_group_id
+    }
+
+    fn get_lod_group(&self, lod_group_id: LodGroupId) -> Option<&LodGroup> {
+        self.lod_groups.get(&lod_group_id)
+    }
+
+    fn get_lod_group_mut(&mut self, lod_group_id: LodGroupId) -> Option<&mut LodGroup> {
+        self.lod_groups.get_mut(&lod_group_id)
+    }
+
+    fn register_occluder(&mut self, occluder: Occluder) -> OccluderId {
+        let occluder_id = self.occluder_counter.next();
+
+        let old = self.occluders.insert(occluder_id, occluder);
+        assert!(old.is_none());
+
+        occluder_id
+    }
+
+    fn get_occluder(&self, occluder_id: OccluderId) -> Option<&Occluder> {
+        self.occluders.get(&occluder_id)
+    }
+
+    fn get_occluder_mut(&mut self, occluder_id: OccluderId) -> Option<&mut Occluder> {
+        self.occluders.get_mut(&occluder_id)
+    }
+
+    fn register_terrain(&mut self, mut terrain: Terrain) -> TerrainId {
+        let terrain_anchor = terrain.anchor().expect("Terrain must have an anchor to be registered");
+        let lod_levels = terrain.lod_levels();
+
+        let mut chunks = Vec::new();
+        for chunk in terrain.chunk_layout() {
+            let mut anchor = Anchor::new();
+            anchor.set_position(chunk.center(&terrain));
+            let anchor_id = self.register_anchor(anchor);
+            anchor::set_parent(&mut self.anchors, anchor_id, Some(terrain_anchor));
+
+            // Each level halves the screen size at which the next level takes over, and the
+            // least detailed level is always drawn.
+            let mut lod_group = LodGroup::new(chunk.radius(&terrain));
+            for lod in 0..lod_levels {
+                let mesh = self.register_mesh(&chunk.build_mesh(&terrain, lod));
+                let screen_size = if lod + 1 == lod_levels { 0.0 } else { 0.5 / (1 << lod) as f32 };
+                lod_group.add_level(mesh, screen_size);
+            }
+
+            let mesh = lod_group.levels()[0].mesh;
+            let lod_group_id = self.register_lod_group(lod_group);
+
+            let mut mesh_instance = MeshInstance::with_owned_material(mesh, terrain.material().clone());
+            mesh_instance.set_anchor(anchor_id);
+            mesh_instance.set_lod_group(lod_group_id);
+            chunks.push(self.register_mesh_instance(mesh_instance));
+        }
+        terrain.set_chunks(chunks);
+
+        let terrain_id = self.terrain_counter.next();
+
+        let old = self.terrains.insert(terrain_id, terrain);
+        assert!(old.is_none());
+
+        terrain_id
+    }
+
+    fn get_terrain(&self, terrain_id: TerrainId) -> Option<&Terrain> {
+        self.terrains.get(&terrain_id)
+    }
+
     fn set_ambient_light(&mut self, color: Color) {
         self.ambient_color = color;
     }
+
+    fn set_environment_map(&mut self, faces: Option<[&Texture2d; 6]>) {
+        let environment = faces.map(|faces| self.build_environment(faces));
+        self.environment = environment;
+    }
+
+    fn render_path(&self) -> RenderPath {
+        self.render_path
+    }
+
+    fn set_render_path(&mut self, render_path: RenderPath) {
+        self.render_path = render_path;
+
+        // Free the G-buffer when it's no longer needed, it's recreated on demand.
+        if render_path != RenderPath::Deferred {
+            self.gbuffer = None;
+        }
+
+        // The deferred pipeline doesn't support MSAA.
+        if !self.msaa_enabled() {
+            self.msaa_target = None;
+        }
+    }
+
+    fn hdr_settings(&self) -> HdrSettings {
+        self.hdr_settings
+    }
+
+    fn set_hdr_settings(&mut self, settings: HdrSettings) {
+        assert!(settings.exposure > 0.0, "Exposure must be positive, got {}", settings.exposure);
+        assert!(
+            settings.min_exposure > 0.0 && settings.min_exposure <= settings.max_exposure,
+            "Auto-exposure range must be positive and non-empty, got {} to {}",
+            settings.min_exposure,
+            settings.max_exposure);
+
+        self.hdr_settings = settings;
+
+        // Free the HDR target when it's no longer needed, it's recreated on demand.
+        if !settings.enabled {
+            self.hdr_target = None;
+        }
+    }
+
+    fn anti_aliasing(&self) -> AntiAliasing {
+        self.anti_aliasing
+    }
+
+    fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        self.anti_aliasing = anti_aliasing;
+
+        // Free the MSAA target when it's no longer needed, it's recreated on demand.
+        if !self.msaa_enabled() {
+            self.msaa_target = None;
+        }
+    }
+
+    fn debug_mode(&self) -> DebugMode {
+        self.debug_mode
+    }
+
+    fn set_debug_mode(&mut self, debug_mode: DebugMode) {
+        self.debug_mode = debug_mode;
+    }
+
+    fn gamma_correct(&self) -> bool {
+        self.gamma_correct
+    }
+
+    fn set_gamma_correct(&mut self, gamma_correct: bool) {
+        self.gamma_correct = gamma_correct;
+
+        // Writes to sRGB render targets and the window are only converted while this is enabled.
+        self.context.set_srgb_enabled(gamma_correct);
+    }
+
+    fn occlusion_settings(&self) -> OcclusionSettings {
+        self.occlusion_settings
+    }
+
+    fn set_occlusion_settings(&mut self, settings: OcclusionSettings) {
+        assert!(
+            settings.width > 0 && settings.height > 0,
+            "Occlusion buffer must have a positive size, width: {}, height: {}",
+            settings.width,
+            settings.height);
+
+        self.occlusion_settings = settings;
+
+        // Free the occlusion buffer when it's no longer needed, it's recreated on demand.
+        if !settings.enabled {
+            self.occlusion_buffer = None;
+        }
+    }
+
+    fn streaming_settings(&self) -> StreamingSettings {
+        self.streaming_settings
+    }
+
+    fn set_streaming_settings(&mut self, settings: StreamingSettings) {
+        assert!(settings.upload_budget > 0, "Texture streaming upload budget must be positive");
+
+        self.streaming_settings = settings;
+
+        // The upload buffer's regions are as large as the budget, so it's recreated on demand.
+        self.texture_streamer.reset_upload_buffer();
+    }
+
+    fn take_streaming_errors(&mut self) -> Vec<(GpuTexture, StreamingError)> {
+        self.texture_streamer.take_errors()
+    }
+
+    fn shadow_settings(&self) -> ShadowSettings {
+        self.shadow_settings
+    }
+
+    fn set_shadow_settings(&mut self, settings: ShadowSettings) {
+        assert!(
+            settings.cascade_count > 0 && settings.cascade_count <= MAX_CASCADES,
+            "Cascade count must be between 1 and {}, got {}",
+            MAX_CASCADES,
+            settings.cascade_count);
+
+        let rebuild =
+            settings.resolution != self.shadow_settings.resolution
+            || settings.cascade_count != self.shadow_settings.cascade_count
+            || settings.point_resolution != self.shadow_settings.point_resolution;
+
+        self.shadow_settings = settings;
+
+        if rebuild {
+            self.rebuild_shadow_cascades();
+        }
+    }
+
+    fn frame_stats(&self) -> FrameStats {
+        self.frame_stats.clone()
+    }
+
+    fn pass_names(&self) -> Vec<String> {
+        self.passes.names()
+    }
+
+    fn insert_pass_before(&mut self, before: &str, pass: Box<RenderPass>) -> Result<(), PassError> {
+        self.passes.insert_before(before, pass)
+    }
+
+    fn insert_pass_after(&mut self, after: &str, pass: Box<RenderPass>) -> Result<(), PassError> {
+        self.passes.insert_after(after, pass)
+    }
+
+    fn remove_pass(&mut self, name: &str) -> Option<Box<RenderPass>> {
+        self.passes.remove(name)
+    }
+
+    fn line_renderer(&self) -> &LineRenderer {
+        &self.line_renderer
+    }
+
+    fn line_renderer_mut(&mut self) -> &mut LineRenderer {
+        &mut self.line_renderer
+    }
+
+    fn set_frame_capture(&mut self, enabled: bool) {
+        self.frame_capture = enabled;
+        if !enabled {
+            self.captured_frame = None;
+        }
+    }
+
+    fn capture_frame(&self) -> Image {
+        self.captured_frame
+            .clone()
+            .expect("No frame has been captured, frame capture must be enabled before drawing")
+    }
+
+    fn capture_target(&self, target: GpuTexture) -> Image {
+        let render_target = self.render_targets.get(&target).expect("No such render target exists");
+        let rect = Rect::new(0, 0, render_target.width as i32, render_target.height as i32);
+        let pixels = framebuffer::read_pixels(&self.context, Some(&render_target.framebuffer), rect);
+        Image::from_bottom_up(render_target.width, render_target.height, pixels)
+    }
 }
 
 unsafe impl Send for GlRender {}
@@ -808,6 +1867,99 @@ struct MeshData {
     normal_attribute: Option<VertexAttribute>,
     uv_attribute: Option<VertexAttribute>,
     element_count: usize,
+    submeshes: Vec<Submesh>,
+
+    /// The minimum and maximum corners of the mesh's bounding box, used for occlusion culling.
+    bounds: (Point, Point),
+
+    /// Whether the mesh was registered with `register_dynamic_mesh()`.
+    dynamic: bool,
+
+    /// The vertex attributes the mesh has, which a dynamic mesh's updates must match.
+    attributes: MeshAttributes,
+}
+
+impl MeshData {
+    fn new(vertex_array: VertexArray, mesh: &Mesh, dynamic: bool) -> MeshData {
+        MeshData {
+            vertex_array: vertex_array,
+            position_attribute: mesh.position(),
+            normal_attribute: mesh.normal(),
+            uv_attribute: None,
+            element_count: mesh.indices().len(),
+            submeshes: mesh.submeshes().to_vec(),
+            bounds: mesh.bounds(),
+            dynamic: dynamic,
+            attributes: MeshAttributes::of(mesh),
+        }
+    }
+}
+
+/// Sets a newly declared material property to its default value.
+///
+/// Texture properties are left unset, since there's no texture to default to.
+fn set_default_property(material: &mut Material, name: String, property_type: PropertyType) {
+    match property_type {
+        PropertyType::Color => material.set_color(name, Color::default()),
+        PropertyType::Texture2d => {},
+        PropertyType::f32 => material.set_f32(name, f32::default()),
+        PropertyType::i32 => material.set_i32(name, i32::default()),
+        PropertyType::Vector2 => material.set_vector2(name, Vector2::default()),
+        PropertyType::Vector3 => material.set_vector3(name, Vector3::default()),
+    };
+}
+
+/// Which of the optional vertex attributes a mesh has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MeshAttributes {
+    normal: bool,
+    tangent: bool,
+    texcoord: bool,
+    color: bool,
+    joints: bool,
+}
+
+impl MeshAttributes {
+    fn of(mesh: &Mesh) -> MeshAttributes {
+        MeshAttributes {
+            normal: mesh.normal().is_some(),
+            tangent: mesh.tangent().is_some(),
+            texcoord: !mesh.texcoord().is_empty(),
+            color: mesh.color().is_some(),
+            joints: mesh.joint_indices().is_some(),
+        }
+    }
+}
+
+/// Points the vertex attributes of `vertex_array` at the attribute data of `mesh`.
+fn set_mesh_attribs(vertex_array: &mut VertexArray, mesh: &Mesh) {
+    vertex_array.set_attrib(AttributeLocation::from_index(0), mesh.position().into());
+
+    if let Some(normal) = mesh.normal() {
+        vertex_array.set_attrib(AttributeLocation::from_index(1), normal.into());
+    }
+
+    // Locations 6 through 12 are used by the per-instance attributes, see `instancing`.
+    if let Some(tangent) = mesh.tangent() {
+        vertex_array.set_attrib(AttributeLocation::from_index(13), tangent.into());
+    }
+
+    // TODO: Support multiple texcoords.
+    if let Some(texcoord) = mesh.texcoord().first().cloned() {
+        vertex_array.set_attrib(AttributeLocation::from_index(2), texcoord.into());
+    }
+
+    if let Some(color) = mesh.color() {
+        vertex_array.set_attrib(AttributeLocation::from_index(3), color.into());
+    }
+
+    if let Some(joint_indices) = mesh.joint_indices() {
+        vertex_array.set_attrib(AttributeLocation::from_index(4), joint_indices.into());
+    }
+
+    if let Some(joint_weights) = mesh.joint_weights() {
+        vertex_array.set_attrib(AttributeLocation::from_index(5), joint_weights.into());
+    }
 }
 
 impl Into<AttribLayout> for VertexAttribute {
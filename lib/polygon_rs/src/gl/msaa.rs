@@ -0,0 +1,146 @@
+//! Multisample anti-aliasing.
+//!
+//! While MSAA is enabled the scene passes draw into an `MsaaTarget` instead of the HDR target or
+//! the camera's output framebuffer. At the start of the post pass the region covered by the
+//! current viewport is resolved into whichever of those the scene would otherwise have been drawn
+//! into, so tone mapping and everything after it works on the resolved image.
+//!
+//! Textures can't be multisampled, so the target is made of renderbuffers. The G-buffer isn't
+//! multisampled either, which is why MSAA is only used by the forward pipeline.
+
+use antialiasing::AntiAliasing;
+use RenderPath;
+use std::cmp;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::context::Context;
+use super::gl_util::framebuffer::{
+    self,
+    BlitFilter,
+    ClearBufferMask,
+    FramebufferAttachment,
+    Framebuffer,
+};
+use super::gl_util::renderbuffer::{Renderbuffer, TextureInternalFormat};
+
+/// The multisampled render targets used while MSAA is enabled.
+#[derive(Debug)]
+pub struct MsaaTarget {
+    pub framebuffer: Framebuffer,
+    pub color: Renderbuffer,
+    pub depth: Renderbuffer,
+
+    /// The format of `color`, which matches the target it's resolved into since resolving
+    /// can't convert between formats.
+    pub format: TextureInternalFormat,
+
+    pub width: usize,
+    pub height: usize,
+    pub samples: usize,
+}
+
+impl MsaaTarget {
+    pub fn new(
+        context: &Context,
+        format: TextureInternalFormat,
+        width: usize,
+        height: usize,
+        samples: usize,
+    ) -> MsaaTarget {
+        let color = Renderbuffer::multisampled(context, format, width, height, samples)
+            .expect("Failed to allocate MSAA color renderbuffer");
+        let depth = Renderbuffer::multisampled(context, TextureInternalFormat::DepthComponent24, width, height, samples)
+            .expect("Failed to allocate MSAA depth renderbuffer");
+
+        let mut framebuffer = Framebuffer::new(context).expect("Failed to create MSAA framebuffer");
+        framebuffer.attach_renderbuffer(FramebufferAttachment::Color0, &color);
+        framebuffer.attach_renderbuffer(FramebufferAttachment::Depth, &depth);
+
+        MsaaTarget {
+            framebuffer: framebuffer,
+            color: color,
+            depth: depth,
+
+            format: format,
+
+            width: width,
+            height: height,
+            samples: samples,
+        }
+    }
+}
+
+impl GlRender {
+    /// Returns `true` if the scene is currently drawn into the multisampled target.
+    pub(super) fn msaa_enabled(&self) -> bool {
+        self.anti_aliasing != AntiAliasing::Off && self.render_path == RenderPath::Forward
+    }
+
+    /// Makes sure the MSAA target exists and matches the size, format, and sample count needed
+    /// by the current settings, then clears it.
+    ///
+    /// Frees the MSAA target if MSAA isn't enabled.
+    pub(super) fn prepare_msaa_target(&mut self) {
+        if !self.msaa_enabled() {
+            self.msaa_target = None;
+            return;
+        }
+
+        let (width, height) = self.intermediate_target_size();
+        let max_samples = self.context.capabilities().max_samples as usize;
+        let samples = cmp::min(self.anti_aliasing.samples(), max_samples);
+        let format = if self.hdr_settings.enabled {
+            TextureInternalFormat::Rgba16F
+        } else {
+            self.color_target_format()
+        };
+
+        let is_current = match self.msaa_target {
+            Some(ref msaa_target) => {
+                msaa_target.width == width
+                    && msaa_target.height == height
+                    && msaa_target.samples == samples
+                    && msaa_target.format == format
+            },
+            None => false,
+        };
+
+        if !is_current {
+            self.msaa_target = Some(MsaaTarget::new(&self.context, format, width, height, samples));
+        }
+
+        let msaa_target = self.msaa_target.as_ref().unwrap();
+        self.context.clear_framebuffer(&msaa_target.framebuffer);
+    }
+
+    /// Resolves the region of the MSAA target covered by the current viewport into the HDR
+    /// target, or into the current camera's output framebuffer if HDR is disabled.
+    ///
+    /// Does nothing if MSAA is disabled.
+    pub(super) fn resolve_msaa(&self) {
+        let msaa_target = match self.msaa_target {
+            Some(ref msaa_target) => msaa_target,
+            None => return,
+        };
+
+        let _stopwatch = Stopwatch::new("Resolve MSAA");
+
+        let resolve_framebuffer = if self.hdr_settings.enabled {
+            self.hdr_target.as_ref().map(|hdr_target| &hdr_target.framebuffer)
+        } else {
+            self.output_framebuffer()
+        };
+
+        // Depth is resolved as well, so that later passes are depth tested against the scene.
+        let viewport = self.context.viewport();
+        framebuffer::blit(
+            &self.context,
+            Some(&msaa_target.framebuffer),
+            resolve_framebuffer,
+            viewport,
+            viewport,
+            ClearBufferMask::Color | ClearBufferMask::Depth,
+            BlitFilter::Nearest,
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Tangent-space normal mapping.
+//!
+//! A material using normal mapping sets its normal map as the `surface_normal` texture property.
+//! Fragment programs can then call `apply_normal_map()` with the interpolated normal and tangent
+//! to get the normal perturbed by the normal map, which is the unchanged normal if the material
+//! doesn't have a normal map or the mesh doesn't have tangents.
+
+use material::{Material, MaterialProperty};
+use super::GlRender;
+use super::gl_util::*;
+use super::gl_util::texture::Texture2d as GlTexture2d;
+
+/// The GLSL declarations for normal mapping, shared by every fragment program that draws mesh
+/// instances.
+///
+/// Declares the `surface_normal` uniform, so materials must not declare a `surface_normal`
+/// property themselves.
+pub static NORMAL_MAP_SOURCE: &'static str = r#"
+    uniform sampler2D surface_normal;
+    uniform int has_surface_normal;
+
+    vec3 apply_normal_map(vec3 normal, vec4 tangent, vec2 uv) {
+        vec3 n = normalize(normal);
+        if (has_surface_normal == 0 || dot(tangent.xyz, tangent.xyz) == 0.0) {
+            return n;
+        }
+
+        // Rebuild an orthonormal tangent frame, since interpolation skews the vertex tangents.
+        vec3 t = normalize(tangent.xyz - n * dot(n, tangent.xyz));
+        vec3 b = cross(n, t) * (tangent.w < 0.0 ? -1.0 : 1.0);
+
+        vec3 sampled = texture(surface_normal, uv).xyz * 2.0 - 1.0;
+        return normalize(mat3(t, b, n) * sampled);
+    }
+"#;
+
+impl GlRender {
+    /// Sets the uniforms used by `apply_normal_map()` for a draw using `material`.
+    pub(super) fn apply_normal_map_uniforms<'a>(
+        &'a self,
+        draw_builder: &mut DrawBuilder<'a>,
+        material: &Material,
+        default_texture: &'a GlTexture2d,
+    ) {
+        let normal_map = match material.get_property("surface_normal") {
+            Some(&MaterialProperty::Texture(ref texture)) => self.textures.get(texture),
+            _ => None,
+        };
+
+        match normal_map {
+            Some(gl_texture) => {
+                draw_builder
+                .uniform("surface_normal", gl_texture)
+                .uniform("has_surface_normal", 1i32);
+            },
+            None => {
+                draw_builder
+                .uniform("surface_normal", default_texture)
+                .uniform("has_surface_normal", 0i32);
+            },
+        }
+    }
+}
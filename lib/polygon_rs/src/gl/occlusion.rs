@@ -0,0 +1,129 @@
+//! Frustum culling, and occlusion culling against the registered occluders, see the `occlusion`
+//! module.
+//!
+//! The occluders are rasterized once for each camera before its passes are executed, and the
+//! render queue skips mesh instances whose mesh bounds are outside of the camera's view frustum or
+//! hidden behind the occluders.
+
+use GpuMesh;
+use anchor::Anchor;
+use camera::Camera;
+use math::*;
+use occlusion::DepthBuffer;
+use stopwatch::Stopwatch;
+use super::GlRender;
+
+impl GlRender {
+    /// Rasterizes the occluders as seen by `camera` from `camera_anchor`, or from the shared
+    /// culling view while drawing a stereo camera.
+    ///
+    /// Clears the occlusion buffer if occlusion culling is disabled, there are no occluders, or
+    /// the camera has no anchor, so that nothing is culled.
+    pub(super) fn prepare_occlusion(&mut self, camera: &Camera, camera_anchor: Option<&Anchor>) {
+        let settings = self.occlusion_settings;
+        let camera_transform = camera_anchor.map(|camera_anchor| {
+            let (camera, anchor) = self.culling_view(camera, camera_anchor);
+            (
+                camera.standard_projection_matrix() * anchor.view_matrix(),
+                anchor.world_position(),
+                anchor.world_orientation(),
+            )
+        });
+
+        let (view_projection, camera_position, camera_orientation) = match camera_transform {
+            Some(camera_transform) if settings.enabled && !self.occluders.is_empty() => camera_transform,
+            _ => {
+                self.occlusion_buffer = None;
+                return;
+            },
+        };
+
+        let _stopwatch = Stopwatch::new("Rasterize occluders");
+
+        // Reuse the previous buffer if it's still the right size.
+        let mut buffer = match self.occlusion_buffer.take() {
+            Some(mut buffer) => {
+                if buffer.width() == settings.width && buffer.height() == settings.height {
+                    buffer.clear();
+                    buffer
+                } else {
+                    DepthBuffer::new(settings.width, settings.height)
+                }
+            },
+            None => DepthBuffer::new(settings.width, settings.height),
+        };
+
+        for occluder in self.occluders.values() {
+            let anchor = match occluder.anchor() {
+                Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                None => continue,
+            };
+
+            let model_transform = anchor.billboard_matrix(camera_position, camera_orientation);
+            let model_view_projection = view_projection * model_transform;
+            for triangle in occluder.triangles() {
+                buffer.rasterize_triangle([
+                    triangle[0] * model_view_projection,
+                    triangle[1] * model_view_projection,
+                    triangle[2] * model_view_projection,
+                ]);
+            }
+        }
+
+        self.occlusion_buffer = Some(buffer);
+    }
+
+    /// Returns `true` if `mesh` drawn at `anchor` is outside of the view frustum of the current
+    /// camera or hidden behind the occluders rasterized for it.
+    ///
+    /// `view_projection` must be the transform the occluders were rasterized with, built from
+    /// `Camera::standard_projection_matrix()` so that the clip-space tests don't depend on the
+    /// camera's depth conventions, and `camera_anchor` the anchor of the camera they were
+    /// rasterized for.
+    pub(super) fn is_culled(
+        &self,
+        mesh: GpuMesh,
+        anchor: &Anchor,
+        camera_anchor: &Anchor,
+        view_projection: Matrix4,
+    ) -> bool {
+        let mesh_data = self.meshes.get(&mesh).expect("Mesh data does not exist for mesh id");
+        let (min, max) = mesh_data.bounds;
+
+        let model_transform = anchor.billboard_matrix(camera_anchor.world_position(), camera_anchor.world_orientation());
+        let model_view_projection = view_projection * model_transform;
+        let corners = [
+            Point::new(min.x, min.y, min.z) * model_view_projection,
+            Point::new(max.x, min.y, min.z) * model_view_projection,
+            Point::new(min.x, max.y, min.z) * model_view_projection,
+            Point::new(max.x, max.y, min.z) * model_view_projection,
+            Point::new(min.x, min.y, max.z) * model_view_projection,
+            Point::new(max.x, min.y, max.z) * model_view_projection,
+            Point::new(min.x, max.y, max.z) * model_view_projection,
+            Point::new(max.x, max.y, max.z) * model_view_projection,
+        ];
+
+        if is_outside_frustum(&corners) {
+            return true;
+        }
+
+        match self.occlusion_buffer {
+            Some(ref buffer) => buffer.is_box_occluded(&corners),
+            None => false,
+        }
+    }
+}
+
+/// Returns `true` if a box with the specified corners in clip space is entirely on the outer side
+/// of any one of the frustum planes.
+///
+/// This is conservative: boxes crossing the corners of the frustum may be kept even though they
+/// aren't visible.
+fn is_outside_frustum(corners: &[Point; 8]) -> bool {
+    corners.iter().all(|corner| corner.x < -corner.w)
+    || corners.iter().all(|corner| corner.x > corner.w)
+    || corners.iter().all(|corner| corner.y < -corner.w)
+    || corners.iter().all(|corner| corner.y > corner.w)
+    || corners.iter().all(|corner| corner.z < -corner.w)
+    || corners.iter().all(|corner| corner.z > corner.w)
+}
@@ -0,0 +1,304 @@
+//! The render queue.
+//!
+//! Every frame each mesh instance to be drawn is given a 64-bit sort key, and the draws are
+//! sorted by key before being submitted. From the most significant bits down a key is made up of:
+//!
+//! - The mesh instance's layer (8 bits), so that lower layers are drawn first.
+//! - The queue pass (2 bits), so that opaque draws come before transparent draws.
+//! - The material's shader (12 bits), so that draws using the same program are adjacent.
+//! - The material (16 bits), so that draws using the same textures and uniforms are adjacent.
+//! - The quantized view-space depth (26 bits).
+//!
+//! Materials are numbered anew each frame while building the queue. Opaque draws are sorted
+//! front-to-back within each material so that the depth test rejects as many hidden fragments as
+//! possible. Transparent draws must be blended back-to-front regardless of their material, so
+//! their shader and material bits are left empty and their depth is inverted. Draws with equal
+//! keys are ordered by mesh instance id, which makes the draw order deterministic.
+//!
+//! Depth is quantized from the bits of the view-space distance as an `f32`, which keeps the
+//! order of any two distances without depending on the far plane, so infinite projections sort
+//! the same way as finite ones. Precision is relative to the distance, like a log encoding.
+//!
+//! Mesh instances using a LOD group get a draw for the level chosen for the camera, or for both
+//! levels while cross-fading, see the `lod` module. Mesh instances outside of the camera's view
+//! frustum or hidden behind occluders are left out of the queue entirely, see the `occlusion`
+//! module.
+
+use {Counter, GpuMesh};
+use anchor::Anchor;
+use camera::Camera;
+use lod::LodSelection;
+use material::Material;
+use mesh_instance::{MeshInstance, MeshInstanceId};
+use stopwatch::Stopwatch;
+use std::mem;
+use super::GlRender;
+
+const LAYER_SHIFT: u64 = 56;
+const PASS_SHIFT: u64 = 54;
+const SHADER_SHIFT: u64 = 42;
+const MATERIAL_SHIFT: u64 = 26;
+
+const PASS_MASK: u64 = 0b11;
+const SHADER_MASK: u64 = (1 << 12) - 1;
+const MATERIAL_MASK: u64 = (1 << 16) - 1;
+const DEPTH_BITS: u32 = 26;
+const DEPTH_MASK: u64 = (1 << DEPTH_BITS) - 1;
+
+/// The passes a draw in the render queue belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueuePass {
+    Opaque = 0,
+    Transparent = 1,
+}
+
+/// A key determining where a draw falls in the render queue, see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SortKey(pub u64);
+
+impl SortKey {
+    /// Creates the key for an opaque draw.
+    ///
+    /// `material` identifies the draw's material within the frame. `depth` is the draw's
+    /// distance along the camera's view direction, negative distances are treated as 0.
+    pub fn opaque(layer: u8, shader: usize, material: usize, depth: f32) -> SortKey {
+        SortKey(
+            (layer as u64) << LAYER_SHIFT
+            | (QueuePass::Opaque as u64) << PASS_SHIFT
+            | (shader as u64 & SHADER_MASK) << SHADER_SHIFT
+            | (material as u64 & MATERIAL_MASK) << MATERIAL_SHIFT
+            | quantize_depth(depth)
+        )
+    }
+
+    /// Creates the key for a transparent draw, see `opaque()` for the meaning of `depth`.
+    pub fn transparent(layer: u8, depth: f32) -> SortKey {
+        SortKey(
+            (layer as u64) << LAYER_SHIFT
+            | (QueuePass::Transparent as u64) << PASS_SHIFT
+            | (DEPTH_MASK - quantize_depth(depth))
+        )
+    }
+
+    /// Gets the pass encoded in the key.
+    pub fn pass(&self) -> QueuePass {
+        match (self.0 >> PASS_SHIFT) & PASS_MASK {
+            0 => QueuePass::Opaque,
+            1 => QueuePass::Transparent,
+            pass => unreachable!("Invalid queue pass in sort key: {}", pass),
+        }
+    }
+}
+
+/// Keeps the most significant bits of `depth` as an `f32`.
+///
+/// The bits of non-negative floats are ordered the same as the floats themselves, and the sign bit
+/// is always clear, so the top `DEPTH_BITS` bits after it preserve the order of depths.
+fn quantize_depth(depth: f32) -> u64 {
+    let depth = if depth > 0.0 { depth } else { 0.0 };
+    let bits = unsafe { mem::transmute::<f32, u32>(depth) };
+    (bits >> (31 - DEPTH_BITS)) as u64 & DEPTH_MASK
+}
+
+/// A single entry in the render queue.
+#[derive(Debug, Clone, Copy)]
+pub struct QueueItem<'a> {
+    pub key: SortKey,
+    pub mesh_instance_id: MeshInstanceId,
+    pub mesh_instance: &'a MeshInstance,
+    pub material: &'a Material,
+
+    /// The mesh to draw, which differs from the mesh instance's mesh if it uses a LOD group.
+    pub mesh: GpuMesh,
+
+    /// The dither fade of the draw while cross-fading between LOD levels. Positive values fade
+    /// the draw in and negative values fade it out, the draw is fully visible at 0.
+    pub lod_fade: f32,
+}
+
+/// The draws for a single camera, sorted by their sort keys.
+#[derive(Debug)]
+pub struct RenderQueue<'a> {
+    items: Vec<QueueItem<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    /// Gets the draws in the specified pass, in the order they should be submitted.
+    pub fn pass(&self, pass: QueuePass) -> Vec<QueueItem<'a>> {
+        self.items.iter().cloned().filter(|item| item.key.pass() == pass).collect()
+    }
+
+    /// Gets the draws in all passes, in the order they should be submitted.
+    pub fn items(&self) -> &[QueueItem<'a>] {
+        &self.items
+    }
+}
+
+impl GlRender {
+    /// Builds the sorted render queue for the view from `camera`.
+    ///
+    /// Mesh instances with both opaque and transparent submeshes appear once in each pass. While
+    /// drawing a stereo camera the queue is built from the culling view shared by both eyes, so
+    /// that they draw the same mesh instances in the same order.
+    pub(super) fn build_render_queue(&self, camera: &Camera, camera_anchor: &Anchor) -> RenderQueue {
+        let _stopwatch = Stopwatch::new("Build render queue");
+
+        let (camera, camera_anchor) = self.culling_view(camera, camera_anchor);
+
+        let view_transform = camera_anchor.view_matrix();
+        let view_projection = camera.standard_projection_matrix() * view_transform;
+        let near = camera.near();
+
+        // Converts the radius of a sphere at a distance of 1 into the fraction of the viewport's
+        // height covered by the sphere.
+        let projection_scale = camera.projection_matrix()[1][1];
+
+        let mut items = Vec::with_capacity(self.mesh_instances.len());
+        for (mesh_instance_id, mesh_instance, material, material_index) in self.mesh_instance_draws() {
+            let mesh_instance_id = *mesh_instance_id;
+
+            // Mesh instances without an anchor aren't rendered.
+            let anchor = match mesh_instance.anchor() {
+                Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                None => continue,
+            };
+
+            // The camera looks down the -z axis, so depth in front of the camera is -z.
+            let view_depth = -(anchor.world_position() * view_transform).z;
+            let layer = mesh_instance.layer();
+
+            let lods = match mesh_instance.lod_group() {
+                Some(lod_group_id) => {
+                    let lod_group = self.lod_groups.get(&lod_group_id).expect("No such LOD group exists");
+
+                    let scale = anchor.world_scale();
+                    let radius = lod_group.radius() * scale.x.max(scale.y).max(scale.z);
+                    let screen_size = radius * projection_scale / view_depth.max(near);
+
+                    match lod_group.select(screen_size) {
+                        Some(LodSelection::Single(mesh)) => vec![(mesh, 0.0)],
+                        Some(LodSelection::CrossFade { from, to, fade }) => vec![(from, -fade), (to, fade)],
+                        None => continue,
+                    }
+                },
+                None => vec![(*mesh_instance.mesh(), 0.0)],
+            };
+
+            for (mesh, lod_fade) in lods {
+                // Skinned meshes can move outside of their bounds, so they're never culled.
+                if mesh_instance.skeleton().is_none() && self.is_culled(mesh, anchor, camera_anchor, view_projection) {
+                    continue;
+                }
+
+                // Instanced mesh instances are drawn as part of their instance batch instead.
+                let (has_opaque, has_transparent) = self.submesh_transparency(mesh, mesh_instance, material);
+                if has_opaque && !self.instanced_mesh_instances.contains(&mesh_instance_id) {
+                    items.push(QueueItem {
+                        key: SortKey::opaque(layer, material.shader().index(), material_index, view_depth),
+                        mesh_instance_id: mesh_instance_id,
+                        mesh_instance: mesh_instance,
+                        material: material,
+                        mesh: mesh,
+                        lod_fade: lod_fade,
+                    });
+                }
+
+                if has_transparent {
+                    items.push(QueueItem {
+                        key: SortKey::transparent(layer, view_depth),
+                        mesh_instance_id: mesh_instance_id,
+                        mesh_instance: mesh_instance,
+                        material: material,
+                        mesh: mesh,
+                        lod_fade: lod_fade,
+                    });
+                }
+            }
+        }
+
+        items.sort_by_key(|item| (item.key, item.mesh_instance_id.index()));
+
+        RenderQueue {
+            items: items,
+        }
+    }
+
+    /// Gathers every mesh instance along with the material used to render it.
+    ///
+    /// Each material is also given an index that is unique among the materials drawn this frame,
+    /// so that draws sharing a material can be grouped together in the sort key.
+    fn mesh_instance_draws(&self) -> Vec<(&MeshInstanceId, &MeshInstance, &Material, usize)> {
+        let mut draws = Vec::with_capacity(self.mesh_instances.len());
+        let mut material_index = 0;
+
+        for (material_id, mesh_instances) in &self.mesh_instances_with_shared_materials {
+            let material = self.shared_materials.get(material_id).expect("No such material exists");
+            for mesh_instance_id in mesh_instances {
+                let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
+                draws.push((mesh_instance_id, mesh_instance, material, material_index));
+            }
+            material_index += 1;
+        }
+
+        for mesh_instance_id in &self.mesh_instances_with_owned_material {
+            let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
+            let material = mesh_instance.material().expect("Mesh instance was in wrong bucket (was in the owned material bucket, had shared material)");
+            draws.push((mesh_instance_id, mesh_instance, material, material_index));
+            material_index += 1;
+        }
+
+        draws
+    }
+
+    /// Returns whether `mesh` has any submeshes drawn with an opaque material and whether it has
+    /// any drawn with a transparent material when drawn for the mesh instance.
+    fn submesh_transparency(&self, mesh: GpuMesh, mesh_instance: &MeshInstance, material: &Material) -> (bool, bool) {
+        let mesh_data = self.meshes.get(&mesh).expect("Mesh data does not exist for mesh id");
+
+        let mut has_opaque = false;
+        let mut has_transparent = false;
+        for submesh in &mesh_data.submeshes {
+            let material = mesh_instance.slot_material(submesh.material_slot).unwrap_or(material);
+            if material.is_transparent() {
+                has_transparent = true;
+            } else {
+                has_opaque = true;
+            }
+        }
+
+        (has_opaque, has_transparent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opaque_groups_materials() {
+        // Draws sharing a material are adjacent even when another material's draw lies between
+        // them in depth.
+        let near = SortKey::opaque(0, 1, 0, 1.0);
+        let middle = SortKey::opaque(0, 1, 1, 2.0);
+        let far = SortKey::opaque(0, 1, 0, 3.0);
+
+        let mut keys = vec![middle, far, near];
+        keys.sort();
+        assert_eq!(vec![near, far, middle], keys);
+    }
+
+    #[test]
+    fn depth_order_without_far_plane() {
+        let depths = [0.0, 0.01, 0.5, 1.0, 100.0, 1.0e6, 1.0e30];
+        for pair in depths.windows(2) {
+            assert!(
+                SortKey::opaque(0, 0, 0, pair[0]) < SortKey::opaque(0, 0, 0, pair[1]),
+                "Opaque draw at {} should sort before {}", pair[0], pair[1]);
+            assert!(
+                SortKey::transparent(0, pair[0]) > SortKey::transparent(0, pair[1]),
+                "Transparent draw at {} should sort after {}", pair[0], pair[1]);
+        }
+
+        assert_eq!(SortKey::opaque(0, 0, 0, 0.0), SortKey::opaque(0, 0, 0, -5.0));
+    }
+}
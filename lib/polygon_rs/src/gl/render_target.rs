@@ -0,0 +1,86 @@
+//! Render targets that cameras can draw into instead of the window.
+//!
+//! A render target's color texture is registered as a regular texture, so the scene drawn by one
+//! camera can be displayed on a surface drawn by another, e.g. for mirrors, security monitors,
+//! and portals. Cameras with a target are drawn before cameras drawing to the window, so their
+//! textures are up to date when they're sampled.
+//!
+//! The intermediate render targets (the G-buffer, the HDR target, and the MSAA target) are shared
+//! by all cameras, so they're made large enough for both the window and the largest render target.
+
+use std::cmp;
+use super::GlRender;
+use super::gl_util::context::Context;
+use super::gl_util::framebuffer::{FramebufferAttachment, Framebuffer};
+use super::gl_util::texture::{Texture2d as GlTexture2d, TextureInternalFormat};
+
+/// The framebuffer and depth texture backing a render target.
+///
+/// The color texture is owned by the renderer's textures, so that materials can sample it.
+#[derive(Debug)]
+pub struct RenderTarget {
+    pub framebuffer: Framebuffer,
+    pub depth: GlTexture2d,
+
+    pub width: usize,
+    pub height: usize,
+}
+
+impl RenderTarget {
+    /// Creates a render target drawing into `color`, along with a matching depth texture.
+    pub fn new(context: &Context, color: &GlTexture2d, width: usize, height: usize) -> RenderTarget {
+        let depth = GlTexture2d::allocate(context, TextureInternalFormat::DepthComponent24, width, height)
+            .expect("Failed to allocate render target depth texture");
+
+        let mut framebuffer = Framebuffer::new(context).expect("Failed to create render target framebuffer");
+        framebuffer.attach_texture(FramebufferAttachment::Color0, color);
+        framebuffer.attach_texture(FramebufferAttachment::Depth, &depth);
+
+        RenderTarget {
+            framebuffer: framebuffer,
+            depth: depth,
+
+            width: width,
+            height: height,
+        }
+    }
+}
+
+impl GlRender {
+    /// Returns the framebuffer the current camera's final image is drawn into, or `None` for the
+    /// default framebuffer.
+    ///
+    /// This is where the post pass resolves to, and where everything drawn after it goes.
+    pub(super) fn output_framebuffer(&self) -> Option<&Framebuffer> {
+        self.camera_target.map(|texture| {
+            &self.render_targets
+                .get(&texture)
+                .expect("Camera's render target does not exist")
+                .framebuffer
+        })
+    }
+
+    /// Returns the format of the color textures of render targets.
+    ///
+    /// Render targets are sRGB while gamma-correct rendering is enabled, so that they're
+    /// converted from linear when written and back to linear when sampled.
+    pub(super) fn color_target_format(&self) -> TextureInternalFormat {
+        if self.gamma_correct {
+            TextureInternalFormat::Srgb8Alpha8
+        } else {
+            TextureInternalFormat::Rgba8
+        }
+    }
+
+    /// Returns the size needed for the intermediate render targets, which is large enough for
+    /// both the window and every render target.
+    pub(super) fn intermediate_target_size(&self) -> (usize, usize) {
+        let viewport = self.context.viewport();
+        let initial = (viewport.width as usize, viewport.height as usize);
+        self.render_targets
+            .values()
+            .fold(initial, |(width, height), target| {
+                (cmp::max(width, target.width), cmp::max(height, target.height))
+            })
+    }
+}
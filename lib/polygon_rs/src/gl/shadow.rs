@@ -0,0 +1,490 @@
+//! Shadow map rendering for the OpenGL renderer.
+//!
+//! Directional light shadows use cascaded shadow maps: each cascade is an orthographic depth
+//! render from the light's point of view centered in front of the camera, with each cascade
+//! covering twice the distance of the previous one. The main pass then picks the smallest
+//! cascade that contains the fragment and samples it with 3x3 PCF (see `SHADOW_FUNCTIONS`).
+//!
+//! Point light shadows render the scene into a depth cube map around the light. Rather than
+//! regular depth, each texel stores the distance from the light to the closest surface divided
+//! by the light's radius, which the main pass compares against the fragment's own distance.
+
+use anchor::Anchor;
+use light::LightData;
+use math::*;
+use shadow::{MAX_POINT_SHADOWS, ShadowSettings};
+use super::GlRender;
+use super::gl_util::*;
+use super::gl_util::context::Context;
+use super::gl_util::framebuffer::{DrawBuffer, Framebuffer, FramebufferAttachment, Rect};
+use super::gl_util::texture::{CubeFace, Texture2d as GlTexture2d, TextureCube, TextureInternalFormat};
+use super::skinning::{SKINNING_SOURCE, apply_skinning_uniforms};
+use stopwatch::Stopwatch;
+
+pub fn shadow_vert_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform mat4 model_view_projection;
+
+            layout(location = 0) in vec4 vertex_position;
+
+            void main(void) {{
+                gl_Position = model_view_projection * (skin_transform() * vertex_position);
+            }}
+        "#,
+        SKINNING_SOURCE)
+}
+
+pub static SHADOW_FRAG_SOURCE: &'static str = r#"
+    #version 330 core
+
+    void main(void) {}
+"#;
+
+pub fn point_shadow_vert_source() -> String {
+    format!(r#"
+            #version 330 core
+
+            {}
+
+            uniform mat4 model_transform;
+            uniform mat4 view_projection;
+
+            layout(location = 0) in vec4 vertex_position;
+
+            out vec4 world_position;
+
+            void main(void) {{
+                world_position = model_transform * (skin_transform() * vertex_position);
+                gl_Position = view_projection * world_position;
+            }}
+        "#,
+        SKINNING_SOURCE)
+}
+
+pub static POINT_SHADOW_FRAG_SOURCE: &'static str = r#"
+    #version 330 core
+
+    uniform vec4 light_position;
+    uniform float light_radius;
+
+    in vec4 world_position;
+
+    void main(void) {
+        gl_FragDepth = length(world_position.xyz - light_position.xyz) / light_radius;
+    }
+"#;
+
+/// Built-in functions injected into every material's fragment shader.
+///
+/// Arrays of samplers can only be indexed with constant expressions in GLSL 3.30, hence the
+/// chain of `if` statements when selecting a cascade.
+pub static SHADOW_FUNCTIONS: &'static str = r#"
+    float _sample_shadow_(sampler2DShadow shadow_sampler, vec3 coord) {
+        vec2 texel_size = 1.0 / vec2(textureSize(shadow_sampler, 0));
+        float lit = 0.0;
+        for (int x = -1; x <= 1; x += 1) {
+            for (int y = -1; y <= 1; y += 1) {
+                vec2 offset = vec2(x, y) * texel_size;
+                lit += texture(shadow_sampler, vec3(coord.xy + offset, coord.z - shadow_bias));
+            }
+        }
+
+        return lit / 9.0;
+    }
+
+    float _sample_point_shadow_(samplerCube shadow_sampler, int light_index) {
+        vec3 offset = _vertex_world_position_.xyz - light_position[light_index].xyz;
+        float dist = length(offset) / light_radius[light_index];
+        float closest = texture(shadow_sampler, offset).r;
+        return dist - shadow_bias > closest ? 0.0 : 1.0;
+    }
+
+    float shadow_factor(int light_index) {
+        int point_shadow = light_shadow_map[light_index];
+        if (point_shadow == 0) {
+            return _sample_point_shadow_(point_shadow_map[0], light_index);
+        } else if (point_shadow == 1) {
+            return _sample_point_shadow_(point_shadow_map[1], light_index);
+        } else if (point_shadow == 2) {
+            return _sample_point_shadow_(point_shadow_map[2], light_index);
+        } else if (point_shadow == 3) {
+            return _sample_point_shadow_(point_shadow_map[3], light_index);
+        }
+
+        if (light_index != shadow_light_index) {
+            return 1.0;
+        }
+
+        for (int cascade = 0; cascade < shadow_cascade_count; cascade += 1) {
+            vec4 coord = shadow_transform[cascade] * _vertex_world_position_;
+            if (any(lessThan(coord.xyz, vec3(0.0))) || any(greaterThan(coord.xyz, vec3(1.0)))) {
+                continue;
+            }
+
+            if (cascade == 0) {
+                return _sample_shadow_(shadow_map[0], coord.xyz);
+            } else if (cascade == 1) {
+                return _sample_shadow_(shadow_map[1], coord.xyz);
+            } else if (cascade == 2) {
+                return _sample_shadow_(shadow_map[2], coord.xyz);
+            } else {
+                return _sample_shadow_(shadow_map[3], coord.xyz);
+            }
+        }
+
+        // Fragments outside of every cascade are beyond the shadow distance.
+        return 1.0;
+    }
+"#;
+
+/// Uniform names for the elements of the shadow arrays, since the uniforms for each element of
+/// an array have to be set individually.
+pub static SHADOW_MAP_NAMES: [&'static str; 4] =
+    ["shadow_map[0]", "shadow_map[1]", "shadow_map[2]", "shadow_map[3]"];
+pub static SHADOW_TRANSFORM_NAMES: [&'static str; 4] =
+    ["shadow_transform[0]", "shadow_transform[1]", "shadow_transform[2]", "shadow_transform[3]"];
+pub static POINT_SHADOW_MAP_NAMES: [&'static str; 4] =
+    ["point_shadow_map[0]", "point_shadow_map[1]", "point_shadow_map[2]", "point_shadow_map[3]"];
+
+/// The render target for a single shadow cascade.
+#[derive(Debug)]
+pub struct ShadowCascade {
+    pub framebuffer: Framebuffer,
+    pub depth_texture: GlTexture2d,
+}
+
+impl ShadowCascade {
+    pub fn new(context: &Context, resolution: usize) -> ShadowCascade {
+        let mut depth_texture = GlTexture2d::allocate(
+            context,
+            TextureInternalFormat::DepthComponent24,
+            resolution,
+            resolution,
+        ).expect("Failed to allocate shadow map");
+        depth_texture.set_depth_compare(Some(Comparison::LessEqual));
+
+        let mut framebuffer = Framebuffer::new(context).expect("Failed to create shadow framebuffer");
+        framebuffer.attach_texture(FramebufferAttachment::Depth, &depth_texture);
+
+        // Shadow maps only have a depth attachment.
+        framebuffer.set_draw_buffers(&[DrawBuffer::None]);
+        framebuffer.set_read_buffer(DrawBuffer::None);
+
+        ShadowCascade {
+            framebuffer: framebuffer,
+            depth_texture: depth_texture,
+        }
+    }
+}
+
+/// The render target for a single point light's shadow.
+///
+/// The faces of the cube map are attached to the framebuffer one at a time while rendering.
+#[derive(Debug)]
+pub struct PointShadow {
+    pub framebuffer: Framebuffer,
+    pub depth_texture: TextureCube,
+}
+
+impl PointShadow {
+    pub fn new(context: &Context, resolution: usize) -> PointShadow {
+        let depth_texture = TextureCube::allocate(
+            context,
+            TextureInternalFormat::DepthComponent24,
+            resolution,
+        ).expect("Failed to allocate point shadow map");
+
+        let mut framebuffer = Framebuffer::new(context).expect("Failed to create shadow framebuffer");
+        framebuffer.set_draw_buffers(&[DrawBuffer::None]);
+        framebuffer.set_read_buffer(DrawBuffer::None);
+
+        PointShadow {
+            framebuffer: framebuffer,
+            depth_texture: depth_texture,
+        }
+    }
+}
+
+impl GlRender {
+    /// Recreates the cascade render targets to match the current shadow settings.
+    pub(super) fn rebuild_shadow_cascades(&mut self) {
+        let ShadowSettings { resolution, cascade_count, .. } = self.shadow_settings;
+
+        self.shadow_cascades.clear();
+        for _ in 0..cascade_count {
+            let cascade = ShadowCascade::new(&self.context, resolution);
+            self.shadow_cascades.push(cascade);
+        }
+
+        // Point shadows are created as needed by `render_point_shadows()`.
+        self.point_shadows.clear();
+    }
+
+    /// Renders the shadow maps for all shadow-casting lights.
+    pub(super) fn update_shadows(&mut self, camera_position: Point, camera_orientation: Orientation) {
+        let _stopwatch = Stopwatch::new("Rendering shadows");
+
+        let old_viewport = self.context.viewport();
+
+        self.render_directional_shadows(camera_position, camera_orientation);
+        self.render_point_shadows(camera_position, camera_orientation);
+
+        self.context.set_viewport(old_viewport);
+    }
+
+    /// Renders the shadow maps for the first shadow-casting directional light.
+    ///
+    /// Updates `shadow_light` and `shadow_transforms` for use in the main pass. If no light casts
+    /// shadows `shadow_light` is set to `None` and nothing is rendered.
+    fn render_directional_shadows(&mut self, camera_position: Point, camera_orientation: Orientation) {
+        self.shadow_light = None;
+        self.shadow_transforms.clear();
+
+        // NOTE: The index must match the order the lights are batched in, see
+        // `GlRender::light_batches()`.
+        let shadow_light = self.lights
+            .values()
+            .enumerate()
+            .filter(|&(_, light)| light.cast_shadows())
+            .filter_map(|(index, light)| match light.data {
+                LightData::Directional { direction } => Some((index, direction)),
+                _ => None,
+            })
+            .next();
+
+        let (light_index, light_direction) = match shadow_light {
+            Some(shadow_light) => shadow_light,
+            None => return,
+        };
+
+        let ShadowSettings { resolution, cascade_count, distance, .. } = self.shadow_settings;
+        for index in 0..cascade_count {
+            // Each cascade covers twice the distance of the previous one, with the last covering
+            // the full shadow distance.
+            let radius = distance / (1 << (cascade_count - 1 - index)) as f32;
+            let center = camera_position + camera_orientation.forward() * (radius * 0.5);
+            self.shadow_transforms.push(cascade_transform(center, light_direction, radius));
+        }
+
+        self.context.set_viewport(Rect::new(0, 0, resolution as i32, resolution as i32));
+
+        for (cascade, shadow_transform) in self.shadow_cascades.iter().zip(&self.shadow_transforms) {
+            self.context.clear_framebuffer(&cascade.framebuffer);
+
+            // The cascade transform maps into texture space, undo that to get back to clip space.
+            let light_projection = texture_to_clip() * *shadow_transform;
+
+            for mesh_instance in self.mesh_instances.values() {
+                let anchor = match mesh_instance.anchor() {
+                    Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                    None => continue,
+                };
+
+                let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+                let model_transform = anchor.billboard_matrix(camera_position, camera_orientation);
+                let model_view_projection = light_projection * model_transform;
+                let joint_palette = self.joint_palette(mesh_instance);
+
+                let mut draw_builder = DrawBuilder::new(&self.context, &mesh_data.vertex_array, DrawMode::Triangles);
+                draw_builder
+                .framebuffer(&cascade.framebuffer)
+                .program(&self.shadow_program)
+                .cull(Face::Back)
+                .depth_test(Comparison::Less)
+                .polygon_offset(1.1, 4.0)
+                .uniform(
+                    "model_view_projection",
+                    GlMatrix {
+                        data: model_view_projection.raw_data(),
+                        transpose: true,
+                    },
+                );
+
+                apply_skinning_uniforms(&mut draw_builder, joint_palette.as_ref().map(|data| &**data));
+                draw_builder.draw();
+            }
+        }
+
+        self.shadow_light = Some(light_index);
+    }
+
+    /// Renders a depth cube map for each shadow-casting point light, up to `MAX_POINT_SHADOWS`.
+    ///
+    /// Updates `point_shadow_lights` with the index of the light for each cube map.
+    fn render_point_shadows(&mut self, camera_position: Point, camera_orientation: Orientation) {
+        self.point_shadow_lights.clear();
+
+        let resolution = self.shadow_settings.point_resolution;
+        self.context.set_viewport(Rect::new(0, 0, resolution as i32, resolution as i32));
+
+        // NOTE: The index must match the order the lights are batched in, see
+        // `GlRender::light_batches()`.
+        let shadow_lights = self.lights
+            .values()
+            .enumerate()
+            .filter(|&(_, light)| light.cast_shadows())
+            .filter_map(|(index, light)| match light.data {
+                LightData::Point { radius } => Some((index, light, radius)),
+                _ => None,
+            })
+            .take(MAX_POINT_SHADOWS)
+            .enumerate();
+
+        for (shadow_index, (light_index, light, radius)) in shadow_lights {
+            let light_anchor = match light.anchor() {
+                Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                None => panic!("Cannot render point light if it's not attached to an anchor"),
+            };
+            let light_position = light_anchor.world_position();
+
+            if self.point_shadows.len() <= shadow_index {
+                self.point_shadows.push(PointShadow::new(&self.context, resolution));
+            }
+            let point_shadow = &mut self.point_shadows[shadow_index];
+
+            // Objects further away than the light's radius aren't lit by it, so they don't need to
+            // be in the shadow map.
+            let near = radius * 0.001;
+            let projection = cube_face_projection(near, radius);
+
+            for &face in &CubeFace::ALL {
+                point_shadow.framebuffer.attach_cube_face(
+                    FramebufferAttachment::Depth,
+                    &point_shadow.depth_texture,
+                    face,
+                );
+                self.context.clear_framebuffer(&point_shadow.framebuffer);
+
+                let view_projection = projection * cube_face_view(light_position, face);
+
+                for mesh_instance in self.mesh_instances.values() {
+                    let anchor = match mesh_instance.anchor() {
+                        Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                        None => continue,
+                    };
+
+                    let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+                    let model_transform = anchor.billboard_matrix(camera_position, camera_orientation);
+                    let joint_palette = self.joint_palette(mesh_instance);
+
+                    let mut draw_builder = DrawBuilder::new(&self.context, &mesh_data.vertex_array, DrawMode::Triangles);
+                    draw_builder
+                    .framebuffer(&point_shadow.framebuffer)
+                    .program(&self.point_shadow_program)
+                    .cull(Face::Back)
+                    .depth_test(Comparison::Less)
+                    .uniform(
+                        "model_transform",
+                        GlMatrix {
+                            data: model_transform.raw_data(),
+                            transpose: true,
+                        },
+                    )
+                    .uniform(
+                        "view_projection",
+                        GlMatrix {
+                            data: view_projection.raw_data(),
+                            transpose: true,
+                        },
+                    )
+                    .uniform("light_position", *light_position.as_array())
+                    .uniform("light_radius", radius);
+
+                    apply_skinning_uniforms(&mut draw_builder, joint_palette.as_ref().map(|data| &**data));
+                    draw_builder.draw();
+                }
+            }
+
+            self.point_shadow_lights.push(light_index);
+        }
+    }
+}
+
+/// Calculates the projection for rendering a single face of a cube map, a perspective projection
+/// with a 90 degree field of view.
+fn cube_face_projection(near: f32, far: f32) -> Matrix4 {
+    let mut projection = Matrix4::new();
+    projection[0][0] = 1.0;
+    projection[1][1] = 1.0;
+    projection[2][2] = -(far + near) / (far - near);
+    projection[2][3] = -2.0 * far * near / (far - near);
+    projection[3][2] = -1.0;
+    projection
+}
+
+/// Calculates the view matrix for rendering a single face of a cube map centered at `position`.
+///
+/// The forward and up directions for each face follow the GL cube map conventions, so that
+/// sampling the cube map with a direction returns the texel that was rendered in that direction.
+fn cube_face_view(position: Point, face: CubeFace) -> Matrix4 {
+    let (forward, up) = match face {
+        CubeFace::PositiveX => (Vector3::right(), Vector3::down()),
+        CubeFace::NegativeX => (Vector3::left(), Vector3::down()),
+        CubeFace::PositiveY => (Vector3::up(), Vector3::back()),
+        CubeFace::NegativeY => (Vector3::down(), Vector3::forward()),
+        CubeFace::PositiveZ => (Vector3::back(), Vector3::down()),
+        CubeFace::NegativeZ => (Vector3::forward(), Vector3::down()),
+    };
+
+    let right = Vector3::cross(forward, up);
+    let up = Vector3::cross(right, forward);
+    let position = Vector3::from(position);
+
+    let mut view = Matrix4::identity();
+    for col in 0..3 {
+        view[0][col] = right[col];
+        view[1][col] = up[col];
+        view[2][col] = -forward[col];
+    }
+    view[0][3] = -right.dot(position);
+    view[1][3] = -up.dot(position);
+    view[2][3] = forward.dot(position);
+    view
+}
+
+/// Calculates the transform from world space to shadow map texture space for a cascade.
+///
+/// The cascade is an orthographic box around a sphere of `radius` centered at `center`, looking
+/// down `direction`. The box extends back towards the light so that objects between the light
+/// and the cascade still cast shadows into it.
+fn cascade_transform(center: Point, direction: Vector3, radius: f32) -> Matrix4 {
+    // Avoid an up vector that's parallel to the light direction.
+    let up = if direction.y.abs() > 0.99 { Vector3::forward() } else { Vector3::up() };
+
+    let mut light_anchor = Anchor::new();
+    light_anchor.set_position(center - direction * (radius * 3.0));
+    light_anchor.set_orientation(Orientation::look_rotation(direction, up));
+
+    let near = 0.0;
+    let far = radius * 6.0;
+
+    let mut projection = Matrix4::identity();
+    projection[0][0] = 1.0 / radius;
+    projection[1][1] = 1.0 / radius;
+    projection[2][2] = -2.0 / (far - near);
+    projection[2][3] = -(far + near) / (far - near);
+
+    clip_to_texture() * projection * light_anchor.view_matrix()
+}
+
+/// Maps clip space coordinates in [-1, 1] to texture coordinates in [0, 1].
+fn clip_to_texture() -> Matrix4 {
+    let mut bias = Matrix4::scale(0.5, 0.5, 0.5);
+    bias[0][3] = 0.5;
+    bias[1][3] = 0.5;
+    bias[2][3] = 0.5;
+    bias
+}
+
+/// The inverse of `clip_to_texture()`.
+fn texture_to_clip() -> Matrix4 {
+    let mut unbias = Matrix4::scale(2.0, 2.0, 2.0);
+    unbias[0][3] = -1.0;
+    unbias[1][3] = -1.0;
+    unbias[2][3] = -1.0;
+    unbias
+}
@@ -0,0 +1,70 @@
+//! GPU skinning.
+//!
+//! Every vertex program includes `SKINNING_SOURCE`, which declares the joint palette and the
+//! joint vertex attributes. Vertex programs call `skin_transform()` to get the transform from the
+//! bind pose to the current pose, which is the identity for meshes without a skeleton. Each
+//! skeleton's palette is recalculated for every mesh instance drawn with it.
+
+use mesh_instance::MeshInstance;
+use super::GlRender;
+use super::gl_util::*;
+
+/// The GLSL declarations for skinning, shared by every vertex program.
+///
+/// The size of `joint_palette` must match `skeleton::MAX_JOINTS`.
+pub static SKINNING_SOURCE: &'static str = r#"
+    uniform mat4 joint_palette[64];
+    uniform int skinned;
+
+    layout(location = 4) in vec4 vertex_joint_indices;
+    layout(location = 5) in vec4 vertex_joint_weights;
+
+    mat4 skin_transform() {
+        if (skinned == 0) {
+            return mat4(1.0);
+        }
+
+        return joint_palette[int(vertex_joint_indices.x)] * vertex_joint_weights.x
+             + joint_palette[int(vertex_joint_indices.y)] * vertex_joint_weights.y
+             + joint_palette[int(vertex_joint_indices.z)] * vertex_joint_weights.z
+             + joint_palette[int(vertex_joint_indices.w)] * vertex_joint_weights.w;
+    }
+"#;
+
+impl GlRender {
+    /// Calculates the joint palette for `mesh_instance` as raw matrix data, or `None` if the
+    /// mesh instance doesn't have a skeleton.
+    pub(super) fn joint_palette(&self, mesh_instance: &MeshInstance) -> Option<Vec<f32>> {
+        mesh_instance.skeleton().map(|skeleton_id| {
+            let skeleton = self.skeletons.get(&skeleton_id).expect("No such skeleton exists");
+
+            let palette = skeleton.palette();
+            let mut data = Vec::with_capacity(palette.len() * 16);
+            for matrix in &palette {
+                data.extend(matrix.raw_data());
+            }
+
+            data
+        })
+    }
+}
+
+/// Sets the skinning uniforms for a draw, see `SKINNING_SOURCE`.
+pub fn apply_skinning_uniforms<'a>(draw_builder: &mut DrawBuilder<'a>, joint_palette: Option<&'a [f32]>) {
+    match joint_palette {
+        Some(joint_palette) => {
+            draw_builder
+            .uniform("skinned", 1i32)
+            .uniform(
+                "joint_palette",
+                UniformValue::Matrix4v(GlMatrix {
+                    data: joint_palette,
+                    transpose: true,
+                }),
+            );
+        },
+        None => {
+            draw_builder.uniform("skinned", 0i32);
+        },
+    }
+}
@@ -0,0 +1,87 @@
+//! Clearing a camera's viewport to the environment map, see `CameraClear::Skybox`.
+//!
+//! The skybox is drawn as a full-screen triangle before the scene, with the view direction of
+//! each pixel reconstructed from the camera's inverse projection and view transforms. It samples
+//! the most detailed level of the prefiltered specular map, which is the environment map itself.
+
+use anchor::Anchor;
+use camera::Camera;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::depth::near_ndc_depth;
+use super::gl_util::*;
+
+pub static SKYBOX_FRAG_SOURCE: &'static str = r#"
+    #version 330 core
+
+    uniform samplerCube environment_specular;
+    uniform mat4 inverse_projection;
+    uniform mat4 inverse_view;
+    uniform float near_depth;
+
+    in vec2 _uv_;
+
+    out vec4 fragment_color;
+
+    void main(void) {
+        // Points on the far plane may be at infinity, so the direction is taken through the
+        // near plane instead.
+        vec4 view_position = inverse_projection * vec4(_uv_ * 2.0 - 1.0, near_depth, 1.0);
+        vec3 world_direction = mat3(inverse_view) * (view_position.xyz / view_position.w);
+        fragment_color = vec4(textureLod(environment_specular, world_direction, 0.0).rgb, 1.0);
+    }
+"#;
+
+impl GlRender {
+    /// Fills the current viewport with the environment map as seen by `camera`.
+    ///
+    /// Draws black if no environment map is set. Depth isn't written, so the viewport's depth
+    /// should be cleared separately.
+    pub(super) fn render_skybox(&self, camera: &Camera, camera_anchor: &Anchor) {
+        let _stopwatch = Stopwatch::new("Skybox");
+
+        let environment = match self.environment {
+            Some(ref environment) => environment,
+            None => {
+                let rect = self.context.viewport();
+                self.context.clear_rect(self.scene_framebuffer(), rect, Some([0.0, 0.0, 0.0, 1.0]), None);
+                return;
+            },
+        };
+
+        let inverse_projection = camera.inverse_projection_matrix();
+        let inverse_view = camera_anchor.inverse_view_matrix();
+
+        let mut draw_builder = DrawBuilder::new(
+            &self.context,
+            &self.fullscreen_triangle,
+            DrawMode::Triangles,
+        );
+
+        draw_builder
+        .program(&self.skybox_program)
+        .depth_write(false)
+        .uniform("environment_specular", &environment.specular)
+        .uniform("near_depth", near_ndc_depth(camera))
+        .uniform(
+            "inverse_projection",
+            GlMatrix {
+                data: inverse_projection.raw_data(),
+                transpose: true,
+            },
+        )
+        .uniform(
+            "inverse_view",
+            GlMatrix {
+                data: inverse_view.raw_data(),
+                transpose: true,
+            },
+        );
+
+        if let Some(framebuffer) = self.scene_framebuffer() {
+            draw_builder.framebuffer(framebuffer);
+        }
+
+        draw_builder.draw();
+    }
+}
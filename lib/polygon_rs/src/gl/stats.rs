@@ -0,0 +1,112 @@
+//! GPU timing of render passes.
+//!
+//! Every pass executed for a camera is wrapped in a debug group named after the pass, so that
+//! captures in tools like RenderDoc and Nsight are grouped by pass, and is timed with a
+//! `TimeElapsed` query. Reading a query's result before the GPU has finished with it would stall
+//! the CPU, so the queries of up to `MAX_PENDING_FRAMES` frames are kept in flight and a frame's
+//! stats are collected once all of its queries are available.
+
+use stats::{FrameStats, PassStats};
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Duration;
+use super::gl_util::context::Context;
+use super::gl_util::query::{Query, QueryType};
+
+/// The largest number of frames whose timer queries may still be pending.
+///
+/// If the results of the oldest frame still aren't available after this many frames they're
+/// read anyway, blocking until the GPU catches up.
+pub const MAX_PENDING_FRAMES: usize = 3;
+
+/// The timer queries of the current frame and of the frames still waiting for their results.
+#[derive(Debug)]
+pub struct GpuTimers {
+    current: Vec<(String, Query)>,
+    pending: VecDeque<Vec<(String, Query)>>,
+
+    /// Queries whose results have been read, ready to be reused.
+    spare: Vec<Query>,
+}
+
+impl GpuTimers {
+    pub fn new() -> GpuTimers {
+        GpuTimers {
+            current: Vec::new(),
+            pending: VecDeque::new(),
+            spare: Vec::new(),
+        }
+    }
+
+    /// Returns an unused timer query, reusing one from an earlier frame if possible.
+    ///
+    /// The query should be passed back with `record()` once it has ended.
+    pub fn query(&mut self, context: &Context) -> Query {
+        self.spare.pop().unwrap_or_else(|| {
+            Query::new(context, QueryType::TimeElapsed).expect("Failed to create timer query")
+        })
+    }
+
+    /// Records the query that timed the pass named `name` in the current frame.
+    pub fn record(&mut self, name: String, query: Query) {
+        self.current.push((name, query));
+    }
+
+    /// Ends the current frame.
+    ///
+    /// Returns the stats of the most recent frame whose results became available, or `None` if
+    /// no frame finished.
+    pub fn end_frame(&mut self) -> Option<FrameStats> {
+        let current = mem::replace(&mut self.current, Vec::new());
+        self.pending.push_back(current);
+
+        let mut stats = None;
+        loop {
+            let is_ready = match self.pending.front() {
+                Some(frame) => {
+                    self.pending.len() > MAX_PENDING_FRAMES
+                        || frame.iter().all(|&(_, ref query)| query.is_result_available())
+                },
+                None => false,
+            };
+
+            if !is_ready {
+                break;
+            }
+
+            let frame = self.pending.pop_front().unwrap();
+            stats = Some(self.collect(frame));
+        }
+
+        stats
+    }
+
+    /// Frees all queries, including those still pending.
+    pub fn clear(&mut self) {
+        self.current.clear();
+        self.pending.clear();
+        self.spare.clear();
+    }
+
+    /// Reads the results of a frame's queries, summing the times of passes that were executed
+    /// for several cameras.
+    fn collect(&mut self, frame: Vec<(String, Query)>) -> FrameStats {
+        let mut passes: Vec<PassStats> = Vec::new();
+        for (name, query) in frame {
+            let nanos = query.result();
+            let gpu_time = Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32);
+
+            match passes.iter().position(|pass| pass.name == name) {
+                Some(index) => passes[index].gpu_time += gpu_time,
+                None => passes.push(PassStats {
+                    name: name,
+                    gpu_time: gpu_time,
+                }),
+            }
+
+            self.spare.push(query);
+        }
+
+        FrameStats { passes: passes }
+    }
+}
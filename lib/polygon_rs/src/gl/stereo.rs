@@ -0,0 +1,43 @@
+//! Stereo rendering, see `Camera::set_stereo()`.
+//!
+//! A stereo camera runs its passes once for each eye, each with its own camera and anchor. While
+//! the eyes are drawn the renderer holds a culling view enclosing both eyes, which is used instead
+//! of the eye's view when building the render queue and rasterizing occluders. This way both eyes
+//! draw exactly the same mesh instances and levels of detail, and the occluders and shadow maps
+//! are only prepared for the first eye.
+
+use anchor::Anchor;
+use camera::{Camera, Eye};
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::framebuffer::Rect;
+
+impl GlRender {
+    /// Draws the views of both eyes of the stereo camera `camera`.
+    pub(super) fn render_stereo(&mut self, camera: &Camera, camera_anchor: &Anchor, window_viewport: Rect) {
+        let _stopwatch = Stopwatch::new("Rendering stereo camera");
+
+        let (culling_camera, culling_offset) = camera.culling_camera().expect("Camera isn't a stereo camera");
+        let culling_anchor = camera_anchor.displaced(camera_anchor.world_orientation() * culling_offset);
+        self.culling_view = Some((culling_camera, culling_anchor));
+
+        for (index, &eye) in [Eye::Left, Eye::Right].iter().enumerate() {
+            let eye_camera = camera.eye_camera(eye);
+            let eye_anchor = camera_anchor.displaced(camera_anchor.world_orientation() * camera.eye_offset(eye));
+            self.render_view(&eye_camera, Some(&eye_anchor), window_viewport, index == 0);
+        }
+
+        self.culling_view = None;
+    }
+
+    /// Gets the view mesh instances are culled against while drawing from `camera`.
+    ///
+    /// This is the culling view shared by both eyes while drawing a stereo camera, and `camera`
+    /// itself otherwise.
+    pub(super) fn culling_view<'a>(&'a self, camera: &'a Camera, camera_anchor: &'a Anchor) -> (&'a Camera, &'a Anchor) {
+        match self.culling_view {
+            Some((ref culling_camera, ref culling_anchor)) => (culling_camera, culling_anchor),
+            None => (camera, camera_anchor),
+        }
+    }
+}
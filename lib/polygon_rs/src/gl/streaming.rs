@@ -0,0 +1,372 @@
+//! Background loading and budgeted uploading of streaming textures, see the `streaming` module.
+//!
+//! A single worker thread is started when the first streaming texture is registered. It loads
+//! each requested container and sends back its description followed by its mip levels, smallest
+//! first. At the start of each frame the renderer allocates storage for newly described textures
+//! and uploads waiting levels until the upload budget is used up. If buffer storage is supported
+//! the data is copied into a persistently mapped pixel unpack buffer first, so the copy into the
+//! texture happens on the GPU without stalling the CPU. A texture's base level is lowered each
+//! time one of its levels has been uploaded completely.
+
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::gl_util::buffer::{BufferTarget, StreamingBuffer};
+use super::gl_util::container::{ContainerFormat, Error as ContainerError, MipLevel, TextureContainer};
+use super::gl_util::texture::Texture2d as GlTexture2d;
+use streaming::StreamingError;
+use texture::GpuTexture;
+
+/// The worker thread and the state of every texture that's still streaming.
+#[derive(Debug)]
+pub struct TextureStreamer {
+    worker: Option<Worker>,
+    textures: HashMap<GpuTexture, StreamingTexture>,
+
+    /// The pixel unpack buffer levels are uploaded through, created on demand with a region as
+    /// large as the upload budget.
+    upload_buffer: Option<StreamingBuffer<u8>>,
+
+    /// Textures that failed to load, waiting to be returned by `take_errors()`.
+    errors: Vec<(GpuTexture, StreamingError)>,
+}
+
+impl TextureStreamer {
+    pub fn new() -> TextureStreamer {
+        TextureStreamer {
+            worker: None,
+            textures: HashMap::new(),
+            upload_buffer: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Queues the container at `path` to be loaded for `texture_id`.
+    pub fn request(&mut self, texture_id: GpuTexture, path: PathBuf) {
+        if self.worker.is_none() {
+            self.worker = Some(Worker::spawn());
+        }
+
+        let worker = self.worker.as_ref().unwrap();
+        worker.requests.send((texture_id, path)).expect("Texture streaming thread has stopped");
+    }
+
+    /// Drops the upload buffer so that it's recreated with the current budget.
+    pub fn reset_upload_buffer(&mut self) {
+        self.upload_buffer = None;
+    }
+
+    /// Returns the textures that failed to load since the last call.
+    pub fn take_errors(&mut self) -> Vec<(GpuTexture, StreamingError)> {
+        mem::replace(&mut self.errors, Vec::new())
+    }
+}
+
+/// The worker thread loading containers, along with the channels used to talk to it.
+///
+/// Dropping the worker closes the request channel, which stops the thread once it's finished
+/// with the container it's currently loading.
+#[derive(Debug)]
+struct Worker {
+    requests: Sender<(GpuTexture, PathBuf)>,
+    events: Receiver<(GpuTexture, StreamEvent)>,
+    _thread: JoinHandle<()>,
+}
+
+impl Worker {
+    fn spawn() -> Worker {
+        let (request_sender, request_receiver) = mpsc::channel::<(GpuTexture, PathBuf)>();
+        let (event_sender, event_receiver) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name("Texture streaming".into())
+            .spawn(move || {
+                for (texture_id, path) in request_receiver.iter() {
+                    for event in load_container(path) {
+                        // The renderer has been dropped, so there's no one left to load for.
+                        if event_sender.send((texture_id, event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+            .expect("Failed to start texture streaming thread");
+
+        Worker {
+            requests: request_sender,
+            events: event_receiver,
+            _thread: thread,
+        }
+    }
+}
+
+/// The messages sent from the worker thread for each streaming texture.
+#[derive(Debug)]
+enum StreamEvent {
+    /// The container was loaded, and its levels follow.
+    Loaded {
+        format: ContainerFormat,
+        width: usize,
+        height: usize,
+        level_count: usize,
+    },
+
+    /// One of the container's levels.
+    Level(usize, MipLevel),
+
+    /// The container couldn't be loaded, so the texture will stay black.
+    Failed(ContainerError),
+}
+
+/// Loads the container at `path`, returning the events describing it.
+fn load_container(path: PathBuf) -> Vec<StreamEvent> {
+    let container = match TextureContainer::load(&path) {
+        Ok(container) => container,
+        Err(error) => return vec![StreamEvent::Failed(error)],
+    };
+
+    if container.is_cube_map() {
+        return vec![StreamEvent::Failed(ContainerError::Unsupported("Streaming cube maps"))];
+    }
+
+    let mut events = vec![StreamEvent::Loaded {
+        format: container.format(),
+        width: container.width(),
+        height: container.height(),
+        level_count: container.levels().len(),
+    }];
+
+    let levels = container.into_levels();
+    events.extend(levels.into_iter().enumerate().rev().map(|(index, level)| StreamEvent::Level(index, level)));
+    events
+}
+
+/// A texture whose levels haven't all been uploaded yet.
+#[derive(Debug)]
+struct StreamingTexture {
+    format: ContainerFormat,
+
+    /// The number of levels that haven't been uploaded completely, including those that haven't
+    /// been received yet.
+    remaining_levels: usize,
+
+    /// Levels that have been received but not completely uploaded, smallest first.
+    pending: VecDeque<PendingLevel>,
+}
+
+#[derive(Debug)]
+struct PendingLevel {
+    index: usize,
+    level: MipLevel,
+
+    /// The number of rows of the level that have already been uploaded.
+    uploaded_rows: usize,
+}
+
+impl GlRender {
+    /// Receives levels from the worker thread and uploads as many as the budget allows.
+    pub(super) fn update_streaming(&mut self) {
+        let _stopwatch = Stopwatch::new("Texture streaming");
+
+        self.receive_streamed_levels();
+        self.upload_streamed_levels();
+    }
+
+    fn receive_streamed_levels(&mut self) {
+        loop {
+            let (texture_id, event) = match self.texture_streamer.worker {
+                Some(ref worker) => match worker.events.try_recv() {
+                    Ok(message) => message,
+                    Err(_) => return,
+                },
+                None => return,
+            };
+
+            match event {
+                StreamEvent::Loaded { format, width, height, level_count } => {
+                    match GlTexture2d::allocate_levels(&self.context, format, width, height, level_count) {
+                        Ok(texture) => {
+                            self.textures.insert(texture_id, texture);
+                            self.texture_streamer.textures.insert(texture_id, StreamingTexture {
+                                format: format,
+                                remaining_levels: level_count,
+                                pending: VecDeque::new(),
+                            });
+                        },
+                        Err(error) => {
+                            let error = StreamingError::Allocation(format!("{:?}", error));
+                            self.texture_streamer.errors.push((texture_id, error));
+                        },
+                    }
+                },
+
+                StreamEvent::Level(index, level) => {
+                    if let Some(texture) = self.texture_streamer.textures.get_mut(&texture_id) {
+                        texture.pending.push_back(PendingLevel {
+                            index: index,
+                            level: level,
+                            uploaded_rows: 0,
+                        });
+                    }
+                },
+
+                StreamEvent::Failed(error) => {
+                    let error = StreamingError::Load(error.to_string());
+                    self.texture_streamer.errors.push((texture_id, error));
+                },
+            }
+        }
+    }
+
+    fn upload_streamed_levels(&mut self) {
+        if self.texture_streamer.textures.is_empty() {
+            return;
+        }
+
+        let budget = self.streaming_settings.upload_budget;
+
+        // Buffer storage is needed for the persistently mapped upload buffer, without it the
+        // data is uploaded straight from memory.
+        let buffer_storage = {
+            let capabilities = self.context.capabilities();
+            capabilities.version >= (4, 4) || capabilities.extensions.supports("GL_ARB_buffer_storage")
+        };
+        if self.texture_streamer.upload_buffer.is_none() && buffer_storage {
+            self.texture_streamer.upload_buffer = StreamingBuffer::new(&self.context, BufferTarget::PixelUnpack, budget).ok();
+        }
+
+        let TextureStreamer { ref mut textures, ref mut upload_buffer, .. } = self.texture_streamer;
+
+        // Pick the bands of rows to upload this frame, starting with the smallest levels.
+        let mut uploads = Vec::new();
+        let mut uploaded = 0;
+        'textures: for (&texture_id, streaming_texture) in textures.iter_mut() {
+            let format = streaming_texture.format;
+
+            // Compressed levels are uploaded in bands of whole 4x4 blocks.
+            let band_height = match format {
+                ContainerFormat::Compressed(_) => 4,
+                ContainerFormat::Uncompressed { .. } => 1,
+            };
+
+            for (position, pending) in streaming_texture.pending.iter_mut().enumerate() {
+                let width = pending.level.width;
+                let height = pending.level.height;
+                let band_size = format.image_size(width, band_height);
+
+                // Always make some progress, even if a single band is larger than the budget.
+                let remaining_budget = budget.saturating_sub(uploaded);
+                let max_bands = if uploaded == 0 {
+                    cmp::max(remaining_budget / band_size, 1)
+                } else {
+                    remaining_budget / band_size
+                };
+                if max_bands == 0 {
+                    break 'textures;
+                }
+
+                let y = pending.uploaded_rows;
+                let rows = cmp::min(max_bands * band_height, height - y);
+                let upload = Upload {
+                    texture_id: texture_id,
+                    position: position,
+                    y: y,
+                    rows: rows,
+                    start: format.image_size(width, y),
+                    len: format.image_size(width, rows),
+                    buffer_offset: uploaded,
+                };
+
+                uploaded += upload.len;
+                pending.uploaded_rows += rows;
+                uploads.push(upload);
+
+                if pending.uploaded_rows < height {
+                    break 'textures;
+                }
+            }
+        }
+
+        // Copy the bands that fit into the upload buffer's next region.
+        let region_len = upload_buffer.as_ref().map(|buffer| buffer.region_len()).unwrap_or(0);
+        if let Some(ref mut buffer) = *upload_buffer {
+            let region = buffer.next_region();
+            for upload in uploads.iter().filter(|upload| upload.buffer_offset + upload.len <= region_len) {
+                let data = upload.data(textures);
+                region[upload.buffer_offset..upload.buffer_offset + upload.len].copy_from_slice(data);
+            }
+        }
+
+        for upload in &uploads {
+            let streaming_texture = &textures[&upload.texture_id];
+            let pending = &streaming_texture.pending[upload.position];
+            let format = streaming_texture.format;
+            let width = pending.level.width;
+
+            let gl_texture = self.textures.get_mut(&upload.texture_id).expect("Streaming texture does not exist");
+            match *upload_buffer {
+                Some(ref buffer) if upload.buffer_offset + upload.len <= region_len => {
+                    gl_texture.set_level_rows_from_buffer(
+                        pending.index,
+                        format,
+                        width,
+                        upload.y,
+                        upload.rows,
+                        buffer,
+                        buffer.region_offset() + upload.buffer_offset,
+                        upload.len);
+                },
+                _ => gl_texture.set_level_rows(pending.index, format, width, upload.y, upload.rows, upload.data(textures)),
+            }
+
+            // Once a level is complete it can be sampled.
+            if upload.y + upload.rows == pending.level.height {
+                gl_texture.set_base_level(pending.index);
+            }
+        }
+
+        if let Some(ref mut buffer) = *upload_buffer {
+            buffer.fence_region();
+        }
+
+        // Forget the completed levels, and the textures that are now complete.
+        for streaming_texture in textures.values_mut() {
+            while streaming_texture.pending.front().map(|pending| pending.uploaded_rows == pending.level.height).unwrap_or(false) {
+                streaming_texture.pending.pop_front();
+                streaming_texture.remaining_levels -= 1;
+            }
+        }
+        textures.retain(|_, streaming_texture| streaming_texture.remaining_levels > 0);
+    }
+}
+
+/// A band of rows of a pending level uploaded this frame.
+#[derive(Debug)]
+struct Upload {
+    texture_id: GpuTexture,
+
+    /// The position of the level in the texture's pending levels.
+    position: usize,
+
+    y: usize,
+    rows: usize,
+
+    /// The range of the band's data in the level.
+    start: usize,
+    len: usize,
+
+    /// Where the band goes in the upload buffer's region, if it fits.
+    buffer_offset: usize,
+}
+
+impl Upload {
+    fn data<'a>(&self, textures: &'a HashMap<GpuTexture, StreamingTexture>) -> &'a [u8] {
+        let pending = &textures[&self.texture_id].pending[self.position];
+        &pending.level.faces[0][self.start..self.start + self.len]
+    }
+}
@@ -0,0 +1,325 @@
+//! Uniform buffers for the built-in uniforms, see `BUILT_IN_UNIFORMS`.
+//!
+//! Instead of setting every built-in uniform with its own call for each draw, the built-in
+//! uniforms that aren't samplers are grouped into two `std140` uniform blocks:
+//!
+//! - `FrameUniforms` holds the values shared by every draw in a pass: the camera's transforms
+//!   and position, the ambient light, the current light batch, the shadow parameters, and the
+//!   time. It's uploaded once for each light pass of each camera.
+//! - `ObjectUniforms` holds the model transforms of a single draw along with the mesh's vertex
+//!   color flag, and is uploaded once per draw.
+//!
+//! The blocks are declared `row_major` so that matrices can be uploaded in the same order they're
+//! stored in. Every program including `BUILT_IN_UNIFORMS` must have its blocks assigned to
+//! `FRAME_BINDING` and `OBJECT_BINDING` with `bind_uniform_blocks()`. Custom programs don't
+//! declare the blocks, so they're given the built-in uniforms they consume individually by
+//! `apply_custom_program_uniforms()`.
+
+use anchor::Anchor;
+use camera::Camera;
+use material::EngineUniforms;
+use math::*;
+use shadow::MAX_CASCADES;
+use std::mem;
+use stopwatch::Stopwatch;
+use super::GlRender;
+use super::forward::LightBatch;
+use super::gl_util::{DrawBuilder, GlMatrix};
+use super::gl_util::shader::Program;
+use super::shadow::SHADOW_TRANSFORM_NAMES;
+
+/// The uniform buffer binding point of the `FrameUniforms` block.
+pub const FRAME_BINDING: u32 = 0;
+
+/// The uniform buffer binding point of the `ObjectUniforms` block.
+pub const OBJECT_BINDING: u32 = 1;
+
+/// Assigns the binding points of the uniform blocks declared by `BUILT_IN_UNIFORMS`.
+///
+/// Programs that don't use one of the blocks are left as is.
+pub fn bind_uniform_blocks(program: &Program) {
+    program.bind_uniform_block("FrameUniforms", FRAME_BINDING);
+    program.bind_uniform_block("ObjectUniforms", OBJECT_BINDING);
+}
+
+/// The per-draw values of the `ObjectUniforms` block.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectUniforms {
+    pub model_transform: Matrix4,
+    pub normal_transform: Matrix3,
+    pub view_normal_transform: Matrix3,
+    pub model_view_transform: Matrix4,
+    pub model_view_projection: Matrix4,
+    pub has_vertex_color: bool,
+}
+
+impl ObjectUniforms {
+    fn write(&self, block: &mut Std140) {
+        block.mat4(self.model_transform);
+        block.mat3(self.normal_transform);
+        block.mat3(self.view_normal_transform);
+        block.mat4(self.model_view_transform);
+        block.mat4(self.model_view_projection);
+        block.i32(self.has_vertex_color as i32);
+    }
+}
+
+impl GlRender {
+    /// Uploads the `FrameUniforms` block for a pass drawing the lights in `light_batch` as seen
+    /// by `camera`.
+    ///
+    /// The ambient light is only added by the first pass, so it's black if `additive` is `true`.
+    pub(super) fn upload_frame_uniforms(
+        &self,
+        camera: &Camera,
+        camera_anchor: &Anchor,
+        light_batch: &LightBatch,
+        additive: bool,
+    ) {
+        let _stopwatch = Stopwatch::new("Frame uniforms");
+
+        let ambient_color = if additive { Color::rgb(0.0, 0.0, 0.0) } else { self.ambient_color };
+
+        let mut block = Std140::new();
+        block.mat4(camera_anchor.view_matrix());
+        block.mat4(camera.projection_matrix());
+        block.vec4(*camera_anchor.world_position().as_array());
+        block.vec4(ambient_color.into());
+        block.f32(self.time);
+
+        block.i32_array(&light_batch.light_type);
+        block.vec4_array(light_batch.light_position.iter().map(|point| *point.as_array()));
+        block.vec4_array(light_batch.light_position_view.iter().map(|point| *point.as_array()));
+        block.f32_array(&light_batch.light_strength);
+        block.vec4_array(light_batch.light_color.iter().map(|&color| color.into()));
+        block.f32_array(&light_batch.light_radius);
+        block.vec3_array(light_batch.light_direction.iter().map(|&direction| direction.into()));
+        block.vec3_array(light_batch.light_direction_view.iter().map(|&direction| direction.into()));
+        block.f32_array(&light_batch.light_cos_inner);
+        block.f32_array(&light_batch.light_cos_outer);
+
+        block.i32(light_batch.shadow_light_index);
+        block.i32(self.shadow_transforms.len() as i32);
+        block.f32(self.shadow_settings.bias);
+        for index in 0..MAX_CASCADES {
+            block.mat4(self.shadow_transforms.get(index).cloned().unwrap_or(Matrix4::identity()));
+        }
+        block.i32_array(&light_batch.light_shadow_map);
+
+        self.frame_uniforms.set_data(block.finish());
+        self.frame_uniforms.bind(FRAME_BINDING);
+    }
+
+    /// Sets the built-in uniforms consumed by a custom program one by one.
+    ///
+    /// The same values as in the uniform blocks are used, except that the ambient light is set
+    /// here instead of being uploaded with the frame.
+    pub(super) fn apply_custom_program_uniforms<'a>(
+        &'a self,
+        draw_builder: &mut DrawBuilder<'a>,
+        engine_uniforms: EngineUniforms,
+        object: &'a ObjectUniforms,
+        view_transform: &'a Matrix4,
+        projection_transform: &'a Matrix4,
+        camera_anchor: &Anchor,
+        light_batch: &'a LightBatch,
+        additive: bool,
+    ) {
+        let _stopwatch = Stopwatch::new("Custom program uniforms");
+
+        if engine_uniforms.transforms {
+            draw_builder
+            .uniform(
+                "model_transform",
+                GlMatrix {
+                    data: object.model_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "normal_transform",
+                GlMatrix {
+                    data: object.normal_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "view_normal_transform",
+                GlMatrix {
+                    data: object.view_normal_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "view_transform",
+                GlMatrix {
+                    data: view_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "model_view_transform",
+                GlMatrix {
+                    data: object.model_view_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "projection_transform",
+                GlMatrix {
+                    data: projection_transform.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform(
+                "model_view_projection",
+                GlMatrix {
+                    data: object.model_view_projection.raw_data(),
+                    transpose: true,
+                },
+            )
+            .uniform("camera_position", *camera_anchor.world_position().as_array());
+        }
+
+        if engine_uniforms.lights {
+            // The ambient term is only added once, by the first pass.
+            let ambient_color = if additive { Color::rgb(0.0, 0.0, 0.0) } else { self.ambient_color };
+            draw_builder.uniform::<[f32; 4]>("global_ambient", ambient_color.into());
+
+            draw_builder
+            .uniform("light_type", &light_batch.light_type[..])
+            .uniform("light_strength", &light_batch.light_strength[..])
+            .uniform("light_color", Color::as_slice_of_arrays(&light_batch.light_color))
+            .uniform("light_position", Point::as_slice_of_arrays(&light_batch.light_position))
+            .uniform("light_position_view", Point::as_slice_of_arrays(&light_batch.light_position_view))
+            .uniform("light_radius", &light_batch.light_radius[..])
+            .uniform("light_direction", Vector3::as_slice_of_arrays(&light_batch.light_direction))
+            .uniform("light_direction_view", Vector3::as_slice_of_arrays(&light_batch.light_direction_view))
+            .uniform("light_cos_inner", &light_batch.light_cos_inner[..])
+            .uniform("light_cos_outer", &light_batch.light_cos_outer[..]);
+
+            draw_builder
+            .uniform("shadow_light_index", light_batch.shadow_light_index)
+            .uniform("shadow_cascade_count", self.shadow_transforms.len() as i32)
+            .uniform("shadow_bias", self.shadow_settings.bias)
+            .uniform("light_shadow_map", &light_batch.light_shadow_map[..]);
+
+            for (index, shadow_transform) in self.shadow_transforms.iter().enumerate() {
+                draw_builder.uniform(
+                    SHADOW_TRANSFORM_NAMES[index],
+                    GlMatrix {
+                        data: shadow_transform.raw_data(),
+                        transpose: true,
+                    },
+                );
+            }
+        }
+
+        if engine_uniforms.time {
+            draw_builder.uniform("time", self.time);
+        }
+    }
+
+    /// Uploads the `ObjectUniforms` block for a single draw.
+    pub(super) fn upload_object_uniforms(&self, uniforms: &ObjectUniforms) {
+        let _stopwatch = Stopwatch::new("Object uniforms");
+
+        let mut block = Std140::new();
+        uniforms.write(&mut block);
+
+        self.object_uniforms.set_data(block.finish());
+        self.object_uniforms.bind(OBJECT_BINDING);
+    }
+}
+
+/// Packs values into a buffer following the `std140` layout rules.
+///
+/// Values are stored as 4-byte words. Vectors of 3 or 4 components, matrix rows, and array
+/// elements are aligned to 16 bytes, and so is the end of every array.
+#[derive(Debug, Default)]
+struct Std140 {
+    words: Vec<u32>,
+}
+
+impl Std140 {
+    fn new() -> Std140 {
+        Std140::default()
+    }
+
+    fn align(&mut self) {
+        while self.words.len() % 4 != 0 {
+            self.words.push(0);
+        }
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.words.push(unsafe { mem::transmute::<f32, u32>(value) });
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.words.push(value as u32);
+    }
+
+    fn vec3(&mut self, value: [f32; 3]) {
+        self.align();
+        for &component in &value {
+            self.f32(component);
+        }
+    }
+
+    fn vec4(&mut self, value: [f32; 4]) {
+        self.align();
+        for &component in &value {
+            self.f32(component);
+        }
+    }
+
+    fn mat3(&mut self, value: Matrix3) {
+        for row in value.raw_data().chunks(3) {
+            self.vec3([row[0], row[1], row[2]]);
+        }
+        self.align();
+    }
+
+    fn mat4(&mut self, value: Matrix4) {
+        self.align();
+        for &component in value.raw_data().iter() {
+            self.f32(component);
+        }
+    }
+
+    fn f32_array(&mut self, values: &[f32]) {
+        for &value in values {
+            self.align();
+            self.f32(value);
+        }
+        self.align();
+    }
+
+    fn i32_array(&mut self, values: &[i32]) {
+        for &value in values {
+            self.align();
+            self.i32(value);
+        }
+        self.align();
+    }
+
+    fn vec3_array<I: Iterator<Item = [f32; 3]>>(&mut self, values: I) {
+        for value in values {
+            self.vec3(value);
+        }
+        self.align();
+    }
+
+    fn vec4_array<I: Iterator<Item = [f32; 4]>>(&mut self, values: I) {
+        for value in values {
+            self.vec4(value);
+        }
+    }
+
+    /// Pads the buffer to a multiple of 16 bytes and returns it.
+    fn finish(&mut self) -> &[u32] {
+        self.align();
+        &self.words
+    }
+}
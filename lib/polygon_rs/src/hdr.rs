@@ -0,0 +1,63 @@
+//! High dynamic range rendering configuration.
+//!
+//! With HDR enabled the scene is rendered into a floating point target so that lit surfaces can
+//! be brighter than 1.0 without clipping. The built-in `"post"` pass then scales the image by
+//! the exposure and maps it into the displayable range with the selected tone mapping operator.
+//! See `Renderer::set_hdr_settings()`.
+
+/// The operators used to map HDR colors into the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToneMapping {
+    /// `color / (1 + color)`, applied per channel. Simple and never clips, but desaturates
+    /// bright colors.
+    Reinhard,
+
+    /// A fit of the ACES filmic curve, with more contrast and better preserved highlights.
+    Aces,
+}
+
+/// Settings for HDR rendering and tone mapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrSettings {
+    /// Whether the scene is rendered into an HDR target and tone mapped.
+    ///
+    /// When disabled the scene is rendered directly to the window and colors above 1.0 clip.
+    pub enabled: bool,
+
+    /// The operator used to map the exposed image into the displayable range.
+    pub tone_mapping: ToneMapping,
+
+    /// The scale applied to the scene's colors before tone mapping.
+    ///
+    /// With auto-exposure enabled this acts as exposure compensation on top of the automatic
+    /// exposure.
+    pub exposure: f32,
+
+    /// Whether the exposure is adjusted automatically based on the scene's average luminance.
+    pub auto_exposure: bool,
+
+    /// The luminance that the scene's average luminance is mapped to by auto-exposure.
+    pub auto_exposure_key: f32,
+
+    /// The smallest exposure chosen by auto-exposure, limiting how far very bright scenes are
+    /// darkened.
+    pub min_exposure: f32,
+
+    /// The largest exposure chosen by auto-exposure, limiting how far very dark scenes are
+    /// brightened.
+    pub max_exposure: f32,
+}
+
+impl Default for HdrSettings {
+    fn default() -> HdrSettings {
+        HdrSettings {
+            enabled: false,
+            tone_mapping: ToneMapping::Reinhard,
+            exposure: 1.0,
+            auto_exposure: false,
+            auto_exposure_key: 0.18,
+            min_exposure: 0.1,
+            max_exposure: 10.0,
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Decoding of image files into `Image`s.
+//!
+//! Each decoder is behind a cargo feature of the same name, and both are enabled by default:
+//!
+//! - `png`: PNG images with 8 bits per channel, in grayscale, grayscale with alpha, RGB, or RGBA.
+//! - `tga`: Truecolor and grayscale TGA images, with or without run-length encoding.
+//!
+//! Decoded images are always RGBA with 8 bits per channel, stored from the top-left corner. To
+//! draw an image, convert it with `Texture2d::from_image()` and register the texture with the
+//! renderer:
+//!
+//! ```ignore
+//! let image = Image::load("textures/crate.png")?;
+//! let texture = renderer.register_texture(&Texture2d::from_image(&image));
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use texture::Image;
+
+#[cfg(feature = "png")]
+use png;
+
+/// The signature at the start of every PNG file.
+const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+impl Image {
+    /// Loads and decodes an image file from disk, see `decode()`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Image, ImageError> {
+        let path = path.as_ref();
+
+        let mut file = File::open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        // TGA files have no signature, so they can only be recognized by their extension.
+        let is_tga = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase() == "tga")
+            .unwrap_or(false);
+        if is_tga {
+            Image::from_tga(&bytes)
+        } else {
+            Image::decode(&bytes)
+        }
+    }
+
+    /// Decodes an image file, detecting its format from its contents.
+    ///
+    /// Data without a recognized signature is decoded as TGA, since TGA files don't have one.
+    pub fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+        if bytes.starts_with(&PNG_SIGNATURE) {
+            Image::from_png(bytes)
+        } else {
+            Image::from_tga(bytes)
+        }
+    }
+
+    /// Decodes a PNG file.
+    ///
+    /// Requires the `png` feature.
+    #[cfg(feature = "png")]
+    pub fn from_png(bytes: &[u8]) -> Result<Image, ImageError> {
+        let decoder = png::Decoder::new(bytes);
+        let (info, mut reader) = decoder.read_info()?;
+
+        if info.bit_depth != png::BitDepth::Eight {
+            return Err(ImageError::Unsupported("PNG bit depths other than 8"));
+        }
+
+        if info.width == 0 || info.height == 0 {
+            return Err(ImageError::Malformed("Image has no size"));
+        }
+
+        let mut buffer = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buffer)?;
+
+        let data = match info.color_type {
+            png::ColorType::RGBA => buffer,
+            png::ColorType::RGB => to_rgba(&buffer, 3, |rgb| [rgb[0], rgb[1], rgb[2], 255]),
+            png::ColorType::GrayscaleAlpha => to_rgba(&buffer, 2, |gray| [gray[0], gray[0], gray[0], gray[1]]),
+            png::ColorType::Grayscale => to_rgba(&buffer, 1, |gray| [gray[0], gray[0], gray[0], 255]),
+            png::ColorType::Indexed => return Err(ImageError::Unsupported("Indexed PNG images")),
+        };
+
+        let (width, height) = (info.width as usize, info.height as usize);
+        if data.len() != width * height * 4 {
+            return Err(ImageError::Malformed("PNG data doesn't match the image size"));
+        }
+
+        Ok(Image::new(width, height, data))
+    }
+
+    /// Decodes a PNG file.
+    ///
+    /// Always fails with `ImageError::DisabledFormat`, since the `png` feature is disabled.
+    #[cfg(not(feature = "png"))]
+    pub fn from_png(_bytes: &[u8]) -> Result<Image, ImageError> {
+        Err(ImageError::DisabledFormat("png"))
+    }
+
+    /// Decodes a TGA file.
+    ///
+    /// Requires the `tga` feature. Color-mapped images aren't supported.
+    #[cfg(feature = "tga")]
+    pub fn from_tga(bytes: &[u8]) -> Result<Image, ImageError> {
+        const HEADER_SIZE: usize = 18;
+
+        if bytes.len() < HEADER_SIZE {
+            return Err(ImageError::UnexpectedEnd);
+        }
+
+        let read_u16 = |offset: usize| bytes[offset] as usize | (bytes[offset + 1] as usize) << 8;
+
+        let id_length = bytes[0] as usize;
+        let color_map_type = bytes[1];
+        let image_type = bytes[2];
+        let color_map_length = read_u16(5);
+        let color_map_entry_bits = bytes[7] as usize;
+        let width = read_u16(12);
+        let height = read_u16(14);
+        let pixel_depth = bytes[16];
+        let descriptor = bytes[17];
+
+        if width == 0 || height == 0 {
+            return Err(ImageError::Malformed("Image has no size"));
+        }
+
+        let (run_length_encoded, grayscale) = match image_type {
+            2 => (false, false),
+            3 => (false, true),
+            10 => (true, false),
+            11 => (true, true),
+            1 | 9 => return Err(ImageError::Unsupported("Color-mapped TGA images")),
+            _ => return Err(ImageError::Malformed("Invalid TGA image type")),
+        };
+
+        let bytes_per_pixel = match (grayscale, pixel_depth) {
+            (true, 8) => 1,
+            (false, 24) => 3,
+            (false, 32) => 4,
+            _ => return Err(ImageError::Unsupported("TGA pixel depth")),
+        };
+
+        // Skip the image ID and a color map that isn't used by truecolor images.
+        let color_map_size = if color_map_type == 1 { color_map_length * ((color_map_entry_bits + 7) / 8) } else { 0 };
+        let mut source = &bytes[HEADER_SIZE..];
+        if source.len() < id_length + color_map_size {
+            return Err(ImageError::UnexpectedEnd);
+        }
+        source = &source[id_length + color_map_size..];
+
+        // Read the pixels in the order they're stored, converting them to RGBA.
+        let pixels = width * height;
+        let mut data = Vec::with_capacity(pixels * 4);
+        {
+            let mut push_pixel = |pixel: &[u8]| {
+                let rgba = match bytes_per_pixel {
+                    1 => [pixel[0], pixel[0], pixel[0], 255],
+                    3 => [pixel[2], pixel[1], pixel[0], 255],
+                    _ => [pixel[2], pixel[1], pixel[0], pixel[3]],
+                };
+                data.extend_from_slice(&rgba);
+            };
+
+            let mut remaining = pixels;
+            while remaining > 0 {
+                // Run-length encoded images are made up of packets, each either repeating a
+                // single pixel or holding a run of raw pixels.
+                let (count, repeated) = if run_length_encoded {
+                    let (&packet, rest) = source.split_first().ok_or(ImageError::UnexpectedEnd)?;
+                    source = rest;
+                    ((packet & 0x7F) as usize + 1, packet & 0x80 != 0)
+                } else {
+                    (remaining, false)
+                };
+
+                if count > remaining {
+                    return Err(ImageError::Malformed("TGA packet overflows the image"));
+                }
+
+                let packet_size = if repeated { bytes_per_pixel } else { count * bytes_per_pixel };
+                if source.len() < packet_size {
+                    return Err(ImageError::UnexpectedEnd);
+                }
+
+                if repeated {
+                    for _ in 0..count {
+                        push_pixel(&source[..bytes_per_pixel]);
+                    }
+                } else {
+                    for pixel in source[..packet_size].chunks(bytes_per_pixel) {
+                        push_pixel(pixel);
+                    }
+                }
+
+                source = &source[packet_size..];
+                remaining -= count;
+            }
+        }
+
+        if data.len() != pixels * 4 {
+            return Err(ImageError::Malformed("TGA data doesn't match the image size"));
+        }
+
+        // Rows are stored starting from the bottom unless the descriptor says otherwise, and
+        // pixels are stored from the left unless the descriptor says otherwise.
+        let row_len = width * 4;
+        if descriptor & 0x10 != 0 {
+            for row in data.chunks_mut(row_len) {
+                for column in 0..width / 2 {
+                    for channel in 0..4 {
+                        row.swap(column * 4 + channel, (width - 1 - column) * 4 + channel);
+                    }
+                }
+            }
+        }
+        if descriptor & 0x20 == 0 {
+            return Ok(Image::from_bottom_up(width, height, data));
+        }
+
+        Ok(Image::new(width, height, data))
+    }
+
+    /// Decodes a TGA file.
+    ///
+    /// Always fails with `ImageError::DisabledFormat`, since the `tga` feature is disabled.
+    #[cfg(not(feature = "tga"))]
+    pub fn from_tga(_bytes: &[u8]) -> Result<Image, ImageError> {
+        Err(ImageError::DisabledFormat("tga"))
+    }
+}
+
+/// Converts pixels with `channels` channels each into RGBA pixels.
+#[cfg(feature = "png")]
+fn to_rgba<F: Fn(&[u8]) -> [u8; 4]>(source: &[u8], channels: usize, convert: F) -> Vec<u8> {
+    let mut data = Vec::with_capacity(source.len() / channels * 4);
+    for pixel in source.chunks(channels) {
+        data.extend_from_slice(&convert(pixel));
+    }
+    data
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    IoError(io::Error),
+
+    /// The image's format is supported, but its decoder's feature is disabled.
+    DisabledFormat(&'static str),
+
+    /// The data ended before the whole image was read.
+    UnexpectedEnd,
+
+    /// The data isn't a valid image.
+    Malformed(&'static str),
+
+    /// The image uses a feature of its format that isn't supported.
+    Unsupported(&'static str),
+
+    /// The PNG decoder failed.
+    #[cfg(feature = "png")]
+    Png(png::DecodingError),
+}
+
+impl From<io::Error> for ImageError {
+    fn from(from: io::Error) -> ImageError {
+        ImageError::IoError(from)
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<png::DecodingError> for ImageError {
+    fn from(from: png::DecodingError) -> ImageError {
+        ImageError::Png(from)
+    }
+}
+
+impl Display for ImageError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            ImageError::IoError(ref error) => write!(formatter, "Failed to read image: {}", error),
+            ImageError::DisabledFormat(feature) => {
+                write!(formatter, "Image decoder is disabled, enable the \"{}\" feature", feature)
+            },
+            ImageError::UnexpectedEnd => write!(formatter, "Image data ended unexpectedly"),
+            ImageError::Malformed(reason) => write!(formatter, "Malformed image: {}", reason),
+            ImageError::Unsupported(feature) => write!(formatter, "Unsupported image feature: {}", feature),
+            #[cfg(feature = "png")]
+            ImageError::Png(ref error) => write!(formatter, "Failed to decode PNG image: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use texture::Texture2d;
+
+    /// Builds the header of a 24-bit truecolor TGA image.
+    #[cfg(feature = "tga")]
+    fn tga_header(image_type: u8, width: u16, height: u16, descriptor: u8) -> Vec<u8> {
+        vec![
+            0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            width as u8, (width >> 8) as u8, height as u8, (height >> 8) as u8, 24, descriptor,
+        ]
+    }
+
+    #[cfg(feature = "tga")]
+    #[test]
+    fn tga_bottom_up() {
+        let mut bytes = tga_header(2, 2, 2, 0);
+        bytes.extend(&[
+            1, 2, 3, 4, 5, 6,
+            7, 8, 9, 10, 11, 12,
+        ]);
+
+        let image = Image::from_tga(&bytes).unwrap();
+        assert_eq!((2, 2), (image.width(), image.height()));
+        assert_eq!((9, 8, 7, 255), image.pixel(0, 0));
+        assert_eq!((12, 11, 10, 255), image.pixel(1, 0));
+        assert_eq!((3, 2, 1, 255), image.pixel(0, 1));
+    }
+
+    #[cfg(feature = "tga")]
+    #[test]
+    fn tga_run_length_encoded() {
+        let mut bytes = tga_header(10, 3, 1, 0x20);
+        bytes.extend(&[0x81, 1, 2, 3, 0x00, 4, 5, 6]);
+
+        let image = Image::from_tga(&bytes).unwrap();
+        assert_eq!((3, 2, 1, 255), image.pixel(0, 0));
+        assert_eq!((3, 2, 1, 255), image.pixel(1, 0));
+        assert_eq!((6, 5, 4, 255), image.pixel(2, 0));
+    }
+
+    #[cfg(feature = "tga")]
+    #[test]
+    fn tga_zero_size() {
+        for &(width, height) in &[(0, 2), (2, 0), (0, 0)] {
+            match Image::from_tga(&tga_header(2, width, height, 0)) {
+                Err(ImageError::Malformed(_)) => {},
+                result => panic!("Expected Malformed for {}x{}, got {:?}", width, height, result),
+            }
+        }
+    }
+
+    #[cfg(feature = "tga")]
+    #[test]
+    fn tga_truncated() {
+        let mut bytes = tga_header(2, 2, 2, 0);
+        bytes.extend(&[1, 2, 3, 4, 5, 6]);
+
+        match Image::from_tga(&bytes) {
+            Err(ImageError::UnexpectedEnd) => {},
+            result => panic!("Expected UnexpectedEnd, got {:?}", result),
+        }
+
+        match Image::from_tga(&bytes[..10]) {
+            Err(ImageError::UnexpectedEnd) => {},
+            result => panic!("Expected UnexpectedEnd, got {:?}", result),
+        }
+    }
+
+    #[cfg(feature = "tga")]
+    #[test]
+    fn tga_packet_overflow() {
+        let mut bytes = tga_header(10, 1, 1, 0);
+        bytes.extend(&[0x81, 1, 2, 3]);
+
+        match Image::from_tga(&bytes) {
+            Err(ImageError::Malformed(_)) => {},
+            result => panic!("Expected Malformed, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn zero_width_image() {
+        let image = Image::from_bottom_up(0, 4, Vec::new());
+        assert_eq!((0, 4), (image.width(), image.height()));
+
+        let texture = Texture2d::from_image(&image);
+        assert_eq!(0, texture.width());
+    }
+
+    #[test]
+    fn zero_height_image() {
+        let image = Image::from_bottom_up(4, 0, Vec::new());
+        assert_eq!((4, 0), (image.width(), image.height()));
+        assert!(image.data().is_empty());
+
+        let texture = Texture2d::from_image(&Image::new(0, 0, Vec::new()));
+        assert_eq!((0, 0), (texture.width(), texture.height()));
+    }
+}
@@ -1,5 +1,7 @@
 extern crate bootstrap_rs as bootstrap;
 extern crate parse_bmp;
+#[cfg(feature = "png")]
+extern crate png;
 extern crate polygon_material;
 extern crate stopwatch;
 
@@ -11,23 +13,49 @@ pub extern crate polygon_math as math;
 mod macros;
 
 pub mod anchor;
+pub mod antialiasing;
 pub mod camera;
+pub mod debug;
 pub mod geometry;
 pub mod gl;
+pub mod hdr;
+pub mod image;
 pub mod light;
+pub mod line_renderer;
+pub mod lod;
 pub mod material;
 pub mod mesh_instance;
+pub mod occlusion;
+pub mod pass;
 pub mod shader;
+pub mod shadow;
+pub mod skeleton;
+pub mod stats;
+pub mod streaming;
+pub mod terrain;
 pub mod texture;
 
 use anchor::*;
+use antialiasing::AntiAliasing;
 use bootstrap::window::Window;
 use camera::*;
+use debug::DebugMode;
 use geometry::mesh::Mesh;
+use hdr::HdrSettings;
 use light::*;
+use line_renderer::LineRenderer;
+use lod::{LodGroup, LodGroupId};
 use material::*;
 use math::Color;
 use mesh_instance::*;
+use occlusion::{Occluder, OccluderId, OcclusionSettings};
+use pass::{PassError, RenderPass};
+use shadow::ShadowSettings;
+use skeleton::*;
+use stats::FrameStats;
+use std::path::Path;
+use streaming::{StreamingError, StreamingSettings};
+use terrain::{Terrain, TerrainId};
 use texture::*;
 
 /// Identifies mesh data that has been sent to the GPU.
@@ -43,9 +71,20 @@ pub trait Renderer: 'static + Send {
     /// Gets a copy of the default material for the renderer.
     fn default_material(&self) -> Material;
 
+    /// Gets a copy of the built-in material for a shading model.
+    ///
+    /// The material for `ShadingModel::Simple` is the default material.
+    fn shading_model_material(&self, shading_model: ShadingModel) -> Material;
+
+    /// Gets a copy of the built-in terrain material, see the `terrain` module.
+    fn terrain_material(&self) -> Material;
+
     /// Parses a material source file and generates a material from it.
     fn build_material(&mut self, source: MaterialSource) -> Result<Material, BuildMaterialError>;
 
+    /// Compiles a custom program and generates a material from it, see the `material` module.
+    fn build_custom_material(&mut self, program: CustomProgram) -> Result<Material, BuildMaterialError>;
+
     /// Registers a material to be used as a shared material.
     fn register_shared_material(&mut self, material: Material) -> MaterialId;
 
@@ -55,9 +94,48 @@ pub trait Renderer: 'static + Send {
     /// Registers mesh data with the renderer, returning a unique id for the mesh.
     fn register_mesh(&mut self, mesh: &Mesh) -> GpuMesh;
 
+    /// Registers mesh data that will be replaced often, e.g. every frame, returning a unique id
+    /// for the mesh.
+    ///
+    /// Dynamic meshes are used like any other mesh, but their data can be replaced with
+    /// `update_dynamic_mesh()`. Their data is written to persistently mapped ring buffers, so
+    /// updating them doesn't wait for the GPU to finish drawing the previous data. They're meant
+    /// for geometry generated on the CPU such as cloth, trails, and water surfaces.
+    fn register_dynamic_mesh(&mut self, mesh: &Mesh) -> GpuMesh;
+
+    /// Replaces the vertex and index data of a dynamic mesh.
+    ///
+    /// The number of vertices, indices, and submeshes may change, and every mesh instance using
+    /// the mesh draws the new data from then on.
+    ///
+    /// # Panics
+    ///
+    /// - If `mesh_id` wasn't registered with `register_dynamic_mesh()`.
+    /// - If `mesh` doesn't have the same vertex attributes as the registered mesh data.
+    fn update_dynamic_mesh(&mut self, mesh_id: GpuMesh, mesh: &Mesh);
+
     /// Registers texture data with the renderer, returning a unique id for the texture.
     fn register_texture(&mut self, texture: &Texture2d) -> GpuTexture;
 
+    /// Creates a texture that cameras can draw into, returning a unique id for the texture.
+    ///
+    /// The texture can be used by materials like any other registered texture, see
+    /// `Camera::set_target()`.
+    ///
+    /// # Panics
+    ///
+    /// - If `width` or `height` is 0.
+    fn register_render_target(&mut self, width: usize, height: usize) -> GpuTexture;
+
+    /// Starts streaming the KTX or DDS file at `path` into a texture, returning a unique id for
+    /// the texture.
+    ///
+    /// The id can be used right away, but the texture samples as black until its first mip level
+    /// has been loaded, and it sharpens as the rest arrive, see the `streaming` module. Only 2D
+    /// textures can be streamed. If the file can't be loaded the texture stays black, and the
+    /// error is reported by `take_streaming_errors()`.
+    fn register_streaming_texture(&mut self, path: &Path) -> GpuTexture;
+
     /// Registers a mesh instance with the renderer, returning a unique id for that mesh instance.
     fn register_mesh_instance(&mut self, mesh_instance: MeshInstance) -> MeshInstanceId;
 
@@ -76,6 +154,16 @@ pub trait Renderer: 'static + Send {
     /// Gets a mutable reference to a registered anchor.
     fn get_anchor_mut(&mut self, anchor_id: AnchorId) -> Option<&mut Anchor>;
 
+    /// Attaches an anchor to a parent anchor, or detaches it if `parent` is `None`.
+    ///
+    /// The anchor's transform becomes relative to its parent, see the `anchor` module.
+    ///
+    /// # Panics
+    ///
+    /// - If either anchor doesn't exist.
+    /// - If `parent` is the anchor itself or one of its descendants.
+    fn set_anchor_parent(&mut self, anchor_id: AnchorId, parent: Option<AnchorId>);
+
     /// Registers a camera with the renderer, returning a unique id for the camera.
     fn register_camera(&mut self, camera: Camera) -> CameraId;
 
@@ -94,13 +182,215 @@ pub trait Renderer: 'static + Send {
     /// Gets a mutable reference to a registered light.
     fn get_light_mut(&mut self, light_id: LightId) -> Option<&mut Light>;
 
+    /// Registers a skeleton with the renderer, returning a unique id for the skeleton.
+    fn register_skeleton(&mut self, skeleton: Skeleton) -> SkeletonId;
+
+    /// Gets a reference to a registered skeleton.
+    fn get_skeleton(&self, skeleton_id: SkeletonId) -> Option<&Skeleton>;
+
+    /// Gets a mutable reference to a registered skeleton.
+    fn get_skeleton_mut(&mut self, skeleton_id: SkeletonId) -> Option<&mut Skeleton>;
+
+    /// Registers a LOD group with the renderer, returning a unique id for the LOD group.
+    fn register_lod_group(&mut self, lod_group: LodGroup) -> LodGroupId;
+
+    /// Gets a reference to a registered LOD group.
+    fn get_lod_group(&self, lod_group_id: LodGroupId) -> Option<&LodGroup>;
+
+    /// Gets a mutable reference to a registered LOD group.
+    fn get_lod_group_mut(&mut self, lod_group_id: LodGroupId) -> Option<&mut LodGroup>;
+
+    /// Registers an occluder with the renderer, returning a unique id for the occluder.
+    fn register_occluder(&mut self, occluder: Occluder) -> OccluderId;
+
+    /// Gets a reference to a registered occluder.
+    fn get_occluder(&self, occluder_id: OccluderId) -> Option<&Occluder>;
+
+    /// Gets a mutable reference to a registered occluder.
+    fn get_occluder_mut(&mut self, occluder_id: OccluderId) -> Option<&mut Occluder>;
+
+    /// Registers a terrain with the renderer, returning a unique id for the terrain.
+    ///
+    /// A mesh instance is registered for each chunk of the terrain, along with its anchor,
+    /// meshes, and LOD group, see `Terrain::chunks()`.
+    ///
+    /// # Panics
+    ///
+    /// - If the terrain doesn't have an anchor, or its anchor doesn't exist.
+    fn register_terrain(&mut self, terrain: Terrain) -> TerrainId;
+
+    /// Gets a reference to a registered terrain.
+    fn get_terrain(&self, terrain_id: TerrainId) -> Option<&Terrain>;
+
     fn set_ambient_light(&mut self, color: Color);
+
+    /// Sets the environment map used for image-based lighting, or removes it with `None`.
+    ///
+    /// `faces` are the six faces of a cube map in the order +X, -X, +Y, -Y, +Z, -Z, usually the
+    /// same images as the scene's skybox. The irradiance and prefiltered specular maps are
+    /// generated from the faces when the environment map is set, which can take a moment. The
+    /// environment lights materials that call `environment_lighting()`, such as the
+    /// `ShadingModel::MetallicRoughness` material, see the `material` module.
+    ///
+    /// # Panics
+    ///
+    /// - If the faces aren't square, or don't all have the same size.
+    fn set_environment_map(&mut self, faces: Option<[&Texture2d; 6]>);
+
+    /// Gets the rendering pipeline currently used to draw the scene.
+    fn render_path(&self) -> RenderPath;
+
+    /// Sets the rendering pipeline used to draw the scene.
+    fn set_render_path(&mut self, render_path: RenderPath);
+
+    /// Gets the current HDR and tone mapping settings.
+    fn hdr_settings(&self) -> HdrSettings;
+
+    /// Sets the HDR and tone mapping settings.
+    ///
+    /// # Panics
+    ///
+    /// - If `settings.exposure` isn't positive.
+    /// - If `settings.min_exposure` isn't positive or is greater than `settings.max_exposure`.
+    fn set_hdr_settings(&mut self, settings: HdrSettings);
+
+    /// Gets the current anti-aliasing mode.
+    fn anti_aliasing(&self) -> AntiAliasing;
+
+    /// Sets the anti-aliasing mode.
+    ///
+    /// Multisampling is only supported by `RenderPath::Forward`, the deferred pipeline ignores
+    /// this setting. If the GL supports fewer samples than requested the largest supported number
+    /// of samples is used instead.
+    fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing);
+
+    /// Gets the current debug visualization mode.
+    fn debug_mode(&self) -> DebugMode;
+
+    /// Sets the debug visualization mode, see the `debug` module.
+    ///
+    /// Can be changed at any time, and takes effect from the next call to `draw()`.
+    fn set_debug_mode(&mut self, debug_mode: DebugMode);
+
+    /// Returns `true` if gamma-correct rendering is enabled.
+    fn gamma_correct(&self) -> bool;
+
+    /// Sets whether rendering is gamma-correct.
+    ///
+    /// While enabled, sRGB textures (see `ColorSpace`) are converted to linear values when
+    /// they're sampled, all shading and blending is done in linear space, and the final image is
+    /// converted to sRGB when it's written to the window or a render target. Material and light
+    /// colors are treated as linear values. Disabling it restores the old behavior where colors
+    /// are used as is and shading is done in gamma space.
+    ///
+    /// Enabled by default. Textures and render targets keep the format they were registered
+    /// with, so this should be set before registering any, e.g. with
+    /// `RendererBuilder::gamma_correct()`.
+    fn set_gamma_correct(&mut self, gamma_correct: bool);
+
+    /// Gets the current occlusion culling settings.
+    fn occlusion_settings(&self) -> OcclusionSettings;
+
+    /// Sets the occlusion culling settings, see the `occlusion` module.
+    ///
+    /// # Panics
+    ///
+    /// - If `settings.width` or `settings.height` is 0.
+    fn set_occlusion_settings(&mut self, settings: OcclusionSettings);
+
+    /// Gets the current texture streaming settings.
+    fn streaming_settings(&self) -> StreamingSettings;
+
+    /// Sets the texture streaming settings, see the `streaming` module.
+    ///
+    /// # Panics
+    ///
+    /// - If `settings.upload_budget` is 0.
+    fn set_streaming_settings(&mut self, settings: StreamingSettings);
+
+    /// Returns the streaming textures that failed to load since the last call, along with the
+    /// reason each one failed.
+    fn take_streaming_errors(&mut self) -> Vec<(GpuTexture, StreamingError)>;
+
+    /// Gets the current shadow quality settings.
+    fn shadow_settings(&self) -> ShadowSettings;
+
+    /// Sets the shadow quality settings.
+    ///
+    /// # Panics
+    ///
+    /// - If `settings.cascade_count` is 0 or greater than `shadow::MAX_CASCADES`.
+    fn set_shadow_settings(&mut self, settings: ShadowSettings);
+
+    /// Gets the statistics of the most recently measured frame.
+    ///
+    /// GPU timings are read back a few frames after they were measured so that waiting for them
+    /// doesn't stall the renderer, so the stats lag behind the frame being drawn. Returns empty
+    /// stats until the first frame has been measured.
+    fn frame_stats(&self) -> FrameStats;
+
+    /// Gets the names of the render passes in the order they're executed.
+    fn pass_names(&self) -> Vec<String>;
+
+    /// Inserts a custom render pass immediately before the pass named `before`.
+    ///
+    /// Fails if there's no pass named `before`, if a pass with the same name already exists, or
+    /// if one of the pass's inputs isn't written by an earlier pass.
+    fn insert_pass_before(&mut self, before: &str, pass: Box<RenderPass>) -> Result<(), PassError>;
+
+    /// Inserts a custom render pass immediately after the pass named `after`.
+    ///
+    /// Fails under the same conditions as `insert_pass_before()`.
+    fn insert_pass_after(&mut self, after: &str, pass: Box<RenderPass>) -> Result<(), PassError>;
+
+    /// Removes a custom render pass, returning it.
+    ///
+    /// Returns `None` if there's no custom pass named `name`. Built-in passes can't be removed.
+    fn remove_pass(&mut self, name: &str) -> Option<Box<RenderPass>>;
+
+    /// Gets the line renderer used to draw debug lines, triangles, and points.
+    fn line_renderer(&self) -> &LineRenderer;
+
+    /// Gets a mutable reference to the line renderer.
+    ///
+    /// Primitives added to the line renderer are drawn on top of the scene in the next frame,
+    /// then cleared.
+    fn line_renderer_mut(&mut self) -> &mut LineRenderer;
+
+    /// Sets whether each frame drawn by `draw()` is kept so that it can be read back with
+    /// `capture_frame()`.
+    ///
+    /// Reading back a frame stalls until the GPU has finished drawing it, so frame capture should
+    /// only be enabled while it's needed, e.g. for automated tests comparing frames against
+    /// reference images. Disabled by default.
+    fn set_frame_capture(&mut self, enabled: bool);
+
+    /// Reads back the last frame presented by `draw()` into CPU memory.
+    ///
+    /// Frames drawn while the window is minimized are captured as images without any pixels.
+    ///
+    /// # Panics
+    ///
+    /// - If no frame has been drawn since frame capture was enabled with `set_frame_capture()`.
+    fn capture_frame(&self) -> Image;
+
+    /// Reads back the current contents of a render target into CPU memory.
+    ///
+    /// Render targets with a width or height of 0 are captured as images without any pixels.
+    ///
+    /// # Panics
+    ///
+    /// - If `target` wasn't created with `register_render_target()`.
+    fn capture_target(&self, target: GpuTexture) -> Image;
 }
 
 /// A helper struct for selecting and initializing the most suitable renderer for the client's
 /// needs.
 pub struct RendererBuilder<'a> {
     window: &'a Window,
+    render_path: RenderPath,
+    anti_aliasing: AntiAliasing,
+    gamma_correct: bool,
 }
 
 impl<'a> RendererBuilder<'a> {
@@ -108,16 +398,65 @@ impl<'a> RendererBuilder<'a> {
     pub fn new(window: &Window) -> RendererBuilder {
         RendererBuilder {
             window: window,
+            render_path: RenderPath::Forward,
+            anti_aliasing: AntiAliasing::Off,
+            gamma_correct: true,
         }
     }
 
+    /// Sets the rendering pipeline the renderer starts with.
+    ///
+    /// Defaults to `RenderPath::Forward`.
+    pub fn render_path(&mut self, render_path: RenderPath) -> &mut RendererBuilder<'a> {
+        self.render_path = render_path;
+        self
+    }
+
+    /// Sets the anti-aliasing mode the renderer starts with.
+    ///
+    /// Defaults to `AntiAliasing::Off`.
+    pub fn anti_aliasing(&mut self, anti_aliasing: AntiAliasing) -> &mut RendererBuilder<'a> {
+        self.anti_aliasing = anti_aliasing;
+        self
+    }
+
+    /// Sets whether the renderer starts with gamma-correct rendering.
+    ///
+    /// Defaults to `true`.
+    pub fn gamma_correct(&mut self, gamma_correct: bool) -> &mut RendererBuilder<'a> {
+        self.gamma_correct = gamma_correct;
+        self
+    }
+
     /// Constructs a new renderer using the options set in the builder.
     pub fn build(&mut self) -> Box<Renderer> {
-        let renderer = gl::GlRender::new(self.window).unwrap();
+        let mut renderer = gl::GlRender::new(self.window).unwrap();
+        renderer.set_render_path(self.render_path);
+        renderer.set_anti_aliasing(self.anti_aliasing);
+        renderer.set_gamma_correct(self.gamma_correct);
         Box::new(renderer) as Box<Renderer>
     }
 }
 
+/// The pipelines a renderer can use to draw the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderPath {
+    /// Each mesh is shaded by its material as it's drawn, with an additional pass for every 8
+    /// lights.
+    ///
+    /// Supports all materials, and is the best choice for scenes with few lights.
+    Forward,
+
+    /// Meshes are drawn into a G-buffer first, then lighting is applied in full-screen passes.
+    ///
+    /// The cost of lighting doesn't depend on the amount of geometry, making this the better
+    /// choice for scenes with many dynamic lights. Custom fragment programs are ignored, meshes
+    /// are shaded like `diffuse_lit.material` using the material's `surface_color`,
+    /// `surface_diffuse`, `surface_specular`, and `surface_shininess` properties. Transparent
+    /// materials are still drawn with the forward pipeline.
+    Deferred,
+}
+
 /// Extra special secret trait for keep counter functionality local to this crate.
 ///
 /// All resources managed by a renderer have an associated ID type used to reference the data
@@ -134,6 +473,9 @@ trait Counter {
 
     /// Returns the next valid ID value, updating the internal counter in the process.
     fn next(&mut self) -> Self;
+
+    /// Returns the raw value of the ID, e.g. for building sort keys.
+    fn index(&self) -> usize;
 }
 
 #[derive(Debug)]
@@ -7,6 +7,7 @@ pub struct Light {
     pub color: Color,
     pub strength: f32,
     anchor: Option<AnchorId>,
+    cast_shadows: bool,
 }
 
 impl Light {
@@ -16,6 +17,7 @@ impl Light {
             color: color,
             strength: strength,
             anchor: None,
+            cast_shadows: false,
         }
     }
 
@@ -25,6 +27,40 @@ impl Light {
             color: color,
             strength: strength,
             anchor: None,
+            cast_shadows: false,
+        }
+    }
+
+    /// Creates a spot light shining in `direction`, relative to the orientation of its anchor.
+    ///
+    /// `inner_angle` and `outer_angle` are the half-angles of the cone in radians. The light is
+    /// at full strength inside of `inner_angle` and fades out to nothing at `outer_angle`. Like
+    /// point lights, spot lights must be attached to an anchor to be rendered.
+    pub fn spot(
+        radius: f32,
+        direction: Vector3,
+        inner_angle: f32,
+        outer_angle: f32,
+        strength: f32,
+        color: Color,
+    ) -> Light {
+        debug_assert!(
+            inner_angle <= outer_angle,
+            "Inner angle must not be larger than outer angle, inner: {}, outer: {}",
+            inner_angle,
+            outer_angle);
+
+        Light {
+            data: LightData::Spot {
+                radius: radius,
+                direction: direction.normalized(),
+                inner_angle: inner_angle,
+                outer_angle: outer_angle,
+            },
+            color: color,
+            strength: strength,
+            anchor: None,
+            cast_shadows: false,
         }
     }
 
@@ -35,12 +71,32 @@ impl Light {
     pub fn set_anchor(&mut self, anchor_id: AnchorId) {
         self.anchor = Some(anchor_id);
     }
+
+    /// Returns `true` if the light casts shadows.
+    pub fn cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
+    /// Enables or disables shadows for the light.
+    ///
+    /// Shadows are disabled by default. Currently only directional and point lights cast shadows.
+    /// Only the first shadow-casting directional light and the first `shadow::MAX_POINT_SHADOWS`
+    /// shadow-casting point lights are used.
+    pub fn set_cast_shadows(&mut self, cast_shadows: bool) {
+        self.cast_shadows = cast_shadows;
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum LightData {
     Point { radius: f32 },
     Directional { direction: Vector3 },
+    Spot {
+        radius: f32,
+        direction: Vector3,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
 }
 
 /// Identifies a light that has been registered with the renderer.
@@ -0,0 +1,222 @@
+//! Batched drawing of lines, triangles, and points for debug visualization and gizmos.
+//!
+//! The `LineRenderer` owned by the renderer (see `Renderer::line_renderer_mut()`) collects
+//! primitives in world space over the course of a frame. When the frame is drawn all collected
+//! primitives are drawn on top of the scene in a handful of draw calls, then cleared, so
+//! primitives must be added again every frame for them to stay visible.
+//!
+//! Each primitive is either depth tested against the scene, so that it's hidden behind other
+//! geometry, or drawn as an overlay that's always visible. Use `set_depth_test()` to choose which
+//! for the primitives added afterwards.
+
+use math::*;
+use std::f32::consts::PI;
+
+/// The number of segments used to draw each circle of a sphere.
+const SPHERE_SEGMENTS: usize = 24;
+
+/// A single vertex of a line, triangle, or point.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrimitiveVertex {
+    pub position: Point,
+    pub color: Color,
+}
+
+/// The primitives drawn with the same depth test setting.
+#[derive(Debug, Clone, Default)]
+pub struct PrimitiveBatch {
+    /// Line segments, two vertices per segment.
+    pub lines: Vec<PrimitiveVertex>,
+
+    /// Triangles, three vertices per triangle.
+    pub triangles: Vec<PrimitiveVertex>,
+
+    /// Points, one vertex per point.
+    pub points: Vec<PrimitiveVertex>,
+}
+
+impl PrimitiveBatch {
+    /// Returns `true` if the batch doesn't contain any primitives.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.triangles.is_empty() && self.points.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.lines.clear();
+        self.triangles.clear();
+        self.points.clear();
+    }
+
+    fn append(&mut self, other: &mut PrimitiveBatch) {
+        self.lines.append(&mut other.lines);
+        self.triangles.append(&mut other.triangles);
+        self.points.append(&mut other.points);
+    }
+}
+
+/// Collects lines, triangles, and points to be drawn in a single batch.
+#[derive(Debug, Clone)]
+pub struct LineRenderer {
+    depth_tested: PrimitiveBatch,
+    overlay: PrimitiveBatch,
+
+    depth_test: bool,
+    point_size: f32,
+}
+
+impl LineRenderer {
+    /// Creates an empty line renderer.
+    ///
+    /// Primitives are depth tested by default.
+    pub fn new() -> LineRenderer {
+        LineRenderer {
+            depth_tested: PrimitiveBatch::default(),
+            overlay: PrimitiveBatch::default(),
+
+            depth_test: true,
+            point_size: 4.0,
+        }
+    }
+
+    /// Gets whether primitives added now are depth tested against the scene.
+    pub fn depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    /// Sets whether primitives added after this call are depth tested against the scene.
+    ///
+    /// Primitives that aren't depth tested are drawn on top of everything else.
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    /// Gets the size of points in pixels.
+    pub fn point_size(&self) -> f32 {
+        self.point_size
+    }
+
+    /// Sets the size of points in pixels.
+    ///
+    /// # Panics
+    ///
+    /// - If `point_size` isn't positive.
+    pub fn set_point_size(&mut self, point_size: f32) {
+        assert!(point_size > 0.0, "Point size must be positive, but was {}", point_size);
+        self.point_size = point_size;
+    }
+
+    /// Adds a line segment from `start` to `end`.
+    pub fn line(&mut self, start: Point, end: Point, color: Color) {
+        let batch = self.current_batch();
+        batch.lines.push(PrimitiveVertex { position: start, color: color });
+        batch.lines.push(PrimitiveVertex { position: end, color: color });
+    }
+
+    /// Adds a filled triangle with corners `a`, `b`, and `c`.
+    ///
+    /// Triangles aren't culled, so the winding order doesn't matter.
+    pub fn triangle(&mut self, a: Point, b: Point, c: Point, color: Color) {
+        let batch = self.current_batch();
+        batch.triangles.push(PrimitiveVertex { position: a, color: color });
+        batch.triangles.push(PrimitiveVertex { position: b, color: color });
+        batch.triangles.push(PrimitiveVertex { position: c, color: color });
+    }
+
+    /// Adds a point, drawn as a square `point_size()` pixels across.
+    pub fn point(&mut self, position: Point, color: Color) {
+        self.current_batch().points.push(PrimitiveVertex { position: position, color: color });
+    }
+
+    /// Adds the edges of the axis-aligned box with corners `min` and `max`.
+    pub fn box_min_max(&mut self, min: Point, max: Point, color: Color) {
+        let corners = [
+            Point::new(min.x, min.y, min.z),
+            Point::new(max.x, min.y, min.z),
+            Point::new(max.x, max.y, min.z),
+            Point::new(min.x, max.y, min.z),
+            Point::new(min.x, min.y, max.z),
+            Point::new(max.x, min.y, max.z),
+            Point::new(max.x, max.y, max.z),
+            Point::new(min.x, max.y, max.z),
+        ];
+
+        for index in 0..4 {
+            let next = (index + 1) % 4;
+            self.line(corners[index], corners[next], color);
+            self.line(corners[index + 4], corners[next + 4], color);
+            self.line(corners[index], corners[index + 4], color);
+        }
+    }
+
+    /// Adds an outline of the sphere at `center`, drawn as a circle around each axis.
+    pub fn sphere(&mut self, center: Point, radius: f32, color: Color) {
+        for segment in 0..SPHERE_SEGMENTS {
+            let start_angle = segment as f32 / SPHERE_SEGMENTS as f32 * 2.0 * PI;
+            let end_angle = (segment + 1) as f32 / SPHERE_SEGMENTS as f32 * 2.0 * PI;
+
+            let (start_sin, start_cos) = (start_angle.sin() * radius, start_angle.cos() * radius);
+            let (end_sin, end_cos) = (end_angle.sin() * radius, end_angle.cos() * radius);
+
+            self.line(
+                center + Vector3::new(start_cos, start_sin, 0.0),
+                center + Vector3::new(end_cos, end_sin, 0.0),
+                color,
+            );
+            self.line(
+                center + Vector3::new(start_cos, 0.0, start_sin),
+                center + Vector3::new(end_cos, 0.0, end_sin),
+                color,
+            );
+            self.line(
+                center + Vector3::new(0.0, start_cos, start_sin),
+                center + Vector3::new(0.0, end_cos, end_sin),
+                color,
+            );
+        }
+    }
+
+    /// Moves all primitives from `other` into this line renderer, leaving `other` empty.
+    ///
+    /// This allows primitives to be collected separately, e.g. on another thread, and handed to
+    /// the renderer all at once.
+    pub fn append(&mut self, other: &mut LineRenderer) {
+        self.depth_tested.append(&mut other.depth_tested);
+        self.overlay.append(&mut other.overlay);
+    }
+
+    /// Removes all primitives.
+    pub fn clear(&mut self) {
+        self.depth_tested.clear();
+        self.overlay.clear();
+    }
+
+    /// Returns `true` if no primitives have been added since the last clear.
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.overlay.is_empty()
+    }
+
+    /// Gets the primitives that are depth tested against the scene.
+    pub fn depth_tested(&self) -> &PrimitiveBatch {
+        &self.depth_tested
+    }
+
+    /// Gets the primitives that are drawn on top of the scene.
+    pub fn overlay(&self) -> &PrimitiveBatch {
+        &self.overlay
+    }
+
+    fn current_batch(&mut self) -> &mut PrimitiveBatch {
+        if self.depth_test {
+            &mut self.depth_tested
+        } else {
+            &mut self.overlay
+        }
+    }
+}
+
+impl Default for LineRenderer {
+    fn default() -> LineRenderer {
+        LineRenderer::new()
+    }
+}
@@ -0,0 +1,155 @@
+//! Level-of-detail groups.
+//!
+//! A `LodGroup` holds several versions of a mesh at decreasing levels of detail. A mesh instance
+//! using a LOD group (see `MeshInstance::set_lod_group()`) is drawn with the level matching its
+//! size on screen, which is measured separately for each camera as the height of its bounding
+//! sphere relative to the height of the camera's viewport.
+//!
+//! Each level has a screen size threshold, and the first level whose threshold is smaller than
+//! the mesh instance's screen size is drawn. If the mesh instance is smaller than the threshold
+//! of the last level it isn't drawn at all, so the last level's threshold should be 0 if the mesh
+//! instance should always be visible.
+//!
+//! To avoid visibly popping between levels a LOD group can cross-fade between them, see
+//! `LodGroup::set_cross_fade()`. While cross-fading both levels are drawn with complementary
+//! dither patterns, so each pixel is covered by exactly one of them.
+
+use GpuMesh;
+
+/// A single level of a `LodGroup`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodLevel {
+    /// The mesh drawn for this level.
+    pub mesh: GpuMesh,
+
+    /// The smallest screen size at which this level is drawn.
+    pub screen_size: f32,
+}
+
+/// The meshes to draw for a LOD group, see `LodGroup::select()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LodSelection {
+    /// Draws a single level.
+    Single(GpuMesh),
+
+    /// Draws two levels, fading from the less detailed level `from` to the more detailed level
+    /// `to`. `fade` goes from 0 to 1 as `to` becomes fully visible.
+    CrossFade {
+        from: GpuMesh,
+        to: GpuMesh,
+        fade: f32,
+    },
+}
+
+/// Several versions of a mesh at decreasing levels of detail.
+#[derive(Debug, Clone)]
+pub struct LodGroup {
+    levels: Vec<LodLevel>,
+    radius: f32,
+    cross_fade: f32,
+}
+
+impl LodGroup {
+    /// Creates a new LOD group without any levels.
+    ///
+    /// `radius` is the radius of a bounding sphere around the origin of the meshes, which is
+    /// scaled by the largest component of the anchor's scale when measuring screen size.
+    ///
+    /// # Panics
+    ///
+    /// - If `radius` isn't positive.
+    pub fn new(radius: f32) -> LodGroup {
+        assert!(radius > 0.0, "LOD group radius must be positive, but was {}", radius);
+
+        LodGroup {
+            levels: Vec::new(),
+            radius: radius,
+            cross_fade: 0.0,
+        }
+    }
+
+    /// Adds a level, which is less detailed than all of the existing levels.
+    ///
+    /// `screen_size` is the smallest screen size at which the level is drawn, where 1 means the
+    /// bounding sphere is as tall as the viewport.
+    ///
+    /// # Panics
+    ///
+    /// - If `screen_size` is negative.
+    /// - If `screen_size` isn't smaller than the screen size of the previous level.
+    pub fn add_level(&mut self, mesh: GpuMesh, screen_size: f32) {
+        assert!(screen_size >= 0.0, "LOD screen size must not be negative, but was {}", screen_size);
+        if let Some(previous) = self.levels.last() {
+            assert!(
+                screen_size < previous.screen_size,
+                "LOD levels must be added in order of decreasing screen size, previous: {}, new: {}",
+                previous.screen_size,
+                screen_size,
+            );
+        }
+
+        self.levels.push(LodLevel {
+            mesh: mesh,
+            screen_size: screen_size,
+        });
+    }
+
+    /// Gets the levels, from most to least detailed.
+    pub fn levels(&self) -> &[LodLevel] {
+        &self.levels
+    }
+
+    /// Gets the radius of the bounding sphere used to measure screen size.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Gets the width of the cross-fade between levels.
+    pub fn cross_fade(&self) -> f32 {
+        self.cross_fade
+    }
+
+    /// Sets the width of the cross-fade between levels, relative to the level thresholds.
+    ///
+    /// With a width of `w` the switch to a more detailed level with threshold `t` starts fading
+    /// in at a screen size of `t / (1 + w)`. A width of 0, the default, switches levels
+    /// instantly.
+    ///
+    /// # Panics
+    ///
+    /// - If `cross_fade` is negative.
+    pub fn set_cross_fade(&mut self, cross_fade: f32) {
+        assert!(cross_fade >= 0.0, "LOD cross-fade must not be negative, but was {}", cross_fade);
+        self.cross_fade = cross_fade;
+    }
+
+    /// Chooses the meshes to draw at `screen_size`.
+    ///
+    /// Returns `None` if the screen size is below the threshold of every level.
+    pub fn select(&self, screen_size: f32) -> Option<LodSelection> {
+        let index = match self.levels.iter().position(|level| screen_size >= level.screen_size) {
+            Some(index) => index,
+            None => return None,
+        };
+
+        let level = self.levels[index];
+        if index > 0 && self.cross_fade > 0.0 {
+            let next = self.levels[index - 1];
+            let fade_start = (next.screen_size / (1.0 + self.cross_fade)).max(level.screen_size);
+            if screen_size > fade_start {
+                return Some(LodSelection::CrossFade {
+                    from: level.mesh,
+                    to: next.mesh,
+                    fade: (screen_size - fade_start) / (next.screen_size - fade_start),
+                });
+            }
+        }
+
+        Some(LodSelection::Single(level.mesh))
+    }
+}
+
+/// Identifies a LOD group that has been registered with the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LodGroupId(usize);
+derive_Counter!(LodGroupId);
@@ -13,6 +13,10 @@ macro_rules! derive_Counter {
                 self.0 += 1;
                 next
             }
+
+            fn index(&self) -> usize {
+                self.0 as usize
+            }
         }
     }
 }
@@ -38,6 +38,10 @@
 //! in order to handle things like transforms and lighting. The following are the uniforms
 //! currently injected by the OpenGL renderer:
 //!
+//! All of them except the samplers are members of two uniform blocks, `FrameUniforms` for the
+//! values shared by every draw in a pass and `ObjectUniforms` for the transforms of a single
+//! mesh, so they're read like any other uniform but can't be redeclared by a material.
+//!
 //! Transforms:
 //!
 //! - `model_transform: Matrix4` - The transform converting points in model space to world space.
@@ -58,14 +62,130 @@
 //! - `light_strength: f32` - The strength of the current light.
 //! - `light_color: Color` - The color of the current light.
 //! - `light_type: u32` - An integer constant specifying the type of the current light: 0 means no
-//!   light, 1 means point light, 2 means directional light. All light-related uniforms will be present
+//!   light, 1 means point light, 2 means directional light, 3 means spot light. All light-related uniforms will be present
 //!   regardless of the light type, but uniforms not used for the current light type will not be
 //!   set, so reading them will yield some kind of garbage.
-//! - `light_radius: f32` - The radius of the current light (only for point lights).
+//! - `light_radius: f32` - The radius of the current light (only for point and spot lights).
 //! - `light_direction: Vector3` - The normalized direction in world space of the current light (only
-//!   for directional lights).
+//!   for directional and spot lights).
 //! - `light_direction_view` - The normalized direction in view space of the current light (only
-//!   for directional lights).
+//!   for directional and spot lights).
+//! - `light_cos_inner: f32` - The cosine of the angle of the current light's cone within which
+//!   the light is at full strength (only for spot lights).
+//! - `light_cos_outer: f32` - The cosine of the angle of the current light's cone outside of
+//!   which there is no light (only for spot lights).
+//!
+//! Shadows:
+//!
+//! - `shadow_light_index: i32` - The index of the directional light that casts shadows, or -1 if
+//!   no directional light casts shadows.
+//! - `shadow_cascade_count: i32` - The number of shadow cascades in use.
+//! - `shadow_bias: f32` - The depth bias to apply when sampling the shadow maps.
+//! - `shadow_transform: Matrix4` - The transform converting points in world space to shadow
+//!   map texture space for each cascade.
+//! - `shadow_map: sampler2DShadow` - The shadow map for each cascade.
+//! - `light_shadow_map: i32` - For each light, the index of its point shadow map, or -1 if the
+//!   light doesn't have one.
+//! - `point_shadow_map: samplerCube` - The shadow cube map for each shadow-casting point light,
+//!   storing the distance from the light to the closest surface divided by the light's radius.
+//!
+//! Fragment programs can also call the built-in function `float shadow_factor(int light_index)`
+//! which returns how much of the light at `light_index` reaches the current fragment, from 0.0
+//! (fully shadowed) to 1.0 (fully lit). Lights that don't cast shadows always return 1.0.
+//!
+//! Skinning (vertex programs only):
+//!
+//! - `skinned: i32` - 1 if the mesh instance has a skeleton, 0 otherwise.
+//! - `joint_palette: Matrix4` - The transform from the bind pose to the current pose for each
+//!   joint in the mesh instance's skeleton.
+//!
+//! Skinning is applied automatically: `vertex_position`, `vertex_normal`, and `vertex_tangent`
+//! already hold the skinned position, normal, and tangent when the vertex program runs, so custom
+//! vertex programs don't need to handle skinned meshes specially.
+//!
+//! Normal mapping (fragment programs only):
+//!
+//! - `surface_normal: sampler2D` - The material's `surface_normal` texture property, a
+//!   tangent-space normal map. Materials shouldn't declare this property themselves. Normal maps
+//!   must have `ColorSpace::Linear`.
+//! - `has_surface_normal: i32` - 1 if the material has a normal map, 0 otherwise.
+//!
+//! Fragment programs can call the built-in function
+//! `vec3 apply_normal_map(vec3 normal, vec4 tangent, vec2 uv)` with the interpolated normal and
+//! tangent (e.g. `@vertex.view_normal` and `@vertex.view_tangent`) to get the normal perturbed by
+//! the normal map, in the same space as the inputs. Meshes without tangents (see
+//! `MeshBuilder::generate_tangents()`) and materials without a normal map get the unchanged
+//! normal.
+//!
+//! Image-based lighting (fragment programs only):
+//!
+//! - `environment_irradiance: samplerCube` - The diffuse irradiance from the environment map in
+//!   each world space direction.
+//! - `environment_specular: samplerCube` - The environment map prefiltered for increasing
+//!   roughness in each mip level.
+//! - `environment_max_lod: f32` - The mip level of `environment_specular` for a roughness of 1.
+//! - `has_environment: i32` - 1 if there's an environment map and the current pass should apply
+//!   it, 0 otherwise.
+//!
+//! Fragment programs can call the built-in function
+//! `vec3 environment_lighting(vec3 n, vec3 v, vec3 diffuse_color, vec3 f0, float roughness)`
+//! with the view space normal and direction to the camera to get the light reflected from the
+//! environment, see `Renderer::set_environment_map()`.
+//!
+//! Texture properties:
+//!
+//! - `has_<name>: i32` - For each texture property `<name>` declared by the material, 1 if the
+//!   property is set to a registered texture, 0 otherwise. Texture properties start out unset,
+//!   so this allows materials to treat textures as optional.
+//!
+//! Vertex colors:
+//!
+//! - `has_vertex_color: i32` - 1 if the mesh has vertex colors, 0 otherwise.
+//!
+//! Vertex programs read the mesh's vertex colors from `vertex_color`, which is white for meshes
+//! without vertex colors, and the default vertex program passes it on as `@vertex.color`. The
+//! built-in materials multiply their base color by it. Vertex colors are used as is, so they're
+//! treated as linear values even when gamma-correct rendering is enabled.
+//!
+//! Time:
+//!
+//! - `time: f32` - The number of seconds since the renderer was created, which is the same for
+//!   every draw in a frame.
+//!
+//! # Shading Models
+//!
+//! The renderer provides a built-in material for each `ShadingModel`, see
+//! `Renderer::shading_model_material()`:
+//!
+//! - `ShadingModel::Simple` is the default material (`diffuse_lit.material`), a Phong model using
+//!   `surface_color`, `surface_specular`, and `surface_shininess`.
+//! - `ShadingModel::MetallicRoughness` (`pbr_lit.material`) is a physically based model using
+//!   a Cook-Torrance BRDF. It uses the factors `base_color`, `metallic`, `roughness`, and
+//!   `emissive`, each multiplied by the optional texture maps `base_color_map`,
+//!   `metallic_roughness_map` (roughness in green and metalness in blue), and `emissive_map`,
+//!   along with an optional ambient occlusion map `ao_map`. It reflects the environment map if
+//!   there is one, falling back to the ambient light otherwise. The metallic-roughness and
+//!   ambient occlusion maps must have `ColorSpace::Linear`.
+//!
+//! Only the forward render path runs material programs, so the deferred path shades every
+//! material with the simple model.
+//!
+//! # Custom Programs
+//!
+//! Effects that don't fit the material syntax can be written as complete GLSL vertex and
+//! fragment programs with `CustomProgram`, see `Renderer::build_custom_material()`. The resulting
+//! material is drawn through the render queue like any other, so it's sorted, culled, and
+//! LOD-selected as usual, but nothing is injected into its source: it must declare the vertex
+//! attributes, uniforms, and outputs it uses itself. Mesh data is bound to the attribute
+//! locations 0 (position), 1 (normal), 2 (first texcoords), 3 (color), and 13 (tangent).
+//!
+//! A custom program declares which groups of built-in uniforms it consumes with
+//! `EngineUniforms`, and only those are set for its draws, so its own uniforms can reuse the
+//! names of the groups it doesn't consume. The consumed uniforms are set as plain uniforms
+//! rather than through the uniform blocks. Custom programs that don't consume lights are only
+//! drawn once, rather than once per batch of lights. Mesh instances using a custom program are
+//! never instanced, and the custom program is responsible for skinning if it's used with
+//! skeletons.
 
 use math::*;
 use shader::Shader;
@@ -73,7 +193,97 @@ use std::collections::HashMap;
 use std::collections::hash_map::Iter as HashMapIter;
 use texture::GpuTexture;
 
-pub use polygon_material::material_source::{Error as MaterialSourceError, MaterialSource};
+pub use polygon_material::material_source::{Error as MaterialSourceError, MaterialSource, PropertyType};
+
+/// The shading models of the renderer's built-in materials, see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShadingModel {
+    Simple,
+    MetallicRoughness,
+}
+
+/// The groups of built-in uniforms a `CustomProgram` consumes, see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EngineUniforms {
+    /// The transform uniforms, from `model_transform` through `model_view_projection`, along
+    /// with `camera_position`.
+    pub transforms: bool,
+
+    /// `global_ambient` and the light, shadow, and image-based lighting uniforms.
+    pub lights: bool,
+
+    /// The `time` uniform.
+    pub time: bool,
+}
+
+impl EngineUniforms {
+    /// Consumes every group of built-in uniforms, which is what material sources get.
+    pub fn all() -> EngineUniforms {
+        EngineUniforms {
+            transforms: true,
+            lights: true,
+            time: true,
+        }
+    }
+}
+
+/// A material program written as complete GLSL sources, see the module docs.
+#[derive(Debug, Clone)]
+pub struct CustomProgram {
+    vertex_source: String,
+    fragment_source: String,
+    engine_uniforms: EngineUniforms,
+    properties: Vec<(String, PropertyType)>,
+}
+
+impl CustomProgram {
+    /// Creates a custom program from the GLSL sources of its vertex and fragment shaders.
+    ///
+    /// The program doesn't consume any built-in uniforms until `set_engine_uniforms()` is
+    /// called.
+    pub fn new<V: Into<String>, F: Into<String>>(vertex_source: V, fragment_source: F) -> CustomProgram {
+        CustomProgram {
+            vertex_source: vertex_source.into(),
+            fragment_source: fragment_source.into(),
+            engine_uniforms: EngineUniforms::default(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Gets the source of the vertex shader.
+    pub fn vertex_source(&self) -> &str {
+        &*self.vertex_source
+    }
+
+    /// Gets the source of the fragment shader.
+    pub fn fragment_source(&self) -> &str {
+        &*self.fragment_source
+    }
+
+    /// Gets the groups of built-in uniforms the program consumes.
+    pub fn engine_uniforms(&self) -> EngineUniforms {
+        self.engine_uniforms
+    }
+
+    /// Sets the groups of built-in uniforms the program consumes.
+    pub fn set_engine_uniforms(&mut self, engine_uniforms: EngineUniforms) {
+        self.engine_uniforms = engine_uniforms;
+    }
+
+    /// Declares a uniform of the program as a material property.
+    ///
+    /// Materials built from the program start out with a default value for each property like
+    /// materials built from a material source, and texture properties get a `has_<name>`
+    /// uniform if the program declares one.
+    pub fn add_property<S: Into<String>>(&mut self, name: S, property_type: PropertyType) {
+        self.properties.push((name.into(), property_type));
+    }
+
+    /// Gets the declared material properties.
+    pub fn properties(&self) -> &[(String, PropertyType)] {
+        &*self.properties
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MaterialId(usize);
@@ -84,6 +294,7 @@ derive_Counter!(MaterialId);
 pub struct Material {
     shader: Shader,
     properties: HashMap<String, MaterialProperty>,
+    transparent: bool,
 }
 
 impl Material {
@@ -92,6 +303,7 @@ impl Material {
         Material {
             shader: shader,
             properties: HashMap::new(),
+            transparent: false,
         }
     }
 
@@ -100,6 +312,21 @@ impl Material {
         &self.shader
     }
 
+    /// Gets whether the material is alpha blended.
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    /// Sets whether the material is alpha blended.
+    ///
+    /// Transparent materials are drawn by the `"transparent"` pass after all opaque geometry,
+    /// sorted back-to-front and blended using the alpha of the fragment program's output. They
+    /// are depth tested against the scene but don't write depth, so they never hide geometry
+    /// drawn after them. Materials are opaque by default.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
     /// Gets an iterator yielding the the current material properties.
     pub fn properties(&self) -> HashMapIter<String, MaterialProperty> {
         self.properties.iter()
@@ -136,6 +363,32 @@ impl Material {
         }
     }
 
+    /// Sets a property value to be the specified `i32` value.
+    pub fn set_i32<S: Into<String>>(&mut self, name: S, value: i32) {
+        self.properties.insert(name.into(), MaterialProperty::i32(value));
+    }
+
+    /// Gets the value of a `i32` material property.
+    pub fn get_i32(&self, name: &str) -> Option<&i32> {
+        match self.properties.get(name) {
+            Some(&MaterialProperty::i32(ref value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Sets a property value to be the specified `Vector2` value.
+    pub fn set_vector2<S: Into<String>>(&mut self, name: S, value: Vector2) {
+        self.properties.insert(name.into(), MaterialProperty::Vector2(value));
+    }
+
+    /// Gets the value of a `Vector2` material property.
+    pub fn get_vector2(&self, name: &str) -> Option<&Vector2> {
+        match self.properties.get(name) {
+            Some(&MaterialProperty::Vector2(ref value)) => Some(value),
+            _ => None,
+        }
+    }
+
     /// Sets a property value to be the specified `Vector3` value.
     pub fn set_vector3<S: Into<String>>(&mut self, name: S, value: Vector3) {
         self.properties.insert(name.into(), MaterialProperty::Vector3(value));
@@ -154,6 +407,34 @@ impl Material {
         self.properties.insert(name.into(), MaterialProperty::Texture(texture));
     }
 
+    /// Gets the value of a texture property.
+    pub fn get_texture(&self, name: &str) -> Option<&GpuTexture> {
+        match self.properties.get(name) {
+            Some(&MaterialProperty::Texture(ref texture)) => Some(texture),
+            _ => None,
+        }
+    }
+
+    /// Sets a property to any kind of value.
+    ///
+    /// This is useful for copying properties between materials, e.g. when applying a set of
+    /// parameters with `set_properties()`.
+    pub fn set_property<S: Into<String>>(&mut self, name: S, property: MaterialProperty) {
+        self.properties.insert(name.into(), property);
+    }
+
+    /// Sets each of the properties in `properties`, replacing any existing values.
+    ///
+    /// This allows a set of parameters to be kept separately and applied to any material, e.g.
+    /// to give several objects the same shader with different colors and textures.
+    pub fn set_properties<'a, I>(&mut self, properties: I)
+        where I: IntoIterator<Item = (&'a String, &'a MaterialProperty)>
+    {
+        for (name, property) in properties {
+            self.properties.insert(name.clone(), property.clone());
+        }
+    }
+
     /// Removes a property from the material.
     ///
     /// The existing property is returned if any.
@@ -169,6 +450,8 @@ pub enum MaterialProperty {
     Color(Color),
     Texture(GpuTexture),
     f32(f32),
+    i32(i32),
+    Vector2(Vector2),
     Vector3(Vector3),
 }
 
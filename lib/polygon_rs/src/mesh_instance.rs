@@ -9,18 +9,28 @@
 
 use {GpuMesh};
 use anchor::AnchorId;
+use lod::LodGroupId;
 use material::*;
+use skeleton::SkeletonId;
+use std::collections::HashMap;
 
 /// Represents an instance of a mesh in the scene.
 ///
 /// By default a mesh instance will not be attached to an anchor, and will not be rendered in
 /// the scene until one is set with `set_anchor()` and the mesh instance is registered with
 /// the renderer using `Renderer::register_mesh_instance()`.
+///
+/// The mesh instance's material is used for every submesh of the mesh, unless a different
+/// material has been set for the submesh's material slot with `set_slot_material()`.
 #[derive(Debug)]
 pub struct MeshInstance {
     mesh: GpuMesh,
     material: MaterialType,
-    anchor: Option<AnchorId>
+    slot_materials: HashMap<usize, Material>,
+    anchor: Option<AnchorId>,
+    skeleton: Option<SkeletonId>,
+    lod_group: Option<LodGroupId>,
+    layer: u8,
 }
 
 impl MeshInstance {
@@ -29,7 +39,11 @@ impl MeshInstance {
         MeshInstance {
             mesh: mesh,
             material: MaterialType::Shared(material),
+            slot_materials: HashMap::new(),
             anchor: None,
+            skeleton: None,
+            lod_group: None,
+            layer: 0,
         }
     }
 
@@ -38,7 +52,11 @@ impl MeshInstance {
         MeshInstance {
             mesh: mesh,
             material: MaterialType::Owned(material),
+            slot_materials: HashMap::new(),
             anchor: None,
+            skeleton: None,
+            lod_group: None,
+            layer: 0,
         }
     }
 
@@ -81,6 +99,26 @@ impl MeshInstance {
         }
     }
 
+    /// Sets the material used for submeshes in `slot`, overriding the mesh instance's material.
+    pub fn set_slot_material(&mut self, slot: usize, material: Material) {
+        self.slot_materials.insert(slot, material);
+    }
+
+    /// Removes the material override for `slot`, returning it if there was one.
+    pub fn clear_slot_material(&mut self, slot: usize) -> Option<Material> {
+        self.slot_materials.remove(&slot)
+    }
+
+    /// Gets the material override for `slot`, if one has been set.
+    pub fn slot_material(&self, slot: usize) -> Option<&Material> {
+        self.slot_materials.get(&slot)
+    }
+
+    /// Returns `true` if a material override has been set for any slot.
+    pub fn has_slot_materials(&self) -> bool {
+        !self.slot_materials.is_empty()
+    }
+
     /// Attaches the mesh instance to the specified anchor.
     pub fn set_anchor(&mut self, anchor_id: AnchorId) {
         self.anchor = Some(anchor_id);
@@ -90,6 +128,55 @@ impl MeshInstance {
     pub fn anchor(&self) -> Option<AnchorId> {
         self.anchor
     }
+
+    /// Sets the skeleton used to deform the mesh.
+    ///
+    /// The mesh must have joint indices and weights for the skeleton to have any effect.
+    pub fn set_skeleton(&mut self, skeleton_id: SkeletonId) {
+        self.skeleton = Some(skeleton_id);
+    }
+
+    /// Detaches the mesh instance from its skeleton, rendering the mesh in its bind pose.
+    pub fn clear_skeleton(&mut self) {
+        self.skeleton = None;
+    }
+
+    /// Gets the skeleton used to deform the mesh, if any.
+    pub fn skeleton(&self) -> Option<SkeletonId> {
+        self.skeleton
+    }
+
+    /// Sets the LOD group used to choose the mesh drawn for each camera, see the `lod` module.
+    ///
+    /// While a LOD group is set the mesh instance's own mesh is only used to draw shadows. The
+    /// meshes in the LOD group should all have the same submeshes and material slots.
+    pub fn set_lod_group(&mut self, lod_group_id: LodGroupId) {
+        self.lod_group = Some(lod_group_id);
+    }
+
+    /// Stops using a LOD group, so the mesh instance's own mesh is drawn again.
+    pub fn clear_lod_group(&mut self) {
+        self.lod_group = None;
+    }
+
+    /// Gets the LOD group used to choose the mesh drawn for each camera, if any.
+    pub fn lod_group(&self) -> Option<LodGroupId> {
+        self.lod_group
+    }
+
+    /// Sets the layer the mesh instance is drawn in.
+    ///
+    /// Within each render pass all mesh instances in lower layers are drawn before any in higher
+    /// layers, regardless of their distance from the camera. Mesh instances are in layer 0 by
+    /// default.
+    pub fn set_layer(&mut self, layer: u8) {
+        self.layer = layer;
+    }
+
+    /// Gets the layer the mesh instance is drawn in.
+    pub fn layer(&self) -> u8 {
+        self.layer
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -0,0 +1,277 @@
+//! Software occlusion culling.
+//!
+//! In dense scenes, e.g. building interiors, most mesh instances in front of the camera are
+//! hidden behind walls and terrain but would still be drawn. When occlusion culling is enabled
+//! (see `Renderer::set_occlusion_settings()`) the renderer rasterizes the depth of a few large
+//! `Occluder`s into a small depth buffer on the CPU for each camera. Mesh instances whose
+//! bounding box is entirely behind the occluders are then skipped before any draw calls are
+//! issued.
+//!
+//! Occluders are registered separately from mesh instances with `Renderer::register_occluder()`,
+//! so they can be simplified versions of the visible geometry. Since the depth buffer has a low
+//! resolution an occluder should lie slightly inside the geometry it stands in for, otherwise
+//! objects just visible past its edges may be culled.
+//!
+//! Mesh instances are tested using the bounds of their mesh in its rest pose, so skinned mesh
+//! instances are never culled. Instanced mesh instances and shadow casters aren't culled either.
+//!
+//! Regardless of the occlusion settings, mesh instances whose bounding box is entirely outside of
+//! the camera's view frustum are always skipped in the same way.
+
+use anchor::AnchorId;
+use geometry::mesh::{Mesh, MeshIndex};
+use math::Point;
+use std::f32;
+
+/// Settings for occlusion culling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OcclusionSettings {
+    /// Whether mesh instances hidden behind occluders are culled.
+    pub enabled: bool,
+
+    /// The width in pixels of the depth buffer the occluders are rasterized into.
+    pub width: usize,
+
+    /// The height in pixels of the depth buffer the occluders are rasterized into.
+    ///
+    /// This should match the aspect ratio of the cameras' viewports.
+    pub height: usize,
+}
+
+impl Default for OcclusionSettings {
+    fn default() -> OcclusionSettings {
+        OcclusionSettings {
+            enabled: false,
+            width: 256,
+            height: 128,
+        }
+    }
+}
+
+/// Geometry that hides the mesh instances behind it, see the module docs.
+///
+/// Occluders are never drawn, and are double-sided.
+#[derive(Debug, Clone)]
+pub struct Occluder {
+    positions: Vec<Point>,
+    indices: Vec<MeshIndex>,
+    anchor: Option<AnchorId>,
+}
+
+impl Occluder {
+    /// Creates an occluder from the triangles of `mesh`.
+    ///
+    /// Occluders are rasterized on the CPU, so the mesh should have as few triangles as possible.
+    pub fn new(mesh: &Mesh) -> Occluder {
+        Occluder {
+            positions: mesh.positions(),
+            indices: mesh.indices().to_vec(),
+            anchor: None,
+        }
+    }
+
+    /// Attaches the occluder to the specified anchor.
+    ///
+    /// Occluders without an anchor don't hide anything.
+    pub fn set_anchor(&mut self, anchor_id: AnchorId) {
+        self.anchor = Some(anchor_id);
+    }
+
+    /// Gets the anchor the occluder is attached to.
+    pub fn anchor(&self) -> Option<AnchorId> {
+        self.anchor
+    }
+
+    /// Gets the number of triangles in the occluder.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Gets the corners of each triangle in the occluder, in model space.
+    pub(crate) fn triangles<'a>(&'a self) -> Box<Iterator<Item = [Point; 3]> + 'a> {
+        Box::new(self.indices.chunks(3).filter(|triangle| triangle.len() == 3).map(move |triangle| {
+            [
+                self.positions[triangle[0] as usize],
+                self.positions[triangle[1] as usize],
+                self.positions[triangle[2] as usize],
+            ]
+        }))
+    }
+}
+
+/// Identifies an occluder that has been registered with the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OccluderId(usize);
+derive_Counter!(OccluderId);
+
+/// A low resolution depth buffer that occluders are rasterized into.
+///
+/// All points are given in clip space. Depths are stored in normalized device coordinates, so
+/// the buffer is cleared to the far plane at 1.0.
+#[derive(Debug, Clone)]
+pub(crate) struct DepthBuffer {
+    width: usize,
+    height: usize,
+    depth: Vec<f32>,
+}
+
+impl DepthBuffer {
+    pub fn new(width: usize, height: usize) -> DepthBuffer {
+        DepthBuffer {
+            width: width,
+            height: height,
+            depth: vec![1.0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn clear(&mut self) {
+        for depth in &mut self.depth {
+            *depth = 1.0;
+        }
+    }
+
+    /// Rasterizes a triangle, keeping the nearest depth of each pixel whose center it covers.
+    ///
+    /// The triangle is clipped against the near plane first, so it may extend behind the camera.
+    pub fn rasterize_triangle(&mut self, triangle: [Point; 3]) {
+        let clipped = clip_near(&triangle);
+        if clipped.len() < 3 {
+            return;
+        }
+
+        let screen: Vec<(f32, f32, f32)> = clipped.iter().map(|&point| self.to_screen(point)).collect();
+        for index in 1..screen.len() - 1 {
+            self.rasterize_screen_triangle(screen[0], screen[index], screen[index + 1]);
+        }
+    }
+
+    /// Returns `true` if the box with the specified corners is hidden behind the rasterized
+    /// occluders.
+    ///
+    /// Boxes that cross the near plane are never occluded.
+    pub fn is_box_occluded(&self, corners: &[Point; 8]) -> bool {
+        if corners.iter().any(|corner| corner.z + corner.w < 0.0) {
+            return false;
+        }
+
+        let mut min_x = f32::INFINITY;
+        let mut min_y = f32::INFINITY;
+        let mut max_x = f32::NEG_INFINITY;
+        let mut max_y = f32::NEG_INFINITY;
+        let mut min_depth = f32::INFINITY;
+        for &corner in corners {
+            let (x, y, depth) = self.to_screen(corner);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+            min_depth = min_depth.min(depth);
+        }
+
+        // Test every pixel the box touches, including partially covered ones.
+        let start_x = clamp_pixel(min_x.floor(), self.width);
+        let start_y = clamp_pixel(min_y.floor(), self.height);
+        let end_x = clamp_pixel(max_x.ceil(), self.width).max(start_x + 1).min(self.width);
+        let end_y = clamp_pixel(max_y.ceil(), self.height).max(start_y + 1).min(self.height);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                if self.depth[y * self.width + x] >= min_depth {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Converts a point in clip space into pixel coordinates and normalized device depth.
+    fn to_screen(&self, point: Point) -> (f32, f32, f32) {
+        (
+            (point.x / point.w * 0.5 + 0.5) * self.width as f32,
+            (point.y / point.w * 0.5 + 0.5) * self.height as f32,
+            point.z / point.w,
+        )
+    }
+
+    fn rasterize_screen_triangle(&mut self, a: (f32, f32, f32), b: (f32, f32, f32), c: (f32, f32, f32)) {
+        let area = edge(a, b, c.0, c.1);
+        if area.abs() < 1e-6 {
+            return;
+        }
+
+        let start_x = clamp_pixel(a.0.min(b.0).min(c.0).floor(), self.width);
+        let start_y = clamp_pixel(a.1.min(b.1).min(c.1).floor(), self.height);
+        let end_x = clamp_pixel(a.0.max(b.0).max(c.0).ceil(), self.width);
+        let end_y = clamp_pixel(a.1.max(b.1).max(c.1).ceil(), self.height);
+
+        for y in start_y..end_y {
+            let center_y = y as f32 + 0.5;
+            for x in start_x..end_x {
+                let center_x = x as f32 + 0.5;
+
+                // Dividing by the area makes the weights positive inside the triangle regardless
+                // of its winding.
+                let weight_a = edge(b, c, center_x, center_y) / area;
+                let weight_b = edge(c, a, center_x, center_y) / area;
+                let weight_c = edge(a, b, center_x, center_y) / area;
+                if weight_a < 0.0 || weight_b < 0.0 || weight_c < 0.0 {
+                    continue;
+                }
+
+                let depth = weight_a * a.2 + weight_b * b.2 + weight_c * c.2;
+                let pixel = &mut self.depth[y * self.width + x];
+                if depth < *pixel {
+                    *pixel = depth;
+                }
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `(a, b, (x, y))`.
+fn edge(a: (f32, f32, f32), b: (f32, f32, f32), x: f32, y: f32) -> f32 {
+    (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0)
+}
+
+/// Clamps a pixel coordinate to `[0, size]`.
+fn clamp_pixel(coordinate: f32, size: usize) -> usize {
+    coordinate.max(0.0).min(size as f32) as usize
+}
+
+/// Clips a polygon in clip space against the near plane, where `z = -w`.
+fn clip_near(polygon: &[Point]) -> Vec<Point> {
+    let distance = |point: &Point| point.z + point.w;
+
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for index in 0..polygon.len() {
+        let current = polygon[index];
+        let next = polygon[(index + 1) % polygon.len()];
+        let current_distance = distance(&current);
+        let next_distance = distance(&next);
+
+        if current_distance >= 0.0 {
+            clipped.push(current);
+        }
+
+        if (current_distance >= 0.0) != (next_distance >= 0.0) {
+            let t = current_distance / (current_distance - next_distance);
+            clipped.push(Point {
+                x: current.x + (next.x - current.x) * t,
+                y: current.y + (next.y - current.y) * t,
+                z: current.z + (next.z - current.z) * t,
+                w: current.w + (next.w - current.w) * t,
+            });
+        }
+    }
+
+    clipped
+}
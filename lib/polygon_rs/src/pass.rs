@@ -0,0 +1,388 @@
+//! Render passes and the renderer's pass list.
+//!
+//! Each frame the renderer executes an ordered list of passes for the active camera. By default
+//! the list contains the built-in passes:
+//!
+//! 1. `"shadow"` - Renders the shadow maps for shadow-casting lights.
+//! 2. `"opaque"` - Renders all opaque geometry using the current `RenderPath`.
+//! 3. `"transparent"` - Renders transparent geometry over the opaque geometry.
+//! 4. `"post"` - Applies post-processing to the final image, e.g. HDR tone mapping.
+//!
+//! Custom passes implement `RenderPass` and are inserted relative to an existing pass with
+//! `Renderer::insert_pass_before()` or `Renderer::insert_pass_after()`. Every pass declares the
+//! render targets it reads and writes, and a pass may only be inserted at a point where each of
+//! its inputs has been written by an earlier pass.
+//!
+//! Every pass is wrapped in a GL debug group named after the pass, so the passes show up in
+//! captures from graphics debuggers, and its GPU time is reported by `Renderer::frame_stats()`.
+
+use anchor::Anchor;
+use camera::Camera;
+use gl::gl_util::context::Context;
+use gl::gl_util::framebuffer::Framebuffer;
+use std::fmt::{self, Debug, Display, Formatter};
+
+/// A render target read or written by a render pass.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RenderTarget {
+    /// The color buffer the scene is rendered into. It's always available as an input.
+    ///
+    /// When HDR is enabled this is the HDR target until the `"post"` pass has resolved it to the
+    /// window.
+    Backbuffer,
+
+    /// The depth of the opaque geometry in the scene.
+    SceneDepth,
+
+    /// The shadow maps for shadow-casting lights.
+    ShadowMaps,
+
+    /// A render target owned by a custom pass, identified by name.
+    Custom(String),
+}
+
+/// A stage of rendering that can be added to the renderer's pass list.
+pub trait RenderPass: 'static + Send {
+    /// The name of the pass, which must be unique within the pass list.
+    fn name(&self) -> &str;
+
+    /// The render targets read by the pass.
+    fn inputs(&self) -> Vec<RenderTarget> {
+        Vec::new()
+    }
+
+    /// The render targets written by the pass.
+    fn outputs(&self) -> Vec<RenderTarget>;
+
+    /// Performs the pass's rendering for a single camera.
+    fn execute(&mut self, context: &PassContext);
+}
+
+/// The state available to a custom pass while it executes.
+pub struct PassContext<'a> {
+    /// The OpenGL context.
+    pub context: &'a Context,
+
+    /// The framebuffer holding the backbuffer, or `None` if it's the default framebuffer.
+    ///
+    /// Passes that draw into the scene should target this framebuffer.
+    pub framebuffer: Option<&'a Framebuffer>,
+
+    /// The camera being rendered.
+    pub camera: &'a Camera,
+
+    /// The anchor the camera is attached to.
+    pub camera_anchor: &'a Anchor,
+}
+
+/// The passes provided by the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltInPass {
+    Shadow,
+    Opaque,
+    Transparent,
+    Post,
+}
+
+impl BuiltInPass {
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltInPass::Shadow => "shadow",
+            BuiltInPass::Opaque => "opaque",
+            BuiltInPass::Transparent => "transparent",
+            BuiltInPass::Post => "post",
+        }
+    }
+
+    pub fn inputs(self) -> Vec<RenderTarget> {
+        match self {
+            BuiltInPass::Shadow => vec![],
+            BuiltInPass::Opaque => vec![RenderTarget::ShadowMaps],
+            BuiltInPass::Transparent => vec![RenderTarget::ShadowMaps, RenderTarget::SceneDepth],
+            BuiltInPass::Post => vec![RenderTarget::Backbuffer],
+        }
+    }
+
+    pub fn outputs(self) -> Vec<RenderTarget> {
+        match self {
+            BuiltInPass::Shadow => vec![RenderTarget::ShadowMaps],
+            BuiltInPass::Opaque => vec![RenderTarget::Backbuffer, RenderTarget::SceneDepth],
+            BuiltInPass::Transparent => vec![RenderTarget::Backbuffer],
+            BuiltInPass::Post => vec![RenderTarget::Backbuffer],
+        }
+    }
+}
+
+/// An entry in the pass list.
+pub enum Pass {
+    BuiltIn(BuiltInPass),
+    Custom(Box<RenderPass>),
+}
+
+impl Pass {
+    pub fn name(&self) -> &str {
+        match *self {
+            Pass::BuiltIn(pass) => pass.name(),
+            Pass::Custom(ref pass) => pass.name(),
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<RenderTarget> {
+        match *self {
+            Pass::BuiltIn(pass) => pass.inputs(),
+            Pass::Custom(ref pass) => pass.inputs(),
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<RenderTarget> {
+        match *self {
+            Pass::BuiltIn(pass) => pass.outputs(),
+            Pass::Custom(ref pass) => pass.outputs(),
+        }
+    }
+}
+
+impl Debug for Pass {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            Pass::BuiltIn(pass) => write!(formatter, "Pass::BuiltIn({:?})", pass),
+            Pass::Custom(ref pass) => write!(formatter, "Pass::Custom({:?})", pass.name()),
+        }
+    }
+}
+
+/// The ordered list of passes executed by a renderer.
+#[derive(Debug)]
+pub struct PassList {
+    passes: Vec<Pass>,
+}
+
+impl PassList {
+    /// Creates a pass list containing the built-in passes.
+    pub fn new() -> PassList {
+        PassList {
+            passes: vec![
+                Pass::BuiltIn(BuiltInPass::Shadow),
+                Pass::BuiltIn(BuiltInPass::Opaque),
+                Pass::BuiltIn(BuiltInPass::Transparent),
+                Pass::BuiltIn(BuiltInPass::Post),
+            ],
+        }
+    }
+
+    /// Returns the names of the passes in the order they're executed.
+    pub fn names(&self) -> Vec<String> {
+        self.passes.iter().map(|pass| String::from(pass.name())).collect()
+    }
+
+    /// Inserts `pass` immediately before the pass named `before`.
+    pub fn insert_before(&mut self, before: &str, pass: Box<RenderPass>) -> Result<(), PassError> {
+        let index = self.index_of(before).ok_or_else(|| PassError::PassNotFound(String::from(before)))?;
+        self.insert(index, pass)
+    }
+
+    /// Inserts `pass` immediately after the pass named `after`.
+    pub fn insert_after(&mut self, after: &str, pass: Box<RenderPass>) -> Result<(), PassError> {
+        let index = self.index_of(after).ok_or_else(|| PassError::PassNotFound(String::from(after)))?;
+        self.insert(index + 1, pass)
+    }
+
+    /// Removes the custom pass named `name`, returning it.
+    ///
+    /// Returns `None` if there's no such pass, or if it's a built-in pass since those can't be
+    /// removed. Passes after the removed pass that read its outputs will no longer have valid
+    /// inputs, so remove dependent passes first.
+    pub fn remove(&mut self, name: &str) -> Option<Box<RenderPass>> {
+        let index = match self.index_of(name) {
+            Some(index) => index,
+            None => return None,
+        };
+
+        if let Pass::BuiltIn(_) = self.passes[index] {
+            return None;
+        }
+
+        match self.passes.remove(index) {
+            Pass::Custom(pass) => Some(pass),
+            Pass::BuiltIn(_) => unreachable!(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> ::std::slice::IterMut<Pass> {
+        self.passes.iter_mut()
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.passes.iter().position(|pass| pass.name() == name)
+    }
+
+    fn insert(&mut self, index: usize, pass: Box<RenderPass>) -> Result<(), PassError> {
+        if self.index_of(pass.name()).is_some() {
+            return Err(PassError::DuplicateName(String::from(pass.name())));
+        }
+
+        // Every input must be written by an earlier pass.
+        let mut available = vec![RenderTarget::Backbuffer];
+        for earlier in &self.passes[..index] {
+            available.extend(earlier.outputs());
+        }
+
+        for input in pass.inputs() {
+            if !available.contains(&input) {
+                return Err(PassError::MissingInput {
+                    pass: String::from(pass.name()),
+                    input: input,
+                });
+            }
+        }
+
+        self.passes.insert(index, Pass::Custom(pass));
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassError {
+    /// Indicates that there's no pass with the specified name.
+    PassNotFound(String),
+
+    /// Indicates that a pass with the same name is already in the pass list.
+    DuplicateName(String),
+
+    /// Indicates that a pass reads a render target that isn't written by any earlier pass.
+    MissingInput {
+        pass: String,
+        input: RenderTarget,
+    },
+}
+
+impl Display for PassError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            PassError::PassNotFound(ref name) => write!(formatter, "No pass named \"{}\"", name),
+            PassError::DuplicateName(ref name) => {
+                write!(formatter, "A pass named \"{}\" already exists", name)
+            },
+            PassError::MissingInput { ref pass, ref input } => write!(
+                formatter,
+                "Pass \"{}\" reads {:?}, which isn't written by any earlier pass",
+                pass,
+                input,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPass {
+        name: String,
+        inputs: Vec<RenderTarget>,
+        outputs: Vec<RenderTarget>,
+    }
+
+    impl RenderPass for TestPass {
+        fn name(&self) -> &str {
+            &*self.name
+        }
+
+        fn inputs(&self) -> Vec<RenderTarget> {
+            self.inputs.clone()
+        }
+
+        fn outputs(&self) -> Vec<RenderTarget> {
+            self.outputs.clone()
+        }
+
+        fn execute(&mut self, _context: &PassContext) {}
+    }
+
+    fn test_pass(
+        name: &str,
+        inputs: Vec<RenderTarget>,
+        outputs: Vec<RenderTarget>,
+    ) -> Box<RenderPass> {
+        Box::new(TestPass {
+            name: String::from(name),
+            inputs: inputs,
+            outputs: outputs,
+        })
+    }
+
+    #[test]
+    fn ordering() {
+        let mut passes = PassList::new();
+        let outline = test_pass("outline", vec![RenderTarget::SceneDepth], vec![]);
+        passes.insert_after("opaque", outline).unwrap();
+        let fog = test_pass("fog", vec![], vec![RenderTarget::Backbuffer]);
+        passes.insert_before("post", fog).unwrap();
+        passes.insert_before("shadow", test_pass("setup", vec![], vec![])).unwrap();
+
+        assert_eq!(
+            vec!["setup", "shadow", "opaque", "outline", "transparent", "fog", "post"],
+            passes.names(),
+        );
+
+        assert!(passes.remove("outline").is_some());
+        assert!(passes.remove("opaque").is_none());
+        assert_eq!(
+            vec!["setup", "shadow", "opaque", "transparent", "fog", "post"],
+            passes.names(),
+        );
+
+        assert_eq!(
+            Err(PassError::PassNotFound(String::from("missing"))),
+            passes.insert_after("missing", test_pass("late", vec![], vec![])),
+        );
+    }
+
+    #[test]
+    fn duplicate_name() {
+        let mut passes = PassList::new();
+        passes.insert_after("opaque", test_pass("outline", vec![], vec![])).unwrap();
+
+        assert_eq!(
+            Err(PassError::DuplicateName(String::from("outline"))),
+            passes.insert_after("transparent", test_pass("outline", vec![], vec![])),
+        );
+        assert_eq!(
+            Err(PassError::DuplicateName(String::from("opaque"))),
+            passes.insert_before("post", test_pass("opaque", vec![], vec![])),
+        );
+        assert_eq!(5, passes.names().len());
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let mut passes = PassList::new();
+        let glow = RenderTarget::Custom(String::from("glow"));
+
+        // Scene depth is only written by the opaque pass.
+        let outline = test_pass("outline", vec![RenderTarget::SceneDepth], vec![]);
+        assert_eq!(
+            Err(PassError::MissingInput {
+                pass: String::from("outline"),
+                input: RenderTarget::SceneDepth,
+            }),
+            passes.insert_before("opaque", outline),
+        );
+
+        // Custom targets must be written by an earlier custom pass.
+        assert_eq!(
+            Err(PassError::MissingInput {
+                pass: String::from("bloom"),
+                input: glow.clone(),
+            }),
+            passes.insert_after("opaque", test_pass("bloom", vec![glow.clone()], vec![])),
+        );
+        passes.insert_after("opaque", test_pass("glow", vec![], vec![glow.clone()])).unwrap();
+        passes.insert_after("glow", test_pass("bloom", vec![glow.clone()], vec![])).unwrap();
+
+        assert_eq!(
+            vec!["shadow", "opaque", "glow", "bloom", "transparent", "post"],
+            passes.names(),
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Shadow rendering configuration.
+//!
+//! Lights only cast shadows if they have been enabled with `Light::set_cast_shadows()`. The
+//! quality of the shadows for all lights is controlled through the renderer's `ShadowSettings`,
+//! see `Renderer::set_shadow_settings()`.
+
+/// The maximum number of cascades supported for directional light shadows.
+pub const MAX_CASCADES: usize = 4;
+
+/// The maximum number of point lights that can cast shadows at once.
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+/// Quality settings for shadow maps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+    /// The width and height in texels of each shadow map.
+    pub resolution: usize,
+
+    /// The depth offset applied when comparing against the shadow map.
+    ///
+    /// Too small a bias causes "shadow acne" where surfaces shadow themselves, and too large a
+    /// bias causes shadows to detach from the objects casting them.
+    pub bias: f32,
+
+    /// The number of shadow maps used for directional lights, from 1 to `MAX_CASCADES`.
+    ///
+    /// Each cascade covers twice the distance of the previous one, so nearby shadows get more
+    /// resolution than distant ones.
+    pub cascade_count: usize,
+
+    /// The distance from the camera covered by directional light shadows.
+    pub distance: f32,
+
+    /// The width and height in texels of each face of a point light's shadow cube map.
+    pub point_resolution: usize,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> ShadowSettings {
+        ShadowSettings {
+            resolution: 2048,
+            bias: 0.002,
+            cascade_count: 3,
+            distance: 50.0,
+            point_resolution: 512,
+        }
+    }
+}
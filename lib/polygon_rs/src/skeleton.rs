@@ -0,0 +1,119 @@
+//! Skeletons for skinned meshes.
+//!
+//! A skeleton is a hierarchy of joints. Each vertex of a skinned mesh references up to 4 joints
+//! with a weight for each (see `Vertex::joint_indices` and `Vertex::joint_weights`), and is
+//! moved by the weighted sum of those joints' transforms. To animate a skinned mesh register a
+//! skeleton with `Renderer::register_skeleton()`, attach it to a mesh instance with
+//! `MeshInstance::set_skeleton()`, and update the joints' local transforms each frame through
+//! `Renderer::get_skeleton_mut()`.
+
+use math::*;
+
+/// The maximum number of joints in a skeleton.
+pub const MAX_JOINTS: usize = 64;
+
+/// A single joint in a skeleton.
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: String,
+
+    /// The index of the joint's parent, which must come before the joint in the skeleton.
+    pub parent: Option<usize>,
+
+    /// The joint's transform relative to its parent in the bind pose, or relative to the mesh
+    /// for root joints.
+    pub bind_transform: Matrix4,
+
+    /// The transform from mesh space to the joint's space in the bind pose.
+    pub inverse_bind_matrix: Matrix4,
+}
+
+/// A hierarchy of joints along with their current pose.
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+    local_transforms: Vec<Matrix4>,
+}
+
+impl Skeleton {
+    /// Creates a skeleton in its bind pose.
+    ///
+    /// # Panics
+    ///
+    /// - If there are more than `MAX_JOINTS` joints.
+    /// - If a joint's parent doesn't come before it.
+    pub fn new(joints: Vec<Joint>) -> Skeleton {
+        assert!(
+            joints.len() <= MAX_JOINTS,
+            "Skeleton has {} joints, but at most {} are supported",
+            joints.len(),
+            MAX_JOINTS);
+
+        for (index, joint) in joints.iter().enumerate() {
+            if let Some(parent) = joint.parent {
+                assert!(
+                    parent < index,
+                    "Joint {} (\"{}\") has parent {}, but parents must come before their children",
+                    index,
+                    joint.name,
+                    parent);
+            }
+        }
+
+        let local_transforms = joints.iter().map(|joint| joint.bind_transform).collect();
+        Skeleton {
+            joints: joints,
+            local_transforms: local_transforms,
+        }
+    }
+
+    /// Gets the joints in the skeleton.
+    pub fn joints(&self) -> &[Joint] {
+        &*self.joints
+    }
+
+    /// Gets the index of the joint named `name`.
+    pub fn joint_index(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    /// Gets the current transform of a joint relative to its parent.
+    pub fn local_transform(&self, joint: usize) -> Matrix4 {
+        self.local_transforms[joint]
+    }
+
+    /// Sets the current transform of a joint relative to its parent.
+    pub fn set_local_transform(&mut self, joint: usize, transform: Matrix4) {
+        self.local_transforms[joint] = transform;
+    }
+
+    /// Returns every joint to its bind pose.
+    pub fn reset_to_bind_pose(&mut self) {
+        for (local_transform, joint) in self.local_transforms.iter_mut().zip(&self.joints) {
+            *local_transform = joint.bind_transform;
+        }
+    }
+
+    /// Calculates the skinning matrix for each joint, transforming vertices in mesh space from
+    /// the bind pose to the current pose.
+    pub fn palette(&self) -> Vec<Matrix4> {
+        let mut world_transforms = Vec::<Matrix4>::with_capacity(self.joints.len());
+        for (joint, &local_transform) in self.joints.iter().zip(&self.local_transforms) {
+            let world_transform = match joint.parent {
+                Some(parent) => world_transforms[parent] * local_transform,
+                None => local_transform,
+            };
+            world_transforms.push(world_transform);
+        }
+
+        world_transforms
+            .iter()
+            .zip(&self.joints)
+            .map(|(&world_transform, joint)| world_transform * joint.inverse_bind_matrix)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SkeletonId(usize);
+derive_Counter!(SkeletonId);
@@ -0,0 +1,36 @@
+//! Rendering statistics, see `Renderer::frame_stats()`.
+
+use std::time::Duration;
+
+/// Statistics measured while drawing a frame.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FrameStats {
+    /// The GPU time spent in each render pass, in the order the passes were executed.
+    ///
+    /// Each pass is listed once, with its time summed over all cameras.
+    pub passes: Vec<PassStats>,
+}
+
+impl FrameStats {
+    /// Returns the total GPU time spent in render passes.
+    pub fn gpu_time(&self) -> Duration {
+        self.passes
+            .iter()
+            .fold(Duration::new(0, 0), |total, pass| total + pass.gpu_time)
+    }
+
+    /// Returns the statistics for the pass named `name`, if it was executed.
+    pub fn pass(&self, name: &str) -> Option<&PassStats> {
+        self.passes.iter().find(|pass| pass.name == name)
+    }
+}
+
+/// Statistics for a single render pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassStats {
+    /// The name of the pass, see `Renderer::pass_names()`.
+    pub name: String,
+
+    /// The time the GPU spent executing the pass.
+    pub gpu_time: Duration,
+}
@@ -0,0 +1,59 @@
+//! Texture streaming, see `Renderer::register_streaming_texture()`.
+//!
+//! Streaming textures are loaded from KTX or DDS files on a background thread, so registering
+//! one never blocks on reading the file. Once a file has been parsed its mip levels are handed to
+//! the renderer smallest first, and the renderer uploads them over the following frames. Each
+//! frame at most `StreamingSettings::upload_budget` bytes are uploaded across all streaming
+//! textures, with large levels split into bands of rows, so that loading a large world doesn't
+//! cause a hitch.
+//!
+//! A streaming texture samples as black until its smallest level has been uploaded. After that it
+//! always samples the most detailed level that has been uploaded completely, so textures start out
+//! blurry and sharpen as their larger levels arrive.
+//!
+//! A texture that can't be loaded stays black, and the reason is reported by
+//! `Renderer::take_streaming_errors()`.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Settings for texture streaming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamingSettings {
+    /// The largest number of bytes of texture data uploaded each frame.
+    ///
+    /// At least one row of texels, or one row of blocks for compressed textures, is uploaded each
+    /// frame while data is waiting, even if it's larger than the budget.
+    pub upload_budget: usize,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> StreamingSettings {
+        StreamingSettings {
+            upload_budget: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// The reason a streaming texture couldn't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamingError {
+    /// The file couldn't be read, isn't a valid KTX or DDS file, or holds a kind of texture that
+    /// can't be streamed.
+    Load(String),
+
+    /// The GPU storage for the texture couldn't be allocated.
+    Allocation(String),
+}
+
+impl Display for StreamingError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            StreamingError::Load(ref reason) => {
+                write!(formatter, "Failed to load texture: {}", reason)
+            },
+            StreamingError::Allocation(ref reason) => {
+                write!(formatter, "Failed to allocate texture: {}", reason)
+            },
+        }
+    }
+}
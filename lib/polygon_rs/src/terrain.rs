@@ -0,0 +1,490 @@
+//! Heightmap terrain.
+//!
+//! A `Terrain` turns a `Heightmap` into a grid of square chunks when it's registered with the
+//! renderer (see `Renderer::register_terrain()`). Each chunk becomes a regular mesh instance with
+//! its own anchor, attached to the terrain's anchor, so chunks are frustum and occlusion culled
+//! individually like any other mesh instance.
+//!
+//! Every chunk is generated at several levels of detail, each one skipping every other sample of
+//! the previous level (geo-mipmapping), and uses a `LodGroup` to pick its level based on its size
+//! on screen. Chunks are surrounded by skirts hanging below their edges, so the cracks between
+//! neighboring chunks at different levels of detail aren't visible. Normals are always computed
+//! from the full resolution heightmap, so lighting stays detailed at lower levels.
+//!
+//! The built-in terrain material (see `Renderer::terrain_material()`) blends up to four texture
+//! layers using a splat map, which covers the whole terrain once:
+//!
+//! - `layer0` is the base layer, which is visible everywhere the other layers aren't.
+//! - `layer1`, `layer2`, and `layer3` are blended on top of it in order, weighted by the red,
+//!   green, and blue channels of `splat_map` respectively.
+//! - `layer_scale` is the number of times the layers repeat across the terrain.
+//!
+//! Terrains are static: the heightmap can't be changed once the terrain is registered.
+
+use anchor::AnchorId;
+use geometry::mesh::{Mesh, MeshBuilder, MeshIndex};
+use material::Material;
+use math::*;
+use mesh_instance::MeshInstanceId;
+use std::f32;
+use texture::{DataFormat, Texture2d, TextureData};
+
+/// A grid of height samples, each one between 0 and 1.
+///
+/// Samples are stored row by row, with `x` increasing along each row and `z` increasing from
+/// row to row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightmap {
+    width: usize,
+    depth: usize,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Creates a heightmap from `width * depth` height samples.
+    ///
+    /// # Panics
+    ///
+    /// - If `width` or `depth` is less than 2.
+    /// - If the length of `heights` isn't `width * depth`.
+    pub fn new(width: usize, depth: usize, heights: Vec<f32>) -> Heightmap {
+        assert!(width >= 2 && depth >= 2, "Heightmap must be at least 2x2 samples, but was {}x{}", width, depth);
+        assert!(
+            heights.len() == width * depth,
+            "Heightmap of {}x{} samples needs {} heights, but got {}",
+            width,
+            depth,
+            width * depth,
+            heights.len(),
+        );
+
+        Heightmap {
+            width: width,
+            depth: depth,
+            heights: heights,
+        }
+    }
+
+    /// Creates a heightmap from the red channel of a texture.
+    ///
+    /// The first row of the texture's data becomes the first row of the heightmap, so the
+    /// heightmap lines up with textures sampled using the terrain's texcoords.
+    ///
+    /// # Panics
+    ///
+    /// - If the texture is less than 2x2 pixels.
+    pub fn from_texture(texture: &Texture2d) -> Heightmap {
+        let is_bgr = match texture.format() {
+            DataFormat::Bgr | DataFormat::Bgra => true,
+            DataFormat::Rgb | DataFormat::Rgba => false,
+        };
+
+        let heights = match *texture.data() {
+            TextureData::f32(ref data) => {
+                let channels = data.len() / (texture.width() * texture.height());
+                data.chunks(channels).map(|pixel| pixel[0]).collect()
+            },
+            TextureData::u8(ref data) => {
+                let channels = data.len() / (texture.width() * texture.height());
+                data.chunks(channels).map(|pixel| pixel[0] as f32 / 255.0).collect()
+            },
+            TextureData::u8x3(ref data) => data.iter().map(|&(first, _, third)| {
+                let red = if is_bgr { third } else { first };
+                red as f32 / 255.0
+            }).collect(),
+            TextureData::u8x4(ref data) => data.iter().map(|&(first, _, third, _)| {
+                let red = if is_bgr { third } else { first };
+                red as f32 / 255.0
+            }).collect(),
+        };
+
+        Heightmap::new(texture.width(), texture.height(), heights)
+    }
+
+    /// Gets the number of samples along the x axis.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the number of samples along the z axis.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the height sample at the specified coordinates.
+    ///
+    /// # Panics
+    ///
+    /// - If `x` or `z` is outside of the heightmap.
+    pub fn height(&self, x: usize, z: usize) -> f32 {
+        assert!(x < self.width && z < self.depth, "Sample ({}, {}) is outside of the heightmap", x, z);
+        self.heights[z * self.width + x]
+    }
+
+    /// Gets the heightmap's bilinearly interpolated height at the specified fractional sample
+    /// coordinates, which are clamped to the heightmap.
+    pub fn sample(&self, x: f32, z: f32) -> f32 {
+        let x = x.max(0.0).min((self.width - 1) as f32);
+        let z = z.max(0.0).min((self.depth - 1) as f32);
+
+        let x0 = (x.floor() as usize).min(self.width - 2);
+        let z0 = (z.floor() as usize).min(self.depth - 2);
+        let tx = x - x0 as f32;
+        let tz = z - z0 as f32;
+
+        let near = self.height(x0, z0) * (1.0 - tx) + self.height(x0 + 1, z0) * tx;
+        let far = self.height(x0, z0 + 1) * (1.0 - tx) + self.height(x0 + 1, z0 + 1) * tx;
+        near * (1.0 - tz) + far * tz
+    }
+}
+
+/// A heightmap terrain, see the module docs.
+#[derive(Debug, Clone)]
+pub struct Terrain {
+    heightmap: Heightmap,
+    material: Material,
+    size: Vector3,
+    chunk_size: usize,
+    lod_levels: usize,
+    anchor: Option<AnchorId>,
+    chunks: Vec<MeshInstanceId>,
+}
+
+impl Terrain {
+    /// Creates a terrain from a heightmap, drawn with `material`.
+    ///
+    /// By default each cell of the heightmap is 1 unit wide and deep, heights range from 0 to 1,
+    /// chunks are 32 cells on a side, and chunks have 4 levels of detail.
+    pub fn new(heightmap: Heightmap, material: Material) -> Terrain {
+        let size = Vector3::new(
+            (heightmap.width() - 1) as f32,
+            1.0,
+            (heightmap.depth() - 1) as f32,
+        );
+
+        Terrain {
+            heightmap: heightmap,
+            material: material,
+            size: size,
+            chunk_size: 32,
+            lod_levels: 4,
+            anchor: None,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Gets the terrain's heightmap.
+    pub fn heightmap(&self) -> &Heightmap {
+        &self.heightmap
+    }
+
+    /// Gets the material every chunk of the terrain is drawn with.
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Gets the size of the terrain, see `set_size()`.
+    pub fn size(&self) -> Vector3 {
+        self.size
+    }
+
+    /// Sets the size of the terrain in the space of its anchor.
+    ///
+    /// The terrain is centered on its anchor along the x and z axes, and a height sample of 0 is
+    /// at y = 0 while a height sample of 1 is at y = `size.y`.
+    ///
+    /// # Panics
+    ///
+    /// - If any component of `size` isn't positive.
+    pub fn set_size(&mut self, size: Vector3) {
+        assert!(
+            size.x > 0.0 && size.y > 0.0 && size.z > 0.0,
+            "Terrain size must be positive, but was {:?}",
+            size,
+        );
+        self.size = size;
+    }
+
+    /// Gets the number of heightmap cells along each side of a chunk.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Sets the number of heightmap cells along each side of a chunk.
+    ///
+    /// Chunks at the far edges of the terrain are smaller if the heightmap doesn't divide evenly.
+    ///
+    /// # Panics
+    ///
+    /// - If `chunk_size` isn't a power of two.
+    /// - If `chunk_size` is too small for the number of LOD levels, see `set_lod_levels()`.
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        assert!(chunk_size.is_power_of_two(), "Chunk size must be a power of two, but was {}", chunk_size);
+        assert_lod_levels(chunk_size, self.lod_levels);
+        self.chunk_size = chunk_size;
+    }
+
+    /// Gets the number of levels of detail each chunk is generated at.
+    pub fn lod_levels(&self) -> usize {
+        self.lod_levels
+    }
+
+    /// Sets the number of levels of detail each chunk is generated at.
+    ///
+    /// Level `n` uses every `2^n`th heightmap sample, so the least detailed level must still have
+    /// at least one cell per chunk.
+    ///
+    /// # Panics
+    ///
+    /// - If `lod_levels` is 0.
+    /// - If `2^(lod_levels - 1)` is greater than the chunk size.
+    pub fn set_lod_levels(&mut self, lod_levels: usize) {
+        assert_lod_levels(self.chunk_size, lod_levels);
+        self.lod_levels = lod_levels;
+    }
+
+    /// Attaches the terrain to the specified anchor.
+    ///
+    /// Terrains must have an anchor when they're registered, and the chunks stay attached to it
+    /// afterwards.
+    pub fn set_anchor(&mut self, anchor_id: AnchorId) {
+        self.anchor = Some(anchor_id);
+    }
+
+    /// Gets the anchor the terrain is attached to.
+    pub fn anchor(&self) -> Option<AnchorId> {
+        self.anchor
+    }
+
+    /// Gets the mesh instances of the terrain's chunks.
+    ///
+    /// The chunks are created when the terrain is registered, so this is empty before then.
+    pub fn chunks(&self) -> &[MeshInstanceId] {
+        &self.chunks
+    }
+
+    /// Gets the height of the terrain surface at the specified position in the space of its
+    /// anchor, ignoring the position's y coordinate.
+    ///
+    /// Positions outside of the terrain get the height of the nearest edge.
+    pub fn height_at(&self, position: Point) -> f32 {
+        let x = (position.x / self.size.x + 0.5) * (self.heightmap.width() - 1) as f32;
+        let z = (position.z / self.size.z + 0.5) * (self.heightmap.depth() - 1) as f32;
+        self.heightmap.sample(x, z) * self.size.y
+    }
+
+    pub(crate) fn set_chunks(&mut self, chunks: Vec<MeshInstanceId>) {
+        self.chunks = chunks;
+    }
+
+    /// Splits the heightmap into chunks, row by row.
+    pub(crate) fn chunk_layout(&self) -> Vec<TerrainChunk> {
+        let mut chunks = Vec::new();
+
+        let mut start_z = 0;
+        while start_z < self.heightmap.depth() - 1 {
+            let end_z = (start_z + self.chunk_size).min(self.heightmap.depth() - 1);
+
+            let mut start_x = 0;
+            while start_x < self.heightmap.width() - 1 {
+                let end_x = (start_x + self.chunk_size).min(self.heightmap.width() - 1);
+                chunks.push(TerrainChunk {
+                    start_x: start_x,
+                    end_x: end_x,
+                    start_z: start_z,
+                    end_z: end_z,
+                });
+                start_x = end_x;
+            }
+
+            start_z = end_z;
+        }
+
+        chunks
+    }
+
+    /// Gets the position of a heightmap sample in the space of the terrain's anchor.
+    fn sample_position(&self, x: usize, z: usize) -> Point {
+        Point::new(
+            (x as f32 / (self.heightmap.width() - 1) as f32 - 0.5) * self.size.x,
+            self.heightmap.height(x, z) * self.size.y,
+            (z as f32 / (self.heightmap.depth() - 1) as f32 - 0.5) * self.size.z,
+        )
+    }
+
+    /// Gets the surface normal at a heightmap sample using the slope to its neighbors.
+    fn sample_normal(&self, x: usize, z: usize) -> Vector3 {
+        let left = x.saturating_sub(1);
+        let right = (x + 1).min(self.heightmap.width() - 1);
+        let back = z.saturating_sub(1);
+        let front = (z + 1).min(self.heightmap.depth() - 1);
+
+        let cell_width = self.size.x / (self.heightmap.width() - 1) as f32;
+        let cell_depth = self.size.z / (self.heightmap.depth() - 1) as f32;
+
+        let slope_x = (self.heightmap.height(right, z) - self.heightmap.height(left, z)) * self.size.y
+            / ((right - left) as f32 * cell_width);
+        let slope_z = (self.heightmap.height(x, front) - self.heightmap.height(x, back)) * self.size.y
+            / ((front - back) as f32 * cell_depth);
+
+        Vector3::new(-slope_x, 1.0, -slope_z).normalized()
+    }
+}
+
+fn assert_lod_levels(chunk_size: usize, lod_levels: usize) {
+    assert!(lod_levels > 0, "Terrain must have at least one LOD level");
+    assert!(
+        lod_levels - 1 < 64 && 1 << (lod_levels - 1) <= chunk_size,
+        "Chunks of {} cells are too small for {} LOD levels",
+        chunk_size,
+        lod_levels,
+    );
+}
+
+/// The range of heightmap samples covered by a chunk, with both ends inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TerrainChunk {
+    pub start_x: usize,
+    pub end_x: usize,
+    pub start_z: usize,
+    pub end_z: usize,
+}
+
+impl TerrainChunk {
+    /// Gets the center of the chunk at y = 0, in the space of the terrain's anchor.
+    ///
+    /// Chunk meshes are built relative to this point.
+    pub fn center(&self, terrain: &Terrain) -> Point {
+        let min = terrain.sample_position(self.start_x, self.start_z);
+        let max = terrain.sample_position(self.end_x, self.end_z);
+        Point::new((min.x + max.x) * 0.5, 0.0, (min.z + max.z) * 0.5)
+    }
+
+    /// Gets the radius of a sphere around the chunk's center containing the whole chunk.
+    pub fn radius(&self, terrain: &Terrain) -> f32 {
+        let center = self.center(terrain);
+
+        let mut radius = 0.0_f32;
+        for z in self.start_z..self.end_z + 1 {
+            for x in self.start_x..self.end_x + 1 {
+                let offset = terrain.sample_position(x, z) - center;
+                radius = radius.max(offset.magnitude());
+            }
+        }
+
+        radius
+    }
+
+    /// Builds the chunk's mesh at the specified level of detail, skirts included.
+    pub fn build_mesh(&self, terrain: &Terrain, lod: usize) -> Mesh {
+        let step = 1 << lod;
+        let xs = sample_range(self.start_x, self.end_x, step);
+        let zs = sample_range(self.start_z, self.end_z, step);
+
+        let center = self.center(terrain);
+        let width = terrain.heightmap.width();
+        let depth = terrain.heightmap.depth();
+
+        let mut positions = Vec::with_capacity(xs.len() * zs.len());
+        let mut normals = Vec::with_capacity(xs.len() * zs.len());
+        let mut texcoords = Vec::with_capacity(xs.len() * zs.len());
+        let mut indices = Vec::new();
+
+        let mut min_height = f32::INFINITY;
+        let mut max_height = f32::NEG_INFINITY;
+        for &z in &zs {
+            for &x in &xs {
+                let position = terrain.sample_position(x, z);
+                min_height = min_height.min(position.y);
+                max_height = max_height.max(position.y);
+
+                positions.push(Point::from(position - center));
+                normals.push(terrain.sample_normal(x, z));
+                texcoords.push(Vector2::new(
+                    x as f32 / (width - 1) as f32,
+                    z as f32 / (depth - 1) as f32,
+                ));
+            }
+        }
+
+        // Wound counter-clockwise when seen from above.
+        let row = xs.len();
+        for iz in 0..zs.len() - 1 {
+            for ix in 0..xs.len() - 1 {
+                let near_left = (iz * row + ix) as MeshIndex;
+                let near_right = near_left + 1;
+                let far_left = near_left + row as MeshIndex;
+                let far_right = far_left + 1;
+
+                indices.extend_from_slice(&[
+                    near_left, far_left, near_right,
+                    near_right, far_left, far_right,
+                ]);
+            }
+        }
+
+        // Neighboring chunks can't be further apart at their shared edge than the height range of
+        // the chunk, so skirts that deep always hide the cracks. Each side of the chunk is walked
+        // in the direction that makes its skirt face outwards.
+        let skirt_depth = max_height - min_height;
+        let last_x = xs.len() - 1;
+        let last_z = zs.len() - 1;
+        let sides: [Vec<usize>; 4] = [
+            (0..xs.len()).rev().collect(),
+            (0..xs.len()).map(|ix| last_z * row + ix).collect(),
+            (0..zs.len()).map(|iz| iz * row).collect(),
+            (0..zs.len()).rev().map(|iz| iz * row + last_x).collect(),
+        ];
+
+        for side in &sides {
+            let skirt_start = positions.len();
+            for &top in side {
+                let mut position = positions[top];
+                position.y -= skirt_depth;
+
+                let normal = normals[top];
+                let texcoord = texcoords[top];
+                positions.push(position);
+                normals.push(normal);
+                texcoords.push(texcoord);
+            }
+
+            for offset in 0..side.len() - 1 {
+                let top = side[offset] as MeshIndex;
+                let next_top = side[offset + 1] as MeshIndex;
+                let bottom = (skirt_start + offset) as MeshIndex;
+                let next_bottom = bottom + 1;
+
+                indices.extend_from_slice(&[
+                    top, bottom, next_top,
+                    next_top, bottom, next_bottom,
+                ]);
+            }
+        }
+
+        MeshBuilder::new()
+            .set_position_data(&*positions)
+            .set_normal_data(&*normals)
+            .set_texcoord_data(&*texcoords)
+            .set_indices(&*indices)
+            .build()
+            .expect("Generated terrain chunk mesh is invalid")
+    }
+}
+
+/// Gets every `step`th sample from `start` to `end`, always including `end`.
+fn sample_range(start: usize, end: usize, step: usize) -> Vec<usize> {
+    let mut samples = Vec::new();
+    let mut sample = start;
+    while sample < end {
+        samples.push(sample);
+        sample += step;
+    }
+    samples.push(end);
+    samples
+}
+
+/// Identifies a terrain that has been registered with the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TerrainId(usize);
+derive_Counter!(TerrainId);
@@ -14,13 +14,15 @@ derive_Counter!(GpuTexture);
 /// used by any of the rendering backends to send texture data to the GPU. It encapsulates all
 /// relevant information about the texture, including the raw bytes of the texture and information
 /// describing the in-memory layout of that data. It also provides functionality for safely
-/// loading textures from common formats (NOTE: Only bitmap is supported currently).
+/// loading textures from common formats, either from bitmaps or from any image decoded by the
+/// `image` module.
 #[derive(Debug)]
 pub struct Texture2d {
     width: usize,
     height: usize,
     format: DataFormat,
     data: TextureData,
+    color_space: ColorSpace,
 }
 
 impl Texture2d {
@@ -33,6 +35,7 @@ impl Texture2d {
                     height: bitmap.height(),
                     format: DataFormat::Bgr,
                     data: TextureData::u8x3(data.clone()), // TODO: Don't clone the data.
+                    color_space: ColorSpace::Srgb,
                 }
             },
             &BitmapData::Bgra(ref data) => {
@@ -41,6 +44,7 @@ impl Texture2d {
                     height: bitmap.height(),
                     format: DataFormat::Bgra,
                     data: TextureData::u8x4(data.clone()), // TODO: Don't clone the data.
+                    color_space: ColorSpace::Srgb,
                 }
             },
         };
@@ -48,6 +52,28 @@ impl Texture2d {
         texture
     }
 
+    /// Creates a new `Texture2d` from a decoded image, e.g. one loaded with `Image::load()`.
+    ///
+    /// The texture holds the image's RGBA pixels in the sRGB color space.
+    pub fn from_image(image: &Image) -> Texture2d {
+        // Textures are stored starting from the bottom row, like bitmaps.
+        let row_len = image.width() * 4;
+        let mut data = Vec::with_capacity(image.data().len());
+        if row_len > 0 {
+            for row in image.data().chunks(row_len).rev() {
+                data.extend_from_slice(row);
+            }
+        }
+
+        Texture2d {
+            width: image.width(),
+            height: image.height(),
+            format: DataFormat::Rgba,
+            data: TextureData::u8(data),
+            color_space: ColorSpace::Srgb,
+        }
+    }
+
     /// Returns the width of the texture.
     pub fn width(&self) -> usize {
         self.width
@@ -67,6 +93,34 @@ impl Texture2d {
     pub fn data(&self) -> &TextureData {
         &self.data
     }
+
+    /// Gets the color space of the texture's data.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Sets the color space of the texture's data.
+    ///
+    /// Textures are `ColorSpace::Srgb` by default. Textures that hold data rather than colors,
+    /// e.g. normal maps and metallic-roughness maps, must be set to `ColorSpace::Linear` before
+    /// they're registered.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+}
+
+/// The color spaces a texture's data can be stored in.
+///
+/// While gamma-correct rendering is enabled (see `Renderer::set_gamma_correct()`) sRGB textures
+/// are converted to linear values when they're sampled, so that shading is done in linear space.
+/// Linear textures are always sampled as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Gamma-encoded colors, which is how most images are authored.
+    Srgb,
+
+    /// Values that are used as is, e.g. normals, roughness, or already linear colors.
+    Linear,
 }
 
 /// An enum representing the supported data formats for a texture.
@@ -95,3 +149,84 @@ pub enum TextureData {
     u8x3(Vec<(u8, u8, u8)>),
     u8x4(Vec<(u8, u8, u8, u8)>),
 }
+
+/// An image read back from the GPU, e.g. with `Renderer::capture_frame()`.
+///
+/// Pixels are stored as RGBA with 8 bits per channel, row by row starting from the top-left
+/// corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image from RGBA pixel data, stored row by row starting from the top-left corner.
+    ///
+    /// # Panics
+    ///
+    /// - If `data` doesn't hold exactly `width * height` pixels.
+    pub fn new(width: usize, height: usize, data: Vec<u8>) -> Image {
+        assert!(
+            data.len() == width * height * 4,
+            "Image data has the wrong size, expected {} bytes but got {}",
+            width * height * 4,
+            data.len(),
+        );
+
+        Image {
+            width: width,
+            height: height,
+            data: data,
+        }
+    }
+
+    /// Creates an image from RGBA pixel data stored row by row starting from the bottom-left
+    /// corner, the order used by OpenGL.
+    ///
+    /// # Panics
+    ///
+    /// - If `data` doesn't hold exactly `width * height` pixels.
+    pub fn from_bottom_up(width: usize, height: usize, data: Vec<u8>) -> Image {
+        // An image without columns has no rows to flip, and `chunks()` can't split it into rows.
+        let row_len = width * 4;
+        if row_len == 0 {
+            return Image::new(width, height, data);
+        }
+
+        let mut flipped = Vec::with_capacity(data.len());
+        for row in data.chunks(row_len).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        Image::new(width, height, flipped)
+    }
+
+    /// Returns the width of the image.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the image.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the raw RGBA pixel data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Gets the color of the pixel in column `x` and row `y`, counting from the top-left corner.
+    ///
+    /// # Panics
+    ///
+    /// - If `(x, y)` is outside of the image.
+    pub fn pixel(&self, x: usize, y: usize) -> (u8, u8, u8, u8) {
+        assert!(x < self.width && y < self.height, "Pixel ({}, {}) is outside of the image", x, y);
+
+        let index = (y * self.width + x) * 4;
+        (self.data[index], self.data[index + 1], self.data[index + 2], self.data[index + 3])
+    }
+}
@@ -1,3 +1,5 @@
+use std::f32::{MAX, MIN};
+use std::mem;
 use std::slice::Iter;
 use std::iter::Zip;
 
@@ -8,7 +10,11 @@ use collections::EntityMap;
 use component::TransformManager;
 use scene::*;
 use ecs::*;
-use super::{CachedCollider, ColliderManager, Sphere};
+use polygon::camera::Ray;
+use super::{CachedCollider, ColliderManager, LayerMask, RaycastHit, Sphere};
+use super::contact::{self, Contact};
+use super::sweep;
+use super::grid_collision::{GridSettings, QueryGrid};
 use debug_draw;
 
 // TODO: Build a custom BVH manager that automatically constructs hierarchy.
@@ -23,6 +29,13 @@ pub struct BoundingVolumeManager {
 
     longest_axis: f32,
     collision_region: AABB,
+
+    /// The settings for the grid, and the size of each level's cells calculated from them.
+    grid_settings: GridSettings,
+    cell_sizes: Vec<f32>,
+
+    /// The grid used for scene queries, rebuilt in `bvh_update()`.
+    grid: QueryGrid,
 }
 
 impl BoundingVolumeManager {
@@ -37,6 +50,11 @@ impl BoundingVolumeManager {
                 min: Point::min(),
                 max: Point::max(),
             },
+
+            grid_settings: GridSettings::default(),
+            cell_sizes: Vec::new(),
+
+            grid: QueryGrid::new(),
         }
     }
 
@@ -110,6 +128,28 @@ impl BoundingVolumeManager {
     pub fn collision_region(&self) -> AABB {
         self.collision_region
     }
+
+    pub fn grid_settings(&self) -> GridSettings {
+        self.grid_settings
+    }
+
+    pub fn set_grid_settings(&mut self, grid_settings: GridSettings) {
+        self.grid_settings = grid_settings;
+    }
+
+    /// Gets the size of the cells on each level of the grid, calculated in `bvh_update()`.
+    pub fn cell_sizes(&self) -> &[f32] {
+        &self.cell_sizes
+    }
+
+    pub fn grid(&self) -> &QueryGrid {
+        &self.grid
+    }
+
+    fn rebuild_grid(&mut self) {
+        self.cell_sizes = self.grid_settings.cell_sizes(self.longest_axis);
+        self.grid.rebuild(&self.components, &self.cell_sizes);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -117,6 +157,17 @@ pub struct BoundVolume {
     pub entity: Entity,
     pub aabb: AABB,
     pub collider: CachedCollider,
+
+    /// The collision layers of the volume's collider, see `ColliderManager::set_layers()`.
+    pub layers: LayerMask,
+
+    /// Whether the volume uses continuous collision detection, see
+    /// `ColliderManager::set_continuous()`.
+    pub continuous: bool,
+
+    /// How far the collider moved since the previous update. Only tracked for continuous volumes,
+    /// whose `aabb` also covers the collider's previous position.
+    pub displacement: Vector3,
 }
 
 impl BoundVolume {
@@ -131,13 +182,85 @@ impl BoundVolume {
         false
     }
 
+    /// Generates the contact between this BVH and `other` if they collide.
+    ///
+    /// If either volume is continuous and they don't overlap, the continuous one is swept along
+    /// its movement since the previous update, relative to the other volume, to find collisions
+    /// it passed through.
+    pub fn contact(&self, other: &BoundVolume) -> Option<Contact> {
+        if !self.aabb.test_aabb(&other.aabb) {
+            return None;
+        }
+
+        if self.collider.test(&other.collider) {
+            return Some(contact::contact(&self.collider, &other.collider));
+        }
+
+        if self.can_sweep() {
+            self.sweep_contact(other)
+        } else if other.can_sweep() {
+            other.sweep_contact(self).map(|contact| contact.flipped())
+        } else {
+            None
+        }
+    }
+
+    fn can_sweep(&self) -> bool {
+        match self.collider {
+            CachedCollider::Mesh(_) => false,
+            _ => self.continuous,
+        }
+    }
+
+    /// Sweeps this BVH's collider from its previous position against `other`.
+    fn sweep_contact(&self, other: &BoundVolume) -> Option<Contact> {
+        let displacement = self.displacement - other.displacement;
+        if displacement.is_zero() {
+            return None;
+        }
+
+        let distance = displacement.magnitude();
+        let direction = displacement / distance;
+        let start = self.collider.translated(-displacement);
+
+        // The sweep's normal faces this collider, the contact's faces into the other one.
+        sweep::sweep(&start, direction, distance, &other.collider).map(|hit| {
+            contact::swept(-hit.normal, hit.point, hit.distance / distance)
+        })
+    }
+
+    /// Casts `ray` against this BVH, ignoring it if it isn't in any of the layers in `layer_mask`.
+    pub fn raycast(&self, ray: &Ray, max_distance: f32, layer_mask: LayerMask) -> Option<RaycastHit> {
+        if self.layers & layer_mask == 0 {
+            return None;
+        }
+
+        // Test the AABB first since it's much cheaper than testing the collider.
+        match self.aabb.intersect_ray(ray.origin, ray.direction) {
+            Some((enter, _)) if enter <= max_distance => {},
+            _ => return None,
+        }
+
+        match self.collider.raycast(ray) {
+            Some((distance, normal)) if distance <= max_distance => {
+                Some(RaycastHit {
+                    entity: self.entity,
+                    point: ray.point_at(distance),
+                    normal: normal,
+                    distance: distance,
+                })
+            },
+            _ => None,
+        }
+    }
+
     pub fn debug_draw(&self) {
         debug_draw::box_min_max(self.aabb.min, self.aabb.max);
         self.collider.debug_draw();
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct AABB {
     pub min: Point,
     pub max: Point,
@@ -331,7 +454,23 @@ impl AABB {
                     max: max,
                 }
             },
-            &CachedCollider::Mesh => unimplemented!(),
+            &CachedCollider::Mesh(ref mesh) => mesh.aabb(),
+        }
+    }
+
+    /// Gets the box covering this box as it moves by `offset`.
+    pub fn swept(&self, offset: Vector3) -> AABB {
+        let (start, end) = (self.min.as_vector3(), self.max.as_vector3());
+        let mut min = start;
+        let mut max = end;
+        for axis in 0..3 {
+            min[axis] = min[axis].min(start[axis] + offset[axis]);
+            max[axis] = max[axis].max(end[axis] + offset[axis]);
+        }
+
+        AABB {
+            min: Point::from(min),
+            max: Point::from(max),
         }
     }
 
@@ -340,6 +479,47 @@ impl AABB {
      && test_ranges((self.min.y, self.max.y), (other.min.y, other.max.y))
      && test_ranges((self.min.z, self.max.z), (other.min.z, other.max.z))
     }
+
+    /// Finds where the ray from `origin` along `direction` enters and exits the box.
+    ///
+    /// Returns the distances along the ray, in multiples of `direction`, at which the ray enters
+    /// and exits the box, or `None` if the ray misses the box. The entry distance is negative if
+    /// the ray starts inside the box.
+    pub fn intersect_ray(&self, origin: Point, direction: Vector3) -> Option<(f32, f32)> {
+        let origin = origin.as_vector3();
+        let min = self.min.as_vector3();
+        let max = self.max.as_vector3();
+
+        let mut enter = MIN;
+        let mut exit = MAX;
+        for axis in 0..3 {
+            if direction[axis].abs() < EPSILON {
+                // The ray is parallel to the slab, so it misses unless it starts inside it.
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+            } else {
+                let inv_direction = 1.0 / direction[axis];
+                let mut near = (min[axis] - origin[axis]) * inv_direction;
+                let mut far = (max[axis] - origin[axis]) * inv_direction;
+                if near > far {
+                    mem::swap(&mut near, &mut far);
+                }
+
+                enter = enter.max(near);
+                exit = exit.min(far);
+                if enter > exit {
+                    return None;
+                }
+            }
+        }
+
+        if exit < 0.0 {
+            return None;
+        }
+
+        Some((enter, exit))
+    }
 }
 
 pub fn bvh_update(scene: &Scene, _delta: f32) {
@@ -359,7 +539,16 @@ pub fn bvh_update(scene: &Scene, _delta: f32) {
         let transform = transform_manager.get(entity).unwrap(); // TOOD: Don't panic?
 
         let cached_collider = CachedCollider::from_collider_transform(&*collider, &*transform);
-        let aabb = AABB::from_collider(&cached_collider);
+        let layers = collider_manager.layers(entity);
+
+        // Continuous volumes cover the collider's whole path since the previous update, so that
+        // broadphase finds everything it could have passed through.
+        let continuous = collider_manager.is_continuous(entity);
+        let displacement = match bvh_manager.get(entity) {
+            Some(bvh) if continuous => cached_collider.center() - bvh.collider.center(),
+            _ => Vector3::zero(),
+        };
+        let aabb = AABB::from_collider(&cached_collider).swept(-displacement);
 
         // Update longest axis.
         {
@@ -394,6 +583,9 @@ pub fn bvh_update(scene: &Scene, _delta: f32) {
         if let Some(mut bvh) = bvh_manager.get_mut(entity) {
             bvh.collider = cached_collider;
             bvh.aabb = aabb;
+            bvh.layers = layers;
+            bvh.continuous = continuous;
+            bvh.displacement = displacement;
 
             continue;
         }
@@ -403,9 +595,14 @@ pub fn bvh_update(scene: &Scene, _delta: f32) {
                 entity: entity,
                 aabb: aabb,
                 collider: cached_collider,
+                layers: layers,
+                continuous: continuous,
+                displacement: displacement,
             });
         }
     }
+
+    bvh_manager.rebuild_grid();
 }
 
 fn test_ranges(first: (f32, f32), second: (f32, f32)) -> bool {
@@ -0,0 +1,482 @@
+//! Contact generation for colliding pairs, see `Contact`.
+//!
+//! Narrowphase first tests each candidate pair with `CachedCollider::test()`, and then calls
+//! `contact()` for the pairs that collide to find out how they collide. Different pairs of shapes
+//! use different methods:
+//!
+//! - Sphere vs sphere and sphere vs box are solved exactly from the closest points between the
+//!   shapes. If the sphere's center is inside the box the sphere is pushed out through the
+//!   nearest face.
+//! - Box vs box uses the separating axis test, picking the axis along which the boxes overlap the
+//!   least. The contact points are the corners of each box that are inside the other, or the
+//!   closest points between the two edges if the boxes only touch edge to edge.
+//! - Sphere vs mesh and box vs mesh generate a contact for each triangle the shape touches and
+//!   keep the normal and depth of the deepest one, along with the deepest points of all of them.
+//!
+//! Continuous colliders that pass through another collider during a frame don't overlap it at the
+//! end of the frame, so instead they get a contact with a depth of 0 at the point where the sweep
+//! first touched the other collider, see `Contact::time_of_impact`.
+//!
+//! A contact keeps at most `MAX_CONTACT_POINTS` points, which is enough to describe a box resting
+//! flat on another box or on the ground. When more points are found the deepest ones are kept.
+
+use std::cmp::Ordering;
+
+use math::*;
+use super::{CachedCollider, OBB, Sphere};
+use super::bounding_volume::AABB;
+use super::mesh_collider::{Triangle, TransformedMesh, closest_points_segments};
+use super::sweep::{box_interval, candidate_triangles, triangle_interval};
+
+/// The most points a single contact can have.
+pub const MAX_CONTACT_POINTS: usize = 4;
+
+/// How far a point can be outside a shape and still count as touching it.
+const TOLERANCE: f32 = 1e-4;
+
+/// How a pair of colliders touch each other.
+///
+/// A contact is always described from the point of view of one of the two colliders, referred to
+/// as the first collider. Use `flipped()` to get the contact from the other collider's point of
+/// view.
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    /// The direction from the first collider into the second, in world space.
+    ///
+    /// Moving the second collider by `normal * depth` (or the first by `-normal * depth`)
+    /// separates the colliders.
+    pub normal: Vector3,
+
+    /// How far the colliders overlap along `normal`.
+    pub depth: f32,
+
+    /// How far through the frame's movement the colliders first touched, from 0 at the start of
+    /// the frame to 1 at the end.
+    ///
+    /// This is 1 for colliders that overlap at the end of the frame. It's only less than 1 for
+    /// continuous colliders (see `ColliderManager::set_continuous()`) that passed through the
+    /// other collider during the frame, in which case the points and normal are those of the
+    /// first touch and the depth is 0.
+    pub time_of_impact: f32,
+
+    points: [Point; MAX_CONTACT_POINTS],
+    point_count: usize,
+}
+
+impl Contact {
+    /// Creates a contact from a list of candidate points and how deep each one is, keeping the
+    /// deepest `MAX_CONTACT_POINTS` of them.
+    fn new(normal: Vector3, depth: f32, mut candidates: Vec<(Point, f32)>) -> Contact {
+        candidates.sort_by(|lhs, rhs| rhs.1.partial_cmp(&lhs.1).unwrap_or(Ordering::Equal));
+
+        let mut contact = Contact {
+            normal: normal,
+            depth: depth.max(0.0),
+            time_of_impact: 1.0,
+            points: [Point::origin(); MAX_CONTACT_POINTS],
+            point_count: 0,
+        };
+        for (point, _) in candidates.into_iter().take(MAX_CONTACT_POINTS) {
+            contact.points[contact.point_count] = point;
+            contact.point_count += 1;
+        }
+
+        contact
+    }
+
+    /// The points where the colliders touch, in world space.
+    ///
+    /// There's always at least one point, and at most `MAX_CONTACT_POINTS`.
+    pub fn points(&self) -> &[Point] {
+        &self.points[..self.point_count]
+    }
+
+    /// Gets the same contact from the point of view of the second collider.
+    pub fn flipped(&self) -> Contact {
+        Contact {
+            normal: -self.normal,
+            .. *self
+        }
+    }
+}
+
+/// Creates the contact for a continuous collider that touched another collider at `point` part
+/// way through the frame.
+pub fn swept(normal: Vector3, point: Point, time_of_impact: f32) -> Contact {
+    let mut contact = Contact::new(normal, 0.0, vec![(point, 0.0)]);
+    contact.time_of_impact = time_of_impact;
+    contact
+}
+
+/// Generates the contact between a pair of colliders.
+///
+/// The colliders are expected to be colliding, as reported by `CachedCollider::test()`. Shapes
+/// that are only just touching generate a contact with a depth of 0.
+pub fn contact(first: &CachedCollider, second: &CachedCollider) -> Contact {
+    match (first, second) {
+        (&CachedCollider::Sphere(ref first), &CachedCollider::Sphere(ref second)) => {
+            sphere_sphere(first, second)
+        },
+        (&CachedCollider::Sphere(ref sphere), &CachedCollider::Box(ref obb)) => sphere_obb(sphere, obb),
+        (&CachedCollider::Box(ref obb), &CachedCollider::Sphere(ref sphere)) => sphere_obb(sphere, obb).flipped(),
+        (&CachedCollider::Box(ref first), &CachedCollider::Box(ref second)) => obb_obb(first, second),
+        (&CachedCollider::Sphere(ref sphere), &CachedCollider::Mesh(ref mesh)) => sphere_mesh(sphere, mesh),
+        (&CachedCollider::Mesh(ref mesh), &CachedCollider::Sphere(ref sphere)) => sphere_mesh(sphere, mesh).flipped(),
+        (&CachedCollider::Box(ref obb), &CachedCollider::Mesh(ref mesh)) => obb_mesh(obb, mesh),
+        (&CachedCollider::Mesh(ref mesh), &CachedCollider::Box(ref obb)) => obb_mesh(obb, mesh).flipped(),
+
+        // Mesh colliders don't collide with each other, see the `mesh_collider` module.
+        (&CachedCollider::Mesh(ref first), &CachedCollider::Mesh(ref second)) => {
+            touching(first.center, second.center)
+        },
+    }
+}
+
+fn sphere_sphere(first: &Sphere, second: &Sphere) -> Contact {
+    let offset = second.center - first.center;
+    let distance = offset.magnitude();
+    let normal = direction_or_up(offset);
+    let depth = first.radius + second.radius - distance;
+
+    // Put the point halfway through the overlap.
+    let point = first.center + normal * (first.radius - depth * 0.5);
+    Contact::new(normal, depth, vec![(point, depth)])
+}
+
+fn sphere_obb(sphere: &Sphere, obb: &OBB) -> Contact {
+    let closest = obb.closest_point(sphere.center);
+    let offset = closest - sphere.center;
+    if !offset.is_zero() {
+        let distance = offset.magnitude();
+        let depth = sphere.radius - distance;
+        return Contact::new(offset / distance, depth, vec![(closest, depth)]);
+    }
+
+    // The sphere's center is inside the box, so push it out through the nearest face.
+    let local = (sphere.center - obb.center) * obb.orientation.transpose();
+    let mut face_axis = 0;
+    let mut face_distance = obb.half_widths[0] - local[0].abs();
+    for axis in 1..3 {
+        let distance = obb.half_widths[axis] - local[axis].abs();
+        if distance < face_distance {
+            face_axis = axis;
+            face_distance = distance;
+        }
+    }
+
+    let face_normal = if local[face_axis] < 0.0 {
+        -obb.orientation.col(face_axis)
+    } else {
+        obb.orientation.col(face_axis)
+    };
+
+    let depth = sphere.radius + face_distance;
+    let point = sphere.center + face_normal * face_distance;
+    Contact::new(-face_normal, depth, vec![(point, depth)])
+}
+
+fn obb_obb(first: &OBB, second: &OBB) -> Contact {
+    let first_center = first.center.as_vector3();
+    let second_center = second.center.as_vector3();
+    let first_axes = [first.orientation.col(0), first.orientation.col(1), first.orientation.col(2)];
+    let second_axes = [second.orientation.col(0), second.orientation.col(1), second.orientation.col(2)];
+    let offset = second_center - first_center;
+
+    // Find the axis along which the boxes overlap the least, oriented from the first box to the
+    // second. Remember which edges produced it if it's an edge axis.
+    let mut best: Option<(Vector3, f32, Option<(usize, usize)>)> = None;
+    {
+        let mut test_axis = |axis: Vector3, edges: Option<(usize, usize)>| {
+            // Edges that are (nearly) parallel produce a degenerate axis.
+            if axis.magnitude_squared() < EPSILON {
+                return;
+            }
+
+            let axis = axis.normalized();
+            let (first_min, first_max) = box_interval(first_center, &first_axes, first.half_widths, axis);
+            let (second_min, second_max) = box_interval(second_center, &second_axes, second.half_widths, axis);
+            let overlap = first_max.min(second_max) - first_min.max(second_min);
+
+            // Prefer face axes over edge axes that are only slightly better, since face contacts
+            // are more stable.
+            let bias = if edges.is_some() { TOLERANCE } else { 0.0 };
+            if best.map(|(_, depth, _)| overlap + bias < depth).unwrap_or(true) {
+                let axis = if offset.dot(axis) < 0.0 { -axis } else { axis };
+                best = Some((axis, overlap, edges));
+            }
+        };
+
+        for &axis in first_axes.iter().chain(second_axes.iter()) {
+            test_axis(axis, None);
+        }
+        for first_edge in 0..3 {
+            for second_edge in 0..3 {
+                test_axis(Vector3::cross(first_axes[first_edge], second_axes[second_edge]), Some((first_edge, second_edge)));
+            }
+        }
+    }
+
+    let (normal, depth, edges) = best.expect("Boxes always have a face axis");
+
+    // The corners of each box that are inside the other box, measured by how far they are past
+    // the other box's face.
+    let mut candidates = Vec::new();
+    let (_, first_max) = box_interval(first_center, &first_axes, first.half_widths, normal);
+    for corner in obb_corners(second).iter().filter(|&&corner| obb_contains(first, corner)) {
+        candidates.push((*corner, first_max - corner.as_vector3().dot(normal)));
+    }
+    let (second_min, _) = box_interval(second_center, &second_axes, second.half_widths, normal);
+    for corner in obb_corners(first).iter().filter(|&&corner| obb_contains(second, corner)) {
+        candidates.push((*corner, corner.as_vector3().dot(normal) - second_min));
+    }
+
+    if candidates.is_empty() {
+        let point = match edges {
+            // The boxes touch edge to edge, so use the closest points between the edges of each
+            // box that are furthest into the other box.
+            Some((first_edge, second_edge)) => {
+                let (first_start, first_end) = support_edge(first, first_edge, normal);
+                let (second_start, second_end) = support_edge(second, second_edge, -normal);
+                let (first_point, second_point) =
+                    closest_points_segments(first_start, first_end, second_start, second_end);
+                Point::from((first_point + second_point) * 0.5)
+            },
+
+            // Without any corners inside the other box the closest points will have to do.
+            None => {
+                let first_point = first.closest_point(second.center).as_vector3();
+                let second_point = second.closest_point(first.center).as_vector3();
+                Point::from((first_point + second_point) * 0.5)
+            },
+        };
+        candidates.push((point, depth));
+    }
+
+    Contact::new(normal, depth, candidates)
+}
+
+fn sphere_mesh(sphere: &Sphere, mesh: &TransformedMesh) -> Contact {
+    let center = mesh.to_local_point(sphere.center);
+    let radius = sphere.radius;
+    let bounds = AABB {
+        min: Point::from(center - Vector3::new(radius, radius, radius)),
+        max: Point::from(center + Vector3::new(radius, radius, radius)),
+    };
+
+    // Each triangle touching the sphere contributes its closest point to the sphere's center.
+    let mut deepest: Option<(Vector3, f32)> = None;
+    let mut candidates = Vec::new();
+    for triangle in candidate_triangles(mesh, &bounds) {
+        let closest = triangle.closest_point(center);
+        let offset = closest - center;
+        let distance = offset.magnitude();
+        if distance > radius {
+            continue;
+        }
+
+        // A sphere centered on the triangle is pushed out along the triangle's normal.
+        let normal = if offset.is_zero() { -triangle.normal() } else { offset / distance };
+        let depth = radius - distance;
+        if deepest.map(|(_, deepest)| depth > deepest).unwrap_or(true) {
+            deepest = Some((normal, depth));
+        }
+        candidates.push((mesh.to_world_point(closest), depth));
+    }
+
+    match deepest {
+        Some((normal, depth)) => Contact::new(mesh.to_world_vector(normal), depth, candidates),
+        None => touching(sphere.center, mesh.center),
+    }
+}
+
+fn obb_mesh(obb: &OBB, mesh: &TransformedMesh) -> Contact {
+    let center = mesh.to_local_point(obb.center);
+    let axes = [
+        mesh.to_local_vector(obb.orientation.col(0)),
+        mesh.to_local_vector(obb.orientation.col(1)),
+        mesh.to_local_vector(obb.orientation.col(2)),
+    ];
+
+    let mut extents = Vector3::zero();
+    for axis in 0..3 {
+        extents[axis] =
+            obb.half_widths.x * axes[0][axis].abs()
+          + obb.half_widths.y * axes[1][axis].abs()
+          + obb.half_widths.z * axes[2][axis].abs();
+    }
+    let bounds = AABB {
+        min: Point::from(center - extents),
+        max: Point::from(center + extents),
+    };
+
+    let corners = obb_corners(obb);
+    let local_corners: Vec<Vector3> = corners.iter().map(|&corner| mesh.to_local_point(corner)).collect();
+
+    let mut deepest: Option<(Vector3, f32)> = None;
+    let mut candidates = Vec::new();
+    for triangle in candidate_triangles(mesh, &bounds) {
+        if !triangle.test_box(center, &axes, obb.half_widths) {
+            continue;
+        }
+
+        let (normal, depth) = box_triangle_axis(center, &axes, obb.half_widths, &triangle);
+        if deepest.map(|(_, deepest)| depth > deepest).unwrap_or(true) {
+            deepest = Some((normal, depth));
+        }
+
+        // The corners of the box that are past the triangle and over it.
+        let (triangle_min, _) = triangle_interval(&triangle.vertices(), normal);
+        let mut found = false;
+        for &corner in &local_corners {
+            let corner_depth = corner.dot(normal) - triangle_min;
+            if corner_depth >= -TOLERANCE
+                && (corner - triangle.closest_point(corner)).magnitude() <= depth + TOLERANCE
+            {
+                candidates.push((mesh.to_world_point(corner), corner_depth));
+                found = true;
+            }
+        }
+
+        // The vertices of the triangle that are inside the box, for triangles smaller than it.
+        for &vertex in &triangle.vertices() {
+            let vertex = mesh.to_world_point(vertex);
+            if obb_contains(obb, vertex) {
+                candidates.push((vertex, depth));
+                found = true;
+            }
+        }
+
+        if !found {
+            candidates.push((mesh.to_world_point(triangle.closest_point(center)), depth));
+        }
+    }
+
+    match deepest {
+        Some((normal, depth)) => Contact::new(mesh.to_world_vector(normal), depth, candidates),
+        None => touching(obb.center, mesh.center),
+    }
+}
+
+/// Finds the axis along which a box and a triangle overlap the least, oriented from the box to the
+/// triangle, along with how much they overlap.
+fn box_triangle_axis(center: Vector3, axes: &[Vector3; 3], half_widths: Vector3, triangle: &Triangle) -> (Vector3, f32) {
+    let vertices = triangle.vertices();
+    let edges = [triangle.b - triangle.a, triangle.c - triangle.b, triangle.a - triangle.c];
+    let offset = triangle.centroid() - center;
+
+    let mut best: Option<(Vector3, f32)> = None;
+    {
+        let mut test_axis = |axis: Vector3| {
+            if axis.magnitude_squared() < EPSILON {
+                return;
+            }
+
+            let axis = axis.normalized();
+            let (box_min, box_max) = box_interval(center, axes, half_widths, axis);
+            let (triangle_min, triangle_max) = triangle_interval(&vertices, axis);
+            let overlap = box_max.min(triangle_max) - box_min.max(triangle_min);
+            if best.map(|(_, depth)| overlap < depth).unwrap_or(true) {
+                let axis = if offset.dot(axis) < 0.0 { -axis } else { axis };
+                best = Some((axis, overlap));
+            }
+        };
+
+        test_axis(triangle.normal());
+        for &axis in axes {
+            test_axis(axis);
+        }
+        for &axis in axes {
+            for &edge in &edges {
+                test_axis(Vector3::cross(axis, edge));
+            }
+        }
+    }
+
+    best.expect("Triangles always have a face axis")
+}
+
+/// Gets the corners of a box in world space.
+fn obb_corners(obb: &OBB) -> [Point; 8] {
+    let mut corners = [obb.center; 8];
+    for (index, corner) in corners.iter_mut().enumerate() {
+        for axis in 0..3 {
+            let sign = if index & (1 << axis) == 0 { -1.0 } else { 1.0 };
+            *corner += obb.orientation.col(axis) * obb.half_widths[axis] * sign;
+        }
+    }
+
+    corners
+}
+
+/// Checks if `point` is inside a box, or close enough to count as touching it.
+fn obb_contains(obb: &OBB, point: Point) -> bool {
+    let local = (point - obb.center) * obb.orientation.transpose();
+    (0..3).all(|axis| local[axis].abs() <= obb.half_widths[axis] + TOLERANCE)
+}
+
+/// Gets the edge of a box parallel to its axis `edge_axis` that's furthest along `direction`.
+fn support_edge(obb: &OBB, edge_axis: usize, direction: Vector3) -> (Vector3, Vector3) {
+    let mut middle = obb.center.as_vector3();
+    for axis in (0..3).filter(|&axis| axis != edge_axis) {
+        let axis_direction = obb.orientation.col(axis);
+        let sign = if axis_direction.dot(direction) < 0.0 { -1.0 } else { 1.0 };
+        middle += axis_direction * obb.half_widths[axis] * sign;
+    }
+
+    let half_edge = obb.orientation.col(edge_axis) * obb.half_widths[edge_axis];
+    (middle - half_edge, middle + half_edge)
+}
+
+/// Creates a contact with no depth halfway between two points, for pairs that are colliding
+/// without any of their parts actually overlapping.
+fn touching(first: Point, second: Point) -> Contact {
+    let point = Point::from((first.as_vector3() + second.as_vector3()) * 0.5);
+    Contact::new(direction_or_up(second - first), 0.0, vec![(point, 0.0)])
+}
+
+/// Normalizes `offset`, falling back to up if it's too short to have a direction.
+fn direction_or_up(offset: Vector3) -> Vector3 {
+    if offset.is_zero() {
+        Vector3::up()
+    } else {
+        offset.normalized()
+    }
+}
+
+#[test]
+fn contact_tests() {
+    let first = Sphere { center: Point::origin(), radius: 1.0 };
+    let second = Sphere { center: Point::new(1.5, 0.0, 0.0), radius: 1.0 };
+    let contact = sphere_sphere(&first, &second);
+    assert!((contact.normal - Vector3::new(1.0, 0.0, 0.0)).is_zero(), "Unexpected normal {:?}", contact.normal);
+    assert!((contact.depth - 0.5).is_zero(), "Expected depth 0.5, got {}", contact.depth);
+    assert!((contact.points()[0] - Point::new(0.75, 0.0, 0.0)).is_zero());
+
+    // A unit box resting slightly inside a larger box below it touches at its four bottom corners.
+    let ground = OBB {
+        center: Point::new(0.0, -1.0, 0.0),
+        orientation: Matrix3::identity(),
+        half_widths: Vector3::new(5.0, 1.0, 5.0),
+    };
+    let crate_box = OBB {
+        center: Point::new(0.0, 0.45, 0.0),
+        orientation: Matrix3::identity(),
+        half_widths: Vector3::new(0.5, 0.5, 0.5),
+    };
+    let contact = obb_obb(&ground, &crate_box);
+    assert!((contact.normal - Vector3::new(0.0, 1.0, 0.0)).is_zero(), "Unexpected normal {:?}", contact.normal);
+    assert!((contact.depth - 0.05).abs() < TOLERANCE, "Expected depth 0.05, got {}", contact.depth);
+    assert_eq!(MAX_CONTACT_POINTS, contact.points().len());
+    for point in contact.points() {
+        assert!((point.y + 0.05).abs() < TOLERANCE, "Unexpected contact point {:?}", point);
+    }
+
+    let flipped = contact.flipped();
+    assert!((flipped.normal + contact.normal).is_zero());
+
+    // A sphere whose center is inside a box is pushed out through the nearest face.
+    let sphere = Sphere { center: Point::new(0.0, -0.1, 0.0), radius: 0.5 };
+    let contact = sphere_obb(&sphere, &ground);
+    assert!((contact.normal - Vector3::new(0.0, -1.0, 0.0)).is_zero(), "Unexpected normal {:?}", contact.normal);
+    assert!((contact.depth - 0.6).abs() < TOLERANCE, "Expected depth 0.6, got {}", contact.depth);
+}
@@ -14,20 +14,26 @@
 //! volume's AABB), and if there are other colliders in those cells then they are added to the list
 //! of candidate collisions that gets sent to narrowphase processing.
 //!
-//! For this implementation the grid is represented by a `HashTable<GridCell, Vec<*const BoundVolume>>`,
-//! where the key is the coordinates of the grid and the value is a list of the collision volumes
-//! that have been placed into that cell.
+//! For this implementation each level of the grid is represented by a
+//! `HashTable<GridCell, Vec<*const BoundVolume>>`, where the key is the coordinates of the grid and
+//! the value is a list of the collision volumes that have been placed into that cell.
 //!
 //! As psuedocode the algorithm goes as follows:
 //!
 //! ```rust
 //! for volume in collision_volumes {
 //!     for cell in volume.aabb {
+//!         cell.push(volume);
+//!     }
+//! }
+//!
+//! for volume in collision_volumes {
+//!     for cell in volume.aabb {
 //!         for other_volume in cell {
-//!             candidate_collisions.push(volume, other_volume);
+//!             if other_volume < volume {
+//!                 candidate_collisions.push(volume, other_volume);
+//!             }
 //!         }
-//!
-//!         cell.push(volume);
 //!     }
 //! }
 //! ```
@@ -50,12 +56,21 @@
 //! `<x, y, z>` covers the space from `grid_center + <x, y, z> * cell_size` to `grid_center +
 //! <x + 1, y + 1, z + 1> * cell_size`.
 //!
-//! In order to minimize the number of grid cells that any given collision volume overlaps the cell
-//! size is dynamically updated to be as long as the longest axis of any volume's AABB. This
-//! guarantees that no matter how volumes are positioned or oriented in space no volume can ever
-//! be placed in more than 8 grid cells on a given frame. This helps to minimize the number of
-//! grid lookups needed to perform the broadphase pass at the cost of potentially more candidate
-//! collisions that need to be processed in narrowphase.
+//! In order to minimize the number of grid cells that any given collision volume overlaps the
+//! grid has several levels, each with cells `LEVEL_RATIO` times larger than the level below it.
+//! Each volume is placed in the lowest level whose cells are at least as long as the longest axis
+//! of its AABB, which guarantees that it's placed in at most 8 grid cells of that level. A volume
+//! is tested against the volumes sharing a cell with it on its own level, and against the volumes
+//! in the cells it overlaps on every higher level, so a pair of volumes of different sizes is
+//! tested by the smaller one. Without levels the cells would have to be as large as the largest
+//! volume, so a single large volume (such as the level's terrain) would put every small volume in
+//! the same few cells and produce a huge number of candidate collisions.
+//!
+//! By default the cell size is dynamically updated so that the cells of the highest level are as
+//! long as the longest axis of any volume's AABB. The cell size of the lowest level and the number
+//! of levels can also be configured with `ColliderManager::set_grid_settings()`, in which case
+//! volumes larger than the highest level's cells are placed in as many of its cells as they
+//! overlap.
 //!
 //! Parallel Collision processing
 //! ============================
@@ -85,7 +100,7 @@
 //! workers, however this benefits somewhat from being done in parallel as well, helping to keep
 //! overhead low.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use std::f32::{MAX, MIN};
 use std::{mem, thread};
@@ -100,23 +115,87 @@ use stopwatch::Stopwatch;
 
 use ecs::Entity;
 use super::bounding_volume::*;
+use super::contact::Contact;
 
 const NUM_WORKERS: usize = 8;
 const NUM_WORK_UNITS: usize = 8;
 
 pub type CollisionGrid = HashMap<GridCell, Vec<*const BoundVolume>, FnvHashState>;
 
-/// A collision processor that partitions the space into a regular grid.
+/// How many times larger each level's cells are than the cells of the level below it.
+pub const LEVEL_RATIO: f32 = 4.0;
+
+/// Settings for the grid used by broadphase and scene queries, see
+/// `ColliderManager::set_grid_settings()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridSettings {
+    /// The size of the cells on the grid's lowest level.
+    ///
+    /// If this is `None` the size is picked each frame so that the cells on the highest level
+    /// are as large as the largest collider.
+    pub cell_size: Option<f32>,
+
+    /// The number of levels in the grid.
+    ///
+    /// Each level's cells are `LEVEL_RATIO` times larger than the level below it, and each
+    /// collider goes in the lowest level whose cells are at least as large as it is. More levels
+    /// keep small colliders in small cells when colliders vary a lot in size, at the cost of an
+    /// extra lookup per level for each collider.
+    pub levels: usize,
+}
+
+impl GridSettings {
+    /// Calculates the size of the cells on each level, given the longest axis of the largest
+    /// collider's AABB.
+    pub fn cell_sizes(&self, longest_axis: f32) -> Vec<f32> {
+        let cell_size = match self.cell_size {
+            Some(cell_size) => cell_size,
+            None => longest_axis / LEVEL_RATIO.powi(self.levels as i32 - 1),
+        };
+
+        // Volumes that are all points would otherwise give a cell size of 0.
+        let cell_size = if cell_size > 0.0 { cell_size } else { 1.0 };
+
+        (0..self.levels).map(|level| cell_size * LEVEL_RATIO.powi(level as i32)).collect()
+    }
+}
+
+impl Default for GridSettings {
+    fn default() -> GridSettings {
+        GridSettings {
+            cell_size: None,
+            levels: 3,
+        }
+    }
+}
+
+/// Finds the level of the grid that a volume goes in, which is the lowest level whose cells are
+/// at least as large as the longest axis of its AABB.
 ///
-/// # TODO
+/// Volumes larger than the cells of the highest level go in the highest level, where they may
+/// overlap more than 8 cells.
+pub fn grid_level(cell_sizes: &[f32], aabb: &AABB) -> usize {
+    let size = (aabb.max.x - aabb.min.x)
+        .max(aabb.max.y - aabb.min.y)
+        .max(aabb.max.z - aabb.min.z);
+
+    cell_sizes.iter()
+        .position(|&cell_size| cell_size >= size)
+        .unwrap_or(cell_sizes.len() - 1)
+}
+
+/// A collision processor that partitions the space into a hierarchy of regular grids.
 ///
-/// - Do something to configure the size of the grid.
+/// The size of the grid's cells is configured through `ColliderManager::set_grid_settings()`.
 pub struct GridCollisionSystem {
     _workers: Vec<JoinHandle<()>>,
     thread_data: Arc<ThreadData>,
     channel: Receiver<WorkUnit>,
     processed_work: Vec<WorkUnit>,
-    pub collisions: HashSet<(Entity, Entity), FnvHashState>,
+
+    /// The collisions detected during the last update and their contacts. Each colliding pair
+    /// appears once, and its contact's normal points from the first entity to the second.
+    pub collisions: HashMap<(Entity, Entity), Contact, FnvHashState>,
 }
 
 impl GridCollisionSystem {
@@ -210,7 +289,7 @@ impl GridCollisionSystem {
             _workers: workers,
             thread_data: thread_data.clone(),
             channel: receiver,
-            collisions: HashSet::default(),
+            collisions: HashMap::default(),
             processed_work: processed_work,
         }
     }
@@ -236,7 +315,8 @@ impl GridCollisionSystem {
             );
 
             for work_unit in self.processed_work.iter_mut() {
-                work_unit.cell_size = bvh_manager.longest_axis();
+                work_unit.cell_sizes.clear();
+                work_unit.cell_sizes.extend_from_slice(bvh_manager.cell_sizes());
             }
 
             // Prepare work unit by giving it a copy of the list of volumes.
@@ -265,8 +345,8 @@ impl GridCollisionSystem {
             work_unit.returned_time = timer.now();
 
             // Merge results of work unit into total.
-            for (collision, _) in work_unit.collisions.drain() {
-                self.collisions.insert(collision);
+            for (collision, contact) in work_unit.collisions.drain() {
+                self.collisions.insert(collision, contact);
             }
             self.processed_work.push(work_unit);
         }
@@ -296,11 +376,12 @@ impl Clone for GridCollisionSystem {
 
 #[derive(Debug)]
 struct WorkUnit {
-    collisions: HashMap<(Entity, Entity), (), FnvHashState>, // This should be a HashSet, but HashSet doesn't have a way to get at entries directly.
+    collisions: HashMap<(Entity, Entity), Contact, FnvHashState>,
     bounds: AABB,
 
-    grid: HashMap<GridCell, Vec<*const BoundVolume>, FnvHashState>,
-    cell_size: f32,
+    /// The grid of each level, along with the size of each level's cells.
+    grids: Vec<CollisionGrid>,
+    cell_sizes: Vec<f32>,
 
     received_time: TimeMark,
     broadphase_time: TimeMark,
@@ -315,8 +396,8 @@ impl WorkUnit {
             bounds: bounds,
             collisions: HashMap::default(),
 
-            grid: HashMap::default(),
-            cell_size: 1.0,
+            grids: Vec::new(),
+            cell_sizes: Vec::new(),
 
             received_time: timer.now(),
             broadphase_time: timer.now(),
@@ -325,13 +406,9 @@ impl WorkUnit {
         }
     }
 
-    /// Converts a point in world space to its grid cell.
-    fn world_to_grid(&self, point: Point) -> GridCell {
-        GridCell {
-            x: (point.x / self.cell_size).floor() as GridCoord,
-            y: (point.y / self.cell_size).floor() as GridCoord,
-            z: (point.z / self.cell_size).floor() as GridCoord,
-        }
+    /// Converts a point in world space to its cell on the given level of the grid.
+    fn world_to_grid(&self, point: Point, level: usize) -> GridCell {
+        GridCell::from_point(point, self.cell_sizes[level])
     }
 }
 
@@ -389,103 +466,68 @@ impl Worker {
     fn do_broadphase(&mut self, work: &mut WorkUnit) {
         // let _stopwatch = Stopwatch::new("Broadphase Testing (Grid Based)");
         let volumes = self.thread_data.volumes.read().unwrap();
-        for bvh in &*volumes {
-            // Retrieve the AABB at the root of the BVH.
-            let aabb = bvh.aabb;
-
-            // Only test volumes that are within the bounds of this work unit's testing area.
-            if !aabb.test_aabb(&work.bounds) {
-                continue;
-            }
+        let bounds = work.bounds;
+        while work.grids.len() < work.cell_sizes.len() {
+            work.grids.push(CollisionGrid::default());
+        }
 
-            let min = work.world_to_grid(aabb.min);
-            let max = work.world_to_grid(aabb.max);
-            debug_assert!(
-                max.x - min.x <= 1
-             && max.y - min.y <= 1
-             && max.z - min.z <= 1,
-                "AABB spans too many grid cells (min: {:?}, max: {:?}), grid cells are too small, bvh: {:?}",
-                min,
-                max,
-                bvh);
-
-            // Iterate over all grid cells that the AABB touches. Test the BVH against any entities
-            // that have already been placed in that cell, then add the BVH to the cell, creating
-            // new cells as necessary.
-            {
-                let cell_cache = &mut self.cell_cache;
-                let candidate_collisions = &mut self.candidate_collisions;
-                let _cell_size = work.cell_size;
-                let mut test_cell = |grid_cell: GridCell| {
-                    // // Visualize test cell.
-                    // ::debug_draw::box_min_max(
-                    //     Point::new(
-                    //         grid_cell.x as f32 * _cell_size,
-                    //         grid_cell.y as f32 * _cell_size,
-                    //         grid_cell.z as f32 * _cell_size,
-                    //     ),
-                    //     Point::new(
-                    //         grid_cell.x as f32 * _cell_size + _cell_size,
-                    //         grid_cell.y as f32 * _cell_size + _cell_size,
-                    //         grid_cell.z as f32 * _cell_size + _cell_size,
-                    //     )
-                    // );
-
-                    let mut cell = work.grid.entry(grid_cell).or_insert_with(|| {
-                        cell_cache.pop().unwrap_or(Vec::new())
-                    });
-
-                    // Check against other volumes.
-                    for other_bvh in cell.iter().cloned() {
-                        candidate_collisions.push((bvh, other_bvh));
+        // Place each volume in the cells it overlaps on its own level of the grid. Only test
+        // volumes that are within the bounds of this work unit's testing area.
+        for bvh in volumes.iter().filter(|bvh| bvh.aabb.test_aabb(&bounds)) {
+            let level = grid_level(&work.cell_sizes, &bvh.aabb);
+            let min = work.world_to_grid(bvh.aabb.min, level);
+            let max = work.world_to_grid(bvh.aabb.max, level);
+            for x in min.x..max.x + 1 {
+                for y in min.y..max.y + 1 {
+                    for z in min.z..max.z + 1 {
+                        let cell_cache = &mut self.cell_cache;
+                        let cell = work.grids[level].entry(GridCell::new(x, y, z)).or_insert_with(|| {
+                            cell_cache.pop().unwrap_or(Vec::new())
+                        });
+                        cell.push(bvh as *const BoundVolume);
                     }
+                }
+            }
+        }
 
-                    // Add to existing cell.
-                    cell.push(bvh);
-                };
-
-                test_cell(min);
-
-                let overlap_x = min.x < max.x;
-                let overlap_y = min.y < max.y;
-                let overlap_z = min.z < max.z;
-
-                // Test cases where volume overlaps along x.
-                if overlap_x {
-                    test_cell(GridCell::new(max.x, min.y, min.z));
-
-                    if overlap_y {
-                        test_cell(GridCell::new(min.x, max.y, min.z));
-                        test_cell(GridCell::new(max.x, max.y, min.z));
-
-                        if overlap_z {
-                            test_cell(GridCell::new(min.x, min.y, max.z));
-                            test_cell(GridCell::new(min.x, max.y, max.z));
-                            test_cell(GridCell::new(max.x, min.y, max.z));
-                            test_cell(GridCell::new(max.x, max.y, max.z));
+        // Pair each volume with the volumes that share a cell with it, both on its own level and
+        // on every level of larger cells. Pairs on the same level are found from both volumes, so
+        // only the one from the volume that comes later in the list is kept.
+        for bvh in volumes.iter().filter(|bvh| bvh.aabb.test_aabb(&bounds)) {
+            let bvh = bvh as *const BoundVolume;
+            let aabb = unsafe { (*bvh).aabb };
+            let level = grid_level(&work.cell_sizes, &aabb);
+            for other_level in level..work.grids.len() {
+                let min = work.world_to_grid(aabb.min, other_level);
+                let max = work.world_to_grid(aabb.max, other_level);
+                for x in min.x..max.x + 1 {
+                    for y in min.y..max.y + 1 {
+                        for z in min.z..max.z + 1 {
+                            let cell = match work.grids[other_level].get(&GridCell::new(x, y, z)) {
+                                Some(cell) => cell,
+                                None => continue,
+                            };
+
+                            for &other_bvh in cell {
+                                if other_level == level && other_bvh >= bvh {
+                                    continue;
+                                }
+
+                                self.candidate_collisions.push((bvh, other_bvh));
+                            }
                         }
-                    } else if overlap_z {
-                        test_cell(GridCell::new(min.x, min.y, max.z));
-                        test_cell(GridCell::new(max.x, min.y, max.z));
-                    }
-                } else if overlap_y {
-                    test_cell(GridCell::new(min.x, max.y, min.z));
-
-                    if overlap_z {
-                        test_cell(GridCell::new(min.x, min.y, max.z));
-                        test_cell(GridCell::new(min.x, max.y, max.z));
                     }
-                } else if overlap_z {
-                    test_cell(GridCell::new(min.x, min.y, max.z));
                 }
             }
         }
 
         // Clear out grid contents from previous frame, start each frame with an empty grid and
         // rebuild it rather than trying to update the grid as objects move.
-        for (_, mut cell) in work.grid.drain() {
-            cell.clear();
-            self.cell_cache.push(cell);
+        for grid in &mut work.grids {
+            for (_, mut cell) in grid.drain() {
+                cell.clear();
+                self.cell_cache.push(cell);
+            }
         }
     }
 
@@ -502,9 +544,9 @@ impl Worker {
             match work.collisions.entry(collision_pair) {
                 Entry::Vacant(vacant_entry) => {
                     // Collision hasn't already been detected, so do the test.
-                    if bvh.test(other_bvh) {
+                    if let Some(contact) = bvh.contact(other_bvh) {
                         // Woo, we have a collison.
-                        vacant_entry.insert(());
+                        vacant_entry.insert(contact);
                     }
                 },
                 _ => {},
@@ -513,6 +555,199 @@ impl Worker {
     }
 }
 
+/// A grid of the collision volumes used to answer scene queries such as raycasts.
+///
+/// The workers' grids only exist while they're running broadphase, so the bounding volume manager
+/// keeps its own grid that's rebuilt after the volumes are updated each frame. It uses the same
+/// levels and cells as the broadphase grid, and lists the entity of each volume overlapping a
+/// cell. Entities are listed rather than the volumes themselves so that volumes destroyed since
+/// the grid was built are skipped rather than left dangling.
+#[derive(Debug, Clone)]
+pub struct QueryGrid {
+    /// The cells of each level, along with the size of each level's cells.
+    levels: Vec<HashMap<GridCell, Vec<Entity>, FnvHashState>>,
+    cell_sizes: Vec<f32>,
+
+    /// The box bounding every volume in the grid.
+    bounds: AABB,
+}
+
+impl QueryGrid {
+    pub fn new() -> QueryGrid {
+        QueryGrid {
+            levels: Vec::new(),
+            cell_sizes: Vec::new(),
+            bounds: AABB {
+                min: Point::origin(),
+                max: Point::origin(),
+            },
+        }
+    }
+
+    /// Rebuilds the grid from `volumes`, using the cell size of each level in `cell_sizes`.
+    pub fn rebuild(&mut self, volumes: &[BoundVolume], cell_sizes: &[f32]) {
+        for level in self.levels.iter_mut() {
+            for (_, cell) in level.iter_mut() {
+                cell.clear();
+            }
+        }
+        while self.levels.len() < cell_sizes.len() {
+            self.levels.push(HashMap::default());
+        }
+        self.levels.truncate(cell_sizes.len());
+
+        self.cell_sizes.clear();
+        self.cell_sizes.extend_from_slice(cell_sizes);
+
+        self.bounds = AABB {
+            min: Point::new(MAX, MAX, MAX),
+            max: Point::new(MIN, MIN, MIN),
+        };
+        for volume in volumes {
+            let (min, max) = (volume.aabb.min, volume.aabb.max);
+            self.bounds.min = Point::new(self.bounds.min.x.min(min.x), self.bounds.min.y.min(min.y), self.bounds.min.z.min(min.z));
+            self.bounds.max = Point::new(self.bounds.max.x.max(max.x), self.bounds.max.y.max(max.y), self.bounds.max.z.max(max.z));
+        }
+
+        for volume in volumes {
+            let level = grid_level(&self.cell_sizes, &volume.aabb);
+            let min = self.world_to_grid(volume.aabb.min, level);
+            let max = self.world_to_grid(volume.aabb.max, level);
+            for x in min.x..max.x + 1 {
+                for y in min.y..max.y + 1 {
+                    for z in min.z..max.z + 1 {
+                        self.levels[level].entry(GridCell::new(x, y, z)).or_insert_with(Vec::new).push(volume.entity);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Invokes `visitor` with the entities in each cell the ray from `origin` along `direction`
+    /// passes through, until it's traveled `max_distance`.
+    ///
+    /// `visitor` is also passed the distance at which the ray enters the cell. Each level of the
+    /// grid is walked in turn, visiting its cells in order along the ray, and returning `true`
+    /// from `visitor` stops walking the current level. An entity overlapping several cells is
+    /// visited once for each cell. `direction` must be normalized.
+    pub fn visit_ray<F>(&self, origin: Point, direction: Vector3, max_distance: f32, mut visitor: F)
+        where F: FnMut(&[Entity], f32) -> bool
+    {
+        // Only walk the part of the ray that's inside the grid.
+        let (enter, exit) = match self.bounds.intersect_ray(origin, direction) {
+            Some((enter, exit)) if enter <= max_distance => (enter.max(0.0), exit.min(max_distance)),
+            _ => return,
+        };
+
+        for level in 0..self.levels.len() {
+            // Empty levels can be skipped entirely.
+            if self.levels[level].is_empty() {
+                continue;
+            }
+
+            self.visit_ray_level(level, origin, direction, enter, exit, &mut visitor);
+        }
+    }
+
+    /// Walks the ray through the cells of one level of the grid, from `enter` to `exit`.
+    fn visit_ray_level<F>(&self, level: usize, origin: Point, direction: Vector3, enter: f32, exit: f32, visitor: &mut F)
+        where F: FnMut(&[Entity], f32) -> bool
+    {
+        let cell_size = self.cell_sizes[level];
+        let start = origin + direction * enter;
+        let origin = origin.as_vector3();
+        let cell = self.world_to_grid(start, level);
+        let mut cell = [cell.x as i32, cell.y as i32, cell.z as i32];
+
+        // For each axis, the direction to step in, the distance along the ray at which it crosses
+        // into the next cell, and the distance it travels to cross a whole cell.
+        let mut step = [0; 3];
+        let mut next_crossing = [MAX; 3];
+        let mut crossing_delta = [MAX; 3];
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                next_crossing[axis] = ((cell[axis] + 1) as f32 * cell_size - origin[axis]) / direction[axis];
+                crossing_delta[axis] = cell_size / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                next_crossing[axis] = (cell[axis] as f32 * cell_size - origin[axis]) / direction[axis];
+                crossing_delta[axis] = -cell_size / direction[axis];
+            }
+        }
+
+        let mut distance = enter;
+        loop {
+            let grid_cell = GridCell::new(
+                cell[0] as GridCoord,
+                cell[1] as GridCoord,
+                cell[2] as GridCoord);
+            if let Some(entities) = self.levels[level].get(&grid_cell) {
+                if visitor(entities, distance) {
+                    return;
+                }
+            }
+
+            // Step into the next cell along whichever axis the ray crosses first.
+            let axis = if next_crossing[0] < next_crossing[1] && next_crossing[0] < next_crossing[2] {
+                0
+            } else if next_crossing[1] < next_crossing[2] {
+                1
+            } else {
+                2
+            };
+
+            if next_crossing[axis] > exit {
+                return;
+            }
+
+            distance = next_crossing[axis];
+            cell[axis] += step[axis];
+            next_crossing[axis] += crossing_delta[axis];
+        }
+    }
+
+    /// Invokes `visitor` with the entities in each cell overlapping `aabb`.
+    ///
+    /// An entity overlapping several cells is visited once for each cell.
+    pub fn visit_aabb<F>(&self, aabb: &AABB, mut visitor: F) where F: FnMut(&[Entity]) {
+        if !aabb.test_aabb(&self.bounds) {
+            return;
+        }
+
+        // Only visit the cells that are inside the grid, in case `aabb` is much larger than it.
+        let clipped = AABB {
+            min: Point::new(
+                aabb.min.x.max(self.bounds.min.x),
+                aabb.min.y.max(self.bounds.min.y),
+                aabb.min.z.max(self.bounds.min.z)),
+            max: Point::new(
+                aabb.max.x.min(self.bounds.max.x),
+                aabb.max.y.min(self.bounds.max.y),
+                aabb.max.z.min(self.bounds.max.z)),
+        };
+
+        for level in 0..self.levels.len() {
+            let min = self.world_to_grid(clipped.min, level);
+            let max = self.world_to_grid(clipped.max, level);
+            for x in min.x..max.x + 1 {
+                for y in min.y..max.y + 1 {
+                    for z in min.z..max.z + 1 {
+                        if let Some(entities) = self.levels[level].get(&GridCell::new(x, y, z)) {
+                            visitor(entities);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Converts a point in world space to its cell on the given level of the grid.
+    fn world_to_grid(&self, point: Point, level: usize) -> GridCell {
+        GridCell::from_point(point, self.cell_sizes[level])
+    }
+}
+
 /// A wrapper type around a triple of coordinates that uniquely identify a grid cell.
 ///
 /// # Details
@@ -542,4 +777,13 @@ impl GridCell {
             z: z,
         }
     }
+
+    /// Gets the cell containing `point` in a grid with cells of size `cell_size`.
+    pub fn from_point(point: Point, cell_size: f32) -> GridCell {
+        GridCell {
+            x: (point.x / cell_size).floor() as GridCoord,
+            y: (point.y / cell_size).floor() as GridCoord,
+            z: (point.z / cell_size).floor() as GridCoord,
+        }
+    }
 }
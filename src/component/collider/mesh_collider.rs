@@ -0,0 +1,673 @@
+//! Triangle mesh colliders.
+//!
+//! A `MeshCollider` holds a copy of a mesh's triangles along with a bounding volume hierarchy
+//! over them. The hierarchy is built once when the collider is created, so assigning a mesh
+//! collider is relatively expensive but testing against it each frame is cheap. The mesh data is
+//! shared through an `Arc`, so many entities can use the same collider without copying it:
+//!
+//! ```rust
+//! let mesh_collider = Arc::new(MeshCollider::new(&mesh));
+//! collider_manager.assign(entity, Collider::Mesh {
+//!     offset: Vector3::zero(),
+//!     mesh: mesh_collider.clone(),
+//! });
+//! ```
+//!
+//! Baking Transforms
+//! =================
+//!
+//! Transforming every triangle into world space each frame would defeat the point of building
+//! the hierarchy up front, so instead `CachedCollider::Mesh` bakes the entity's transform into a
+//! `TransformedMesh`. Tests against a transformed mesh move the other volume into the mesh's
+//! frame by undoing the translation and rotation, and apply the scale to the triangles and the
+//! nodes of the hierarchy as they're visited. Since only the scale is applied to the mesh, spheres
+//! and capsules stay spheres and capsules even if the mesh is scaled non-uniformly.
+//!
+//! Mesh colliders are meant for level geometry and other large static objects, so they can
+//! collide with spheres and boxes but not with other mesh colliders.
+
+use std::cmp::Ordering;
+use std::f32::{MAX, MIN};
+use std::sync::Arc;
+
+use math::*;
+use polygon::camera::Ray;
+use polygon::geometry::mesh::Mesh;
+use super::{CachedCollider, Capsule, OBB, Sphere};
+use super::bounding_volume::AABB;
+
+/// The most triangles held by a leaf node of the hierarchy.
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+/// A triangle mesh with a bounding volume hierarchy, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshCollider {
+    triangles: Vec<Triangle>,
+
+    /// The nodes of the hierarchy in depth-first order, so the first child of a branch always
+    /// immediately follows it.
+    nodes: Vec<BvhNode>,
+}
+
+impl MeshCollider {
+    /// Builds a collider from the triangles of `mesh`.
+    pub fn new(mesh: &Mesh) -> MeshCollider {
+        MeshCollider::from_triangles(&mesh.positions(), mesh.indices())
+    }
+
+    /// Builds a collider from a list of vertex positions and the indices of each triangle's
+    /// vertices.
+    ///
+    /// Panics
+    /// ======
+    ///
+    /// Panics if `indices` has an index that's out of bounds for `positions`.
+    pub fn from_triangles(positions: &[Point], indices: &[u32]) -> MeshCollider {
+        let mut triangles = indices
+            .chunks(3)
+            .filter(|indices| indices.len() == 3)
+            .map(|indices| Triangle {
+                a: positions[indices[0] as usize].as_vector3(),
+                b: positions[indices[1] as usize].as_vector3(),
+                c: positions[indices[2] as usize].as_vector3(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut nodes = Vec::new();
+        if triangles.len() > 0 {
+            build_node(&mut nodes, &mut triangles, 0);
+        }
+
+        MeshCollider {
+            triangles: triangles,
+            nodes: nodes,
+        }
+    }
+
+    /// Gets the triangles of the mesh in local space.
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    /// Gets the box bounding the mesh in local space.
+    ///
+    /// A mesh without triangles has an empty box at the origin.
+    pub fn bounds(&self) -> AABB {
+        match self.nodes.first() {
+            Some(root) => root.aabb,
+            None => AABB {
+                min: Point::origin(),
+                max: Point::origin(),
+            },
+        }
+    }
+
+    /// Invokes `visitor` with each triangle, scaled by `scale`, whose node overlaps `bounds`.
+    ///
+    /// Visiting stops as soon as `visitor` returns `true`, in which case this returns `true` as
+    /// well. `bounds` must be in the scaled local space of the mesh.
+    pub fn visit<F>(&self, scale: Vector3, bounds: &AABB, mut visitor: F) -> bool
+        where F: FnMut(&Triangle) -> bool
+    {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !scale_aabb(&node.aabb, scale).test_aabb(bounds) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for triangle in &self.triangles[start..start + count] {
+                        if visitor(&triangle.scaled(scale)) {
+                            return true;
+                        }
+                    }
+                },
+                NodeKind::Branch { right } => {
+                    stack.push(right);
+                    stack.push(index + 1);
+                },
+            }
+        }
+
+        false
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BvhNode {
+    aabb: AABB,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NodeKind {
+    /// A leaf node holding `count` triangles starting from `start`.
+    Leaf { start: usize, count: usize },
+
+    /// A branch node, the index of its second child is `right`.
+    Branch { right: usize },
+}
+
+/// Builds the node for `triangles`, which start at `start` in the collider's list of triangles,
+/// returning the index of the node.
+///
+/// Triangles are split in half along the longest axis of their centroids' bounds until each leaf
+/// holds at most `MAX_LEAF_TRIANGLES`.
+fn build_node(nodes: &mut Vec<BvhNode>, triangles: &mut [Triangle], start: usize) -> usize {
+    let aabb = aabb_from_points(triangles.iter().flat_map(|triangle| triangle.vertices().to_vec()));
+
+    let index = nodes.len();
+    nodes.push(BvhNode {
+        aabb: aabb,
+        kind: NodeKind::Leaf { start: start, count: triangles.len() },
+    });
+
+    if triangles.len() <= MAX_LEAF_TRIANGLES {
+        return index;
+    }
+
+    let centroid_bounds = aabb_from_points(triangles.iter().map(Triangle::centroid));
+    let extents = centroid_bounds.max - centroid_bounds.min;
+    let axis = if extents.x >= extents.y && extents.x >= extents.z {
+        0
+    } else if extents.y >= extents.z {
+        1
+    } else {
+        2
+    };
+
+    triangles.sort_by(|lhs, rhs| {
+        lhs.centroid()[axis].partial_cmp(&rhs.centroid()[axis]).unwrap_or(Ordering::Equal)
+    });
+
+    let middle = triangles.len() / 2;
+    let (left, right) = triangles.split_at_mut(middle);
+    build_node(nodes, left, start);
+    let right_index = build_node(nodes, right, start + middle);
+    nodes[index].kind = NodeKind::Branch { right: right_index };
+
+    index
+}
+
+/// A triangle of a mesh collider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub a: Vector3,
+    pub b: Vector3,
+    pub c: Vector3,
+}
+
+impl Triangle {
+    pub fn vertices(&self) -> [Vector3; 3] {
+        [self.a, self.b, self.c]
+    }
+
+    pub fn centroid(&self) -> Vector3 {
+        (self.a + self.b + self.c) / 3.0
+    }
+
+    /// Gets the unit normal of the triangle, facing the side its vertices wind counter-clockwise
+    /// around.
+    pub fn normal(&self) -> Vector3 {
+        Vector3::cross(self.b - self.a, self.c - self.a).normalized()
+    }
+
+    /// Scales each of the triangle's vertices by `scale`.
+    pub fn scaled(&self, scale: Vector3) -> Triangle {
+        Triangle {
+            a: self.a * scale,
+            b: self.b * scale,
+            c: self.c * scale,
+        }
+    }
+
+    /// Calculates the closest point to `point` on the triangle.
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        let ab = self.b - self.a;
+        let ac = self.c - self.a;
+
+        // Check if the point is in the vertex region outside `a`.
+        let ap = point - self.a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return self.a;
+        }
+
+        // Check if the point is in the vertex region outside `b`.
+        let bp = point - self.b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return self.b;
+        }
+
+        // Check if the point is in the edge region of `ab`.
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return self.a + ab * (d1 / (d1 - d3));
+        }
+
+        // Check if the point is in the vertex region outside `c`.
+        let cp = point - self.c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return self.c;
+        }
+
+        // Check if the point is in the edge region of `ac`.
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return self.a + ac * (d2 / (d2 - d6));
+        }
+
+        // Check if the point is in the edge region of `bc`.
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            return self.b + (self.c - self.b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+
+        // The point is inside the face region.
+        let denom = 1.0 / (va + vb + vc);
+        self.a + ab * (vb * denom) + ac * (vc * denom)
+    }
+
+    /// Finds where the ray from `origin` along `direction` crosses the triangle, from either side.
+    ///
+    /// Returns the distance along the ray in multiples of `direction`, or `None` if the ray misses
+    /// the triangle or starts past it.
+    pub fn intersect_ray(&self, origin: Vector3, direction: Vector3) -> Option<f32> {
+        let ab = self.b - self.a;
+        let ac = self.c - self.a;
+
+        let p = Vector3::cross(direction, ac);
+        let det = ab.dot(p);
+        if det.abs() < EPSILON {
+            // The ray is parallel to the triangle.
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let t_vec = origin - self.a;
+        let u = t_vec.dot(p) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let q = Vector3::cross(t_vec, ab);
+        let v = direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = ac.dot(q) * inv_det;
+        if t < 0.0 {
+            return None;
+        }
+
+        Some(t)
+    }
+
+    /// Calculates the squared distance between the triangle and the segment from `start` to `end`.
+    pub fn segment_distance_squared(&self, start: Vector3, end: Vector3) -> f32 {
+        match self.intersect_ray(start, end - start) {
+            Some(t) if t <= 1.0 => return 0.0,
+            _ => {},
+        }
+
+        // The segment doesn't cross the triangle, so the closest points are on either one of the
+        // segment's end points or one of the triangle's edges.
+        let mut distance = (start - self.closest_point(start)).magnitude_squared();
+        distance = distance.min((end - self.closest_point(end)).magnitude_squared());
+        for &(edge_start, edge_end) in &[(self.a, self.b), (self.b, self.c), (self.c, self.a)] {
+            let (first, second) = closest_points_segments(start, end, edge_start, edge_end);
+            distance = distance.min((first - second).magnitude_squared());
+        }
+
+        distance
+    }
+
+    /// Tests the triangle against a box with its center at `center`, its axes along `axes`, and
+    /// the half widths `half_widths`, using the separating axis test.
+    pub fn test_box(&self, center: Vector3, axes: &[Vector3; 3], half_widths: Vector3) -> bool {
+        let vertices = [self.a - center, self.b - center, self.c - center];
+        let edges = [
+            vertices[1] - vertices[0],
+            vertices[2] - vertices[1],
+            vertices[0] - vertices[2],
+        ];
+
+        let is_separating = |axis: Vector3| {
+            // Edges that are (nearly) parallel produce a degenerate axis that can't separate.
+            if axis.magnitude_squared() < EPSILON {
+                return false;
+            }
+
+            let projections = [vertices[0].dot(axis), vertices[1].dot(axis), vertices[2].dot(axis)];
+            let min = projections[0].min(projections[1]).min(projections[2]);
+            let max = projections[0].max(projections[1]).max(projections[2]);
+            let radius =
+                half_widths.x * axes[0].dot(axis).abs()
+              + half_widths.y * axes[1].dot(axis).abs()
+              + half_widths.z * axes[2].dot(axis).abs();
+
+            min > radius || max < -radius
+        };
+
+        // Test the box's axes and the triangle's normal.
+        if axes.iter().any(|&axis| is_separating(axis)) {
+            return false;
+        }
+
+        if is_separating(Vector3::cross(edges[0], edges[1])) {
+            return false;
+        }
+
+        // Test the cross products of the box's axes and the triangle's edges.
+        for &axis in axes {
+            for &edge in &edges {
+                if is_separating(Vector3::cross(axis, edge)) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// A mesh collider with the transform of its entity baked in, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct TransformedMesh {
+    pub mesh: Arc<MeshCollider>,
+    pub center: Point,
+    pub orientation: Matrix3,
+    pub scale: Vector3,
+}
+
+impl TransformedMesh {
+    pub fn test_collider(&self, other: &CachedCollider) -> bool {
+        match *other {
+            CachedCollider::Sphere(ref sphere) => self.test_sphere(sphere),
+            CachedCollider::Box(ref obb) => self.test_obb(obb),
+
+            // Mesh colliders don't collide with each other, see the module documentation.
+            CachedCollider::Mesh(_) => false,
+        }
+    }
+
+    pub fn test_sphere(&self, sphere: &Sphere) -> bool {
+        let center = self.to_local_point(sphere.center);
+        let radius = sphere.radius;
+        let radius_sqr = radius * radius;
+        let bounds = aabb_around(center, Vector3::new(radius, radius, radius));
+
+        self.mesh.visit(self.scale, &bounds, |triangle| {
+            (center - triangle.closest_point(center)).magnitude_squared() <= radius_sqr
+        })
+    }
+
+    pub fn test_obb(&self, obb: &OBB) -> bool {
+        let center = self.to_local_point(obb.center);
+        let axes = [
+            self.to_local_vector(obb.orientation.col(0)),
+            self.to_local_vector(obb.orientation.col(1)),
+            self.to_local_vector(obb.orientation.col(2)),
+        ];
+
+        // The extents of the box along each of the mesh's axes.
+        let mut extents = Vector3::zero();
+        for axis in 0..3 {
+            extents[axis] =
+                obb.half_widths.x * axes[0][axis].abs()
+              + obb.half_widths.y * axes[1][axis].abs()
+              + obb.half_widths.z * axes[2][axis].abs();
+        }
+        let bounds = aabb_around(center, extents);
+
+        self.mesh.visit(self.scale, &bounds, |triangle| {
+            triangle.test_box(center, &axes, obb.half_widths)
+        })
+    }
+
+    pub fn test_capsule(&self, capsule: &Capsule) -> bool {
+        let start = self.to_local_point(capsule.start);
+        let end = self.to_local_point(capsule.end);
+        let radius = capsule.radius;
+        let radius_sqr = radius * radius;
+
+        let segment_bounds = aabb_from_points(vec![start, end]);
+        let bounds = AABB {
+            min: segment_bounds.min - Vector3::new(radius, radius, radius),
+            max: segment_bounds.max + Vector3::new(radius, radius, radius),
+        };
+
+        self.mesh.visit(self.scale, &bounds, |triangle| {
+            triangle.segment_distance_squared(start, end) <= radius_sqr
+        })
+    }
+
+    /// Casts `ray` against the mesh, returning the distance to the closest triangle it hits and
+    /// the triangle's normal, facing back towards the ray's origin.
+    pub fn raycast(&self, ray: &Ray) -> Option<(f32, Vector3)> {
+        let origin = self.to_local_point(ray.origin);
+        let direction = self.to_local_vector(ray.direction);
+
+        // Only visit the nodes along the part of the ray that's inside the mesh's bounds.
+        let bounds = scale_aabb(&self.mesh.bounds(), self.scale);
+        let (enter, exit) = match bounds.intersect_ray(Point::from(origin), direction) {
+            Some(range) => range,
+            None => return None,
+        };
+        let segment_bounds = aabb_from_points(vec![
+            origin + direction * enter.max(0.0),
+            origin + direction * exit,
+        ]);
+
+        let mut closest: Option<(f32, Vector3)> = None;
+        self.mesh.visit(self.scale, &segment_bounds, |triangle| {
+            if let Some(distance) = triangle.intersect_ray(origin, direction) {
+                if closest.map(|(closest, _)| distance < closest).unwrap_or(true) {
+                    let normal = triangle.normal();
+                    let normal = if normal.dot(direction) > 0.0 { -normal } else { normal };
+                    closest = Some((distance, normal));
+                }
+            }
+
+            false
+        });
+
+        closest.map(|(distance, normal)| (distance, self.to_world_vector(normal)))
+    }
+
+    /// Calculates the box bounding the transformed mesh in world space.
+    pub fn aabb(&self) -> AABB {
+        let bounds = scale_aabb(&self.mesh.bounds(), self.scale);
+        let corners = (0..8).map(|corner| {
+            let local = Vector3::new(
+                if corner & 1 == 0 { bounds.min.x } else { bounds.max.x },
+                if corner & 2 == 0 { bounds.min.y } else { bounds.max.y },
+                if corner & 4 == 0 { bounds.min.z } else { bounds.max.z },
+            );
+            (self.center + self.to_world_vector(local)).as_vector3()
+        });
+
+        aabb_from_points(corners)
+    }
+
+    /// Moves a point from world space into the scaled local space of the mesh.
+    pub fn to_local_point(&self, point: Point) -> Vector3 {
+        self.to_local_vector(point - self.center)
+    }
+
+    /// Rotates a vector from world space into the scaled local space of the mesh.
+    pub fn to_local_vector(&self, vector: Vector3) -> Vector3 {
+        vector * self.orientation.transpose()
+    }
+
+    /// Moves a point from the scaled local space of the mesh into world space.
+    pub fn to_world_point(&self, point: Vector3) -> Point {
+        self.center + self.to_world_vector(point)
+    }
+
+    /// Rotates a vector from the scaled local space of the mesh into world space.
+    pub fn to_world_vector(&self, vector: Vector3) -> Vector3 {
+        vector * self.orientation
+    }
+}
+
+/// Calculates the closest points between the segments from `p1` to `q1` and from `p2` to `q2`.
+pub fn closest_points_segments(p1: Vector3, q1: Vector3, p2: Vector3, q2: Vector3) -> (Vector3, Vector3) {
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.dot(d1);
+    let e = d2.dot(d2);
+    let f = d2.dot(r);
+
+    // Handle either or both segments degenerating into points.
+    if a <= EPSILON && e <= EPSILON {
+        return (p1, p2);
+    }
+
+    let (s, t) = if a <= EPSILON {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e <= EPSILON {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+
+            // Parallel segments can use any point on the first segment.
+            let s = if denom != 0.0 { ((b * f - c * e) / denom).clamp(0.0, 1.0) } else { 0.0 };
+
+            // Find the closest point on the second segment, and recompute the point on the first
+            // segment if it had to be clamped.
+            let t = (b * s + f) / e;
+            if t < 0.0 {
+                ((-c / a).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / a).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+/// Calculates the box with its center at `center` and half widths `extents`.
+fn aabb_around(center: Vector3, extents: Vector3) -> AABB {
+    AABB {
+        min: Point::from(center - extents),
+        max: Point::from(center + extents),
+    }
+}
+
+/// Calculates the smallest box containing every point in `points`.
+fn aabb_from_points<I>(points: I) -> AABB where I: IntoIterator<Item = Vector3> {
+    let mut min = Vector3::new(MAX, MAX, MAX);
+    let mut max = Vector3::new(MIN, MIN, MIN);
+    for point in points {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(point[axis]);
+            max[axis] = max[axis].max(point[axis]);
+        }
+    }
+
+    AABB {
+        min: Point::from(min),
+        max: Point::from(max),
+    }
+}
+
+/// Scales a box by `scale`, keeping its minimum and maximum in order if `scale` is negative.
+fn scale_aabb(aabb: &AABB, scale: Vector3) -> AABB {
+    aabb_from_points(vec![aabb.min.as_vector3() * scale, aabb.max.as_vector3() * scale])
+}
+
+#[test]
+fn mesh_sphere_tests() {
+    // A unit quad on the XZ plane, split into two triangles.
+    let mesh = Arc::new(MeshCollider::from_triangles(
+        &[
+            Point::new(-0.5, 0.0, -0.5),
+            Point::new(0.5, 0.0, -0.5),
+            Point::new(0.5, 0.0, 0.5),
+            Point::new(-0.5, 0.0, 0.5),
+        ],
+        &[0, 1, 2, 0, 2, 3]));
+
+    let floor = TransformedMesh {
+        mesh: mesh.clone(),
+        center: Point::origin(),
+        orientation: Matrix3::identity(),
+        scale: Vector3::new(10.0, 1.0, 10.0),
+    };
+
+    let touching = Sphere { center: Point::new(4.0, 0.5, -4.0), radius: 1.0 };
+    let above = Sphere { center: Point::new(0.0, 2.0, 0.0), radius: 1.0 };
+    let outside = Sphere { center: Point::new(6.0, 0.0, 0.0), radius: 0.5 };
+
+    assert!(floor.test_sphere(&touching));
+    assert!(!floor.test_sphere(&above));
+    assert!(!floor.test_sphere(&outside));
+
+    let raised = TransformedMesh {
+        center: Point::new(0.0, 1.5, 0.0),
+        .. floor.clone()
+    };
+    assert!(raised.test_sphere(&above));
+    assert!(!raised.test_sphere(&touching));
+}
+
+#[test]
+fn mesh_obb_capsule_tests() {
+    let mesh = Arc::new(MeshCollider::from_triangles(
+        &[Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0), Point::new(0.0, 0.0, 1.0)],
+        &[0, 1, 2]));
+
+    let triangle = TransformedMesh {
+        mesh: mesh,
+        center: Point::origin(),
+        orientation: Matrix3::identity(),
+        scale: Vector3::one(),
+    };
+
+    let crossing = OBB {
+        center: Point::new(0.25, 0.0, 0.25),
+        orientation: Matrix3::rotation(0.25 * ::std::f32::consts::PI, 0.0, 0.0),
+        half_widths: Vector3::new(0.1, 0.1, 0.1),
+    };
+    let above = OBB {
+        center: Point::new(0.25, 1.0, 0.25),
+        .. crossing
+    };
+
+    assert!(triangle.test_obb(&crossing));
+    assert!(!triangle.test_obb(&above));
+
+    let through = Capsule {
+        start: Point::new(0.2, -1.0, 0.2),
+        end: Point::new(0.2, 1.0, 0.2),
+        radius: 0.1,
+    };
+    let beside = Capsule {
+        start: Point::new(2.0, -1.0, 0.0),
+        end: Point::new(2.0, 1.0, 0.0),
+        radius: 0.5,
+    };
+
+    assert!(triangle.test_capsule(&through));
+    assert!(!triangle.test_capsule(&beside));
+}
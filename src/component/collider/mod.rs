@@ -30,9 +30,9 @@
 //! }
 //! ```
 //!
-//! There are currently two types of collision primitives supported (oriented boxes and spheres)
-//! with several more types planned for support in the future (meshes, axis-aligned boxes,
-//! capsules, and planes). Each of these types have configurable options, such as the radius for
+//! There are currently three types of collision primitives supported (oriented boxes, spheres,
+//! and triangle meshes) with several more types planned for support in the future (axis-aligned
+//! boxes, capsules, and planes). Each of these types have configurable options, such as the radius for
 //! sphere colliders, and all colliders are placed in the world based on the entity's transform.
 //! Each collider has a natural "anchor point", which is by default located at the transform's
 //! world position (e.g. the sphere and box colliders' anchor are their center point), and all
@@ -59,7 +59,7 @@
 //! when it collides with the breadcrumb, you might have something as follows:
 //!
 //! ```rust
-//! fn duck_callback(scene: &Scene, collisions: &[Collision]) {
+//! fn duck_callback(scene: &Scene, entity: Entity, collisions: &[Collision]) {
 //!     let duck_manager = scene.get_manager::<DuckManager>();
 //!     let bread_manager = scene.get_manager::<BreadManager>();
 //!
@@ -71,7 +71,7 @@
 //!         if bread_manager.has_component(collision.other) {
 //!
 //!             // Retrieve the `Duck` object from the duck manager.
-//!             let mut duck = duck_manager.get_mut(collision.entity).unwrap();
+//!             let mut duck = duck_manager.get_mut(entity).unwrap();
 //!
 //!             // Give the duck the breadcrumb.
 //!             duck.gain_breadcrumb();
@@ -91,6 +91,43 @@
 //! in game code (either assigning the callback without the component or removing the component
 //! without unasigning the callback).
 //!
+//! Each `Collision` also has a `Contact` describing how the colliders touch: the points where
+//! they touch, the normal pointing from the entity's collider into the other collider, and how
+//! deep they overlap. This is what a physics response would need to push the colliders apart.
+//! The contacts for every collision detected in the last update are also available through
+//! `CollisionSystem::contacts()`.
+//!
+//! Scene Queries
+//! -------------
+//!
+//! Game code can also ask the collision system about the scene directly, without registering a
+//! callback. For example, `ColliderManager::raycast()` finds the first collider hit by a ray,
+//! which is useful for line-of-sight checks or for picking the entity under the mouse:
+//!
+//! ```rust
+//! fn update(scene: &Scene) {
+//!     let collider_manager = scene.get_manager::<ColliderManager>();
+//!
+//!     let ray = camera.ray_from_viewport(mouse_position);
+//!     if let Some(hit) = collider_manager.raycast(ray, 100.0, ALL_LAYERS) {
+//!         println!("Clicked on {:?} at {:?}", hit.entity, hit.point);
+//!     }
+//! }
+//! ```
+//!
+//! `ColliderManager::sphere_cast()` and `ColliderManager::box_cast()` work the same way but move a
+//! whole shape along the ray, which is useful for character controllers and fast projectiles that
+//! would otherwise pass through thin walls. See the `sweep` module for details.
+//! `ColliderManager::overlap_sphere()` and `ColliderManager::overlap_box()` list every collider
+//! touching a shape, for things like area-of-effect damage that only need to know what's nearby
+//! at one moment rather than being told every frame.
+//!
+//! Queries are answered using the colliders' positions as of the last collision update, and use
+//! the same grid as the broadphase to avoid testing colliders that are nowhere near the query.
+//! Each collider belongs to one or more layers (see `ColliderManager::set_layers()`), and queries
+//! take a mask of the layers they should consider so that e.g. a line-of-sight check can ignore
+//! triggers and other characters.
+//!
 //! Back End System
 //! ===============
 //!
@@ -109,8 +146,8 @@
 //!    optimized.
 //! 3. Narrowphase collision processing. This step takes the list of potential collision pairs
 //!    and performs the final collision test to determine which pairs of collision volumes are
-//!    intersecting. This step also gathers any other collision information which may be forwarded
-//!    to game code such as a list of collision points.
+//!    intersecting. This step also generates the contact for each intersecting pair, see the
+//!    `contact` module.
 //! 4. Collision callbacks. This step takes the list of detected collisions from narrowphase and
 //!    invokes the appropriate collision callbacks with the lists of colliding entities.
 //!
@@ -123,20 +160,30 @@ use component::transform::Transform;
 use debug_draw;
 use ecs::*;
 use engine::*;
+use hash::*;
 use math::*;
+use polygon::camera::Ray;
 use scene::Scene;
-use self::bounding_volume::{BoundingVolumeManager, bvh_update};
-use self::grid_collision::GridCollisionSystem;
+use self::bounding_volume::{AABB, BoundingVolumeManager, bvh_update};
+use self::contact::Contact;
+use self::grid_collision::{GridCollisionSystem, GridSettings};
+use self::mesh_collider::{MeshCollider, TransformedMesh};
 use std::cell::{RefCell, Ref, RefMut};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
 use stopwatch::Stopwatch;
 use super::DefaultMessage;
 use super::struct_component_manager::{StructComponentManager, Iter};
 
 pub mod grid_collision;
 pub mod bounding_volume;
+pub mod contact;
+pub mod mesh_collider;
+pub mod sweep;
 
 /// An enum representing all possible collision volumes. See each variant for more information.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Collider {
     /// Represents a sphere collider.
     ///
@@ -178,10 +225,35 @@ pub enum Collider {
         widths:  Vector3,
     },
 
-    /// Placeholder for mesh collider. Mesh colliders aren't supported currently, but they will be!
-    Mesh,
+    /// Represents a triangle mesh collider.
+    ///
+    /// Details
+    /// =======
+    ///
+    /// The mesh collider is positioned at its entity's global position anchored at the mesh's
+    /// origin, shifted by `offset` like the other colliders. Mesh colliders are affected by the
+    /// transform's position, orientation, and scale, so the mesh lines up with the entity's
+    /// rendered mesh. The triangles and their bounding volume hierarchy are built when the
+    /// `MeshCollider` is created, see the `mesh_collider` module for more information. Mesh
+    /// colliders collide with spheres and boxes, but not with other mesh colliders.
+    Mesh {
+        offset: Vector3,
+        mesh: Arc<MeshCollider>,
+    },
 }
 
+/// A set of collision layers, one for each bit.
+///
+/// Each collider belongs to one or more layers, and scene queries take a `LayerMask` of the layers
+/// they should consider. The meaning of each layer is up to game code.
+pub type LayerMask = u32;
+
+/// The layers colliders belong to unless they're set with `ColliderManager::set_layers()`.
+pub const DEFAULT_LAYERS: LayerMask = 1;
+
+/// A mask of every layer, for queries that should consider all colliders.
+pub const ALL_LAYERS: LayerMask = !0;
+
 impl Component for Collider {
     type Manager = ColliderManager;
     type Message = DefaultMessage<Collider>;
@@ -198,6 +270,8 @@ pub struct ColliderManager {
     inner: StructComponentManager<Collider>,
     callback_manager: RefCell<CollisionCallbackManager>,
     bvh_manager: RefCell<BoundingVolumeManager>,
+    layers: RefCell<EntityMap<LayerMask>>,
+    continuous: RefCell<EntitySet>,
     marked_for_destroy: RefCell<EntitySet>,
 }
 
@@ -208,6 +282,8 @@ impl ColliderManager {
             inner: StructComponentManager::new(),
             callback_manager: RefCell::new(CollisionCallbackManager::new()),
             bvh_manager: RefCell::new(BoundingVolumeManager::new()),
+            layers: RefCell::new(EntityMap::default()),
+            continuous: RefCell::new(EntitySet::default()),
             marked_for_destroy: RefCell::new(EntitySet::default()),
         }
     }
@@ -253,6 +329,268 @@ impl ColliderManager {
         self.callback_manager.borrow_mut().assign(entity, callback);
     }
 
+    /// Sets the collision layers the entity's collider belongs to.
+    ///
+    /// Colliders belong to `DEFAULT_LAYERS` until their layers are set. The layers only affect
+    /// scene queries such as `raycast()`, and take effect at the next collision update.
+    pub fn set_layers(&self, entity: Entity, layers: LayerMask) {
+        self.layers.borrow_mut().insert(entity, layers);
+    }
+
+    /// Gets the collision layers the entity's collider belongs to.
+    pub fn layers(&self, entity: Entity) -> LayerMask {
+        self.layers.borrow().get(&entity).cloned().unwrap_or(DEFAULT_LAYERS)
+    }
+
+    /// Sets how the grid used by broadphase and scene queries is laid out.
+    ///
+    /// By default the grid picks its cell size each frame from the largest collider, and uses a
+    /// few levels of larger cells so that a single large collider doesn't force every collider
+    /// into large cells. Games whose colliders have a known range of sizes can get better
+    /// performance by setting the size of the smallest cells explicitly, ideally to about the
+    /// size of the most common colliders. Takes effect at the next collision update.
+    ///
+    /// Panics
+    /// ======
+    ///
+    /// Panics if `settings.levels` is 0 or if `settings.cell_size` isn't positive.
+    pub fn set_grid_settings(&self, settings: GridSettings) {
+        assert!(settings.levels > 0, "Collision grid must have at least one level");
+        if let Some(cell_size) = settings.cell_size {
+            assert!(cell_size > 0.0, "Collision grid cell size must be positive, got {}", cell_size);
+        }
+
+        self.bvh_manager.borrow_mut().set_grid_settings(settings);
+    }
+
+    /// Gets the settings of the grid used by broadphase and scene queries.
+    pub fn grid_settings(&self) -> GridSettings {
+        self.bvh_manager.borrow().grid_settings()
+    }
+
+    /// Enables or disables continuous collision detection for the entity's collider.
+    ///
+    /// Colliders normally only collide with the colliders they overlap at the end of each
+    /// collision update, so a small collider moving fast enough can pass through a thin collider
+    /// without ever overlapping it. When continuous collision detection is enabled the collider
+    /// is also swept from where it was at the previous update to where it is now, and a collision
+    /// is reported with the first collider it touched on the way, see `Contact::time_of_impact`.
+    ///
+    /// The sweep only follows the collider's change in position, not its rotation. It's meant for
+    /// small, fast colliders such as projectiles since it's much more expensive than the regular
+    /// test, and it has no effect on mesh colliders since they can't be swept. Continuous
+    /// collision detection is disabled by default and takes effect at the next collision update.
+    pub fn set_continuous(&self, entity: Entity, continuous: bool) {
+        if continuous {
+            self.continuous.borrow_mut().insert(entity);
+        } else {
+            self.continuous.borrow_mut().remove(&entity);
+        }
+    }
+
+    /// Checks if continuous collision detection is enabled for the entity's collider.
+    pub fn is_continuous(&self, entity: Entity) -> bool {
+        self.continuous.borrow().contains(&entity)
+    }
+
+    /// Finds the first collider hit by `ray` within `max_distance` of its origin.
+    ///
+    /// Only colliders in at least one of the layers in `layer_mask` are considered. A collider
+    /// containing the ray's origin is hit at a distance of 0, with the normal facing back along
+    /// the ray. `ray.direction` must be normalized.
+    pub fn raycast(&self, ray: Ray, max_distance: f32, layer_mask: LayerMask) -> Option<RaycastHit> {
+        let bvh_manager = self.bvh_manager.borrow();
+
+        let mut closest: Option<RaycastHit> = None;
+        let mut tested = EntitySet::default();
+        bvh_manager.grid().visit_ray(ray.origin, ray.direction, max_distance, |entities, cell_distance| {
+            // Colliders in this cell or any further along the ray can't be closer than a hit
+            // that's closer than the cell.
+            if let Some(ref closest) = closest {
+                if closest.distance < cell_distance {
+                    return true;
+                }
+            }
+
+            for &entity in entities {
+                if !tested.insert(entity) {
+                    continue;
+                }
+
+                let hit = match bvh_manager.get(entity) {
+                    Some(bvh) => bvh.raycast(&ray, max_distance, layer_mask),
+                    None => None,
+                };
+
+                if let Some(hit) = hit {
+                    if closest.as_ref().map(|closest| hit.distance < closest.distance).unwrap_or(true) {
+                        closest = Some(hit);
+                    }
+                }
+            }
+
+            false
+        });
+
+        closest
+    }
+
+    /// Finds every collider hit by `ray` within `max_distance` of its origin, sorted from closest
+    /// to furthest.
+    ///
+    /// See `raycast()` for more details.
+    pub fn raycast_all(&self, ray: Ray, max_distance: f32, layer_mask: LayerMask) -> Vec<RaycastHit> {
+        let bvh_manager = self.bvh_manager.borrow();
+
+        let mut hits = Vec::new();
+        let mut tested = EntitySet::default();
+        bvh_manager.grid().visit_ray(ray.origin, ray.direction, max_distance, |entities, _| {
+            for &entity in entities {
+                if !tested.insert(entity) {
+                    continue;
+                }
+
+                if let Some(hit) = bvh_manager.get(entity).and_then(|bvh| bvh.raycast(&ray, max_distance, layer_mask)) {
+                    hits.push(hit);
+                }
+            }
+
+            false
+        });
+
+        hits.sort_by(|lhs, rhs| lhs.distance.partial_cmp(&rhs.distance).unwrap_or(Ordering::Equal));
+        hits
+    }
+
+    /// Moves `sphere` along `direction` and finds the first collider it touches within
+    /// `max_distance`.
+    ///
+    /// The hit's `point` and `normal` are on the surface of the collider that was touched, and its
+    /// `distance` is how far the sphere moved before touching it. Colliders that the sphere
+    /// already overlaps are hit at a distance of 0, so a sphere cast from an entity's own collider
+    /// should use a `layer_mask` that excludes it. `direction` must be normalized.
+    pub fn sphere_cast(
+        &self,
+        sphere: Sphere,
+        direction: Vector3,
+        max_distance: f32,
+        layer_mask: LayerMask,
+    ) -> Option<RaycastHit> {
+        self.sweep_cast(&CachedCollider::Sphere(sphere), direction, max_distance, layer_mask)
+    }
+
+    /// Moves `obb` along `direction` without rotating it, and finds the first collider it touches
+    /// within `max_distance`.
+    ///
+    /// See `sphere_cast()` for more details.
+    pub fn box_cast(
+        &self,
+        obb: OBB,
+        direction: Vector3,
+        max_distance: f32,
+        layer_mask: LayerMask,
+    ) -> Option<RaycastHit> {
+        self.sweep_cast(&CachedCollider::Box(obb), direction, max_distance, layer_mask)
+    }
+
+    /// Finds every collider touching the sphere at `center` with radius `radius`.
+    ///
+    /// The entities are returned in no particular order. Only colliders in one of the layers in
+    /// `layer_mask` are considered.
+    pub fn overlap_sphere(&self, center: Point, radius: f32, layer_mask: LayerMask) -> Vec<Entity> {
+        let sphere = Sphere {
+            center: center,
+            radius: radius,
+        };
+        self.overlap(&CachedCollider::Sphere(sphere), layer_mask)
+    }
+
+    /// Finds every collider touching `obb`.
+    ///
+    /// See `overlap_sphere()` for more details.
+    pub fn overlap_box(&self, obb: OBB, layer_mask: LayerMask) -> Vec<Entity> {
+        self.overlap(&CachedCollider::Box(obb), layer_mask)
+    }
+
+    fn overlap(&self, shape: &CachedCollider, layer_mask: LayerMask) -> Vec<Entity> {
+        let bvh_manager = self.bvh_manager.borrow();
+        let aabb = AABB::from_collider(shape);
+
+        let mut overlapping = Vec::new();
+        let mut tested = EntitySet::default();
+        bvh_manager.grid().visit_aabb(&aabb, |entities| {
+            for &entity in entities {
+                if !tested.insert(entity) {
+                    continue;
+                }
+
+                let bvh = match bvh_manager.get(entity) {
+                    Some(bvh) => bvh,
+                    None => continue,
+                };
+
+                if bvh.layers & layer_mask != 0 && bvh.aabb.test_aabb(&aabb) && shape.test(&bvh.collider) {
+                    overlapping.push(entity);
+                }
+            }
+        });
+
+        overlapping
+    }
+
+    fn sweep_cast(
+        &self,
+        shape: &CachedCollider,
+        direction: Vector3,
+        max_distance: f32,
+        layer_mask: LayerMask,
+    ) -> Option<RaycastHit> {
+        let bvh_manager = self.bvh_manager.borrow();
+
+        // Gather candidates from every cell the shape passes through.
+        let start = AABB::from_collider(shape);
+        let end = AABB {
+            min: start.min + direction * max_distance,
+            max: start.max + direction * max_distance,
+        };
+        let swept = AABB {
+            min: Point::new(start.min.x.min(end.min.x), start.min.y.min(end.min.y), start.min.z.min(end.min.z)),
+            max: Point::new(start.max.x.max(end.max.x), start.max.y.max(end.max.y), start.max.z.max(end.max.z)),
+        };
+
+        let mut closest: Option<RaycastHit> = None;
+        let mut tested = EntitySet::default();
+        bvh_manager.grid().visit_aabb(&swept, |entities| {
+            for &entity in entities {
+                if !tested.insert(entity) {
+                    continue;
+                }
+
+                let bvh = match bvh_manager.get(entity) {
+                    Some(bvh) => bvh,
+                    None => continue,
+                };
+
+                if bvh.layers & layer_mask == 0 || !bvh.aabb.test_aabb(&swept) {
+                    continue;
+                }
+
+                // Only contacts closer than the current closest hit are interesting.
+                let max_distance = closest.as_ref().map(|closest| closest.distance).unwrap_or(max_distance);
+                if let Some(contact) = sweep::sweep(shape, direction, max_distance, &bvh.collider) {
+                    closest = Some(RaycastHit {
+                        entity: entity,
+                        point: contact.point,
+                        normal: contact.normal,
+                        distance: contact.distance,
+                    });
+                }
+            }
+        });
+
+        closest
+    }
+
     pub fn iter(&self) -> Iter<Collider> {
         self.inner.iter()
     }
@@ -266,6 +604,24 @@ impl ColliderManager {
     }
 }
 
+/// A collider hit by a ray or a swept shape, see `ColliderManager::raycast()` and
+/// `ColliderManager::sphere_cast()`.
+#[derive(Debug, Clone, Copy)]
+pub struct RaycastHit {
+    /// The entity that owns the collider that was hit.
+    pub entity: Entity,
+
+    /// The point where the ray or shape hit the collider, in world space.
+    pub point: Point,
+
+    /// The surface normal of the collider where it was hit, in world space.
+    pub normal: Vector3,
+
+    /// The distance from the ray's origin to `point`, or for a sweep how far the shape moved
+    /// before touching the collider.
+    pub distance: f32,
+}
+
 impl ComponentManagerBase for ColliderManager {}
 
 impl ComponentManager for ColliderManager {
@@ -300,11 +656,11 @@ impl ComponentManager for ColliderManager {
 /// Like `Collider` this is an enum, but whereas `Collider` uses struct variants, `CachedCollider`
 /// opts to create separate types for each variant. This allows for each variant to have it's own
 /// member functions which helps to clean up the collision testing code a bit.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum CachedCollider {
     Sphere(Sphere),
     Box(OBB),
-    Mesh,
+    Mesh(TransformedMesh),
 }
 
 impl CachedCollider {
@@ -328,7 +684,14 @@ impl CachedCollider {
                 };
                 CachedCollider::Box(obb)
             },
-            &Collider::Mesh => unimplemented!(),
+            &Collider::Mesh { offset, ref mesh } => {
+                CachedCollider::Mesh(TransformedMesh {
+                    mesh: mesh.clone(),
+                    center: transform.position_derived() + offset,
+                    orientation: Matrix3::from_quaternion(transform.rotation_derived()),
+                    scale: transform.scale_derived(),
+                })
+            },
         }
     }
 
@@ -340,7 +703,46 @@ impl CachedCollider {
             &CachedCollider::Box(obb) => {
                 obb.test_collider(other)
             },
-            &CachedCollider::Mesh => unimplemented!(),
+            &CachedCollider::Mesh(ref mesh) => {
+                mesh.test_collider(other)
+            },
+        }
+    }
+
+    /// Gets the center of the collider in world space.
+    pub fn center(&self) -> Point {
+        match self {
+            &CachedCollider::Sphere(sphere) => sphere.center,
+            &CachedCollider::Box(obb) => obb.center,
+            &CachedCollider::Mesh(ref mesh) => mesh.center,
+        }
+    }
+
+    /// Gets a copy of the collider moved by `offset`.
+    pub fn translated(&self, offset: Vector3) -> CachedCollider {
+        match self {
+            &CachedCollider::Sphere(sphere) => CachedCollider::Sphere(Sphere {
+                center: sphere.center + offset,
+                .. sphere
+            }),
+            &CachedCollider::Box(obb) => CachedCollider::Box(OBB {
+                center: obb.center + offset,
+                .. obb
+            }),
+            &CachedCollider::Mesh(ref mesh) => CachedCollider::Mesh(TransformedMesh {
+                center: mesh.center + offset,
+                .. mesh.clone()
+            }),
+        }
+    }
+
+    /// Casts `ray` against the collider, returning the distance to where it hits the collider and
+    /// the collider's normal at that point.
+    pub fn raycast(&self, ray: &Ray) -> Option<(f32, Vector3)> {
+        match self {
+            &CachedCollider::Sphere(sphere) => sphere.raycast(ray),
+            &CachedCollider::Box(obb) => obb.raycast(ray),
+            &CachedCollider::Mesh(ref mesh) => mesh.raycast(ray),
         }
     }
 
@@ -360,7 +762,10 @@ impl CachedCollider {
                   * Matrix4::from_scale_vector(obb.half_widths * 2.0);
                 debug_draw::box_matrix_color(transform, color);
             },
-            &CachedCollider::Mesh => unimplemented!(),
+            &CachedCollider::Mesh(ref mesh) => {
+                let aabb = mesh.aabb();
+                debug_draw::box_min_max_color(aabb.min, aabb.max, color);
+            },
         }
     }
 }
@@ -376,7 +781,7 @@ impl Sphere {
         match *other {
             CachedCollider::Sphere(sphere) => self.test_sphere(&sphere),
             CachedCollider::Box(obb) => self.test_obb(&obb),
-            CachedCollider::Mesh => unimplemented!(),
+            CachedCollider::Mesh(ref mesh) => mesh.test_sphere(self),
         }
     }
 
@@ -392,6 +797,42 @@ impl Sphere {
         let dist_sqr = obb.closest_distance_squared(self.center);
         dist_sqr < self.radius * self.radius
     }
+
+    pub fn raycast(&self, ray: &Ray) -> Option<(f32, Vector3)> {
+        let offset = ray.origin - self.center;
+        let b = offset.dot(ray.direction);
+        let c = offset.magnitude_squared() - self.radius * self.radius;
+
+        // The ray starts inside the sphere.
+        if c <= 0.0 {
+            return Some((0.0, -ray.direction));
+        }
+
+        // The ray starts outside the sphere and points away from it.
+        if b > 0.0 {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let distance = -b - discriminant.sqrt();
+        let normal = (ray.point_at(distance) - self.center).normalized();
+        Some((distance, normal))
+    }
+}
+
+/// A sphere swept along the segment from `start` to `end`.
+///
+/// Capsules aren't supported as colliders yet, but they can be tested against mesh colliders with
+/// `TransformedMesh::test_capsule()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule {
+    pub start: Point,
+    pub end: Point,
+    pub radius: f32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -406,7 +847,7 @@ impl OBB {
         match other {
             &CachedCollider::Sphere(sphere) => sphere.test_obb(self),
             &CachedCollider::Box(ref obb) => self.test_obb(obb),
-            &CachedCollider::Mesh => unimplemented!(),
+            &CachedCollider::Mesh(ref mesh) => mesh.test_obb(self),
         }
     }
 
@@ -545,6 +986,35 @@ impl OBB {
         true
     }
 
+    pub fn raycast(&self, ray: &Ray) -> Option<(f32, Vector3)> {
+        // Cast the ray against the box in its local space, where it's an AABB.
+        let to_local = self.orientation.transpose();
+        let origin = Point::from((ray.origin - self.center) * to_local);
+        let direction = ray.direction * to_local;
+        let local_box = AABB {
+            min: Point::from(-self.half_widths),
+            max: Point::from(self.half_widths),
+        };
+
+        let distance = match local_box.intersect_ray(origin, direction) {
+            Some((enter, _)) if enter <= 0.0 => return Some((0.0, -ray.direction)),
+            Some((enter, _)) => enter,
+            None => return None,
+        };
+
+        // The ray entered through the face it hit closest to the face's plane.
+        let hit = (origin + direction * distance).as_vector3();
+        let mut axis = 0;
+        for candidate in 1..3 {
+            if (hit[candidate] / self.half_widths[candidate]).abs() > (hit[axis] / self.half_widths[axis]).abs() {
+                axis = candidate;
+            }
+        }
+
+        let normal = self.orientation.col(axis) * hit[axis].signum();
+        Some((distance, normal))
+    }
+
     /// Calculates the closest point to the given point on (or in) the OBB.
     pub fn closest_point(&self, point: Point) -> Point {
         let d = point - self.center;
@@ -585,6 +1055,14 @@ impl CollisionSystem {
             grid_system: GridCollisionSystem::new(),
         }
     }
+
+    /// Gets the collisions detected during the last update along with their contacts.
+    ///
+    /// Each colliding pair of entities appears once, in no particular order. The contact's normal
+    /// points from the first entity of the pair into the second.
+    pub fn contacts(&self) -> &HashMap<(Entity, Entity), Contact, FnvHashState> {
+        &self.grid_system.collisions
+    }
 }
 
 impl System for CollisionSystem {
@@ -606,17 +1084,19 @@ impl System for CollisionSystem {
         for entity in marked_for_destroy.drain() {
             collider_manager.callback_manager.borrow_mut().unregister_all(entity);
             collider_manager.bvh_manager.borrow_mut().destroy_immediate(entity);
+            collider_manager.layers.borrow_mut().remove(&entity);
+            collider_manager.continuous.borrow_mut().remove(&entity);
         }
     }
 }
 
 pub trait CollisionCallback {
-    fn invoke(&mut self, scene: &Scene, first: Entity, others: &[Entity]);
+    fn invoke(&mut self, scene: &Scene, first: Entity, collisions: &[Collision]);
 }
 
-impl<T: ?Sized + 'static> CollisionCallback for T where T: FnMut(&Scene, Entity, &[Entity]) {
-    fn invoke(&mut self, scene: &Scene, first: Entity, others: &[Entity]) {
-        self.call_mut((scene, first, others));
+impl<T: ?Sized + 'static> CollisionCallback for T where T: FnMut(&Scene, Entity, &[Collision]) {
+    fn invoke(&mut self, scene: &Scene, first: Entity, collisions: &[Collision]) {
+        self.call_mut((scene, first, collisions));
     }
 }
 
@@ -626,11 +1106,21 @@ impl ::std::fmt::Debug for CollisionCallback {
     }
 }
 
+/// A collision with another entity, passed to collision callbacks.
+#[derive(Debug, Clone, Copy)]
+pub struct Collision {
+    /// The entity that was collided with.
+    pub other: Entity,
+
+    /// How the colliders touch, with the normal pointing into `other`'s collider.
+    pub contact: Contact,
+}
+
 #[derive(Debug, Clone)]
 pub struct CollisionCallbackManager {
     callbacks: CallbackManager<CollisionCallback>,
     entity_callbacks: EntityMap<Vec<CallbackId>>,
-    entity_collisions: EntityMap<Vec<Entity>>,
+    entity_collisions: EntityMap<Vec<Collision>>,
 }
 
 impl CollisionCallbackManager {
@@ -674,19 +1164,26 @@ impl CollisionCallbackManager {
     }
 
     /// For a pair of colliding entities A and B, we assume that there is either an entry (A, B) or
-    /// (B, A), but not both. We manually invoke the callback for both colliding entities.
+    /// (B, A), but not both. We manually invoke the callback for both colliding entities, flipping
+    /// the contact for B so that its normal points away from B.
     pub fn process_collisions<'a, H>(
         &mut self,
         scene: &Scene,
         collisions: H,
-    ) where H: IntoIterator<Item = &'a (Entity, Entity)>{
+    ) where H: IntoIterator<Item = (&'a (Entity, Entity), &'a Contact)>{
         let _stopwatch = Stopwatch::new("Process Collision Callbacks");
 
         {
             let _stopwatch = Stopwatch::new("Sort Collision Data");
-            for &(entity, other) in collisions {
-                self.entity_collisions.entry(entity).or_insert(Vec::new()).push(other);
-                self.entity_collisions.entry(other).or_insert(Vec::new()).push(entity);
+            for (&(entity, other), contact) in collisions {
+                self.entity_collisions.entry(entity).or_insert(Vec::new()).push(Collision {
+                    other: other,
+                    contact: *contact,
+                });
+                self.entity_collisions.entry(other).or_insert(Vec::new()).push(Collision {
+                    other: entity,
+                    contact: contact.flipped(),
+                });
             }
         }
 
@@ -780,3 +1277,30 @@ fn obb_obb_tests() {
     obb_test!(unit, unit, true);
     obb_test!(unit, rot_z, true);
 }
+
+#[test]
+fn raycast_tests() {
+    let sphere = Sphere {
+        center: Point::new(0.0, 0.0, -5.0),
+        radius: 1.0,
+    };
+
+    let obb = OBB {
+        center: Point::new(3.0, 0.0, 0.0),
+        orientation: Matrix3::identity(),
+        half_widths: Vector3::new(0.5, 0.5, 0.5),
+    };
+
+    let forward = Ray { origin: Point::origin(), direction: Vector3::new(0.0, 0.0, -1.0) };
+    let (distance, normal) = sphere.raycast(&forward).unwrap();
+    assert!((distance - 4.0).is_zero(), "Expected distance 4, got {}", distance);
+    assert!((normal - Vector3::new(0.0, 0.0, 1.0)).is_zero(), "Unexpected normal {:?}", normal);
+
+    let right = Ray { origin: Point::origin(), direction: Vector3::new(1.0, 0.0, 0.0) };
+    assert!(sphere.raycast(&right).is_none());
+
+    let (distance, normal) = obb.raycast(&right).unwrap();
+    assert!((distance - 2.5).is_zero(), "Expected distance 2.5, got {}", distance);
+    assert!((normal - Vector3::new(-1.0, 0.0, 0.0)).is_zero(), "Unexpected normal {:?}", normal);
+    assert!(obb.raycast(&forward).is_none());
+}
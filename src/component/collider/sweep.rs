@@ -0,0 +1,473 @@
+//! Swept shape tests, used by `ColliderManager::sphere_cast()` and `ColliderManager::box_cast()`.
+//!
+//! A sweep moves a shape along a direction and finds the first point at which it touches another
+//! collider. Only the moving shape's position changes during the sweep, never its orientation.
+//! Different pairs of shapes use different methods:
+//!
+//! - Sphere vs sphere is solved exactly by casting a ray against a sphere with the sum of their
+//!   radii.
+//! - Sphere vs box and sphere vs mesh use conservative advancement: the sphere is repeatedly moved
+//!   forward by its distance to the other shape, which can never move it past the point of
+//!   contact. This converges quickly unless the sphere is sliding along the surface, in which case
+//!   it gives up after `MAX_ITERATIONS` and reports no contact.
+//! - Box vs box and box vs mesh use the separating axis test extended to moving shapes, which
+//!   finds the time at which the last separating axis stops separating the shapes.
+//! - Box vs sphere sweeps the sphere backwards against the box.
+//!
+//! Moving mesh colliders can't be swept.
+
+use std::f32::{MAX, MIN};
+
+use math::*;
+use polygon::camera::Ray;
+use super::{CachedCollider, OBB, Sphere};
+use super::mesh_collider::{Triangle, TransformedMesh};
+use super::bounding_volume::AABB;
+
+/// The most steps taken by conservative advancement before giving up.
+const MAX_ITERATIONS: usize = 32;
+
+/// How close conservative advancement has to get to the other shape to count as touching it.
+const TOLERANCE: f32 = 1e-4;
+
+/// The first contact found by a sweep.
+#[derive(Debug, Clone, Copy)]
+pub struct SweepContact {
+    /// How far the shape moved before touching the other collider.
+    pub distance: f32,
+
+    /// A point on the surface of the other collider where the shapes touch.
+    pub point: Point,
+
+    /// The surface normal of the other collider at `point`, facing the moving shape.
+    pub normal: Vector3,
+}
+
+/// Sweeps `moving` from its current position along `direction` for up to `max_distance`,
+/// returning the first contact with `target`.
+///
+/// `direction` must be normalized. If the shapes already overlap the contact is at a distance of
+/// 0, with the normal facing back along `direction`.
+pub fn sweep(
+    moving: &CachedCollider,
+    direction: Vector3,
+    max_distance: f32,
+    target: &CachedCollider,
+) -> Option<SweepContact> {
+    match *moving {
+        CachedCollider::Sphere(ref sphere) => sweep_sphere(sphere, direction, max_distance, target),
+        CachedCollider::Box(ref obb) => sweep_obb(obb, direction, max_distance, target),
+        CachedCollider::Mesh(_) => None,
+    }
+}
+
+/// Sweeps a sphere against `target`, see `sweep()`.
+pub fn sweep_sphere(
+    sphere: &Sphere,
+    direction: Vector3,
+    max_distance: f32,
+    target: &CachedCollider,
+) -> Option<SweepContact> {
+    match *target {
+        CachedCollider::Sphere(ref other) => {
+            let expanded = Sphere {
+                center: other.center,
+                radius: sphere.radius + other.radius,
+            };
+            let ray = Ray {
+                origin: sphere.center,
+                direction: direction,
+            };
+
+            let distance = match expanded.raycast(&ray) {
+                Some((distance, _)) if distance <= max_distance => distance,
+                _ => return None,
+            };
+
+            let offset = ray.point_at(distance) - other.center;
+            let normal = if offset.is_zero() { -direction } else { offset.normalized() };
+            Some(SweepContact {
+                distance: distance,
+                point: other.center + normal * other.radius,
+                normal: normal,
+            })
+        },
+
+        CachedCollider::Box(ref obb) => {
+            advance_sphere(
+                sphere.center.as_vector3(),
+                sphere.radius,
+                direction,
+                max_distance,
+                |center| obb.closest_point(Point::from(center)).as_vector3())
+            .map(|(distance, point, normal)| SweepContact {
+                distance: distance,
+                point: Point::from(point),
+                normal: normal,
+            })
+        },
+
+        CachedCollider::Mesh(ref mesh) => sweep_sphere_mesh(sphere, direction, max_distance, mesh),
+    }
+}
+
+/// Sweeps an oriented box against `target`, see `sweep()`.
+pub fn sweep_obb(
+    obb: &OBB,
+    direction: Vector3,
+    max_distance: f32,
+    target: &CachedCollider,
+) -> Option<SweepContact> {
+    match *target {
+        CachedCollider::Sphere(ref sphere) => {
+            // Moving the box towards the sphere is the same as moving the sphere backwards
+            // towards the box, with the normal pointing the other way.
+            advance_sphere(
+                sphere.center.as_vector3(),
+                sphere.radius,
+                -direction,
+                max_distance,
+                |center| obb.closest_point(Point::from(center)).as_vector3())
+            .map(|(distance, _, normal)| SweepContact {
+                distance: distance,
+                point: sphere.center - normal * sphere.radius,
+                normal: -normal,
+            })
+        },
+
+        CachedCollider::Box(ref other) => {
+            let axes = [obb.orientation.col(0), obb.orientation.col(1), obb.orientation.col(2)];
+            let other_axes = [other.orientation.col(0), other.orientation.col(1), other.orientation.col(2)];
+            let center = obb.center.as_vector3();
+            let other_center = other.center.as_vector3();
+            let velocity = direction * max_distance;
+
+            let mut sat = SweptAxes::new();
+            let separated = {
+                let mut test_axis = |axis: Vector3| {
+                    sat.test_axis(
+                        axis,
+                        box_interval(center, &axes, obb.half_widths, axis),
+                        box_interval(other_center, &other_axes, other.half_widths, axis),
+                        velocity.dot(axis))
+                };
+
+                let mut separated = axes.iter().chain(other_axes.iter()).any(|&axis| !test_axis(axis));
+                for &axis in &axes {
+                    for &other_axis in &other_axes {
+                        separated = separated || !test_axis(Vector3::cross(axis, other_axis));
+                    }
+                }
+
+                separated
+            };
+            if separated {
+                return None;
+            }
+
+            sat.contact(direction, max_distance).map(|(distance, normal)| SweepContact {
+                distance: distance,
+                point: other.closest_point(obb.center + direction * distance),
+                normal: normal,
+            })
+        },
+
+        CachedCollider::Mesh(ref mesh) => sweep_obb_mesh(obb, direction, max_distance, mesh),
+    }
+}
+
+fn sweep_sphere_mesh(
+    sphere: &Sphere,
+    direction: Vector3,
+    max_distance: f32,
+    mesh: &TransformedMesh,
+) -> Option<SweepContact> {
+    let center = mesh.to_local_point(sphere.center);
+    let local_direction = mesh.to_local_vector(direction);
+    let radius = Vector3::new(sphere.radius, sphere.radius, sphere.radius);
+    let bounds = swept_bounds(center - radius, center + radius, local_direction * max_distance);
+
+    let mut closest: Option<(f32, Vector3, Vector3)> = None;
+    for triangle in candidate_triangles(mesh, &bounds) {
+        let max_distance = closest.map(|(distance, _, _)| distance).unwrap_or(max_distance);
+        let contact = advance_sphere(
+            center,
+            sphere.radius,
+            local_direction,
+            max_distance,
+            |center| triangle.closest_point(center));
+        if contact.is_some() {
+            closest = contact;
+        }
+    }
+
+    closest.map(|(distance, point, normal)| SweepContact {
+        distance: distance,
+        point: mesh.to_world_point(point),
+        normal: mesh.to_world_vector(normal),
+    })
+}
+
+fn sweep_obb_mesh(
+    obb: &OBB,
+    direction: Vector3,
+    max_distance: f32,
+    mesh: &TransformedMesh,
+) -> Option<SweepContact> {
+    let center = mesh.to_local_point(obb.center);
+    let axes = [
+        mesh.to_local_vector(obb.orientation.col(0)),
+        mesh.to_local_vector(obb.orientation.col(1)),
+        mesh.to_local_vector(obb.orientation.col(2)),
+    ];
+    let local_direction = mesh.to_local_vector(direction);
+    let velocity = local_direction * max_distance;
+
+    let mut extents = Vector3::zero();
+    for axis in 0..3 {
+        extents[axis] =
+            obb.half_widths.x * axes[0][axis].abs()
+          + obb.half_widths.y * axes[1][axis].abs()
+          + obb.half_widths.z * axes[2][axis].abs();
+    }
+    let bounds = swept_bounds(center - extents, center + extents, velocity);
+
+    let mut closest: Option<(f32, Vector3, Vector3)> = None;
+    for triangle in candidate_triangles(mesh, &bounds) {
+        let vertices = triangle.vertices();
+        let edges = [triangle.b - triangle.a, triangle.c - triangle.b, triangle.a - triangle.c];
+
+        let mut sat = SweptAxes::new();
+        let separated = {
+            let mut test_axis = |axis: Vector3| {
+                sat.test_axis(
+                    axis,
+                    box_interval(center, &axes, obb.half_widths, axis),
+                    triangle_interval(&vertices, axis),
+                    velocity.dot(axis))
+            };
+
+            let mut separated = axes.iter().any(|&axis| !test_axis(axis));
+            separated = separated || !test_axis(Vector3::cross(edges[0], edges[1]));
+            for &axis in &axes {
+                for &edge in &edges {
+                    separated = separated || !test_axis(Vector3::cross(axis, edge));
+                }
+            }
+
+            separated
+        };
+        if separated {
+            continue;
+        }
+
+        if let Some((distance, normal)) = sat.contact(local_direction, max_distance) {
+            if closest.map(|(closest, _, _)| distance < closest).unwrap_or(true) {
+                let point = triangle.closest_point(center + local_direction * distance);
+                closest = Some((distance, point, normal));
+            }
+        }
+    }
+
+    closest.map(|(distance, point, normal)| SweepContact {
+        distance: distance,
+        point: mesh.to_world_point(point),
+        normal: mesh.to_world_vector(normal),
+    })
+}
+
+/// Moves a sphere along `direction` until it touches the shape whose closest point to a given
+/// point is found by `closest_point`, using conservative advancement.
+///
+/// Returns the distance moved, the point of contact, and the shape's normal at that point, or
+/// `None` if the sphere doesn't touch the shape within `max_distance`.
+fn advance_sphere<F>(
+    center: Vector3,
+    radius: f32,
+    direction: Vector3,
+    max_distance: f32,
+    closest_point: F,
+) -> Option<(f32, Vector3, Vector3)> where F: Fn(Vector3) -> Vector3 {
+    let mut distance = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        let moved_center = center + direction * distance;
+        let closest = closest_point(moved_center);
+        let offset = moved_center - closest;
+        let gap = offset.magnitude() - radius;
+
+        if gap <= TOLERANCE {
+            let normal = if offset.is_zero() { -direction } else { offset.normalized() };
+            return Some((distance, closest, normal));
+        }
+
+        distance += gap;
+        if distance > max_distance {
+            return None;
+        }
+    }
+
+    None
+}
+
+/// Tracks the separating axis test for a shape moving at a constant velocity over `t` from 0 to
+/// 1, see the module documentation.
+struct SweptAxes {
+    /// The latest time at which an axis stops separating the shapes.
+    enter: f32,
+
+    /// The earliest time at which an axis starts separating the shapes again.
+    exit: f32,
+
+    /// The axis that stops separating the shapes last, facing the moving shape.
+    normal: Vector3,
+}
+
+impl SweptAxes {
+    fn new() -> SweptAxes {
+        SweptAxes {
+            enter: MIN,
+            exit: MAX,
+            normal: Vector3::zero(),
+        }
+    }
+
+    /// Tests `axis`, where `moving` and `target` are the shapes' intervals along the axis at
+    /// `t = 0` and `speed` is how far the moving shape's interval moves by `t = 1`.
+    ///
+    /// Returns `false` if the axis separates the shapes for the whole sweep.
+    fn test_axis(&mut self, axis: Vector3, moving: (f32, f32), target: (f32, f32), speed: f32) -> bool {
+        // Edges that are (nearly) parallel produce a degenerate axis that can't separate.
+        if axis.magnitude_squared() < EPSILON {
+            return true;
+        }
+
+        let (moving_min, moving_max) = moving;
+        let (target_min, target_max) = target;
+
+        if speed.abs() < EPSILON {
+            return moving_max >= target_min && moving_min <= target_max;
+        }
+
+        let first = (target_min - moving_max) / speed;
+        let second = (target_max - moving_min) / speed;
+        let (enter, exit) = if first < second { (first, second) } else { (second, first) };
+
+        if enter > self.enter {
+            self.enter = enter;
+            self.normal = if speed > 0.0 { -axis } else { axis };
+        }
+        self.exit = self.exit.min(exit);
+
+        self.enter <= self.exit
+    }
+
+    /// Gets the distance along `direction` and the normal at which the shapes first touch, if
+    /// they touch during the sweep.
+    fn contact(&self, direction: Vector3, max_distance: f32) -> Option<(f32, Vector3)> {
+        if self.enter > 1.0 || self.exit < 0.0 {
+            return None;
+        }
+
+        if self.enter <= 0.0 {
+            Some((0.0, -direction))
+        } else {
+            Some((self.enter * max_distance, self.normal.normalized()))
+        }
+    }
+}
+
+/// Projects a box onto `axis`.
+pub(super) fn box_interval(center: Vector3, axes: &[Vector3; 3], half_widths: Vector3, axis: Vector3) -> (f32, f32) {
+    let center = center.dot(axis);
+    let radius =
+        half_widths.x * axes[0].dot(axis).abs()
+      + half_widths.y * axes[1].dot(axis).abs()
+      + half_widths.z * axes[2].dot(axis).abs();
+
+    (center - radius, center + radius)
+}
+
+/// Projects a triangle onto `axis`.
+pub(super) fn triangle_interval(vertices: &[Vector3; 3], axis: Vector3) -> (f32, f32) {
+    let projections = [vertices[0].dot(axis), vertices[1].dot(axis), vertices[2].dot(axis)];
+    (
+        projections[0].min(projections[1]).min(projections[2]),
+        projections[0].max(projections[1]).max(projections[2]),
+    )
+}
+
+/// Gets the box covering a box from `min` to `max` as it moves by `velocity`.
+fn swept_bounds(min: Vector3, max: Vector3, velocity: Vector3) -> AABB {
+    let mut swept_min = min;
+    let mut swept_max = max;
+    for axis in 0..3 {
+        if velocity[axis] < 0.0 {
+            swept_min[axis] += velocity[axis];
+        } else {
+            swept_max[axis] += velocity[axis];
+        }
+    }
+
+    AABB {
+        min: Point::from(swept_min),
+        max: Point::from(swept_max),
+    }
+}
+
+/// Gets the scaled triangles of `mesh` that might overlap `bounds`.
+pub(super) fn candidate_triangles(mesh: &TransformedMesh, bounds: &AABB) -> Vec<Triangle> {
+    let mut triangles = Vec::new();
+    mesh.mesh.visit(mesh.scale, bounds, |triangle| {
+        triangles.push(*triangle);
+        false
+    });
+
+    triangles
+}
+
+#[test]
+fn sweep_tests() {
+    let sphere = Sphere {
+        center: Point::origin(),
+        radius: 0.5,
+    };
+
+    let target_sphere = CachedCollider::Sphere(Sphere {
+        center: Point::new(5.0, 0.0, 0.0),
+        radius: 1.0,
+    });
+
+    let target_box = CachedCollider::Box(OBB {
+        center: Point::new(5.0, 0.0, 0.0),
+        orientation: Matrix3::identity(),
+        half_widths: Vector3::new(1.0, 1.0, 1.0),
+    });
+
+    let right = Vector3::new(1.0, 0.0, 0.0);
+    let up = Vector3::new(0.0, 1.0, 0.0);
+
+    let contact = sweep_sphere(&sphere, right, 10.0, &target_sphere).unwrap();
+    assert!((contact.distance - 3.5).abs() < 1e-3, "Expected distance 3.5, got {}", contact.distance);
+    assert!((contact.normal + right).is_zero(), "Unexpected normal {:?}", contact.normal);
+    assert!(sweep_sphere(&sphere, right, 3.0, &target_sphere).is_none());
+    assert!(sweep_sphere(&sphere, up, 10.0, &target_sphere).is_none());
+
+    let contact = sweep_sphere(&sphere, right, 10.0, &target_box).unwrap();
+    assert!((contact.distance - 3.5).abs() < 1e-3, "Expected distance 3.5, got {}", contact.distance);
+    assert!((contact.normal + right).is_zero(), "Unexpected normal {:?}", contact.normal);
+
+    let obb = OBB {
+        center: Point::origin(),
+        orientation: Matrix3::identity(),
+        half_widths: Vector3::new(0.5, 0.5, 0.5),
+    };
+
+    let contact = sweep_obb(&obb, right, 10.0, &target_box).unwrap();
+    assert!((contact.distance - 3.5).abs() < 1e-3, "Expected distance 3.5, got {}", contact.distance);
+    assert!((contact.normal + right).is_zero(), "Unexpected normal {:?}", contact.normal);
+    assert!(sweep_obb(&obb, up, 10.0, &target_box).is_none());
+
+    let contact = sweep_obb(&obb, right, 10.0, &target_sphere).unwrap();
+    assert!((contact.distance - 3.5).abs() < 1e-3, "Expected distance 3.5, got {}", contact.distance);
+    assert!((contact.normal + right).is_zero(), "Unexpected normal {:?}", contact.normal);
+}
@@ -22,7 +22,7 @@ pub use self::mesh::{Mesh, MeshManager};
 pub use self::light::{Light, LightManager};
 pub use self::audio::{AudioSource, AudioSourceManager, AudioSystem};
 pub use self::alarm::{AlarmId, AlarmManager, alarm_update};
-pub use self::collider::{Collider, ColliderManager, CollisionSystem, bounding_volume, grid_collision};
+pub use self::collider::{Collider, ColliderManager, Collision, CollisionSystem, bounding_volume, grid_collision};
 
 #[derive(Debug, Clone)]
 pub struct DefaultManager<T>(StructComponentManager<T>)
@@ -10,6 +10,7 @@ use light::LightInner;
 use polygon::{GpuMesh, Renderer, RendererBuilder};
 use polygon::anchor::Anchor;
 use polygon::camera::{Camera as RenderCamera, CameraId};
+use polygon::line_renderer::LineRenderer;
 use polygon::material::MaterialId as PolygonMaterialId;
 use polygon::mesh_instance::MeshInstance;
 use std::collections::HashMap;
@@ -17,7 +18,7 @@ use std::fs::File;
 use std::io::Write;
 use std::mem;
 use std::ptr::{self, Unique};
-use std::sync::{Arc, Barrier};
+use std::sync::{Arc, Barrier, Mutex};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::{Duration, Instant};
 use std::thread;
@@ -130,6 +131,7 @@ impl EngineBuilder {
             camera: None,
             behaviors: Vec::new(),
             input: Input::new(),
+            debug_lines: Mutex::new(LineRenderer::new()),
 
             default_material_id: default_material_id,
 
@@ -176,6 +178,10 @@ pub struct Engine {
     behaviors: Vec<Box<FnMut() + Send>>,
     input: Input,
 
+    /// Debug primitives drawn by game code and engine systems during the frame, handed to the
+    /// renderer before drawing.
+    debug_lines: Mutex<LineRenderer>,
+
     default_material_id: PolygonMaterialId,
 
     debug_pause: bool,
@@ -221,6 +227,18 @@ pub fn window<F, T>(func: F) -> T
     unsafe { func(&(***engine).window) }
 }
 
+/// Adds debug lines, triangles, and points to be drawn in the current frame.
+///
+/// Can be called from any thread. Primitives are only drawn for one frame, so they must be added
+/// again every frame for them to stay visible.
+pub fn debug_draw<F, T>(func: F) -> T
+    where F: FnOnce(&mut LineRenderer) -> T
+{
+    let engine = INSTANCE.borrow();
+    let mut debug_lines = unsafe { (***engine).debug_lines.lock().unwrap() };
+    func(&mut *debug_lines)
+}
+
 pub enum EngineMessage {
     Anchor(TransformInnerHandle),
     Camera(Box<CameraData>, TransformInnerHandle),
@@ -440,6 +458,14 @@ fn main_loop(mut engine: Box<Engine>) {
                 }
             }
 
+            // Hand this frame's debug primitives to the renderer.
+            {
+                let _s = Stopwatch::new("Update renderer debug lines");
+
+                let mut debug_lines = engine.debug_lines.lock().unwrap();
+                engine.renderer.line_renderer_mut().append(&mut *debug_lines);
+            }
+
             // Draw.
             engine.renderer.draw();
         }
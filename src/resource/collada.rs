@@ -4,6 +4,7 @@ extern crate parse_collada as collada;
 
 use math::*;
 use polygon::geometry::mesh::*;
+use std::collections::HashMap;
 pub use self::collada::{
     AnyUri,
     ArrayElement,
@@ -53,6 +54,9 @@ pub enum Error {
     /// fixed.
     MissingPositionSemantic,
 
+    /// Indicates that the document has no <geometry> element with the requested id.
+    GeometryNotFound(String),
+
     /// Indicates that the <mesh> had no primitive elements.
     MissingPrimitiveElement,
 
@@ -116,6 +120,27 @@ pub fn load_resources<T: Into<String>>(source: T) -> Result<Mesh> {
     unimplemented!();
 }
 
+/// Converts the <geometry> element referenced by `uri` into a mesh.
+///
+/// `uri` must be a fragment referencing a geometry in the document, e.g. `"#cube-mesh"`.
+pub fn mesh_from_collada(collada: &Collada, uri: &str) -> Result<Mesh> {
+    if !uri.starts_with('#') {
+        return Err(Error::NonLocalUri(String::from(uri)));
+    }
+    let id = &uri[1..];
+
+    let geometry = collada.library_geometries
+        .iter()
+        .flat_map(|library| library.geometry.iter())
+        .find(|geometry| geometry.id.as_ref().map_or(false, |geometry_id| geometry_id == id))
+        .ok_or_else(|| Error::GeometryNotFound(String::from(uri)))?;
+
+    match geometry.geometric_element {
+        GeometricElement::Mesh(ref mesh) => collada_mesh_to_mesh(mesh),
+        _ => Err(Error::UnsupportedGeometricElement),
+    }
+}
+
 fn collada_mesh_to_mesh(mesh: &collada::Mesh) -> Result<Mesh> {
     if mesh.primitive_elements.len() > 1 {
         println!("WARNING: Mesh is composed of more than one geometric primitive, which is not currently supported, only part of the mesh will be loaded");
@@ -142,7 +167,6 @@ fn collada_mesh_to_mesh(mesh: &collada::Mesh) -> Result<Mesh> {
     let stride = triangles.input.len(); // TODO: Do we have a better way of calculating stride? What if one of the sources isn't used? OR USED TWICE!?
     let count  = triangles.count;
     let index_count = primitive_indices.len();
-    let vertex_count = count as u32 * 3;
 
     // Verify we have the right number of indices to build the vertices.
     if count * stride * 3 != index_count {
@@ -205,9 +229,20 @@ fn collada_mesh_to_mesh(mesh: &collada::Mesh) -> Result<Mesh> {
         }
     }
 
+    // COLLADA indexes each input separately, so two corners of the mesh are the same vertex
+    // exactly when all of their indices match. Deduplicating on the index groups lets the mesh
+    // use a single index buffer instead of duplicating every vertex for every triangle.
+    let mut vertex_map: HashMap<&[usize], u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(count * 3);
+
     let mut mesh_builder = MeshBuilder::new();
     let mut unsupported_semantic_flag = false;
     for vertex_indices in GroupBy::new(primitive_indices, stride).unwrap() { // TODO: This can't fail... right? I'm pretty sure the above checks make sure this is correct.
+        if let Some(&index) = vertex_map.get(vertex_indices) {
+            indices.push(index);
+            continue;
+        }
+
         // We iterate over each group of indices where each group represents the indices for a
         // single vertex. Within that vertex we need
         let mut vertex = Vertex::new(Point::origin());
@@ -246,13 +281,16 @@ fn collada_mesh_to_mesh(mesh: &collada::Mesh) -> Result<Mesh> {
             }
         }
 
+        let index = vertex_map.len() as u32;
+        vertex_map.insert(vertex_indices, index);
+        indices.push(index);
+
         mesh_builder.add_vertex(vertex);
     }
 
-    let indices: Vec<u32> = (0..vertex_count).collect();
-
     mesh_builder
     .set_indices(&*indices)
+    .generate_tangents()
     .build()
     .map_err(|err| Error::BuildMeshError(err))
 }
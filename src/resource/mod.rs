@@ -128,6 +128,7 @@ pub fn load_mesh<'a, P>(path: P) -> Async<'a, Result<Mesh, LoadMeshError>>
                     .set_normal_data(&*normals)
                     .set_texcoord_data(&*texcoords)
                     .set_indices(&*indices)
+                    .generate_tangents()
                     .build()?
             },
             _ => {